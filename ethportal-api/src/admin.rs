@@ -0,0 +1,47 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+use crate::types::{
+    admin::{AdminNodeInfo, DbStats, PeerInfo},
+    history::ImportLocalContentInfo,
+};
+
+/// Admin JSON-RPC endpoints
+#[rpc(client, server, namespace = "admin")]
+pub trait AdminApi {
+    /// Returns the local ENR, node id, addresses and client version.
+    #[method(name = "nodeInfo")]
+    async fn node_info(&self) -> RpcResult<AdminNodeInfo>;
+
+    /// Returns the peers of every enabled subnetwork, keyed by subnetwork name.
+    #[method(name = "peers")]
+    async fn peers(&self) -> RpcResult<BTreeMap<String, Vec<PeerInfo>>>;
+
+    /// Returns the storage usage of every enabled subnetwork that stores content, keyed by
+    /// subnetwork name.
+    #[method(name = "dbStats")]
+    async fn db_stats(&self) -> RpcResult<BTreeMap<String, DbStats>>;
+
+    /// Copies the content database to a new database file at `path`, relative to the node data
+    /// directory, while the node keeps running. Returns the size of the backup in bytes. Requires
+    /// the history network.
+    #[method(name = "backupDb")]
+    async fn backup_db(&self, path: PathBuf) -> RpcResult<u64>;
+
+    /// Writes the local history network content, for the same range as
+    /// portal_paginateLocalContentKeys, to a content archive at `path`, relative to the node data
+    /// directory. Returns the number of exported items. Requires the history network.
+    #[method(name = "exportLocalContent")]
+    async fn export_local_content(&self, path: PathBuf, offset: u64, limit: u64) -> RpcResult<u64>;
+
+    /// Stores the validated content of a content archive at `path`, relative to the node data
+    /// directory, skipping invalid content and content outside the radius. Requires the history
+    /// network.
+    #[method(name = "importLocalContent")]
+    async fn import_local_content(&self, path: PathBuf) -> RpcResult<ImportLocalContentInfo>;
+
+    /// Stops the node gracefully. Returns once the shutdown has been initiated.
+    #[method(name = "shutdown")]
+    async fn shutdown(&self) -> RpcResult<bool>;
+}
@@ -39,4 +39,13 @@ pub trait Discv5Api {
     /// Fetch the ENR representation associated with the given Node ID.
     #[method(name = "lookupEnr")]
     async fn lookup_enr(&self, node_id: NodeId) -> RpcResult<Enr>;
+
+    /// Send a TALKREQ with the given hex-encoded protocol and payload to the designated node and
+    /// return the hex-encoded TALKRESP payload.
+    #[method(name = "talkReq")]
+    async fn talk_req(&self, enr: Enr, protocol: String, request: String) -> RpcResult<String>;
+
+    /// Lookup the closest nodes to a target node ID in the discv5 network.
+    #[method(name = "recursiveFindNodes")]
+    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>>;
 }
@@ -1,6 +1,18 @@
-use ethereum_types::{H256, U256};
-use jsonrpsee::{core::RpcResult, proc_macros::rpc};
-use reth_rpc_types::Block;
+use ethereum_types::{H160, H256, U256, U64};
+use jsonrpsee::{
+    core::{RpcResult, SubscriptionResult},
+    proc_macros::rpc,
+};
+use reth_rpc_types::{Block, BlockNumberOrTag, Log};
+use serde_json::Value;
+
+use crate::types::{
+    execution::{
+        call_request::CallRequest, fee_history::FeeHistory, log_filter::LogFilter,
+        receipts::RpcReceipt, transaction::RpcTransaction,
+    },
+    subscription::SubscriptionKind,
+};
 
 /// Web3 JSON-RPC endpoints
 #[rpc(client, server, namespace = "eth")]
@@ -14,4 +26,86 @@ pub trait EthApi {
         block_hash: H256,
         hydrated_transactions: bool,
     ) -> RpcResult<Block>;
+
+    /// Only pre-merge block numbers can be resolved, using the epoch accumulators from the
+    /// history network.
+    #[method(name = "getBlockByNumber")]
+    async fn get_block_by_number(
+        &self,
+        block_number: BlockNumberOrTag,
+        hydrated_transactions: bool,
+    ) -> RpcResult<Block>;
+
+    /// Finds the block that includes the transaction through the canonical indices network.
+    #[method(name = "getTransactionByHash")]
+    async fn get_transaction_by_hash(&self, transaction_hash: H256) -> RpcResult<RpcTransaction>;
+
+    /// Finds the block that includes the transaction through the canonical indices network.
+    #[method(name = "getTransactionReceipt")]
+    async fn get_transaction_receipt(&self, transaction_hash: H256) -> RpcResult<RpcReceipt>;
+
+    /// Either a block hash or an explicit pre-merge block range must be given. The range may not
+    /// be longer than the node's configured maximum.
+    #[method(name = "getLogs")]
+    async fn get_logs(&self, filter: LogFilter) -> RpcResult<Vec<Log>>;
+
+    /// Reads the account from the state network, so only pre-merge blocks can be resolved.
+    #[method(name = "getBalance")]
+    async fn get_balance(&self, address: H160, block_number: BlockNumberOrTag) -> RpcResult<U256>;
+
+    /// Reads the account from the state network, so only pre-merge blocks can be resolved.
+    #[method(name = "getTransactionCount")]
+    async fn get_transaction_count(
+        &self,
+        address: H160,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<U256>;
+
+    /// Reads the contract bytecode from the state network, so only pre-merge blocks can be
+    /// resolved.
+    #[method(name = "getCode")]
+    async fn get_code(&self, address: H160, block_number: BlockNumberOrTag) -> RpcResult<String>;
+
+    /// Reads the storage slot from the state network, so only pre-merge blocks can be resolved.
+    #[method(name = "getStorageAt")]
+    async fn get_storage_at(
+        &self,
+        address: H160,
+        slot: U256,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<H256>;
+
+    /// Executes the call locally, fetching every account, contract and storage slot it touches
+    /// from the state network. Only pre-merge blocks can be resolved.
+    #[method(name = "call")]
+    async fn call(&self, request: CallRequest, block_number: BlockNumberOrTag)
+        -> RpcResult<String>;
+
+    /// Executes the call locally like `eth_call`, and returns the lowest gas limit with which it
+    /// succeeds. Only pre-merge blocks can be resolved.
+    #[method(name = "estimateGas")]
+    async fn estimate_gas(
+        &self,
+        request: CallRequest,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<U256>;
+
+    /// The newest block is either a pre-merge block number, or `latest`, which resolves to the
+    /// highest block header stored by this node.
+    #[method(name = "feeHistory")]
+    async fn fee_history(
+        &self,
+        block_count: U64,
+        newest_block: BlockNumberOrTag,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> RpcResult<FeeHistory>;
+
+    /// Suggests the next base fee plus the median priority fee of the highest block header stored
+    /// by this node.
+    #[method(name = "gasPrice")]
+    async fn gas_price(&self) -> RpcResult<U256>;
+
+    /// Subscribe to notifications of the given kind. Only available over ws and ipc.
+    #[subscription(name = "subscribe" => "subscription", unsubscribe = "unsubscribe", item = Value)]
+    async fn subscribe(&self, kind: SubscriptionKind) -> SubscriptionResult;
 }
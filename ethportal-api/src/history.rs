@@ -3,7 +3,10 @@ use crate::{
         content_key::history::HistoryContentKey,
         enr::Enr,
         history::{ContentInfo, PaginateLocalContentInfo, TraceContentInfo},
-        portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
+        portal::{
+            AcceptInfo, ContentPage, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo,
+            TraceOfferInfo,
+        },
     },
     HistoryContentValue, PossibleHistoryContentValue, RoutingTableInfo,
 };
@@ -72,6 +75,19 @@ pub trait HistoryNetworkApi {
         content_key: HistoryContentKey,
     ) -> RpcResult<TraceContentInfo>;
 
+    /// Lookup a target content key in the network, and return the first page of its value, of at
+    /// most `page_size` bytes. The remaining pages are fetched with `historyContentPage`.
+    #[method(name = "historyRecursiveFindContentPaged")]
+    async fn recursive_find_content_paged(
+        &self,
+        content_key: HistoryContentKey,
+        page_size: Option<u64>,
+    ) -> RpcResult<ContentPage>;
+
+    /// Return the next page of a content value found by `historyRecursiveFindContentPaged`.
+    #[method(name = "historyContentPage")]
+    async fn content_page(&self, continuation: String) -> RpcResult<ContentPage>;
+
     /// Pagination of local content keys
     #[method(name = "historyPaginateLocalContentKeys")]
     async fn paginate_local_content_keys(
@@ -98,6 +114,15 @@ pub trait HistoryNetworkApi {
         content_value: HistoryContentValue,
     ) -> RpcResult<TraceGossipInfo>;
 
+    /// Send the provided content values to interested peers, offering each peer all of the content
+    /// it is interested in with as few OFFER requests as possible. Return tracing info detailing
+    /// the gossip propagation of each content item, in the order of the items.
+    #[method(name = "historyTraceGossipBatch")]
+    async fn trace_gossip_batch(
+        &self,
+        content_items: Vec<(HistoryContentKey, HistoryContentValue)>,
+    ) -> RpcResult<Vec<TraceGossipInfo>>;
+
     /// Send an OFFER request with given ContentKey, to the designated peer and wait for a response.
     /// Returns the content keys bitlist upon successful content transmission or empty bitlist
     /// receive.
@@ -109,6 +134,16 @@ pub trait HistoryNetworkApi {
         content_value: Option<HistoryContentValue>,
     ) -> RpcResult<AcceptInfo>;
 
+    /// Send an OFFER request with the given ContentKeys, read from the local database, to the
+    /// designated peer and wait for the uTP transfer to finish. Returns the content keys bitlist
+    /// along with whether the accepted content was successfully transferred.
+    #[method(name = "historyTraceOffer")]
+    async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_keys: Vec<HistoryContentKey>,
+    ) -> RpcResult<TraceOfferInfo>;
+
     /// Store content key with a content data to the local database.
     #[method(name = "historyStore")]
     async fn store(
@@ -7,30 +7,38 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod admin;
 mod beacon;
 mod dashboard;
 pub mod discv5;
 mod eth;
 mod history;
+mod net;
+mod state;
 pub mod types;
 pub mod utils;
 mod web3;
 
 pub use crate::discv5::{Discv5ApiClient, Discv5ApiServer};
+pub use admin::{AdminApiClient, AdminApiServer};
 pub use beacon::{BeaconNetworkApiClient, BeaconNetworkApiServer};
 pub use eth::{EthApiClient, EthApiServer};
 pub use history::{HistoryNetworkApiClient, HistoryNetworkApiServer};
+pub use net::{NetApiClient, NetApiServer};
+pub use state::{StateNetworkApiClient, StateNetworkApiServer};
 pub use web3::{Web3ApiClient, Web3ApiServer};
 
 pub use types::content_key::{
     beacon::{BeaconContentKey, LightClientBootstrapKey, LightClientUpdatesByRangeKey},
+    canonical_indices::{CanonicalIndicesContentKey, TransactionIndexKey},
     error::ContentKeyError,
     history::{
         BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, EpochAccumulatorKey, HistoryContentKey,
         RawContentKey,
     },
     overlay::{IdentityContentKey, OverlayContentKey},
-    state::StateContentKey,
+    state::{AccountTrieNode, ContractBytecode, ContractStorageTrieNode, StateContentKey},
+    transaction_gossip::{TransactionGossipContentKey, TransactionKey},
 };
 
 pub use types::{
@@ -38,8 +46,11 @@ pub use types::{
     consensus::light_client,
     content_value::{
         beacon::{BeaconContentValue, PossibleBeaconContentValue},
+        canonical_indices::TransactionIndex,
         error::ContentValueError,
         history::{HistoryContentValue, PossibleHistoryContentValue},
+        state::{PossibleStateContentValue, StateContentValue},
+        transaction_gossip::TransactionGossipContentValue,
     },
     execution::{block_body::*, header::*, receipts::*},
 };
@@ -0,0 +1,14 @@
+use ethereum_types::U64;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Net JSON-RPC endpoints
+#[rpc(client, server, namespace = "net")]
+pub trait NetApi {
+    #[method(name = "version")]
+    async fn version(&self) -> RpcResult<String>;
+
+    /// Returns the number of distinct peers in the routing tables of all enabled overlay
+    /// networks.
+    #[method(name = "peerCount")]
+    async fn peer_count(&self) -> RpcResult<U64>;
+}
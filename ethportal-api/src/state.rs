@@ -0,0 +1,74 @@
+use crate::{
+    types::{
+        content_key::state::StateContentKey,
+        enr::Enr,
+        portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo},
+        state::ContentInfo,
+    },
+    PossibleStateContentValue, RoutingTableInfo, StateContentValue,
+};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Portal State JSON-RPC endpoints
+#[rpc(client, server, namespace = "portal")]
+pub trait StateNetworkApi {
+    /// Returns meta information about overlay routing table.
+    #[method(name = "stateRoutingTableInfo")]
+    async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo>;
+
+    /// Returns the node data radios
+    #[method(name = "stateRadius")]
+    async fn radius(&self) -> RpcResult<DataRadius>;
+
+    /// Send a PING message to the designated node and wait for a PONG response
+    #[method(name = "statePing")]
+    async fn ping(&self, enr: Enr) -> RpcResult<PongInfo>;
+
+    /// Send a FINDNODES request for nodes that fall within the given set of distances, to the
+    /// designated peer and wait for a response
+    #[method(name = "stateFindNodes")]
+    async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo>;
+
+    /// Send FINDCONTENT message to get the content with a content key.
+    #[method(name = "stateFindContent")]
+    async fn find_content(&self, enr: Enr, content_key: StateContentKey) -> RpcResult<ContentInfo>;
+
+    /// Lookup a target content key in the network
+    #[method(name = "stateRecursiveFindContent")]
+    async fn recursive_find_content(&self, content_key: StateContentKey) -> RpcResult<ContentInfo>;
+
+    /// Send the provided content value to interested peers. Clients may choose to send to some or
+    /// all peers. Return the number of peers that the content was gossiped to.
+    #[method(name = "stateGossip")]
+    async fn gossip(
+        &self,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> RpcResult<u32>;
+
+    /// Send an OFFER request with given ContentKey, to the designated peer and wait for a response.
+    /// Returns the content keys bitlist upon successful content transmission or empty bitlist
+    /// receive.
+    #[method(name = "stateOffer")]
+    async fn offer(
+        &self,
+        enr: Enr,
+        content_key: StateContentKey,
+        content_value: Option<StateContentValue>,
+    ) -> RpcResult<AcceptInfo>;
+
+    /// Store content key with a content data to the local database.
+    #[method(name = "stateStore")]
+    async fn store(
+        &self,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> RpcResult<bool>;
+
+    /// Get a content from the local database
+    #[method(name = "stateLocalContent")]
+    async fn local_content(
+        &self,
+        content_key: StateContentKey,
+    ) -> RpcResult<PossibleStateContentValue>;
+}
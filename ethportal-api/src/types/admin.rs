@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use super::{distance::DataRadius, enr::Enr};
+
+/// Information about the local node, as returned by `admin_nodeInfo`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminNodeInfo {
+    pub enr: Enr,
+    pub node_id: String,
+    /// Externally reachable ip address, as advertised in the ENR.
+    pub ip: Option<String>,
+    /// Socket address that the discv5 service listens on.
+    pub listen_address: String,
+    pub client_version: String,
+    /// Names of the enabled subnetworks.
+    pub networks: Vec<String>,
+}
+
+/// Storage usage of a subnetwork, as returned by `admin_dbStats`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStats {
+    /// Sum of the sizes of the stored content values, in bytes.
+    pub content_bytes: u64,
+    /// Limit on `content_bytes`, in bytes.
+    pub capacity_bytes: u64,
+    pub entry_count: u64,
+    /// Number of entries evicted to stay within capacity since trin started.
+    pub eviction_count: u64,
+    pub radius: DataRadius,
+}
+
+/// A peer in the routing table of a subnetwork, as returned by `admin_peers`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    pub enr: Enr,
+    pub node_id: String,
+    /// Last data radius advertised by the peer.
+    pub radius: DataRadius,
+    pub connected: bool,
+    /// Time of the last message received from the peer, in seconds since the unix epoch. Unset if
+    /// no message was received since trin started.
+    pub last_seen: Option<u64>,
+    /// Client name advertised in the ENR.
+    pub client: Option<String>,
+    /// Highest portal protocol version supported by both the local node and the peer.
+    pub protocol_version: Option<u8>,
+}
@@ -11,65 +11,56 @@ pub struct Bootnode {
 }
 
 lazy_static! {
-    pub static ref DEFAULT_BOOTNODES: Vec<Bootnode> = vec![
-        // https://github.com/ethereum/portal-network-specs/blob/master/testnet.md
-        // Trin bootstrap nodes
-        Bootnode{
-            enr: Enr::from_str("enr:-Jy4QIs2pCyiKna9YWnAF0zgf7bT0GzlAGoF8MEKFJOExmtofBIqzm71zDvmzRiiLkxaEJcs_Amr7XIhLI74k1rtlXICY5Z0IDAuMS4xLWFscGhhLjEtMTEwZjUwgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQLSC_nhF1iRwsCw0n3J4jRjqoaRxtKgsEe5a-Dz7y0JloN1ZHCCIyg").expect("Parsing static bootnode enr to work"),
-            alias: "trin-ams3-1".to_string()
-        },
-        Bootnode{
-            enr: Enr::from_str("enr:-Jy4QKSLYMpku9F0Ebk84zhIhwTkmn80UnYvE4Z4sOcLukASIcofrGdXVLAUPVHh8oPCfnEOZm1W1gcAxB9kV2FJywkCY5Z0IDAuMS4xLWFscGhhLjEtMTEwZjUwgmlkgnY0gmlwhJO2oc6Jc2VjcDI1NmsxoQLMSGVlxXL62N3sPtaV-n_TbZFCEM5AR7RDyIwOadbQK4N1ZHCCIyg").expect("Parsing static bootnode enr to work"),
-            alias: "trin-nyc1-1".to_string()
-        },
-        Bootnode{
-        enr: Enr::from_str("enr:-Jy4QH4_H4cW--ejWDl_W7ngXw2m31MM2GT8_1ZgECnfWxMzZTiZKvHDgkmwUS_l2aqHHU54Q7hcFSPz6VGzkUjOqkcCY5Z0IDAuMS4xLWFscGhhLjEtMTEwZjUwgmlkgnY0gmlwhJ31OTWJc2VjcDI1NmsxoQPC0eRkjRajDiETr_DRa5N5VJRm-ttCWDoO1QAMMCg5pIN1ZHCCIyg").expect("Parsing static bootnode enr to work"),
-            alias: "trin-sgp1-1".to_string()
-        },
-
-        // Fluffy bootstrap nodes
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QGUtAA29qeT3cWVr8lmJfySmkceR2wp6oFQtvO_uMe7KWaK_qd1UQvd93MJKXhMnubSsTQPJ6KkbIu0ywjvNdNEBgmlkgnY0gmlwhMIhKO6Jc2VjcDI1NmsxoQJ508pIqRqsjsvmUQfYGvaUFTxfsELPso_62FKDqlxI24N1ZHCCI40").expect("Parsing static bootnode enr to work"),
-            alias: "fluffy-1".to_string()
-        },
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QNaaoQuHGReAMJKoDd6DbQKMbQ4Mked3Gi3GRatwgRVVPXynPlO_-gJKRF_ZSuJr3wyHfwMHyJDbd6q1xZQVZ2kBgmlkgnY0gmlwhMIhKO6Jc2VjcDI1NmsxoQM2kBHT5s_Uh4gsNiOclQDvLK4kPpoQucge3mtbuLuUGYN1ZHCCI44").expect("Parsing static bootnode enr to work"),
-            alias: "fluffy-2".to_string()
-        },
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QBdIjs6S1ZkvlahSkuYNq5QW3DbD-UDcrm1l81f2PPjnNjb_NDa4B5x4olHCXtx0d2ZeZBHQyoHyNnuVZ-P1GVkBgmlkgnY0gmlwhMIhKO-Jc2VjcDI1NmsxoQOO3gFuaCAyQKscaiNLC9HfLbVzFdIerESFlOGcEuKWH4N1ZHCCI40").expect("Parsing static bootnode enr to work"),
-            alias: "fluffy-3".to_string()
-        },
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QM731tV0CvQXLTDcZNvgFyhhpAjYDKU5XLbM7sZ1WEzIRq4zsakgrv3KO3qyOYZ8jFBK-VzENF8o-vnykuQ99iABgmlkgnY0gmlwhMIhKO-Jc2VjcDI1NmsxoQMTq6Cdx3HmL3Q9sitavcPHPbYKyEibKPKvyVyOlNF8J4N1ZHCCI44").expect("Parsing static bootnode enr to work"),
-            alias: "fluffy-4".to_string()
-        },
-
-        // Ultralight bootstrap nodes
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QFV_wTNknw7qiCGAbHf6LxB-xPQCktyrCEZX-b-7PikMOIKkBg-frHRBkfwhI3XaYo_T-HxBYmOOQGNwThkBBHYDgmlkgnY0gmlwhKRc9_OJc2VjcDI1NmsxoQKHPt5CQ0D66ueTtSUqwGjfhscU_LiwS28QvJ0GgJFd-YN1ZHCCE4k").expect("Parsing static bootnode enr to work"),
-            alias: "ultralight-1".to_string()
-        },
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QDpUz2hQBNt0DECFm8Zy58Hi59PF_7sw780X3qA0vzJEB2IEd5RtVdPUYZUbeg4f0LMradgwpyIhYUeSxz2Tfa8DgmlkgnY0gmlwhKRc9_OJc2VjcDI1NmsxoQJd4NAVKOXfbdxyjSOUJzmA4rjtg43EDeEJu1f8YRhb_4N1ZHCCE4o").expect("Parsing static bootnode enr to work"),
-            alias: "ultralight-2".to_string()
-        },
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QGG6moBhLW1oXz84NaKEHaRcim64qzFn1hAG80yQyVGNLoKqzJe887kEjthr7rJCNlt6vdVMKMNoUC9OCeNK-EMDgmlkgnY0gmlwhKRc9-KJc2VjcDI1NmsxoQLJhXByb3LmxHQaqgLDtIGUmpANXaBbFw3ybZWzGqb9-IN1ZHCCE4k").expect("Parsing static bootnode enr to work"),
-            alias: "ultralight-3".to_string()
-        },
-        Bootnode{
-            enr:
-        Enr::from_str("enr:-IS4QA5hpJikeDFf1DD1_Le6_ylgrLGpdwn3SRaneGu9hY2HUI7peHep0f28UUMzbC0PvlWjN8zSfnqMG07WVcCyBhADgmlkgnY0gmlwhKRc9-KJc2VjcDI1NmsxoQJMpHmGj1xSP1O-Mffk_jYIHVcg6tY5_CjmWVg1gJEsPIN1ZHCCE4o").expect("Parsing static bootnode enr to work"),
-            alias: "ultralight-4".to_string()
-        }];
+    /// The bootnodes of the Portal mainnet.
+    pub static ref DEFAULT_BOOTNODES: Vec<Bootnode> =
+        compiled_in_bootnodes(include_str!("../assets/bootnodes/mainnet.txt"));
+    /// The bootnodes of the Portal testnet.
+    pub static ref TESTNET_BOOTNODES: Vec<Bootnode> =
+        compiled_in_bootnodes(include_str!("../assets/bootnodes/testnet.txt"));
+    /// The bootnodes of the Portal angelfood testnet.
+    pub static ref ANGELFOOD_BOOTNODES: Vec<Bootnode> =
+        compiled_in_bootnodes(include_str!("../assets/bootnodes/angelfood.txt"));
+}
+
+fn compiled_in_bootnodes(contents: &str) -> Vec<Bootnode> {
+    let (bootnodes, errors) = parse_bootnodes_file(contents);
+    if let Some(err) = errors.first() {
+        panic!("Parsing static bootnodes to work: {err}");
+    }
+    bootnodes
+}
+
+/// Parses a bootnodes file, which lists one ENR per line, optionally followed by an alias. Empty
+/// lines and everything after a `#` are ignored.
+///
+/// Returns the bootnodes of the valid lines, along with an error for every invalid line, so that
+/// a single bad line doesn't discard the whole file.
+pub fn parse_bootnodes_file(contents: &str) -> (Vec<Bootnode>, Vec<String>) {
+    let mut bootnodes = vec![];
+    let mut errors = vec![];
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let Some(enr) = fields.next() else {
+            continue;
+        };
+        let alias = fields.next();
+        if fields.next().is_some() {
+            errors.push(format!(
+                "line {line_number}: expected an ENR optionally followed by an alias"
+            ));
+            continue;
+        }
+        match Enr::from_str(enr) {
+            Ok(enr) => bootnodes.push(Bootnode {
+                enr,
+                alias: alias.unwrap_or("custom").to_string(),
+            }),
+            Err(err) => errors.push(format!("line {line_number}: invalid ENR {enr}: {err}")),
+        }
+    }
+    (bootnodes, errors)
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -133,8 +124,10 @@ mod test {
     use super::*;
     use crate::types::cli::TrinConfig;
     use rstest::rstest;
+    use serial_test::serial;
 
     #[test_log::test]
+    #[serial]
     fn test_bootnodes_default_with_testnet_bootnodes() {
         let config = TrinConfig::new_from(["trin"].iter()).unwrap();
         assert_eq!(config.bootnodes, Bootnodes::Default);
@@ -143,6 +136,7 @@ mod test {
     }
 
     #[test_log::test]
+    #[serial]
     fn test_bootnodes_default_with_no_bootnodes() {
         let config = TrinConfig::new_from(["trin", "--bootnodes", "none"].iter()).unwrap();
         assert_eq!(config.bootnodes, Bootnodes::None);
@@ -150,7 +144,32 @@ mod test {
         assert_eq!(bootnodes.len(), 0);
     }
 
+    #[test]
+    fn test_default_bootnodes_per_network() {
+        assert_eq!(DEFAULT_BOOTNODES.len(), 11);
+        assert_eq!(DEFAULT_BOOTNODES[0].alias, "trin-ams3-1");
+        assert!(TESTNET_BOOTNODES.is_empty());
+        assert!(ANGELFOOD_BOOTNODES.is_empty());
+    }
+
+    #[test]
+    fn test_parse_bootnodes_file() {
+        let enr = "enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg";
+        let contents = format!(
+            "# my bootnodes\n{enr} my-node\n\nenr:invalid\n{enr}  # without alias\n{enr} an alias\n"
+        );
+        let (bootnodes, errors) = parse_bootnodes_file(&contents);
+        assert_eq!(bootnodes.len(), 2);
+        assert_eq!(bootnodes[0].alias, "my-node");
+        assert_eq!(bootnodes[1].alias, "custom");
+        assert_eq!(bootnodes[1].enr, Enr::from_str(enr).unwrap());
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 4: invalid ENR enr:invalid"));
+        assert!(errors[1].starts_with("line 6: expected an ENR"));
+    }
+
     #[rstest]
+    #[serial]
     #[case("invalid")]
     #[case("enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg,invalid")]
     #[should_panic]
@@ -159,6 +178,7 @@ mod test {
     }
 
     #[rstest]
+    #[serial]
     #[case("enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg", 1)]
     #[case("enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg,enr:-IS4QPUT9hwV4YfNTxazR2ltch4qKzvX_HwxQBw8gUN3q1MDfNyaD1EHc1wQZRTUzQQD-RVYx3h4nA1Sqk0Wx9DwzNABgmlkgnY0gmlwhM69ZOyJc2VjcDI1NmsxoQLaI-m2CDIjpwcnUf1ESspvOctJLpIrLA8AZ4zbo_1bFIN1ZHCCIyg", 2)]
     #[case("enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg,enr:-IS4QPUT9hwV4YfNTxazR2ltch4qKzvX_HwxQBw8gUN3q1MDfNyaD1EHc1wQZRTUzQQD-RVYx3h4nA1Sqk0Wx9DwzNABgmlkgnY0gmlwhM69ZOyJc2VjcDI1NmsxoQLaI-m2CDIjpwcnUf1ESspvOctJLpIrLA8AZ4zbo_1bFIN1ZHCCIyg,enr:-IS4QB77AROcGX-TSkY-U-SaZJ5ma9ICQj6ETO3FqUdCnTZeJ0mDrdCKUqd5AQ0jrHa7m9-mOLvFFKMV_-tBD8uDYZUBgmlkgnY0gmlwhJ_fCDaJc2VjcDI1NmsxoQN9rahqamBOJfj4u6yssJQJ1-EZoyAw-7HIgp1FwNUdnoN1ZHCCIyg", 3)]
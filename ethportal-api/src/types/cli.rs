@@ -1,13 +1,14 @@
 use clap::{
     arg,
     error::{Error, ErrorKind},
-    Args, Parser, Subcommand,
+    parser::ValueSource,
+    ArgMatches, Args, Command, CommandFactory, FromArgMatches, Parser, Subcommand,
 };
 use ethereum_types::H256;
-use std::{env, ffi::OsString, fmt, net::SocketAddr, path::PathBuf, str::FromStr};
-use url::Url;
+use std::{env, ffi::OsString, fmt, fs, net::SocketAddr, path::PathBuf, str::FromStr};
+use url::{Host, Url};
 
-use crate::types::bootnodes::Bootnodes;
+use crate::types::{bootnodes::Bootnodes, network::PortalNetwork};
 
 pub const DEFAULT_MASTER_ACC_PATH: &str = "validation_assets/merge_macc.bin";
 pub const DEFAULT_WEB3_IPC_PATH: &str = "/tmp/trin-jsonrpc.ipc";
@@ -17,10 +18,23 @@ pub const DEFAULT_WEB3_WS_PORT: u16 = 8546;
 pub const DEFAULT_DISCOVERY_PORT: u16 = 9009;
 pub const BEACON_NETWORK: &str = "beacon";
 pub const HISTORY_NETWORK: &str = "history";
+pub const CANONICAL_INDICES_NETWORK: &str = "canonical_indices";
 pub const STATE_NETWORK: &str = "state";
+pub const TRANSACTION_GOSSIP_NETWORK: &str = "transaction_gossip";
 const DEFAULT_SUBNETWORKS: &str = "history";
 pub const DEFAULT_STORAGE_CAPACITY_MB: &str = "100";
 pub const DEFAULT_WEB3_TRANSPORT: &str = "ipc";
+pub const DEFAULT_NETWORK: &str = "mainnet";
+pub const DEFAULT_MAX_LOGS_BLOCK_RANGE: u64 = 1000;
+/// Recommended lookup concurrency (α) from the kademlia paper.
+pub const DEFAULT_QUERY_PARALLELISM: usize = 3;
+pub const DEFAULT_QUERY_PEER_TIMEOUT_SECS: u64 = 2;
+pub const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_JSON_LOG_MAX_FILE_SIZE_MB: &str = "100";
+pub const DEFAULT_JSON_LOG_MAX_FILES: usize = 10;
+/// Prefix of the environment variables that set the options, e.g. `TRIN_WEB3_TRANSPORT` sets
+/// `--web3-transport`.
+pub const ENV_VAR_PREFIX: &str = "TRIN_";
 
 use crate::dashboard::grafana::{GrafanaAPI, DASHBOARD_TEMPLATES};
 
@@ -59,6 +73,12 @@ impl FromStr for Web3TransportType {
     about = "Run an eth portal client"
 )]
 pub struct TrinConfig {
+    #[arg(
+        long = "config",
+        help = "Path to a TOML file setting any of the other options, keyed by their long flag names, e.g. `networks = [\"history\", \"beacon\"]`. Flags on the command line override the values in the file."
+    )]
+    pub config: Option<PathBuf>,
+
     #[arg(
         default_value = DEFAULT_WEB3_TRANSPORT,
         long = "web3-transport",
@@ -80,6 +100,13 @@ pub struct TrinConfig {
     )]
     pub web3_ipc_path: PathBuf,
 
+    #[arg(
+        long = "web3-ipc-mode",
+        value_parser = parse_ipc_mode,
+        help = "Octal file mode of the json-rpc IPC socket, e.g. 600 to only let the owner connect. Defaults to the process umask."
+    )]
+    pub web3_ipc_mode: Option<u32>,
+
     #[arg(
         default_value_t = DEFAULT_DISCOVERY_PORT,
         long = "discovery-port",
@@ -87,13 +114,26 @@ pub struct TrinConfig {
     )]
     pub discovery_port: u16,
 
+    #[arg(
+        default_value = DEFAULT_NETWORK,
+        long = "network",
+        help = "The portal network to join: mainnet, testnet or angelfood. Selects the protocol ids, the default bootnodes and the default data directory."
+    )]
+    pub network: PortalNetwork,
+
     #[arg(
         default_value = "default",
         long = "bootnodes",
-        help = "One or more comma-delimited base64-encoded ENR's or multiaddr strings of peers to initially add to the local routing table"
+        help = "One or more comma-delimited base64-encoded ENR's or multiaddr strings of peers to initially add to the local routing table. Defaults to the bootnodes of the --network."
     )]
     pub bootnodes: Bootnodes,
 
+    #[arg(
+        long = "bootnodes-file",
+        help = "Path to a file listing bootnodes, one ENR per line, optionally followed by an alias. They're added to the bootnodes of --bootnodes, so combine it with `--bootnodes none` to replace the default bootnodes. Invalid lines are reported and skipped."
+    )]
+    pub bootnodes_file: Option<PathBuf>,
+
     #[arg(
         long = "external-address",
         group = "external-ips",
@@ -114,6 +154,12 @@ pub struct TrinConfig {
     )]
     pub no_upnp: bool,
 
+    #[arg(
+        long = "ipv6",
+        help = "Also listen on IPv6 with the discovery port, and advertise it in the udp6 field of the ENR, so that peers with only IPv6 endpoints can reach this node."
+    )]
+    pub ipv6: bool,
+
     #[arg(
         long = "unsafe-private-key",
         value_parser = check_private_key_length,
@@ -121,6 +167,20 @@ pub struct TrinConfig {
     )]
     pub private_key: Option<H256>,
 
+    #[arg(
+        long = "keystore-password",
+        conflicts_with = "keystore_password_file",
+        hide_env_values = true,
+        help = "Password of the encrypted keystore that the node key is kept in. Prefer setting it with the TRIN_KEYSTORE_PASSWORD env var or --keystore-password-file, as flags are stored in the terminal history. Without a password, the node key is stored in plaintext."
+    )]
+    pub keystore_password: Option<String>,
+
+    #[arg(
+        long = "keystore-password-file",
+        help = "Path to a file holding the password of the encrypted keystore that the node key is kept in."
+    )]
+    pub keystore_password_file: Option<PathBuf>,
+
     #[arg(
         long,
         value_parser = check_trusted_block_root,
@@ -129,9 +189,10 @@ pub struct TrinConfig {
     pub trusted_block_root: Option<String>,
 
     #[arg(
-    long = "networks",
-        help = "Comma-separated list of which portal subnetworks to activate",
+        long = "networks",
+        help = "Comma-separated list of which portal subnetworks to activate: history, state, beacon, transaction_gossip or canonical_indices. Only the activated subnetworks get a routing table, storage and json-rpc endpoints. Defaults to history, except on the testnets without a --master-accumulator-path, where it defaults to beacon, as history content can't be validated there.",
         default_value = DEFAULT_SUBNETWORKS,
+        value_parser = check_subnetwork,
         use_value_delimiter = true
     )]
     pub networks: Vec<String>,
@@ -140,7 +201,9 @@ pub struct TrinConfig {
     #[arg(
         default_value(DEFAULT_STORAGE_CAPACITY_MB),
         long,
-        help = "Maximum number of megabytes of total data to store in the DB (actual usage will exceed limit due to overhead)"
+        visible_alias = "storage",
+        value_parser = parse_storage_size,
+        help = "Maximum amount of data to store in the DB for each subnetwork, in megabytes or with a unit like 2GB or 512MiB (actual usage will exceed limit due to overhead)"
     )]
     pub mb: u32,
 
@@ -153,10 +216,17 @@ pub struct TrinConfig {
     #[arg(
         short = 'e',
         long = "ephemeral",
-        help = "Use temporary data storage that is deleted on exit."
+        help = "Keep all content, the node key and the ENR in memory only, so that nothing is left behind on exit."
     )]
     pub ephemeral: bool,
 
+    #[arg(
+        long = "storage-read-only",
+        conflicts_with = "ephemeral",
+        help = "Open the existing content database read-only, e.g. on a shared read-only volume. Content is served, but never stored, neither when offered nor when found."
+    )]
+    pub storage_read_only: bool,
+
     #[arg(
         long = "master-accumulator-path",
         help = "Path to master accumulator for validation",
@@ -170,6 +240,30 @@ pub struct TrinConfig {
     )]
     pub disable_poke: bool,
 
+    #[arg(
+        long = "query-parallelism",
+        value_parser = check_query_parallelism,
+        help = "Maximum number of peers that a recursive node or content lookup queries at the same time.",
+        default_value_t = DEFAULT_QUERY_PARALLELISM
+    )]
+    pub query_parallelism: usize,
+
+    #[arg(
+        long = "query-peer-timeout",
+        value_parser = check_query_timeout,
+        help = "Number of seconds that a recursive lookup waits for each peer to respond before querying the next closest peers.",
+        default_value_t = DEFAULT_QUERY_PEER_TIMEOUT_SECS
+    )]
+    pub query_peer_timeout: u64,
+
+    #[arg(
+        long = "query-timeout",
+        value_parser = check_query_timeout,
+        help = "Number of seconds after which a recursive lookup gives up and returns the closest nodes found so far.",
+        default_value_t = DEFAULT_QUERY_TIMEOUT_SECS
+    )]
+    pub query_timeout: u64,
+
     #[arg(long = "ws", help = "Used to enable WebSocket rpc.")]
     pub ws: bool,
 
@@ -181,6 +275,70 @@ pub struct TrinConfig {
     )]
     pub ws_port: u16,
 
+    #[arg(
+        long = "max-logs-block-range",
+        help = "Maximum number of blocks that a single eth_getLogs request may scan.",
+        default_value_t = DEFAULT_MAX_LOGS_BLOCK_RANGE
+    )]
+    pub max_logs_block_range: u64,
+
+    #[arg(
+        long = "web3-http-cors-domains",
+        help = "Comma-separated list of origins allowed to make cross-origin json-rpc requests over http and ws, or '*' to allow any origin."
+    )]
+    pub web3_http_cors_domains: Option<String>,
+
+    #[arg(
+        long = "web3-jwt-secret",
+        help = "Path to a file holding a hex encoded 32 byte secret. When set, every http and ws json-rpc request must carry a bearer JWT signed with it (HS256)."
+    )]
+    pub web3_jwt_secret: Option<PathBuf>,
+
+    #[arg(
+        long = "web3-admin",
+        help = "Serve the admin_ json-rpc methods, which can shut the node down and read or write files on its host. They're only served over ipc, over http on a localhost address, or over http and ws with a --web3-jwt-secret."
+    )]
+    pub web3_admin: bool,
+
+    #[arg(
+        long = "web3-rate-limit-local",
+        help = "Maximum number of json-rpc requests per second that each http and ws client may make to methods answered locally, like routing table or local content queries. Unlimited by default. Not supported over ipc, and local processes can bypass it through the json-rpc server behind the limiter, on a random localhost port."
+    )]
+    pub web3_rate_limit_local: Option<u32>,
+
+    #[arg(
+        long = "web3-rate-limit-network",
+        help = "Maximum number of json-rpc requests per second that each http and ws client may make to methods that query other nodes, like content lookups and eth_ methods. Unlimited by default. Not supported over ipc, and local processes can bypass it through the json-rpc server behind the limiter, on a random localhost port."
+    )]
+    pub web3_rate_limit_network: Option<u32>,
+
+    #[arg(
+        long = "disable-console-log",
+        help = "Don't write logs to the console, eg. when only logging to JSON files."
+    )]
+    pub disable_console_log: bool,
+
+    #[arg(
+        long = "json-log-dir",
+        help = "Write logs as JSON lines to rotating files in this directory, for ingestion by log collectors like Loki or Elasticsearch. Independent of the console logs."
+    )]
+    pub json_log_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "json-log-max-file-size",
+        value_parser = parse_log_file_size,
+        default_value(DEFAULT_JSON_LOG_MAX_FILE_SIZE_MB),
+        help = "Size at which the JSON log file is rotated, in megabytes or with a unit like 50MB."
+    )]
+    pub json_log_max_file_size: u32,
+
+    #[arg(
+        long = "json-log-max-files",
+        default_value_t = DEFAULT_JSON_LOG_MAX_FILES,
+        help = "Number of rotated JSON log files to keep besides the current one. Older files are deleted."
+    )]
+    pub json_log_max_files: usize,
+
     #[command(subcommand)]
     pub command: Option<TrinConfigCommands>,
 }
@@ -188,17 +346,24 @@ pub struct TrinConfig {
 impl Default for TrinConfig {
     fn default() -> Self {
         TrinConfig {
+            config: None,
             web3_transport: Web3TransportType::from_str(DEFAULT_WEB3_TRANSPORT)
                 .expect("Parsing static DEFAULT_WEB3_TRANSPORT to work"),
             web3_http_address: Url::parse(DEFAULT_WEB3_HTTP_ADDRESS)
                 .expect("Parsing static DEFAULT_WEB3_HTTP_ADDRESS to work"),
             web3_ipc_path: PathBuf::from(DEFAULT_WEB3_IPC_PATH),
+            web3_ipc_mode: None,
             discovery_port: DEFAULT_DISCOVERY_PORT,
+            network: PortalNetwork::default(),
             bootnodes: Bootnodes::Default,
+            bootnodes_file: None,
             external_addr: None,
             no_stun: false,
             no_upnp: false,
+            ipv6: false,
             private_key: None,
+            keystore_password: None,
+            keystore_password_file: None,
             trusted_block_root: None,
             networks: DEFAULT_SUBNETWORKS
                 .split(',')
@@ -209,10 +374,26 @@ impl Default for TrinConfig {
                 .expect("Parsing static DEFAULT_STORAGE_CAPACITY_MB to work"),
             enable_metrics_with_url: None,
             ephemeral: false,
+            storage_read_only: false,
             master_acc_path: PathBuf::from(DEFAULT_MASTER_ACC_PATH.to_string()),
             disable_poke: false,
+            query_parallelism: DEFAULT_QUERY_PARALLELISM,
+            query_peer_timeout: DEFAULT_QUERY_PEER_TIMEOUT_SECS,
+            query_timeout: DEFAULT_QUERY_TIMEOUT_SECS,
             ws: false,
             ws_port: DEFAULT_WEB3_WS_PORT,
+            max_logs_block_range: DEFAULT_MAX_LOGS_BLOCK_RANGE,
+            web3_http_cors_domains: None,
+            web3_jwt_secret: None,
+            web3_admin: false,
+            web3_rate_limit_local: None,
+            web3_rate_limit_network: None,
+            disable_console_log: false,
+            json_log_dir: None,
+            json_log_max_file_size: DEFAULT_JSON_LOG_MAX_FILE_SIZE_MB
+                .parse()
+                .expect("Parsing static DEFAULT_JSON_LOG_MAX_FILE_SIZE_MB to work"),
+            json_log_max_files: DEFAULT_JSON_LOG_MAX_FILES,
             command: None,
         }
     }
@@ -227,7 +408,9 @@ impl TrinConfig {
         I: Iterator<Item = T>,
         T: Into<OsString> + Clone,
     {
-        let config = Self::try_parse_from(args)?;
+        let args = Self::with_config_file_args(args.map(Into::into).collect())?;
+        let matches = Self::command_with_env().try_get_matches_from(args)?;
+        let mut config = Self::from_arg_matches(&matches)?;
 
         if let Some(TrinConfigCommands::CreateDashboard(dashboard_config)) = config.command {
             if let Err(err) = create_dashboard(dashboard_config) {
@@ -237,6 +420,50 @@ impl TrinConfig {
             std::process::exit(0);
         }
 
+        for (index, network) in config.networks.iter().enumerate() {
+            if config.networks[..index].contains(network) {
+                return Err(Error::raw(
+                    ErrorKind::ValueValidation,
+                    format!("Must not activate the {network} network more than once"),
+                ));
+            }
+        }
+
+        if config.disable_console_log && config.json_log_dir.is_none() {
+            return Err(Error::raw(
+                ErrorKind::ArgumentConflict,
+                "Must not disable console logs without a --json-log-dir, as nothing would be logged",
+            ));
+        }
+
+        // Only the master accumulator of mainnet is bundled, so history content can't be validated
+        // on the other networks without an accumulator for the network. The history network is
+        // left out of their default subnetworks, so that they can be joined without one.
+        let has_network_accumulator = config.network == PortalNetwork::Mainnet
+            || config.master_acc_path != PathBuf::from(DEFAULT_MASTER_ACC_PATH);
+        if !has_network_accumulator
+            && matches.value_source("networks") == Some(ValueSource::DefaultValue)
+        {
+            config.networks = vec![BEACON_NETWORK.to_string()];
+        }
+        let validates_history = config
+            .networks
+            .iter()
+            .any(|network| network == HISTORY_NETWORK)
+            || matches!(
+                config.command,
+                Some(TrinConfigCommands::ImportEra1(_) | TrinConfigCommands::ImportContent(_))
+            );
+        if validates_history && !has_network_accumulator {
+            return Err(Error::raw(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "History content can't be validated on {}, as only the master accumulator of mainnet is available. Supply the --master-accumulator-path of the network, or leave out the history network",
+                    config.network
+                ),
+            ));
+        }
+
         match config.web3_transport {
             Web3TransportType::HTTP => {
                 match &config.web3_ipc_path.as_path().display().to_string()[..] {
@@ -248,6 +475,24 @@ impl TrinConfig {
                         ))
                     }
                 }
+                if config.web3_ipc_mode.is_some() {
+                    return Err(Error::raw(
+                        ErrorKind::ArgumentConflict,
+                        "Must not supply an ipc mode when using http protocol for json-rpc",
+                    ));
+                }
+                let is_localhost = match config.web3_http_address.host() {
+                    Some(Host::Ipv4(ip)) => ip.is_loopback(),
+                    Some(Host::Ipv6(ip)) => ip.is_loopback(),
+                    Some(Host::Domain(domain)) => domain == "localhost",
+                    None => false,
+                };
+                if config.web3_admin && !is_localhost && config.web3_jwt_secret.is_none() {
+                    return Err(Error::raw(
+                        ErrorKind::ArgumentConflict,
+                        "Must not serve the admin methods over http on a non-localhost address without a --web3-jwt-secret",
+                    ));
+                }
             }
             Web3TransportType::IPC => {
                 match config.web3_http_address.as_str() {
@@ -257,10 +502,151 @@ impl TrinConfig {
                 if config.ws {
                     return Err(Error::raw(ErrorKind::ArgumentConflict,format!("Must not enable ws when using ipc protocol for json-rpc (received: {})", config.web3_http_address.as_str())));
                 }
+                if config.web3_http_cors_domains.is_some() {
+                    return Err(Error::raw(
+                        ErrorKind::ArgumentConflict,
+                        "Must not supply cors domains when using ipc protocol for json-rpc",
+                    ));
+                }
+                if config.web3_rate_limit_local.is_some()
+                    || config.web3_rate_limit_network.is_some()
+                {
+                    return Err(Error::raw(
+                        ErrorKind::ArgumentConflict,
+                        "Must not supply rate limits when using ipc protocol for json-rpc",
+                    ));
+                }
+                if config.web3_jwt_secret.is_some() {
+                    return Err(Error::raw(
+                        ErrorKind::ArgumentConflict,
+                        "Must not supply a jwt secret when using ipc protocol for json-rpc",
+                    ));
+                }
             }
         }
         Ok(config)
     }
+
+    /// Returns the password of the encrypted node keystore, if one was given, either directly or
+    /// by a password file.
+    pub fn read_keystore_password(&self) -> std::io::Result<Option<String>> {
+        match &self.keystore_password_file {
+            Some(path) => {
+                let password = fs::read_to_string(path)?;
+                Ok(Some(password.trim_end_matches(['\r', '\n']).to_string()))
+            }
+            None => Ok(self.keystore_password.clone()),
+        }
+    }
+
+    /// The command line interface, where every option may also be set by an environment variable
+    /// named after its long flag, e.g. `TRIN_WEB3_TRANSPORT` for `--web3-transport`.
+    ///
+    /// Options are resolved in order of precedence: command line flags, environment variables,
+    /// the `--config` file, and finally the defaults.
+    fn command_with_env() -> Command {
+        Self::command().mut_args(|arg| {
+            let env_var = arg
+                .get_long()
+                .filter(|long| !matches!(*long, "help" | "version"))
+                .map(env_var_name);
+            match env_var {
+                Some(env_var) => arg.env(env_var),
+                None => arg,
+            }
+        })
+    }
+
+    /// Prepends the options of the `--config` file, if any, to the command line arguments, so
+    /// that they're parsed and validated like flags. Options that are also given on the command
+    /// line or by environment variables are left out, so that those take precedence.
+    fn with_config_file_args(mut args: Vec<OsString>) -> Result<Vec<OsString>, clap::Error> {
+        let matches = Self::command_with_env().try_get_matches_from(&args)?;
+        let Some(path) = matches.get_one::<PathBuf>("config") else {
+            return Ok(args);
+        };
+        let contents = fs::read_to_string(path).map_err(|err| {
+            Error::raw(
+                ErrorKind::Io,
+                format!("Unable to read config file {}: {err}", path.display()),
+            )
+        })?;
+        let file_args = config_file_args(&contents, &matches)?;
+        let first_arg = args.len().min(1);
+        args.splice(first_arg..first_arg, file_args);
+        Ok(args)
+    }
+}
+
+/// Returns the name of the environment variable that sets the option with the `long` flag.
+pub fn env_var_name(long: &str) -> String {
+    format!("{ENV_VAR_PREFIX}{}", long.to_uppercase().replace('-', "_"))
+}
+
+/// Converts the options of a TOML config file to command line flags, skipping the options that
+/// were already given on the command line or by environment variables.
+fn config_file_args(contents: &str, matches: &ArgMatches) -> Result<Vec<OsString>, clap::Error> {
+    let invalid_config = |msg: String| {
+        Error::raw(
+            ErrorKind::InvalidValue,
+            format!("Invalid config file: {msg}"),
+        )
+    };
+    let options: toml::Table = contents
+        .parse()
+        .map_err(|err: toml::de::Error| invalid_config(err.to_string()))?;
+    let command = TrinConfig::command();
+    let mut args = vec![];
+    for (name, value) in options {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()))
+            .filter(|arg| !matches!(arg.get_id().as_str(), "config" | "help" | "version"))
+            .ok_or_else(|| invalid_config(format!("unknown option `{name}`")))?;
+        if matches!(
+            matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+        let flag = format!("--{name}");
+        match value {
+            toml::Value::Boolean(true) => args.push(flag.into()),
+            toml::Value::Boolean(false) => {}
+            toml::Value::Array(values) => {
+                let values: Vec<String> = values.into_iter().map(config_value_to_string).collect();
+                args.push(flag.into());
+                args.push(values.join(",").into());
+            }
+            toml::Value::Table(_) => {
+                return Err(invalid_config(format!(
+                    "option `{name}` must not be a table"
+                )))
+            }
+            value => {
+                args.push(flag.into());
+                args.push(config_value_to_string(value).into());
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn config_value_to_string(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+pub fn parse_ipc_mode(mode: &str) -> Result<u32, String> {
+    let digits = mode.strip_prefix("0o").unwrap_or(mode);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err(format!(
+            "Invalid ipc mode: {mode}, expected octal permissions between 000 and 777"
+        )),
+    }
 }
 
 pub fn check_private_key_length(private_key: &str) -> Result<H256, String> {
@@ -273,6 +659,89 @@ pub fn check_private_key_length(private_key: &str) -> Result<H256, String> {
     ))
 }
 
+fn check_subnetwork(network: &str) -> Result<String, String> {
+    match network {
+        HISTORY_NETWORK
+        | STATE_NETWORK
+        | BEACON_NETWORK
+        | TRANSACTION_GOSSIP_NETWORK
+        | CANONICAL_INDICES_NETWORK => Ok(network.to_string()),
+        _ => Err(format!(
+            "Invalid network: {network}, expected one of {HISTORY_NETWORK}, {STATE_NETWORK}, {BEACON_NETWORK}, {TRANSACTION_GOSSIP_NETWORK} or {CANONICAL_INDICES_NETWORK}"
+        )),
+    }
+}
+
+/// Parses a storage size like `500`, `2GB` or `1.5GiB` into megabytes. Sizes without a unit are
+/// in megabytes.
+pub fn parse_storage_size(size: &str) -> Result<u32, String> {
+    let size = size.trim();
+    let unit_start = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_start);
+    let number: f64 = number.parse().map_err(|_| {
+        format!("Invalid storage size: {size}, expected a number of megabytes or a size like 2GB")
+    })?;
+    let bytes_per_unit: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kb" | "k" => 1e3,
+        "" | "mb" | "m" => 1e6,
+        "gb" | "g" => 1e9,
+        "tb" | "t" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => {
+            return Err(format!(
+                "Invalid storage size unit: {unit}, expected one of B, KB, MB, GB, TB, KiB, MiB, GiB or TiB"
+            ))
+        }
+    };
+    let mb = number * bytes_per_unit / 1e6;
+    // Sizes are kept in whole megabytes, so smaller sizes would silently disable the storage.
+    if mb > 0.0 && mb < 1.0 {
+        return Err(format!(
+            "Storage size is too small: {size}, expected 0 or at least 1MB"
+        ));
+    }
+    let mb = mb.round();
+    if mb > f64::from(u32::MAX) {
+        return Err(format!("Storage size is too large: {size}"));
+    }
+    Ok(mb as u32)
+}
+
+/// Parses the size at which the JSON log file is rotated, which must not be 0, as every line would
+/// rotate the file.
+fn parse_log_file_size(size: &str) -> Result<u32, String> {
+    match parse_storage_size(size)? {
+        0 => Err(format!(
+            "Invalid log file size: {size}, expected at least 1MB"
+        )),
+        mb => Ok(mb),
+    }
+}
+
+fn check_query_parallelism(parallelism: &str) -> Result<usize, String> {
+    match parallelism.parse::<usize>() {
+        Ok(parallelism) if parallelism > 0 => Ok(parallelism),
+        _ => Err(format!(
+            "Invalid query parallelism: {parallelism}, expected a positive number of peers"
+        )),
+    }
+}
+
+fn check_query_timeout(timeout: &str) -> Result<u64, String> {
+    match timeout.parse::<u64>() {
+        Ok(timeout) if timeout > 0 => Ok(timeout),
+        _ => Err(format!(
+            "Invalid query timeout: {timeout}, expected a positive number of seconds"
+        )),
+    }
+}
+
 fn check_trusted_block_root(trusted_root: &str) -> Result<String, String> {
     if !trusted_root.starts_with("0x") {
         return Err("Trusted block root must be prefixed with 0x".to_owned());
@@ -297,8 +766,8 @@ impl fmt::Display for TrinConfig {
 
         write!(
             f,
-            "TrinConfig {{ networks: {:?}, capacity_mb: {}, ephemeral: {}, json_rpc_url: {}, metrics_enabled: {} }}",
-            self.networks, self.mb, self.ephemeral, json_rpc_url, self.enable_metrics_with_url.is_some()
+            "TrinConfig {{ network: {}, networks: {:?}, capacity_mb: {}, ephemeral: {}, json_rpc_url: {}, metrics_enabled: {} }}",
+            self.network, self.networks, self.mb, self.ephemeral, json_rpc_url, self.enable_metrics_with_url.is_some()
         )
     }
 }
@@ -307,6 +776,108 @@ impl fmt::Display for TrinConfig {
 #[allow(clippy::enum_variant_names)]
 pub enum TrinConfigCommands {
     CreateDashboard(DashboardConfig),
+    /// Validate the era1 archives in a directory and store their history network content, then
+    /// exit.
+    ImportEra1(ImportEra1Config),
+    /// Write the local history network content to a content archive, then exit.
+    ExportContent(ExportContentConfig),
+    /// Validate the content of a content archive and store it in the history network storage, then
+    /// exit. Block bodies and receipts are only stored if the archive also holds their headers.
+    ImportContent(ImportContentConfig),
+    /// Maintain the local storage, then exit.
+    Db(DbConfig),
+    /// Manage the node key, which determines the node id, then exit.
+    Keys(KeysConfig),
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct ImportEra1Config {
+    /// The directory holding the era1 archives.
+    pub dir: PathBuf,
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct ExportContentConfig {
+    /// The path of the content archive to write.
+    pub path: PathBuf,
+
+    /// The number of content items to skip, in the order of their content keys.
+    #[arg(long, default_value_t = 0)]
+    pub offset: u64,
+
+    /// The maximum number of content items to export. All items are exported if not set.
+    #[arg(long)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct ImportContentConfig {
+    /// The path of the content archive to read.
+    pub path: PathBuf,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct DbConfig {
+    #[command(subcommand)]
+    pub command: DbCommands,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum DbCommands {
+    /// Check the integrity of the stored history network content and report corrupt content.
+    Check(DbCheckConfig),
+    /// Replace the content database with a backup taken by admin_backupDb. The node must be
+    /// stopped, its stores derive their radius from the restored content once it starts again.
+    Restore(DbRestoreConfig),
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct DbCheckConfig {
+    /// Delete the corrupt content.
+    #[arg(long)]
+    pub repair: bool,
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct DbRestoreConfig {
+    /// The path of the backup to restore.
+    pub path: PathBuf,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct KeysConfig {
+    #[command(subcommand)]
+    pub command: KeysCommands,
+}
+
+/// The node key is kept in the data directory, in the encrypted keystore if a keystore password is
+/// given and in plaintext otherwise.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum KeysCommands {
+    /// Generate a new random node key.
+    Generate(KeysGenerateConfig),
+    /// Show the node id and ENR of the node key.
+    Show,
+    /// Import a node key from a file, holding either a 0x-prefixed hex encoded private key or an
+    /// encrypted keystore, which is decrypted with the keystore password.
+    Import(KeysImportConfig),
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct KeysGenerateConfig {
+    /// Replace the node key if one exists, which changes the node id.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct KeysImportConfig {
+    /// The file holding the private key to import.
+    pub path: PathBuf,
+
+    /// Replace the node key if one exists, which changes the node id.
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Args, Debug, Default, Clone, PartialEq)]
@@ -353,10 +924,12 @@ pub fn create_dashboard(
 #[allow(clippy::unwrap_used)]
 mod test {
     use super::*;
+    use serial_test::serial;
     use std::net::{IpAddr, Ipv4Addr};
     use test_log::test;
 
     #[test]
+    #[serial]
     fn test_default_args() {
         let expected_config = TrinConfig::default();
         let actual_config = TrinConfig::new_from(["trin"].iter()).unwrap();
@@ -368,15 +941,289 @@ mod test {
         assert_eq!(actual_config.external_addr, expected_config.external_addr);
         assert_eq!(actual_config.no_stun, expected_config.no_stun);
         assert_eq!(actual_config.no_upnp, expected_config.no_upnp);
+        assert_eq!(actual_config.ipv6, expected_config.ipv6);
         assert_eq!(actual_config.ephemeral, expected_config.ephemeral);
     }
 
     #[test]
+    #[serial]
+    fn test_query_args() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.query_parallelism, DEFAULT_QUERY_PARALLELISM);
+        assert_eq!(config.query_peer_timeout, DEFAULT_QUERY_PEER_TIMEOUT_SECS);
+        assert_eq!(config.query_timeout, DEFAULT_QUERY_TIMEOUT_SECS);
+
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--query-parallelism",
+                "5",
+                "--query-peer-timeout",
+                "4",
+                "--query-timeout",
+                "30",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.query_parallelism, 5);
+        assert_eq!(config.query_peer_timeout, 4);
+        assert_eq!(config.query_timeout, 30);
+        assert!(TrinConfig::new_from(["trin", "--query-parallelism", "0"].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--query-peer-timeout", "0"].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--query-timeout", "0"].iter()).is_err());
+    }
+
+    fn write_config_file(contents: &str) -> (tempfile::TempDir, String) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("trin.toml");
+        fs::write(&path, contents).unwrap();
+        (temp_dir, path.display().to_string())
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_file() {
+        let (_temp_dir, path) = write_config_file(
+            r#"
+            networks = ["history", "beacon"]
+            mb = 500
+            bootnodes = "none"
+            web3-transport = "http"
+            web3-http-address = "http://0.0.0.0:8080/"
+            ws = true
+            no-upnp = false
+            "#,
+        );
+        let config = TrinConfig::new_from(["trin", "--config", path.as_str()].iter()).unwrap();
+        assert_eq!(config.networks, vec!["history", "beacon"]);
+        assert_eq!(config.mb, 500);
+        assert_eq!(config.bootnodes, Bootnodes::None);
+        assert_eq!(config.web3_transport, Web3TransportType::HTTP);
+        assert_eq!(
+            config.web3_http_address,
+            Url::parse("http://0.0.0.0:8080/").unwrap()
+        );
+        assert!(config.ws);
+        assert!(!config.no_upnp);
+    }
+
+    #[test]
+    #[serial]
+    fn test_cli_flags_override_config_file() {
+        let (_temp_dir, path) = write_config_file("networks = [\"history\", \"beacon\"]\nmb = 500");
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--mb",
+                "200",
+                "--config",
+                path.as_str(),
+                "--networks",
+                "state",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.networks, vec!["state"]);
+        assert_eq!(config.mb, 200);
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_vars() {
+        assert_eq!(env_var_name("web3-transport"), "TRIN_WEB3_TRANSPORT");
+        // The environment is shared by all tests of the process, so every test that parses a
+        // config is serial, and no other test relies on these options.
+        env::set_var("TRIN_MAX_LOGS_BLOCK_RANGE", "200");
+        env::set_var("TRIN_DISABLE_POKE", "true");
+        let (_temp_dir, path) =
+            write_config_file("max-logs-block-range = 100\ndisable-poke = false");
+
+        let config = TrinConfig::new_from(["trin", "--config", path.as_str()].iter()).unwrap();
+        assert_eq!(config.max_logs_block_range, 200);
+        assert!(config.disable_poke);
+        let config =
+            TrinConfig::new_from(["trin", "--max-logs-block-range", "300"].iter()).unwrap();
+        assert_eq!(config.max_logs_block_range, 300);
+
+        env::remove_var("TRIN_MAX_LOGS_BLOCK_RANGE");
+        env::remove_var("TRIN_DISABLE_POKE");
+        let config = TrinConfig::new_from(["trin", "--config", path.as_str()].iter()).unwrap();
+        assert_eq!(config.max_logs_block_range, 100);
+        assert!(!config.disable_poke);
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_file_is_validated() {
+        let (_temp_dir, path) = write_config_file("megabytes = 500");
+        let err = TrinConfig::new_from(["trin", "--config", path.as_str()].iter()).unwrap_err();
+        assert!(err.to_string().contains("unknown option `megabytes`"));
+
+        let (_temp_dir, path) = write_config_file("query-parallelism = 0");
+        assert!(TrinConfig::new_from(["trin", "--config", path.as_str()].iter()).is_err());
+
+        assert!(TrinConfig::new_from(["trin", "--config", "/does/not/exist.toml"].iter()).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_networks() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.networks, vec![HISTORY_NETWORK]);
+        let config =
+            TrinConfig::new_from(["trin", "--networks", "history,state,beacon"].iter()).unwrap();
+        assert_eq!(
+            config.networks,
+            vec![HISTORY_NETWORK, STATE_NETWORK, BEACON_NETWORK]
+        );
+        let config = TrinConfig::new_from(["trin", "--networks", "beacon"].iter()).unwrap();
+        assert_eq!(config.networks, vec![BEACON_NETWORK]);
+
+        assert!(TrinConfig::new_from(["trin", "--networks", "history,execution"].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--networks", ""].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--networks", "history,history"].iter()).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_size() {
+        assert_eq!(parse_storage_size("500").unwrap(), 500);
+        assert_eq!(parse_storage_size("500MB").unwrap(), 500);
+        assert_eq!(parse_storage_size("2GB").unwrap(), 2000);
+        assert_eq!(parse_storage_size("1.5 gb").unwrap(), 1500);
+        assert_eq!(parse_storage_size("1T").unwrap(), 1_000_000);
+        assert_eq!(parse_storage_size("512MiB").unwrap(), 537);
+        assert_eq!(parse_storage_size("1GiB").unwrap(), 1074);
+        assert_eq!(parse_storage_size("1000KB").unwrap(), 1);
+        assert_eq!(parse_storage_size("0").unwrap(), 0);
+        assert!(parse_storage_size("100KB").is_err());
+        assert!(parse_storage_size("0.4").is_err());
+        assert!(parse_storage_size("GB").is_err());
+        assert!(parse_storage_size("2 parsecs").is_err());
+        assert!(parse_storage_size("1.2.3GB").is_err());
+        assert!(parse_storage_size("5000TB").is_err());
+
+        let config = TrinConfig::new_from(["trin", "--storage", "2GB"].iter()).unwrap();
+        assert_eq!(config.mb, 2000);
+        let config = TrinConfig::new_from(["trin", "--mb", "1GiB"].iter()).unwrap();
+        assert_eq!(config.mb, 1074);
+    }
+
+    #[test]
+    #[serial]
+    fn test_logging_args() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert!(!config.disable_console_log);
+        assert_eq!(config.json_log_dir, None);
+        assert_eq!(config.json_log_max_file_size, 100);
+        assert_eq!(config.json_log_max_files, DEFAULT_JSON_LOG_MAX_FILES);
+
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--disable-console-log",
+                "--json-log-dir",
+                "/var/log/trin",
+                "--json-log-max-file-size",
+                "1GB",
+                "--json-log-max-files",
+                "3",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert!(config.disable_console_log);
+        assert_eq!(config.json_log_dir, Some(PathBuf::from("/var/log/trin")));
+        assert_eq!(config.json_log_max_file_size, 1000);
+        assert_eq!(config.json_log_max_files, 3);
+
+        assert!(TrinConfig::new_from(["trin", "--disable-console-log"].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--json-log-max-file-size", "0"].iter()).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_network() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.network, PortalNetwork::Mainnet);
+        let config =
+            TrinConfig::new_from(["trin", "--network", "angelfood", "--networks", "beacon"].iter())
+                .unwrap();
+        assert_eq!(config.network, PortalNetwork::Angelfood);
+        assert!(TrinConfig::new_from(["trin", "--network", "devnet"].iter()).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_history_validation_requires_network_accumulator() {
+        let config = TrinConfig::new_from(["trin", "--network", "testnet"].iter()).unwrap();
+        assert_eq!(config.networks, vec![BEACON_NETWORK]);
+        let err =
+            TrinConfig::new_from(["trin", "--network", "testnet", "--networks", "history"].iter())
+                .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("only the master accumulator of mainnet is available"));
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--network",
+                "testnet",
+                "--master-accumulator-path",
+                "path/to/testnet_macc.bin",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.networks, vec![HISTORY_NETWORK]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_keystore_password() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.read_keystore_password().unwrap(), None);
+        let config =
+            TrinConfig::new_from(["trin", "--keystore-password", "passphrase"].iter()).unwrap();
+        assert_eq!(
+            config.read_keystore_password().unwrap(),
+            Some("passphrase".to_string())
+        );
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("password.txt");
+        fs::write(&path, "passphrase\n").unwrap();
+        let path = path.display().to_string();
+        let config =
+            TrinConfig::new_from(["trin", "--keystore-password-file", path.as_str()].iter())
+                .unwrap();
+        assert_eq!(
+            config.read_keystore_password().unwrap(),
+            Some("passphrase".to_string())
+        );
+        assert!(TrinConfig::new_from(
+            [
+                "trin",
+                "--keystore-password",
+                "passphrase",
+                "--keystore-password-file",
+                path.as_str()
+            ]
+            .iter()
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[serial]
     fn test_help() {
         TrinConfig::new_from(["trin", "-h"].iter()).expect_err("Should be an error to exit early");
     }
 
     #[test]
+    #[serial]
     fn test_custom_http_args() {
         let expected_config = TrinConfig {
             web3_http_address: Url::parse("http://0.0.0.0:8080/").unwrap(),
@@ -402,6 +1249,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     fn test_ipc_protocol() {
         let actual_config: TrinConfig = Default::default();
         let expected_config = TrinConfig {
@@ -417,6 +1265,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     fn test_ipc_with_custom_path() {
         let actual_config =
             TrinConfig::new_from(["trin", "--web3-ipc-path", "/path/test.ipc"].iter()).unwrap();
@@ -435,6 +1284,26 @@ mod test {
     }
 
     #[test]
+    #[serial]
+    fn test_ipc_mode() {
+        let actual_config =
+            TrinConfig::new_from(["trin", "--web3-ipc-mode", "600"].iter()).unwrap();
+        assert_eq!(actual_config.web3_ipc_mode, Some(0o600));
+        assert_eq!(parse_ipc_mode("0o660"), Ok(0o660));
+        assert!(parse_ipc_mode("800").is_err());
+        assert!(parse_ipc_mode("1777").is_err());
+    }
+
+    #[test]
+    #[serial]
+    #[should_panic(expected = "Must not supply an ipc mode when using http")]
+    fn test_http_protocol_rejects_ipc_mode() {
+        TrinConfig::new_from(["trin", "--web3-transport", "http", "--web3-ipc-mode", "600"].iter())
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
     #[should_panic(expected = "Must not supply an ipc path when using http")]
 
     fn test_http_protocol_rejects_custom_web3_ipc_path() {
@@ -452,6 +1321,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     #[should_panic(expected = "Must not supply an http address when using ipc")]
     fn test_ipc_protocol_rejects_custom_web3_http_address() {
         TrinConfig::new_from(["trin", "--web3-http-address", "http://127.0.0.1:1234/"].iter())
@@ -459,6 +1329,85 @@ mod test {
     }
 
     #[test]
+    #[serial]
+    fn test_http_cors_domains() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-transport",
+                "http",
+                "--web3-http-cors-domains",
+                "http://localhost:3000,https://app.example.com",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            actual_config.web3_http_cors_domains,
+            Some("http://localhost:3000,https://app.example.com".to_string())
+        );
+    }
+
+    #[test]
+    #[serial]
+    #[should_panic(expected = "Must not supply cors domains when using ipc")]
+    fn test_ipc_protocol_rejects_cors_domains() {
+        TrinConfig::new_from(["trin", "--web3-http-cors-domains", "*"].iter()).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    #[should_panic(expected = "Must not supply a jwt secret when using ipc")]
+    fn test_ipc_protocol_rejects_jwt_secret() {
+        TrinConfig::new_from(["trin", "--web3-jwt-secret", "/path/jwt.hex"].iter()).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_web3_admin() {
+        assert!(!TrinConfig::new_from(["trin"].iter()).unwrap().web3_admin);
+        assert!(
+            TrinConfig::new_from(["trin", "--web3-admin"].iter())
+                .unwrap()
+                .web3_admin
+        );
+        TrinConfig::new_from(["trin", "--web3-transport", "http", "--web3-admin"].iter()).unwrap();
+        TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-transport",
+                "http",
+                "--web3-http-address",
+                "http://0.0.0.0:8545/",
+                "--web3-jwt-secret",
+                "/path/jwt.hex",
+                "--web3-admin",
+            ]
+            .iter(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    #[should_panic(expected = "Must not serve the admin methods over http on a non-localhost")]
+    fn test_web3_admin_rejects_public_http_without_jwt_secret() {
+        TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-transport",
+                "http",
+                "--web3-http-address",
+                "http://0.0.0.0:8545/",
+                "--web3-admin",
+            ]
+            .iter(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[serial]
     fn test_custom_discovery_port() {
         let expected_config = TrinConfig {
             discovery_port: 999,
@@ -470,6 +1419,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     fn test_manual_external_addr_v4() {
         let actual_config =
             TrinConfig::new_from(["trin", "--external-address", "127.0.0.1:1234"].iter()).unwrap();
@@ -480,6 +1430,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     fn test_manual_external_addr_v6() {
         let actual_config =
             TrinConfig::new_from(["trin", "--external-address", "[::1]:1234"].iter()).unwrap();
@@ -490,6 +1441,14 @@ mod test {
     }
 
     #[test]
+    #[serial]
+    fn test_ipv6() {
+        let actual_config = TrinConfig::new_from(["trin", "--ipv6"].iter()).unwrap();
+        assert!(actual_config.ipv6);
+    }
+
+    #[test]
+    #[serial]
     fn test_custom_private_key() {
         let expected_config = TrinConfig {
             private_key: Some(H256::from_slice(&[1; 32])),
@@ -508,6 +1467,17 @@ mod test {
     }
 
     #[test]
+    #[serial]
+    fn test_storage_read_only() {
+        let config = TrinConfig::new_from(["trin", "--storage-read-only"].iter()).unwrap();
+        assert!(config.storage_read_only);
+        assert!(
+            TrinConfig::new_from(["trin", "--storage-read-only", "--ephemeral"].iter()).is_err()
+        );
+    }
+
+    #[test]
+    #[serial]
     fn test_ephemeral() {
         let expected_config = TrinConfig {
             ephemeral: true,
@@ -518,6 +1488,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     fn test_enable_metrics_with_url() {
         let expected_config = TrinConfig {
             enable_metrics_with_url: Some(SocketAddr::new(
@@ -536,6 +1507,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     #[should_panic(
         expected = "Invalid private key length: 65, expected 66 (0x-prefixed 32 byte hexstring)"
     )]
@@ -552,6 +1524,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     #[should_panic(
         expected = "Invalid private key length: 64, expected 66 (0x-prefixed 32 byte hexstring)"
     )]
@@ -568,6 +1541,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     #[should_panic(
         expected = "Invalid trusted block root length: 64, expected 66 (0x-prefixed 32 byte hexstring)"
     )]
@@ -584,6 +1558,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     #[should_panic(expected = "Trusted block root must be prefixed with 0x")]
     fn test_trusted_block_root_starts_with_0x() {
         TrinConfig::new_from(
@@ -598,6 +1573,101 @@ mod test {
     }
 
     #[test]
+    #[serial]
+    fn test_trin_with_import_era1() {
+        let config =
+            TrinConfig::try_parse_from(["trin", "--mb", "500", "import-era1", "./era1"]).unwrap();
+        assert_eq!(config.mb, 500);
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::ImportEra1(ImportEra1Config {
+                dir: PathBuf::from("./era1"),
+            }))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_trin_with_export_and_import_content() {
+        let config =
+            TrinConfig::try_parse_from(["trin", "export-content", "seed.archive", "--limit", "10"])
+                .unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::ExportContent(ExportContentConfig {
+                path: PathBuf::from("seed.archive"),
+                offset: 0,
+                limit: Some(10),
+            }))
+        );
+
+        let config =
+            TrinConfig::try_parse_from(["trin", "import-content", "seed.archive"]).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::ImportContent(ImportContentConfig {
+                path: PathBuf::from("seed.archive"),
+            }))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_trin_with_db_check() {
+        let config = TrinConfig::try_parse_from(["trin", "db", "check", "--repair"]).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Check(DbCheckConfig { repair: true }),
+            }))
+        );
+
+        let config =
+            TrinConfig::try_parse_from(["trin", "db", "restore", "backup.sqlite"]).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Restore(DbRestoreConfig {
+                    path: PathBuf::from("backup.sqlite"),
+                }),
+            }))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_trin_with_keys() {
+        let config = TrinConfig::try_parse_from(["trin", "keys", "generate", "--force"]).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Keys(KeysConfig {
+                command: KeysCommands::Generate(KeysGenerateConfig { force: true }),
+            }))
+        );
+
+        let config = TrinConfig::try_parse_from(["trin", "keys", "show"]).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Keys(KeysConfig {
+                command: KeysCommands::Show,
+            }))
+        );
+
+        let config = TrinConfig::try_parse_from(["trin", "keys", "import", "node.key"]).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Keys(KeysConfig {
+                command: KeysCommands::Import(KeysImportConfig {
+                    path: PathBuf::from("node.key"),
+                    force: false,
+                }),
+            }))
+        );
+        assert!(TrinConfig::try_parse_from(["trin", "keys", "import"]).is_err());
+    }
+
+    #[test]
+    #[serial]
     fn test_trin_with_create_dashboard() {
         let config = TrinConfig::try_parse_from([
             "trin",
@@ -625,6 +1695,7 @@ mod test {
     }
 
     #[test]
+    #[serial]
     #[should_panic(expected = "Invalid web3-transport arg. Expected either 'http' or 'ipc'")]
     fn test_invalid_web3_transport_argument() {
         TrinConfig::new_from(["trin", "--web3-transport", "invalid"].iter()).unwrap();
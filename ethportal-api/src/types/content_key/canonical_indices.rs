@@ -0,0 +1,143 @@
+use crate::{
+    types::content_key::{error::ContentKeyError, overlay::OverlayContentKey},
+    utils::bytes::{hex_decode, hex_encode, hex_encode_compact},
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::fmt;
+
+/// A content key in the canonical indices overlay network.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[ssz(enum_behaviour = "union")]
+pub enum CanonicalIndicesContentKey {
+    /// The position of a transaction in the canonical chain.
+    TransactionIndex(TransactionIndexKey),
+}
+
+/// A key for the position of a transaction in the canonical chain.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+pub struct TransactionIndexKey {
+    /// Hash of the signed transaction.
+    pub transaction_hash: [u8; 32],
+}
+
+impl Serialize for CanonicalIndicesContentKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for CanonicalIndicesContentKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = String::deserialize(deserializer)?.to_lowercase();
+
+        if !data.starts_with("0x") {
+            return Err(de::Error::custom(format!(
+                "Hex strings must start with 0x, but found {}",
+                &data[..2]
+            )));
+        }
+
+        let ssz_bytes = hex_decode(&data).map_err(de::Error::custom)?;
+
+        CanonicalIndicesContentKey::from_ssz_bytes(&ssz_bytes)
+            .map_err(|e| ContentKeyError::DecodeSsz {
+                decode_error: e,
+                input: hex_encode(ssz_bytes),
+            })
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<CanonicalIndicesContentKey> for Vec<u8> {
+    fn from(val: CanonicalIndicesContentKey) -> Self {
+        val.as_ssz_bytes()
+    }
+}
+
+impl TryFrom<Vec<u8>> for CanonicalIndicesContentKey {
+    type Error = ContentKeyError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        CanonicalIndicesContentKey::from_ssz_bytes(&value).map_err(|e| ContentKeyError::DecodeSsz {
+            decode_error: e,
+            input: hex_encode(value),
+        })
+    }
+}
+
+impl fmt::Display for CanonicalIndicesContentKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::TransactionIndex(key) => format!(
+                "TransactionIndex {{ transaction_hash: {} }}",
+                hex_encode_compact(key.transaction_hash)
+            ),
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+impl OverlayContentKey for CanonicalIndicesContentKey {
+    fn content_id(&self) -> [u8; 32] {
+        let mut sha256 = Sha256::new();
+        sha256.update(self.as_ssz_bytes());
+        sha256.finalize().into()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    const TRANSACTION_HASH: [u8; 32] = [
+        0x33, 0x46, 0x9b, 0x22, 0xe9, 0xf6, 0x36, 0x35, 0x6c, 0x41, 0x60, 0xa8, 0x7e, 0xb1, 0x9d,
+        0xf5, 0x2b, 0x74, 0x12, 0xe8, 0xea, 0xc3, 0x2a, 0x4a, 0x55, 0xff, 0xe8, 0x8e, 0xa8, 0x35,
+        0x07, 0x88,
+    ];
+
+    #[test]
+    fn transaction_index() {
+        let key = CanonicalIndicesContentKey::TransactionIndex(TransactionIndexKey {
+            transaction_hash: TRANSACTION_HASH,
+        });
+        let encoded: Vec<u8> = key.clone().into();
+
+        assert_eq!(encoded[0], 0x00);
+        assert_eq!(encoded[1..], TRANSACTION_HASH);
+        assert_eq!(
+            CanonicalIndicesContentKey::try_from(encoded.clone()).unwrap(),
+            key
+        );
+        let content_id: [u8; 32] = Sha256::digest(&encoded).into();
+        assert_eq!(key.content_id(), content_id);
+    }
+
+    #[test]
+    fn ser_de_transaction_index() {
+        let key = CanonicalIndicesContentKey::TransactionIndex(TransactionIndexKey {
+            transaction_hash: TRANSACTION_HASH,
+        });
+        let json = serde_json::to_string(&key).unwrap();
+
+        assert_eq!(json, format!("\"{}\"", key.to_hex()));
+        assert_eq!(
+            serde_json::from_str::<CanonicalIndicesContentKey>(&json).unwrap(),
+            key
+        );
+    }
+}
@@ -98,6 +98,14 @@ pub struct BlockReceiptsKey {
     pub block_hash: [u8; 32],
 }
 
+impl From<H256> for BlockReceiptsKey {
+    fn from(block_hash: H256) -> Self {
+        Self {
+            block_hash: block_hash.to_fixed_bytes(),
+        }
+    }
+}
+
 /// A key for an epoch header accumulator.
 #[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 pub struct EpochAccumulatorKey {
@@ -1,5 +1,9 @@
-use crate::{types::content_key::overlay::OverlayContentKey, utils::bytes::hex_encode_compact};
+use crate::{
+    types::content_key::{error::ContentKeyError, overlay::OverlayContentKey},
+    utils::bytes::{hex_decode, hex_encode, hex_encode_compact},
+};
 use ethereum_types::{U256, U512};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{Digest as Sha2Digest, Sha256};
 use sha3::{Digest, Keccak256};
 use ssz::{Decode, Encode};
@@ -8,7 +12,7 @@ use ssz_types::{typenum, FixedVector, VariableList};
 use std::fmt;
 
 /// A content key in the state overlay network.
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 #[ssz(enum_behaviour = "union")]
 pub enum StateContentKey {
     /// A trie node from the state trie.
@@ -26,7 +30,7 @@ pub enum StateContentKey {
 }
 
 /// A key for a trie node from the state trie.
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 pub struct AccountTrieNode {
     /// Trie path of the node.
     pub path: VariableList<u8, typenum::U64>,
@@ -37,47 +41,81 @@ pub struct AccountTrieNode {
 }
 
 /// A key for a trie node from some account's contract storage.
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 pub struct ContractStorageTrieNode {
     /// Address of the account.
-    address: FixedVector<u8, typenum::U20>,
+    pub address: FixedVector<u8, typenum::U20>,
     /// Trie path of the node.
-    path: VariableList<u8, typenum::U64>,
+    pub path: VariableList<u8, typenum::U64>,
     /// Hash of the node.
-    node_hash: [u8; 32],
+    pub node_hash: [u8; 32],
     /// Hash of the root of the state trie in which the node exists.
-    state_root: [u8; 32],
+    pub state_root: [u8; 32],
 }
 
 /// A key for a leaf node from the state trie and the associated Merkle proof against a particular
 /// state root.
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 pub struct AccountTrieProof {
     /// Address of the account.
-    address: FixedVector<u8, typenum::U20>,
+    pub address: FixedVector<u8, typenum::U20>,
     /// Hash of the root of the state trie in which the node exists.
-    state_root: [u8; 32],
+    pub state_root: [u8; 32],
 }
 
 /// A key for a leaf node from some account's contract storage and the associated Merkle proof
 /// against a particular state root.
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 pub struct ContractStorageTrieProof {
     /// Address of the account.
-    address: FixedVector<u8, typenum::U20>,
+    pub address: FixedVector<u8, typenum::U20>,
     /// Storage slot.
-    slot: U256,
+    pub slot: U256,
     /// Hash of the root of the state trie in which the node exists.
-    state_root: [u8; 32],
+    pub state_root: [u8; 32],
 }
 
 /// A key for an account's contract bytecode.
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
 pub struct ContractBytecode {
     /// Address of the account.
-    address: FixedVector<u8, typenum::U20>,
+    pub address: FixedVector<u8, typenum::U20>,
     /// Hash of the bytecode.
-    code_hash: [u8; 32],
+    pub code_hash: [u8; 32],
+}
+
+impl Serialize for StateContentKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for StateContentKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = String::deserialize(deserializer)?.to_lowercase();
+
+        if !data.starts_with("0x") {
+            return Err(de::Error::custom(format!(
+                "Hex strings must start with 0x, but found {}",
+                &data[..2]
+            )));
+        }
+
+        let ssz_bytes = hex_decode(&data).map_err(de::Error::custom)?;
+
+        StateContentKey::from_ssz_bytes(&ssz_bytes)
+            .map_err(|e| ContentKeyError::DecodeSsz {
+                decode_error: e,
+                input: hex_encode(ssz_bytes),
+            })
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 // Silence clippy to avoid implementing newtype pattern on imported type.
@@ -211,7 +249,7 @@ impl OverlayContentKey for StateContentKey {
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        panic!("Not implemented: Implement in pr along w/ new state content key spec update.")
+        self.as_ssz_bytes()
     }
 }
 
@@ -363,4 +401,21 @@ mod test {
         assert_eq!(hex_decode(expected_content_key).unwrap(), encoded);
         assert_eq!(expected_content_id, key.content_id());
     }
+
+    #[test]
+    fn ser_de_contract_bytecode() {
+        let content_key_json = "\"0x04829bd824b016326a401d083b33d092293333a830d1c390624d3bd4e409a61a858e5dcc5517729a9170d014a6c96530d64dd8621d\"";
+        let expected_content_key = StateContentKey::ContractBytecode(ContractBytecode {
+            address: ADDRESS.to_vec().into(),
+            code_hash: CODE_HASH,
+        });
+
+        let content_key: StateContentKey = serde_json::from_str(content_key_json).unwrap();
+
+        assert_eq!(content_key, expected_content_key);
+        assert_eq!(
+            serde_json::to_string(&content_key).unwrap(),
+            content_key_json
+        );
+    }
 }
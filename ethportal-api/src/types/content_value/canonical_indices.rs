@@ -0,0 +1,48 @@
+use crate::{types::content_value::ContentValue, utils::bytes::hex_encode, ContentValueError};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+
+/// A Portal Canonical Indices content value: the position of a transaction in the canonical chain.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct TransactionIndex {
+    /// Hash of the block that includes the transaction.
+    pub block_hash: [u8; 32],
+    /// Index of the transaction within the block's transactions.
+    pub index: u64,
+}
+
+impl ContentValue for TransactionIndex {
+    fn encode(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn decode(buf: &[u8]) -> Result<Self, ContentValueError> {
+        Self::from_ssz_bytes(buf).map_err(|decode_error| ContentValueError::DecodeSsz {
+            decode_error,
+            input: hex_encode(buf),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ssz_transaction_index() {
+        let value = TransactionIndex {
+            block_hash: [1; 32],
+            index: 3,
+        };
+        let encoded = ContentValue::encode(&value);
+
+        assert_eq!(encoded.len(), 40);
+        assert_eq!(encoded[32..], [3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            <TransactionIndex as ContentValue>::decode(&encoded).unwrap(),
+            value
+        );
+        assert!(<TransactionIndex as ContentValue>::decode(&encoded[1..]).is_err());
+    }
+}
@@ -1,9 +1,12 @@
 use crate::ContentValueError;
 
 pub mod beacon;
+pub mod canonical_indices;
 pub mod constants;
 pub mod error;
 pub mod history;
+pub mod state;
+pub mod transaction_gossip;
 
 /// An encodable portal network content value.
 pub trait ContentValue: Sized {
@@ -0,0 +1,147 @@
+use crate::{
+    types::{constants::CONTENT_ABSENT, content_value::ContentValue},
+    utils::bytes::{hex_decode, hex_encode},
+    ContentValueError,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ssz_derive::{Decode, Encode};
+use ssz_types::{typenum, VariableList};
+
+/// A Portal State content value.
+///
+/// The value is carried as the raw bytes received from the network, whose encoding depends on the
+/// content key:
+/// - trie nodes are the RLP encoded node,
+/// - account trie proofs are an SSZ encoded [TrieProof],
+/// - contract storage trie proofs are an SSZ encoded [ContractStorageProof],
+/// - contract bytecode is the bytecode itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateContentValue(pub Vec<u8>);
+
+/// An RLP encoded node of the account trie or of a contract storage trie.
+pub type EncodedTrieNode = VariableList<u8, typenum::U1024>;
+
+/// The encoded nodes on the path from the root of a trie to one of its keys, starting at the root.
+///
+/// Nodes that are embedded in their parent are not repeated in the proof.
+pub type TrieProof = VariableList<EncodedTrieNode, typenum::U65>;
+
+/// The proofs of a contract storage slot against a state root: the proof of the account in the
+/// account trie, followed by the proof of the slot in the storage trie of the account.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ContractStorageProof {
+    pub account_proof: TrieProof,
+    pub storage_proof: TrieProof,
+}
+
+/// A content response from the RPC server.
+///
+/// This type allows the RPC response to be non-error,
+/// functioning as an Option, but with None serializing to "0x"
+/// rather than 'null'.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PossibleStateContentValue {
+    ContentPresent(StateContentValue),
+    ContentAbsent,
+}
+
+impl Serialize for PossibleStateContentValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            PossibleStateContentValue::ContentPresent(content) => content.serialize(serializer),
+            PossibleStateContentValue::ContentAbsent => serializer.serialize_str(CONTENT_ABSENT),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PossibleStateContentValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        if s.as_str() == CONTENT_ABSENT {
+            return Ok(PossibleStateContentValue::ContentAbsent);
+        }
+
+        let content_bytes = hex_decode(&s).map_err(serde::de::Error::custom)?;
+        Ok(Self::ContentPresent(StateContentValue(content_bytes)))
+    }
+}
+
+impl ContentValue for StateContentValue {
+    fn encode(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    fn decode(buf: &[u8]) -> Result<Self, ContentValueError> {
+        if buf.is_empty() {
+            return Err(ContentValueError::DeserializeAbsentContent);
+        }
+        Ok(Self(buf.to_vec()))
+    }
+}
+
+impl Serialize for StateContentValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex_encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for StateContentValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let content_bytes = hex_decode(&s).map_err(serde::de::Error::custom)?;
+        Self::decode(&content_bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ssz::{Decode, Encode};
+
+    #[test]
+    fn ser_de_state_content_value() {
+        let content_value_json = "\"0x01020304\"";
+        let content_value: StateContentValue = serde_json::from_str(content_value_json).unwrap();
+
+        assert_eq!(content_value, StateContentValue(vec![1, 2, 3, 4]));
+        assert_eq!(
+            serde_json::to_string(&content_value).unwrap(),
+            content_value_json
+        );
+    }
+
+    #[test]
+    fn ssz_contract_storage_proof() {
+        let proof = ContractStorageProof {
+            account_proof: vec![vec![1, 2].into(), vec![3].into()].into(),
+            storage_proof: vec![].into(),
+        };
+        let encoded = proof.as_ssz_bytes();
+        assert_eq!(
+            ContractStorageProof::from_ssz_bytes(&encoded).unwrap(),
+            proof
+        );
+    }
+
+    #[test]
+    fn ser_de_possible_state_content_value_absent() {
+        let content_value: PossibleStateContentValue = serde_json::from_str("\"0x\"").unwrap();
+
+        assert_eq!(content_value, PossibleStateContentValue::ContentAbsent);
+        assert_eq!(serde_json::to_string(&content_value).unwrap(), "\"0x\"");
+    }
+}
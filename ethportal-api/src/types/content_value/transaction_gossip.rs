@@ -0,0 +1,60 @@
+use crate::{
+    types::content_value::{state::TrieProof, ContentValue},
+    utils::bytes::hex_encode,
+    ContentValueError,
+};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use ssz_types::{typenum, VariableList};
+
+/// Largest signed transaction that is gossiped, matching the limit of execution clients' pools.
+pub type MaxTransactionLength = typenum::U131072;
+
+/// A Portal Transaction Gossip content value.
+///
+/// Pending transactions are gossiped with the proof of their sender's account against a recent
+/// state root, so that peers can check the sender's nonce and balance without a state lookup.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct TransactionGossipContentValue {
+    /// The signed transaction, in its execution layer encoding.
+    pub transaction: VariableList<u8, MaxTransactionLength>,
+    /// The state root that the account proof is against.
+    pub state_root: [u8; 32],
+    /// The proof of the sender's account in the account trie, encoded as in the state network.
+    pub account_proof: TrieProof,
+}
+
+impl ContentValue for TransactionGossipContentValue {
+    fn encode(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn decode(buf: &[u8]) -> Result<Self, ContentValueError> {
+        Self::from_ssz_bytes(buf).map_err(|decode_error| ContentValueError::DecodeSsz {
+            decode_error,
+            input: hex_encode(buf),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ssz_transaction_gossip_content_value() {
+        let value = TransactionGossipContentValue {
+            transaction: vec![0x02, 0xf8].into(),
+            state_root: [1; 32],
+            account_proof: vec![vec![3, 4].into()].into(),
+        };
+        let encoded = ContentValue::encode(&value);
+
+        assert_eq!(
+            <TransactionGossipContentValue as ContentValue>::decode(&encoded).unwrap(),
+            value
+        );
+        assert!(<TransactionGossipContentValue as ContentValue>::decode(&encoded[1..]).is_err());
+    }
+}
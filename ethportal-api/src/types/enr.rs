@@ -13,6 +13,31 @@ use validator::ValidationError;
 
 pub type Enr = Discv5Enr<CombinedKey>;
 
+/// ENR key under which nodes advertise the portal protocol versions they support, as one byte per
+/// version.
+pub const ENR_PROTOCOL_VERSIONS_KEY: &str = "pv";
+
+/// Portal protocol versions supported by this client.
+pub const SUPPORTED_PROTOCOL_VERSIONS: [u8; 1] = [0];
+
+/// Returns the portal protocol versions advertised in `enr`. Nodes that don't advertise any
+/// versions only support the initial version 0.
+pub fn protocol_versions(enr: &Enr) -> Vec<u8> {
+    match enr.get(ENR_PROTOCOL_VERSIONS_KEY) {
+        Some(versions) => versions.to_vec(),
+        None => vec![0],
+    }
+}
+
+/// Returns the highest portal protocol version supported by both the local node and the node of
+/// `enr`, or `None` if the nodes are incompatible.
+pub fn negotiate_protocol_version(enr: &Enr) -> Option<u8> {
+    protocol_versions(enr)
+        .into_iter()
+        .filter(|version| SUPPORTED_PROTOCOL_VERSIONS.contains(version))
+        .max()
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SszEnr(pub Enr);
 
@@ -100,6 +125,7 @@ pub fn generate_random_remote_enr() -> (CombinedKey, Enr) {
 
 #[cfg(test)]
 mod test {
+    use super::*;
     use crate::{
         generate_random_node_id,
         types::distance::{Metric, XorMetric},
@@ -107,6 +133,29 @@ mod test {
     use discv5::enr::NodeId;
     use test_log::test;
 
+    fn enr_with_protocol_versions(versions: &[u8]) -> Enr {
+        let key = CombinedKey::generate_secp256k1();
+        Discv5Enr::builder()
+            .udp4(8000)
+            .add_value(ENR_PROTOCOL_VERSIONS_KEY, &versions)
+            .build(&key)
+            .expect("Failed to generate ENR.")
+    }
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        let (_, legacy_enr) = generate_random_remote_enr();
+        assert_eq!(protocol_versions(&legacy_enr), vec![0]);
+        assert_eq!(negotiate_protocol_version(&legacy_enr), Some(0));
+
+        let enr = enr_with_protocol_versions(&[0, 1]);
+        assert_eq!(protocol_versions(&enr), vec![0, 1]);
+        assert_eq!(negotiate_protocol_version(&enr), Some(0));
+
+        let enr = enr_with_protocol_versions(&[1, 2]);
+        assert_eq!(negotiate_protocol_version(&enr), None);
+    }
+
     #[test]
     fn test_generate_random_node_id_1() {
         let target_bucket_idx: u8 = 5;
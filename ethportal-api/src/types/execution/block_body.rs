@@ -3,7 +3,7 @@ use std::sync::Arc;
 use anyhow::{anyhow, bail};
 use eth_trie::{EthTrie, MemoryDB, Trie};
 use ethereum_types::H256;
-use rlp::RlpStream;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use serde::Deserialize;
 use sha3::{Digest, Keccak256};
 use ssz::{Decode, Encode, SszDecoderBuilder, SszEncoder};
@@ -220,6 +220,40 @@ impl ssz::Decode for BlockBodyLegacy {
     }
 }
 
+/// RLP list of the transactions list and the uncles list, as stored in era1 archives. Typed
+/// transactions are wrapped in byte strings.
+impl Encodable for BlockBodyLegacy {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        s.begin_list(self.txs.len());
+        for tx in &self.txs {
+            match tx {
+                Transaction::Legacy(_) => s.append_raw(&tx.encode(), 1),
+                _ => s.append(&tx.encode()),
+            };
+        }
+        s.append_list(&self.uncles);
+    }
+}
+
+impl Decodable for BlockBodyLegacy {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 2 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        let txs = rlp
+            .at(0)?
+            .iter()
+            .map(|tx| match tx.is_list() {
+                true => Transaction::decode(tx.as_raw()),
+                false => Transaction::decode(tx.data()?),
+            })
+            .collect::<Result<_, _>>()?;
+        let uncles = rlp.list_at(1)?;
+        Ok(Self { txs, uncles })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 pub struct BlockBodyMerge {
     pub txs: Vec<Transaction>,
@@ -413,6 +447,17 @@ mod tests {
         );
     }
 
+    #[test_log::test]
+    fn block_body_rlp_encoding_decoding_round_trip() {
+        let block_body = match get_14764013_block_body() {
+            BlockBody::Legacy(body) => body,
+            _ => panic!("Expected a legacy block body"),
+        };
+        let encoded = rlp::encode(&block_body);
+        let decoded: BlockBodyLegacy = rlp::decode(&encoded).unwrap();
+        assert_eq!(decoded, block_body);
+    }
+
     #[test_log::test]
     fn block_body_roots_invalidates_transactions_root() {
         let block_body = get_14764013_block_body();
@@ -526,6 +571,29 @@ mod tests {
         // and without the typed transactions seems like a good start for now.
     }
 
+    #[test_log::test]
+    fn shanghai_block_body_validates_withdrawals_root() {
+        // block 17139055
+        let raw = std::fs::read("../test_assets/mainnet/block_body_17139055.bin").unwrap();
+        let body = BlockBody::Shanghai(BlockBodyShanghai::from_ssz_bytes(&raw).unwrap());
+        let withdrawals_root = H256::from_slice(
+            &hex_decode("0x413f0935d01b220feb4c062960d0a859d1f58448af55dd1434ed9c98a91ee1db")
+                .unwrap(),
+        );
+        // Only the roots of the header are checked, so any header will do as a template.
+        let uncles: Vec<Header> = rlp::decode_list(&hex_decode(UNCLE).unwrap());
+        let mut header = uncles[0].clone();
+        header.uncles_hash = body.uncles_root().unwrap();
+        header.transactions_root = body.transactions_root().unwrap();
+        header.withdrawals_root = Some(withdrawals_root);
+        body.validate_against_header(&header).unwrap();
+
+        header.withdrawals_root = Some(H256::zero());
+        assert!(body.validate_against_header(&header).is_err());
+        header.withdrawals_root = None;
+        assert!(body.validate_against_header(&header).is_err());
+    }
+
     fn shanghai_withdrawals() -> String {
         r#"[{"index":"0x196f2d","validatorIndex":"0x771aa","address":"0x2c885c22321746ab958980a5d060be90cd3fa79b","amount":"0xbc501e"},{"index":"0x196f2e","validatorIndex":"0x771ab","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbcc397"},{"index":"0x196f2f","validatorIndex":"0x771ac","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbd2f54"},{"index":"0x196f30","validatorIndex":"0x771ad","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbc2316"},{"index":"0x196f31","validatorIndex":"0x771ae","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbb358a"},{"index":"0x196f32","validatorIndex":"0x771af","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbc4c7e"},{"index":"0x196f33","validatorIndex":"0x771b0","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbce826"},{"index":"0x196f34","validatorIndex":"0x771b1","address":"0x2c885c22321746ab958980a5d060be90cd3fa79b","amount":"0xbcb59b"},{"index":"0x196f35","validatorIndex":"0x771b2","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbca420"},{"index":"0x196f36","validatorIndex":"0x771b3","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbc1dd0"},{"index":"0x196f37","validatorIndex":"0x771b4","address":"0x2c885c22321746ab958980a5d060be90cd3fa79b","amount":"0xbbe0da"},{"index":"0x196f38","validatorIndex":"0x771b5","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbb714c"},{"index":"0x196f39","validatorIndex":"0x771b6","address":"0x2c885c22321746ab958980a5d060be90cd3fa79b","amount":"0xbc3c1d"},{"index":"0x196f3a","validatorIndex":"0x771b7","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbc2726"},{"index":"0x196f3b","validatorIndex":"0x771b8","address":"0xa1c52afa77d87796b8cd34f4801e062fb54e7df6","amount":"0xad94c3"},{"index":"0x196f3c","validatorIndex":"0x771b9","address":"0xa578c8a6fbddbdff3646ea05a7998bb251c2e972","amount":"0xbad3ed"}]"#.to_string()
     }
@@ -0,0 +1,18 @@
+use ethereum_types::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use super::transaction::JsonBytes;
+
+/// Parameters of an `eth_call` request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+    pub from: Option<Address>,
+    pub to: Option<Address>,
+    pub gas: Option<U256>,
+    pub gas_price: Option<U256>,
+    pub value: Option<U256>,
+    /// Also accepted under its newer name, `input`.
+    #[serde(alias = "input")]
+    pub data: Option<JsonBytes>,
+}
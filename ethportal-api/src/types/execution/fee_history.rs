@@ -0,0 +1,240 @@
+use ethereum_types::U256;
+use serde::{Deserialize, Serialize};
+
+use super::{header::Header, receipts::Receipt, transaction::Transaction};
+
+/// The maximum number of blocks that a single `eth_feeHistory` request may cover.
+pub const MAX_FEE_HISTORY_BLOCK_COUNT: u64 = 1024;
+
+const LONDON_BLOCK_NUMBER: u64 = 12_965_000;
+const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+const ELASTICITY_MULTIPLIER: u64 = 2;
+
+/// Response of an `eth_feeHistory` request.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistory {
+    pub oldest_block: U256,
+    /// Base fee of every block in the range, followed by the base fee of the next block.
+    pub base_fee_per_gas: Vec<U256>,
+    pub gas_used_ratio: Vec<f64>,
+    /// Effective priority fees at the requested percentiles of gas used, for every block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<Vec<U256>>>,
+}
+
+/// Returns the ratio of gas used to the gas limit of a block, which is 0 for a zero gas limit.
+pub fn gas_used_ratio(header: &Header) -> f64 {
+    if header.gas_limit.is_zero() {
+        return 0.0;
+    }
+    header.gas_used.low_u64() as f64 / header.gas_limit.low_u64() as f64
+}
+
+/// Returns the base fee of the block following `parent`, as defined in EIP-1559.
+pub fn next_base_fee(parent: &Header) -> U256 {
+    let base_fee = match parent.base_fee_per_gas {
+        Some(base_fee) => base_fee,
+        None if parent.number + 1 >= LONDON_BLOCK_NUMBER => return U256::from(INITIAL_BASE_FEE),
+        None => return U256::zero(),
+    };
+    let gas_target = parent.gas_limit / ELASTICITY_MULTIPLIER;
+    if gas_target.is_zero() || parent.gas_used == gas_target {
+        return base_fee;
+    }
+    if parent.gas_used > gas_target {
+        let change = base_fee * (parent.gas_used - gas_target)
+            / gas_target
+            / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee + change.max(U256::one())
+    } else {
+        let change = base_fee * (gas_target - parent.gas_used)
+            / gas_target
+            / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee.saturating_sub(change)
+    }
+}
+
+/// Returns the priority fee per gas that `tx` effectively paid in a block with the given base fee.
+pub fn effective_tip(tx: &Transaction, base_fee: U256) -> U256 {
+    match tx {
+        Transaction::Legacy(tx) => tx.gas_price.saturating_sub(base_fee),
+        Transaction::AccessList(tx) => tx.gas_price.saturating_sub(base_fee),
+        Transaction::EIP1559(tx) => tx
+            .max_priority_fee_per_gas
+            .min(tx.max_fee_per_gas.saturating_sub(base_fee)),
+    }
+}
+
+/// Returns the effective priority fees paid at the given percentiles of the gas used in a block.
+///
+/// Transactions are sorted by their tip, and each percentile picks the tip of the transaction that
+/// brings the cumulative gas used over that share of the block's gas used.
+pub fn reward_percentiles(
+    header: &Header,
+    transactions: &[Transaction],
+    receipts: &[Receipt],
+    percentiles: &[f64],
+) -> Vec<U256> {
+    let base_fee = header.base_fee_per_gas.unwrap_or_default();
+    let mut previous_cumulative_gas = U256::zero();
+    let mut tips: Vec<(U256, U256)> = transactions
+        .iter()
+        .zip(receipts)
+        .map(|(tx, receipt)| {
+            let cumulative_gas = receipt.receipt().cumulative_gas_used;
+            let gas_used = cumulative_gas.saturating_sub(previous_cumulative_gas);
+            previous_cumulative_gas = cumulative_gas;
+            (effective_tip(tx, base_fee), gas_used)
+        })
+        .collect();
+    if tips.is_empty() {
+        return vec![U256::zero(); percentiles.len()];
+    }
+    tips.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let block_gas_used = header.gas_used.low_u64() as f64;
+    let mut index = 0;
+    let mut cumulative_gas = tips[0].1.low_u64() as f64;
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = block_gas_used * percentile / 100.0;
+            while cumulative_gas < threshold && index < tips.len() - 1 {
+                index += 1;
+                cumulative_gas += tips[index].1.low_u64() as f64;
+            }
+            tips[index].0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use crate::types::execution::{
+        receipts::{LegacyReceipt, TransactionOutcome},
+        transaction::{EIP1559Transaction, LegacyTransaction, ToAddress},
+    };
+
+    fn header(number: u64, base_fee: Option<u64>, gas_used: u64) -> Header {
+        Header {
+            parent_hash: Default::default(),
+            uncles_hash: Default::default(),
+            author: Default::default(),
+            state_root: Default::default(),
+            transactions_root: Default::default(),
+            receipts_root: Default::default(),
+            logs_bloom: Default::default(),
+            difficulty: U256::zero(),
+            number,
+            gas_limit: U256::from(30_000_000),
+            gas_used: U256::from(gas_used),
+            timestamp: 0,
+            extra_data: vec![],
+            mix_hash: None,
+            nonce: None,
+            base_fee_per_gas: base_fee.map(U256::from),
+            withdrawals_root: None,
+        }
+    }
+
+    fn receipt(cumulative_gas_used: u64) -> Receipt {
+        Receipt::Legacy(LegacyReceipt {
+            cumulative_gas_used: U256::from(cumulative_gas_used),
+            log_bloom: Default::default(),
+            logs: vec![],
+            outcome: TransactionOutcome::StatusCode(1),
+        })
+    }
+
+    fn legacy_tx(gas_price: u64) -> Transaction {
+        Transaction::Legacy(LegacyTransaction {
+            nonce: U256::zero(),
+            gas_price: U256::from(gas_price),
+            gas: U256::from(21_000),
+            to: ToAddress::Empty,
+            value: U256::zero(),
+            data: Default::default(),
+            v: Default::default(),
+            r: U256::zero(),
+            s: U256::zero(),
+        })
+    }
+
+    #[test]
+    fn base_fee_follows_gas_used() {
+        let base_fee = 1_000_000_000;
+        assert_eq!(
+            next_base_fee(&header(15_000_000, Some(base_fee), 15_000_000)),
+            U256::from(base_fee)
+        );
+        assert_eq!(
+            next_base_fee(&header(15_000_000, Some(base_fee), 30_000_000)),
+            U256::from(1_125_000_000)
+        );
+        assert_eq!(
+            next_base_fee(&header(15_000_000, Some(base_fee), 0)),
+            U256::from(875_000_000)
+        );
+        assert_eq!(
+            next_base_fee(&header(LONDON_BLOCK_NUMBER - 1, None, 0)),
+            U256::from(INITIAL_BASE_FEE)
+        );
+        assert_eq!(next_base_fee(&header(1, None, 0)), U256::zero());
+    }
+
+    #[test]
+    fn gas_used_ratio_handles_zero_gas_limit() {
+        assert_eq!(gas_used_ratio(&header(1, None, 15_000_000)), 0.5);
+        let mut header = header(1, None, 0);
+        header.gas_limit = U256::zero();
+        assert_eq!(gas_used_ratio(&header), 0.0);
+    }
+
+    #[test]
+    fn effective_tip_is_capped_by_max_fee() {
+        let tx = Transaction::EIP1559(EIP1559Transaction {
+            chain_id: U256::one(),
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::from(10),
+            max_fee_per_gas: U256::from(105),
+            gas_limit: U256::from(21_000),
+            to: ToAddress::Empty,
+            value: U256::zero(),
+            data: Default::default(),
+            access_list: Default::default(),
+            y_parity: Default::default(),
+            r: U256::zero(),
+            s: U256::zero(),
+        });
+        assert_eq!(effective_tip(&tx, U256::from(100)), U256::from(5));
+        assert_eq!(effective_tip(&tx, U256::from(50)), U256::from(10));
+        assert_eq!(effective_tip(&legacy_tx(40), U256::from(50)), U256::zero());
+    }
+
+    #[test]
+    fn rewards_are_weighted_by_gas_used() {
+        let header = header(1, None, 100_000);
+        let transactions = vec![legacy_tx(30), legacy_tx(10), legacy_tx(20)];
+        // Gas used by each transaction: 50_000, 25_000, 25_000.
+        let receipts = vec![receipt(50_000), receipt(75_000), receipt(100_000)];
+        let rewards =
+            reward_percentiles(&header, &transactions, &receipts, &[0.0, 25.0, 40.0, 100.0]);
+        assert_eq!(
+            rewards,
+            vec![
+                U256::from(10),
+                U256::from(10),
+                U256::from(20),
+                U256::from(30)
+            ]
+        );
+        assert_eq!(
+            reward_percentiles(&header, &[], &[], &[50.0]),
+            vec![U256::zero()]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use ethereum_types::{Address, H256};
+use reth_rpc_types::BlockNumberOrTag;
+use serde::{Deserialize, Serialize};
+
+use super::receipts::LogEntry;
+
+/// A filter parameter that matches either a single value or any value of a list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueOrArray<T> {
+    Value(T),
+    Array(Vec<T>),
+}
+
+impl<T: PartialEq> ValueOrArray<T> {
+    /// Returns true if `value` is matched by this filter parameter.
+    pub fn matches(&self, value: &T) -> bool {
+        match self {
+            Self::Value(expected) => expected == value,
+            Self::Array(expected) => expected.contains(value),
+        }
+    }
+}
+
+/// Parameters of an `eth_getLogs` request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilter {
+    pub from_block: Option<BlockNumberOrTag>,
+    pub to_block: Option<BlockNumberOrTag>,
+    /// Restricts the filter to a single block. Cannot be combined with a block range.
+    pub block_hash: Option<H256>,
+    pub address: Option<ValueOrArray<Address>>,
+    /// Topics are matched by position, a missing or null position matches any topic.
+    #[serde(default)]
+    pub topics: Vec<Option<ValueOrArray<H256>>>,
+}
+
+impl LogFilter {
+    /// Returns true if `log` matches the address and topics of this filter.
+    pub fn matches(&self, log: &LogEntry) -> bool {
+        if let Some(address) = &self.address {
+            if !address.matches(&log.address) {
+                return false;
+            }
+        }
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(index, topic)| match topic {
+                None => true,
+                Some(topic) => log
+                    .topics
+                    .get(index)
+                    .map_or(false, |log_topic| topic.matches(log_topic)),
+            })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn log_filter_matches_address_and_topics() {
+        let address = Address::from_low_u64_be(1);
+        let topic = H256::from_low_u64_be(2);
+        let log = LogEntry {
+            address,
+            topics: vec![topic, H256::from_low_u64_be(3)],
+            data: Default::default(),
+        };
+
+        let filter: LogFilter = serde_json::from_value(json!({
+            "address": address,
+            "topics": [null, [topic, H256::from_low_u64_be(3)]],
+        }))
+        .unwrap();
+        assert!(filter.matches(&log));
+
+        let filter: LogFilter = serde_json::from_value(json!({
+            "address": [Address::from_low_u64_be(4)],
+        }))
+        .unwrap();
+        assert!(!filter.matches(&log));
+
+        let filter: LogFilter = serde_json::from_value(json!({
+            "topics": [topic, null, topic],
+        }))
+        .unwrap();
+        assert!(!filter.matches(&log));
+    }
+}
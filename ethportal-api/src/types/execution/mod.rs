@@ -1,5 +1,8 @@
 pub mod accumulator;
 pub mod block_body;
+pub mod call_request;
+pub mod fee_history;
 pub mod header;
+pub mod log_filter;
 pub mod receipts;
 pub mod transaction;
@@ -6,13 +6,17 @@ use std::{
 use anyhow::anyhow;
 use bytes::Bytes;
 use eth_trie::{EthTrie, MemoryDB, Trie};
-use ethereum_types::{Address, Bloom, BloomInput, H256, U256};
+use ethereum_types::{Address, Bloom, BloomInput, H160, H256, U256, U64};
+use reth_rpc_types::Log as RpcLog;
 use rlp::{self, Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use rlp_derive::{RlpDecodable, RlpEncodable};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
-use super::transaction::JsonBytes;
+use super::{
+    header::{u64_to_uint256, Header},
+    transaction::{JsonBytes, Transaction},
+};
 use crate::utils::bytes::hex_decode;
 
 // 2 ^ 14
@@ -40,6 +44,101 @@ impl Receipts {
         trie.root_hash()
             .map_err(|err| anyhow!("Error calculating receipts root: {err:?}"))
     }
+
+    /// Converts the receipt of the transaction at `index` into its JSON-RPC representation, given
+    /// the header and the transactions of the block.
+    pub fn rpc_receipt(
+        &self,
+        header: &Header,
+        transactions: &[Transaction],
+        index: usize,
+    ) -> anyhow::Result<RpcReceipt> {
+        let (receipt, transaction) = match (self.receipt_list.get(index), transactions.get(index)) {
+            (Some(receipt), Some(transaction)) => (receipt.receipt(), transaction),
+            _ => {
+                return Err(anyhow!(
+                    "Block has {} receipts and {} transactions, so there is no receipt at index {index}",
+                    self.receipt_list.len(),
+                    transactions.len()
+                ))
+            }
+        };
+        let previous_receipts = &self.receipt_list[..index];
+        let gas_used = match previous_receipts.last() {
+            Some(previous) => receipt
+                .cumulative_gas_used
+                .saturating_sub(previous.cumulative_gas_used),
+            None => receipt.cumulative_gas_used,
+        };
+        // Log indices count the logs of the whole block.
+        let first_log_index: usize = previous_receipts
+            .iter()
+            .map(|previous| previous.logs.len())
+            .sum();
+        let block_hash = header.hash();
+        let transaction_hash = transaction.hash();
+        let logs = receipt
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(log_index, log)| {
+                log.clone().into_rpc_log(
+                    block_hash,
+                    header.number,
+                    transaction_hash,
+                    index as u64,
+                    (first_log_index + log_index) as u64,
+                )
+            })
+            .collect();
+        let (status, root) = match receipt.outcome {
+            TransactionOutcome::StatusCode(status) => (Some(U64::from(status)), None),
+            TransactionOutcome::StateRoot(root) => (None, Some(root)),
+        };
+        Ok(RpcReceipt {
+            transaction_hash,
+            transaction_index: U64::from(index as u64),
+            block_hash,
+            block_number: U64::from(header.number),
+            from: transaction.sender()?,
+            to: transaction.to(),
+            cumulative_gas_used: receipt.cumulative_gas_used,
+            gas_used,
+            effective_gas_price: transaction.effective_gas_price(header.base_fee_per_gas),
+            contract_address: transaction.contract_address()?,
+            logs,
+            logs_bloom: receipt.log_bloom,
+            transaction_type: U64::from(transaction.transaction_type() as u8),
+            status,
+            root,
+        })
+    }
+}
+
+/// A transaction receipt, as returned by `eth_getTransactionReceipt`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+    pub transaction_hash: H256,
+    pub transaction_index: U64,
+    pub block_hash: H256,
+    pub block_number: U64,
+    pub from: H160,
+    pub to: Option<H160>,
+    pub cumulative_gas_used: U256,
+    pub gas_used: U256,
+    pub effective_gas_price: U256,
+    pub contract_address: Option<H160>,
+    pub logs: Vec<RpcLog>,
+    pub logs_bloom: Bloom,
+    #[serde(rename = "type")]
+    pub transaction_type: U64,
+    /// The status of receipts since Byzantium.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<U64>,
+    /// The post-transaction state root of receipts before Byzantium.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<H256>,
 }
 
 impl ssz::Encode for Receipts {
@@ -69,6 +168,36 @@ impl ssz::Decode for Receipts {
     }
 }
 
+/// RLP list of the receipts, as stored in era1 archives. Typed receipts are wrapped in byte
+/// strings.
+impl Encodable for Receipts {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(self.receipt_list.len());
+        for receipt in &self.receipt_list {
+            match receipt {
+                Receipt::Legacy(receipt) => s.append(receipt),
+                _ => s.append(&receipt.encode()),
+            };
+        }
+    }
+}
+
+impl Decodable for Receipts {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if !rlp.is_list() {
+            return Err(DecoderError::RlpExpectedToBeList);
+        }
+        let receipt_list = rlp
+            .iter()
+            .map(|receipt| match receipt.is_list() {
+                true => Receipt::decode(receipt.as_raw()),
+                false => Receipt::decode(receipt.data()?),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { receipt_list })
+    }
+}
+
 // Deserialize is currently only implemented for BATCHED responses from an execution client
 // Used inside portal-bridge
 impl<'de> Deserialize<'de> for Receipts {
@@ -181,6 +310,32 @@ impl From<LogEntryHelper> for LogEntry {
 }
 
 impl LogEntry {
+    /// Converts the log entry into its JSON-RPC representation, given its position in the chain.
+    pub fn into_rpc_log(
+        self,
+        block_hash: H256,
+        block_number: u64,
+        transaction_hash: H256,
+        transaction_index: u64,
+        log_index: u64,
+    ) -> RpcLog {
+        RpcLog {
+            address: self.address.to_fixed_bytes().into(),
+            topics: self
+                .topics
+                .into_iter()
+                .map(|topic| topic.to_fixed_bytes().into())
+                .collect(),
+            data: self.data.to_vec().into(),
+            block_hash: Some(block_hash.to_fixed_bytes().into()),
+            block_number: Some(u64_to_uint256(block_number)),
+            transaction_hash: Some(transaction_hash.to_fixed_bytes().into()),
+            transaction_index: Some(u64_to_uint256(transaction_index)),
+            log_index: Some(u64_to_uint256(log_index)),
+            removed: false,
+        }
+    }
+
     /// Calculates the bloom of this log entry.
     pub fn bloom(&self) -> Bloom {
         self.topics.iter().fold(
@@ -466,7 +621,6 @@ mod tests {
     use super::*;
     use std::str::FromStr;
 
-    use ethereum_types::H160;
     use serde_json::json;
     use ssz::{Decode, Encode};
     use test_log::test;
@@ -576,6 +730,56 @@ mod tests {
         assert_eq!(receipts, decoded);
     }
 
+    #[test]
+    fn rlp_encoding_decoding_receipts() {
+        let receipts = std::fs::read("../test_assets/mainnet/receipts_14764013.bin").unwrap();
+        let receipts = Receipts::from_ssz_bytes(&receipts).unwrap();
+
+        let encoded = rlp::encode(&receipts);
+        let decoded: Receipts = rlp::decode(&encoded).unwrap();
+        assert_eq!(decoded, receipts);
+        assert_eq!(decoded.root().unwrap(), receipts.root().unwrap());
+    }
+
+    #[test]
+    fn rpc_receipts_of_block() {
+        let block =
+            std::fs::read_to_string("../test_assets/mainnet/block_14764013_value.json").unwrap();
+        let block: Value = serde_json::from_str(&block).unwrap();
+        let header: Header = serde_json::from_value(block["result"].clone()).unwrap();
+        let transactions: Vec<Transaction> =
+            serde_json::from_value(block["result"]["transactions"].clone()).unwrap();
+        let receipts = std::fs::read("../test_assets/mainnet/receipts_14764013.bin").unwrap();
+        let receipts = Receipts::from_ssz_bytes(&receipts).unwrap();
+
+        let rpc_receipts: Vec<RpcReceipt> = (0..transactions.len())
+            .map(|index| receipts.rpc_receipt(&header, &transactions, index).unwrap())
+            .collect();
+        let total_gas_used = rpc_receipts
+            .iter()
+            .fold(U256::zero(), |total, receipt| total + receipt.gas_used);
+        assert_eq!(total_gas_used, header.gas_used);
+        let log_indices: Vec<_> = rpc_receipts
+            .iter()
+            .flat_map(|receipt| receipt.logs.iter().map(|log| log.log_index))
+            .collect();
+        let expected_log_indices: Vec<_> = (0..log_indices.len() as u64)
+            .map(|index| Some(u64_to_uint256(index)))
+            .collect();
+        assert_eq!(log_indices, expected_log_indices);
+        for (receipt, transaction) in rpc_receipts.iter().zip(&transactions) {
+            assert_eq!(receipt.transaction_hash, transaction.hash());
+            assert_eq!(receipt.block_hash, header.hash());
+            assert_eq!(receipt.root, None);
+        }
+        // The fifth transaction of the block failed.
+        assert_eq!(rpc_receipts[0].status, Some(U64::one()));
+        assert_eq!(rpc_receipts[4].status, Some(U64::zero()));
+        assert!(receipts
+            .rpc_receipt(&header, &transactions, transactions.len())
+            .is_err());
+    }
+
     //
     // OpenEthereum Tests
     // https://github.com/openethereum/openethereum/blob/main/crates/ethcore/types/src/receipt.rs
@@ -1,12 +1,17 @@
+use anyhow::anyhow;
 use bytes::Bytes;
 use ethereum_types::{H160, H256, U256, U64};
+use k256::{
+    ecdsa::{RecoveryId, Signature, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+};
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use rlp_derive::{RlpDecodable, RlpEncodable};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{json, Value};
 
-use super::receipts::TransactionId;
-use crate::utils::bytes::hex_decode;
+use super::{header::Header, receipts::TransactionId};
+use crate::utils::bytes::{hex_decode, hex_encode};
 
 #[derive(Eq, Debug, Clone, PartialEq)]
 pub enum Transaction {
@@ -52,6 +57,247 @@ impl Transaction {
             }
         }
     }
+
+    pub fn nonce(&self) -> U256 {
+        match self {
+            Self::Legacy(tx) => tx.nonce,
+            Self::AccessList(tx) => tx.nonce,
+            Self::EIP1559(tx) => tx.nonce,
+        }
+    }
+
+    /// Returns the most wei the sender can be charged for the transaction: its value, and its gas
+    /// limit at the highest gas price it allows.
+    pub fn max_cost(&self) -> U256 {
+        let (gas_limit, max_gas_price, value) = match self {
+            Self::Legacy(tx) => (tx.gas, tx.gas_price, tx.value),
+            Self::AccessList(tx) => (tx.gas_limit, tx.gas_price, tx.value),
+            Self::EIP1559(tx) => (tx.gas_limit, tx.max_fee_per_gas, tx.value),
+        };
+        gas_limit
+            .saturating_mul(max_gas_price)
+            .saturating_add(value)
+    }
+
+    /// Returns the hash signed by the sender of the transaction.
+    pub fn signature_hash(&self) -> anyhow::Result<H256> {
+        let encoded = match self {
+            Self::Legacy(tx) => {
+                let chain_id = tx.chain_id()?;
+                let mut stream = RlpStream::new_list(if chain_id.is_some() { 9 } else { 6 });
+                stream
+                    .append(&tx.nonce)
+                    .append(&tx.gas_price)
+                    .append(&tx.gas)
+                    .append(&tx.to)
+                    .append(&tx.value)
+                    .append(&tx.data);
+                // Replay protected transactions also sign their chain id, see EIP-155.
+                if let Some(chain_id) = chain_id {
+                    stream.append(&chain_id).append(&0u8).append(&0u8);
+                }
+                stream.out().freeze().to_vec()
+            }
+            Self::AccessList(tx) => {
+                let mut stream = RlpStream::new_list(8);
+                stream
+                    .append(&tx.chain_id)
+                    .append(&tx.nonce)
+                    .append(&tx.gas_price)
+                    .append(&tx.gas_limit)
+                    .append(&tx.to)
+                    .append(&tx.value)
+                    .append(&tx.data)
+                    .append(&tx.access_list);
+                [&[TransactionId::AccessList as u8], stream.as_raw()].concat()
+            }
+            Self::EIP1559(tx) => {
+                let mut stream = RlpStream::new_list(9);
+                stream
+                    .append(&tx.chain_id)
+                    .append(&tx.nonce)
+                    .append(&tx.max_priority_fee_per_gas)
+                    .append(&tx.max_fee_per_gas)
+                    .append(&tx.gas_limit)
+                    .append(&tx.to)
+                    .append(&tx.value)
+                    .append(&tx.data)
+                    .append(&tx.access_list);
+                [&[TransactionId::EIP1559 as u8], stream.as_raw()].concat()
+            }
+        };
+        Ok(keccak_hash::keccak(encoded))
+    }
+
+    /// Recovers the address of the sender from the signature of the transaction.
+    ///
+    /// Signatures with a high `s` value, which are invalid since Homestead, are rejected.
+    pub fn sender(&self) -> anyhow::Result<H160> {
+        let (r, s, recovery_id) = match self {
+            Self::Legacy(tx) => (tx.r, tx.s, tx.recovery_id()?),
+            Self::AccessList(tx) => (tx.r, tx.s, tx.y_parity.as_u64()),
+            Self::EIP1559(tx) => (tx.r, tx.s, tx.y_parity.as_u64()),
+        };
+        let mut signature = [0u8; 64];
+        r.to_big_endian(&mut signature[..32]);
+        s.to_big_endian(&mut signature[32..]);
+        let signature = Signature::from_slice(&signature)
+            .map_err(|err| anyhow!("Invalid transaction signature: {err}"))?;
+        let recovery_id = u8::try_from(recovery_id)
+            .ok()
+            .and_then(RecoveryId::from_byte)
+            .ok_or_else(|| anyhow!("Invalid signature recovery id: {recovery_id}"))?;
+        let key = VerifyingKey::recover_from_prehash(
+            self.signature_hash()?.as_bytes(),
+            &signature,
+            recovery_id,
+        )
+        .map_err(|err| anyhow!("Unable to recover transaction sender: {err}"))?;
+        let public_key = key.to_encoded_point(false);
+        // The first byte of an uncompressed point only tags it as such.
+        let public_key_hash = keccak_hash::keccak(&public_key.as_bytes()[1..]);
+        Ok(H160::from_slice(&public_key_hash[12..]))
+    }
+
+    /// Returns the recipient of the transaction, or `None` if it creates a contract.
+    pub fn to(&self) -> Option<H160> {
+        let to = match self {
+            Self::Legacy(tx) => &tx.to,
+            Self::AccessList(tx) => &tx.to,
+            Self::EIP1559(tx) => &tx.to,
+        };
+        match to {
+            ToAddress::Empty => None,
+            ToAddress::Exists(address) => Some(*address),
+        }
+    }
+
+    pub fn transaction_type(&self) -> TransactionId {
+        match self {
+            Self::Legacy(_) => TransactionId::Legacy,
+            Self::AccessList(_) => TransactionId::AccessList,
+            Self::EIP1559(_) => TransactionId::EIP1559,
+        }
+    }
+
+    /// Returns the price per gas that the sender paid in a block with the given base fee.
+    pub fn effective_gas_price(&self, base_fee: Option<U256>) -> U256 {
+        match self {
+            Self::Legacy(tx) => tx.gas_price,
+            Self::AccessList(tx) => tx.gas_price,
+            Self::EIP1559(tx) => match base_fee {
+                Some(base_fee) => tx
+                    .max_fee_per_gas
+                    .min(base_fee.saturating_add(tx.max_priority_fee_per_gas)),
+                None => tx.max_fee_per_gas,
+            },
+        }
+    }
+
+    /// Returns the address of the contract created by the transaction, if it creates one.
+    pub fn contract_address(&self) -> anyhow::Result<Option<H160>> {
+        if self.to().is_some() {
+            return Ok(None);
+        }
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&self.sender()?).append(&self.nonce());
+        let hash = keccak_hash::keccak(stream.out());
+        Ok(Some(H160::from_slice(&hash[12..])))
+    }
+
+    /// Converts the transaction into its JSON-RPC representation, given the header of the block
+    /// that includes it and its index in that block.
+    pub fn into_rpc_transaction(
+        self,
+        header: &Header,
+        transaction_index: u64,
+    ) -> anyhow::Result<RpcTransaction> {
+        let hash = self.hash();
+        let from = self.sender()?;
+        let to = self.to();
+        let gas_price = self.effective_gas_price(header.base_fee_per_gas);
+        let transaction_type = U64::from(self.transaction_type() as u8);
+        let transaction = RpcTransaction {
+            hash,
+            block_hash: header.hash(),
+            block_number: U64::from(header.number),
+            transaction_index: U64::from(transaction_index),
+            from,
+            to,
+            nonce: self.nonce(),
+            gas_price,
+            transaction_type,
+            ..match self {
+                Self::Legacy(tx) => RpcTransaction {
+                    value: tx.value,
+                    gas: tx.gas,
+                    input: tx.data.into(),
+                    chain_id: tx.chain_id()?.map(U64::from),
+                    v: tx.v,
+                    r: tx.r,
+                    s: tx.s,
+                    ..Default::default()
+                },
+                Self::AccessList(tx) => RpcTransaction {
+                    value: tx.value,
+                    gas: tx.gas_limit,
+                    input: tx.data.into(),
+                    chain_id: Some(U64::from(tx.chain_id.low_u64())),
+                    access_list: Some(tx.access_list.list),
+                    v: tx.y_parity,
+                    r: tx.r,
+                    s: tx.s,
+                    ..Default::default()
+                },
+                Self::EIP1559(tx) => RpcTransaction {
+                    value: tx.value,
+                    gas: tx.gas_limit,
+                    max_fee_per_gas: Some(tx.max_fee_per_gas),
+                    max_priority_fee_per_gas: Some(tx.max_priority_fee_per_gas),
+                    input: tx.data.into(),
+                    chain_id: Some(U64::from(tx.chain_id.low_u64())),
+                    access_list: Some(tx.access_list.list),
+                    v: tx.y_parity,
+                    r: tx.r,
+                    s: tx.s,
+                    ..Default::default()
+                },
+            }
+        };
+        Ok(transaction)
+    }
+}
+
+/// A transaction included in a block, as returned by `eth_getTransactionByHash`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransaction {
+    pub hash: H256,
+    pub block_hash: H256,
+    pub block_number: U64,
+    pub transaction_index: U64,
+    pub from: H160,
+    pub to: Option<H160>,
+    pub nonce: U256,
+    pub value: U256,
+    pub gas: U256,
+    /// The price per gas that the sender effectively paid.
+    pub gas_price: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub input: JsonBytes,
+    #[serde(rename = "type")]
+    pub transaction_type: U64,
+    /// Only missing from legacy transactions that aren't replay protected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<Vec<AccessListItem>>,
+    pub v: U64,
+    pub r: U256,
+    pub s: U256,
 }
 
 impl<'de> Deserialize<'de> for Transaction {
@@ -103,6 +349,25 @@ pub struct LegacyTransaction {
     pub s: U256,
 }
 
+impl LegacyTransaction {
+    /// Returns the chain id of a replay protected transaction, encoded in its `v` by EIP-155.
+    fn chain_id(&self) -> anyhow::Result<Option<u64>> {
+        match self.v.as_u64() {
+            27 | 28 => Ok(None),
+            v if v >= 35 => Ok(Some((v - 35) / 2)),
+            v => Err(anyhow!("Invalid legacy transaction signature v: {v}")),
+        }
+    }
+
+    fn recovery_id(&self) -> anyhow::Result<u64> {
+        match self.v.as_u64() {
+            v @ (27 | 28) => Ok(v - 27),
+            v if v >= 35 => Ok((v - 35) % 2),
+            v => Err(anyhow!("Invalid legacy transaction signature v: {v}")),
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct LegacyTransactionHelper {
@@ -308,6 +573,15 @@ impl<'de> Deserialize<'de> for JsonBytes {
     }
 }
 
+impl Serialize for JsonBytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex_encode(&self.0))
+    }
+}
+
 impl From<Bytes> for JsonBytes {
     fn from(val: Bytes) -> Self {
         Self(val)
@@ -340,9 +614,78 @@ impl Encodable for AccessList {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Deserialize, RlpDecodable, RlpEncodable)]
+#[derive(Debug, PartialEq, Clone, Eq, Deserialize, Serialize, RlpDecodable, RlpEncodable)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessListItem {
     pub address: H160,
     pub storage_keys: Vec<H256>,
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    /// The signed example transaction of EIP-155.
+    const EIP155_TX: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    /// An EIP-1559 transaction signed with the private key of the EIP-155 example.
+    const EIP1559_TX: &str = "0x02f8730180843b9aca008506fc23ac00825208943535353535353535353535353535353535353535880de0b6b3a764000080c080a0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a03d67d2dee8bba6b176737751b901df8b99950fd774d0ba00c97424e1fa67e391";
+    const SENDER: &str = "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+
+    #[test]
+    fn legacy_transaction_sender() {
+        let tx = Transaction::decode(&hex_decode(EIP155_TX).unwrap()).unwrap();
+        assert_eq!(
+            tx.signature_hash().unwrap(),
+            H256::from_str("0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+                .unwrap()
+        );
+        assert_eq!(tx.sender().unwrap(), H160::from_str(SENDER).unwrap());
+        assert_eq!(tx.nonce(), U256::from(9));
+        assert_eq!(
+            tx.max_cost(),
+            U256::from(21_000u64 * 20_000_000_000 + 1_000_000_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn eip1559_transaction_sender() {
+        let tx = Transaction::decode(&hex_decode(EIP1559_TX).unwrap()).unwrap();
+        assert_eq!(tx.sender().unwrap(), H160::from_str(SENDER).unwrap());
+        assert_eq!(tx.nonce(), U256::zero());
+    }
+
+    #[test]
+    fn tampered_transaction_has_another_sender() {
+        let mut tx = Transaction::decode(&hex_decode(EIP155_TX).unwrap()).unwrap();
+        if let Transaction::Legacy(tx) = &mut tx {
+            tx.value = U256::from(2);
+        }
+        assert_ne!(tx.sender().unwrap(), H160::from_str(SENDER).unwrap());
+    }
+
+    #[test]
+    fn rpc_transactions_match_execution_client() {
+        let block =
+            std::fs::read_to_string("../test_assets/mainnet/block_14764013_value.json").unwrap();
+        let block: Value = serde_json::from_str(&block).unwrap();
+        let header: Header = serde_json::from_value(block["result"].clone()).unwrap();
+        let expected = block["result"]["transactions"].as_array().unwrap();
+        let transactions: Vec<Transaction> =
+            serde_json::from_value(block["result"]["transactions"].clone()).unwrap();
+
+        for (index, (transaction, expected)) in transactions.into_iter().zip(expected).enumerate() {
+            let rpc_transaction = transaction
+                .into_rpc_transaction(&header, index as u64)
+                .unwrap();
+            let rpc_transaction = serde_json::to_value(rpc_transaction).unwrap();
+            for field in expected.as_object().unwrap().keys() {
+                assert_eq!(
+                    rpc_transaction[field], expected[field],
+                    "field {field} of transaction {index}"
+                );
+            }
+        }
+    }
+}
@@ -36,3 +36,15 @@ pub struct PaginateLocalContentInfo {
     pub content_keys: Vec<HistoryContentKey>,
     pub total_entries: u64,
 }
+
+/// Response for ImportLocalContent endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportLocalContentInfo {
+    /// The number of content items that were stored.
+    pub stored: u64,
+    /// The number of content items that were skipped for falling outside the radius.
+    pub skipped: u64,
+    /// The number of content items that were skipped for failing validation.
+    pub invalid: u64,
+}
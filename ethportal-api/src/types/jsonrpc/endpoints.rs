@@ -1,7 +1,9 @@
 use crate::{
-    types::enr::Enr, BeaconContentKey, BeaconContentValue, HistoryContentKey, HistoryContentValue,
+    types::enr::Enr, BeaconContentKey, BeaconContentValue, CanonicalIndicesContentKey,
+    HistoryContentKey, HistoryContentValue, StateContentKey, StateContentValue, TransactionIndex,
 };
 use discv5::enr::NodeId;
+use std::path::PathBuf;
 
 /// Discv5 JSON-RPC endpoints. Start with "discv5_" prefix
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -13,15 +15,39 @@ pub enum Discv5Endpoint {
 /// State network JSON-RPC endpoints. Start with "portal_state" prefix
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum StateEndpoint {
+    /// params: None
     DataRadius,
-    FindContent,
-    FindNodes,
-    LocalContent,
-    SendOffer,
-    Store,
-    Ping,
-    RecursiveFindContent,
+    /// params: [enr, content_key]
+    FindContent(Enr, StateContentKey),
+    /// params: [enr, distances]
+    FindNodes(Enr, Vec<u16>),
+    /// params: [content_key, content_value]
+    Gossip(StateContentKey, StateContentValue),
+    /// params: content_key
+    LocalContent(StateContentKey),
+    /// params: [enr, content_key, content_value]
+    Offer(Enr, StateContentKey, Option<StateContentValue>),
+    /// params: [content_key, content_value]
+    Store(StateContentKey, StateContentValue),
+    /// params: [enr]
+    Ping(Enr),
+    /// params: content_key
+    RecursiveFindContent(StateContentKey),
+    /// params: None
     RoutingTableInfo,
+    /// params: None
+    Peers,
+}
+
+/// Canonical indices network JSON-RPC endpoints. Only used internally, by the `eth_` endpoints.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CanonicalIndicesEndpoint {
+    /// params: content_key
+    LocalContent(CanonicalIndicesContentKey),
+    /// params: [content_key, content_value]
+    Store(CanonicalIndicesContentKey, TransactionIndex),
+    /// params: content_key
+    RecursiveFindContent(CanonicalIndicesContentKey),
 }
 
 /// History network JSON-RPC endpoints. Start with "portal_history" prefix
@@ -47,8 +73,12 @@ pub enum HistoryEndpoint {
     Gossip(HistoryContentKey, HistoryContentValue),
     /// params: [content_key, content_value]
     TraceGossip(HistoryContentKey, HistoryContentValue),
+    /// params: [content_items]
+    TraceGossipBatch(Vec<(HistoryContentKey, HistoryContentValue)>),
     /// params: [enr, content_key]
     Offer(Enr, HistoryContentKey, Option<HistoryContentValue>),
+    /// params: [enr, content_keys]
+    TraceOffer(Enr, Vec<HistoryContentKey>),
     /// params: [enr]
     Ping(Enr),
     /// params: content_key
@@ -59,11 +89,32 @@ pub enum HistoryEndpoint {
     Store(HistoryContentKey, HistoryContentValue),
     /// params: None
     RoutingTableInfo,
+    /// params: None
+    Peers,
+    /// params: None
+    DbStats,
+    /// Returns the hash of the stored block header with the highest number, if any.
+    /// params: None
+    LatestStoredHeader,
     // This endpoint is not History network specific
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
+    /// params: [path, offset, limit]
+    ExportLocalContent(PathBuf, u64, u64),
+    /// params: path
+    ImportLocalContent(PathBuf),
+    /// params: path
+    BackupDb(PathBuf),
     /// params: [node_id]
     RecursiveFindNodes(NodeId),
+    /// Streams the content key of every newly stored content item through the responder,
+    /// until the responder is dropped.
+    /// params: None
+    SubscribeStoredContent,
+    /// Streams every change of the routing table through the responder, until the responder is
+    /// dropped.
+    /// params: None
+    SubscribeRoutingTable,
 }
 
 /// Beacon network JSON-RPC endpoints. Start with "portal_beacon" prefix
@@ -101,6 +152,10 @@ pub enum BeaconEndpoint {
     Store(BeaconContentKey, BeaconContentValue),
     /// params: None
     RoutingTableInfo,
+    /// params: None
+    Peers,
+    /// params: None
+    DbStats,
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
     /// params: [node_id]
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// A JSON-RPC 2.0 error object, returned by the portal subnetwork request handlers.
+///
+/// See: https://www.jsonrpc.org/specification#error_object
+#[derive(Clone, Debug, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[error("{message}")]
+pub struct JsonRpcError {
+    /// The error code, one of the constants defined on this type.
+    pub code: i32,
+    /// A short description of the error.
+    pub message: String,
+    /// Additional information about the error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    /// The JSON sent is not a valid request object.
+    pub const INVALID_REQUEST: i32 = -32600;
+    /// The method does not exist or is not available.
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    /// Invalid method parameter(s).
+    pub const INVALID_PARAMS: i32 = -32602;
+    /// Internal JSON-RPC error.
+    pub const INTERNAL_ERROR: i32 = -32603;
+
+    // The -32000 to -32099 range is reserved for implementation-defined server errors. Trin
+    // avoids the low end of the range, which is taken by the standard Ethereum error codes:
+    // https://docs.infura.io/networks/ethereum/json-rpc-methods#error-codes
+
+    /// A request sent to a remote peer on the overlay network failed or timed out.
+    pub const OVERLAY_REQUEST_FAILED: i32 = -32090;
+    /// Reading from or writing to the local content store failed.
+    pub const STORAGE_ERROR: i32 = -32091;
+    /// Any other server error.
+    pub const SERVER_ERROR: i32 = -32099;
+
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(Self::INVALID_REQUEST, message)
+    }
+
+    pub fn method_not_found(message: impl Into<String>) -> Self {
+        Self::new(Self::METHOD_NOT_FOUND, message)
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(Self::INVALID_PARAMS, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(Self::INTERNAL_ERROR, message)
+    }
+
+    pub fn overlay_request_failed(message: impl Into<String>) -> Self {
+        Self::new(Self::OVERLAY_REQUEST_FAILED, message)
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        Self::new(Self::STORAGE_ERROR, message)
+    }
+
+    pub fn server(message: impl Into<String>) -> Self {
+        Self::new(Self::SERVER_ERROR, message)
+    }
+
+    /// Attaches additional information about the error.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serialize_without_data() {
+        let err = JsonRpcError::invalid_params("Invalid distances: Empty list");
+        assert_eq!(
+            serde_json::to_value(err).unwrap(),
+            json!({"code": -32602, "message": "Invalid distances: Empty list"})
+        );
+    }
+
+    #[test]
+    fn serialize_with_data() {
+        let err = JsonRpcError::storage("Database error").with_data(json!({"offset": 10}));
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value,
+            json!({"code": -32091, "message": "Database error", "data": {"offset": 10}})
+        );
+        assert_eq!(serde_json::from_value::<JsonRpcError>(value).unwrap(), err);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod endpoints;
+pub mod error;
 pub mod params;
 pub mod request;
@@ -1,15 +1,50 @@
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Number, Value};
 use tokio::sync::mpsc;
+use tracing::Span;
 use validator::{Validate, ValidationError};
 
 use super::{
-    endpoints::{BeaconEndpoint, HistoryEndpoint, StateEndpoint},
+    endpoints::{BeaconEndpoint, CanonicalIndicesEndpoint, HistoryEndpoint, StateEndpoint},
+    error::JsonRpcError,
     params::Params,
 };
 
 type Responder<T, E> = mpsc::UnboundedSender<Result<T, E>>;
 
+/// The id of a JSON-RPC request, which the response must carry verbatim.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JsonRpcId {
+    Number(Number),
+    String(String),
+    Null,
+}
+
+impl From<u32> for JsonRpcId {
+    fn from(id: u32) -> Self {
+        JsonRpcId::Number(id.into())
+    }
+}
+
+impl From<u64> for JsonRpcId {
+    fn from(id: u64) -> Self {
+        JsonRpcId::Number(id.into())
+    }
+}
+
+impl From<String> for JsonRpcId {
+    fn from(id: String) -> Self {
+        JsonRpcId::String(id)
+    }
+}
+
+impl From<&str> for JsonRpcId {
+    fn from(id: &str) -> Self {
+        JsonRpcId::String(id.to_string())
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Validate, Clone)]
 pub struct JsonRequest {
     #[validate(custom = "validate_jsonrpc_version")]
@@ -17,18 +52,38 @@ pub struct JsonRequest {
     #[serde(default = "default_params")]
     pub params: Params,
     pub method: String,
-    pub id: u32,
+    /// Requests without an id are notifications, which get no response. A `null` id is still
+    /// an id.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_id",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub id: Option<JsonRpcId>,
 }
 
 impl JsonRequest {
-    pub fn new(method: String, params: Params, id: u32) -> Self {
+    pub fn new(method: String, params: Params, id: impl Into<JsonRpcId>) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             params,
             method,
-            id,
+            id: Some(id.into()),
         }
     }
+
+    pub fn notification(method: String, params: Params) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            params,
+            method,
+            id: None,
+        }
+    }
+
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
 }
 
 impl Default for JsonRequest {
@@ -37,7 +92,7 @@ impl Default for JsonRequest {
             jsonrpc: "2.0".to_string(),
             params: Params::None,
             method: "".to_string(),
-            id: 0,
+            id: Some(JsonRpcId::from(0u32)),
         }
     }
 }
@@ -46,21 +101,88 @@ impl Default for JsonRequest {
 #[derive(Debug, Clone)]
 pub struct HistoryJsonRpcRequest {
     pub endpoint: HistoryEndpoint,
-    pub resp: Responder<Value, String>,
+    pub resp: Responder<Value, JsonRpcError>,
+    /// Span of the json-rpc request that caused this request, so that the logs of the network
+    /// handling it can be matched to it.
+    pub span: Span,
+}
+
+impl HistoryJsonRpcRequest {
+    /// Creates a request within the current span.
+    pub fn new(endpoint: HistoryEndpoint, resp: Responder<Value, JsonRpcError>) -> Self {
+        Self {
+            endpoint,
+            resp,
+            span: Span::current(),
+        }
+    }
 }
 
 /// State network JSON-RPC request
 #[derive(Debug)]
 pub struct StateJsonRpcRequest {
     pub endpoint: StateEndpoint,
-    pub resp: Responder<Value, String>,
+    pub resp: Responder<Value, JsonRpcError>,
+    /// Span of the json-rpc request that caused this request.
+    pub span: Span,
+}
+
+impl StateJsonRpcRequest {
+    /// Creates a request within the current span.
+    pub fn new(endpoint: StateEndpoint, resp: Responder<Value, JsonRpcError>) -> Self {
+        Self {
+            endpoint,
+            resp,
+            span: Span::current(),
+        }
+    }
+}
+
+/// Canonical indices network JSON-RPC request
+#[derive(Debug)]
+pub struct CanonicalIndicesJsonRpcRequest {
+    pub endpoint: CanonicalIndicesEndpoint,
+    pub resp: Responder<Value, JsonRpcError>,
+    /// Span of the json-rpc request that caused this request.
+    pub span: Span,
+}
+
+impl CanonicalIndicesJsonRpcRequest {
+    /// Creates a request within the current span.
+    pub fn new(endpoint: CanonicalIndicesEndpoint, resp: Responder<Value, JsonRpcError>) -> Self {
+        Self {
+            endpoint,
+            resp,
+            span: Span::current(),
+        }
+    }
 }
 
 /// Beacon chain network JSON-RPC request
 #[derive(Debug)]
 pub struct BeaconJsonRpcRequest {
     pub endpoint: BeaconEndpoint,
-    pub resp: Responder<Value, String>,
+    pub resp: Responder<Value, JsonRpcError>,
+    /// Span of the json-rpc request that caused this request.
+    pub span: Span,
+}
+
+impl BeaconJsonRpcRequest {
+    /// Creates a request within the current span.
+    pub fn new(endpoint: BeaconEndpoint, resp: Responder<Value, JsonRpcError>) -> Self {
+        Self {
+            endpoint,
+            resp,
+            span: Span::current(),
+        }
+    }
+}
+
+/// Only called when the id is present, so that a `null` id isn't mistaken for a missing one.
+fn deserialize_id<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<JsonRpcId>, D::Error> {
+    JsonRpcId::deserialize(deserializer).map(Some)
 }
 
 fn default_params() -> Params {
@@ -78,13 +200,14 @@ fn validate_jsonrpc_version(jsonrpc: &str) -> Result<(), ValidationError> {
 #[allow(clippy::unwrap_used)]
 mod test {
     use super::*;
+    use rstest::rstest;
     use validator::ValidationErrors;
 
     #[test_log::test]
     fn test_json_validator_accepts_valid_json() {
         let request = JsonRequest {
             jsonrpc: "2.0".to_string(),
-            id: 1,
+            id: Some(1u32.into()),
             params: Params::None,
             method: "eth_blockNumber".to_string(),
         };
@@ -95,11 +218,43 @@ mod test {
     fn test_json_validator_with_invalid_jsonrpc_field() {
         let request = JsonRequest {
             jsonrpc: "1.0".to_string(),
-            id: 1,
+            id: Some(1u32.into()),
             params: Params::None,
             method: "eth_blockNumber".to_string(),
         };
         let errors = request.validate();
         assert!(ValidationErrors::has_error(&errors, "jsonrpc"));
     }
+
+    #[test_log::test]
+    fn test_request_without_id_is_notification() {
+        let request: JsonRequest =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","method":"eth_chainId"}"#).unwrap();
+        assert!(request.is_notification());
+        assert_eq!(request.validate(), Ok(()));
+
+        let request: JsonRequest =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","method":"eth_chainId","id":7}"#).unwrap();
+        assert_eq!(request.id, Some(7u32.into()));
+
+        let notification = JsonRequest::notification("eth_chainId".to_string(), Params::None);
+        assert!(!serde_json::to_string(&notification)
+            .unwrap()
+            .contains("\"id\""));
+    }
+
+    #[rstest]
+    #[case(r#"1"#, JsonRpcId::from(1u32))]
+    #[case(r#"18446744073709551615"#, JsonRpcId::from(u64::MAX))]
+    #[case(r#""0xabc""#, JsonRpcId::from("0xabc"))]
+    #[case(r#"null"#, JsonRpcId::Null)]
+    fn test_request_ids_round_trip(#[case] id: &str, #[case] expected: JsonRpcId) {
+        let json = format!(r#"{{"jsonrpc":"2.0","method":"eth_chainId","id":{id}}}"#);
+        let request: JsonRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(request.id, Some(expected));
+        assert!(!request.is_notification());
+        assert!(serde_json::to_string(&request)
+            .unwrap()
+            .contains(&format!(r#""id":{id}"#)));
+    }
 }
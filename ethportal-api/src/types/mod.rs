@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod beacon;
 pub mod bootnodes;
 pub mod bytes;
@@ -12,7 +13,11 @@ pub mod enr;
 pub mod execution;
 pub mod history;
 pub mod jsonrpc;
+pub mod network;
 pub mod node_id;
 pub mod portal;
 pub mod portal_wire;
 pub mod query_trace;
+pub mod state;
+pub mod state_trie;
+pub mod subscription;
@@ -0,0 +1,158 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    types::{
+        bootnodes::{Bootnodes, ANGELFOOD_BOOTNODES, TESTNET_BOOTNODES},
+        portal_wire::{ProtocolId, ProtocolIdError},
+    },
+    utils::bytes::hex_decode,
+};
+
+/// A Portal network that the node can join.
+///
+/// Every network has its own protocol ids, so that the nodes of different networks ignore each
+/// other, as well as its own default bootnodes and data directory. Only the master accumulator of
+/// mainnet is bundled, so the history network is only validated on the other networks with an
+/// accumulator of the network, and isn't activated on them by default without one.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PortalNetwork {
+    #[default]
+    Mainnet,
+    Testnet,
+    Angelfood,
+}
+
+impl fmt::Display for PortalNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mainnet => write!(f, "mainnet"),
+            Self::Testnet => write!(f, "testnet"),
+            Self::Angelfood => write!(f, "angelfood"),
+        }
+    }
+}
+
+impl FromStr for PortalNetwork {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet" => Ok(Self::Testnet),
+            "angelfood" => Ok(Self::Angelfood),
+            _ => Err("Invalid network arg. Expected either 'mainnet', 'testnet' or 'angelfood'"),
+        }
+    }
+}
+
+const PROTOCOL_IDS: [ProtocolId; 6] = [
+    ProtocolId::State,
+    ProtocolId::History,
+    ProtocolId::TransactionGossip,
+    ProtocolId::CanonicalIndices,
+    ProtocolId::Beacon,
+    ProtocolId::Utp,
+];
+
+impl PortalNetwork {
+    /// Returns the hex encoded protocol id of the subnetwork or uTP on this network. uTP uses the
+    /// same protocol id on every network.
+    pub fn protocol_id_hex(&self, protocol_id: ProtocolId) -> &'static str {
+        match (self, protocol_id) {
+            (_, ProtocolId::Utp) => "0x757470",
+            (Self::Mainnet, ProtocolId::State) => "0x500A",
+            (Self::Mainnet, ProtocolId::History) => "0x500B",
+            (Self::Mainnet, ProtocolId::TransactionGossip) => "0x500C",
+            (Self::Mainnet, ProtocolId::CanonicalIndices) => "0x500D",
+            (Self::Mainnet, ProtocolId::Beacon) => "0x501A",
+            (Self::Testnet, ProtocolId::State) => "0x502A",
+            (Self::Testnet, ProtocolId::History) => "0x502B",
+            (Self::Testnet, ProtocolId::TransactionGossip) => "0x502C",
+            (Self::Testnet, ProtocolId::CanonicalIndices) => "0x502D",
+            (Self::Testnet, ProtocolId::Beacon) => "0x503A",
+            (Self::Angelfood, ProtocolId::State) => "0x504A",
+            (Self::Angelfood, ProtocolId::History) => "0x504B",
+            (Self::Angelfood, ProtocolId::TransactionGossip) => "0x504C",
+            (Self::Angelfood, ProtocolId::CanonicalIndices) => "0x504D",
+            (Self::Angelfood, ProtocolId::Beacon) => "0x505A",
+        }
+    }
+
+    /// Returns the protocol id encoded as an upper case hex string, if it's used on this network.
+    pub fn protocol_id(&self, hex: &str) -> Result<ProtocolId, ProtocolIdError> {
+        PROTOCOL_IDS
+            .into_iter()
+            .find(|protocol_id| self.protocol_id_hex(*protocol_id) == hex)
+            .ok_or(ProtocolIdError::Invalid)
+    }
+
+    /// Returns the raw bytes of the protocol id on this network.
+    pub fn protocol_id_bytes(&self, protocol_id: ProtocolId) -> Result<Vec<u8>, ProtocolIdError> {
+        hex_decode(self.protocol_id_hex(protocol_id)).map_err(ProtocolIdError::Decode)
+    }
+
+    /// Returns the compiled-in bootnodes that the node joins the network through, unless others
+    /// are given.
+    pub fn default_bootnodes(&self) -> Bootnodes {
+        match self {
+            Self::Mainnet => Bootnodes::Default,
+            Self::Testnet => Bootnodes::Custom(TESTNET_BOOTNODES.clone()),
+            Self::Angelfood => Bootnodes::Custom(ANGELFOOD_BOOTNODES.clone()),
+        }
+    }
+
+    /// Returns the name of the default data directory on this network, so that the content and
+    /// node keys of different networks are kept apart.
+    pub fn data_dir_name(&self) -> String {
+        match self {
+            Self::Mainnet => "trin".to_string(),
+            network => format!("trin-{network}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_protocol_ids_are_unique_per_network() {
+        let networks = [
+            PortalNetwork::Mainnet,
+            PortalNetwork::Testnet,
+            PortalNetwork::Angelfood,
+        ];
+        for network in networks {
+            for protocol_id in PROTOCOL_IDS {
+                let hex = network.protocol_id_hex(protocol_id);
+                assert_eq!(network.protocol_id(hex).unwrap(), protocol_id);
+                for other_network in networks.into_iter().filter(|other| *other != network) {
+                    if protocol_id != ProtocolId::Utp {
+                        assert!(other_network.protocol_id(hex).is_err());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mainnet_protocol_ids() {
+        assert_eq!(
+            PortalNetwork::Mainnet
+                .protocol_id_bytes(ProtocolId::History)
+                .unwrap(),
+            Vec::try_from(ProtocolId::History).unwrap()
+        );
+        assert_eq!(
+            PortalNetwork::Mainnet.protocol_id("0x501A").unwrap(),
+            ProtocolId::from_str("0x501A").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_data_dir_name() {
+        assert_eq!(PortalNetwork::Mainnet.data_dir_name(), "trin");
+        assert_eq!(PortalNetwork::Angelfood.data_dir_name(), "trin-angelfood");
+    }
+}
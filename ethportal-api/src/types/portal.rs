@@ -24,6 +24,20 @@ pub struct PongInfo {
 
 pub type FindNodesInfo = Vec<Enr>;
 
+/// A page of a content value, for content too large to be returned in a single response.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentPage {
+    /// Hex encoded bytes of the ssz-encoded content value, starting at `offset`. "0x" if the
+    /// content was not found.
+    pub content: String,
+    pub offset: u64,
+    /// Size of the whole ssz-encoded content value, in bytes.
+    pub total_size: u64,
+    /// Token to pass to the content page endpoint to get the next page, unset on the last page.
+    pub continuation: Option<String>,
+}
+
 /// Response for Offer endpoint
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,6 +45,15 @@ pub struct AcceptInfo {
     pub content_keys: BitList<typenum::U8>,
 }
 
+/// Response for TraceOffer endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceOfferInfo {
+    pub content_keys: BitList<typenum::U8>,
+    // Whether the accepted content was successfully transferred over uTP
+    pub transferred: bool,
+}
+
 /// Response for TraceGossip endpoint
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -20,6 +20,7 @@ use crate::{
         bytes::ByteList,
         distance::Distance,
         enr::{Enr, SszEnr},
+        network::PortalNetwork,
     },
     utils::bytes::{hex_decode, hex_encode, ByteUtilsError},
     RawContentKey,
@@ -67,6 +68,10 @@ pub const MAX_PORTAL_CONTENT_PAYLOAD_SIZE: usize = MAX_DISCV5_TALK_REQ_PAYLOAD_S
     - (ssz::BYTES_PER_UNION_SELECTOR * 2)
     - ssz::BYTES_PER_LENGTH_OFFSET;
 
+/// The maximum number of content keys in an OFFER request, the capacity of the `content_keys`
+/// bitlist of the ACCEPT response.
+pub const MAX_CONTENT_KEYS_PER_OFFER: usize = 8;
+
 /// Custom payload element of Ping and Pong overlay messages
 #[derive(Debug, PartialEq, Clone)]
 pub struct CustomPayload {
@@ -175,20 +180,12 @@ pub enum ProtocolId {
     Utp,
 }
 
-/// Encode hex string to protocol id
+/// Encode hex string to protocol id on mainnet
 impl FromStr for ProtocolId {
     type Err = ProtocolIdError;
 
     fn from_str(input: &str) -> Result<ProtocolId, Self::Err> {
-        match input {
-            "0x500A" => Ok(ProtocolId::State),
-            "0x500B" => Ok(ProtocolId::History),
-            "0x500C" => Ok(ProtocolId::TransactionGossip),
-            "0x500D" => Ok(ProtocolId::CanonicalIndices),
-            "0x501A" => Ok(ProtocolId::Beacon),
-            "0x757470" => Ok(ProtocolId::Utp),
-            _ => Err(ProtocolIdError::Invalid),
-        }
+        PortalNetwork::Mainnet.protocol_id(input)
     }
 }
 
@@ -206,20 +203,12 @@ impl fmt::Display for ProtocolId {
     }
 }
 
-/// Decode ProtocolId to raw bytes
+/// Decode ProtocolId to raw bytes on mainnet
 impl TryFrom<ProtocolId> for Vec<u8> {
     type Error = ProtocolIdError;
 
     fn try_from(protocol_id: ProtocolId) -> Result<Self, Self::Error> {
-        let bytes = match protocol_id {
-            ProtocolId::State => hex_decode("0x500A"),
-            ProtocolId::History => hex_decode("0x500B"),
-            ProtocolId::TransactionGossip => hex_decode("0x500C"),
-            ProtocolId::CanonicalIndices => hex_decode("0x500D"),
-            ProtocolId::Beacon => hex_decode("0x501A"),
-            ProtocolId::Utp => hex_decode("0x757470"),
-        };
-        bytes.map_err(ProtocolIdError::Decode)
+        PortalNetwork::Mainnet.protocol_id_bytes(protocol_id)
     }
 }
 
@@ -561,17 +550,20 @@ impl From<PopulatedOffer> for Offer {
 /// The content necessary to make an offer message and return the result of propagation
 #[derive(Debug, Clone)]
 pub struct PopulatedOfferWithResult {
-    /// The offered content key & value
-    pub content_item: (RawContentKey, Vec<u8>),
+    /// The offered content keys & values
+    pub content_items: Vec<(RawContentKey, Vec<u8>)>,
     /// The channel to send the result of the offer to
     pub result_tx: tokio::sync::mpsc::UnboundedSender<bool>,
 }
 
 impl From<PopulatedOfferWithResult> for Offer {
     fn from(val: PopulatedOfferWithResult) -> Self {
-        Self {
-            content_keys: vec![val.content_item.0],
-        }
+        let content_keys = val
+            .content_items
+            .into_iter()
+            .map(|(key, _val)| key)
+            .collect();
+        Self { content_keys }
     }
 }
 
@@ -594,6 +586,12 @@ mod test {
     use crate::utils::bytes::hex_encode_upper;
     use test_log::test;
 
+    #[test]
+    fn max_content_keys_per_offer_fit_in_accept() {
+        use ssz_types::typenum::Unsigned;
+        assert_eq!(MAX_CONTENT_KEYS_PER_OFFER, typenum::U8::USIZE);
+    }
+
     #[test]
     #[should_panic]
     fn protocol_id_invalid() {
@@ -29,6 +29,9 @@ pub struct QueryTrace {
     pub target_id: ContentId,
     /// List of pending requests that were unresolved when the content was found.
     pub cancelled: Vec<NodeId>,
+    /// Map of a node's ID to the `QueryFailure` of the request sent to it.
+    #[serde(default)]
+    pub failures: HashMap<NodeId, QueryFailure>,
 }
 
 impl QueryTrace {
@@ -40,6 +43,7 @@ impl QueryTrace {
             metadata: HashMap::new(),
             started_at_ms: SystemTime::now(),
             cancelled: Vec::new(),
+            failures: HashMap::new(),
             target_id,
         }
     }
@@ -86,6 +90,19 @@ impl QueryTrace {
         self.add_metadata(enr, true);
     }
 
+    /// Mark the node whose request failed, and when the failure occurred.
+    pub fn node_failed(&mut self, enr: &Enr, failure: String) {
+        let duration_ms = QueryTrace::timestamp_millis_u64(self.started_at_ms);
+        self.failures.insert(
+            enr.into(),
+            QueryFailure {
+                duration_ms,
+                failure,
+            },
+        );
+        self.add_metadata(enr, false);
+    }
+
     /// Returns milliseconds since the time provided.
     fn timestamp_millis_u64(since: SystemTime) -> u64 {
         let timestamp_millis_u128 = SystemTime::now()
@@ -139,6 +156,16 @@ pub struct QueryResponse {
     pub responded_with: Vec<NodeId>,
 }
 
+/// Represents a failed request to a single node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryFailure {
+    /// Milliseconds since query started.
+    pub duration_ms: u64,
+    /// Description of the failure.
+    pub failure: String,
+}
+
 /// Represents additional info for a given node.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -206,4 +233,21 @@ mod tests {
         let local_data = tracer.metadata.get(local_node_id).unwrap();
         assert_eq!(local_data.enr, local_enr);
     }
+
+    #[test]
+    fn test_query_trace_failure() {
+        let (_, local_enr) = generate_random_remote_enr();
+        let mut tracer = QueryTrace::new(&local_enr, local_enr.node_id().raw());
+        let (_, enr_a) = generate_random_remote_enr();
+        let node_id_a: &NodeId = &enr_a.clone().into();
+
+        tracer.node_responded_with(&local_enr, vec![&enr_a]);
+        tracer.node_failed(&enr_a, "The request timed out".to_string());
+
+        let a_failure = tracer.failures.get(node_id_a).unwrap();
+        assert_eq!(a_failure.failure, "The request timed out");
+        assert!(!tracer.responses.contains_key(node_id_a));
+        assert_eq!(tracer.metadata.get(node_id_a).unwrap().enr, enr_a);
+        assert_eq!(tracer.received_from, None);
+    }
 }
@@ -0,0 +1,17 @@
+use crate::{types::enr::Enr, PossibleStateContentValue};
+use serde::{Deserialize, Serialize};
+
+/// Response for FindContent & RecursiveFindContent endpoints
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ContentInfo {
+    #[serde(rename_all = "camelCase")]
+    ConnectionId { connection_id: u16 },
+    #[serde(rename_all = "camelCase")]
+    Content {
+        content: PossibleStateContentValue,
+        utp_transfer: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Enrs { enrs: Vec<Enr> },
+}
@@ -0,0 +1,375 @@
+use ethereum_types::{Address, H256, U256};
+use keccak_hash::{KECCAK_EMPTY, KECCAK_NULL_RLP};
+use rlp::{DecoderError, Rlp};
+use rlp_derive::{RlpDecodable, RlpEncodable};
+
+/// The state of an account, as stored in the leaves of the account trie.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct AccountState {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: H256,
+    pub code_hash: H256,
+}
+
+/// The default account is the empty account, with no storage and no code.
+impl Default for AccountState {
+    fn default() -> Self {
+        Self {
+            nonce: 0,
+            balance: U256::zero(),
+            storage_root: KECCAK_NULL_RLP,
+            code_hash: KECCAK_EMPTY,
+        }
+    }
+}
+
+impl AccountState {
+    /// Decodes an account from the value of its account trie leaf.
+    pub fn from_rlp(bytes: &[u8]) -> Result<Self, DecoderError> {
+        rlp::decode(bytes)
+    }
+
+    pub fn has_empty_storage(&self) -> bool {
+        self.storage_root == KECCAK_NULL_RLP
+    }
+
+    pub fn has_empty_code(&self) -> bool {
+        self.code_hash == KECCAK_EMPTY
+    }
+}
+
+/// A reference from a trie node to one of its children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeRef {
+    /// The child is stored separately, under the hash of its encoding.
+    Hash(H256),
+    /// The child encodes to less than 32 bytes, and is embedded in its parent.
+    Inline(Vec<u8>),
+}
+
+/// The outcome of following a trie path through a single node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrieStep {
+    /// The path ends at this node, which holds the given value.
+    Value(Vec<u8>),
+    /// The trie holds no value at the path.
+    Absent,
+    /// The path continues at `node`, whose own path is the first `depth` nibbles.
+    Child { node: NodeRef, depth: usize },
+}
+
+/// Splits a trie key into the nibbles that make up its path.
+pub fn key_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Returns the key of an account in the account trie.
+pub fn account_trie_key(address: Address) -> H256 {
+    keccak_hash::keccak(address)
+}
+
+/// Returns the key of a storage slot in a contract storage trie.
+pub fn storage_trie_key(slot: U256) -> H256 {
+    let mut slot_bytes = [0u8; 32];
+    slot.to_big_endian(&mut slot_bytes);
+    keccak_hash::keccak(slot_bytes)
+}
+
+/// Decodes the value of a contract storage trie leaf.
+pub fn decode_storage_value(bytes: &[u8]) -> Result<U256, DecoderError> {
+    rlp::decode(bytes)
+}
+
+/// Returns true if `code` is the contract bytecode with hash `code_hash`.
+pub fn verify_bytecode(code: &[u8], code_hash: H256) -> bool {
+    keccak_hash::keccak(code) == code_hash
+}
+
+/// Returns true if `node` is the encoded trie node with hash `node_hash`.
+pub fn verify_node(node: &[u8], node_hash: H256) -> bool {
+    keccak_hash::keccak(node) == node_hash
+}
+
+/// Follows the path `nibbles` through the encoded trie `node`, whose own path is the first `depth`
+/// nibbles.
+pub fn walk_node(node: &[u8], nibbles: &[u8], depth: usize) -> Result<TrieStep, DecoderError> {
+    let rlp = Rlp::new(node);
+    let remaining = nibbles
+        .get(depth..)
+        .ok_or(DecoderError::Custom("path too short"))?;
+    match rlp.item_count()? {
+        17 => match remaining.first() {
+            None => {
+                let value = rlp.at(16)?.data()?;
+                match value.is_empty() {
+                    true => Ok(TrieStep::Absent),
+                    false => Ok(TrieStep::Value(value.to_vec())),
+                }
+            }
+            Some(nibble) => match decode_node_ref(&rlp.at(*nibble as usize)?)? {
+                Some(node) => Ok(TrieStep::Child {
+                    node,
+                    depth: depth + 1,
+                }),
+                None => Ok(TrieStep::Absent),
+            },
+        },
+        2 => {
+            let (path, is_leaf) = decode_hex_prefix(rlp.at(0)?.data()?)?;
+            if !remaining.starts_with(&path) {
+                return Ok(TrieStep::Absent);
+            }
+            match is_leaf {
+                true if remaining.len() == path.len() => {
+                    Ok(TrieStep::Value(rlp.at(1)?.data()?.to_vec()))
+                }
+                true => Ok(TrieStep::Absent),
+                false => match decode_node_ref(&rlp.at(1)?)? {
+                    Some(node) => Ok(TrieStep::Child {
+                        node,
+                        depth: depth + path.len(),
+                    }),
+                    None => Err(DecoderError::Custom("extension node without child")),
+                },
+            }
+        }
+        _ => Err(DecoderError::RlpIncorrectListLen),
+    }
+}
+
+/// Follows `key` through `proof`, the encoded nodes on its path through the trie with root `root`
+/// starting at the root node, and returns the value the trie holds at `key`, if any.
+///
+/// Every node on the path that is not embedded in its parent must be the next node of the proof,
+/// and proofs with nodes left over past the end of the path are rejected.
+pub fn verify_proof<T: AsRef<[u8]>>(
+    root: H256,
+    proof: &[T],
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, DecoderError> {
+    // The empty trie has no node to prove anything with.
+    if root == KECCAK_NULL_RLP && proof.is_empty() {
+        return Ok(None);
+    }
+    let nibbles = key_nibbles(key);
+    let mut nodes = proof.iter();
+    let mut node = NodeRef::Hash(root);
+    let mut depth = 0;
+    loop {
+        let encoded = match node {
+            NodeRef::Hash(hash) => {
+                let encoded = nodes
+                    .next()
+                    .ok_or(DecoderError::Custom("proof is missing a trie node"))?
+                    .as_ref()
+                    .to_vec();
+                if !verify_node(&encoded, hash) {
+                    return Err(DecoderError::Custom("proof node does not match its hash"));
+                }
+                encoded
+            }
+            NodeRef::Inline(encoded) => encoded,
+        };
+        let value = match walk_node(&encoded, &nibbles, depth)? {
+            TrieStep::Value(value) => Some(value),
+            TrieStep::Absent => None,
+            TrieStep::Child {
+                node: child,
+                depth: child_depth,
+            } => {
+                node = child;
+                depth = child_depth;
+                continue;
+            }
+        };
+        if nodes.next().is_some() {
+            return Err(DecoderError::Custom(
+                "proof has nodes past the end of its path",
+            ));
+        }
+        return Ok(value);
+    }
+}
+
+fn decode_node_ref(rlp: &Rlp) -> Result<Option<NodeRef>, DecoderError> {
+    if rlp.is_list() {
+        return Ok(Some(NodeRef::Inline(rlp.as_raw().to_vec())));
+    }
+    let data = rlp.data()?;
+    match data.len() {
+        0 => Ok(None),
+        32 => Ok(Some(NodeRef::Hash(H256::from_slice(data)))),
+        _ => Err(DecoderError::Custom("invalid trie node reference")),
+    }
+}
+
+/// Decodes a hex-prefix encoded path into its nibbles, and whether it belongs to a leaf node.
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), DecoderError> {
+    let nibbles = key_nibbles(encoded);
+    let flag = *nibbles
+        .first()
+        .ok_or(DecoderError::Custom("empty hex-prefix path"))?;
+    if flag > 3 {
+        return Err(DecoderError::Custom("invalid hex-prefix flag"));
+    }
+    let is_leaf = flag >= 2;
+    // Even length paths are padded with an extra zero nibble after the flag.
+    let skip = match flag % 2 {
+        1 => 1,
+        _ => 2,
+    };
+    Ok((nibbles[skip..].to_vec(), is_leaf))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    use eth_trie::{EthTrie, MemoryDB, Trie};
+
+    /// Follows `key` through the nodes of a proof, as trie nodes would be fetched from the network.
+    fn walk_proof(root: H256, proof: &[Vec<u8>], key: &[u8]) -> Option<Vec<u8>> {
+        let nibbles = key_nibbles(key);
+        let mut nodes = proof.iter();
+        let mut node = NodeRef::Hash(root);
+        let mut depth = 0;
+        loop {
+            let encoded = match node {
+                NodeRef::Hash(hash) => {
+                    let encoded = nodes.next().unwrap().clone();
+                    assert!(verify_node(&encoded, hash));
+                    encoded
+                }
+                NodeRef::Inline(encoded) => encoded,
+            };
+            match walk_node(&encoded, &nibbles, depth).unwrap() {
+                TrieStep::Value(value) => return Some(value),
+                TrieStep::Absent => return None,
+                TrieStep::Child {
+                    node: child,
+                    depth: child_depth,
+                } => {
+                    node = child;
+                    depth = child_depth;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walk_account_trie_proof() {
+        let mut trie = EthTrie::new(Arc::new(MemoryDB::new(true)));
+        let accounts: Vec<(H256, AccountState)> = (0..64u64)
+            .map(|i| {
+                let key = account_trie_key(Address::from_low_u64_be(i));
+                let account = AccountState {
+                    nonce: i,
+                    balance: U256::from(i * 1000),
+                    ..Default::default()
+                };
+                (key, account)
+            })
+            .collect();
+        for (key, account) in accounts.iter() {
+            trie.insert(key.as_bytes(), &rlp::encode(account)).unwrap();
+        }
+        let root = trie.root_hash().unwrap();
+
+        for (key, account) in accounts.iter() {
+            let proof = trie.get_proof(key.as_bytes()).unwrap();
+            let value = walk_proof(root, &proof, key.as_bytes()).unwrap();
+            assert_eq!(AccountState::from_rlp(&value).unwrap(), *account);
+        }
+
+        let missing_key = account_trie_key(Address::from_low_u64_be(64));
+        let proof = trie.get_proof(missing_key.as_bytes()).unwrap();
+        assert_eq!(walk_proof(root, &proof, missing_key.as_bytes()), None);
+    }
+
+    #[test]
+    fn walk_storage_trie_proof() {
+        let mut trie = EthTrie::new(Arc::new(MemoryDB::new(true)));
+        for slot in 0..16u64 {
+            let value = rlp::encode(&U256::from(slot + 1));
+            trie.insert(storage_trie_key(U256::from(slot)).as_bytes(), &value)
+                .unwrap();
+        }
+        let root = trie.root_hash().unwrap();
+
+        let key = storage_trie_key(U256::from(7));
+        let proof = trie.get_proof(key.as_bytes()).unwrap();
+        let value = walk_proof(root, &proof, key.as_bytes()).unwrap();
+        assert_eq!(decode_storage_value(&value).unwrap(), U256::from(8));
+    }
+
+    #[test]
+    fn verify_account_trie_proof() {
+        let mut trie = EthTrie::new(Arc::new(MemoryDB::new(true)));
+        for i in 0..64u64 {
+            let key = account_trie_key(Address::from_low_u64_be(i));
+            let account = AccountState {
+                nonce: i,
+                ..Default::default()
+            };
+            trie.insert(key.as_bytes(), &rlp::encode(&account)).unwrap();
+        }
+        let root = trie.root_hash().unwrap();
+        let key = account_trie_key(Address::from_low_u64_be(7));
+        let proof = trie.get_proof(key.as_bytes()).unwrap();
+
+        let value = verify_proof(root, &proof, key.as_bytes()).unwrap().unwrap();
+        assert_eq!(AccountState::from_rlp(&value).unwrap().nonce, 7);
+
+        let missing_key = account_trie_key(Address::from_low_u64_be(64));
+        let missing_proof = trie.get_proof(missing_key.as_bytes()).unwrap();
+        assert_eq!(
+            verify_proof(root, &missing_proof, missing_key.as_bytes()).unwrap(),
+            None
+        );
+
+        // Proofs must be complete, must not have extra nodes, and must match the root.
+        assert!(verify_proof(root, &proof[..proof.len() - 1], key.as_bytes()).is_err());
+        let mut extended_proof = proof.clone();
+        extended_proof.push(proof[0].clone());
+        assert!(verify_proof(root, &extended_proof, key.as_bytes()).is_err());
+        assert!(verify_proof(H256::repeat_byte(0x11), &proof, key.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn verify_empty_trie_proof() {
+        let key = storage_trie_key(U256::zero());
+        let proof: Vec<Vec<u8>> = vec![];
+        assert_eq!(
+            verify_proof(KECCAK_NULL_RLP, &proof, key.as_bytes()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_account() {
+        let account = AccountState::default();
+        assert!(account.has_empty_storage());
+        assert!(account.has_empty_code());
+        assert!(verify_bytecode(&[], account.code_hash));
+    }
+
+    #[test]
+    fn hex_prefix_paths() {
+        assert_eq!(
+            decode_hex_prefix(&[0x00, 0x12]).unwrap(),
+            (vec![1, 2], false)
+        );
+        assert_eq!(
+            decode_hex_prefix(&[0x11, 0x23]).unwrap(),
+            (vec![1, 2, 3], false)
+        );
+        assert_eq!(decode_hex_prefix(&[0x20]).unwrap(), (vec![], true));
+        assert_eq!(decode_hex_prefix(&[0x3f]).unwrap(), (vec![0x0f], true));
+        assert!(decode_hex_prefix(&[0x40]).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Kinds of notifications that can be subscribed to with `eth_subscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+    /// Block headers newly stored by the history network.
+    NewHeads,
+    /// Content keys of any content newly stored by the history network.
+    NewContent,
+    /// Peers connected in or removed from the history network routing table.
+    RoutingTable,
+}
+
+/// A change of the history network routing table, as notified to `routingTable` subscribers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum RoutingTableChange {
+    /// A peer was connected in the routing table.
+    #[serde(rename_all = "camelCase")]
+    PeerAdded { node_id: String },
+    /// A connected peer was disconnected or removed from the routing table.
+    #[serde(rename_all = "camelCase")]
+    PeerRemoved { node_id: String },
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn subscription_kind_serde() {
+        let kind: SubscriptionKind = serde_json::from_str("\"newHeads\"").unwrap();
+        assert_eq!(kind, SubscriptionKind::NewHeads);
+        let kind: SubscriptionKind = serde_json::from_str("\"newContent\"").unwrap();
+        assert_eq!(kind, SubscriptionKind::NewContent);
+        let kind: SubscriptionKind = serde_json::from_str("\"routingTable\"").unwrap();
+        assert_eq!(kind, SubscriptionKind::RoutingTable);
+        assert!(serde_json::from_str::<SubscriptionKind>("\"logs\"").is_err());
+    }
+
+    #[test]
+    fn routing_table_change_serde() {
+        let change = RoutingTableChange::PeerAdded {
+            node_id: "0x01".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            serde_json::json!({"event": "peerAdded", "nodeId": "0x01"})
+        );
+    }
+}
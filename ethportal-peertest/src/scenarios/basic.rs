@@ -7,13 +7,12 @@ use ethportal_api::{
 use jsonrpsee::async_client::Client;
 use ssz::Encode;
 use tracing::info;
-use trin_utils::version::get_trin_version;
+use trin_utils::version::get_trin_client_version;
 
 pub async fn test_web3_client_version(target: &Client) {
     info!("Testing web3_clientVersion");
     let result = target.client_version().await.unwrap();
-    let expected_version = format!("trin v{}", get_trin_version());
-    assert_eq!(result, expected_version);
+    assert_eq!(result, get_trin_client_version());
 }
 
 pub async fn test_discv5_node_info(peertest: &Peertest) {
@@ -9,7 +9,10 @@ use ethportal_api::{
 };
 use portal_bridge::{
     api::{consensus::ConsensusApi, execution::ExecutionApi},
-    bridge::{beacon::BeaconBridge, history::HistoryBridge},
+    bridge::{
+        beacon::BeaconBridge,
+        history::{HistoryBridge, HistoryBridgeOptions},
+    },
     cli::Provider,
     types::mode::BridgeMode,
 };
@@ -33,6 +36,7 @@ pub async fn test_history_bridge(peertest: &Peertest, target: &HttpClient) {
         portal_clients,
         header_oracle,
         epoch_acc_path,
+        HistoryBridgeOptions::default(),
     );
     bridge.launch().await;
     let (content_key, content_value) = fixture_header_with_proof_1000010();
@@ -0,0 +1,133 @@
+use crate::{utils::wait_for_state_content, Peertest};
+use ethereum_types::U256;
+use ethportal_api::{
+    types::{distance::Distance, state::ContentInfo},
+    utils::bytes::hex_encode,
+    ContractBytecode, PossibleStateContentValue, StateContentKey, StateContentValue,
+    StateNetworkApiClient,
+};
+use jsonrpsee::async_client::Client;
+use ssz::Encode;
+use tracing::info;
+
+fn fixture_contract_bytecode() -> (StateContentKey, StateContentValue) {
+    let code_hash: [u8; 32] =
+        hex::decode("1c3374235d773b2189aed115aa13143020fcdbbe86e38f358cf3e4771b2f0244")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let content_key = StateContentKey::ContractBytecode(ContractBytecode {
+        address: vec![0x82; 20].into(),
+        code_hash,
+    });
+    // The code hash is the keccak256 hash of this bytecode, as required by content validation.
+    let content_value = StateContentValue(vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+    (content_key, content_value)
+}
+
+pub async fn test_state_radius(target: &Client) {
+    info!("Testing portal_stateRadius");
+    let result = target.radius().await.unwrap();
+    assert_eq!(
+        result,
+        U256::from_big_endian(Distance::MAX.as_ssz_bytes().as_slice())
+    );
+}
+
+pub async fn test_state_routing_table_info(target: &Client) {
+    info!("Testing portal_stateRoutingTableInfo");
+    let result = target.routing_table_info().await.unwrap();
+    assert!(result.local_node_id.starts_with("0x"));
+}
+
+pub async fn test_state_ping(target: &Client, peertest: &Peertest) {
+    info!("Testing portal_statePing");
+    let result = target.ping(peertest.bootnode.enr.clone()).await.unwrap();
+    assert_eq!(
+        result.data_radius,
+        U256::from_big_endian(Distance::MAX.as_ssz_bytes().as_slice())
+    );
+}
+
+pub async fn test_state_find_nodes_zero_distance(target: &Client, peertest: &Peertest) {
+    info!("Testing portal_stateFindNodes with zero distance");
+    let result = target
+        .find_nodes(peertest.bootnode.enr.clone(), vec![0])
+        .await
+        .unwrap();
+    assert!(result.contains(&peertest.bootnode.enr));
+}
+
+pub async fn test_state_local_content_absent(target: &Client) {
+    info!("Testing portal_stateLocalContent absent");
+    let (content_key, _) = fixture_contract_bytecode();
+    let result = target.local_content(content_key).await.unwrap();
+    assert_eq!(result, PossibleStateContentValue::ContentAbsent);
+}
+
+pub async fn test_state_store(target: &Client) {
+    info!("Testing portal_stateStore and portal_stateLocalContent");
+    let (content_key, content_value) = fixture_contract_bytecode();
+    let result = target
+        .store(content_key.clone(), content_value.clone())
+        .await
+        .unwrap();
+    assert!(result);
+
+    let result = target.local_content(content_key.clone()).await.unwrap();
+    assert_eq!(
+        result,
+        PossibleStateContentValue::ContentPresent(content_value.clone())
+    );
+
+    info!("Testing portal_stateRecursiveFindContent from the local db");
+    let result = target.recursive_find_content(content_key).await.unwrap();
+    assert_eq!(
+        result,
+        ContentInfo::Content {
+            content: PossibleStateContentValue::ContentPresent(content_value),
+            utp_transfer: false,
+        }
+    );
+}
+
+pub async fn test_state_offer(target: &Client, peertest: &Peertest) {
+    info!("Testing portal_stateOffer");
+    let (content_key, content_value) = fixture_contract_bytecode();
+    let result = target
+        .offer(
+            peertest.bootnode.enr.clone(),
+            content_key.clone(),
+            Some(content_value.clone()),
+        )
+        .await
+        .unwrap();
+    // Check that ACCEPT response sent by bootnode accepted the offered content
+    assert_eq!(hex_encode(result.content_keys.into_bytes()), "0x03");
+    let response = wait_for_state_content(&peertest.bootnode.ipc_client, content_key.clone()).await;
+    assert_eq!(
+        response,
+        PossibleStateContentValue::ContentPresent(content_value.clone())
+    );
+
+    info!("Testing portal_stateFindContent");
+    let result = target
+        .find_content(peertest.bootnode.enr.clone(), content_key)
+        .await
+        .unwrap();
+    match result {
+        ContentInfo::Content { content, .. } => assert_eq!(
+            content,
+            PossibleStateContentValue::ContentPresent(content_value)
+        ),
+        other => panic!("Expected content, got {other:?}"),
+    }
+}
+
+pub async fn test_state_gossip(target: &Client) {
+    info!("Testing portal_stateGossip");
+    let (content_key, content_value) = fixture_contract_bytecode();
+    let result = target.gossip(content_key, content_value).await.unwrap();
+    // The bootnode is the only peer in the routing table, after it answered the earlier requests.
+    assert_eq!(result, 1);
+}
@@ -2,7 +2,8 @@ use tracing::error;
 
 use ethportal_api::{
     BeaconContentKey, BeaconNetworkApiClient, HistoryContentKey, HistoryNetworkApiClient,
-    PossibleBeaconContentValue, PossibleHistoryContentValue,
+    PossibleBeaconContentValue, PossibleHistoryContentValue, PossibleStateContentValue,
+    StateContentKey, StateNetworkApiClient,
 };
 
 /// Wait for the history content to be transferred
@@ -32,6 +33,33 @@ pub async fn wait_for_history_content<P: HistoryNetworkApiClient + std::marker::
     received_content_value.unwrap()
 }
 
+/// Wait for the state content to be transferred
+pub async fn wait_for_state_content<P: StateNetworkApiClient + std::marker::Sync>(
+    ipc_client: &P,
+    content_key: StateContentKey,
+) -> PossibleStateContentValue {
+    let mut received_content_value = ipc_client.local_content(content_key.clone()).await;
+
+    let mut counter = 0;
+
+    // If content is absent an error will be returned.
+    while counter < 5 {
+        let message = match received_content_value {
+            Ok(cp @ PossibleStateContentValue::ContentPresent(_)) => return cp,
+            Ok(PossibleStateContentValue::ContentAbsent) => {
+                "absent state content response received".to_string()
+            }
+            Err(e) => format!("received an error {e}"),
+        };
+        error!("Retrying after 0.5s, because {message}");
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        received_content_value = ipc_client.local_content(content_key.clone()).await;
+        counter += 1;
+    }
+
+    received_content_value.unwrap()
+}
+
 /// Wait for the beacon content to be transferred
 pub async fn wait_for_beacon_content<P: BeaconNetworkApiClient + std::marker::Sync>(
     ipc_client: &P,
@@ -1,6 +1,11 @@
 use anyhow::{anyhow, bail};
 use ethereum_types::H256;
 use futures::future::join_all;
+use jsonrpsee::{
+    core::client::{Subscription, SubscriptionClientT},
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
 use serde_json::{json, Value};
 use surf::{
     middleware::{Middleware, Next},
@@ -69,7 +74,7 @@ impl ExecutionApi {
                 let tx_hash = hex_encode(tx_hash);
                 let params = Params::Array(vec![json!(tx_hash)]);
                 let method = "eth_getTransactionReceipt".to_string();
-                JsonRequest::new(method, params, id as u32)
+                JsonRequest::new(method, params, id as u64)
             })
             .collect();
         let response = self.batch_requests(request).await?;
@@ -79,7 +84,7 @@ impl ExecutionApi {
     pub async fn get_latest_block_number(&self) -> anyhow::Result<u64> {
         let params = Params::Array(vec![json!("latest"), json!(false)]);
         let method = "eth_getBlockByNumber".to_string();
-        let request = JsonRequest::new(method, params, 1);
+        let request = JsonRequest::new(method, params, 1u64);
         let response = self.batch_requests(vec![request]).await?;
         let response: Vec<Value> = serde_json::from_str(&response)?;
         let result = response[0]
@@ -97,7 +102,7 @@ impl ExecutionApi {
                 let uncle_hash = hex_encode(uncle);
                 let params = Params::Array(vec![json!(uncle_hash), json!(false)]);
                 let method = "eth_getBlockByHash".to_string();
-                JsonRequest::new(method, params, id as u32)
+                JsonRequest::new(method, params, id as u64)
             })
             .collect();
         let response = self.batch_requests(batch_request).await?;
@@ -117,12 +122,33 @@ impl ExecutionApi {
     pub async fn get_header(&self, height: u64) -> anyhow::Result<FullHeader> {
         // Geth requires block numbers to be formatted using the following padding.
         let block_param = format!("0x{height:01X}");
+        self.request_full_header("eth_getBlockByNumber", block_param, height)
+            .await
+    }
+
+    /// Fetches the block with the header hashing to `block_hash`, which stays the same block
+    /// after a reorg unlike the block at its height.
+    pub async fn get_header_by_hash(&self, block_hash: H256) -> anyhow::Result<FullHeader> {
+        let full_header = self
+            .request_full_header("eth_getBlockByHash", hex_encode(block_hash), 1)
+            .await?;
+        if full_header.header.hash() != block_hash {
+            bail!(
+                "Provider returned block {:?} instead of block {block_hash:?}",
+                full_header.header.hash()
+            );
+        }
+        Ok(full_header)
+    }
+
+    async fn request_full_header(
+        &self,
+        method: &str,
+        block_param: String,
+        id: u64,
+    ) -> anyhow::Result<FullHeader> {
         let params = Params::Array(vec![json!(block_param), json!(true)]);
-        let batch_request = vec![JsonRequest::new(
-            "eth_getBlockByNumber".to_string(),
-            params,
-            height as u32,
-        )];
+        let batch_request = vec![JsonRequest::new(method.to_string(), params, id)];
         let response = self.batch_requests(batch_request).await?;
         let batch: FullHeaderBatch = serde_json::from_str(&response)?;
         if batch.headers.len() != 1 {
@@ -171,6 +197,20 @@ impl ExecutionApi {
     }
 }
 
+/// Subscribes to the new heads of a trusted execution layer node over a websocket connection.
+/// The subscription ends once the returned client is dropped.
+pub async fn subscribe_new_heads(ws_url: &Url) -> anyhow::Result<(WsClient, Subscription<Header>)> {
+    let client = WsClientBuilder::default()
+        .build(ws_url.as_str())
+        .await
+        .map_err(|err| anyhow!("Unable to connect to websocket provider: {err:?}"))?;
+    let subscription = client
+        .subscribe("eth_subscribe", rpc_params!["newHeads"], "eth_unsubscribe")
+        .await
+        .map_err(|err| anyhow!("Unable to subscribe to new heads: {err:?}"))?;
+    Ok((client, subscription))
+}
+
 #[derive(Debug)]
 pub struct Retry {
     attempts: u8,
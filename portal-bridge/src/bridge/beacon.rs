@@ -1,5 +1,6 @@
 use std::{
     cmp::Ordering,
+    ops::Range,
     path::PathBuf,
     sync::{Arc, Mutex},
     time::SystemTime,
@@ -43,6 +44,9 @@ use ethportal_api::{
 /// The number of slots in a sync committee period.
 const SLOTS_PER_PERIOD: u64 = 32 * 256;
 
+/// The maximum number of `LightClientUpdate`s that can be requested at once.
+const MAX_REQUEST_LIGHT_CLIENT_UPDATES: u64 = 128;
+
 pub struct BeaconBridge {
     pub api: ConsensusApi,
     mode: BridgeMode,
@@ -302,44 +306,56 @@ impl BeaconBridge {
             }
         }
 
-        let data = api.get_lc_updates(expected_current_period, 1).await?;
-        let update: Value = serde_json::from_str(&data)?;
-        let update: LightClientUpdateCapella = serde_json::from_value(update[0]["data"].clone())?;
-        let finalized_header_period = update.finalized_header.beacon.slot / SLOTS_PER_PERIOD;
-
-        // We don't serve a `LightClientUpdate` if its finalized header slot is not within the
-        // expected current period.
-        if finalized_header_period != expected_current_period {
-            warn!(
-                "LightClientUpdate finalized header is not for the expected period: Expected: {expected_current_period}, Actual: {actual_period}",
-                expected_current_period = expected_current_period,
-                actual_period = finalized_header_period
-            );
-            return Ok(current_period);
-        }
+        let periods = periods_to_serve(current_period, expected_current_period);
+        let data = api
+            .get_lc_updates(periods.start, periods.end - periods.start)
+            .await?;
+        let updates: Vec<Value> = serde_json::from_str(&data)?;
+        let mut served_period = current_period;
+        for (period, update) in periods.zip(updates) {
+            let update: LightClientUpdateCapella = serde_json::from_value(update["data"].clone())?;
+            let finalized_header_period = update.finalized_header.beacon.slot / SLOTS_PER_PERIOD;
+
+            // We don't serve a `LightClientUpdate` if its finalized header slot is not within the
+            // expected period.
+            if finalized_header_period != period {
+                warn!(
+                    "LightClientUpdate finalized header is not for the expected period: Expected: {period}, Actual: {finalized_header_period}",
+                );
+                break;
+            }
 
-        let fork_versioned_update = ForkVersionedLightClientUpdate {
-            fork_name: ForkName::Capella,
-            update: LightClientUpdate::Capella(update.clone()),
-        };
+            let fork_versioned_update = ForkVersionedLightClientUpdate {
+                fork_name: ForkName::Capella,
+                update: LightClientUpdate::Capella(update),
+            };
 
-        let content_value = BeaconContentValue::LightClientUpdatesByRange(
-            LightClientUpdatesByRange(VariableList::from(vec![fork_versioned_update])),
-        );
-        let content_key =
-            BeaconContentKey::LightClientUpdatesByRange(LightClientUpdatesByRangeKey {
-                start_period: expected_current_period,
-                count: 1,
-            });
-        info!(
-            period = %expected_current_period,
-            "Generated LightClientUpdate",
-        );
+            let content_value = BeaconContentValue::LightClientUpdatesByRange(
+                LightClientUpdatesByRange(VariableList::from(vec![fork_versioned_update])),
+            );
+            let content_key =
+                BeaconContentKey::LightClientUpdatesByRange(LightClientUpdatesByRangeKey {
+                    start_period: period,
+                    count: 1,
+                });
+            info!(
+                period = %period,
+                "Generated LightClientUpdate",
+            );
 
-        // Update the current known period if we successfully gossiped the latest data.
-        gossip_beacon_content(portal_clients, content_key, content_value, slot_stats).await?;
+            // Update the last served period if we successfully gossiped its data, so that the
+            // periods after it are served again on failure.
+            gossip_beacon_content(
+                Arc::clone(&portal_clients),
+                content_key,
+                content_value,
+                slot_stats.clone(),
+            )
+            .await?;
+            served_period = period;
+        }
 
-        Ok(expected_current_period)
+        Ok(served_period)
     }
 
     async fn serve_light_client_optimistic_update(
@@ -405,3 +421,29 @@ impl BeaconBridge {
         Ok(new_finalized_slot)
     }
 }
+
+/// Returns the sync committee periods to serve the `LightClientUpdate` of, after `current_period`
+/// was served: every period since, so that no period is skipped when the bridge falls behind, eg.
+/// when gossiping failed at the end of a period. Only the expected current period is served on
+/// launch, when no period was served yet.
+fn periods_to_serve(current_period: u64, expected_current_period: u64) -> Range<u64> {
+    let start_period = match current_period {
+        0 => expected_current_period,
+        _ => (current_period + 1)
+            .max((expected_current_period + 1).saturating_sub(MAX_REQUEST_LIGHT_CLIENT_UPDATES)),
+    };
+    start_period..expected_current_period + 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_periods_to_serve() {
+        assert_eq!(periods_to_serve(0, 900), 900..901);
+        assert_eq!(periods_to_serve(899, 900), 900..901);
+        assert_eq!(periods_to_serve(896, 900), 897..901);
+        assert_eq!(periods_to_serve(100, 900), 773..901);
+    }
+}
@@ -1,21 +1,25 @@
 use std::{
     fs,
     ops::Range,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
 use anyhow::{anyhow, bail};
+use ethereum_types::H256;
 use ssz::Decode;
 use tokio::{
     sync::{OwnedSemaphorePermit, Semaphore},
     time::{sleep, timeout, Duration},
 };
 use tracing::{debug, error, info, warn, Instrument};
+use url::Url;
 
 use crate::{
-    api::execution::ExecutionApi,
-    gossip::gossip_history_content,
+    api::execution::{subscribe_new_heads, ExecutionApi},
+    checkpoint::BackfillCheckpoint,
+    gossip::{GossipRateLimiter, HistoryGossiper},
+    retry_queue::GossipRetryQueue,
     stats::{HistoryBlockStats, StatsReporter},
     types::{
         full_header::FullHeader,
@@ -38,6 +42,7 @@ use ethportal_api::{
     BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, EpochAccumulatorKey, HistoryContentKey,
     HistoryContentValue,
 };
+use trin_history::era1::{find_era1_file, Era1};
 use trin_validation::{
     accumulator::MasterAccumulator,
     constants::{EPOCH_SIZE as EPOCH_SIZE_USIZE, MERGE_BLOCK_NUMBER},
@@ -57,6 +62,31 @@ pub struct HistoryBridge {
     pub execution_api: ExecutionApi,
     pub header_oracle: HeaderOracle,
     pub epoch_acc_path: PathBuf,
+    pub options: HistoryBridgeOptions,
+    gossiper: HistoryGossiper,
+}
+
+/// Optional sources and settings of the history bridge.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryBridgeOptions {
+    /// Websocket url of a trusted execution layer node to follow new heads from in latest mode.
+    pub el_ws_provider: Option<Url>,
+    /// Directory of era1 archives to serve pre-merge blocks from in backfill mode.
+    pub era1_dir: Option<PathBuf>,
+    /// File to record the progress of a backfill in.
+    pub checkpoint_path: Option<PathBuf>,
+    /// Limits the rate at which content is gossiped.
+    pub gossip_rate_limiter: Option<GossipRateLimiter>,
+    /// Number of content items to gossip together, or 0 or 1 to gossip every item on its own.
+    pub gossip_batch_size: usize,
+    /// Queue of content that failed to be gossiped, to gossip again later.
+    pub gossip_retry_queue: Option<Arc<GossipRetryQueue>>,
+}
+
+/// A validated era1 archive, along with the epoch accumulator of its blocks.
+struct Era1Epoch {
+    era1: Era1,
+    epoch_acc: Arc<EpochAccumulator>,
 }
 
 impl HistoryBridge {
@@ -66,13 +96,22 @@ impl HistoryBridge {
         portal_clients: Vec<HttpClient>,
         header_oracle: HeaderOracle,
         epoch_acc_path: PathBuf,
+        options: HistoryBridgeOptions,
     ) -> Self {
+        let gossiper = HistoryGossiper::new(
+            portal_clients.clone(),
+            options.gossip_rate_limiter.clone(),
+            options.gossip_batch_size,
+            options.gossip_retry_queue.clone(),
+        );
         Self {
             mode,
             portal_clients,
             execution_api,
             header_oracle,
             epoch_acc_path,
+            options,
+            gossiper,
         }
     }
 }
@@ -97,25 +136,33 @@ impl HistoryBridge {
         // test files have no block number data, so we report all gossiped content at height 0.
         let block_stats = Arc::new(Mutex::new(HistoryBlockStats::new(0)));
         for asset in assets.0.into_iter() {
-            let _ = gossip_history_content(
-                &self.portal_clients,
-                asset.content_key.clone(),
-                asset.content_value,
-                block_stats.clone(),
-            )
-            .await;
+            let _ = self
+                .gossiper
+                .gossip(
+                    asset.content_key.clone(),
+                    asset.content_value,
+                    block_stats.clone(),
+                )
+                .await;
             if let HistoryContentKey::BlockHeaderWithProof(_) = asset.content_key {
                 sleep(Duration::from_millis(50)).await;
             }
         }
     }
 
-    // Devops nodes don't have websockets available, so we can't actually poll the latest block.
-    // Instead we loop on a short interval and fetch the latest blocks not yet served.
     async fn launch_latest(&self) {
-        let mut block_index = self.execution_api.get_latest_block_number().await.expect(
+        let block_index = self.execution_api.get_latest_block_number().await.expect(
             "Error launching bridge in latest mode. Unable to get latest block from provider.",
         );
+        match &self.options.el_ws_provider {
+            Some(ws_url) => self.follow_new_heads(ws_url, block_index).await,
+            None => self.poll_latest(block_index).await,
+        }
+    }
+
+    // Devops nodes don't have websockets available, so we can't actually poll the latest block.
+    // Instead we loop on a short interval and fetch the latest blocks not yet served.
+    async fn poll_latest(&self, mut block_index: u64) {
         loop {
             sleep(Duration::from_secs(LATEST_BLOCK_POLL_RATE)).await;
             let latest_block = match self.execution_api.get_latest_block_number().await {
@@ -131,20 +178,67 @@ impl HistoryBridge {
                     end: latest_block + 1,
                 };
                 info!("Discovered new blocks to gossip: {gossip_range:?}");
-                for height in gossip_range.clone() {
-                    Self::spawn_serve_full_block(
-                        height,
-                        None,
-                        self.portal_clients.clone(),
-                        self.execution_api.clone(),
-                        None,
-                    );
-                }
+                self.serve_latest_blocks(gossip_range.clone().map(|height| (height, None)));
                 block_index = gossip_range.end;
             }
         }
     }
 
+    // Follows the new heads announced by a trusted execution layer node, serving each block as
+    // soon as it's announced along with any blocks skipped since the previous head. The announced
+    // block is fetched by its hash, so it's the block that's served even if the provider reorgs
+    // again before it's fetched. A head announced at a height that was already served replaces
+    // the block at that height after a reorg, so it's served again. The subscription is
+    // re-established whenever it ends.
+    async fn follow_new_heads(&self, ws_url: &Url, mut block_index: u64) {
+        loop {
+            match subscribe_new_heads(ws_url).await {
+                Ok((_client, mut subscription)) => {
+                    info!("Subscribed to new heads from: {ws_url}");
+                    while let Some(head) = subscription.next().await {
+                        let head = match head {
+                            Ok(head) => head,
+                            Err(err) => {
+                                warn!("error receiving new head: {err:?}");
+                                break;
+                            }
+                        };
+                        let head_hash = head.hash();
+                        info!(
+                            "Discovered new head to gossip: #{} {head_hash:?}",
+                            head.number
+                        );
+                        self.serve_latest_blocks(new_head_blocks(
+                            head.number,
+                            head_hash,
+                            block_index,
+                        ));
+                        block_index = block_index.max(head.number + 1);
+                    }
+                    warn!("New heads subscription ended");
+                }
+                Err(msg) => warn!("error subscribing to new heads: {msg:?}"),
+            }
+            sleep(Duration::from_secs(LATEST_BLOCK_POLL_RATE)).await;
+        }
+    }
+
+    /// Serves blocks by their height, and by their hash if it's known.
+    fn serve_latest_blocks(&self, blocks: impl IntoIterator<Item = (u64, Option<H256>)>) {
+        for (height, block_hash) in blocks {
+            Self::spawn_serve_full_block(
+                height,
+                block_hash,
+                None,
+                None,
+                self.gossiper.clone(),
+                self.execution_api.clone(),
+                None,
+                None,
+            );
+        }
+    }
+
     async fn launch_backfill(&self) {
         let latest_block = self.execution_api.get_latest_block_number().await.expect(
             "Error launching bridge in backfill mode. Unable to get latest block from provider.",
@@ -181,6 +275,26 @@ impl HistoryBridge {
                         Please specify a starting block/epoch that begins before the current block."
             );
         }
+        let mut start_block = start_block;
+        let checkpoint = match &self.options.checkpoint_path {
+            Some(path) => {
+                let (checkpoint, resume_from) = BackfillCheckpoint::open(path.clone())
+                    .expect("Error launching bridge in backfill mode. Unable to open checkpoint.");
+                match resume_from {
+                    Some(resume_from) if resume_from >= end_block => {
+                        info!("Backfill already completed up to block {resume_from}, according to the checkpoint");
+                        return;
+                    }
+                    Some(resume_from) if resume_from > start_block => {
+                        info!("Resuming backfill from block {resume_from}, according to the checkpoint");
+                        start_block = resume_from;
+                    }
+                    _ => {}
+                }
+                Some(Arc::new(checkpoint))
+            }
+            None => None,
+        };
         // initialize current_epoch_index as an impossible value u64::MAX so that
         // epoch_acc gets set on the first iteration of the loop
         let mut current_epoch_index = u64::MAX;
@@ -195,13 +309,32 @@ impl HistoryBridge {
 
         info!("fetching headers in range: {gossip_range:?}");
         let mut epoch_acc = None;
+        let mut era1_epoch = None;
         for height in gossip_range {
             // Using epoch_size chunks & epoch boundaries ensures that every
             // "chunk" shares an epoch accumulator avoiding the need to
             // look up the epoch acc on a header by header basis
             if height <= MERGE_BLOCK_NUMBER && current_epoch_index != height / EPOCH_SIZE {
                 current_epoch_index = height / EPOCH_SIZE;
-                epoch_acc = match self.get_epoch_acc(current_epoch_index).await {
+                era1_epoch = match &self.options.era1_dir {
+                    Some(era1_dir) => match self.get_era1_epoch(era1_dir, current_epoch_index) {
+                        Ok(val) => val.map(Arc::new),
+                        Err(msg) => {
+                            warn!("Unable to read era1 archive for gossip range: {current_epoch_index}. Fetching blocks from provider: {msg:?}");
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                let epoch_acc_result = match &era1_epoch {
+                    Some(era1_epoch) => {
+                        self.gossip_epoch_acc(current_epoch_index, &era1_epoch.epoch_acc)
+                            .await;
+                        Ok(era1_epoch.epoch_acc.clone())
+                    }
+                    None => self.get_epoch_acc(current_epoch_index).await,
+                };
+                epoch_acc = match epoch_acc_result {
                     Ok(val) => Some(val),
                     Err(msg) => {
                         warn!("Unable to find epoch acc for gossip range: {current_epoch_index}. Skipping iteration: {msg:?}");
@@ -210,65 +343,148 @@ impl HistoryBridge {
                 };
             } else if height > MERGE_BLOCK_NUMBER {
                 epoch_acc = None;
+                era1_epoch = None;
             }
             let permit = gossip_send_semaphore.clone().acquire_owned().await.expect(
                 "acquire_owned() can only error on semaphore close, this should be impossible",
             );
             Self::spawn_serve_full_block(
                 height,
+                None,
                 epoch_acc.clone(),
-                self.portal_clients.clone(),
+                era1_epoch.clone(),
+                self.gossiper.clone(),
                 self.execution_api.clone(),
                 Some(permit),
+                checkpoint.clone(),
             );
         }
+        // Wait for the blocks still being served, so that the backfill doesn't end before them.
+        let _ = gossip_send_semaphore
+            .acquire_many(GOSSIP_LIMIT as u32)
+            .await;
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn spawn_serve_full_block(
         height: u64,
+        block_hash: Option<H256>,
         epoch_acc: Option<Arc<EpochAccumulator>>,
-        portal_clients: Vec<HttpClient>,
+        era1_epoch: Option<Arc<Era1Epoch>>,
+        gossiper: HistoryGossiper,
         execution_api: ExecutionApi,
         permit: Option<OwnedSemaphorePermit>,
+        checkpoint: Option<Arc<BackfillCheckpoint>>,
     ) {
+        if let Some(checkpoint) = &checkpoint {
+            checkpoint.start(height);
+        }
         tokio::spawn(async move {
-            if (timeout(
-                SERVE_BLOCK_TIMEOUT,
-                Self::serve_full_block(height, epoch_acc, portal_clients, execution_api)
-                    .in_current_span(),
-            )
-            .await)
-                .is_err()
-            {
+            let serve_block = async {
+                match era1_epoch {
+                    Some(era1_epoch) => {
+                        Self::serve_era1_block(height, &era1_epoch, &gossiper).await
+                    }
+                    None => {
+                        Self::serve_full_block(
+                            height,
+                            block_hash,
+                            epoch_acc,
+                            gossiper,
+                            execution_api,
+                        )
+                        .await
+                    }
+                }
+            };
+            if (timeout(SERVE_BLOCK_TIMEOUT, serve_block.in_current_span()).await).is_err() {
                 error!("serve_full_block() timed out on height {height}: this is an indication a bug is present")
             };
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.finish(height);
+            }
             if let Some(permit) = permit {
                 drop(permit);
             }
         });
     }
 
+    /// Serves a block from a validated era1 archive, instead of fetching it from the provider.
+    async fn serve_era1_block(
+        height: u64,
+        era1_epoch: &Era1Epoch,
+        gossiper: &HistoryGossiper,
+    ) -> anyhow::Result<()> {
+        info!("Serving block from era1 archive: {height}");
+        let block = usize::try_from(height - era1_epoch.era1.starting_number)
+            .ok()
+            .and_then(|index| era1_epoch.era1.blocks.get(index))
+            .ok_or_else(|| anyhow!("Era1 archive is missing block #{height}"))?;
+        let block_hash = block.header.hash().to_fixed_bytes();
+        let block_stats = Arc::new(Mutex::new(HistoryBlockStats::new(height)));
+
+        let header_with_proof =
+            HistoryBridge::construct_proof(block.header.clone(), &era1_epoch.epoch_acc).await?;
+        debug!("Gossip: Block #{height:?} HeaderWithProof");
+        let _ = gossiper
+            .gossip(
+                HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey { block_hash }),
+                HistoryContentValue::BlockHeaderWithProof(header_with_proof),
+                block_stats.clone(),
+            )
+            .await;
+        // Sleep for 10 seconds to allow headers to saturate network,
+        // since they must be available for body / receipt validation.
+        sleep(Duration::from_secs(HEADER_SATURATION_DELAY)).await;
+        debug!("Gossip: Block #{height:?} BlockBody");
+        let _ = gossiper
+            .gossip(
+                HistoryContentKey::BlockBody(BlockBodyKey { block_hash }),
+                HistoryContentValue::BlockBody(BlockBody::Legacy(block.body.clone())),
+                block_stats.clone(),
+            )
+            .await;
+        debug!("Gossip: Block #{height:?} Receipts");
+        let _ = gossiper
+            .gossip(
+                HistoryContentKey::BlockReceipts(BlockReceiptsKey { block_hash }),
+                HistoryContentValue::Receipts(block.receipts.clone()),
+                block_stats.clone(),
+            )
+            .await;
+        if let Ok(stats) = block_stats.lock() {
+            stats.report();
+        } else {
+            warn!("Error displaying history gossip stats. Unable to acquire lock.");
+        }
+        Ok(())
+    }
+
     async fn serve_full_block(
         height: u64,
+        block_hash: Option<H256>,
         epoch_acc: Option<Arc<EpochAccumulator>>,
-        portal_clients: Vec<HttpClient>,
+        gossiper: HistoryGossiper,
         execution_api: ExecutionApi,
     ) -> anyhow::Result<()> {
         info!("Serving block: {height}");
-        let mut full_header = execution_api.get_header(height).await?;
+        let mut full_header = match block_hash {
+            Some(block_hash) => execution_api.get_header_by_hash(block_hash).await?,
+            None => execution_api.get_header(height).await?,
+        };
         if full_header.header.number <= MERGE_BLOCK_NUMBER {
             full_header.epoch_acc = epoch_acc;
         }
         let block_stats = Arc::new(Mutex::new(HistoryBlockStats::new(
             full_header.header.number,
         )));
-        HistoryBridge::gossip_header(&full_header, &portal_clients, block_stats.clone()).await?;
+        HistoryBridge::gossip_header(&full_header, &gossiper, block_stats.clone()).await?;
         // Sleep for 10 seconds to allow headers to saturate network,
         // since they must be available for body / receipt validation.
         sleep(Duration::from_secs(HEADER_SATURATION_DELAY)).await;
         HistoryBridge::construct_and_gossip_block_body(
             &full_header,
-            &portal_clients,
+            &gossiper,
             &execution_api,
             block_stats.clone(),
         )
@@ -277,7 +493,7 @@ impl HistoryBridge {
 
         HistoryBridge::construct_and_gossip_receipt(
             &full_header,
-            &portal_clients,
+            &gossiper,
             &execution_api,
             block_stats.clone(),
         )
@@ -293,7 +509,7 @@ impl HistoryBridge {
 
     async fn gossip_header(
         full_header: &FullHeader,
-        portal_clients: &Vec<HttpClient>,
+        gossiper: &HistoryGossiper,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
     ) -> anyhow::Result<()> {
         debug!("Serving header: {}", full_header.header.number);
@@ -335,11 +551,34 @@ impl HistoryBridge {
             "Gossip: Block #{:?} HeaderWithProof",
             full_header.header.number
         );
-        let _ =
-            gossip_history_content(portal_clients, content_key, content_value, block_stats).await;
+        let _ = gossiper
+            .gossip(content_key, content_value, block_stats)
+            .await;
         Ok(())
     }
 
+    /// Attempt to read the era1 archive of an epoch from the era1 directory provided via cli arg,
+    /// validating it against the master accumulator. Returns `None` if the directory holds no
+    /// archive for the epoch.
+    fn get_era1_epoch(
+        &self,
+        era1_dir: &Path,
+        epoch_index: u64,
+    ) -> anyhow::Result<Option<Era1Epoch>> {
+        let Some(path) = find_era1_file(era1_dir, epoch_index)? else {
+            return Ok(None);
+        };
+        let era1 = Era1::decode(&fs::read(&path)?)
+            .map_err(|err| anyhow!("Invalid era1 archive {}: {err}", path.display()))?;
+        let epoch_acc = era1
+            .validate(&self.header_oracle.master_acc)
+            .map_err(|err| anyhow!("Invalid era1 archive {}: {err}", path.display()))?;
+        Ok(Some(Era1Epoch {
+            era1,
+            epoch_acc: Arc::new(epoch_acc),
+        }))
+    }
+
     /// Attempt to lookup an epoch accumulator from local portal-accumulators path provided via cli
     /// arg. Gossip the epoch accumulator if found.
     async fn get_epoch_acc(&self, epoch_index: u64) -> anyhow::Result<Arc<EpochAccumulator>> {
@@ -359,23 +598,25 @@ impl HistoryBridge {
             }
         };
         // Gossip epoch acc to network if found locally
+        self.gossip_epoch_acc(epoch_index, &local_epoch_acc).await;
+        Ok(Arc::new(local_epoch_acc))
+    }
+
+    async fn gossip_epoch_acc(&self, epoch_index: u64, epoch_acc: &EpochAccumulator) {
+        let epoch_hash = self.header_oracle.master_acc.historical_epochs[epoch_index as usize];
         let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey { epoch_hash });
-        let content_value = HistoryContentValue::EpochAccumulator(local_epoch_acc.clone());
+        let content_value = HistoryContentValue::EpochAccumulator(epoch_acc.clone());
         // create unique stats for epoch accumulator, since it's rarely gossiped
         let block_stats = Arc::new(Mutex::new(HistoryBlockStats::new(epoch_index * EPOCH_SIZE)));
-        let _ = gossip_history_content(
-            &self.portal_clients,
-            content_key,
-            content_value,
-            block_stats,
-        )
-        .await;
-        Ok(Arc::new(local_epoch_acc))
+        let _ = self
+            .gossiper
+            .gossip(content_key, content_value, block_stats)
+            .await;
     }
 
     async fn construct_and_gossip_receipt(
         full_header: &FullHeader,
-        portal_clients: &Vec<HttpClient>,
+        gossiper: &HistoryGossiper,
         execution_api: &ExecutionApi,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
     ) -> anyhow::Result<()> {
@@ -404,14 +645,15 @@ impl HistoryBridge {
         });
         let content_value = HistoryContentValue::Receipts(receipts);
         debug!("Gossip: Block #{:?} Receipts", full_header.header.number,);
-        let _ =
-            gossip_history_content(portal_clients, content_key, content_value, block_stats).await;
+        let _ = gossiper
+            .gossip(content_key, content_value, block_stats)
+            .await;
         Ok(())
     }
 
     async fn construct_and_gossip_block_body(
         full_header: &FullHeader,
-        portal_clients: &Vec<HttpClient>,
+        gossiper: &HistoryGossiper,
         execution_api: &ExecutionApi,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
     ) -> anyhow::Result<()> {
@@ -448,8 +690,9 @@ impl HistoryBridge {
         });
         let content_value = HistoryContentValue::BlockBody(block_body);
         debug!("Gossip: Block #{:?} BlockBody", full_header.header.number);
-        let _ =
-            gossip_history_content(portal_clients, content_key, content_value, block_stats).await;
+        let _ = gossiper
+            .gossip(content_key, content_value, block_stats)
+            .await;
         Ok(())
     }
 
@@ -463,3 +706,41 @@ impl HistoryBridge {
         Ok(HeaderWithProof { header, proof })
     }
 }
+
+/// Returns the blocks to serve for a new head at `head_number`, while `block_index` is the next
+/// height to serve: any skipped blocks by their height, followed by the head by its hash.
+fn new_head_blocks(
+    head_number: u64,
+    head_hash: H256,
+    block_index: u64,
+) -> Vec<(u64, Option<H256>)> {
+    (block_index..head_number)
+        .map(|height| (height, None))
+        .chain([(head_number, Some(head_hash))])
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new_heads_are_served_by_their_hash() {
+        let head_hash = H256::random();
+        assert_eq!(
+            new_head_blocks(100, head_hash, 100),
+            vec![(100, Some(head_hash))]
+        );
+        // Blocks skipped since the previous head are served by their height.
+        assert_eq!(
+            new_head_blocks(102, head_hash, 100),
+            vec![(100, None), (101, None), (102, Some(head_hash))]
+        );
+        // A reorged head replaces the block that was served at its height.
+        assert_eq!(
+            new_head_blocks(98, head_hash, 101),
+            vec![(98, Some(head_hash))]
+        );
+    }
+}
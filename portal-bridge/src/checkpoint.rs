@@ -0,0 +1,126 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use tracing::warn;
+
+/// Records the progress of a backfill in a file, so that an interrupted backfill resumes from the
+/// first block that wasn't served.
+///
+/// Blocks are served concurrently and may finish out of order, so the checkpoint is the lowest
+/// block that was started but hasn't finished yet, or the block after the last started block once
+/// all started blocks have finished.
+#[derive(Debug)]
+pub struct BackfillCheckpoint {
+    path: PathBuf,
+    progress: Mutex<Progress>,
+}
+
+#[derive(Debug, Default)]
+struct Progress {
+    in_flight: BTreeSet<u64>,
+    next_block: u64,
+    checkpoint: Option<u64>,
+}
+
+impl BackfillCheckpoint {
+    /// Opens the checkpoint file at `path`, returning the checkpoint along with the block to
+    /// resume from, if an earlier backfill recorded one.
+    pub fn open(path: PathBuf) -> anyhow::Result<(Self, Option<u64>)> {
+        let resume_from = match fs::read_to_string(&path) {
+            Ok(contents) => Some(contents.trim().parse().map_err(|err| {
+                anyhow!("Invalid backfill checkpoint at {}: {err}", path.display())
+            })?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        let checkpoint = Self {
+            path,
+            progress: Mutex::new(Progress {
+                checkpoint: resume_from,
+                ..Default::default()
+            }),
+        };
+        Ok((checkpoint, resume_from))
+    }
+
+    /// Records that serving `height` started.
+    pub fn start(&self, height: u64) {
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.in_flight.insert(height);
+            progress.next_block = progress.next_block.max(height + 1);
+        }
+    }
+
+    /// Records that serving `height` finished, moving the checkpoint forward if every block
+    /// before it finished as well.
+    pub fn finish(&self, height: u64) {
+        let Ok(mut progress) = self.progress.lock() else {
+            warn!("Error updating backfill checkpoint. Unable to acquire lock.");
+            return;
+        };
+        progress.in_flight.remove(&height);
+        let checkpoint = match progress.in_flight.first() {
+            Some(first_in_flight) => *first_in_flight,
+            None => progress.next_block,
+        };
+        if progress.checkpoint == Some(checkpoint) {
+            return;
+        }
+        match write_checkpoint(&self.path, checkpoint) {
+            Ok(()) => progress.checkpoint = Some(checkpoint),
+            Err(err) => warn!("Error writing backfill checkpoint: {err:?}"),
+        }
+    }
+}
+
+/// Writes the checkpoint to a temporary file first, so that an interrupted write doesn't leave a
+/// truncated checkpoint behind.
+fn write_checkpoint(path: &Path, checkpoint: u64) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, checkpoint.to_string())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_resumes_from_first_unfinished_block() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("checkpoint");
+
+        let (checkpoint, resume_from) = BackfillCheckpoint::open(path.clone()).unwrap();
+        assert_eq!(resume_from, None);
+        for height in 10..14 {
+            checkpoint.start(height);
+        }
+        checkpoint.finish(11);
+        checkpoint.finish(12);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "10");
+        checkpoint.finish(10);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "13");
+        drop(checkpoint);
+
+        let (checkpoint, resume_from) = BackfillCheckpoint::open(path.clone()).unwrap();
+        assert_eq!(resume_from, Some(13));
+        checkpoint.start(13);
+        checkpoint.finish(13);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "14");
+    }
+
+    #[test]
+    fn test_invalid_checkpoint() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("checkpoint");
+        fs::write(&path, "block 10").unwrap();
+        assert!(BackfillCheckpoint::open(path).is_err());
+    }
+}
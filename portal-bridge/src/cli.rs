@@ -1,4 +1,4 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{num::NonZeroU32, path::PathBuf, str::FromStr};
 
 use clap::{Parser, Subcommand};
 use ethereum_types::H256;
@@ -9,7 +9,9 @@ use crate::{
     client_handles::{fluffy_handle, trin_handle},
     types::{mode::BridgeMode, network::NetworkKind},
 };
-use ethportal_api::types::cli::check_private_key_length;
+use ethportal_api::types::{
+    cli::check_private_key_length, portal_wire::MAX_CONTENT_KEYS_PER_OFFER,
+};
 
 // max value of 16 b/c...
 // - reliably calculate spaced private keys in a reasonable time
@@ -86,6 +88,45 @@ pub struct BridgeConfig {
     )]
     pub el_provider: Provider,
 
+    #[arg(
+        long = "el-ws-provider",
+        value_parser = check_ws_url,
+        help = "Websocket url of a trusted execution layer node (eg. a local geth) to subscribe to new heads from in latest mode, instead of polling the el provider for new blocks"
+    )]
+    pub el_ws_provider: Option<Url>,
+
+    #[arg(
+        long = "era1-dir",
+        help = "Directory of era1 archives to serve pre-merge blocks from in backfill mode, instead of fetching them from the el provider"
+    )]
+    pub era1_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "checkpoint-path",
+        help = "File to record the progress of a backfill in, so that an interrupted backfill resumes from the first block that wasn't served"
+    )]
+    pub checkpoint_path: Option<PathBuf>,
+
+    #[arg(
+        long = "gossip-rate-limit",
+        help = "Maximum number of history content items to gossip per second. Unlimited by default"
+    )]
+    pub gossip_rate_limit: Option<NonZeroU32>,
+
+    #[arg(
+        long = "gossip-batch-size",
+        default_value = "1",
+        value_parser = check_gossip_batch_size,
+        help = "Number of history content items to gossip together, offering each peer all of the items it's interested in with a single OFFER request (max 8). Content that a batch doesn't transfer to any peer is gossiped again on its own"
+    )]
+    pub gossip_batch_size: usize,
+
+    #[arg(
+        long = "gossip-retry-queue-path",
+        help = "File to queue history content that failed to be gossiped in, to gossip it again with backoff until it's propagated, also after restarts"
+    )]
+    pub gossip_retry_queue_path: Option<PathBuf>,
+
     #[arg(
         long = "cl-provider",
         default_value_t = Provider::PandaOps,
@@ -103,6 +144,29 @@ fn check_node_count(val: &str) -> Result<u8, String> {
     }
 }
 
+fn check_gossip_batch_size(val: &str) -> Result<usize, String> {
+    let batch_size: usize = val
+        .parse()
+        .map_err(|_| "Invalid gossip batch size".to_string())?;
+    if batch_size > 0 && batch_size <= MAX_CONTENT_KEYS_PER_OFFER {
+        Ok(batch_size)
+    } else {
+        Err(format!(
+            "Gossip batch size must be between 1 and {MAX_CONTENT_KEYS_PER_OFFER}"
+        ))
+    }
+}
+
+fn check_ws_url(val: &str) -> Result<Url, String> {
+    let url = Url::parse(val).map_err(|err| format!("Invalid url: {err}"))?;
+    match url.scheme() {
+        "ws" | "wss" => Ok(url),
+        scheme => Err(format!(
+            "Expected a ws:// or wss:// url, got a {scheme}:// url"
+        )),
+    }
+}
+
 type ParseError = &'static str;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -204,12 +268,131 @@ mod test {
         assert_eq!(bridge_config.epoch_acc_path, PathBuf::from(EPOCH_ACC_PATH));
         assert_eq!(bridge_config.el_provider, Provider::PandaOps);
         assert_eq!(bridge_config.cl_provider, Provider::PandaOps);
+        assert_eq!(bridge_config.el_ws_provider, None);
+        assert_eq!(bridge_config.era1_dir, None);
+        assert_eq!(bridge_config.checkpoint_path, None);
+        assert_eq!(bridge_config.gossip_rate_limit, None);
+        assert_eq!(bridge_config.gossip_batch_size, 1);
+        assert_eq!(bridge_config.gossip_retry_queue_path, None);
         assert_eq!(
             bridge_config.network,
             vec![NetworkKind::History, NetworkKind::Beacon]
         );
     }
 
+    #[test]
+    fn test_bridge_config_with_resumable_era1_backfill() {
+        let bridge_config = BridgeConfig::parse_from([
+            "bridge",
+            "--executable-path",
+            "path/to/executable",
+            "--epoch-accumulator-path",
+            "path/to/epoch/accumulator",
+            "--mode",
+            "backfill:r100-200",
+            "--era1-dir",
+            "path/to/era1",
+            "--checkpoint-path",
+            "path/to/checkpoint",
+            "trin",
+        ]);
+        assert_eq!(
+            bridge_config.mode,
+            BridgeMode::Backfill(ModeType::BlockRange(100, 200))
+        );
+        assert_eq!(bridge_config.era1_dir, Some(PathBuf::from("path/to/era1")));
+        assert_eq!(
+            bridge_config.checkpoint_path,
+            Some(PathBuf::from("path/to/checkpoint"))
+        );
+    }
+
+    #[test]
+    fn test_bridge_config_with_gossip_throttling() {
+        let bridge_config = BridgeConfig::parse_from([
+            "bridge",
+            "--executable-path",
+            "path/to/executable",
+            "--epoch-accumulator-path",
+            "path/to/epoch/accumulator",
+            "--gossip-rate-limit",
+            "50",
+            "--gossip-batch-size",
+            "8",
+            "trin",
+        ]);
+        assert_eq!(bridge_config.gossip_rate_limit, NonZeroU32::new(50));
+        assert_eq!(bridge_config.gossip_batch_size, 8);
+    }
+
+    #[test]
+    fn test_bridge_config_with_gossip_retry_queue() {
+        let bridge_config = BridgeConfig::parse_from([
+            "bridge",
+            "--executable-path",
+            "path/to/executable",
+            "--epoch-accumulator-path",
+            "path/to/epoch/accumulator",
+            "--gossip-retry-queue-path",
+            "path/to/retry_queue.log",
+            "trin",
+        ]);
+        assert_eq!(
+            bridge_config.gossip_retry_queue_path,
+            Some(PathBuf::from("path/to/retry_queue.log"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Gossip batch size must be between 1 and 8")]
+    fn test_gossip_batch_size_must_fit_in_offer() {
+        BridgeConfig::try_parse_from([
+            "bridge",
+            "--executable-path",
+            "path/to/executable",
+            "--epoch-accumulator-path",
+            "path/to/epoch/accumulator",
+            "--gossip-batch-size",
+            "9",
+            "trin",
+        ])
+        .unwrap();
+    }
+
+    #[test]
+    fn test_bridge_config_with_el_ws_provider() {
+        let bridge_config = BridgeConfig::parse_from([
+            "bridge",
+            "--executable-path",
+            "path/to/executable",
+            "--epoch-accumulator-path",
+            "path/to/epoch/accumulator",
+            "--el-ws-provider",
+            "ws://127.0.0.1:8546",
+            "trin",
+        ]);
+        assert_eq!(
+            bridge_config.el_ws_provider,
+            Some(Url::parse("ws://127.0.0.1:8546").unwrap())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a ws:// or wss:// url")]
+    fn test_el_ws_provider_requires_websocket_url() {
+        BridgeConfig::try_parse_from([
+            "bridge",
+            "--executable-path",
+            "path/to/executable",
+            "--epoch-accumulator-path",
+            "path/to/epoch/accumulator",
+            "--el-ws-provider",
+            "http://127.0.0.1:8545",
+            "trin",
+        ])
+        .unwrap();
+    }
+
     #[test]
     fn test_bridge_config_with_epoch() {
         const NODE_COUNT: &str = "1";
@@ -1,10 +1,20 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+};
 
+use anyhow::anyhow;
 use jsonrpsee::http_client::HttpClient;
-use tokio::time::{sleep, Duration};
-use tracing::{debug, warn, Instrument};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{interval, sleep, timeout_at, Duration, Instant, Interval, MissedTickBehavior},
+};
+use tracing::{debug, info, warn, Instrument};
 
-use crate::stats::{BeaconSlotStats, HistoryBlockStats, StatsReporter};
+use crate::{
+    retry_queue::GossipRetryQueue,
+    stats::{BeaconSlotStats, HistoryBlockStats, StatsReporter},
+};
 use ethportal_api::{
     jsonrpsee::core::Error, types::portal::TraceGossipInfo, BeaconContentKey, BeaconContentValue,
     BeaconNetworkApiClient, HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient,
@@ -13,6 +23,302 @@ use ethportal_api::{
 
 const GOSSIP_RETRY_COUNT: u64 = 3;
 const RETRY_AFTER: Duration = Duration::from_secs(15);
+/// The longest time that content waits for its batch to fill up before the batch is gossiped.
+const GOSSIP_BATCH_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the retry queue is checked for content that is due to be gossiped again.
+const RETRY_QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Limits the rate at which content is gossiped, evenly spacing out the gossip of every item.
+#[derive(Clone, Debug)]
+pub struct GossipRateLimiter {
+    interval: Arc<tokio::sync::Mutex<Interval>>,
+}
+
+impl GossipRateLimiter {
+    pub fn new(items_per_second: NonZeroU32) -> Self {
+        let period = Duration::from_secs(1) / items_per_second.get();
+        let mut interval = interval(period.max(Duration::from_nanos(1)));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            interval: Arc::new(tokio::sync::Mutex::new(interval)),
+        }
+    }
+
+    /// Waits until `count` more items can be gossiped.
+    pub async fn acquire(&self, count: usize) {
+        let mut interval = self.interval.lock().await;
+        for _ in 0..count {
+            interval.tick().await;
+        }
+    }
+}
+
+/// Gossips history content through the portal clients of the bridge, throttled by the rate
+/// limiter. Content that fails to be propagated is queued in the retry queue, if any, which is
+/// gossiped again in the background.
+///
+/// If batching is enabled, content is collected into batches that are gossiped with a single
+/// request, so that every peer is offered all of the content of a batch that it's interested in
+/// together. Content that the batch doesn't transfer to any peer is gossiped again on its own,
+/// just like content that isn't batched.
+#[derive(Clone, Debug)]
+pub struct HistoryGossiper {
+    portal_clients: Vec<HttpClient>,
+    rate_limiter: Option<GossipRateLimiter>,
+    batch_tx: Option<mpsc::Sender<BatchItem>>,
+    retry_queue: Option<Arc<GossipRetryQueue>>,
+}
+
+/// History content waiting to be gossiped in a batch.
+struct BatchItem {
+    content_key: HistoryContentKey,
+    content_value: HistoryContentValue,
+    block_stats: Arc<Mutex<HistoryBlockStats>>,
+    /// Notified once the content is gossiped.
+    done_tx: oneshot::Sender<()>,
+}
+
+impl HistoryGossiper {
+    pub fn new(
+        portal_clients: Vec<HttpClient>,
+        rate_limiter: Option<GossipRateLimiter>,
+        batch_size: usize,
+        retry_queue: Option<Arc<GossipRetryQueue>>,
+    ) -> Self {
+        if let Some(retry_queue) = &retry_queue {
+            tokio::spawn(
+                retry_history_gossip(
+                    portal_clients.clone(),
+                    rate_limiter.clone(),
+                    retry_queue.clone(),
+                )
+                .in_current_span(),
+            );
+        }
+        let batch_tx = (batch_size > 1).then(|| {
+            let (batch_tx, batch_rx) = mpsc::channel(batch_size);
+            tokio::spawn(
+                gossip_history_batches(
+                    portal_clients.clone(),
+                    rate_limiter.clone(),
+                    retry_queue.clone(),
+                    batch_size,
+                    batch_rx,
+                )
+                .in_current_span(),
+            );
+            batch_tx
+        });
+        Self {
+            portal_clients,
+            rate_limiter,
+            batch_tx,
+            retry_queue,
+        }
+    }
+
+    /// Gossip the given content key / value, in the next batch if batching is enabled. Returns once
+    /// the content is gossiped and reported in `block_stats`.
+    pub async fn gossip(
+        &self,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+        block_stats: Arc<Mutex<HistoryBlockStats>>,
+    ) -> anyhow::Result<()> {
+        if let Some(batch_tx) = &self.batch_tx {
+            let (done_tx, done_rx) = oneshot::channel();
+            let item = BatchItem {
+                content_key,
+                content_value,
+                block_stats,
+                done_tx,
+            };
+            let stopped = || anyhow!("History gossip batching stopped");
+            batch_tx.send(item).await.map_err(|_| stopped())?;
+            return done_rx.await.map_err(|_| stopped());
+        }
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(1).await;
+        }
+        gossip_or_queue_retry(
+            &self.portal_clients,
+            self.retry_queue.as_deref(),
+            content_key,
+            content_value,
+            block_stats,
+        )
+        .await
+    }
+}
+
+/// Gossips the given content on its own, and queues it in the retry queue, if any, if it isn't
+/// propagated.
+async fn gossip_or_queue_retry(
+    portal_clients: &Vec<HttpClient>,
+    retry_queue: Option<&GossipRetryQueue>,
+    content_key: HistoryContentKey,
+    content_value: HistoryContentValue,
+    block_stats: Arc<Mutex<HistoryBlockStats>>,
+) -> anyhow::Result<()> {
+    let propagated = gossip_history_content(
+        portal_clients,
+        content_key.clone(),
+        content_value.clone(),
+        block_stats,
+    )
+    .await?;
+    if !propagated {
+        if let Some(retry_queue) = retry_queue {
+            retry_queue.push(content_key, content_value);
+        }
+    }
+    Ok(())
+}
+
+/// Gossips the content of the retry queue again once it's due, until it's propagated.
+async fn retry_history_gossip(
+    portal_clients: Vec<HttpClient>,
+    rate_limiter: Option<GossipRateLimiter>,
+    retry_queue: Arc<GossipRetryQueue>,
+) {
+    if !retry_queue.is_empty() {
+        info!(
+            "Retrying gossip of {} queued history content items",
+            retry_queue.len()
+        );
+    }
+    let mut interval = interval(RETRY_QUEUE_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        for (content_key, content_value) in retry_queue.due() {
+            if let Some(rate_limiter) = &rate_limiter {
+                rate_limiter.acquire(1).await;
+            }
+            // retried content isn't reported in the stats of any block
+            let block_stats = Arc::new(Mutex::new(HistoryBlockStats::new(0)));
+            match gossip_history_content(
+                &portal_clients,
+                content_key.clone(),
+                content_value,
+                block_stats,
+            )
+            .await
+            {
+                Ok(true) => retry_queue.remove(&content_key),
+                Ok(false) => retry_queue.retry_failed(&content_key),
+                Err(err) => {
+                    warn!("Failed to retry gossip of history content: {err:?}");
+                    retry_queue.retry_failed(&content_key);
+                }
+            }
+        }
+    }
+}
+
+/// Gossips the content received on `batch_rx` in batches of up to `batch_size` items.
+///
+/// The gossip of every item of a batch is reported in the stats of its block, if the batch
+/// transferred the item to any peer. Other items are gossiped again on their own, in the
+/// background, with the retries of content that isn't batched.
+async fn gossip_history_batches(
+    portal_clients: Vec<HttpClient>,
+    rate_limiter: Option<GossipRateLimiter>,
+    retry_queue: Option<Arc<GossipRetryQueue>>,
+    batch_size: usize,
+    mut batch_rx: mpsc::Receiver<BatchItem>,
+) {
+    while let Some(batch) = collect_batch(&mut batch_rx, batch_size).await {
+        if let Some(rate_limiter) = &rate_limiter {
+            rate_limiter.acquire(batch.len()).await;
+        }
+        let content_items: Vec<(HistoryContentKey, HistoryContentValue)> = batch
+            .iter()
+            .map(|item| (item.content_key.clone(), item.content_value.clone()))
+            .collect();
+        // The traces of the gossip of every item, by each client.
+        let mut item_traces: Vec<Vec<TraceGossipInfo>> = vec![vec![]; batch.len()];
+        for client in &portal_clients {
+            match HistoryNetworkApiClient::trace_gossip_batch(client, content_items.clone()).await {
+                Ok(traces) => {
+                    for (item_traces, trace) in item_traces.iter_mut().zip(traces) {
+                        item_traces.push(trace);
+                    }
+                }
+                Err(err) => warn!("Failed to gossip batch of history content: {err:?}"),
+            }
+        }
+        debug!("Gossiped batch of {} history content items", batch.len());
+
+        for (item, traces) in batch.into_iter().zip(item_traces) {
+            match batched_gossip_results(traces) {
+                Some(results) => {
+                    if let Ok(mut data) = item.block_stats.lock() {
+                        data.update(item.content_key, results.into());
+                    } else {
+                        warn!("Error updating history gossip stats. Unable to acquire lock.");
+                    }
+                    let _ = item.done_tx.send(());
+                }
+                None => {
+                    let portal_clients = portal_clients.clone();
+                    let rate_limiter = rate_limiter.clone();
+                    let retry_queue = retry_queue.clone();
+                    tokio::spawn(
+                        async move {
+                            if let Some(rate_limiter) = &rate_limiter {
+                                rate_limiter.acquire(1).await;
+                            }
+                            if let Err(err) = gossip_or_queue_retry(
+                                &portal_clients,
+                                retry_queue.as_deref(),
+                                item.content_key,
+                                item.content_value,
+                                item.block_stats,
+                            )
+                            .await
+                            {
+                                warn!("Failed to gossip history content: {err:?}");
+                            }
+                            let _ = item.done_tx.send(());
+                        }
+                        .in_current_span(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Receives the next batch of up to `batch_size` items from `batch_rx`, which is complete once
+/// it's full or `GOSSIP_BATCH_FLUSH_INTERVAL` after its first item. Returns `None` once every
+/// sender is dropped.
+async fn collect_batch<T>(batch_rx: &mut mpsc::Receiver<T>, batch_size: usize) -> Option<Vec<T>> {
+    let mut batch = vec![batch_rx.recv().await?];
+    let flush_at = Instant::now() + GOSSIP_BATCH_FLUSH_INTERVAL;
+    while batch.len() < batch_size {
+        match timeout_at(flush_at, batch_rx.recv()).await {
+            Ok(Some(item)) => batch.push(item),
+            _ => break,
+        }
+    }
+    Some(batch)
+}
+
+/// Returns the gossip results of a batched content item, from the traces of every client, or
+/// `None` if the item wasn't transferred to any peer.
+fn batched_gossip_results(
+    traces: Vec<TraceGossipInfo>,
+) -> Option<Vec<Result<(Vec<TraceGossipInfo>, u64), Error>>> {
+    traces
+        .iter()
+        .any(|trace| !trace.transferred.is_empty())
+        .then(|| {
+            traces
+                .into_iter()
+                .map(|trace| Ok((vec![trace], 0)))
+                .collect()
+        })
+}
 
 /// Gossip any given content key / value to the history network.
 pub async fn gossip_beacon_content(
@@ -84,12 +390,15 @@ async fn beacon_trace_gossip(
 }
 
 /// Gossip any given content key / value to the history network.
+///
+/// Returns whether the content was propagated through any of the clients: transferred to at least
+/// one peer, or found on the network.
 pub async fn gossip_history_content(
     portal_clients: &Vec<HttpClient>,
     content_key: HistoryContentKey,
     content_value: HistoryContentValue,
     block_stats: Arc<Mutex<HistoryBlockStats>>,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<bool> {
     let mut results: Vec<Result<(Vec<TraceGossipInfo>, u64), Error>> = vec![];
     for client in portal_clients {
         let client = client.clone();
@@ -101,12 +410,15 @@ pub async fn gossip_history_content(
         .await?;
         results.push(result);
     }
+    let propagated = results
+        .iter()
+        .any(|result| matches!(result, Ok((_, retries)) if *retries < GOSSIP_RETRY_COUNT));
     if let Ok(mut data) = block_stats.lock() {
         data.update(content_key, results.into());
     } else {
         warn!("Error updating history gossip stats. Unable to acquire lock.");
     }
-    Ok(())
+    Ok(propagated)
 }
 
 // todo why doesn't history return PossibleHistoryContentValue?
@@ -153,3 +465,140 @@ async fn history_trace_gossip(
     );
     Ok((traces, retry_count))
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use jsonrpsee::{
+        http_client::HttpClientBuilder,
+        server::{ServerBuilder, ServerHandle},
+        types::ErrorObjectOwned,
+        RpcModule,
+    };
+    use serde_json::Value;
+
+    use ethportal_api::{
+        generate_random_remote_enr,
+        types::execution::{
+            block_body::{BlockBody, BlockBodyLegacy},
+            receipts::Receipts,
+        },
+        BlockBodyKey, BlockReceiptsKey,
+    };
+
+    fn transferred_trace() -> TraceGossipInfo {
+        let enr = generate_random_remote_enr().1.to_base64();
+        TraceGossipInfo {
+            offered: vec![enr.clone()],
+            accepted: vec![enr.clone()],
+            transferred: vec![enr],
+        }
+    }
+
+    /// Starts a portal client that only transfers the first content item of every batch, and
+    /// transfers all content that is gossiped on its own. Returns the client along with the number
+    /// of items that were gossiped on their own.
+    async fn mock_portal_client() -> (HttpClient, Arc<AtomicUsize>, ServerHandle) {
+        let gossip_count = Arc::new(AtomicUsize::new(0));
+        let mut module = RpcModule::new(gossip_count.clone());
+        module
+            .register_method("portal_historyTraceGossipBatch", |params, _| {
+                let content_items: Vec<Value> = params.one()?;
+                let traces: Vec<TraceGossipInfo> = (0..content_items.len())
+                    .map(|index| match index {
+                        0 => transferred_trace(),
+                        _ => TraceGossipInfo {
+                            offered: vec![],
+                            accepted: vec![],
+                            transferred: vec![],
+                        },
+                    })
+                    .collect();
+                Ok::<_, ErrorObjectOwned>(traces)
+            })
+            .unwrap();
+        module
+            .register_method("portal_historyTraceGossip", |_, gossip_count| {
+                gossip_count.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, ErrorObjectOwned>(transferred_trace())
+            })
+            .unwrap();
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap();
+        let handle = server.start(module);
+        let client = HttpClientBuilder::default()
+            .build(format!("http://{address}"))
+            .unwrap();
+        (client, gossip_count, handle)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_spaces_out_gossip() {
+        let rate_limiter = GossipRateLimiter::new(NonZeroU32::new(10).unwrap());
+        let start = Instant::now();
+        rate_limiter.acquire(1).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        rate_limiter.acquire(5).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batches_are_flushed() {
+        let (batch_tx, mut batch_rx) = mpsc::channel(4);
+        for item in 0..3 {
+            batch_tx.send(item).await.unwrap();
+        }
+        let start = Instant::now();
+        assert_eq!(collect_batch(&mut batch_rx, 2).await, Some(vec![0, 1]));
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        // A batch that doesn't fill up is gossiped once the flush interval passed.
+        assert_eq!(collect_batch(&mut batch_rx, 2).await, Some(vec![2]));
+        assert_eq!(start.elapsed(), GOSSIP_BATCH_FLUSH_INTERVAL);
+        drop(batch_tx);
+        assert_eq!(collect_batch(&mut batch_rx, 2).await, None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_batched_gossip_is_reported_and_retried() {
+        let (portal_client, gossip_count, _server) = mock_portal_client().await;
+        let gossiper = HistoryGossiper::new(vec![portal_client], None, 2, None);
+        let block_stats = Arc::new(Mutex::new(HistoryBlockStats::new(1)));
+
+        let (receipts, body) = tokio::join!(
+            gossiper.gossip(
+                HistoryContentKey::BlockReceipts(BlockReceiptsKey {
+                    block_hash: [1; 32],
+                }),
+                HistoryContentValue::Receipts(Receipts {
+                    receipt_list: vec![],
+                }),
+                block_stats.clone(),
+            ),
+            gossiper.gossip(
+                HistoryContentKey::BlockBody(BlockBodyKey {
+                    block_hash: [1; 32],
+                }),
+                HistoryContentValue::BlockBody(BlockBody::Legacy(BlockBodyLegacy {
+                    txs: vec![],
+                    uncles: vec![],
+                })),
+                block_stats.clone(),
+            ),
+        );
+        receipts.unwrap();
+        body.unwrap();
+
+        // The receipts were transferred by the batch, and the block body, which the batch didn't
+        // transfer, was gossiped again on its own.
+        assert_eq!(gossip_count.load(Ordering::SeqCst), 1);
+        let block_stats = block_stats.lock().unwrap();
+        assert_eq!(block_stats.receipts.as_ref().unwrap().transferred.len(), 1);
+        assert_eq!(
+            block_stats.block_body.as_ref().unwrap().transferred.len(),
+            1
+        );
+    }
+}
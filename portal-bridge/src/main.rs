@@ -10,8 +10,13 @@ use ethportal_api::{
 };
 use portal_bridge::{
     api::{consensus::ConsensusApi, execution::ExecutionApi},
-    bridge::{beacon::BeaconBridge, history::HistoryBridge},
+    bridge::{
+        beacon::BeaconBridge,
+        history::{HistoryBridge, HistoryBridgeOptions},
+    },
     cli::BridgeConfig,
+    gossip::GossipRateLimiter,
+    retry_queue::GossipRetryQueue,
     types::network::NetworkKind,
     utils::generate_spaced_private_keys,
 };
@@ -78,6 +83,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Launch History Network portal bridge
     if bridge_config.network.contains(&NetworkKind::History) {
+        let gossip_retry_queue = match &bridge_config.gossip_retry_queue_path {
+            Some(path) => Some(Arc::new(GossipRetryQueue::open(path.clone()).await?)),
+            None => None,
+        };
         let execution_api = ExecutionApi::new(bridge_config.el_provider).await?;
         let bridge_handle = tokio::spawn(async move {
             let master_acc = MasterAccumulator::default();
@@ -89,6 +98,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 portal_clients.expect("Failed to create history JSON-RPC clients"),
                 header_oracle,
                 bridge_config.epoch_acc_path,
+                HistoryBridgeOptions {
+                    el_ws_provider: bridge_config.el_ws_provider,
+                    era1_dir: bridge_config.era1_dir,
+                    checkpoint_path: bridge_config.checkpoint_path,
+                    gossip_rate_limiter: bridge_config
+                        .gossip_rate_limit
+                        .map(GossipRateLimiter::new),
+                    gossip_batch_size: bridge_config.gossip_batch_size,
+                    gossip_retry_queue,
+                },
             );
 
             bridge
@@ -0,0 +1,409 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+use ethportal_api::{HistoryContentKey, HistoryContentValue};
+
+/// The time until the first retry of content that failed to be gossiped, which doubles with every
+/// failed retry.
+const INITIAL_RETRY_BACKOFF_SECS: u64 = 60;
+/// The longest time between two retries of the same content.
+const MAX_RETRY_BACKOFF_SECS: u64 = 60 * 60;
+/// The most content items kept in the queue. The items that were queued first are dropped once
+/// the queue is full.
+const MAX_RETRY_QUEUE_LEN: usize = 10_000;
+/// The number of records in the log after which it's rewritten to hold only the queued content.
+const COMPACT_LOG_AFTER: usize = 2 * MAX_RETRY_QUEUE_LEN;
+
+/// History content that failed to be gossiped, to be gossiped again with exponential backoff
+/// until it's propagated.
+///
+/// Every change of the queue is appended to a log file by a background task, and the queue is
+/// restored from the log when it's opened, so that content is retried across restarts of the
+/// bridge.
+#[derive(Debug)]
+pub struct GossipRetryQueue {
+    state: Mutex<QueueState>,
+    log_tx: mpsc::UnboundedSender<LogCommand>,
+}
+
+#[derive(Debug)]
+struct QueueState {
+    entries: VecDeque<RetryEntry>,
+    /// The number of records in the log.
+    log_len: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct RetryEntry {
+    content_key: HistoryContentKey,
+    content_value: HistoryContentValue,
+    /// The number of times that gossiping the content failed.
+    failures: u32,
+    /// The unix timestamp, in seconds, of the next retry.
+    retry_at: u64,
+}
+
+/// A change of the queue, as recorded in the log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LogRecord {
+    Push(RetryEntry),
+    Backoff {
+        content_key: HistoryContentKey,
+        failures: u32,
+        retry_at: u64,
+    },
+    Remove(HistoryContentKey),
+}
+
+impl LogRecord {
+    /// Applies the change to `entries`. Returns whether the entries changed.
+    fn apply(self, entries: &mut VecDeque<RetryEntry>) -> bool {
+        match self {
+            LogRecord::Push(entry) => {
+                if entries
+                    .iter()
+                    .any(|queued| queued.content_key == entry.content_key)
+                {
+                    return false;
+                }
+                if entries.len() >= MAX_RETRY_QUEUE_LEN {
+                    warn!("Gossip retry queue is full, dropping the oldest content");
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+                true
+            }
+            LogRecord::Backoff {
+                content_key,
+                failures,
+                retry_at,
+            } => match entries
+                .iter_mut()
+                .find(|entry| entry.content_key == content_key)
+            {
+                Some(entry) => {
+                    entry.failures = failures;
+                    entry.retry_at = retry_at;
+                    true
+                }
+                None => false,
+            },
+            LogRecord::Remove(content_key) => {
+                let len = entries.len();
+                entries.retain(|entry| entry.content_key != content_key);
+                entries.len() != len
+            }
+        }
+    }
+}
+
+/// A command for the task that writes the log.
+#[derive(Debug)]
+enum LogCommand {
+    Append(LogRecord),
+    /// Rewrites the log to hold only the given entries.
+    Compact(Vec<RetryEntry>),
+    /// Notifies the sender once all earlier commands are done.
+    Flush(oneshot::Sender<()>),
+}
+
+impl GossipRetryQueue {
+    /// Opens the retry queue logged at `path`, which is empty if the file doesn't exist.
+    pub async fn open(path: PathBuf) -> anyhow::Result<Self> {
+        let (entries, path) = tokio::task::spawn_blocking(move || {
+            let entries = restore_log(&path)?;
+            write_compacted_log(&path, entries.iter())?;
+            Ok::<_, anyhow::Error>((entries, path))
+        })
+        .await??;
+        let (log_tx, log_rx) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || write_log(path, log_rx));
+        Ok(Self {
+            state: Mutex::new(QueueState {
+                log_len: entries.len(),
+                entries,
+            }),
+            log_tx,
+        })
+    }
+
+    /// Queues content that failed to be gossiped, unless it's queued already.
+    pub fn push(&self, content_key: HistoryContentKey, content_value: HistoryContentValue) {
+        self.update(LogRecord::Push(RetryEntry {
+            content_key,
+            content_value,
+            failures: 1,
+            retry_at: now() + backoff_secs(1),
+        }));
+    }
+
+    /// Returns the queued content that is due to be gossiped again.
+    pub fn due(&self) -> Vec<(HistoryContentKey, HistoryContentValue)> {
+        let Ok(state) = self.state.lock() else {
+            warn!("Error reading gossip retry queue. Unable to acquire lock.");
+            return vec![];
+        };
+        let now = now();
+        state
+            .entries
+            .iter()
+            .filter(|entry| entry.retry_at <= now)
+            .map(|entry| (entry.content_key.clone(), entry.content_value.clone()))
+            .collect()
+    }
+
+    /// Removes content that was gossiped successfully from the queue.
+    pub fn remove(&self, content_key: &HistoryContentKey) {
+        self.update(LogRecord::Remove(content_key.clone()));
+    }
+
+    /// Backs off further from retrying content that failed to be gossiped again.
+    pub fn retry_failed(&self, content_key: &HistoryContentKey) {
+        let failures = {
+            let Ok(state) = self.state.lock() else {
+                warn!("Error updating gossip retry queue. Unable to acquire lock.");
+                return;
+            };
+            match state
+                .entries
+                .iter()
+                .find(|entry| &entry.content_key == content_key)
+            {
+                Some(entry) => entry.failures.saturating_add(1),
+                None => return,
+            }
+        };
+        self.update(LogRecord::Backoff {
+            content_key: content_key.clone(),
+            failures,
+            retry_at: now() + backoff_secs(failures),
+        });
+    }
+
+    /// The number of queued content items.
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .map(|state| state.entries.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Waits until every change of the queue so far is written to the log.
+    pub async fn flush(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.log_tx.send(LogCommand::Flush(done_tx)).is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+
+    /// Applies `record` to the queue, and sends it to the log writer if the queue changed. The
+    /// record is sent while the lock is held, so that the log is written in the order of the
+    /// changes.
+    fn update(&self, record: LogRecord) {
+        let Ok(mut state) = self.state.lock() else {
+            warn!("Error updating gossip retry queue. Unable to acquire lock.");
+            return;
+        };
+        if !record.clone().apply(&mut state.entries) {
+            return;
+        }
+        let _ = self.log_tx.send(LogCommand::Append(record));
+        state.log_len += 1;
+        if state.log_len > COMPACT_LOG_AFTER {
+            let entries = state.entries.iter().cloned().collect();
+            let _ = self.log_tx.send(LogCommand::Compact(entries));
+            state.log_len = state.entries.len();
+        }
+    }
+}
+
+/// Returns the time until the next retry of content that failed to be gossiped `failures` times.
+fn backoff_secs(failures: u32) -> u64 {
+    INITIAL_RETRY_BACKOFF_SECS
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(MAX_RETRY_BACKOFF_SECS)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+/// Replays the log at `path`. A truncated last record, left behind by an interrupted write, is
+/// skipped.
+fn restore_log(path: &Path) -> anyhow::Result<VecDeque<RetryEntry>> {
+    let mut entries = VecDeque::new();
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(err) => return Err(err.into()),
+    };
+    let mut lines = BufReader::new(file).lines().peekable();
+    while let Some(line) = lines.next() {
+        match serde_json::from_str::<LogRecord>(&line?) {
+            Ok(record) => {
+                record.apply(&mut entries);
+            }
+            Err(err) if lines.peek().is_none() => {
+                warn!("Skipping truncated gossip retry queue record: {err}");
+            }
+            Err(err) => {
+                anyhow::bail!("Invalid gossip retry queue at {}: {err}", path.display())
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Writes a log holding only `entries` to a temporary file first, which then replaces the log, so
+/// that an interrupted write doesn't leave a truncated queue behind.
+fn write_compacted_log<'a>(
+    path: &Path,
+    entries: impl Iterator<Item = &'a RetryEntry>,
+) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = File::create(&tmp_path)?;
+    for entry in entries {
+        append_record(&mut file, &LogRecord::Push(entry.clone()))?;
+    }
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn append_record(file: &mut File, record: &LogRecord) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Writes the log at `path`, until every sender of `log_rx` is dropped. Runs on the blocking
+/// thread pool, so that gossip doesn't wait on the disk.
+fn write_log(path: PathBuf, mut log_rx: mpsc::UnboundedReceiver<LogCommand>) {
+    let mut log = match open_append(&path) {
+        Ok(log) => log,
+        Err(err) => {
+            warn!("Error opening gossip retry queue, changes won't be persisted: {err:?}");
+            return;
+        }
+    };
+    while let Some(command) = log_rx.blocking_recv() {
+        let result = match command {
+            LogCommand::Append(record) => append_record(&mut log, &record),
+            LogCommand::Compact(entries) => write_compacted_log(&path, entries.iter())
+                .and_then(|()| Ok(open_append(&path)?))
+                .map(|compacted| log = compacted),
+            LogCommand::Flush(done_tx) => {
+                let _ = done_tx.send(());
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            warn!("Error writing gossip retry queue: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ethportal_api::{types::execution::receipts::Receipts, BlockReceiptsKey};
+
+    fn content() -> (HistoryContentKey, HistoryContentValue) {
+        (
+            HistoryContentKey::BlockReceipts(BlockReceiptsKey {
+                block_hash: [1; 32],
+            }),
+            HistoryContentValue::Receipts(Receipts {
+                receipt_list: vec![],
+            }),
+        )
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff_secs(1), 60);
+        assert_eq!(backoff_secs(2), 120);
+        assert_eq!(backoff_secs(6), 1920);
+        assert_eq!(backoff_secs(7), MAX_RETRY_BACKOFF_SECS);
+        assert_eq!(backoff_secs(u32::MAX), MAX_RETRY_BACKOFF_SECS);
+    }
+
+    #[tokio::test]
+    async fn test_queue_is_persisted() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("retry_queue.log");
+        let (content_key, content_value) = content();
+
+        let queue = GossipRetryQueue::open(path.clone()).await.unwrap();
+        queue.push(content_key.clone(), content_value.clone());
+        queue.push(content_key.clone(), content_value.clone());
+        assert_eq!(queue.len(), 1);
+        // The content isn't due until the backoff passed.
+        assert!(queue.due().is_empty());
+        queue.flush().await;
+        drop(queue);
+
+        let queue = GossipRetryQueue::open(path.clone()).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        queue.state.lock().unwrap().entries[0].retry_at = 0;
+        assert_eq!(queue.due(), vec![(content_key.clone(), content_value)]);
+        queue.retry_failed(&content_key);
+        assert!(queue.due().is_empty());
+        assert_eq!(queue.state.lock().unwrap().entries[0].failures, 2);
+        queue.flush().await;
+        drop(queue);
+
+        let queue = GossipRetryQueue::open(path.clone()).await.unwrap();
+        assert_eq!(queue.state.lock().unwrap().entries[0].failures, 2);
+        queue.remove(&content_key);
+        queue.flush().await;
+        drop(queue);
+        assert!(GossipRetryQueue::open(path).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_log_is_restored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("retry_queue.log");
+        let (content_key, content_value) = content();
+        let queue = GossipRetryQueue::open(path.clone()).await.unwrap();
+        queue.push(content_key.clone(), content_value);
+        queue.flush().await;
+        drop(queue);
+
+        // A write of the log that was interrupted halfway.
+        let mut log = open_append(&path).unwrap();
+        let record = serde_json::to_string(&LogRecord::Remove(content_key.clone())).unwrap();
+        log.write_all(&record.as_bytes()[..record.len() / 2])
+            .unwrap();
+
+        let queue = GossipRetryQueue::open(path.clone()).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        // The log is compacted when it's opened, dropping the truncated record.
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
+}
@@ -1,8 +1,18 @@
-use std::net::SocketAddr;
+use std::{fs, net::SocketAddr, path::Path, time::Duration};
 
+use anyhow::anyhow;
 use ethereum_types::H256;
+use tracing::warn;
 
-use ethportal_api::types::{bootnodes::Bootnodes, cli::TrinConfig, distance::Distance};
+use ethportal_api::types::{
+    bootnodes::{parse_bootnodes_file, Bootnode, Bootnodes, DEFAULT_BOOTNODES},
+    cli::{
+        TrinConfig, DEFAULT_QUERY_PARALLELISM, DEFAULT_QUERY_PEER_TIMEOUT_SECS,
+        DEFAULT_QUERY_TIMEOUT_SECS,
+    },
+    distance::Distance,
+    network::PortalNetwork,
+};
 
 /// Capacity of the cache for observed `NodeAddress` values.
 /// Provides capacity for 32 full k-buckets. This capacity will be shared among all active portal
@@ -14,13 +24,25 @@ pub struct PortalnetConfig {
     pub external_addr: Option<SocketAddr>,
     pub private_key: H256,
     pub listen_port: u16,
+    /// The portal network that the node joins, which determines the protocol ids.
+    pub network: PortalNetwork,
     pub bootnodes: Bootnodes,
     pub data_radius: Distance,
     pub internal_ip: bool,
     pub no_stun: bool,
     pub no_upnp: bool,
+    /// Whether to also listen on IPv6 and advertise the udp6 ENR field.
+    pub enable_ipv6: bool,
+    /// Whether to keep the ENR in memory only, instead of saving it to the node data directory.
+    pub ephemeral: bool,
     pub node_addr_cache_capacity: usize,
     pub disable_poke: bool,
+    /// Maximum number of peers queried at the same time by a recursive lookup.
+    pub query_parallelism: usize,
+    /// Time a recursive lookup waits for each peer to respond.
+    pub query_peer_timeout: Duration,
+    /// Time after which a recursive lookup is ended.
+    pub query_timeout: Duration,
     pub trusted_block_root: Option<String>,
 }
 
@@ -30,13 +52,19 @@ impl Default for PortalnetConfig {
             external_addr: None,
             private_key: H256::random(),
             listen_port: 4242,
+            network: PortalNetwork::default(),
             bootnodes: Bootnodes::default(),
             data_radius: Distance::MAX,
             internal_ip: false,
             no_stun: false,
             no_upnp: false,
+            enable_ipv6: false,
+            ephemeral: false,
             node_addr_cache_capacity: NODE_ADDR_CACHE_CAPACITY,
             disable_poke: false,
+            query_parallelism: DEFAULT_QUERY_PARALLELISM,
+            query_peer_timeout: Duration::from_secs(DEFAULT_QUERY_PEER_TIMEOUT_SECS),
+            query_timeout: Duration::from_secs(DEFAULT_QUERY_TIMEOUT_SECS),
             trusted_block_root: None,
         }
     }
@@ -50,10 +78,80 @@ impl PortalnetConfig {
             listen_port: trin_config.discovery_port,
             no_stun: trin_config.no_stun,
             no_upnp: trin_config.no_upnp,
-            bootnodes: trin_config.bootnodes.clone(),
+            enable_ipv6: trin_config.ipv6,
+            ephemeral: trin_config.ephemeral,
+            network: trin_config.network,
+            bootnodes: match &trin_config.bootnodes {
+                Bootnodes::Default => trin_config.network.default_bootnodes(),
+                bootnodes => bootnodes.clone(),
+            },
             disable_poke: trin_config.disable_poke,
+            query_parallelism: trin_config.query_parallelism,
+            query_peer_timeout: Duration::from_secs(trin_config.query_peer_timeout),
+            query_timeout: Duration::from_secs(trin_config.query_timeout),
             trusted_block_root: trin_config.trusted_block_root.clone(),
             ..Default::default()
         }
     }
+
+    /// Adds the bootnodes listed in a bootnodes file to the configured bootnodes. Lines with
+    /// invalid ENRs are reported and skipped.
+    pub fn add_bootnodes_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Unable to read bootnodes file {}: {err}", path.display()))?;
+        let (file_bootnodes, errors) = parse_bootnodes_file(&contents);
+        for err in errors {
+            warn!(file = %path.display(), "Skipping invalid bootnode, {err}");
+        }
+        let mut bootnodes: Vec<Bootnode> = match &self.bootnodes {
+            Bootnodes::Default => DEFAULT_BOOTNODES.clone(),
+            Bootnodes::None => vec![],
+            Bootnodes::Custom(bootnodes) => bootnodes.clone(),
+        };
+        for bootnode in file_bootnodes {
+            if !bootnodes.iter().any(|known| known.enr == bootnode.enr) {
+                bootnodes.push(bootnode);
+            }
+        }
+        self.bootnodes = Bootnodes::Custom(bootnodes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ethportal_api::types::enr::Enr;
+
+    #[test]
+    fn test_add_bootnodes_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("bootnodes.txt");
+        let enr = "enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg";
+        fs::write(&path, format!("{enr} my-node\nenr:invalid\n")).unwrap();
+
+        let mut config = PortalnetConfig::default();
+        config.add_bootnodes_file(&path).unwrap();
+        let bootnodes: Vec<Enr> = config.bootnodes.clone().into();
+        assert_eq!(bootnodes.len(), DEFAULT_BOOTNODES.len() + 1);
+
+        let mut config = PortalnetConfig {
+            bootnodes: Bootnodes::None,
+            ..Default::default()
+        };
+        config.add_bootnodes_file(&path).unwrap();
+        config.add_bootnodes_file(&path).unwrap();
+        match config.bootnodes {
+            Bootnodes::Custom(bootnodes) => {
+                assert_eq!(bootnodes.len(), 1);
+                assert_eq!(bootnodes[0].alias, "my-node");
+            }
+            _ => panic!("Bootnodes should be custom"),
+        }
+
+        assert!(config
+            .add_bootnodes_file(&temp_dir.path().join("missing.txt"))
+            .is_err());
+    }
 }
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// The default maximum total size of the content values in the cache.
+pub const DEFAULT_CONTENT_CACHE_CAPACITY_BYTES: usize = 16 * 1024 * 1024;
+
+/// A bounded in-memory cache of recently served and recently stored content values, keyed by
+/// content id, so that repeated requests for popular content don't hit the store each time.
+///
+/// The cache is kept by the storage actor of a store, which caches only stored content and clears
+/// the cache whenever content may have been evicted, see `StoreHandle`.
+#[derive(Clone, Debug)]
+pub struct ContentCache {
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+#[derive(Debug)]
+struct CacheInner {
+    entries: LruCache<[u8; 32], Vec<u8>>,
+    size_bytes: usize,
+    capacity_bytes: usize,
+}
+
+impl ContentCache {
+    /// Constructs a cache holding content values of up to `capacity_bytes` in total. A capacity of
+    /// zero disables the cache.
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner {
+                entries: LruCache::unbounded(),
+                size_bytes: 0,
+                capacity_bytes,
+            })),
+        }
+    }
+
+    /// Looks up the content value of `content_id`, marking it as recently used.
+    pub fn get(&self, content_id: &[u8; 32]) -> Option<Vec<u8>> {
+        self.inner.lock().entries.get(content_id).cloned()
+    }
+
+    /// Caches the content value of `content_id`, evicting the least recently used values to stay
+    /// within capacity. Values larger than the capacity are never cached.
+    pub fn insert(&self, content_id: [u8; 32], content_value: Vec<u8>) {
+        let mut inner = self.inner.lock();
+        if content_value.len() > inner.capacity_bytes {
+            return;
+        }
+        inner.size_bytes += content_value.len();
+        if let Some(replaced) = inner.entries.put(content_id, content_value) {
+            inner.size_bytes -= replaced.len();
+        }
+        while inner.size_bytes > inner.capacity_bytes {
+            match inner.entries.pop_lru() {
+                Some((_, evicted)) => inner.size_bytes -= evicted.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Drops all cached content values.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.entries.clear();
+        inner.size_bytes = 0;
+    }
+
+    /// Returns the total size of the cached content values, in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.inner.lock().size_bytes
+    }
+}
+
+impl Default for ContentCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONTENT_CACHE_CAPACITY_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_content() {
+        let cache = ContentCache::new(30);
+        cache.insert([1; 32], vec![1; 10]);
+        cache.insert([2; 32], vec![2; 10]);
+        cache.insert([3; 32], vec![3; 10]);
+        // Using the first value makes the second one the least recently used.
+        assert_eq!(cache.get(&[1; 32]), Some(vec![1; 10]));
+
+        cache.insert([4; 32], vec![4; 10]);
+        assert_eq!(cache.size_bytes(), 30);
+        assert_eq!(cache.get(&[2; 32]), None);
+        assert_eq!(cache.get(&[1; 32]), Some(vec![1; 10]));
+
+        // Replacing a value accounts for the size of the replaced value.
+        cache.insert([1; 32], vec![1; 5]);
+        assert_eq!(cache.size_bytes(), 25);
+    }
+
+    #[test]
+    fn never_caches_values_over_capacity() {
+        let cache = ContentCache::new(10);
+        cache.insert([1; 32], vec![1; 11]);
+        assert_eq!(cache.get(&[1; 32]), None);
+        assert_eq!(cache.size_bytes(), 0);
+
+        cache.insert([2; 32], vec![2; 10]);
+        cache.clear();
+        assert_eq!(cache.get(&[2; 32]), None);
+        assert_eq!(cache.size_bytes(), 0);
+
+        let disabled_cache = ContentCache::new(0);
+        disabled_cache.insert([1; 32], vec![1]);
+        assert_eq!(disabled_cache.get(&[1; 32]), None);
+    }
+}
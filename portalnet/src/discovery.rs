@@ -3,8 +3,8 @@ use std::{
     fmt, fs,
     hash::{Hash, Hasher},
     io,
-    net::{Ipv4Addr, SocketAddr},
-    path::PathBuf,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
     time::Duration,
@@ -15,7 +15,7 @@ use async_trait::async_trait;
 use bytes::BytesMut;
 use discv5::{
     enr::{CombinedKey, Enr as Discv5Enr, NodeId},
-    ConfigBuilder, Discv5, Event, ListenConfig, RequestError, TalkRequest,
+    ConfigBuilder, Discv5, Event, ListenConfig, QueryError, RequestError, TalkRequest,
 };
 use lru::LruCache;
 use parking_lot::RwLock;
@@ -25,9 +25,14 @@ use tracing::{debug, info, warn};
 use utp_rs::{cid::ConnectionPeer, udp::AsyncUdpSocket};
 
 use super::config::PortalnetConfig;
-use crate::socket;
+use crate::{socket, types::peer_bans::PeerBans};
 use ethportal_api::{
-    types::{discv5::RoutingTableInfo, enr::Enr, portal_wire::ProtocolId},
+    types::{
+        discv5::RoutingTableInfo,
+        enr::{Enr, ENR_PROTOCOL_VERSIONS_KEY, SUPPORTED_PROTOCOL_VERSIONS},
+        network::PortalNetwork,
+        portal_wire::ProtocolId,
+    },
     utils::bytes::hex_encode,
     NodeInfo,
 };
@@ -42,6 +47,14 @@ const ENR_PORTAL_CLIENT_KEY: &str = "c";
 /// ENR file name saving enr history to disk.
 const ENR_FILE_NAME: &str = "trin.enr";
 
+/// File name of the routing table snapshot, which holds the ENRs of the routing table, one per
+/// line, saved on shutdown.
+const ROUTING_TABLE_FILE_NAME: &str = "routing_table.txt";
+
+/// The number of peers that have to report the same external socket address in their PONGs
+/// before the ENR is updated with it, as long as they are the majority of the reporting peers.
+const ENR_PEER_UPDATE_MIN: usize = 10;
+
 pub type ProtocolRequest = Vec<u8>;
 
 /// The contact info for a remote node.
@@ -59,10 +72,18 @@ pub struct Discovery {
     discv5: Discv5,
     /// A cache of the latest observed `NodeAddress` for a node ID.
     node_addr_cache: Arc<RwLock<LruCache<NodeId, NodeAddress>>>,
+    /// The offences and bans of peers, shared by all overlays.
+    peer_bans: PeerBans,
     /// Indicates if the Discv5 service has been started.
     pub started: bool,
     /// The socket address that the Discv5 service listens on.
     pub listen_socket: SocketAddr,
+    /// The file that the local ENR is saved to, unless it is only kept in memory.
+    enr_file_path: Option<PathBuf>,
+    /// The file that the routing table snapshot is saved to, unless it is only kept in memory.
+    routing_table_path: Option<PathBuf>,
+    /// The portal network that the node joined, which determines the protocol ids.
+    network: PortalNetwork,
 }
 
 impl fmt::Debug for Discovery {
@@ -129,50 +150,79 @@ impl Discovery {
             if let Some(ip_address) = enr_address {
                 builder.ip(ip_address);
             }
-            builder.udp4(enr_port);
+            match enr_address {
+                Some(IpAddr::V6(_)) => builder.udp6(enr_port),
+                _ => builder.udp4(enr_port),
+            };
+            // The IPv6 address is left for Discv5 to fill in from the addresses that peers report.
+            if portal_config.enable_ipv6 && !matches!(enr_address, Some(IpAddr::V6(_))) {
+                builder.udp6(portal_config.listen_port);
+            }
 
             let trin_version = get_trin_version();
             // Use "t" as short-hand for "Trin" to save bytes in ENR.
             let client_info = format!("t {trin_version}");
             // Use "c" as short-hand for "client".
             builder.add_value(ENR_PORTAL_CLIENT_KEY, &client_info.as_bytes());
+            builder.add_value(
+                ENR_PROTOCOL_VERSIONS_KEY,
+                &SUPPORTED_PROTOCOL_VERSIONS.as_slice(),
+            );
             builder
                 .build(&enr_key)
                 .map_err(|e| format!("When adding key to servers ENR: {e:?}"))?
         };
 
-        // Check if we have an old version of our Enr and if we do, increase our sequence number
-        let trin_enr_path = node_data_dir.join(ENR_FILE_NAME);
-        if trin_enr_path.is_file() {
-            let data = fs::read_to_string(trin_enr_path.clone())
-                .expect("Unable to read Trin Enr from file");
-            let old_enr = Enr::from_str(&data).expect("Expected to read valid Trin Enr from file");
-            enr.set_seq(old_enr.seq(), &enr_key)
-                .expect("Unable to set Enr sequence number");
-
-            // If the content is different then increase the sequence number
-            if get_enr_rlp_content(&enr) != get_enr_rlp_content(&old_enr) {
-                enr.set_seq(old_enr.seq() + 1, &enr_key)
-                    .expect("Unable to increase Enr sequence number");
-                fs::write(trin_enr_path, enr.to_base64())
-                    .expect("Unable to update Trin Enr to file");
+        // Check if we have an old version of our Enr and if we do, increase our sequence number.
+        // In ephemeral mode, the Enr is only kept in memory.
+        let enr_file_path = (!portal_config.ephemeral).then(|| node_data_dir.join(ENR_FILE_NAME));
+        if let Some(trin_enr_path) = enr_file_path.clone() {
+            if trin_enr_path.is_file() {
+                let data = fs::read_to_string(trin_enr_path.clone())
+                    .expect("Unable to read Trin Enr from file");
+                let old_enr =
+                    Enr::from_str(&data).expect("Expected to read valid Trin Enr from file");
+                enr.set_seq(old_enr.seq(), &enr_key)
+                    .expect("Unable to set Enr sequence number");
+
+                // If the content is different then increase the sequence number
+                if get_enr_rlp_content(&enr) != get_enr_rlp_content(&old_enr) {
+                    enr.set_seq(old_enr.seq() + 1, &enr_key)
+                        .expect("Unable to increase Enr sequence number");
+                    fs::write(trin_enr_path, enr.to_base64())
+                        .expect("Unable to update Trin Enr to file");
+                } else {
+                    // the content is the same, we don't want to change signatures on restart
+                    // so set enr to old one to keep the same signature per sequence number
+                    enr = old_enr;
+                }
             } else {
-                // the content is the same, we don't want to change signatures on restart
-                // so set enr to old one to keep the same signature per sequence number
-                enr = old_enr;
+                // Write enr to disk
+                fs::write(trin_enr_path, enr.to_base64())
+                    .expect("Unable to write Trin Enr to file");
             }
-        } else {
-            // Write enr to disk
-            fs::write(trin_enr_path, enr.to_base64()).expect("Unable to write Trin Enr to file");
         }
 
-        let listen_config = ListenConfig::Ipv4 {
-            ip: Ipv4Addr::UNSPECIFIED,
-            port: portal_config.listen_port,
+        let listen_config = if portal_config.enable_ipv6 {
+            ListenConfig::DualStack {
+                ipv4: Ipv4Addr::UNSPECIFIED,
+                ipv4_port: portal_config.listen_port,
+                ipv6: Ipv6Addr::UNSPECIFIED,
+                ipv6_port: portal_config.listen_port,
+            }
+        } else {
+            ListenConfig::Ipv4 {
+                ip: Ipv4Addr::UNSPECIFIED,
+                port: portal_config.listen_port,
+            }
         };
 
+        // Let Discv5 update the ENR with the external address that the majority of peers report in
+        // their PONGs, unless the external address was configured explicitly.
         let discv5_config = ConfigBuilder::new(listen_config)
             .request_timeout(Duration::from_secs(3))
+            .enr_update(portal_config.external_addr.is_none())
+            .enr_peer_update_min(ENR_PEER_UPDATE_MIN)
             .build();
         let discv5 = Discv5::new(enr, enr_key, discv5_config)
             .map_err(|e| format!("Failed to create discv5 instance: {e}"))?;
@@ -188,14 +238,33 @@ impl Discovery {
                 .map_err(|e| format!("Failed to add bootnode enr: {e}"))?;
         }
 
+        // Rejoin the network through the peers that were in the routing table on shutdown, along
+        // with the bootnodes.
+        let routing_table_path =
+            (!portal_config.ephemeral).then(|| node_data_dir.join(ROUTING_TABLE_FILE_NAME));
+        if let Some(path) = &routing_table_path {
+            for enr in read_routing_table(path) {
+                if enr.node_id() == discv5.local_enr().node_id() {
+                    continue;
+                }
+                if let Err(err) = discv5.add_enr(enr) {
+                    debug!(%err, "Unable to add ENR from routing table snapshot");
+                }
+            }
+        }
+
         let node_addr_cache = LruCache::new(portal_config.node_addr_cache_capacity);
         let node_addr_cache = Arc::new(RwLock::new(node_addr_cache));
 
         Ok(Self {
             discv5,
             node_addr_cache,
+            peer_bans: PeerBans::default(),
             started: false,
             listen_socket: listen_all_ips,
+            enr_file_path,
+            routing_table_path,
+            network: portal_config.network,
         })
     }
 
@@ -218,12 +287,22 @@ impl Discovery {
         let (talk_req_tx, talk_req_rx) = mpsc::channel(TALKREQ_CHANNEL_BUFFER);
 
         let node_addr_cache = Arc::clone(&self.node_addr_cache);
+        let peer_bans = self.peer_bans.clone();
+        let local_enr = self.discv5.external_enr();
+        let enr_file_path = self.enr_file_path.clone();
 
         tokio::spawn(async move {
             while let Some(event) = event_rx.recv().await {
                 match event {
                     Event::TalkRequest(talk_req) => {
-                        // Forward all TALKREQ messages.
+                        // Drop the TALKREQ messages of banned peers, and forward all others.
+                        if peer_bans.is_banned(talk_req.node_id()) {
+                            tracing::trace!(
+                                peer = %talk_req.node_id(),
+                                "Dropping TALKREQ from banned peer"
+                            );
+                            continue;
+                        }
                         let _ = talk_req_tx.send(talk_req).await;
                     }
                     Event::SessionEstablished(enr, socket_addr) => {
@@ -243,6 +322,23 @@ impl Discovery {
                             tracing::debug!(addr = ?(enr, socket_addr), "node address cached");
                         }
                     }
+                    Event::SocketUpdated(socket_addr) => {
+                        // Save the ENR that Discv5 updated with the external address reported by
+                        // the majority of peers, so that its sequence number survives restarts.
+                        let enr = local_enr.read().clone();
+                        info!(
+                            %socket_addr,
+                            seq = enr.seq(),
+                            "Updated ENR with external address reported by peers"
+                        );
+                        if let Some(enr_file_path) = &enr_file_path {
+                            // Writing asynchronously keeps the discv5 events flowing meanwhile.
+                            let contents = enr.to_base64();
+                            if let Err(err) = tokio::fs::write(enr_file_path, contents).await {
+                                warn!(%err, "Unable to update Trin Enr file");
+                            }
+                        }
+                    }
                     _ => continue,
                 }
             }
@@ -251,6 +347,22 @@ impl Discovery {
         Ok(talk_req_rx)
     }
 
+    /// Returns the offences and bans of peers.
+    pub fn peer_bans(&self) -> &PeerBans {
+        &self.peer_bans
+    }
+
+    /// Records an offence of `node_id`, and removes the peer from the Discv5 routing table if it
+    /// got banned. Returns true if the peer got banned.
+    pub fn report_offence(&self, node_id: &NodeId) -> bool {
+        let banned = self.peer_bans.report_offence(node_id);
+        if banned {
+            warn!(peer = %node_id, "Banning peer after repeated offences");
+            self.discv5.remove_node(node_id);
+        }
+        banned
+    }
+
     /// Returns number of connected peers in the Discv5 routing table.
     pub fn connected_peers_len(&self) -> usize {
         self.discv5.connected_peers()
@@ -261,6 +373,22 @@ impl Discovery {
         self.discv5.table_entries_enr()
     }
 
+    /// Saves the ENRs of the routing table, so that the node rejoins the network through them on
+    /// the next start. Returns the number of saved ENRs, which is zero in ephemeral mode.
+    pub fn save_routing_table(&self) -> io::Result<usize> {
+        let Some(path) = &self.routing_table_path else {
+            return Ok(0);
+        };
+        let enrs = self.table_entries_enr();
+        let snapshot: String = enrs.iter().map(|enr| enr.to_base64() + "\n").collect();
+        // Write to a temporary file first, so that an interrupted write doesn't leave a truncated
+        // snapshot behind.
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, snapshot)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(enrs.len())
+    }
+
     /// Returns ENR and nodeId information of the local Discv5 node.
     pub fn node_info(&self) -> anyhow::Result<NodeInfo> {
         Ok(NodeInfo {
@@ -271,7 +399,9 @@ impl Discovery {
                 .discv5
                 .local_enr()
                 .ip4()
-                .map_or(Some("None".to_owned()), |ip| Some(ip.to_string())),
+                .map(|ip| ip.to_string())
+                .or_else(|| self.discv5.local_enr().ip6().map(|ip| ip.to_string()))
+                .or(Some("None".to_owned())),
         })
     }
 
@@ -317,6 +447,27 @@ impl Discovery {
             .put(node_addr.enr.node_id(), node_addr)
     }
 
+    /// Replaces a cached ENR with `enr` if it has a higher sequence number, along with the socket
+    /// address it advertises, so that the node's new endpoint is dialed from then on. Returns
+    /// whether the cache was updated.
+    pub fn update_cached_node_addr(&self, enr: &Enr) -> bool {
+        let mut node_addr_cache = self.node_addr_cache.write();
+        match node_addr_cache.get_mut(&enr.node_id()) {
+            Some(node_addr) if node_addr.enr.seq() < enr.seq() => {
+                if let Some(socket_addr) = enr
+                    .udp4_socket()
+                    .map(SocketAddr::V4)
+                    .or_else(|| enr.udp6_socket().map(SocketAddr::V6))
+                {
+                    node_addr.socket_addr = socket_addr;
+                }
+                node_addr.enr = enr.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Sends a TALKREQ message to `enr`.
     pub async fn send_talk_req(
         &self,
@@ -325,11 +476,27 @@ impl Discovery {
         request: ProtocolRequest,
     ) -> Result<Vec<u8>, RequestError> {
         // Send empty protocol id if unable to convert it to bytes
-        let protocol = Vec::try_from(protocol).unwrap_or_default();
+        let protocol = self.network.protocol_id_bytes(protocol).unwrap_or_default();
 
+        self.send_raw_talk_req(enr, protocol, request).await
+    }
+
+    /// Sends a TALKREQ message with an arbitrary protocol identifier to `enr`.
+    pub async fn send_raw_talk_req(
+        &self,
+        enr: Enr,
+        protocol: Vec<u8>,
+        request: ProtocolRequest,
+    ) -> Result<Vec<u8>, RequestError> {
         let response = self.discv5.talk_req(enr, protocol, request).await?;
         Ok(response)
     }
+
+    /// Performs a recursive FINDNODE lookup for `target` in the Discv5 network, returning the
+    /// ENRs of the closest nodes found.
+    pub async fn recursive_find_nodes(&self, target: NodeId) -> Result<Vec<Enr>, QueryError> {
+        self.discv5.find_node(target).await
+    }
 }
 
 pub struct Discv5UdpSocket {
@@ -463,6 +630,41 @@ fn get_enr_rlp_content(enr: &Enr) -> BytesMut {
     }
 }
 
+/// Returns the local ENR that a node saved to its node data dir, if it ran before.
+pub fn read_saved_enr(node_data_dir: &Path) -> anyhow::Result<Option<Enr>> {
+    let enr_file_path = node_data_dir.join(ENR_FILE_NAME);
+    if !enr_file_path.is_file() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(enr_file_path)?;
+    Ok(Some(Enr::from_str(data.trim()).map_err(|err| {
+        anyhow!("Invalid ENR in {ENR_FILE_NAME}: {err}")
+    })?))
+}
+
+/// Reads the ENRs of the routing table snapshot, skipping invalid ones.
+fn read_routing_table(path: &Path) -> Vec<Enr> {
+    let snapshot = match fs::read_to_string(path) {
+        Ok(snapshot) => snapshot,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return vec![],
+        Err(err) => {
+            warn!(%err, "Unable to read routing table snapshot");
+            return vec![];
+        }
+    };
+    snapshot
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match Enr::from_str(line.trim()) {
+            Ok(enr) => Some(enr),
+            Err(err) => {
+                warn!(%err, "Skipping invalid ENR in routing table snapshot");
+                None
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -472,10 +674,11 @@ mod tests {
     #[test]
     fn test_enr_file() {
         // Setup temp trin data directory if we're in ephemeral mode
-        let trin_data_dir = configure_trin_data_dir(true).unwrap();
+        let trin_data_dir = configure_trin_data_dir(true, PortalNetwork::Mainnet).unwrap();
 
         // Configure node data dir based on the provided private key
-        let (node_data_dir, private_key) = configure_node_data_dir(trin_data_dir, None).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, None).unwrap();
 
         let mut portalnet_config = PortalnetConfig {
             private_key,
@@ -513,4 +716,50 @@ mod tests {
         assert_eq!(old_enr.seq(), 2);
         assert_eq!(discovery.local_enr(), old_enr);
     }
+
+    #[test]
+    fn test_routing_table_snapshot() {
+        let trin_data_dir = configure_trin_data_dir(true, PortalNetwork::Mainnet).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, None).unwrap();
+        let portalnet_config = PortalnetConfig {
+            private_key,
+            bootnodes: Bootnodes::None,
+            ..Default::default()
+        };
+
+        let discovery = Discovery::new(portalnet_config.clone(), node_data_dir.clone()).unwrap();
+        let peer_key = CombinedKey::generate_secp256k1();
+        let peer = Discv5Enr::builder()
+            .ip4(Ipv4Addr::new(192, 0, 2, 1))
+            .udp4(9009)
+            .build(&peer_key)
+            .unwrap();
+        discovery.add_enr(peer.clone()).unwrap();
+        assert_eq!(discovery.save_routing_table().unwrap(), 1);
+
+        // A restarted node rejoins through the peers of the snapshot, skipping invalid lines.
+        let path = node_data_dir.join(ROUTING_TABLE_FILE_NAME);
+        let snapshot = fs::read_to_string(&path).unwrap();
+        fs::write(&path, format!("{snapshot}not an enr\n")).unwrap();
+        let discovery = Discovery::new(portalnet_config, node_data_dir).unwrap();
+        assert_eq!(discovery.table_entries_enr(), vec![peer]);
+    }
+
+    #[test]
+    fn test_ephemeral_enr_is_not_saved() {
+        let trin_data_dir = configure_trin_data_dir(true, PortalNetwork::Mainnet).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, None).unwrap();
+
+        let portalnet_config = PortalnetConfig {
+            private_key,
+            bootnodes: Bootnodes::None,
+            ephemeral: true,
+            ..Default::default()
+        };
+        let discovery = Discovery::new(portalnet_config, node_data_dir.clone()).unwrap();
+        assert_eq!(discovery.local_enr().seq(), 1);
+        assert!(!node_data_dir.join(ENR_FILE_NAME).is_file());
+    }
 }
@@ -1,17 +1,15 @@
-use std::{
-    str::FromStr,
-    time::{Duration, SystemTime, UNIX_EPOCH},
-};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use discv5::TalkRequest;
+use discv5::{enr::NodeId, TalkRequest};
 use futures::stream::{select_all, StreamExt};
 use tokio::sync::{broadcast, mpsc};
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::{debug, error, trace, warn};
 
 use ethportal_api::{
-    types::portal_wire::ProtocolId,
+    types::{network::PortalNetwork, portal_wire::ProtocolId},
     utils::bytes::{hex_encode, hex_encode_upper},
+    RawContentKey,
 };
 
 /// Handles for communication between the main event handler and an overlay.
@@ -61,24 +59,37 @@ pub struct PortalnetEvents {
     pub state_handle: OverlayHandle,
     /// Beacon network send & receive handles.
     pub beacon_handle: OverlayHandle,
+    /// Transaction gossip network send & receive handles.
+    pub transaction_gossip_handle: OverlayHandle,
+    /// Canonical indices network send & receive handles.
+    pub canonical_indices_handle: OverlayHandle,
     /// Send TalkReq events with "utp" protocol id to `UtpListener`
     pub utp_talk_reqs: mpsc::UnboundedSender<TalkRequest>,
+    /// The portal network that the node joined, which determines the protocol ids.
+    pub network: PortalNetwork,
 }
 
 impl PortalnetEvents {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         talk_req_receiver: mpsc::Receiver<TalkRequest>,
         history_channels: OverlayChannels,
         state_channels: OverlayChannels,
         beacon_channels: OverlayChannels,
+        transaction_gossip_channels: OverlayChannels,
+        canonical_indices_channels: OverlayChannels,
         utp_talk_reqs: mpsc::UnboundedSender<TalkRequest>,
+        network: PortalNetwork,
     ) -> Self {
         Self {
             talk_req_receiver,
             history_handle: history_channels.into(),
             state_handle: state_channels.into(),
             beacon_handle: beacon_channels.into(),
+            transaction_gossip_handle: transaction_gossip_channels.into(),
+            canonical_indices_handle: canonical_indices_channels.into(),
             utp_talk_reqs,
+            network,
         }
     }
 
@@ -94,6 +105,12 @@ impl PortalnetEvents {
         if let Some(rx) = self.beacon_handle.rx.take() {
             receivers.push(rx);
         }
+        if let Some(rx) = self.transaction_gossip_handle.rx.take() {
+            receivers.push(rx);
+        }
+        if let Some(rx) = self.canonical_indices_handle.rx.take() {
+            receivers.push(rx);
+        }
 
         if receivers.is_empty() {
             panic!("No networks are available for requests, trin expects at least one");
@@ -118,7 +135,9 @@ impl PortalnetEvents {
 
     /// Dispatch Discv5 TalkRequest event to overlay networks or uTP socket
     fn dispatch_discv5_talk_req(&self, request: TalkRequest) {
-        let protocol_id = ProtocolId::from_str(&hex_encode_upper(request.protocol()));
+        let protocol_id = self
+            .network
+            .protocol_id(&hex_encode_upper(request.protocol()));
 
         match protocol_id {
             Ok(protocol) => match protocol {
@@ -131,6 +150,16 @@ impl PortalnetEvents {
                 ProtocolId::State => {
                     self.send_overlay_request(&self.state_handle.tx, request.into(), "state")
                 }
+                ProtocolId::TransactionGossip => self.send_overlay_request(
+                    &self.transaction_gossip_handle.tx,
+                    request.into(),
+                    "transaction gossip",
+                ),
+                ProtocolId::CanonicalIndices => self.send_overlay_request(
+                    &self.canonical_indices_handle.tx,
+                    request.into(),
+                    "canonical indices",
+                ),
                 ProtocolId::Utp => {
                     if let Err(err) = self.utp_talk_reqs.send(request) {
                         error!(%err, "Error forwarding talk request to uTP socket");
@@ -152,7 +181,18 @@ impl PortalnetEvents {
     fn dispatch_overlay_event(&self, event: EventEnvelope) {
         use OverlayRequest::Event;
 
-        let all_protocols = vec![ProtocolId::History, ProtocolId::Beacon, ProtocolId::State];
+        if event.payload.is_local() {
+            // Local-only event, there is no other overlay to dispatch it to.
+            return;
+        }
+
+        let all_protocols = vec![
+            ProtocolId::History,
+            ProtocolId::Beacon,
+            ProtocolId::State,
+            ProtocolId::TransactionGossip,
+            ProtocolId::CanonicalIndices,
+        ];
         let mut recipients = event
             .destination
             .as_ref()
@@ -174,6 +214,20 @@ impl PortalnetEvents {
         if recipients.contains(&ProtocolId::History) {
             self.send_overlay_request(&self.history_handle.tx, Event(event.clone()), "history");
         }
+        if recipients.contains(&ProtocolId::TransactionGossip) {
+            self.send_overlay_request(
+                &self.transaction_gossip_handle.tx,
+                Event(event.clone()),
+                "transaction gossip",
+            );
+        }
+        if recipients.contains(&ProtocolId::CanonicalIndices) {
+            self.send_overlay_request(
+                &self.canonical_indices_handle.tx,
+                Event(event.clone()),
+                "canonical indices",
+            );
+        }
     }
 
     fn send_overlay_request(
@@ -198,6 +252,33 @@ impl PortalnetEvents {
 pub enum OverlayEvent {
     LightClientOptimisticUpdate,
     LightClientFinalityUpdate,
+    /// Content with the given raw key was received from the network and stored locally.
+    ContentStored(RawContentKey),
+    /// A peer was connected in the routing table.
+    PeerAdded(NodeId),
+    /// A connected peer was disconnected or removed from the routing table.
+    PeerRemoved(NodeId),
+    /// A peer accepted the given content keys of an offer.
+    OfferAccepted {
+        peer: NodeId,
+        content_keys: Vec<RawContentKey>,
+    },
+    /// Content with the given raw key received from a peer failed validation.
+    ValidationFailed {
+        peer: NodeId,
+        content_key: RawContentKey,
+    },
+}
+
+impl OverlayEvent {
+    /// Returns true if the event is only of interest to subscribers of the originating overlay's
+    /// event stream, rather than to other overlays.
+    pub fn is_local(&self) -> bool {
+        !matches!(
+            self,
+            OverlayEvent::LightClientOptimisticUpdate | OverlayEvent::LightClientFinalityUpdate
+        )
+    }
 }
 
 /// Timestamp of an overlay event.
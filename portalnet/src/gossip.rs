@@ -4,7 +4,7 @@ use discv5::{
     enr::NodeId,
     kbucket::{self, KBucketsTable},
 };
-use futures::channel::oneshot;
+use futures::{channel::oneshot, future::join_all};
 use parking_lot::RwLock;
 use rand::seq::IteratorRandom;
 use serde::{Deserialize, Serialize};
@@ -13,13 +13,16 @@ use tracing::{debug, error, warn};
 
 use crate::{
     overlay_service::{OverlayCommand, OverlayRequest, RequestDirection},
-    types::node::Node,
+    types::{node::Node, peer_scores::PeerScores},
 };
 use ethportal_api::{
     types::{
-        distance::{Metric, XorMetric},
+        distance::Metric,
         enr::Enr,
-        portal_wire::{PopulatedOffer, PopulatedOfferWithResult, Request, Response},
+        portal_wire::{
+            Accept, PopulatedOffer, PopulatedOfferWithResult, Request, Response,
+            MAX_CONTENT_KEYS_PER_OFFER,
+        },
     },
     utils::bytes::hex_encode,
     OverlayContentKey, RawContentKey,
@@ -38,10 +41,14 @@ pub struct GossipResult {
 
 /// Propagate gossip in a way that can be used across threads, without &self.
 /// Doesn't trace gossip results
-pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
+///
+/// The content is never offered back to `source`, the peer it was received from, if any.
+pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey, TMetric: Metric>(
     content: Vec<(TContentKey, Vec<u8>)>,
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+    peer_scores: &PeerScores,
     command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    source: Option<NodeId>,
 ) -> usize {
     // Get all connected nodes from overlay routing table
     let kbuckets = kbuckets.read();
@@ -51,6 +58,7 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
             kbucket
                 .iter()
                 .filter(|node| node.status.is_connected())
+                .filter(|node| Some(*node.key.preimage()) != source)
                 .collect::<Vec<&kbucket::Node<NodeId, Node>>>()
         })
         .collect();
@@ -66,7 +74,8 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
     let mut enrs_and_content: HashMap<String, Vec<(RawContentKey, Vec<u8>)>> = HashMap::new();
 
     for (content_key, content_value) in content {
-        let interested_enrs = calculate_interested_enrs(&content_key, &all_nodes);
+        let interested_enrs =
+            calculate_interested_enrs::<_, TMetric>(&content_key, &all_nodes, peer_scores);
 
         // Temporarily store all randomly selected nodes with the content of interest.
         // We want this so we can offer all the content to an interested node in one request.
@@ -79,7 +88,8 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
         }
     }
 
-    let num_propagated_peers = enrs_and_content.len();
+    // Only count the peers whose OFFER was successfully handed to the overlay service
+    let mut num_propagated_peers = 0;
     // Create and send OFFER overlay request to the interested nodes
     for (enr_string, interested_content) in enrs_and_content.into_iter() {
         let enr = match Enr::from_str(&enr_string) {
@@ -90,19 +100,29 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
             }
         };
 
-        let offer_request = Request::PopulatedOffer(PopulatedOffer {
-            content_items: interested_content,
-        });
+        // A peer interested in more content than fits in a single OFFER gets several of them.
+        let mut offered = false;
+        for content_items in interested_content.chunks(MAX_CONTENT_KEYS_PER_OFFER) {
+            let offer_request = Request::PopulatedOffer(PopulatedOffer {
+                content_items: content_items.to_vec(),
+            });
 
-        let overlay_request = OverlayRequest::new(
-            offer_request,
-            RequestDirection::Outgoing { destination: enr },
-            None,
-            None,
-        );
+            let overlay_request = OverlayRequest::new(
+                offer_request,
+                RequestDirection::Outgoing {
+                    destination: enr.clone(),
+                },
+                None,
+                None,
+            );
 
-        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
-            error!(error = %err, "Error sending OFFER message to service")
+            match command_tx.send(OverlayCommand::Request(overlay_request)) {
+                Ok(_) => offered = true,
+                Err(err) => error!(error = %err, "Error sending OFFER message to service"),
+            }
+        }
+        if offered {
+            num_propagated_peers += 1;
         }
     }
 
@@ -113,10 +133,14 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
 /// This function is designed to be used via the JSON-RPC API. Since it is blocking, it should not
 /// be used internally in the offer/accept flow.
 /// Returns a trace detailing the outcome of the gossip.
-pub async fn trace_propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
+pub async fn trace_propagate_gossip_cross_thread<
+    TContentKey: OverlayContentKey,
+    TMetric: Metric,
+>(
     content_key: TContentKey,
     data: Vec<u8>,
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+    peer_scores: &PeerScores,
     command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
 ) -> GossipResult {
     let mut gossip_result = GossipResult::default();
@@ -138,7 +162,7 @@ pub async fn trace_propagate_gossip_cross_thread<TContentKey: OverlayContentKey>
             warn!("No nodes in routing table, gossip cannot proceed.");
             return gossip_result;
         }
-        calculate_interested_enrs(&content_key, &all_nodes)
+        calculate_interested_enrs::<_, TMetric>(&content_key, &all_nodes, peer_scores)
     };
     if interested_enrs.is_empty() {
         return gossip_result;
@@ -146,68 +170,194 @@ pub async fn trace_propagate_gossip_cross_thread<TContentKey: OverlayContentKey>
 
     // Create and send OFFER overlay request to the interested nodes
     for enr in interested_enrs.into_iter() {
-        let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel();
-        let offer_request = Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
-            content_item: (content_key.clone().into(), data.clone()),
-            result_tx,
-        });
+        let content_items = vec![(content_key.clone().into(), data.clone())];
+        match offer_with_result(&command_tx, enr.clone(), content_items).await {
+            OfferOutcome::NotSent => continue,
+            OfferOutcome::NotAccepted => {
+                // update gossip result with peer marked as being offered the content
+                gossip_result.offered.push(enr);
+            }
+            OfferOutcome::Accepted {
+                accept,
+                transferred,
+            } => {
+                gossip_result.offered.push(enr.clone());
+                if !accept.content_keys.is_zero() {
+                    // update gossip result with peer marked as accepting the content
+                    gossip_result.accepted.push(enr.clone());
+                }
+                if transferred {
+                    // update gossip result with peer marked as succesfully transferring the content
+                    gossip_result.transferred.push(enr);
+                }
+            }
+        }
+    }
+    gossip_result
+}
 
-        let (tx, rx) = oneshot::channel();
-        let responder = Some(tx);
-        let overlay_request = OverlayRequest::new(
-            offer_request,
-            RequestDirection::Outgoing {
-                destination: enr.clone(),
-            },
-            responder,
-            None,
-        );
-        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
-            error!(error = %err, "Error sending OFFER message to service");
-            continue;
+/// Propagate a batch of gossip in a way that can be used across threads, without &self.
+/// Like `trace_propagate_gossip_cross_thread`, but every interested peer is offered all of the
+/// content that it is interested in together, with OFFER requests of up to
+/// `MAX_CONTENT_KEYS_PER_OFFER` items, and the peers are offered the content concurrently.
+/// Returns a trace detailing the outcome of the gossip of each content item, in the order of the
+/// items.
+pub async fn trace_propagate_gossip_batch_cross_thread<
+    TContentKey: OverlayContentKey,
+    TMetric: Metric,
+>(
+    content: Vec<(TContentKey, Vec<u8>)>,
+    kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+    peer_scores: &PeerScores,
+    command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+) -> Vec<GossipResult> {
+    let mut gossip_results = vec![GossipResult::default(); content.len()];
+    // The OFFER requests to make, with the indices of the offered content items.
+    let mut offers: Vec<(Enr, Vec<usize>)> = vec![];
+    {
+        let kbuckets = kbuckets.read();
+        let all_nodes: Vec<&kbucket::Node<NodeId, Node>> = kbuckets
+            .buckets_iter()
+            .flat_map(|kbucket| {
+                kbucket
+                    .iter()
+                    .filter(|node| node.status.is_connected())
+                    .collect::<Vec<&kbucket::Node<NodeId, Node>>>()
+            })
+            .collect();
+
+        if all_nodes.is_empty() {
+            // If there are no nodes whatsoever in the routing table the gossip cannot proceed.
+            warn!("No nodes in routing table, gossip cannot proceed.");
+            return gossip_results;
+        }
+
+        let mut interested_content: HashMap<NodeId, (Enr, Vec<usize>)> = HashMap::new();
+        for (index, (content_key, _)) in content.iter().enumerate() {
+            for enr in calculate_interested_enrs::<_, TMetric>(content_key, &all_nodes, peer_scores)
+            {
+                interested_content
+                    .entry(enr.node_id())
+                    .or_insert_with(|| (enr, vec![]))
+                    .1
+                    .push(index);
+            }
         }
-        // update gossip result with peer marked as being offered the content
-        gossip_result.offered.push(enr.clone());
-        match rx.await {
-            Ok(res) => {
-                if let Ok(Response::Accept(accept)) = res {
-                    if !accept.content_keys.is_zero() {
-                        // update gossip result with peer marked as accepting the content
-                        gossip_result.accepted.push(enr.clone());
-                    }
-                } else {
-                    // continue to next peer if no content was accepted
-                    continue;
+        for (enr, indices) in interested_content.into_values() {
+            for chunk in indices.chunks(MAX_CONTENT_KEYS_PER_OFFER) {
+                offers.push((enr.clone(), chunk.to_vec()));
+            }
+        }
+    }
+
+    let outcomes = join_all(offers.into_iter().map(|(enr, indices)| {
+        let content_items = indices
+            .iter()
+            .map(|index| {
+                let (content_key, data) = &content[*index];
+                (content_key.clone().into(), data.clone())
+            })
+            .collect();
+        let command_tx = command_tx.clone();
+        async move {
+            let outcome = offer_with_result(&command_tx, enr.clone(), content_items).await;
+            (enr, indices, outcome)
+        }
+    }))
+    .await;
+
+    for (enr, indices, outcome) in outcomes {
+        let (accept, transferred) = match outcome {
+            OfferOutcome::NotSent => continue,
+            OfferOutcome::NotAccepted => (None, false),
+            OfferOutcome::Accepted {
+                accept,
+                transferred,
+            } => (Some(accept), transferred),
+        };
+        for (position, index) in indices.into_iter().enumerate() {
+            let gossip_result = &mut gossip_results[index];
+            gossip_result.offered.push(enr.clone());
+            let is_accepted = accept
+                .as_ref()
+                .and_then(|accept| accept.content_keys.get(position).ok())
+                .unwrap_or(false);
+            if is_accepted {
+                gossip_result.accepted.push(enr.clone());
+                if transferred {
+                    gossip_result.transferred.push(enr.clone());
                 }
             }
-            // continue to next peer if err while waiting for response
-            Err(_) => continue,
         }
-        if let Some(result) = result_rx.recv().await {
-            if result {
-                // update gossip result with peer marked as succesfully transferring the content
-                gossip_result.transferred.push(enr);
+    }
+    gossip_results
+}
+
+/// The outcome of an OFFER request that is made while tracing gossip.
+enum OfferOutcome {
+    /// The request couldn't be sent to the overlay service.
+    NotSent,
+    /// The peer didn't respond with an ACCEPT.
+    NotAccepted,
+    /// The peer responded with an ACCEPT, along with whether the accepted content was
+    /// successfully transferred.
+    Accepted { accept: Accept, transferred: bool },
+}
+
+/// Sends an OFFER request with the given content items to `enr` through the overlay service, and
+/// waits for the transfer of the accepted content to complete.
+async fn offer_with_result<TContentKey>(
+    command_tx: &mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    enr: Enr,
+    content_items: Vec<(RawContentKey, Vec<u8>)>,
+) -> OfferOutcome {
+    let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel();
+    let offer_request = Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
+        content_items,
+        result_tx,
+    });
+
+    let (tx, rx) = oneshot::channel();
+    let overlay_request = OverlayRequest::new(
+        offer_request,
+        RequestDirection::Outgoing { destination: enr },
+        Some(tx),
+        None,
+    );
+    if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
+        error!(error = %err, "Error sending OFFER message to service");
+        return OfferOutcome::NotSent;
+    }
+    match rx.await {
+        Ok(Ok(Response::Accept(accept))) => {
+            // The result is sent once the uTP transfer finishes, or immediately when no content
+            // was accepted.
+            let transferred = result_rx.recv().await.unwrap_or(false);
+            OfferOutcome::Accepted {
+                accept,
+                transferred,
             }
         }
+        _ => OfferOutcome::NotAccepted,
     }
-    gossip_result
 }
 
-/// Filter all nodes from overlay routing table where XOR_distance(content_id, nodeId) < node radius
-fn calculate_interested_enrs<TContentKey: OverlayContentKey>(
+/// Filter all nodes from overlay routing table where distance(content_id, nodeId) < node radius,
+/// according to the distance function of the overlay network.
+fn calculate_interested_enrs<TContentKey: OverlayContentKey, TMetric: Metric>(
     content_key: &TContentKey,
     all_nodes: &Vec<&kbucket::Node<NodeId, Node>>,
+    peer_scores: &PeerScores,
 ) -> Vec<Enr> {
     // HashMap to temporarily store all interested ENRs and the content.
     // Key is base64 string of node's ENR.
 
-    // Filter all nodes from overlay routing table where XOR_distance(content_id, nodeId) < node
-    // radius
+    // Filter all nodes from overlay routing table where distance(content_id, nodeId) < node radius
     let mut interested_enrs: Vec<Enr> = all_nodes
         .clone()
         .into_iter()
         .filter(|node| {
-            XorMetric::distance(&content_key.content_id(), &node.key.preimage().raw())
+            TMetric::distance(&content_key.content_id(), &node.key.preimage().raw())
                 < node.value.data_radius()
         })
         .map(|node| node.value.enr())
@@ -225,13 +375,15 @@ fn calculate_interested_enrs<TContentKey: OverlayContentKey>(
 
     // Sort all eligible nodes by proximity to the content.
     interested_enrs.sort_by(|a, b| {
-        let distance_a = XorMetric::distance(&content_key.content_id(), &a.node_id().raw());
-        let distance_b = XorMetric::distance(&content_key.content_id(), &b.node_id().raw());
+        let distance_a = TMetric::distance(&content_key.content_id(), &a.node_id().raw());
+        let distance_b = TMetric::distance(&content_key.content_id(), &b.node_id().raw());
         distance_a.partial_cmp(&distance_b).unwrap_or_else(|| {
             warn!(a = %distance_a, b = %distance_b, "Error comparing two distances");
             std::cmp::Ordering::Less
         })
     });
+    // Only gossip to unreliable peers if there aren't enough reliable peers.
+    peer_scores.prefer_reliable(&mut interested_enrs, |enr| enr.node_id());
 
     select_gossip_recipients(interested_enrs)
 }
@@ -275,9 +427,43 @@ fn select_gossip_recipients(interested_sorted_enrs: Vec<Enr>) -> Vec<Enr> {
 mod tests {
     use super::*;
 
+    use std::time::Duration;
+
+    use discv5::kbucket::{ConnectionDirection, ConnectionState, NodeStatus};
     use rstest::rstest;
 
-    use ethportal_api::types::enr::generate_random_remote_enr;
+    use ethportal_api::types::{
+        content_key::overlay::IdentityContentKey,
+        distance::{Distance, XorMetric},
+        enr::generate_random_remote_enr,
+    };
+    use ssz_types::BitList;
+
+    /// Returns a routing table with a single connected peer, which is interested in all content.
+    fn kbuckets_with_peer(peer_enr: &Enr) -> Arc<RwLock<KBucketsTable<NodeId, Node>>> {
+        let kbuckets = Arc::new(RwLock::new(KBucketsTable::new(
+            NodeId::random().into(),
+            Duration::from_secs(60),
+            16,
+            None,
+            None,
+        )));
+        let peer = Node::new(peer_enr.clone(), Distance::MAX);
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Incoming,
+        };
+        let _ = kbuckets
+            .write()
+            .insert_or_update(&peer_enr.node_id().into(), peer, status);
+        kbuckets
+    }
+
+    fn random_content(num_items: usize) -> Vec<(IdentityContentKey, Vec<u8>)> {
+        (0..num_items)
+            .map(|_| (IdentityContentKey::new(NodeId::random().raw()), vec![0xef]))
+            .collect()
+    }
 
     #[rstest]
     #[case(vec![generate_random_remote_enr().1; 0], 0)]
@@ -293,4 +479,163 @@ mod tests {
         let gossip_recipients = select_gossip_recipients(all_nodes);
         assert_eq!(gossip_recipients.len(), expected_size);
     }
+
+    #[test]
+    fn test_propagate_gossip_skips_source() {
+        let (_, peer_enr) = generate_random_remote_enr();
+        let kbuckets = kbuckets_with_peer(&peer_enr);
+
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let content = random_content(1);
+        let peer_scores = PeerScores::default();
+        let num_peers = propagate_gossip_cross_thread::<_, XorMetric>(
+            content.clone(),
+            kbuckets.clone(),
+            &peer_scores,
+            command_tx.clone(),
+            Some(peer_enr.node_id()),
+        );
+        assert_eq!(num_peers, 0);
+        assert!(command_rx.try_recv().is_err());
+
+        let num_peers = propagate_gossip_cross_thread::<_, XorMetric>(
+            content,
+            kbuckets,
+            &peer_scores,
+            command_tx,
+            None,
+        );
+        assert_eq!(num_peers, 1);
+        match command_rx.try_recv().unwrap() {
+            OverlayCommand::Request(request) => assert_eq!(
+                request.direction,
+                RequestDirection::Outgoing {
+                    destination: peer_enr
+                }
+            ),
+            _ => panic!("Unexpected overlay command variant"),
+        }
+    }
+
+    #[test]
+    fn test_propagate_gossip_chunks_offers() {
+        let (_, peer_enr) = generate_random_remote_enr();
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let num_peers = propagate_gossip_cross_thread::<_, XorMetric>(
+            random_content(MAX_CONTENT_KEYS_PER_OFFER + 2),
+            kbuckets_with_peer(&peer_enr),
+            &PeerScores::default(),
+            command_tx,
+            None,
+        );
+        assert_eq!(num_peers, 1);
+
+        let mut offer_sizes = vec![];
+        while let Ok(command) = command_rx.try_recv() {
+            match command {
+                OverlayCommand::Request(OverlayRequest {
+                    request: Request::PopulatedOffer(offer),
+                    ..
+                }) => offer_sizes.push(offer.content_items.len()),
+                _ => panic!("Unexpected overlay command"),
+            }
+        }
+        offer_sizes.sort();
+        assert_eq!(offer_sizes, vec![2, MAX_CONTENT_KEYS_PER_OFFER]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_trace_propagate_gossip_batch() {
+        let (_, peer_enr) = generate_random_remote_enr();
+        let (command_tx, mut command_rx) =
+            mpsc::unbounded_channel::<OverlayCommand<IdentityContentKey>>();
+        // The peer accepts all but the first content item of every OFFER.
+        tokio::spawn(async move {
+            while let Some(OverlayCommand::Request(request)) = command_rx.recv().await {
+                let Request::PopulatedOfferWithResult(offer) = request.request else {
+                    panic!("Unexpected request");
+                };
+                let mut content_keys = BitList::with_capacity(offer.content_items.len()).unwrap();
+                for index in 1..offer.content_items.len() {
+                    content_keys.set(index, true).unwrap();
+                }
+                let accept = Accept {
+                    connection_id: 0,
+                    content_keys,
+                };
+                let _ = request
+                    .responder
+                    .unwrap()
+                    .send(Ok(Response::Accept(accept)));
+                let _ = offer.result_tx.send(true);
+            }
+        });
+
+        let num_items = MAX_CONTENT_KEYS_PER_OFFER + 2;
+        let gossip_results = trace_propagate_gossip_batch_cross_thread::<_, XorMetric>(
+            random_content(num_items),
+            kbuckets_with_peer(&peer_enr),
+            &PeerScores::default(),
+            command_tx,
+        )
+        .await;
+
+        assert_eq!(gossip_results.len(), num_items);
+        assert!(gossip_results
+            .iter()
+            .all(|result| result.offered == vec![peer_enr.clone()]));
+        // The content is offered with two OFFERs, and the first item of each isn't accepted.
+        let num_declined = gossip_results
+            .iter()
+            .filter(|result| result.accepted.is_empty() && result.transferred.is_empty())
+            .count();
+        assert_eq!(num_declined, 2);
+        assert!(gossip_results
+            .iter()
+            .filter(|result| !result.accepted.is_empty())
+            .all(|result| result.transferred == vec![peer_enr.clone()]));
+    }
+
+    #[test]
+    fn test_calculate_interested_enrs_prefers_reliable_peers() {
+        let mut kbuckets = KBucketsTable::new(
+            NodeId::random().into(),
+            Duration::from_secs(60),
+            16,
+            None,
+            None,
+        );
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Incoming,
+        };
+        for _ in 0..NUM_CLOSEST_NODES + 1 {
+            let (_, enr) = generate_random_remote_enr();
+            let node = Node::new(enr.clone(), Distance::MAX);
+            let _ = kbuckets.insert_or_update(&enr.node_id().into(), node, status);
+        }
+        let all_nodes: Vec<&kbucket::Node<NodeId, Node>> = kbuckets
+            .buckets_iter()
+            .flat_map(|kbucket| kbucket.iter())
+            .collect();
+        let content_key = IdentityContentKey::new(NodeId::random().raw());
+
+        // The closest peer to the content is unreliable.
+        let closest = all_nodes
+            .iter()
+            .map(|node| *node.key.preimage())
+            .min_by_key(|node_id| XorMetric::distance(&content_key.content_id(), &node_id.raw()))
+            .unwrap();
+        let peer_scores = PeerScores::default();
+        peer_scores.record_failed_request(closest);
+        peer_scores.record_failed_request(closest);
+
+        let recipients =
+            calculate_interested_enrs::<_, XorMetric>(&content_key, &all_nodes, &peer_scores);
+        assert_eq!(recipients.len(), NUM_CLOSEST_NODES + 1);
+        assert!(recipients[..NUM_CLOSEST_NODES]
+            .iter()
+            .all(|enr| enr.node_id() != closest));
+        assert_eq!(recipients[NUM_CLOSEST_NODES].node_id(), closest);
+    }
 }
@@ -2,6 +2,7 @@
 #![warn(clippy::uninlined_format_args)]
 
 pub mod config;
+pub mod content_cache;
 pub mod discovery;
 pub mod events;
 pub mod find;
@@ -9,5 +10,7 @@ pub mod gossip;
 pub mod overlay;
 pub mod overlay_service;
 pub mod socket;
+pub mod store_actor;
 pub mod types;
 pub mod utils;
+pub mod write_batch;
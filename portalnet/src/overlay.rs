@@ -16,7 +16,7 @@ use std::{
     future::Future,
     marker::{PhantomData, Sync},
     sync::Arc,
-    time::Duration,
+    time::{Duration, UNIX_EPOCH},
 };
 use tokio::sync::{broadcast, mpsc::UnboundedSender};
 use tracing::{debug, error, info, warn};
@@ -25,22 +25,30 @@ use utp_rs::socket::UtpSocket;
 use crate::{
     discovery::{Discovery, UtpEnr},
     find::query_info::{FindContentResult, RecursiveFindContentResult},
-    gossip::{propagate_gossip_cross_thread, trace_propagate_gossip_cross_thread, GossipResult},
+    gossip::{
+        propagate_gossip_cross_thread, trace_propagate_gossip_batch_cross_thread,
+        trace_propagate_gossip_cross_thread, GossipResult,
+    },
     overlay_service::{
-        OverlayCommand, OverlayRequest, OverlayRequestError, OverlayService, RequestDirection,
-        UTP_CONN_CFG,
+        report_peer_offence, OverlayCommand, OverlayRequest, OverlayRequestError, OverlayService,
+        RequestDirection, UTP_CONN_CFG,
     },
-    types::node::Node,
+    store_actor::StoreHandle,
+    types::{node::Node, peer_activity::PeerActivity, peer_scores::PeerScores},
 };
 use ethportal_api::{
     types::{
+        admin::PeerInfo,
         bootnodes::Bootnode,
+        cli::{
+            DEFAULT_QUERY_PARALLELISM, DEFAULT_QUERY_PEER_TIMEOUT_SECS, DEFAULT_QUERY_TIMEOUT_SECS,
+        },
         discv5::RoutingTableInfo,
         distance::{Distance, Metric},
-        enr::Enr,
+        enr::{negotiate_protocol_version, Enr},
         portal_wire::{
             Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, Offer, Ping,
-            Pong, PopulatedOffer, ProtocolId, Request, Response,
+            Pong, PopulatedOffer, PopulatedOfferWithResult, ProtocolId, Request, Response,
         },
     },
     utils::bytes::hex_encode,
@@ -50,7 +58,7 @@ use trin_metrics::{overlay::OverlayMetricsReporter, portalnet::PORTALNET_METRICS
 use trin_storage::ContentStore;
 use trin_validation::validator::Validator;
 
-use crate::events::EventEnvelope;
+use crate::{content_cache::ContentCache, events::EventEnvelope, write_batch::WriteBatchConfig};
 
 /// Configuration parameters for the overlay network.
 #[derive(Clone)]
@@ -67,6 +75,11 @@ pub struct OverlayConfig {
     pub query_num_results: usize,
     pub findnodes_query_distances_per_peer: usize,
     pub disable_poke: bool,
+    pub write_batch: WriteBatchConfig,
+    /// Maximum total size of the recently served and stored content values kept in memory. The
+    /// cache is disabled by default, as it's only correct for content that never changes once
+    /// stored.
+    pub content_cache_capacity_bytes: usize,
 }
 
 impl Default for OverlayConfig {
@@ -78,12 +91,14 @@ impl Default for OverlayConfig {
             table_filter: None,
             bucket_filter: None,
             ping_queue_interval: None,
-            query_parallelism: 3, // (recommended α from kademlia paper)
-            query_peer_timeout: Duration::from_secs(2),
-            query_timeout: Duration::from_secs(60),
+            query_parallelism: DEFAULT_QUERY_PARALLELISM,
+            query_peer_timeout: Duration::from_secs(DEFAULT_QUERY_PEER_TIMEOUT_SECS),
+            query_timeout: Duration::from_secs(DEFAULT_QUERY_TIMEOUT_SECS),
             query_num_results: MAX_NODES_PER_BUCKET,
             findnodes_query_distances_per_peer: 3,
             disable_poke: false,
+            write_batch: WriteBatchConfig::default(),
+            content_cache_capacity_bytes: 0,
         }
     }
 }
@@ -98,8 +113,8 @@ type BucketEntry = (NodeId, Enr, NodeStatus, Distance, Option<String>);
 pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     /// Reference to the underlying discv5 protocol
     pub discovery: Arc<Discovery>,
-    /// The data store.
-    pub store: Arc<RwLock<TStore>>,
+    /// The data store, owned by its storage actor.
+    pub store: StoreHandle<TStore>,
     /// The overlay routing table of the local node.
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
     /// The subnetwork protocol of the overlay.
@@ -118,6 +133,10 @@ pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     validator: Arc<TValidator>,
     /// Runtime telemetry metrics for the overlay network.
     metrics: OverlayMetricsReporter,
+    /// Times at which peers were last heard from, recorded by the overlay service.
+    peer_activity: PeerActivity,
+    /// Statistics of peers, recorded by the overlay service and used to prefer reliable peers.
+    peer_scores: PeerScores,
 }
 
 impl<
@@ -133,10 +152,15 @@ where
         config: OverlayConfig,
         discovery: Arc<Discovery>,
         utp_socket: Arc<UtpSocket<UtpEnr>>,
-        store: Arc<RwLock<TStore>>,
+        store: TStore,
         protocol: ProtocolId,
         validator: Arc<TValidator>,
     ) -> Self {
+        let store = StoreHandle::spawn_with_content_cache(
+            store,
+            format!("{protocol}-store"),
+            ContentCache::new(config.content_cache_capacity_bytes),
+        );
         let kbuckets = Arc::new(RwLock::new(KBucketsTable::new(
             discovery.local_enr().node_id().into(),
             config.bucket_pending_timeout,
@@ -149,9 +173,11 @@ where
             overlay_metrics: PORTALNET_METRICS.overlay(),
             protocol: protocol.to_string(),
         };
+        let peer_activity = PeerActivity::default();
+        let peer_scores = PeerScores::default();
         let command_tx = OverlayService::<TContentKey, TMetric, TValidator, TStore>::spawn(
             Arc::clone(&discovery),
-            Arc::clone(&store),
+            store.clone(),
             Arc::clone(&kbuckets),
             config.bootnode_enrs,
             config.ping_queue_interval,
@@ -165,6 +191,9 @@ where
             config.query_num_results,
             config.findnodes_query_distances_per_peer,
             config.disable_poke,
+            config.write_batch,
+            peer_activity.clone(),
+            peer_scores.clone(),
         )
         .await;
 
@@ -179,6 +208,8 @@ where
             phantom_metric: PhantomData,
             validator,
             metrics,
+            peer_activity,
+            peer_scores,
         }
     }
 
@@ -194,7 +225,7 @@ where
 
     /// Returns the data radius of the local node.
     pub fn data_radius(&self) -> Distance {
-        self.store.read().radius()
+        self.store.radius()
     }
 
     /// Processes a single Discovery v5 TALKREQ message.
@@ -205,9 +236,15 @@ where
         let request = match Message::try_from(Vec::<u8>::from(talk_request.body())) {
             Ok(message) => match Request::try_from(message) {
                 Ok(request) => request,
-                Err(err) => return Err(OverlayRequestError::InvalidRequest(err.to_string())),
+                Err(err) => {
+                    self.report_offence(talk_request.node_id());
+                    return Err(OverlayRequestError::InvalidRequest(err.to_string()));
+                }
             },
-            Err(_) => return Err(OverlayRequestError::DecodeError),
+            Err(_) => {
+                self.report_offence(talk_request.node_id());
+                return Err(OverlayRequestError::DecodeError);
+            }
         };
         let direction = RequestDirection::Incoming {
             id: talk_request.id().clone(),
@@ -234,7 +271,13 @@ where
     /// Propagate gossip accepted content via OFFER/ACCEPT, return number of peers propagated
     pub fn propagate_gossip(&self, content: Vec<(TContentKey, Vec<u8>)>) -> usize {
         let kbuckets = Arc::clone(&self.kbuckets);
-        propagate_gossip_cross_thread(content, kbuckets, self.command_tx.clone())
+        propagate_gossip_cross_thread::<_, TMetric>(
+            content,
+            kbuckets,
+            &self.peer_scores,
+            self.command_tx.clone(),
+            None,
+        )
     }
 
     /// Propagate gossip accepted content via OFFER/ACCEPT, returns trace detailing outcome of
@@ -245,8 +288,31 @@ where
         data: Vec<u8>,
     ) -> GossipResult {
         let kbuckets = Arc::clone(&self.kbuckets);
-        trace_propagate_gossip_cross_thread(content_key, data, kbuckets, self.command_tx.clone())
-            .await
+        trace_propagate_gossip_cross_thread::<_, TMetric>(
+            content_key,
+            data,
+            kbuckets,
+            &self.peer_scores,
+            self.command_tx.clone(),
+        )
+        .await
+    }
+
+    /// Propagate a batch of content via OFFER/ACCEPT, offering every interested peer all of the
+    /// content it is interested in together. Returns a trace detailing the outcome of the gossip of
+    /// each content item.
+    pub async fn propagate_gossip_batch_trace(
+        &self,
+        content: Vec<(TContentKey, Vec<u8>)>,
+    ) -> Vec<GossipResult> {
+        let kbuckets = Arc::clone(&self.kbuckets);
+        trace_propagate_gossip_batch_cross_thread::<_, TMetric>(
+            content,
+            kbuckets,
+            &self.peer_scores,
+            self.command_tx.clone(),
+        )
+        .await
     }
 
     /// Returns a vector of all ENR node IDs of nodes currently contained in the routing table.
@@ -275,6 +341,38 @@ where
         }
     }
 
+    /// Returns the peers in the routing table, with their radius and the time they were last
+    /// heard from.
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        self.kbuckets
+            .write()
+            .iter()
+            .map(|entry| {
+                let node_id = *entry.node.key.preimage();
+                let enr = entry.node.value.enr();
+                let client = enr
+                    .get("c")
+                    .and_then(|client| std::str::from_utf8(client).ok())
+                    .map(String::from);
+                let last_seen = self
+                    .peer_activity
+                    .last_seen(&node_id)
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|time| time.as_secs());
+                let protocol_version = negotiate_protocol_version(&enr);
+                PeerInfo {
+                    enr,
+                    node_id: hex_encode(node_id.raw()),
+                    radius: *entry.node.value.data_radius(),
+                    connected: entry.status.is_connected(),
+                    last_seen,
+                    client,
+                    protocol_version,
+                }
+            })
+            .collect()
+    }
+
     /// Returns a map (BTree for its ordering guarantees) with:
     ///     key: usize representing bucket index
     ///     value: Vec of tuples, each tuple represents a node
@@ -367,10 +465,31 @@ where
         Err(OverlayRequestError::Failure("Couldn't get ENR".into()))
     }
 
+    /// Reports an offence of `node_id`, which gets the peer temporarily banned from all overlays
+    /// if it offends repeatedly.
+    fn report_offence(&self, node_id: &NodeId) {
+        report_peer_offence(&self.discovery, &self.kbuckets, &self.command_tx, node_id);
+    }
+
+    /// Records that `content_key` received from `node_id` failed validation, and emits the
+    /// failure on the event stream.
+    fn report_validation_failure(&self, content_key: &TContentKey, node_id: NodeId) {
+        self.peer_scores.record_validation_failure(node_id);
+        self.report_offence(&node_id);
+        let _ = self.command_tx.send(OverlayCommand::ValidationFailed {
+            content_key: content_key.clone(),
+            source: node_id,
+        });
+    }
+
     /// `DeleteEnr` deletes requested `enr` from our kbucket.
     pub fn delete_enr(&self, node_id: NodeId) -> bool {
         let key = &Key::from(node_id);
-        self.kbuckets.write().remove(key)
+        let removed = self.kbuckets.write().remove(key);
+        if removed {
+            let _ = self.command_tx.send(OverlayCommand::PeerRemoved(node_id));
+        }
+        removed
     }
 
     /// `LookupEnr` finds requested `enr` from our kbucket, FindNode, and RecursiveFindNode.
@@ -465,6 +584,7 @@ where
         let request = FindContent {
             content_key: content_key.clone(),
         };
+        let node_id = enr.node_id();
         let direction = RequestDirection::Outgoing {
             destination: enr.clone(),
         };
@@ -484,10 +604,13 @@ where
                     Content::Content(content) => {
                         match self.validate_content(&content_key, &content).await {
                             Ok(_) => Ok((Content::Content(content), false)),
-                            Err(msg) => Err(OverlayRequestError::FailedValidation(format!(
-                                "Network: {:?}, Reason: {msg:?}",
-                                self.protocol
-                            ))),
+                            Err(msg) => {
+                                self.report_validation_failure(&content_key, node_id);
+                                Err(OverlayRequestError::FailedValidation(format!(
+                                    "Network: {:?}, Reason: {msg:?}",
+                                    self.protocol
+                                )))
+                            }
                         }
                     }
                     Content::Enrs(_) => Ok((found_content, false)),
@@ -497,10 +620,13 @@ where
                         let content = self.init_find_content_stream(enr, conn_id).await?;
                         match self.validate_content(&content_key, &content).await {
                             Ok(_) => Ok((Content::Content(content), true)),
-                            Err(msg) => Err(OverlayRequestError::FailedValidation(format!(
-                                "Network: {:?}, Reason: {msg:?}",
-                                self.protocol
-                            ))),
+                            Err(msg) => {
+                                self.report_validation_failure(&content_key, node_id);
+                                Err(OverlayRequestError::FailedValidation(format!(
+                                    "Network: {:?}, Reason: {msg:?}",
+                                    self.protocol
+                                )))
+                            }
                         }
                     }
                 }
@@ -606,6 +732,37 @@ where
         }
     }
 
+    /// Sends an OFFER request with the given content items to the given ENR. Once the peer
+    /// responds with an ACCEPT, waits for the uTP transfer of the accepted content to complete.
+    /// Returns the ACCEPT along with whether the content was successfully transferred.
+    pub async fn send_offer_with_result(
+        &self,
+        enr: Enr,
+        content_items: Vec<(RawContentKey, Vec<u8>)>,
+    ) -> Result<(Accept, bool), OverlayRequestError> {
+        let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel();
+        let request = Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
+            content_items,
+            result_tx,
+        });
+
+        let direction = RequestDirection::Outgoing {
+            destination: enr.clone(),
+        };
+
+        // Send the request and wait on the response.
+        match self.send_overlay_request(request, direction).await {
+            Ok(Response::Accept(accept)) => {
+                // The result is sent once the uTP transfer finishes, or immediately when no
+                // content was accepted.
+                let transferred = result_rx.recv().await.unwrap_or(false);
+                Ok((accept, transferred))
+            }
+            Ok(_) => Err(OverlayRequestError::InvalidResponse),
+            Err(error) => Err(error),
+        }
+    }
+
     pub async fn lookup_node(&self, target: NodeId) -> Vec<Enr> {
         if target == self.local_enr().node_id() {
             return vec![self.local_enr()];
@@ -767,6 +924,16 @@ where
         self.metrics.get_utp_summary()
     }
 
+    /// Shuts the overlay service down, once it wrote the content that it accepted before and
+    /// stopped the storage actor. Requests through the overlay fail afterwards.
+    pub async fn shutdown(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        let command = OverlayCommand::Shutdown(done_tx);
+        if self.command_tx.send(command).is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+
     /// Creates an event stream channel which can be polled to receive overlay events.
     pub fn event_stream(
         &self,
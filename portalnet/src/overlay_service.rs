@@ -1,10 +1,10 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     marker::{PhantomData, Sync},
     sync::Arc,
     task::Poll,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
@@ -49,14 +49,17 @@ use crate::{
         query_pool::{QueryId, QueryPool, QueryPoolState, TargetKey},
     },
     gossip::propagate_gossip_cross_thread,
-    types::node::Node,
+    store_actor::StoreHandle,
+    types::{node::Node, peer_activity::PeerActivity, peer_scores::PeerScores},
     utils::portal_wire,
+    write_batch::{ContentWriter, WriteBatchConfig},
 };
 use ethportal_api::{
     generate_random_node_id,
     types::{
         distance::{Distance, Metric},
-        enr::{Enr, SszEnr},
+        enr::{negotiate_protocol_version, Enr, SszEnr},
+        jsonrpc::error::JsonRpcError,
         portal_wire::{
             Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, Offer, Ping,
             Pong, PopulatedOffer, ProtocolId, Request, Response, MAX_PORTAL_CONTENT_PAYLOAD_SIZE,
@@ -71,7 +74,7 @@ use trin_metrics::{
     labels::{UtpDirectionLabel, UtpOutcomeLabel},
     overlay::OverlayMetricsReporter,
 };
-use trin_storage::{ContentStore, ShouldWeStoreContent};
+use trin_storage::{error::ContentStoreError, ContentStore, ShouldWeStoreContent};
 use trin_validation::validator::Validator;
 
 pub const FIND_NODES_MAX_NODES: usize = 32;
@@ -87,8 +90,28 @@ const EXPECTED_NON_EMPTY_BUCKETS: usize = 17;
 /// Bucket refresh lookup interval in seconds
 const BUCKET_REFRESH_INTERVAL_SECS: u64 = 60;
 
-/// The capacity of the event-stream's broadcast channel.
-const EVENT_STREAM_CHANNEL_CAPACITY: usize = 10;
+/// Initial delay in seconds before retrying the bootnodes while there are no connected peers.
+/// The delay doubles after every retry, up to `MAX_BOOTNODE_RETRY_BACKOFF_SECS`.
+const INITIAL_BOOTNODE_RETRY_BACKOFF_SECS: u64 = 5;
+
+/// Maximum delay in seconds between retries of the bootnodes.
+const MAX_BOOTNODE_RETRY_BACKOFF_SECS: u64 = 300;
+
+/// The capacity of the event-stream's broadcast channel. Large enough for the bursts of peer
+/// events while the routing table fills up.
+const EVENT_STREAM_CHANNEL_CAPACITY: usize = 100;
+
+/// The number of peers with recorded activity above which peers that left the routing table are
+/// forgotten. A full routing table holds 256 buckets of 16 peers.
+const MAX_TRACKED_PEER_ACTIVITY: usize = 4096;
+
+/// Time in seconds during which content keys declined by a peer are not gossiped to that peer
+/// again.
+const DECLINED_OFFER_TIMEOUT_SECS: u64 = 60;
+
+/// Interval in seconds at which the local ENR is checked for updates, such as a new external
+/// address reported by peers, which are then announced to connected peers.
+const ENR_UPDATE_CHECK_INTERVAL_SECS: u64 = 10;
 
 lazy_static! {
     /// The default configuration to use for uTP connections.
@@ -128,6 +151,27 @@ pub enum OverlayCommand<TContentKey> {
     RequestEventStream(oneshot::Sender<broadcast::Receiver<EventEnvelope>>),
     /// Handle an event sent from another overlay.
     Event(EventEnvelope),
+    /// Respond to an incoming request, once the content of the store that the response depends on
+    /// was looked up.
+    RespondToRequest {
+        request: OverlayRequest,
+        id: RequestId,
+        source: NodeId,
+        lookup: Option<StoreLookup>,
+    },
+    /// Notify the event stream that content was received from the network and stored locally.
+    ContentStored(TContentKey),
+    /// Notify the event stream that content received from `source` failed validation.
+    ValidationFailed {
+        content_key: TContentKey,
+        source: NodeId,
+    },
+    /// Notify the event stream that a peer was removed from the routing table outside of the
+    /// overlay service, eg. because it got banned.
+    PeerRemoved(NodeId),
+    /// Stop the service, once the accepted content is written and the storage actor stopped. The
+    /// sender is notified afterwards.
+    Shutdown(oneshot::Sender<()>),
 }
 
 /// An overlay request error.
@@ -182,6 +226,10 @@ pub enum OverlayRequestError {
 
     #[error("Received invalid remote discv5 packet")]
     InvalidRemoteDiscv5Packet,
+
+    /// The peer doesn't support any portal protocol version supported by the local node.
+    #[error("Peer doesn't support a compatible protocol version")]
+    IncompatibleProtocolVersion,
 }
 
 impl From<discv5::RequestError> for OverlayRequestError {
@@ -194,6 +242,16 @@ impl From<discv5::RequestError> for OverlayRequestError {
     }
 }
 
+impl From<OverlayRequestError> for JsonRpcError {
+    fn from(err: OverlayRequestError) -> Self {
+        match err {
+            OverlayRequestError::InvalidRequest(_) => JsonRpcError::invalid_params(err.to_string()),
+            OverlayRequestError::ChannelFailure(_) => JsonRpcError::internal(err.to_string()),
+            _ => JsonRpcError::overlay_request_failed(err.to_string()),
+        }
+    }
+}
+
 /// An incoming or outgoing request.
 #[derive(Debug, PartialEq)]
 pub enum RequestDirection {
@@ -246,6 +304,15 @@ impl OverlayRequest {
     }
 }
 
+/// The content of the store that the response to an incoming request depends on.
+#[derive(Debug)]
+pub enum StoreLookup {
+    /// The content requested by a FINDCONTENT request, if it is stored.
+    Content(Result<Option<Vec<u8>>, ContentStoreError>),
+    /// Whether to store each content item offered by an OFFER request.
+    Availability(Result<Vec<ShouldWeStoreContent>, ContentStoreError>),
+}
+
 /// An active outgoing overlay request.
 struct ActiveOutgoingRequest {
     /// The ENR of the destination (target) node.
@@ -255,6 +322,8 @@ struct ActiveOutgoingRequest {
     pub request: Request,
     /// An optional QueryID for the query that this request is associated with.
     pub query_id: Option<QueryId>,
+    /// The time at which the request was sent.
+    pub sent_at: Instant,
 }
 
 /// A response for a particular overlay request.
@@ -269,8 +338,8 @@ struct OverlayResponse {
 pub struct OverlayService<TContentKey, TMetric, TValidator, TStore> {
     /// The underlying Discovery v5 protocol.
     discovery: Arc<Discovery>,
-    /// The content database of the local node.
-    store: Arc<RwLock<TStore>>,
+    /// The content database of the local node, owned by its storage actor.
+    store: StoreHandle<TStore>,
     /// The routing table of the local node.
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
     /// The protocol identifier.
@@ -280,6 +349,10 @@ pub struct OverlayService<TContentKey, TMetric, TValidator, TStore> {
     /// duration equal to some ping interval, and we continuously poll the queue to check for
     /// expired entries.
     peers_to_ping: HashSetDelay<NodeId>,
+    /// Content keys recently declined by peers in response to our OFFERs.
+    /// Inserted entries expire after a fixed time, until which the content is not gossiped to the
+    /// declining peer again.
+    declined_offers: HashSetDelay<(NodeId, RawContentKey)>,
     // TODO: This should probably be a bounded channel.
     /// The receiver half of the service command channel.
     command_rx: UnboundedReceiver<OverlayCommand<TContentKey>>,
@@ -318,6 +391,20 @@ pub struct OverlayService<TContentKey, TMetric, TValidator, TStore> {
     event_stream: broadcast::Sender<EventEnvelope>,
     /// Disable poke mechanism
     disable_poke: bool,
+    /// Times at which peers were last heard from.
+    peer_activity: PeerActivity,
+    /// Statistics of peers, used to prefer reliable peers.
+    peer_scores: PeerScores,
+    /// Writes content received through gossip to the store in batches.
+    content_writer: ContentWriter<TContentKey>,
+    /// The bootnodes, which are contacted again whenever there are no connected peers.
+    bootnode_enrs: Vec<Enr>,
+    /// The delay before the next retry of the bootnodes.
+    bootnode_retry_backoff: Duration,
+    /// The time at which the bootnodes are retried next, if the node is bootstrapping.
+    next_bootnode_retry: Option<tokio::time::Instant>,
+    /// The sequence number of the local ENR that connected peers were last pinged with.
+    local_enr_seq: u64,
 }
 
 impl<
@@ -337,7 +424,7 @@ where
     #[allow(clippy::too_many_arguments)]
     pub async fn spawn(
         discovery: Arc<Discovery>,
-        store: Arc<RwLock<TStore>>,
+        store: StoreHandle<TStore>,
         kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
         bootnode_enrs: Vec<Enr>,
         ping_queue_interval: Option<Duration>,
@@ -351,12 +438,17 @@ where
         query_num_results: usize,
         findnodes_query_distances_per_peer: usize,
         disable_poke: bool,
+        write_batch_config: WriteBatchConfig,
+        peer_activity: PeerActivity,
+        peer_scores: PeerScores,
     ) -> UnboundedSender<OverlayCommand<TContentKey>>
     where
         <TContentKey as TryFrom<Vec<u8>>>::Error: Send,
     {
         let (command_tx, command_rx) = mpsc::unbounded_channel();
         let internal_command_tx = command_tx.clone();
+        let content_writer =
+            ContentWriter::spawn(store.clone(), write_batch_config, command_tx.clone());
 
         let peers_to_ping = if let Some(interval) = ping_queue_interval {
             HashSetDelay::new(interval)
@@ -368,12 +460,16 @@ where
         let (event_stream, _) = broadcast::channel(EVENT_STREAM_CHANNEL_CAPACITY);
 
         tokio::spawn(async move {
+            let local_enr_seq = discovery.local_enr().seq();
             let mut service = Self {
                 discovery,
                 store,
                 kbuckets,
                 protocol,
                 peers_to_ping,
+                declined_offers: HashSetDelay::new(Duration::from_secs(
+                    DECLINED_OFFER_TIMEOUT_SECS,
+                )),
                 command_rx,
                 command_tx: internal_command_tx,
                 active_outgoing_requests: Arc::new(RwLock::new(HashMap::new())),
@@ -392,6 +488,13 @@ where
                 validator,
                 event_stream,
                 disable_poke,
+                peer_activity,
+                peer_scores,
+                content_writer,
+                bootnode_enrs: bootnode_enrs.clone(),
+                bootnode_retry_backoff: Duration::from_secs(INITIAL_BOOTNODE_RETRY_BACKOFF_SECS),
+                next_bootnode_retry: None,
+                local_enr_seq,
             };
 
             info!(protocol = %protocol, "Starting overlay service");
@@ -458,6 +561,7 @@ where
     /// Begins initial FINDNODES query to populate the routing table.
     fn initialize_routing_table(&mut self, bootnodes: Vec<Enr>) {
         self.add_bootnodes(bootnodes, false);
+        self.next_bootnode_retry = Some(tokio::time::Instant::now() + self.bootnode_retry_backoff);
         let local_node_id = self.local_enr().node_id();
 
         // Begin request for our local node ID.
@@ -469,6 +573,130 @@ where
         }
     }
 
+    /// Returns true if any peer in the routing table is connected.
+    fn has_connected_peers(&self) -> bool {
+        self.kbuckets
+            .read()
+            .buckets_iter()
+            .any(|bucket| bucket.iter().any(|node| node.status.is_connected()))
+    }
+
+    /// Contacts the bootnodes again if there are no connected peers, with exponential backoff
+    /// between retries, so that the node doesn't stay isolated when the bootnodes were
+    /// unreachable or the routing table emptied.
+    fn retry_bootnodes_if_isolated(&mut self) {
+        if self.bootnode_enrs.is_empty() || self.has_connected_peers() {
+            self.bootnode_retry_backoff = Duration::from_secs(INITIAL_BOOTNODE_RETRY_BACKOFF_SECS);
+            self.next_bootnode_retry = None;
+            return;
+        }
+
+        warn!(
+            protocol = %self.protocol,
+            backoff = ?self.bootnode_retry_backoff,
+            "No connected peers, retrying bootnodes",
+        );
+        self.add_bootnodes(self.bootnode_enrs.clone(), false);
+        for enr in self.bootnode_enrs.iter() {
+            self.ping_node(enr);
+        }
+        self.next_bootnode_retry = Some(tokio::time::Instant::now() + self.bootnode_retry_backoff);
+        self.bootnode_retry_backoff = (self.bootnode_retry_backoff * 2)
+            .min(Duration::from_secs(MAX_BOOTNODE_RETRY_BACKOFF_SECS));
+    }
+
+    /// Pings all connected peers if the local ENR changed since they were last pinged, for example
+    /// after Discv5 updated the external address, so that the peers request the new ENR.
+    fn announce_local_enr_update(&mut self) {
+        let enr_seq = self.local_enr().seq();
+        if enr_seq == self.local_enr_seq {
+            return;
+        }
+        self.local_enr_seq = enr_seq;
+
+        let connected_enrs = self.table_entries_enr();
+        info!(
+            protocol = %self.protocol,
+            enr.seq = enr_seq,
+            peers = connected_enrs.len(),
+            "Local ENR updated, pinging connected peers",
+        );
+        for enr in connected_enrs.iter() {
+            self.ping_node(enr);
+        }
+    }
+
+    /// Processes a command sent to the overlay service.
+    fn process_command(&mut self, command: OverlayCommand<TContentKey>) {
+        match command {
+            OverlayCommand::Request(request) => self.process_request(request),
+            OverlayCommand::Event(event) => self.process_event(event),
+            OverlayCommand::FindContentQuery {
+                target,
+                callback,
+                is_trace,
+            } => {
+                if let Some(query_id) =
+                    self.init_find_content_query(target.clone(), Some(callback), is_trace)
+                {
+                    trace!(
+                        query.id = %query_id,
+                        content.id = %hex_encode_compact(target.content_id()),
+                        content.key = %target,
+                        "FindContent query initialized"
+                    );
+                }
+            }
+            OverlayCommand::FindNodeQuery { target, callback } => {
+                if let Some(query_id) = self.init_find_nodes_query(&target, Some(callback)) {
+                    trace!(
+                        query.id = %query_id,
+                        node.id = %hex_encode_compact(target),
+                        "FindNode query initialized"
+                    );
+                }
+            }
+            OverlayCommand::RequestEventStream(callback) => {
+                if callback.send(self.event_stream.subscribe()).is_err() {
+                    error!("Failed to return the event stream channel");
+                }
+            }
+            OverlayCommand::RespondToRequest {
+                request,
+                id,
+                source,
+                lookup,
+            } => {
+                self.respond_to_request(request, id, source, lookup);
+            }
+            OverlayCommand::ContentStored(content_key) => {
+                self.send_local_event(OverlayEvent::ContentStored(content_key.into()));
+            }
+            OverlayCommand::ValidationFailed {
+                content_key,
+                source,
+            } => {
+                self.send_local_event(OverlayEvent::ValidationFailed {
+                    peer: source,
+                    content_key: content_key.into(),
+                });
+            }
+            OverlayCommand::PeerRemoved(node_id) => {
+                self.send_local_event(OverlayEvent::PeerRemoved(node_id));
+            }
+            // Handled by the main loop, which stops once the service is shut down.
+            OverlayCommand::Shutdown(_) => {}
+        }
+    }
+
+    /// Writes the content that was accepted and queued for the store, and stops the storage actor.
+    /// Content that is still being transferred or validated isn't stored.
+    async fn shutdown(&mut self) {
+        info!(protocol = %self.protocol, "Shutting down overlay service");
+        self.content_writer.shutdown().await;
+        self.store.shutdown().await;
+    }
+
     /// The main loop for the overlay service. The loop selects over different possible tasks to
     /// perform.
     ///
@@ -482,42 +710,28 @@ where
     /// information relevant to the overlay network.
     ///
     /// Bucket maintenance: Maintain the routing table (more info documented above function).
+    ///
+    /// Bootnode retry: Contact the bootnodes again while there are no connected peers.
+    ///
+    /// ENR update: Ping connected peers when the local ENR changed.
+    ///
+    /// Shutdown: Write the accepted content, stop the storage actor and return.
     async fn start(&mut self) {
         // Construct bucket refresh interval
         let mut bucket_refresh_interval =
             tokio::time::interval(Duration::from_secs(BUCKET_REFRESH_INTERVAL_SECS));
+        let mut enr_update_interval =
+            tokio::time::interval(Duration::from_secs(ENR_UPDATE_CHECK_INTERVAL_SECS));
 
         loop {
             tokio::select! {
                 Some(command) = self.command_rx.recv() => {
-                    match command {
-                        OverlayCommand::Request(request) => self.process_request(request),
-                        OverlayCommand::Event(event) => self.process_event(event),
-                        OverlayCommand::FindContentQuery { target, callback, is_trace } => {
-                            if let Some(query_id) = self.init_find_content_query(target.clone(), Some(callback), is_trace) {
-                                trace!(
-                                    query.id = %query_id,
-                                    content.id = %hex_encode_compact(target.content_id()),
-                                    content.key = %target,
-                                    "FindContent query initialized"
-                                );
-                            }
-                        }
-                        OverlayCommand::FindNodeQuery { target, callback } => {
-                            if let Some(query_id) = self.init_find_nodes_query(&target, Some(callback)) {
-                                trace!(
-                                    query.id = %query_id,
-                                    node.id = %hex_encode_compact(target),
-                                    "FindNode query initialized"
-                                );
-                            }
-                        }
-                        OverlayCommand::RequestEventStream(callback) => {
-                            if callback.send(self.event_stream.subscribe()).is_err() {
-                                error!("Failed to return the event stream channel");
-                            }
-                        }
+                    if let OverlayCommand::Shutdown(done_tx) = command {
+                        self.shutdown().await;
+                        let _ = done_tx.send(());
+                        return;
                     }
+                    self.process_command(command)
                 }
                 Some(response) = self.response_rx.recv() => {
                     // Look up active request that corresponds to the response.
@@ -532,10 +746,11 @@ where
                         // Perform background processing.
                         match response.response {
                             Ok(response) => {
+                                self.peer_scores.record_response(request.destination.node_id(), request.sent_at.elapsed());
                                 self.metrics.report_inbound_response(&response);
                                 self.process_response(response, request.destination, request.request, request.query_id)
                             }
-                            Err(error) => self.process_request_failure(response.request_id, request.destination, error),
+                            Err(error) => self.process_request_failure(response.request_id, request.destination, error, request.query_id),
                         }
 
                     } else {
@@ -543,13 +758,20 @@ where
                     }
                 }
                 Some(Ok(node_id)) = self.peers_to_ping.next() => {
-                    // If the node is in the routing table, then ping and re-queue the node.
+                    // Peers banned by any overlay are removed from the routing table.
                     let key = kbucket::Key::from(node_id);
-                    if let kbucket::Entry::Present(ref mut entry, _) = self.kbuckets.write().entry(&key) {
+                    if self.discovery.peer_bans().is_banned(&node_id) {
+                        if self.kbuckets.write().remove(&key) {
+                            self.send_local_event(OverlayEvent::PeerRemoved(node_id));
+                        }
+                    } else if let kbucket::Entry::Present(ref mut entry, _) = self.kbuckets.write().entry(&key) {
+                        // If the node is in the routing table, then ping and re-queue the node.
                         self.ping_node(&entry.value().enr());
                         self.peers_to_ping.insert(node_id);
                     }
                 }
+                // Forget expired declined offers, so that the content may be offered again.
+                Some(Ok(_)) = self.declined_offers.next() => {}
                 query_event = OverlayService::<TContentKey, TMetric, TValidator, TStore>::query_event_poll(self.find_node_query_pool.clone()) => {
                     self.handle_find_nodes_query_event(query_event);
                 }
@@ -561,6 +783,15 @@ where
                 _ = bucket_refresh_interval.tick() => {
                     trace!(protocol = %self.protocol, "Routing table bucket refresh");
                     self.bucket_refresh_lookup();
+                    if self.next_bootnode_retry.is_none() {
+                        self.retry_bootnodes_if_isolated();
+                    }
+                }
+                _ = tokio::time::sleep_until(self.next_bootnode_retry.unwrap_or_else(tokio::time::Instant::now)), if self.next_bootnode_retry.is_some() => {
+                    self.retry_bootnodes_if_isolated();
+                }
+                _ = enr_update_interval.tick() => {
+                    self.announce_local_enr_update();
                 }
             }
         }
@@ -573,15 +804,19 @@ where
         // buckets are going to be empty-ish.
         let target_node_id = {
             let buckets = self.kbuckets.read();
-            let buckets = buckets.buckets_iter().enumerate().collect::<Vec<_>>();
-            let buckets = &buckets[256 - EXPECTED_NON_EMPTY_BUCKETS..];
-
-            // Randomly pick one of these buckets.
-            let target_bucket = buckets.choose(&mut rand::thread_rng());
+            let bucket_sizes = buckets
+                .buckets_iter()
+                .map(|bucket| bucket.num_entries())
+                .enumerate()
+                .collect::<Vec<_>>();
+
+            // Randomly pick one of these buckets, preferring those that are under-populated.
+            let target_bucket =
+                choose_bucket_to_refresh(&bucket_sizes[256 - EXPECTED_NON_EMPTY_BUCKETS..]);
             match target_bucket {
                 Some(bucket) => {
-                    trace!(protocol = %self.protocol, bucket = %bucket.0, "Refreshing routing table bucket");
-                    match u8::try_from(bucket.0) {
+                    trace!(protocol = %self.protocol, bucket = %bucket, "Refreshing routing table bucket");
+                    match u8::try_from(bucket) {
                         Ok(idx) => generate_random_node_id(idx, self.local_enr().into()),
                         Err(err) => {
                             error!(error = %err, "Error downcasting bucket index");
@@ -606,7 +841,7 @@ where
 
     /// Returns the data radius of the node.
     fn data_radius(&self) -> Distance {
-        self.store.read().radius()
+        self.store.radius()
     }
 
     /// Maintains the routing table.
@@ -772,7 +1007,8 @@ where
                         query.id = %query_id,
                         "Cannot query peer with unknown ENR"
                     );
-                    if let Some((_, query)) = self.find_node_query_pool.write().get_mut(query_id) {
+                    if let Some((_, query)) = self.find_content_query_pool.write().get_mut(query_id)
+                    {
                         query.on_failure(&node_id);
                     }
                 }
@@ -983,7 +1219,7 @@ where
     }
 
     /// Processes an overlay request.
-    fn process_request(&mut self, request: OverlayRequest) {
+    fn process_request(&mut self, mut request: OverlayRequest) {
         // For incoming requests, handle the request, possibly send the response over the channel,
         // and then process the request.
         //
@@ -992,20 +1228,64 @@ where
         // channel if the request was initiated internally (e.g. for maintenance).
         match request.direction {
             RequestDirection::Incoming { id, source } => {
+                let is_incompatible = self
+                    .discovery
+                    .cached_node_addr(&source)
+                    .is_some_and(|node_addr| negotiate_protocol_version(&node_addr.enr).is_none());
+                if is_incompatible {
+                    if let Some(responder) = request.responder {
+                        let _ =
+                            responder.send(Err(OverlayRequestError::IncompatibleProtocolVersion));
+                    }
+                    return;
+                }
                 self.register_node_activity(source);
 
-                let response = self.handle_request(request.request.clone(), id.clone(), &source);
-                // Send response to responder if present.
-                if let Some(responder) = request.responder {
-                    if let Ok(ref response) = response {
-                        self.metrics.report_outbound_response(response);
-                    }
-                    let _ = responder.send(response);
+                // Requests whose response depends on the content of the store are responded to
+                // once the content was looked up off the service task, so that the service never
+                // waits on disk I/O.
+                if matches!(request.request, Request::FindContent(_) | Request::Offer(_)) {
+                    let store = self.store.clone();
+                    let command_tx = self.command_tx.clone();
+                    tokio::spawn(async move {
+                        let lookup = Self::lookup_store(store, &request.request).await;
+                        let _ = command_tx.send(OverlayCommand::RespondToRequest {
+                            request,
+                            id,
+                            source,
+                            lookup,
+                        });
+                    });
+                    return;
                 }
-                // Perform background processing.
-                self.process_incoming_request(request.request, id, source);
+                self.respond_to_request(request, id, source, None);
             }
             RequestDirection::Outgoing { destination } => {
+                if negotiate_protocol_version(&destination).is_none() {
+                    if let Some(responder) = request.responder {
+                        let _ =
+                            responder.send(Err(OverlayRequestError::IncompatibleProtocolVersion));
+                    }
+                    return;
+                }
+                // Don't gossip content to a peer that recently declined it. Offers requested by a
+                // caller are sent as requested.
+                if request.responder.is_none() {
+                    if let Request::PopulatedOffer(offer) = &mut request.request {
+                        offer.content_items = self.remove_declined_content_items(
+                            &destination.node_id(),
+                            std::mem::take(&mut offer.content_items),
+                        );
+                        if offer.content_items.is_empty() {
+                            trace!(
+                                protocol = %self.protocol,
+                                peer.node_id = %destination.node_id(),
+                                "Skipping OFFER of content recently declined by peer"
+                            );
+                            return;
+                        }
+                    }
+                }
                 self.active_outgoing_requests.write().insert(
                     request.id,
                     ActiveOutgoingRequest {
@@ -1013,6 +1293,7 @@ where
                         responder: request.responder,
                         request: request.request.clone(),
                         query_id: request.query_id,
+                        sent_at: Instant::now(),
                     },
                 );
                 self.metrics.report_outbound_request(&request.request);
@@ -1021,28 +1302,91 @@ where
         }
     }
 
+    /// Looks up the content of the store that the response to `request` depends on, if any.
+    async fn lookup_store(store: StoreHandle<TStore>, request: &Request) -> Option<StoreLookup> {
+        // Requests with invalid content keys are rejected when building the response.
+        match request {
+            Request::FindContent(find_content) => {
+                let Ok(content_key) = TContentKey::try_from(find_content.content_key.clone())
+                else {
+                    return Some(StoreLookup::Content(Ok(None)));
+                };
+                Some(StoreLookup::Content(store.get(content_key).await))
+            }
+            Request::Offer(offer) => {
+                let content_keys: Result<Vec<TContentKey>, _> = offer
+                    .content_keys
+                    .iter()
+                    .map(|content_key| TContentKey::try_from(content_key.clone()))
+                    .collect();
+                let availability = match content_keys {
+                    Ok(content_keys) => {
+                        store
+                            .read(move |store| {
+                                content_keys
+                                    .iter()
+                                    .map(|content_key| {
+                                        store.is_key_within_radius_and_unavailable(content_key)
+                                    })
+                                    .collect()
+                            })
+                            .await
+                    }
+                    Err(_) => Ok(vec![]),
+                };
+                Some(StoreLookup::Availability(availability))
+            }
+            _ => None,
+        }
+    }
+
+    /// Responds to an incoming request, then processes it.
+    fn respond_to_request(
+        &mut self,
+        request: OverlayRequest,
+        id: RequestId,
+        source: NodeId,
+        lookup: Option<StoreLookup>,
+    ) {
+        let response = self.handle_request(request.request.clone(), id.clone(), &source, lookup);
+        // Send response to responder if present.
+        if let Some(responder) = request.responder {
+            if let Ok(ref response) = response {
+                self.metrics.report_outbound_response(response);
+            }
+            let _ = responder.send(response);
+        }
+        // Perform background processing.
+        self.process_incoming_request(request.request, id, source);
+    }
+
     /// Process an event dispatched by another overlay on the discovery.
     fn process_event(&mut self, _event: EventEnvelope) {}
 
-    /// Attempts to build a response for a request.
+    /// Attempts to build a response for a request, given the content of the store that the
+    /// response depends on.
     fn handle_request(
         &mut self,
         request: Request,
         id: RequestId,
         source: &NodeId,
+        lookup: Option<StoreLookup>,
     ) -> Result<Response, OverlayRequestError> {
-        match request {
-            Request::Ping(ping) => Ok(Response::Pong(self.handle_ping(ping, source, id))),
-            Request::FindNodes(find_nodes) => Ok(Response::Nodes(
+        match (request, lookup) {
+            (Request::Ping(ping), _) => Ok(Response::Pong(self.handle_ping(ping, source, id))),
+            (Request::FindNodes(find_nodes), _) => Ok(Response::Nodes(
                 self.handle_find_nodes(find_nodes, source, id),
             )),
-            Request::FindContent(find_content) => Ok(Response::Content(self.handle_find_content(
-                find_content,
-                source,
-                id,
-            )?)),
-            Request::Offer(offer) => Ok(Response::Accept(self.handle_offer(offer, source, id)?)),
-            Request::PopulatedOffer(_) | Request::PopulatedOfferWithResult(_) => {
+            (Request::FindContent(find_content), Some(StoreLookup::Content(content))) => Ok(
+                Response::Content(self.handle_find_content(find_content, source, id, content)?),
+            ),
+            (Request::Offer(offer), Some(StoreLookup::Availability(availability))) => Ok(
+                Response::Accept(self.handle_offer(offer, source, id, availability)?),
+            ),
+            (Request::FindContent(_), _) | (Request::Offer(_), _) => Err(
+                OverlayRequestError::Failure("Content of the store wasn't looked up".to_owned()),
+            ),
+            (Request::PopulatedOffer(_), _) | (Request::PopulatedOfferWithResult(_), _) => {
                 Err(OverlayRequestError::InvalidRequest(
                     "An offer with content attached is not a valid network message to receive"
                         .to_owned(),
@@ -1100,12 +1444,14 @@ where
         Nodes { total: 1, enrs }
     }
 
-    /// Attempts to build a `Content` response for a `FindContent` request.
+    /// Attempts to build a `Content` response for a `FindContent` request, given the requested
+    /// `content` of the store.
     fn handle_find_content(
         &self,
         request: FindContent,
         source: &NodeId,
         request_id: RequestId,
+        content: Result<Option<Vec<u8>>, ContentStoreError>,
     ) -> Result<Content, OverlayRequestError> {
         trace!(
             protocol = %self.protocol,
@@ -1122,7 +1468,7 @@ where
                 ))
             }
         };
-        match self.store.read().get(&content_key) {
+        match content {
             Ok(Some(content)) => {
                 if content.len() <= MAX_PORTAL_CONTENT_PAYLOAD_SIZE {
                     Ok(Content::Content(content))
@@ -1193,12 +1539,14 @@ where
         }
     }
 
-    /// Attempts to build an `Accept` response for an `Offer` request.
+    /// Attempts to build an `Accept` response for an `Offer` request, given the `availability` of
+    /// the offered content in the store.
     fn handle_offer(
         &self,
         request: Offer,
         source: &NodeId,
         request_id: RequestId,
+        availability: Result<Vec<ShouldWeStoreContent>, ContentStoreError>,
     ) -> Result<Accept, OverlayRequestError> {
         trace!(
             protocol = %self.protocol,
@@ -1225,18 +1573,12 @@ where
                 )
             })?;
 
-        for (i, key) in content_keys.iter().enumerate() {
+        let availability = availability.map_err(|err| {
+            OverlayRequestError::AcceptError(format!("Unable to check content availability {err}"))
+        })?;
+        for (i, availability) in availability.iter().enumerate() {
             // Accept content if within radius and not already present in the data store.
-            let accept = self
-                .store
-                .read()
-                .is_key_within_radius_and_unavailable(key)
-                .map(|value| matches!(value, ShouldWeStoreContent::Store))
-                .map_err(|err| {
-                    OverlayRequestError::AcceptError(format!(
-                        "Unable to check content availability {err}"
-                    ))
-                })?;
+            let accept = matches!(availability, ShouldWeStoreContent::Store);
             requested_keys.set(i, accept).map_err(|err| {
                 OverlayRequestError::AcceptError(format!(
                     "Unable to set requested keys bits: {err:?}"
@@ -1266,12 +1608,16 @@ where
         };
         let cid = self.utp_socket.cid(enr, false);
         let cid_send = cid.send;
+        let source = *source;
         let validator = Arc::clone(&self.validator);
-        let store = Arc::clone(&self.store);
+        let store = self.store.clone();
+        let discovery = Arc::clone(&self.discovery);
         let kbuckets = Arc::clone(&self.kbuckets);
         let command_tx = self.command_tx.clone();
+        let content_writer = self.content_writer.clone();
         let utp = Arc::clone(&self.utp_socket);
         let metrics = self.metrics.clone();
+        let peer_scores = self.peer_scores.clone();
 
         let content_keys_string: Vec<String> = content_keys
             .iter()
@@ -1299,6 +1645,7 @@ where
                         UtpDirectionLabel::Inbound,
                         UtpOutcomeLabel::FailedConnection,
                     );
+                    peer_scores.record_utp_transfer(source, false);
                     debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), content_keys = ?content_keys_string, "unable to accept uTP stream");
                     return;
                 }
@@ -1308,19 +1655,25 @@ where
             if let Err(err) = stream.read_to_eof(&mut data).await {
                 metrics
                     .report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::FailedDataTx);
+                peer_scores.record_utp_transfer(source, false);
                 debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), content_keys = ?content_keys_string, "error reading data from uTP stream, while handling an Offer request.");
                 return;
             }
 
             // report utp tx as successful, even if we go on to fail to process the payload
             metrics.report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::Success);
+            peer_scores.record_utp_transfer(source, true);
 
             if let Err(err) = Self::process_accept_utp_payload(
                 validator,
                 store,
                 metrics,
+                discovery,
                 kbuckets,
+                peer_scores,
                 command_tx,
+                content_writer,
+                source,
                 content_keys,
                 data,
             )
@@ -1379,6 +1732,8 @@ where
 
     /// Register source NodeId activity in overlay routing table
     fn register_node_activity(&mut self, source: NodeId) {
+        self.record_peer_activity(source);
+
         // Look up the node in the routing table.
         let key = kbucket::Key::from(source);
         let is_node_in_table = matches!(
@@ -1412,6 +1767,28 @@ where
         }
     }
 
+    /// Records that a message was received from `node_id`.
+    ///
+    /// Activity is only reported for peers in the routing table, so the activity and statistics of
+    /// peers that left it are forgotten once too many peers are tracked.
+    fn record_peer_activity(&self, node_id: NodeId) {
+        self.peer_activity.record(node_id);
+        if self.peer_activity.len() > MAX_TRACKED_PEER_ACTIVITY
+            || self.peer_scores.len() > MAX_TRACKED_PEER_ACTIVITY
+        {
+            let table_node_ids: HashSet<NodeId> = self
+                .kbuckets
+                .write()
+                .iter()
+                .map(|entry| *entry.node.key.preimage())
+                .collect();
+            self.peer_activity
+                .retain(|node_id| table_node_ids.contains(node_id));
+            self.peer_scores
+                .retain(|node_id| table_node_ids.contains(node_id));
+        }
+    }
+
     /// Processes a ping request from some source node.
     fn process_ping(&self, ping: Ping, source: NodeId) {
         // Look up the node in the routing table.
@@ -1447,6 +1824,7 @@ where
         request_id: OverlayRequestId,
         destination: Enr,
         error: OverlayRequestError,
+        query_id: Option<QueryId>,
     ) {
         debug!(
             protocol = %self.protocol,
@@ -1456,9 +1834,29 @@ where
             "Request failed",
         );
 
+        // Record the failure in the trace of the associated content query, if there is one.
+        if let Some(query_id) = query_id {
+            if let Some((query_info, _)) = self.find_content_query_pool.write().get_mut(query_id) {
+                if let Some(trace) = &mut query_info.trace {
+                    trace.node_failed(&destination, error.to_string());
+                }
+            }
+        }
+
         // Attempt to mark the node as disconnected.
         let node_id = destination.node_id();
-        let _ = self.update_node_connection_state(node_id, ConnectionState::Disconnected);
+        self.peer_scores.record_failed_request(node_id);
+        let was_connected = matches!(
+            self.kbuckets.write().entry(&kbucket::Key::from(node_id)),
+            kbucket::Entry::Present(_, status) if status.is_connected()
+        );
+        if self
+            .update_node_connection_state(node_id, ConnectionState::Disconnected)
+            .is_ok()
+            && was_connected
+        {
+            self.send_local_event(OverlayEvent::PeerRemoved(node_id));
+        }
         // Remove the node from the ping queue.
         self.peers_to_ping.remove(&node_id);
     }
@@ -1471,6 +1869,8 @@ where
         request: Request,
         query_id: Option<QueryId>,
     ) {
+        self.record_peer_activity(source.node_id());
+
         // If the node is present in the routing table, but the node is not connected, then
         // use the existing entry's value and direction. Otherwise, build a new entry from
         // the source ENR and establish a connection in the outgoing direction, because this
@@ -1524,21 +1924,60 @@ where
         }
     }
 
+    /// Returns the content items that `node_id` has not recently declined.
+    fn remove_declined_content_items(
+        &self,
+        node_id: &NodeId,
+        content_items: Vec<(RawContentKey, Vec<u8>)>,
+    ) -> Vec<(RawContentKey, Vec<u8>)> {
+        content_items
+            .into_iter()
+            .filter(|(key, _)| !self.declined_offers.contains_key(&(*node_id, key.clone())))
+            .collect()
+    }
+
     // Process ACCEPT response
-    fn process_accept(&self, response: Accept, enr: Enr, offer: Request) -> anyhow::Result<Accept> {
+    fn process_accept(
+        &mut self,
+        response: Accept,
+        enr: Enr,
+        offer: Request,
+    ) -> anyhow::Result<Accept> {
         // Check that a valid triggering request was sent
         let mut gossip_result_tx = None;
-        match &offer {
-            Request::Offer(_) => {}
-            Request::PopulatedOffer(_) => {}
+        let offered_keys: Vec<&RawContentKey> = match &offer {
+            Request::Offer(req) => req.content_keys.iter().collect(),
+            Request::PopulatedOffer(req) => req.content_items.iter().map(|(key, _)| key).collect(),
             Request::PopulatedOfferWithResult(req) => {
-                gossip_result_tx = Some(req.result_tx.clone())
+                gossip_result_tx = Some(req.result_tx.clone());
+                req.content_items.iter().map(|(key, _)| key).collect()
             }
             _ => {
                 return Err(anyhow!("Invalid request message paired with ACCEPT"));
             }
         };
 
+        // Record which of the offered content keys were declined, so that they are not offered
+        // to the same peer again right away.
+        let mut accepted_keys = vec![];
+        let mut num_declined = 0;
+        for (is_accepted, key) in response.content_keys.iter().zip(offered_keys) {
+            if is_accepted {
+                accepted_keys.push(key.clone());
+            } else {
+                num_declined += 1;
+                self.declined_offers.insert((enr.node_id(), key.clone()));
+            }
+        }
+        self.metrics
+            .report_offered_content_keys(accepted_keys.len() as u64, num_declined);
+        if !accepted_keys.is_empty() {
+            self.send_local_event(OverlayEvent::OfferAccepted {
+                peer: enr.node_id(),
+                content_keys: accepted_keys,
+            });
+        }
+
         // Do not initialize uTP stream if remote node doesn't have interest in the offered content
         // keys
         if response.content_keys.is_zero() {
@@ -1556,11 +1995,12 @@ where
             peer: crate::discovery::UtpEnr(enr),
         };
 
-        let store = Arc::clone(&self.store);
+        let store = self.store.clone();
         let response_clone = response.clone();
 
         let utp = Arc::clone(&self.utp_socket);
         let metrics = self.metrics.clone();
+        let peer_scores = self.peer_scores.clone();
 
         tokio::spawn(async move {
             metrics.report_utp_active_inc(UtpDirectionLabel::Outbound);
@@ -1571,6 +2011,7 @@ where
                         UtpDirectionLabel::Outbound,
                         UtpOutcomeLabel::FailedConnection,
                     );
+                    peer_scores.record_utp_transfer(cid.peer.node_id(), false);
                     debug!(
                         %err,
                         cid.send,
@@ -1588,6 +2029,7 @@ where
             let content_items = match offer {
                 Request::Offer(offer) => {
                     Self::provide_requested_content(store, &response_clone, offer.content_keys)
+                        .await
                 }
                 Request::PopulatedOffer(offer) => Ok(response_clone
                     .content_keys
@@ -1599,7 +2041,7 @@ where
                 Request::PopulatedOfferWithResult(offer) => Ok(response_clone
                     .content_keys
                     .iter()
-                    .zip(vec![offer.content_item])
+                    .zip(offer.content_items)
                     .filter(|(is_accepted, _item)| *is_accepted)
                     .map(|(_is_accepted, (_key, val))| val)
                     .collect()),
@@ -1639,7 +2081,9 @@ where
             };
 
             // send the content to the acceptor over a uTP stream
-            match Self::send_utp_content(stream, &content_payload, metrics).await {
+            let result = Self::send_utp_content(stream, &content_payload, metrics).await;
+            peer_scores.record_utp_transfer(cid.peer.node_id(), result.is_ok());
+            match result {
                 Ok(_) => {
                     if let Some(tx) = gossip_result_tx {
                         let _ = tx.send(true);
@@ -1663,13 +2107,18 @@ where
         Ok(response)
     }
 
-    /// Process accepted uTP payload of the OFFER/ACCEPT stream
+    /// Process accepted uTP payload of the OFFER/ACCEPT stream, which was offered by `source`
+    #[allow(clippy::too_many_arguments)]
     async fn process_accept_utp_payload(
         validator: Arc<TValidator>,
-        store: Arc<RwLock<TStore>>,
+        store: StoreHandle<TStore>,
         metrics: OverlayMetricsReporter,
+        discovery: Arc<Discovery>,
         kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+        peer_scores: PeerScores,
         command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+        content_writer: ContentWriter<TContentKey>,
+        source: NodeId,
         content_keys: Vec<TContentKey>,
         payload: Vec<u8>,
     ) -> anyhow::Result<()> {
@@ -1683,12 +2132,19 @@ where
             "Processing accepted uTP payload",
         );
 
-        let content_values = portal_wire::decode_content_payload(payload)?;
+        let content_values = match portal_wire::decode_content_payload(payload) {
+            Ok(content_values) => content_values,
+            Err(err) => {
+                report_peer_offence(&discovery, &kbuckets, &command_tx, &source);
+                return Err(err);
+            }
+        };
 
         // Accepted content keys len should match content value len
         let keys_len = content_keys.len();
         let vals_len = content_values.len();
         if keys_len != vals_len {
+            report_peer_offence(&discovery, &kbuckets, &command_tx, &source);
             return Err(anyhow!(
                 "Content keys len {keys_len} doesn't match content values len {vals_len}."
             ));
@@ -1704,8 +2160,13 @@ where
                 // - Checks if validated content should be stored, and stores it if true
                 // - Propagate all validated content
                 let validator = Arc::clone(&validator);
-                let store = Arc::clone(&store);
+                let store = store.clone();
                 let metrics = metrics.clone();
+                let discovery = Arc::clone(&discovery);
+                let kbuckets = Arc::clone(&kbuckets);
+                let peer_scores = peer_scores.clone();
+                let command_tx = command_tx.clone();
+                let content_writer = content_writer.clone();
                 tokio::spawn(async move {
                     // Validated received content
                     if let Err(err) = validator
@@ -1714,6 +2175,12 @@ where
                     {
                         // Skip storing & propagating content if it's not valid
                         metrics.report_validation(false);
+                        peer_scores.record_validation_failure(source);
+                        report_peer_offence(&discovery, &kbuckets, &command_tx, &source);
+                        let _ = command_tx.send(OverlayCommand::ValidationFailed {
+                            content_key: key.clone(),
+                            source,
+                        });
                         warn!(
                             error = %err,
                             content.key = %key.to_hex(),
@@ -1723,18 +2190,14 @@ where
                     }
                     metrics.report_validation(true);
 
-                    // Check if data should be stored, and store if it is within our radius and not
-                    // already stored.
-                    let key_desired = store.read().is_key_within_radius_and_unavailable(&key);
+                    // Check if data should be stored, and queue it to be stored with the next batch
+                    // if it is within our radius and not already stored.
+                    let key_desired = store
+                        .is_key_within_radius_and_unavailable(key.clone())
+                        .await;
                     match key_desired {
                         Ok(ShouldWeStoreContent::Store) => {
-                            if let Err(err) = store.write().put(key.clone(), &content_value) {
-                                warn!(
-                                    error = %err,
-                                    content.key = %key.to_hex(),
-                                    "Error storing accepted content"
-                                );
-                            }
+                            content_writer.write(key.clone(), content_value.clone());
                         }
                         Ok(ShouldWeStoreContent::NotWithinRadius) => {
                             warn!(
@@ -1785,7 +2248,13 @@ where
             .map(|(k, _)| hex_encode_compact(k.content_id()))
             .collect();
         debug!(ids = ?validated_ids, "propagating validated content");
-        propagate_gossip_cross_thread(validated_content, kbuckets, command_tx.clone());
+        propagate_gossip_cross_thread::<_, TMetric>(
+            validated_content,
+            kbuckets,
+            &peer_scores,
+            command_tx.clone(),
+            Some(source),
+        );
 
         Ok(())
     }
@@ -1930,7 +2399,7 @@ where
         kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
         command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
         validator: Arc<TValidator>,
-        store: Arc<RwLock<TStore>>,
+        store: StoreHandle<TStore>,
         content: Vec<u8>,
         utp_transfer: bool,
         content_key: TContentKey,
@@ -1942,7 +2411,7 @@ where
     ) {
         let mut content = content;
         // Operate under assumption that all content in the store is valid
-        let local_value = store.read().get(&content_key);
+        let local_value = store.get(content_key.clone()).await;
         if let Ok(Some(val)) = local_value {
             // todo validate & replace content value if different & punish bad peer
             warn!("Stored content doesn't match expected value");
@@ -1967,8 +2436,8 @@ where
             // skip storing if the content is already stored
             // or if there's an error reading the store
             let should_store = match store
-                .read()
-                .is_key_within_radius_and_unavailable(&content_key)
+                .is_key_within_radius_and_unavailable(content_key.clone())
+                .await
             {
                 Ok(val) => matches!(val, ShouldWeStoreContent::Store),
                 Err(msg) => {
@@ -1977,13 +2446,15 @@ where
                 }
             };
             if should_store {
-                if let Err(err) = store.write().put(content_key.clone(), content.clone()) {
+                if let Err(err) = store.put(content_key.clone(), content.clone()).await {
                     error!(
                         error = %err,
                         content.id = %hex_encode_compact(content_id),
                         content.key = %content_key,
                         "Error storing content"
                     );
+                } else {
+                    let _ = command_tx.send(OverlayCommand::ContentStored(content_key.clone()));
                 }
             }
         }
@@ -2008,8 +2479,11 @@ where
         for enr in enrs {
             let node_id = enr.node_id();
 
-            // Ignore ourself.
-            if node_id == local_node_id {
+            // Ignore ourself, banned peers and peers with incompatible protocol versions.
+            if node_id == local_node_id
+                || self.discovery.peer_bans().is_banned(&node_id)
+                || negotiate_protocol_version(&enr).is_none()
+            {
                 continue;
             }
 
@@ -2026,6 +2500,8 @@ where
             // succeeds, then add the node to the ping queue. Ignore insertion failures.
             if let Some(node) = optional_node {
                 if node.enr().seq() < enr.seq() {
+                    // Stop dialing the endpoint of the outdated ENR.
+                    self.discovery.update_cached_node_addr(&enr);
                     let updated_node = Node {
                         enr,
                         data_radius: node.data_radius(),
@@ -2082,8 +2558,8 @@ where
     }
 
     /// Provide the requested content key and content value for the acceptor
-    fn provide_requested_content(
-        store: Arc<RwLock<TStore>>,
+    async fn provide_requested_content(
+        store: StoreHandle<TStore>,
         accept_message: &Accept,
         content_keys_offered: Vec<RawContentKey>,
     ) -> anyhow::Result<Vec<Vec<u8>>> {
@@ -2096,29 +2572,25 @@ where
         let content_keys_offered: Vec<TContentKey> = content_keys_offered
             .map_err(|_| anyhow!("Unable to decode our own offered content keys"))?;
 
-        let mut content_items: Vec<Vec<u8>> = Vec::new();
-
-        for (i, key) in accept_message
+        let requested_keys: Vec<TContentKey> = accept_message
             .content_keys
-            .clone()
             .iter()
-            .zip(content_keys_offered.iter())
-        {
-            if i {
-                match store.read().get(key) {
-                    Ok(content) => match content {
-                        Some(content) => content_items.push(content),
-                        None => return Err(anyhow!("Unable to read offered content!")),
-                    },
-                    Err(err) => {
-                        return Err(anyhow!(
-                            "Unable to get offered content from portal store: {err}"
-                        ))
-                    }
-                }
-            }
-        }
-        Ok(content_items)
+            .zip(content_keys_offered)
+            .filter_map(|(requested, key)| requested.then_some(key))
+            .collect();
+        let content_items = store
+            .read(move |store| {
+                requested_keys
+                    .iter()
+                    .map(|key| store.get(key))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .map_err(|err| anyhow!("Unable to get offered content from portal store: {err}"))?;
+        content_items
+            .into_iter()
+            .collect::<Option<Vec<Vec<u8>>>>()
+            .ok_or_else(|| anyhow!("Unable to read offered content!"))
     }
 
     /// Advances a find node query (if one is active for the node) using the received ENRs.
@@ -2270,6 +2742,18 @@ where
     /// Attempts to insert a newly connected node or update an existing node to connected.
     fn connect_node(&mut self, node: Node, connection_direction: ConnectionDirection) {
         let node_id = node.enr().node_id();
+        if self.discovery.peer_bans().is_banned(&node_id) {
+            trace!(protocol = %self.protocol, peer = %node_id, "Not connecting banned peer");
+            return;
+        }
+        if negotiate_protocol_version(&node.enr).is_none() {
+            trace!(
+                protocol = %self.protocol,
+                peer = %node_id,
+                "Not connecting peer with incompatible protocol version"
+            );
+            return;
+        }
         let key = kbucket::Key::from(node_id);
         let status = NodeStatus {
             state: ConnectionState::Connected,
@@ -2287,6 +2771,7 @@ where
                 );
 
                 self.peers_to_ping.insert(node_id);
+                self.send_local_event(OverlayEvent::PeerAdded(node_id));
             }
             InsertResult::Pending { disconnected } => {
                 // The disconnected node is the least-recently connected entry that is
@@ -2308,6 +2793,7 @@ where
                         "Node promoted to connected",
                     );
                     self.peers_to_ping.insert(node_id);
+                    self.send_local_event(OverlayEvent::PeerAdded(node_id));
                 }
             }
             InsertResult::ValueUpdated | InsertResult::UpdatedPending => {}
@@ -2452,6 +2938,9 @@ where
             let b_distance = b.key.distance(target_key);
             a_distance.cmp(&b_distance)
         });
+        // Only start lookups from unreliable peers if there aren't enough reliable peers.
+        self.peer_scores
+            .prefer_reliable(&mut all_nodes, |node| *node.key.preimage());
 
         all_nodes
             .iter()
@@ -2613,7 +3102,6 @@ where
     }
 
     /// Send `OverlayEvent` to the event stream.
-    #[allow(dead_code)] // TODO: remove when used
     fn send_event(&self, event: OverlayEvent, to: Option<Vec<ProtocolId>>) {
         trace!(
             "Sending event={:?} to event-stream from protocol {}",
@@ -2628,6 +3116,12 @@ where
             )
         }
     }
+
+    /// Send an `OverlayEvent` that is only of interest to subscribers of this overlay to the event
+    /// stream.
+    fn send_local_event(&self, event: OverlayEvent) {
+        self.send_event(event, Some(vec![self.protocol]));
+    }
 }
 /// The result of the `query_event_poll` indicating an action is required to further progress an
 /// active query.
@@ -2647,6 +3141,36 @@ fn pop_while_ssz_bytes_len_gt(enrs: &mut Vec<SszEnr>, max_size: usize) {
     }
 }
 
+/// Reports an offence of `node_id`, such as sending malformed messages or invalid content, and
+/// removes the peer from the routing table if it got banned. The removal is sent to the event
+/// stream through `command_tx`.
+pub(crate) fn report_peer_offence<TContentKey>(
+    discovery: &Discovery,
+    kbuckets: &RwLock<KBucketsTable<NodeId, Node>>,
+    command_tx: &UnboundedSender<OverlayCommand<TContentKey>>,
+    node_id: &NodeId,
+) {
+    if discovery.report_offence(node_id) && kbuckets.write().remove(&kbucket::Key::from(*node_id)) {
+        let _ = command_tx.send(OverlayCommand::PeerRemoved(*node_id));
+    }
+}
+
+/// Randomly picks the index of a bucket to refresh from `bucket_sizes`, a list of bucket indices
+/// and their number of entries. Buckets that are not full are picked first, since a lookup is the
+/// only way to find new nodes for them. Returns `None` if `bucket_sizes` is empty.
+fn choose_bucket_to_refresh(bucket_sizes: &[(usize, usize)]) -> Option<usize> {
+    let under_populated: Vec<usize> = bucket_sizes
+        .iter()
+        .filter(|(_, num_entries)| *num_entries < kbucket::MAX_NODES_PER_BUCKET)
+        .map(|(index, _)| *index)
+        .collect();
+    let candidates: Vec<usize> = match under_populated.is_empty() {
+        true => bucket_sizes.iter().map(|(index, _)| *index).collect(),
+        false => under_populated,
+    };
+    candidates.choose(&mut rand::thread_rng()).copied()
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -2665,11 +3189,14 @@ mod tests {
         config::PortalnetConfig,
         discovery::{Discovery, NodeAddress},
         overlay::OverlayConfig,
+        types::peer_bans::MAX_PEER_OFFENCES,
         utils::db::setup_temp_dir,
     };
     use ethportal_api::types::{
-        cli::DEFAULT_DISCOVERY_PORT, content_key::overlay::IdentityContentKey, distance::XorMetric,
-        enr::generate_random_remote_enr,
+        cli::DEFAULT_DISCOVERY_PORT,
+        content_key::overlay::IdentityContentKey,
+        distance::XorMetric,
+        enr::{generate_random_remote_enr, ENR_PROTOCOL_VERSIONS_KEY},
     };
     use trin_metrics::portalnet::PORTALNET_METRICS;
     use trin_storage::{DistanceFunction, MemoryContentStore};
@@ -2698,7 +3225,7 @@ mod tests {
 
         let node_id = discovery.local_enr().node_id();
         let store = MemoryContentStore::new(node_id, DistanceFunction::Xor);
-        let store = Arc::new(RwLock::new(store));
+        let store = StoreHandle::spawn(store, "test-store".to_string());
 
         let overlay_config = OverlayConfig::default();
         let kbuckets = Arc::new(RwLock::new(KBucketsTable::new(
@@ -2719,6 +3246,11 @@ mod tests {
             protocol: "test".to_string(),
         };
         let validator = Arc::new(MockValidator {});
+        let content_writer = ContentWriter::spawn(
+            store.clone(),
+            WriteBatchConfig::default(),
+            command_tx.clone(),
+        );
 
         OverlayService {
             discovery,
@@ -2727,6 +3259,7 @@ mod tests {
             kbuckets,
             protocol,
             peers_to_ping,
+            declined_offers: HashSetDelay::default(),
             command_tx,
             command_rx,
             active_outgoing_requests,
@@ -2748,6 +3281,13 @@ mod tests {
             validator,
             event_stream: broadcast::channel(EVENT_STREAM_CHANNEL_CAPACITY).0,
             disable_poke: false,
+            peer_activity: PeerActivity::default(),
+            peer_scores: PeerScores::default(),
+            content_writer,
+            bootnode_enrs: vec![],
+            bootnode_retry_backoff: Duration::from_secs(INITIAL_BOOTNODE_RETRY_BACKOFF_SECS),
+            next_bootnode_retry: None,
+            local_enr_seq: 1,
         }
     }
 
@@ -2827,6 +3367,98 @@ mod tests {
         assert_pending!(poll_command_rx!(service));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn register_node_activity_records_last_seen() {
+        let mut service = task::spawn(build_service());
+
+        let (_, source) = generate_random_remote_enr();
+        let node_id = source.node_id();
+        assert!(service.peer_activity.last_seen(&node_id).is_none());
+
+        service.register_node_activity(node_id);
+        assert!(service.peer_activity.last_seen(&node_id).is_some());
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn process_accept_records_declined_content_keys() {
+        let mut service = build_service();
+        let (_, peer) = generate_random_remote_enr();
+        let node_id = peer.node_id();
+
+        let accepted_key: RawContentKey = vec![1; 33];
+        let declined_key: RawContentKey = vec![2; 33];
+        let content_items = vec![
+            (accepted_key.clone(), vec![1]),
+            (declined_key.clone(), vec![2]),
+        ];
+        let mut content_keys = BitList::with_capacity(2).unwrap();
+        content_keys.set(0, true).unwrap();
+        let accept = Accept {
+            connection_id: rand::random(),
+            content_keys,
+        };
+        let offer = Request::PopulatedOffer(PopulatedOffer {
+            content_items: content_items.clone(),
+        });
+        service.process_accept(accept, peer, offer).unwrap();
+
+        assert!(!service
+            .declined_offers
+            .contains_key(&(node_id, accepted_key.clone())));
+        assert!(service
+            .declined_offers
+            .contains_key(&(node_id, declined_key)));
+
+        // Only the accepted content is offered to the peer again, while other peers are still
+        // offered all of it.
+        let remaining_items =
+            service.remove_declined_content_items(&node_id, content_items.clone());
+        assert_eq!(remaining_items, vec![(accepted_key, vec![1])]);
+        let other_node_id = NodeId::random();
+        let remaining_items =
+            service.remove_declined_content_items(&other_node_id, content_items.clone());
+        assert_eq!(remaining_items, content_items);
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn banned_peer_is_removed_from_routing_table() {
+        let mut service = build_service();
+        let (_, enr) = generate_random_remote_enr();
+        let node_id = enr.node_id();
+        let key = kbucket::Key::from(node_id);
+        let node = Node::new(enr, Distance::MAX);
+
+        service.connect_node(node.clone(), ConnectionDirection::Outgoing);
+        assert!(matches!(
+            service.kbuckets.write().entry(&key),
+            kbucket::Entry::Present { .. }
+        ));
+
+        for _ in 0..MAX_PEER_OFFENCES {
+            report_peer_offence(
+                &service.discovery,
+                &service.kbuckets,
+                &service.command_tx,
+                &node_id,
+            );
+        }
+        assert!(service.discovery.peer_bans().is_banned(&node_id));
+        assert!(matches!(
+            service.kbuckets.write().entry(&key),
+            kbucket::Entry::Absent(_)
+        ));
+
+        // The banned peer is not inserted into the routing table again.
+        service.connect_node(node, ConnectionDirection::Incoming);
+        assert!(matches!(
+            service.kbuckets.write().entry(&key),
+            kbucket::Entry::Absent(_)
+        ));
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn process_request_failure() {
@@ -2857,9 +3489,13 @@ mod tests {
 
         let request_id = rand::random();
         let error = OverlayRequestError::Timeout;
-        service.process_request_failure(request_id, destination, error);
+        service.process_request_failure(request_id, destination, error, None);
 
         assert!(!service.peers_to_ping.contains_key(&node_id));
+        assert_eq!(
+            service.peer_scores.stats(&node_id).unwrap().failed_requests,
+            1
+        );
 
         match service.kbuckets.write().entry(&key) {
             kbucket::Entry::Present(_entry, status) => {
@@ -3074,6 +3710,40 @@ mod tests {
         };
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn process_discovered_enrs_updates_cached_node_addr() {
+        let mut service = task::spawn(build_service());
+
+        let (sk, mut enr) = generate_random_remote_enr();
+        let key = kbucket::Key::from(enr.node_id());
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        let _ = service.kbuckets.write().insert_or_update(
+            &key,
+            Node::new(enr.clone(), Distance::MAX),
+            status,
+        );
+
+        // Cache the address of the ENR, as if a session was established with it.
+        service.discovery.put_cached_node_addr(NodeAddress {
+            enr: enr.clone(),
+            socket_addr: SocketAddr::V4(enr.udp4_socket().unwrap()),
+        });
+
+        // Move the node to another port, which increments the sequence number.
+        let updated_udp: u16 = DEFAULT_DISCOVERY_PORT;
+        let _ = enr.set_udp4(updated_udp, &sk);
+        service.process_discovered_enrs(vec![enr.clone()]);
+
+        // The cached address should follow the update.
+        let node_addr = service.discovery.cached_node_addr(&enr.node_id()).unwrap();
+        assert_eq!(node_addr.enr.seq(), enr.seq());
+        assert_eq!(node_addr.socket_addr.port(), updated_udp);
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn poke_content() {
@@ -3275,6 +3945,131 @@ mod tests {
         assert!(matches!(request.request, Request::Ping { .. }));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn incompatible_peer_is_refused() {
+        let mut service = task::spawn(build_service());
+        let key = discv5::enr::CombinedKey::generate_secp256k1();
+        let enr = discv5::enr::Enr::builder()
+            .udp4(8000)
+            .add_value(ENR_PROTOCOL_VERSIONS_KEY, &[9u8].as_slice())
+            .build(&key)
+            .unwrap();
+        let node_key = kbucket::Key::from(enr.node_id());
+
+        service.connect_node(
+            Node::new(enr.clone(), Distance::MAX),
+            ConnectionDirection::Outgoing,
+        );
+        assert!(matches!(
+            service.kbuckets.write().entry(&node_key),
+            kbucket::Entry::Absent(_)
+        ));
+        service.process_discovered_enrs(vec![enr.clone()]);
+        assert!(matches!(
+            service.kbuckets.write().entry(&node_key),
+            kbucket::Entry::Absent(_)
+        ));
+
+        let (tx, rx) = oneshot::channel();
+        let request = OverlayRequest::new(
+            Request::FindNodes(FindNodes { distances: vec![0] }),
+            RequestDirection::Outgoing { destination: enr },
+            Some(tx),
+            None,
+        );
+        service.process_request(request);
+        assert!(matches!(
+            rx.await.unwrap(),
+            Err(OverlayRequestError::IncompatibleProtocolVersion)
+        ));
+        assert!(service.active_outgoing_requests.read().is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn retry_bootnodes_if_isolated() {
+        let mut service = task::spawn(build_service());
+        let (_, bootnode) = generate_random_remote_enr();
+        service.bootnode_enrs = vec![bootnode.clone()];
+
+        // Without connected peers, the bootnode is pinged and the backoff grows.
+        service.retry_bootnodes_if_isolated();
+        let command = assert_ready!(poll_command_rx!(service)).unwrap();
+        match command {
+            OverlayCommand::Request(request) => {
+                assert!(matches!(request.request, Request::Ping { .. }));
+                assert_eq!(
+                    request.direction,
+                    RequestDirection::Outgoing {
+                        destination: bootnode.clone()
+                    }
+                );
+            }
+            _ => panic!("Unexpected overlay command variant"),
+        }
+        assert!(service.next_bootnode_retry.is_some());
+        assert!(service.peers_to_ping.contains_key(&bootnode.node_id()));
+        assert_eq!(
+            service.bootnode_retry_backoff,
+            Duration::from_secs(INITIAL_BOOTNODE_RETRY_BACKOFF_SECS * 2)
+        );
+
+        for _ in 0..10 {
+            service.retry_bootnodes_if_isolated();
+        }
+        assert_eq!(
+            service.bootnode_retry_backoff,
+            Duration::from_secs(MAX_BOOTNODE_RETRY_BACKOFF_SECS)
+        );
+
+        // Once a peer is connected, retries stop and the backoff is reset.
+        let _ =
+            service.update_node_connection_state(bootnode.node_id(), ConnectionState::Connected);
+        service.retry_bootnodes_if_isolated();
+        assert!(service.next_bootnode_retry.is_none());
+        assert_eq!(
+            service.bootnode_retry_backoff,
+            Duration::from_secs(INITIAL_BOOTNODE_RETRY_BACKOFF_SECS)
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn announce_local_enr_update() {
+        let mut service = task::spawn(build_service());
+        let (_, enr) = generate_random_remote_enr();
+        let _ = service.kbuckets.write().insert_or_update(
+            &kbucket::Key::from(enr.node_id()),
+            Node::new(enr.clone(), Distance::MAX),
+            NodeStatus {
+                state: ConnectionState::Connected,
+                direction: ConnectionDirection::Outgoing,
+            },
+        );
+
+        // Nothing is announced while the local ENR is unchanged.
+        service.local_enr_seq = service.local_enr().seq();
+        service.announce_local_enr_update();
+        assert_pending!(poll_command_rx!(service));
+
+        // Connected peers are pinged once the local ENR changed.
+        service.local_enr_seq = 0;
+        service.announce_local_enr_update();
+        let command = assert_ready!(poll_command_rx!(service)).unwrap();
+        match command {
+            OverlayCommand::Request(request) => {
+                assert!(matches!(request.request, Request::Ping { .. }));
+                assert_eq!(
+                    request.direction,
+                    RequestDirection::Outgoing { destination: enr }
+                );
+            }
+            _ => panic!("Unexpected overlay command variant"),
+        }
+        assert_eq!(service.local_enr_seq, service.local_enr().seq());
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn connect_node() {
@@ -3995,6 +4790,74 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn handle_find_content_query_event_unknown_enr() {
+        let mut service = task::spawn(build_service());
+
+        let (_, bootnode_enr) = generate_random_remote_enr();
+        let bootnode_node_id = bootnode_enr.node_id();
+        let bootnode_key = kbucket::Key::from(bootnode_node_id);
+
+        let bootnode = Node {
+            enr: bootnode_enr.clone(),
+            data_radius: Distance::MAX,
+        };
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        let _ = service
+            .kbuckets
+            .write()
+            .insert_or_update(&bootnode_key, bootnode, status);
+
+        let target_content_key = IdentityContentKey::new(NodeId::random().raw());
+        let (callback_tx, callback_rx) = oneshot::channel();
+        let query_id =
+            service.init_find_content_query(target_content_key, Some(callback_tx), false);
+        let query_id = query_id.expect("Query ID for new find content query is `None`");
+
+        let query_event =
+            OverlayService::<_, XorMetric, MockValidator, MemoryContentStore>::query_event_poll(
+                service.find_content_query_pool.clone(),
+            )
+            .await;
+        assert!(matches!(query_event, QueryEvent::Waiting(_, _, _)));
+
+        // Forget the bootnode, so that its ENR can't be found when the request is sent.
+        service.kbuckets.write().remove(&bootnode_key);
+        if let Some((query_info, _)) = service.find_content_query_pool.write().get_mut(query_id) {
+            query_info.untrusted_enrs.clear();
+        }
+        service.handle_find_content_query_event(query_event);
+
+        // No request is sent, and the bootnode failing ends the query since no peers are left.
+        assert_pending!(poll_command_rx!(service));
+        let query_event =
+            OverlayService::<_, XorMetric, MockValidator, MemoryContentStore>::query_event_poll(
+                service.find_content_query_pool.clone(),
+            )
+            .await;
+        assert!(matches!(query_event, QueryEvent::Finished(_, _, _)));
+
+        service.handle_find_content_query_event(query_event);
+        assert_eq!(callback_rx.await.unwrap(), (None, false, None));
+    }
+
+    #[test]
+    fn test_choose_bucket_to_refresh() {
+        let full = kbucket::MAX_NODES_PER_BUCKET;
+        assert_eq!(choose_bucket_to_refresh(&[]), None);
+        for _ in 0..10 {
+            assert_eq!(
+                choose_bucket_to_refresh(&[(253, full), (254, full - 1), (255, full)]),
+                Some(254)
+            );
+        }
+        let bucket = choose_bucket_to_refresh(&[(254, full), (255, full)]).unwrap();
+        assert!(bucket == 254 || bucket == 255);
+    }
+
     #[tokio::test]
     async fn test_event_stream() {
         // Get overlay service event stream
@@ -4007,4 +4870,55 @@ mod tests {
         let event = receiver.recv().await.unwrap();
         assert_eq!(event.payload, OverlayEvent::LightClientOptimisticUpdate);
     }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_peer_events() {
+        let mut service = task::spawn(build_service());
+        let (sender, mut receiver) = broadcast::channel(2);
+        service.event_stream = sender;
+
+        let (_, enr) = generate_random_remote_enr();
+        let node_id = enr.node_id();
+        service.connect_node(
+            Node::new(enr.clone(), Distance::MAX),
+            ConnectionDirection::Outgoing,
+        );
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.payload, OverlayEvent::PeerAdded(node_id));
+        assert_eq!(event.destination, Some(vec![service.protocol]));
+
+        service.process_request_failure(1, enr, OverlayRequestError::Timeout, None);
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.payload, OverlayEvent::PeerRemoved(node_id));
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn banned_peer_removal_is_sent_to_subscribers() {
+        let mut service = build_service();
+        let (sender, mut receiver) = broadcast::channel(2);
+        service.event_stream = sender;
+
+        let (_, enr) = generate_random_remote_enr();
+        let node_id = enr.node_id();
+        service.connect_node(Node::new(enr, Distance::MAX), ConnectionDirection::Outgoing);
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.payload, OverlayEvent::PeerAdded(node_id));
+
+        for _ in 0..MAX_PEER_OFFENCES {
+            report_peer_offence(
+                &service.discovery,
+                &service.kbuckets,
+                &service.command_tx,
+                &node_id,
+            );
+        }
+        let command = service.command_rx.recv().await.unwrap();
+        assert!(matches!(command, OverlayCommand::PeerRemoved(removed) if removed == node_id));
+        service.process_command(command);
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.payload, OverlayEvent::PeerRemoved(node_id));
+        assert_eq!(event.destination, Some(vec![service.protocol]));
+    }
 }
@@ -0,0 +1,348 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    thread,
+};
+
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::error;
+
+use ethportal_api::{types::distance::Distance, OverlayContentKey};
+use trin_storage::{error::ContentStoreError, ContentStore, ShouldWeStoreContent};
+
+use crate::content_cache::ContentCache;
+
+/// An operation on the store, run by the storage actor.
+type StoreTask<TStore> = Box<dyn FnOnce(&mut TStore) + Send>;
+
+/// A message to the storage actor.
+enum StoreMessage<TStore> {
+    /// Run an operation on the store.
+    Task(StoreTask<TStore>),
+    /// Stop the actor once the earlier operations completed, dropping the store, and notify the
+    /// sender afterwards.
+    Stop(oneshot::Sender<()>),
+}
+
+/// A handle to a content store that is owned by a dedicated storage thread, the storage actor.
+///
+/// Store operations may wait on disk I/O, so running them on the async tasks of the overlay would
+/// stall TALKREQ handling and uTP transfers whenever the disk is slow. Every operation is sent to
+/// the actor over a channel instead, and its result is sent back to the awaiting task once it
+/// completes. The actor stops once every handle is dropped, or once it is told to by
+/// [`StoreHandle::shutdown`].
+///
+/// The actor publishes the radius of the store after every operation, so that the radius can be
+/// read without waiting on the store.
+///
+/// Content may be served from a content cache in front of the store, which only holds content
+/// that is stored. The store shrinks its radius whenever it evicts content, so the actor clears the
+/// cache when the radius changes, as well as after any other exclusive operation on the store.
+pub struct StoreHandle<TStore> {
+    task_tx: mpsc::UnboundedSender<StoreMessage<TStore>>,
+    radius: watch::Receiver<Distance>,
+    content_cache: ContentCache,
+}
+
+impl<TStore> Clone for StoreHandle<TStore> {
+    fn clone(&self) -> Self {
+        Self {
+            task_tx: self.task_tx.clone(),
+            radius: self.radius.clone(),
+            content_cache: self.content_cache.clone(),
+        }
+    }
+}
+
+impl<TStore: 'static + ContentStore + Send> StoreHandle<TStore> {
+    /// Moves `store` to a new storage actor, running on a thread called `name`, without a content
+    /// cache.
+    pub fn spawn(store: TStore, name: String) -> Self {
+        Self::spawn_with_content_cache(store, name, ContentCache::new(0))
+    }
+
+    /// Moves `store` to a new storage actor, running on a thread called `name`, that serves
+    /// content from `content_cache` before looking it up in the store. The cache must only be used
+    /// for content that never changes once stored.
+    pub fn spawn_with_content_cache(
+        store: TStore,
+        name: String,
+        content_cache: ContentCache,
+    ) -> Self {
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
+        let (radius_tx, radius) = watch::channel(store.radius());
+        let actor_cache = content_cache.clone();
+        thread::Builder::new()
+            .name(name)
+            .spawn(move || run_store_tasks(store, task_rx, radius_tx, actor_cache))
+            .expect("Failed to spawn the storage thread");
+        Self {
+            task_tx,
+            radius,
+            content_cache,
+        }
+    }
+
+    /// Returns the radius of the store, as of the last completed operation.
+    pub fn radius(&self) -> Distance {
+        *self.radius.borrow()
+    }
+
+    /// Stops the storage actor after the operations that were sent before, and waits until it
+    /// dropped the store. Later operations fail, from any handle.
+    pub async fn shutdown(&self) {
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        if self.task_tx.send(StoreMessage::Stop(stopped_tx)).is_ok() {
+            let _ = stopped_rx.await;
+        }
+    }
+
+    /// Runs `f` with shared access to the store on the storage actor.
+    pub async fn read<T: 'static + Send>(
+        &self,
+        f: impl FnOnce(&TStore) -> Result<T, ContentStoreError> + Send + 'static,
+    ) -> Result<T, ContentStoreError> {
+        self.run(move |store| f(store)).await?
+    }
+
+    /// Runs `f` with exclusive access to the store on the storage actor. The content cache is
+    /// cleared afterwards, as `f` may have deleted content.
+    pub async fn write<T: 'static + Send>(
+        &self,
+        f: impl FnOnce(&mut TStore) -> T + Send + 'static,
+    ) -> Result<T, ContentStoreError> {
+        let content_cache = self.content_cache.clone();
+        self.run(move |store| {
+            let result = f(store);
+            content_cache.clear();
+            result
+        })
+        .await
+    }
+
+    async fn run<T: 'static + Send>(
+        &self,
+        f: impl FnOnce(&mut TStore) -> T + Send + 'static,
+    ) -> Result<T, ContentStoreError> {
+        let (result_tx, result_rx) = oneshot::channel();
+        let task: StoreTask<TStore> = Box::new(move |store| {
+            let _ = result_tx.send(f(store));
+        });
+        let stopped = || ContentStoreError::Database("The storage actor stopped".to_string());
+        self.task_tx
+            .send(StoreMessage::Task(task))
+            .map_err(|_| stopped())?;
+        result_rx.await.map_err(|_| stopped())
+    }
+
+    pub async fn get<K: 'static + OverlayContentKey + Send>(
+        &self,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        let content_id = key.content_id();
+        if let Some(content) = self.content_cache.get(&content_id) {
+            return Ok(Some(content));
+        }
+        // Cached by the actor, so that it can't cache content that was evicted in the meantime.
+        let content_cache = self.content_cache.clone();
+        self.read(move |store| {
+            let content = store.get(&key)?;
+            if let Some(content) = &content {
+                content_cache.insert(content_id, content.clone());
+            }
+            Ok(content)
+        })
+        .await
+    }
+
+    pub async fn is_key_within_radius_and_unavailable<K: 'static + OverlayContentKey + Send>(
+        &self,
+        key: K,
+    ) -> Result<ShouldWeStoreContent, ContentStoreError> {
+        self.read(move |store| store.is_key_within_radius_and_unavailable(&key))
+            .await
+    }
+
+    pub async fn put<K: 'static + OverlayContentKey + Send>(
+        &self,
+        key: K,
+        value: Vec<u8>,
+    ) -> Result<(), ContentStoreError> {
+        let content_cache = self.content_cache.clone();
+        self.run(move |store| {
+            let content_id = key.content_id();
+            store.put(key, &value)?;
+            content_cache.insert(content_id, value);
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn put_batch<K: 'static + OverlayContentKey + Send>(
+        &self,
+        items: Vec<(K, Vec<u8>)>,
+    ) -> Result<Vec<Result<(), ContentStoreError>>, ContentStoreError> {
+        let content_cache = self.content_cache.clone();
+        self.run(move |store| {
+            let (keys, values): (Vec<K>, Vec<Vec<u8>>) = items.into_iter().unzip();
+            let content_ids: Vec<[u8; 32]> = keys.iter().map(|key| key.content_id()).collect();
+            let results = store.put_batch(keys.into_iter().zip(&values).collect());
+            for ((content_id, value), result) in content_ids.into_iter().zip(values).zip(&results) {
+                if result.is_ok() {
+                    content_cache.insert(content_id, value);
+                }
+            }
+            results
+        })
+        .await
+    }
+}
+
+/// Runs the operations sent to the storage actor one at a time, until every handle is dropped or
+/// the actor is told to stop.
+fn run_store_tasks<TStore: ContentStore>(
+    mut store: TStore,
+    mut task_rx: mpsc::UnboundedReceiver<StoreMessage<TStore>>,
+    radius_tx: watch::Sender<Distance>,
+    content_cache: ContentCache,
+) {
+    while let Some(message) = task_rx.blocking_recv() {
+        let task = match message {
+            StoreMessage::Task(task) => task,
+            StoreMessage::Stop(stopped_tx) => {
+                // Dropping the receiver fails the operations that are still queued, and the cache
+                // is cleared so that no content is served once the store is gone.
+                drop(task_rx);
+                content_cache.clear();
+                drop(store);
+                let _ = stopped_tx.send(());
+                return;
+            }
+        };
+        // The awaiting task gets an error if the operation panics, the store keeps serving others.
+        if panic::catch_unwind(AssertUnwindSafe(|| task(&mut store))).is_err() {
+            error!("Store operation panicked");
+        }
+        let radius = store.radius();
+        let modified = radius_tx.send_if_modified(|published| {
+            let modified = *published != radius;
+            *published = radius;
+            modified
+        });
+        if modified {
+            content_cache.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use discv5::enr::NodeId;
+    use ethereum_types::U256;
+    use ethportal_api::IdentityContentKey;
+    use trin_storage::{DistanceFunction, MemoryContentStore};
+
+    #[test_log::test(tokio::test)]
+    async fn store_operations_complete() {
+        let store = StoreHandle::spawn(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+        );
+        let content_key = IdentityContentKey::new([1; 32]);
+
+        assert_eq!(store.get(content_key.clone()).await.unwrap(), None);
+        assert_eq!(
+            store
+                .is_key_within_radius_and_unavailable(content_key.clone())
+                .await
+                .unwrap(),
+            ShouldWeStoreContent::Store
+        );
+        store.put(content_key.clone(), vec![1, 2, 3]).await.unwrap();
+        assert_eq!(
+            store.get(content_key.clone()).await.unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            store
+                .is_key_within_radius_and_unavailable(content_key)
+                .await
+                .unwrap(),
+            ShouldWeStoreContent::AlreadyStored
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn radius_is_published_after_operations() {
+        let store = StoreHandle::spawn(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+        );
+        assert_eq!(store.radius(), Distance::MAX);
+
+        let radius = Distance::from(U256::from(1000));
+        store
+            .write(move |store| store.set_radius(radius))
+            .await
+            .unwrap();
+        assert_eq!(store.radius(), radius);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn content_cache_only_holds_stored_content() {
+        let store = StoreHandle::spawn_with_content_cache(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+            ContentCache::new(1024),
+        );
+        let content_key = IdentityContentKey::new([1; 32]);
+        store.put(content_key.clone(), vec![1, 2, 3]).await.unwrap();
+        assert_eq!(
+            store.content_cache.get(&content_key.content_id()),
+            Some(vec![1, 2, 3])
+        );
+
+        // Shrinking the radius may evict content, so the cache is cleared.
+        let radius = Distance::from(U256::from(1000));
+        store
+            .run(move |store| store.set_radius(radius))
+            .await
+            .unwrap();
+        assert_eq!(store.content_cache.get(&content_key.content_id()), None);
+
+        // Content served from the store is cached until the store is modified.
+        store.get(content_key.clone()).await.unwrap();
+        assert_eq!(
+            store.content_cache.get(&content_key.content_id()),
+            Some(vec![1, 2, 3])
+        );
+        store.write(|_| ()).await.unwrap();
+        assert_eq!(store.content_cache.get(&content_key.content_id()), None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn panicking_operations_fail_alone() {
+        let store = StoreHandle::spawn(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+        );
+        let result: Result<(), _> = store.write(|_| panic!("Store failure")).await;
+        assert!(result.is_err());
+        let content_key = IdentityContentKey::new([1; 32]);
+        assert_eq!(store.get(content_key).await.unwrap(), None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn operations_fail_after_shutdown() {
+        let store = StoreHandle::spawn(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+        );
+        let content_key = IdentityContentKey::new([1; 32]);
+        store.put(content_key.clone(), vec![1, 2, 3]).await.unwrap();
+
+        store.shutdown().await;
+        assert!(store.get(content_key).await.is_err());
+    }
+}
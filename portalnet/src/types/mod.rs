@@ -1 +1,4 @@
 pub mod node;
+pub mod peer_activity;
+pub mod peer_bans;
+pub mod peer_scores;
@@ -0,0 +1,61 @@
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+use discv5::enr::NodeId;
+use parking_lot::RwLock;
+
+/// Times at which peers of an overlay network were last heard from.
+///
+/// Shared by the overlay service, which records activity, and the overlay protocol, which reports
+/// it.
+#[derive(Clone, Debug, Default)]
+pub struct PeerActivity {
+    last_seen: Arc<RwLock<HashMap<NodeId, SystemTime>>>,
+}
+
+impl PeerActivity {
+    /// Records that a message was just received from `node_id`.
+    pub fn record(&self, node_id: NodeId) {
+        self.last_seen.write().insert(node_id, SystemTime::now());
+    }
+
+    /// Returns the time at which a message was last received from `node_id`.
+    pub fn last_seen(&self, node_id: &NodeId) -> Option<SystemTime> {
+        self.last_seen.read().get(node_id).copied()
+    }
+
+    /// Returns the number of peers with recorded activity.
+    pub fn len(&self) -> usize {
+        self.last_seen.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_seen.read().is_empty()
+    }
+
+    /// Forgets the activity of peers for which `keep` returns false.
+    pub fn retain(&self, mut keep: impl FnMut(&NodeId) -> bool) {
+        self.last_seen.write().retain(|node_id, _| keep(node_id));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_and_retain() {
+        let activity = PeerActivity::default();
+        let (first, second) = (NodeId::random(), NodeId::random());
+        assert!(activity.last_seen(&first).is_none());
+
+        activity.record(first);
+        activity.record(second);
+        assert!(activity.last_seen(&first).unwrap() <= SystemTime::now());
+        assert_eq!(activity.len(), 2);
+
+        activity.retain(|node_id| *node_id == second);
+        assert!(activity.last_seen(&first).is_none());
+        assert!(activity.last_seen(&second).is_some());
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::enr::NodeId;
+use parking_lot::RwLock;
+
+/// The number of offences after which a peer is banned.
+pub const MAX_PEER_OFFENCES: u32 = 3;
+
+/// The duration for which a peer is banned.
+pub const PEER_BAN_DURATION: Duration = Duration::from_secs(30 * 60);
+
+/// The time after which one offence of a peer is forgiven, so that peers are only banned for
+/// offending repeatedly within a short time.
+pub const OFFENCE_DECAY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// The maximum number of peers whose offences are recorded.
+const MAX_RECORDED_PEERS: usize = 4096;
+
+/// The misbehavior recorded for a single peer.
+#[derive(Clone, Debug)]
+struct PeerRecord {
+    /// The number of offences since the peer was last banned, as of `last_offence`.
+    offences: u32,
+    last_offence: Instant,
+    /// The time at which the current ban of the peer ends.
+    banned_until: Option<Instant>,
+}
+
+impl PeerRecord {
+    fn new(now: Instant) -> Self {
+        Self {
+            offences: 0,
+            last_offence: now,
+            banned_until: None,
+        }
+    }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until.is_some_and(|until| until > now)
+    }
+
+    /// Returns the number of offences left at `now`, after forgiving one offence for every
+    /// `offence_decay` that passed since the last offence.
+    fn decayed_offences(&self, now: Instant, offence_decay: Duration) -> u32 {
+        let elapsed = now.saturating_duration_since(self.last_offence).as_nanos();
+        let forgiven = elapsed / offence_decay.as_nanos().max(1);
+        self.offences
+            .saturating_sub(u32::try_from(forgiven).unwrap_or(u32::MAX))
+    }
+}
+
+/// Offences of peers, such as sending malformed messages or invalid content, and the temporary
+/// bans of peers that offend repeatedly.
+///
+/// Shared by the discovery layer, which drops packets of banned peers, and every overlay, which
+/// reports offences and keeps banned peers out of its routing table.
+#[derive(Clone, Debug)]
+pub struct PeerBans {
+    records: Arc<RwLock<HashMap<NodeId, PeerRecord>>>,
+    max_offences: u32,
+    ban_duration: Duration,
+    offence_decay: Duration,
+    max_recorded_peers: usize,
+}
+
+impl Default for PeerBans {
+    fn default() -> Self {
+        Self::new(MAX_PEER_OFFENCES, PEER_BAN_DURATION, OFFENCE_DECAY_INTERVAL)
+    }
+}
+
+impl PeerBans {
+    pub fn new(max_offences: u32, ban_duration: Duration, offence_decay: Duration) -> Self {
+        Self {
+            records: Arc::new(RwLock::new(HashMap::new())),
+            max_offences,
+            ban_duration,
+            offence_decay,
+            max_recorded_peers: MAX_RECORDED_PEERS,
+        }
+    }
+
+    /// Records an offence of `node_id`. Returns true if the peer got banned by this offence.
+    pub fn report_offence(&self, node_id: &NodeId) -> bool {
+        self.report_offence_at(node_id, Instant::now())
+    }
+
+    fn report_offence_at(&self, node_id: &NodeId, now: Instant) -> bool {
+        let mut records = self.records.write();
+        if !records.contains_key(node_id) && records.len() >= self.max_recorded_peers {
+            self.make_room(&mut records, now);
+        }
+        let record = records
+            .entry(*node_id)
+            .or_insert_with(|| PeerRecord::new(now));
+        if record.is_banned(now) {
+            return false;
+        }
+        record.offences = record.decayed_offences(now, self.offence_decay) + 1;
+        record.last_offence = now;
+        if record.offences < self.max_offences {
+            return false;
+        }
+        record.offences = 0;
+        record.banned_until = Some(now + self.ban_duration);
+        true
+    }
+
+    /// Forgets the peers that are neither banned nor have offences left. If every recorded peer
+    /// is still banned or offending, the peer that offended least recently without being banned
+    /// is forgotten, so that the record of banned peers is kept.
+    fn make_room(&self, records: &mut HashMap<NodeId, PeerRecord>, now: Instant) {
+        records.retain(|_, record| {
+            record.is_banned(now) || record.decayed_offences(now, self.offence_decay) > 0
+        });
+        if records.len() < self.max_recorded_peers {
+            return;
+        }
+        let least_recent = records
+            .iter()
+            .filter(|(_, record)| !record.is_banned(now))
+            .min_by_key(|(_, record)| record.last_offence)
+            .map(|(node_id, _)| *node_id);
+        if let Some(node_id) = least_recent {
+            records.remove(&node_id);
+        }
+    }
+
+    /// Returns true if `node_id` is currently banned. Peers whose ban has ended are forgotten.
+    pub fn is_banned(&self, node_id: &NodeId) -> bool {
+        let now = Instant::now();
+        let banned_until = match self.records.read().get(node_id) {
+            Some(record) => record.banned_until,
+            None => return false,
+        };
+        match banned_until {
+            Some(until) if until > now => true,
+            Some(_) => {
+                self.records.write().remove(node_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the peers that are currently banned.
+    pub fn banned_peers(&self) -> Vec<NodeId> {
+        let now = Instant::now();
+        self.records
+            .read()
+            .iter()
+            .filter(|(_, record)| record.is_banned(now))
+            .map(|(node_id, _)| *node_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeated_offences_ban_peer() {
+        let bans = PeerBans::new(2, Duration::from_secs(60), OFFENCE_DECAY_INTERVAL);
+        let (offender, other) = (NodeId::random(), NodeId::random());
+
+        assert!(!bans.report_offence(&offender));
+        assert!(!bans.is_banned(&offender));
+        assert!(bans.report_offence(&offender));
+        assert!(bans.is_banned(&offender));
+        assert!(!bans.is_banned(&other));
+        assert_eq!(bans.banned_peers(), vec![offender]);
+
+        // Offences during a ban don't extend it.
+        assert!(!bans.report_offence(&offender));
+    }
+
+    #[test]
+    fn ban_ends() {
+        let bans = PeerBans::new(1, Duration::ZERO, OFFENCE_DECAY_INTERVAL);
+        let offender = NodeId::random();
+
+        assert!(bans.report_offence(&offender));
+        assert!(!bans.is_banned(&offender));
+        assert!(bans.banned_peers().is_empty());
+    }
+
+    #[test]
+    fn offences_decay() {
+        let bans = PeerBans::new(2, Duration::from_secs(60), Duration::from_secs(10));
+        let offender = NodeId::random();
+        let now = Instant::now();
+
+        assert!(!bans.report_offence_at(&offender, now));
+        // The first offence is forgiven by the time of the second one.
+        assert!(!bans.report_offence_at(&offender, now + Duration::from_secs(10)));
+        assert!(bans.report_offence_at(&offender, now + Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn recorded_peers_are_capped() {
+        let mut bans = PeerBans::new(2, Duration::from_secs(60), Duration::from_secs(10));
+        bans.max_recorded_peers = 2;
+        let (banned, offender, other) = (NodeId::random(), NodeId::random(), NodeId::random());
+        let now = Instant::now();
+
+        bans.report_offence_at(&banned, now);
+        assert!(bans.report_offence_at(&banned, now));
+        bans.report_offence_at(&offender, now);
+        bans.report_offence_at(&other, now + Duration::from_secs(1));
+
+        // The least recent offender made room, while the banned peer is kept.
+        let records = bans.records.read();
+        assert_eq!(records.len(), 2);
+        assert!(records.contains_key(&banned));
+        assert!(records.contains_key(&other));
+    }
+}
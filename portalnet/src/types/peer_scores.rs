@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::enr::NodeId;
+use parking_lot::RwLock;
+
+/// The score below which a peer is considered unreliable. Unreliable peers are only chosen as
+/// lookup candidates and gossip targets when there aren't enough reliable peers.
+pub const UNRELIABLE_PEER_SCORE: f64 = 0.5;
+
+/// The number of failed requests that a single content validation failure counts as.
+const VALIDATION_FAILURE_WEIGHT: u64 = 5;
+
+/// The length of the windows over which interactions are counted. Peers are scored by the
+/// interactions of the current and the previous window, so that old failures are forgotten.
+pub const PEER_SCORE_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+/// Statistics of the interactions with a single peer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerStats {
+    /// The number of requests that the peer responded to.
+    pub responses: u64,
+    /// The number of requests that failed or that the peer didn't respond to.
+    pub failed_requests: u64,
+    /// The sum of the latencies of the responses of the peer.
+    pub total_latency: Duration,
+    /// The number of times content received from the peer failed validation.
+    pub validation_failures: u64,
+    /// The number of successful uTP transfers with the peer.
+    pub utp_successes: u64,
+    /// The number of failed uTP transfers with the peer.
+    pub utp_failures: u64,
+}
+
+impl PeerStats {
+    /// Returns the average latency of the responses of the peer.
+    pub fn average_latency(&self) -> Option<Duration> {
+        u32::try_from(self.responses)
+            .ok()
+            .filter(|responses| *responses > 0)
+            .map(|responses| self.total_latency / responses)
+    }
+
+    /// Returns the reliability of the peer, between 0 for a peer that always fails and 1 for a
+    /// responsive peer that never failed.
+    ///
+    /// The score is the share of successful interactions, where a validation failure weighs as
+    /// much as several failed requests, scaled down by the average latency in seconds.
+    pub fn score(&self) -> f64 {
+        let successes = self.responses + self.utp_successes;
+        let failures = self.failed_requests
+            + self.utp_failures
+            + self.validation_failures * VALIDATION_FAILURE_WEIGHT;
+        let success_rate = (successes + 1) as f64 / (successes + failures + 1) as f64;
+        let latency_secs = self
+            .average_latency()
+            .map(|latency| latency.as_secs_f64())
+            .unwrap_or_default();
+        success_rate / (1.0 + latency_secs)
+    }
+
+    /// Returns the statistics of both `self` and `other`.
+    fn merged(&self, other: &PeerStats) -> PeerStats {
+        PeerStats {
+            responses: self.responses + other.responses,
+            failed_requests: self.failed_requests + other.failed_requests,
+            total_latency: self.total_latency + other.total_latency,
+            validation_failures: self.validation_failures + other.validation_failures,
+            utp_successes: self.utp_successes + other.utp_successes,
+            utp_failures: self.utp_failures + other.utp_failures,
+        }
+    }
+}
+
+/// The statistics of a peer in the current and the previous window.
+#[derive(Clone, Debug)]
+struct WindowedStats {
+    window_start: Instant,
+    current: PeerStats,
+    previous: PeerStats,
+}
+
+impl WindowedStats {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            current: PeerStats::default(),
+            previous: PeerStats::default(),
+        }
+    }
+
+    /// Starts a new window if the current one ended by `now`, forgetting the statistics of the
+    /// windows before the previous one.
+    fn advance(&mut self, now: Instant, window: Duration) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < window {
+            return;
+        }
+        let current = mem::take(&mut self.current);
+        self.previous = match elapsed < window * 2 {
+            true => current,
+            false => PeerStats::default(),
+        };
+        self.window_start = now;
+    }
+
+    /// Returns the statistics of the current and the previous window as of `now`.
+    fn stats(&self, now: Instant, window: Duration) -> PeerStats {
+        let mut stats = self.clone();
+        stats.advance(now, window);
+        stats.previous.merged(&stats.current)
+    }
+}
+
+/// Statistics of the peers of an overlay network, used to prefer reliable peers.
+///
+/// Shared by the overlay service, which records the statistics and uses them to pick lookup
+/// candidates and gossip targets, and the overlay protocol.
+#[derive(Clone, Debug)]
+pub struct PeerScores {
+    stats: Arc<RwLock<HashMap<NodeId, WindowedStats>>>,
+    window: Duration,
+}
+
+impl Default for PeerScores {
+    fn default() -> Self {
+        Self::new(PEER_SCORE_WINDOW)
+    }
+}
+
+impl PeerScores {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            stats: Arc::new(RwLock::new(HashMap::new())),
+            window,
+        }
+    }
+
+    /// Updates the statistics of `node_id` in the current window.
+    fn record(&self, node_id: NodeId, update: impl FnOnce(&mut PeerStats)) {
+        let now = Instant::now();
+        let mut stats = self.stats.write();
+        let stats = stats
+            .entry(node_id)
+            .or_insert_with(|| WindowedStats::new(now));
+        stats.advance(now, self.window);
+        update(&mut stats.current);
+    }
+
+    /// Records that `node_id` responded to a request after `latency`.
+    pub fn record_response(&self, node_id: NodeId, latency: Duration) {
+        self.record(node_id, |stats| {
+            stats.responses += 1;
+            stats.total_latency += latency;
+        });
+    }
+
+    /// Records that a request to `node_id` failed.
+    pub fn record_failed_request(&self, node_id: NodeId) {
+        self.record(node_id, |stats| stats.failed_requests += 1);
+    }
+
+    /// Records that content received from `node_id` failed validation.
+    pub fn record_validation_failure(&self, node_id: NodeId) {
+        self.record(node_id, |stats| stats.validation_failures += 1);
+    }
+
+    /// Records the outcome of a uTP transfer with `node_id`.
+    pub fn record_utp_transfer(&self, node_id: NodeId, success: bool) {
+        self.record(node_id, |stats| match success {
+            true => stats.utp_successes += 1,
+            false => stats.utp_failures += 1,
+        });
+    }
+
+    /// Returns the statistics of `node_id` in the current and the previous window.
+    pub fn stats(&self, node_id: &NodeId) -> Option<PeerStats> {
+        let now = Instant::now();
+        self.stats
+            .read()
+            .get(node_id)
+            .map(|stats| stats.stats(now, self.window))
+    }
+
+    /// Returns the score of `node_id`. Peers without statistics have the highest score.
+    pub fn score(&self, node_id: &NodeId) -> f64 {
+        self.stats(node_id)
+            .as_ref()
+            .map(PeerStats::score)
+            .unwrap_or(1.0)
+    }
+
+    /// Returns true if the score of `node_id` is below `UNRELIABLE_PEER_SCORE`.
+    pub fn is_unreliable(&self, node_id: &NodeId) -> bool {
+        self.score(node_id) < UNRELIABLE_PEER_SCORE
+    }
+
+    /// Moves the unreliable peers of `items` behind the others, keeping the order of the items
+    /// otherwise.
+    pub fn prefer_reliable<T>(&self, items: &mut [T], node_id: impl Fn(&T) -> NodeId) {
+        items.sort_by_cached_key(|item| self.is_unreliable(&node_id(item)));
+    }
+
+    /// Returns the number of peers with recorded statistics.
+    pub fn len(&self) -> usize {
+        self.stats.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stats.read().is_empty()
+    }
+
+    /// Forgets the statistics of peers for which `keep` returns false.
+    pub fn retain(&self, mut keep: impl FnMut(&NodeId) -> bool) {
+        self.stats.write().retain(|node_id, _| keep(node_id));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn failures_lower_score() {
+        let scores = PeerScores::default();
+        let (reliable, unreliable, unknown) =
+            (NodeId::random(), NodeId::random(), NodeId::random());
+
+        scores.record_response(reliable, Duration::from_millis(100));
+        scores.record_utp_transfer(reliable, true);
+        scores.record_response(unreliable, Duration::from_millis(100));
+        scores.record_failed_request(unreliable);
+        scores.record_validation_failure(unreliable);
+
+        assert_eq!(scores.score(&unknown), 1.0);
+        assert!(scores.score(&reliable) > UNRELIABLE_PEER_SCORE);
+        assert!(scores.is_unreliable(&unreliable));
+        assert_eq!(
+            scores.stats(&reliable).unwrap().average_latency(),
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn slow_responses_lower_score() {
+        let scores = PeerScores::default();
+        let (fast, slow) = (NodeId::random(), NodeId::random());
+        scores.record_response(fast, Duration::from_millis(10));
+        scores.record_response(slow, Duration::from_secs(2));
+
+        assert!(scores.score(&fast) > scores.score(&slow));
+        assert!(scores.is_unreliable(&slow));
+    }
+
+    #[test]
+    fn prefer_reliable_keeps_order() {
+        let scores = PeerScores::default();
+        let node_ids: Vec<NodeId> = (0..4).map(|_| NodeId::random()).collect();
+        scores.record_failed_request(node_ids[1]);
+        scores.record_failed_request(node_ids[1]);
+
+        let mut items = node_ids.clone();
+        scores.prefer_reliable(&mut items, |node_id| *node_id);
+        assert_eq!(
+            items,
+            vec![node_ids[0], node_ids[2], node_ids[3], node_ids[1]]
+        );
+    }
+
+    #[test]
+    fn old_stats_are_forgotten() {
+        let window = Duration::from_secs(60);
+        let now = Instant::now();
+        let mut stats = WindowedStats::new(now);
+        stats.current.failed_requests = 2;
+
+        // The previous window still counts.
+        let later = now + window;
+        assert_eq!(stats.stats(later, window).failed_requests, 2);
+        stats.advance(later, window);
+        stats.current.responses = 1;
+        assert_eq!(stats.stats(later, window).failed_requests, 2);
+
+        // Once another window ended, only the latest window counts.
+        let stats = stats.stats(later + window, window);
+        assert_eq!(stats.failed_requests, 0);
+        assert_eq!(stats.responses, 1);
+    }
+}
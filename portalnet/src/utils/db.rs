@@ -3,18 +3,24 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use directories::ProjectDirs;
 use discv5::enr::{CombinedKey, Enr, NodeId};
 use ethereum_types::H256;
 use tempfile::TempDir;
-use tracing::debug;
+use tracing::{debug, info};
 
-use ethportal_api::utils::bytes::{hex_decode, hex_encode};
+use ethportal_api::{
+    types::{cli::check_private_key_length, network::PortalNetwork},
+    utils::bytes::{hex_decode, hex_encode},
+};
+
+use crate::utils::keystore::Keystore;
 
 const TRIN_DATA_ENV_VAR: &str = "TRIN_DATA_PATH";
 const TRIN_DATA_DIR: &str = "trin";
 const UNSAFE_PRIVATE_KEY_FILE_NAME: &str = "unsafe_private_key.hex";
+const KEYSTORE_FILE_NAME: &str = "node_keystore.json";
 
 /// Create a directory on the file system that is deleted once it goes out of scope
 pub fn setup_temp_dir() -> anyhow::Result<TempDir> {
@@ -29,32 +35,41 @@ pub fn setup_temp_dir() -> anyhow::Result<TempDir> {
     Ok(temp_dir)
 }
 
-pub fn configure_trin_data_dir(ephemeral: bool) -> anyhow::Result<PathBuf> {
+/// Returns the trin data directory. Unless a directory is given by the `TRIN_DATA_PATH` env var,
+/// the default data directory of the `network` is used.
+pub fn configure_trin_data_dir(ephemeral: bool, network: PortalNetwork) -> anyhow::Result<PathBuf> {
     if ephemeral {
         setup_temp_dir().map(|temp_dir| temp_dir.into_path())
     } else {
         // Return the active trin data directory, either default or user specified.
         let trin_data_dir = match env::var(TRIN_DATA_ENV_VAR) {
             Ok(val) => PathBuf::from(val),
-            Err(_) => get_default_data_dir()?,
+            Err(_) => get_default_data_dir(network)?,
         };
         fs::create_dir_all(&trin_data_dir)?;
         Ok(trin_data_dir)
     }
 }
 
+/// Generates a random private key, which is not saved anywhere.
+pub fn generate_private_key() -> H256 {
+    H256::from_slice(&CombinedKey::generate_secp256k1().encode())
+}
+
 /// Configures active node data dir based on the provided private key.
 /// Returns the private key used to configure the node data dir.
-/// If no private key is provided, the application private key is used.
+/// If no private key is provided, the application private key is used, which is kept in an
+/// encrypted keystore if a keystore password is provided.
 pub fn configure_node_data_dir(
     trin_data_dir: PathBuf,
     private_key: Option<H256>,
+    keystore_password: Option<&str>,
 ) -> anyhow::Result<(PathBuf, H256)> {
     let pk = match private_key {
         // user has provided a custom private key...
         Some(val) => CombinedKey::secp256k1_from_bytes(val.0.clone().as_mut_slice())
             .map_err(|e| anyhow!("When building server key pair: {e:?}"))?,
-        None => get_application_private_key(&trin_data_dir)?,
+        None => get_application_private_key(&trin_data_dir, keystore_password)?,
     };
     let node_id = Enr::empty(&pk)?.node_id();
     let node_data_dir = get_node_data_dir(trin_data_dir, node_id);
@@ -63,7 +78,7 @@ pub fn configure_node_data_dir(
 }
 
 /// Returns the node data directory associated with the provided node id.
-fn get_node_data_dir(trin_data_dir: PathBuf, node_id: NodeId) -> PathBuf {
+pub fn get_node_data_dir(trin_data_dir: PathBuf, node_id: NodeId) -> PathBuf {
     // Append first 8 characters of Node ID
     let mut application_string = "trin_".to_owned();
     let node_id_string = hex_encode(node_id.raw());
@@ -72,11 +87,12 @@ fn get_node_data_dir(trin_data_dir: PathBuf, node_id: NodeId) -> PathBuf {
     trin_data_dir.join(application_string)
 }
 
-fn get_default_data_dir() -> anyhow::Result<PathBuf> {
+fn get_default_data_dir(network: PortalNetwork) -> anyhow::Result<PathBuf> {
     // Windows: C:\Users\Username\AppData\Roaming\trin
     // macOS: ~/Library/Application Support/trin
     // Unix-like: $HOME/.local/share/trin
-    match ProjectDirs::from("", "", TRIN_DATA_DIR) {
+    // Networks other than mainnet use a suffixed directory, e.g. trin-angelfood
+    match ProjectDirs::from("", "", &network.data_dir_name()) {
         Some(proj_dirs) => match proj_dirs.data_local_dir().to_str() {
             Some(val) => Ok(PathBuf::from(val)),
             None => Err(anyhow!("Unable to find default data directory")),
@@ -88,13 +104,139 @@ fn get_default_data_dir() -> anyhow::Result<PathBuf> {
 /// Returns application private key.
 /// If the private key does not exist (eg. brand new trin data dir),
 /// a random pk is generated and stored.
-fn get_application_private_key(trin_data_dir: &Path) -> anyhow::Result<CombinedKey> {
+///
+/// With a keystore password, the private key is stored in a keystore encrypted with the password.
+/// A private key that was stored in plaintext before is moved into the keystore.
+fn get_application_private_key(
+    trin_data_dir: &Path,
+    keystore_password: Option<&str>,
+) -> anyhow::Result<CombinedKey> {
+    let keystore_file = trin_data_dir.join(KEYSTORE_FILE_NAME);
+    let unsafe_private_key_file = trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME);
+    let Some(password) = keystore_password else {
+        if keystore_file.exists() {
+            bail!(
+                "The node key is stored in the encrypted keystore {}, unlock it with --keystore-password or --keystore-password-file",
+                keystore_file.display()
+            );
+        }
+        return get_unsafe_private_key(&unsafe_private_key_file);
+    };
+
+    if keystore_file.exists() {
+        let private_key = Keystore::read(&keystore_file)?.decrypt(password)?;
+        return Ok(CombinedKey::secp256k1_from_bytes(
+            private_key.0.clone().as_mut_slice(),
+        )?);
+    }
+    let pk = if unsafe_private_key_file.exists() {
+        get_unsafe_private_key(&unsafe_private_key_file)?
+    } else {
+        CombinedKey::generate_secp256k1()
+    };
+    Keystore::encrypt(H256::from_slice(&pk.encode()), password).write(&keystore_file)?;
+    if unsafe_private_key_file.exists() {
+        fs::remove_file(&unsafe_private_key_file)?;
+        info!(
+            keystore = %keystore_file.display(),
+            "Moved the plaintext node key into the encrypted keystore"
+        );
+    }
+    Ok(pk)
+}
+
+/// Returns the application private key stored in the trin data dir, without generating one if
+/// none is stored, or moving a plaintext key into the keystore. A key in the encrypted keystore is
+/// only returned with the keystore password.
+pub fn read_application_private_key(
+    trin_data_dir: &Path,
+    keystore_password: Option<&str>,
+) -> anyhow::Result<Option<H256>> {
+    let keystore_file = trin_data_dir.join(KEYSTORE_FILE_NAME);
     let unsafe_private_key_file = trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME);
+    if keystore_file.exists() {
+        let Some(password) = keystore_password else {
+            bail!(
+                "The node key is stored in the encrypted keystore {}, unlock it with --keystore-password or --keystore-password-file",
+                keystore_file.display()
+            );
+        };
+        return Ok(Some(Keystore::read(&keystore_file)?.decrypt(password)?));
+    }
+    if unsafe_private_key_file.exists() {
+        let pk = read_unsafe_private_key(&unsafe_private_key_file)?;
+        return Ok(Some(H256::from_slice(&pk.encode())));
+    }
+    Ok(None)
+}
+
+/// Reads a private key from a file holding either a hex encoded key or an encrypted keystore, which
+/// is decrypted with the keystore password.
+pub fn read_private_key_file(path: &Path, keystore_password: Option<&str>) -> anyhow::Result<H256> {
+    let contents = fs::read_to_string(path)?;
+    if contents.trim_start().starts_with('{') {
+        let Some(password) = keystore_password else {
+            bail!("Importing a keystore requires its password, given by --keystore-password or --keystore-password-file");
+        };
+        return Keystore::read(path)?.decrypt(password);
+    }
+    check_private_key_length(contents.trim()).map_err(|err| anyhow!(err))
+}
+
+/// Stores `private_key` as the application private key, in the encrypted keystore if a keystore
+/// password is provided and in plaintext otherwise. Fails if a key is already stored, unless
+/// `overwrite` is set, as replacing the key changes the node id.
+pub fn store_application_private_key(
+    trin_data_dir: &Path,
+    private_key: H256,
+    keystore_password: Option<&str>,
+    overwrite: bool,
+) -> anyhow::Result<()> {
+    CombinedKey::secp256k1_from_bytes(private_key.0.clone().as_mut_slice())
+        .map_err(|e| anyhow!("Invalid private key: {e:?}"))?;
+    let keystore_file = trin_data_dir.join(KEYSTORE_FILE_NAME);
+    let unsafe_private_key_file = trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME);
+    if !overwrite {
+        for file in [&keystore_file, &unsafe_private_key_file] {
+            if file.exists() {
+                bail!(
+                    "A node key is already stored in {}, replacing it changes the node id",
+                    file.display()
+                );
+            }
+        }
+    }
+    // The key in the other format is only removed once the new key is written, so that a key is
+    // stored even if writing fails.
+    let other_file = match keystore_password {
+        Some(password) => {
+            Keystore::encrypt(private_key, password).write(&keystore_file)?;
+            unsafe_private_key_file
+        }
+        None => {
+            let temp_file = unsafe_private_key_file.with_extension("tmp");
+            fs::write(&temp_file, hex_encode(private_key))?;
+            fs::rename(&temp_file, &unsafe_private_key_file)?;
+            keystore_file
+        }
+    };
+    if other_file.exists() {
+        fs::remove_file(other_file)?;
+    }
+    Ok(())
+}
+
+/// Returns the private key stored in plaintext, generating one if it doesn't exist yet.
+fn get_unsafe_private_key(unsafe_private_key_file: &Path) -> anyhow::Result<CombinedKey> {
     if !unsafe_private_key_file.exists() {
         let pk = CombinedKey::generate_secp256k1();
         let pk_hex = hex_encode(pk.encode());
-        fs::write(&unsafe_private_key_file, pk_hex)?;
+        fs::write(unsafe_private_key_file, pk_hex)?;
     }
+    read_unsafe_private_key(unsafe_private_key_file)
+}
+
+fn read_unsafe_private_key(unsafe_private_key_file: &Path) -> anyhow::Result<CombinedKey> {
     let private_key = fs::read_to_string(unsafe_private_key_file)?;
     let mut private_key = hex_decode(&private_key)?;
     Ok(CombinedKey::secp256k1_from_bytes(&mut private_key)?)
@@ -110,8 +252,9 @@ pub mod test {
     #[serial]
     fn app_private_key() {
         let temp_dir = setup_temp_dir().unwrap();
-        let (_, active_pk) = configure_node_data_dir(temp_dir.path().to_path_buf(), None).unwrap();
-        let app_pk = get_application_private_key(temp_dir.path()).unwrap();
+        let (_, active_pk) =
+            configure_node_data_dir(temp_dir.path().to_path_buf(), None, None).unwrap();
+        let app_pk = get_application_private_key(temp_dir.path(), None).unwrap();
         let app_pk = H256::from_slice(&app_pk.encode());
         temp_dir.close().unwrap();
         assert_eq!(active_pk, app_pk);
@@ -124,7 +267,7 @@ pub mod test {
         let pk = CombinedKey::generate_secp256k1();
         let pk = H256::from_slice(&pk.encode());
         let (_, active_pk) =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), Some(pk)).unwrap();
+            configure_node_data_dir(temp_dir.path().to_path_buf(), Some(pk), None).unwrap();
         temp_dir.close().unwrap();
         assert_eq!(pk, active_pk);
     }
@@ -133,16 +276,135 @@ pub mod test {
     #[serial]
     fn activated_private_key_persists_over_reconfigurations() {
         let temp_dir = setup_temp_dir().unwrap();
-        let (_, app_pk) = configure_node_data_dir(temp_dir.path().to_path_buf(), None).unwrap();
+        let (_, app_pk) =
+            configure_node_data_dir(temp_dir.path().to_path_buf(), None, None).unwrap();
 
         // configure data dir to use a custom pk
         let pk = CombinedKey::generate_secp256k1();
         let pk = H256::from_slice(&pk.encode());
-        let _ = configure_node_data_dir(temp_dir.path().to_path_buf(), Some(pk)).unwrap();
+        let _ = configure_node_data_dir(temp_dir.path().to_path_buf(), Some(pk), None).unwrap();
 
         // reconfigure data dir with no pk, should use the original app pk
-        let (_, app_pk_2) = configure_node_data_dir(temp_dir.path().to_path_buf(), None).unwrap();
+        let (_, app_pk_2) =
+            configure_node_data_dir(temp_dir.path().to_path_buf(), None, None).unwrap();
         temp_dir.close().unwrap();
         assert_eq!(app_pk, app_pk_2);
     }
+
+    #[test]
+    #[serial]
+    fn private_key_moves_into_encrypted_keystore() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let trin_data_dir = temp_dir.path().to_path_buf();
+        let (_, app_pk) = configure_node_data_dir(trin_data_dir.clone(), None, None).unwrap();
+
+        let (_, keystore_pk) =
+            configure_node_data_dir(trin_data_dir.clone(), None, Some("passphrase")).unwrap();
+        assert_eq!(app_pk, keystore_pk);
+        assert!(!trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+        let keystore = fs::read_to_string(trin_data_dir.join(KEYSTORE_FILE_NAME)).unwrap();
+        assert!(!keystore.contains(&hex_encode(app_pk)[2..]));
+
+        let (_, keystore_pk) =
+            configure_node_data_dir(trin_data_dir.clone(), None, Some("passphrase")).unwrap();
+        assert_eq!(app_pk, keystore_pk);
+        assert!(configure_node_data_dir(trin_data_dir.clone(), None, Some("wrong")).is_err());
+        assert!(configure_node_data_dir(trin_data_dir, None, None).is_err());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn stored_private_key_is_not_overwritten() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let trin_data_dir = temp_dir.path();
+        assert_eq!(
+            read_application_private_key(trin_data_dir, None).unwrap(),
+            None
+        );
+
+        let pk = generate_private_key();
+        store_application_private_key(trin_data_dir, pk, None, false).unwrap();
+        assert_eq!(
+            read_application_private_key(trin_data_dir, None).unwrap(),
+            Some(pk)
+        );
+        assert!(
+            store_application_private_key(trin_data_dir, generate_private_key(), None, false)
+                .is_err()
+        );
+
+        // Overwriting with a keystore password replaces the plaintext key with the keystore.
+        let other_pk = generate_private_key();
+        store_application_private_key(trin_data_dir, other_pk, Some("passphrase"), true).unwrap();
+        assert!(!trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+        assert!(read_application_private_key(trin_data_dir, None).is_err());
+        assert_eq!(
+            read_application_private_key(trin_data_dir, Some("passphrase")).unwrap(),
+            Some(other_pk)
+        );
+        let (_, active_pk) =
+            configure_node_data_dir(trin_data_dir.to_path_buf(), None, Some("passphrase")).unwrap();
+        assert_eq!(active_pk, other_pk);
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn reading_private_key_doesnt_move_it_into_keystore() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let trin_data_dir = temp_dir.path();
+        let pk = generate_private_key();
+        store_application_private_key(trin_data_dir, pk, None, false).unwrap();
+
+        assert_eq!(
+            read_application_private_key(trin_data_dir, Some("passphrase")).unwrap(),
+            Some(pk)
+        );
+        assert!(trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+        assert!(!trin_data_dir.join(KEYSTORE_FILE_NAME).exists());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn private_key_file_is_imported() {
+        let temp_dir = TempDir::new().unwrap();
+        let pk = generate_private_key();
+        let hex_file = temp_dir.path().join("node_key.hex");
+        fs::write(&hex_file, format!("{}\n", hex_encode(pk))).unwrap();
+        assert_eq!(read_private_key_file(&hex_file, None).unwrap(), pk);
+
+        let invalid_file = temp_dir.path().join("invalid_key.hex");
+        fs::write(&invalid_file, "0x1234").unwrap();
+        assert!(read_private_key_file(&invalid_file, None).is_err());
+
+        let keystore_file = temp_dir.path().join("node_keystore.json");
+        Keystore::encrypt(pk, "passphrase")
+            .write(&keystore_file)
+            .unwrap();
+        assert!(read_private_key_file(&keystore_file, None).is_err());
+        assert!(read_private_key_file(&keystore_file, Some("wrong")).is_err());
+        let imported_pk = read_private_key_file(&keystore_file, Some("passphrase")).unwrap();
+        assert_eq!(imported_pk, pk);
+
+        // An imported key replaces the stored key only when overwriting.
+        let trin_data_dir = temp_dir.path().join("trin");
+        fs::create_dir_all(&trin_data_dir).unwrap();
+        store_application_private_key(&trin_data_dir, generate_private_key(), None, false).unwrap();
+        assert!(store_application_private_key(
+            &trin_data_dir,
+            imported_pk,
+            Some("passphrase"),
+            false
+        )
+        .is_err());
+        store_application_private_key(&trin_data_dir, imported_pk, Some("passphrase"), true)
+            .unwrap();
+        assert_eq!(
+            read_application_private_key(&trin_data_dir, Some("passphrase")).unwrap(),
+            Some(pk)
+        );
+        assert!(!trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+        assert!(!trin_data_dir.join("node_keystore.tmp").exists());
+    }
 }
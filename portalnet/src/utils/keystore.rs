@@ -0,0 +1,262 @@
+use std::{fs, path::Path};
+
+use aes::Aes128;
+use anyhow::{anyhow, ensure};
+use ctr::{
+    cipher::{NewCipher, StreamCipher},
+    Ctr128BE,
+};
+use ethereum_types::H256;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use ethportal_api::utils::bytes::{hex_decode, hex_encode};
+
+/// Number of PBKDF2 iterations used to derive the encryption key from the password, as
+/// recommended by EIP-2335.
+const PBKDF2_ITERATIONS: u32 = 262_144;
+const KEYSTORE_VERSION: u32 = 4;
+
+/// A private key encrypted with a password, in the keystore format of EIP-2335: the key is
+/// encrypted with AES-128-CTR, under a key derived from the password with PBKDF2-HMAC-SHA256.
+///
+/// Unlike EIP-2335, the password isn't normalized, but used as its UTF-8 bytes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keystore {
+    crypto: KeystoreCrypto,
+    description: String,
+    uuid: String,
+    version: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct KeystoreCrypto {
+    kdf: KeystoreModule<KdfParams>,
+    checksum: KeystoreModule<serde_json::Map<String, serde_json::Value>>,
+    cipher: KeystoreModule<CipherParams>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct KeystoreModule<P> {
+    function: String,
+    params: P,
+    message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct KdfParams {
+    dklen: u32,
+    c: u32,
+    prf: String,
+    salt: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+impl Keystore {
+    /// Encrypts the private key with the password.
+    pub fn encrypt(private_key: H256, password: &str) -> Self {
+        Self::encrypt_with_iterations(private_key, password, PBKDF2_ITERATIONS)
+    }
+
+    fn encrypt_with_iterations(private_key: H256, password: &str, iterations: u32) -> Self {
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        let mut uuid = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut iv);
+        rand::thread_rng().fill_bytes(&mut uuid);
+
+        let derived_key = pbkdf2_hmac_sha256(password.as_bytes(), &salt, iterations);
+        let mut ciphertext = private_key.as_bytes().to_vec();
+        apply_cipher(&derived_key, &iv, &mut ciphertext);
+        let checksum = checksum(&derived_key, &ciphertext);
+
+        Self {
+            crypto: KeystoreCrypto {
+                kdf: KeystoreModule {
+                    function: "pbkdf2".to_string(),
+                    params: KdfParams {
+                        dklen: 32,
+                        c: iterations,
+                        prf: "hmac-sha256".to_string(),
+                        salt: encode_hex_field(salt),
+                    },
+                    message: String::new(),
+                },
+                checksum: KeystoreModule {
+                    function: "sha256".to_string(),
+                    params: serde_json::Map::new(),
+                    message: encode_hex_field(checksum),
+                },
+                cipher: KeystoreModule {
+                    function: "aes-128-ctr".to_string(),
+                    params: CipherParams {
+                        iv: encode_hex_field(iv),
+                    },
+                    message: encode_hex_field(ciphertext),
+                },
+            },
+            description: "trin node key".to_string(),
+            uuid: format_uuid(uuid),
+            version: KEYSTORE_VERSION,
+        }
+    }
+
+    /// Decrypts the private key with the password, failing if the password is wrong.
+    pub fn decrypt(&self, password: &str) -> anyhow::Result<H256> {
+        let crypto = &self.crypto;
+        ensure!(
+            self.version == KEYSTORE_VERSION,
+            "Unsupported keystore version: {}",
+            self.version
+        );
+        ensure!(
+            crypto.kdf.function == "pbkdf2"
+                && crypto.kdf.params.prf == "hmac-sha256"
+                && crypto.kdf.params.dklen == 32,
+            "Unsupported keystore key derivation function: {}",
+            crypto.kdf.function
+        );
+        ensure!(
+            crypto.checksum.function == "sha256",
+            "Unsupported keystore checksum function: {}",
+            crypto.checksum.function
+        );
+        ensure!(
+            crypto.cipher.function == "aes-128-ctr",
+            "Unsupported keystore cipher: {}",
+            crypto.cipher.function
+        );
+
+        let salt = decode_hex_field(&crypto.kdf.params.salt)?;
+        let iv = decode_hex_field(&crypto.cipher.params.iv)?;
+        let mut ciphertext = decode_hex_field(&crypto.cipher.message)?;
+        ensure!(
+            iv.len() == 16 && ciphertext.len() == 32,
+            "Invalid keystore cipher params"
+        );
+
+        let derived_key = pbkdf2_hmac_sha256(password.as_bytes(), &salt, crypto.kdf.params.c);
+        let expected_checksum = decode_hex_field(&crypto.checksum.message)?;
+        ensure!(
+            checksum(&derived_key, &ciphertext).as_slice() == expected_checksum.as_slice(),
+            "Invalid keystore password"
+        );
+        apply_cipher(&derived_key, &iv, &mut ciphertext);
+        Ok(H256::from_slice(&ciphertext))
+    }
+
+    /// Reads the keystore from a file.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let keystore = fs::read_to_string(path)?;
+        serde_json::from_str(&keystore)
+            .map_err(|err| anyhow!("Invalid keystore at {}: {err}", path.display()))
+    }
+
+    /// Writes the keystore to a file. The keystore is written to a temporary file first, which
+    /// then replaces the file, so that a failed write doesn't leave a partial keystore behind.
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Keystore fields are hex encoded without a 0x prefix.
+fn encode_hex_field(bytes: impl AsRef<[u8]>) -> String {
+    hex_encode(bytes)[2..].to_string()
+}
+
+fn decode_hex_field(field: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(hex_decode(&format!("0x{field}"))?)
+}
+
+/// Derives a 32 byte key from the password. A single PBKDF2 block suffices for the key length.
+fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let prf = Hmac::<Sha256>::new_from_slice(password).expect("HMAC to accept keys of any length");
+    let mut mac = prf.clone();
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut block = mac.finalize().into_bytes();
+    let mut derived_key = [0u8; 32];
+    derived_key.copy_from_slice(&block);
+    for _ in 1..iterations {
+        let mut mac = prf.clone();
+        mac.update(&block);
+        block = mac.finalize().into_bytes();
+        for (byte, block_byte) in derived_key.iter_mut().zip(block.iter()) {
+            *byte ^= block_byte;
+        }
+    }
+    derived_key
+}
+
+/// Encrypts or decrypts the data in place with the first half of the derived key.
+fn apply_cipher(derived_key: &[u8; 32], iv: &[u8], data: &mut [u8]) {
+    let mut cipher = Ctr128BE::<Aes128>::new_from_slices(&derived_key[..16], iv)
+        .expect("AES-128-CTR to accept a 16 byte key and iv");
+    cipher.apply_keystream(data);
+}
+
+/// Checksums the ciphertext with the second half of the derived key, to detect wrong passwords.
+fn checksum(derived_key: &[u8; 32], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(&derived_key[16..]);
+    hasher.update(ciphertext);
+    hasher.finalize().to_vec()
+}
+
+/// Formats random bytes as a version 4 UUID.
+fn format_uuid(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = encode_hex_field(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pbkdf2_hmac_sha256() {
+        // Test vectors of PBKDF2-HMAC-SHA256 with a 32 byte key
+        assert_eq!(
+            encode_hex_field(pbkdf2_hmac_sha256(b"password", b"salt", 1)),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(
+            encode_hex_field(pbkdf2_hmac_sha256(b"password", b"salt", 2)),
+            "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+        );
+    }
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let private_key = H256::random();
+        let keystore = Keystore::encrypt_with_iterations(private_key, "passphrase", 16);
+        let keystore: Keystore =
+            serde_json::from_str(&serde_json::to_string(&keystore).unwrap()).unwrap();
+        assert_eq!(keystore.decrypt("passphrase").unwrap(), private_key);
+        assert!(keystore
+            .decrypt("wrong passphrase")
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid keystore password"));
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    time::{sleep_until, Instant},
+};
+use tracing::warn;
+
+use crate::{overlay_service::OverlayCommand, store_actor::StoreHandle};
+use ethportal_api::OverlayContentKey;
+use trin_storage::{error::ContentStoreError, ContentStore};
+
+/// The default maximum number of content items in a batch.
+pub const DEFAULT_WRITE_BATCH_MAX_ITEMS: usize = 64;
+
+/// The default maximum time that a content item waits for its batch to be written.
+pub const DEFAULT_WRITE_BATCH_MAX_DELAY: Duration = Duration::from_millis(50);
+
+/// Limits of the batches in which content received through gossip is written to the store.
+///
+/// A batch is written once it holds `max_items` content items, or once its first item has waited
+/// for `max_delay`, whichever comes first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteBatchConfig {
+    pub max_items: usize,
+    pub max_delay: Duration,
+}
+
+impl Default for WriteBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_items: DEFAULT_WRITE_BATCH_MAX_ITEMS,
+            max_delay: DEFAULT_WRITE_BATCH_MAX_DELAY,
+        }
+    }
+}
+
+/// A command to the task that writes the batches.
+#[derive(Debug)]
+enum WriteCommand<TContentKey> {
+    /// Write content to the store with the next batch.
+    Write(TContentKey, Vec<u8>),
+    /// Write the pending batch and stop, notifying the sender afterwards.
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Writes content received through gossip to the store in batches, so that a burst of gossip,
+/// e.g. from a bridge, is written in a few transactions rather than one per content item.
+///
+/// Content is only visible in the store once its batch is written, after which the overlay
+/// service is notified that the content was stored.
+#[derive(Clone, Debug)]
+pub struct ContentWriter<TContentKey> {
+    write_tx: UnboundedSender<WriteCommand<TContentKey>>,
+}
+
+impl<TContentKey: 'static + OverlayContentKey + Send + Sync> ContentWriter<TContentKey> {
+    /// Spawns the task that writes batches of content to `store`. The task ends once every
+    /// `ContentWriter` handle is dropped or once it's shut down, after writing the last batch.
+    pub fn spawn<TStore: 'static + ContentStore + Send + Sync>(
+        store: StoreHandle<TStore>,
+        config: WriteBatchConfig,
+        command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+    ) -> Self {
+        let (write_tx, write_rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_batches(store, config, write_rx, command_tx));
+        Self { write_tx }
+    }
+
+    /// Queues content to be written to the store with the next batch.
+    pub fn write(&self, content_key: TContentKey, content_value: Vec<u8>) {
+        let command = WriteCommand::Write(content_key, content_value);
+        if self.write_tx.send(command).is_err() {
+            warn!("Unable to queue content write, the content writer has stopped");
+        }
+    }
+
+    /// Writes the content that was queued before, without waiting for the batch delay, and stops
+    /// the writer. Returns once the content is written.
+    pub async fn shutdown(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.write_tx.send(WriteCommand::Shutdown(done_tx)).is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+async fn write_batches<TContentKey, TStore>(
+    store: StoreHandle<TStore>,
+    config: WriteBatchConfig,
+    mut write_rx: UnboundedReceiver<WriteCommand<TContentKey>>,
+    command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+) where
+    TContentKey: 'static + OverlayContentKey + Send,
+    TStore: 'static + ContentStore + Send + Sync,
+{
+    loop {
+        let first = match write_rx.recv().await {
+            Some(WriteCommand::Write(content_key, content_value)) => (content_key, content_value),
+            Some(WriteCommand::Shutdown(done_tx)) => {
+                let _ = done_tx.send(());
+                return;
+            }
+            None => return,
+        };
+        let deadline = Instant::now() + config.max_delay;
+        let mut batch = vec![first];
+        let mut shutdown = None;
+        while batch.len() < config.max_items {
+            tokio::select! {
+                command = write_rx.recv() => match command {
+                    Some(WriteCommand::Write(content_key, content_value)) => {
+                        batch.push((content_key, content_value))
+                    }
+                    Some(WriteCommand::Shutdown(done_tx)) => {
+                        shutdown = Some(done_tx);
+                        break;
+                    }
+                    None => break,
+                },
+                _ = sleep_until(deadline) => break,
+            }
+        }
+
+        write_batch(&store, batch, &command_tx).await;
+        if let Some(done_tx) = shutdown {
+            let _ = done_tx.send(());
+            return;
+        }
+    }
+}
+
+/// Writes a batch of content to the store, notifying the overlay service of the stored content.
+async fn write_batch<TContentKey, TStore>(
+    store: &StoreHandle<TStore>,
+    batch: Vec<(TContentKey, Vec<u8>)>,
+    command_tx: &UnboundedSender<OverlayCommand<TContentKey>>,
+) where
+    TContentKey: 'static + OverlayContentKey + Send,
+    TStore: 'static + ContentStore + Send + Sync,
+{
+    let content_keys: Vec<TContentKey> = batch.iter().map(|(key, _)| key.clone()).collect();
+    let results = match store.put_batch(batch).await {
+        Ok(results) => results,
+        Err(err) => content_keys
+            .iter()
+            .map(|_| Err(ContentStoreError::Database(err.to_string())))
+            .collect(),
+    };
+    for (content_key, result) in content_keys.into_iter().zip(results) {
+        match result {
+            Ok(()) => {
+                let _ = command_tx.send(OverlayCommand::ContentStored(content_key));
+            }
+            Err(err) => warn!(
+                error = %err,
+                content.key = %content_key.to_hex(),
+                "Error storing accepted content"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use discv5::enr::NodeId;
+    use ethportal_api::IdentityContentKey;
+    use trin_storage::{DistanceFunction, MemoryContentStore};
+
+    #[test_log::test(tokio::test)]
+    async fn writes_full_batches_and_flushes_after_delay() {
+        let store = StoreHandle::spawn(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+        );
+        let config = WriteBatchConfig {
+            max_items: 2,
+            max_delay: Duration::from_millis(10),
+        };
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let writer = ContentWriter::spawn(store.clone(), config, command_tx);
+
+        let content_keys: Vec<IdentityContentKey> =
+            (0..3u8).map(|i| IdentityContentKey::new([i; 32])).collect();
+        for content_key in &content_keys {
+            writer.write(content_key.clone(), vec![1, 2, 3]);
+        }
+
+        // The first two items are written as a full batch, the last one once the delay passed.
+        for content_key in &content_keys {
+            match command_rx.recv().await.unwrap() {
+                OverlayCommand::ContentStored(stored_key) => assert_eq!(&stored_key, content_key),
+                _ => panic!("Unexpected overlay command"),
+            }
+            assert_eq!(
+                store.get(content_key.clone()).await.unwrap(),
+                Some(vec![1, 2, 3])
+            );
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn shutdown_writes_queued_content() {
+        let store = StoreHandle::spawn(
+            MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor),
+            "test-store".to_string(),
+        );
+        // The batch would only be written after an hour, if not for the shutdown.
+        let config = WriteBatchConfig {
+            max_items: 64,
+            max_delay: Duration::from_secs(3600),
+        };
+        let (command_tx, _command_rx) = mpsc::unbounded_channel();
+        let writer = ContentWriter::spawn(store.clone(), config, command_tx);
+
+        let content_key = IdentityContentKey::new([1; 32]);
+        writer.write(content_key.clone(), vec![1, 2, 3]);
+        tokio::time::timeout(Duration::from_secs(5), writer.shutdown())
+            .await
+            .unwrap();
+        assert_eq!(store.get(content_key).await.unwrap(), Some(vec![1, 2, 3]));
+    }
+}
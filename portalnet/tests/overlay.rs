@@ -5,7 +5,6 @@ use std::{
 };
 
 use discv5::TalkRequest;
-use parking_lot::RwLock;
 use tokio::{
     sync::{mpsc, mpsc::unbounded_channel},
     time::{self, Duration},
@@ -27,7 +26,7 @@ use portalnet::{
     overlay::{OverlayConfig, OverlayProtocol},
     utils::db::setup_temp_dir,
 };
-use trin_storage::{ContentStore, DistanceFunction, MemoryContentStore};
+use trin_storage::{DistanceFunction, MemoryContentStore};
 use trin_validation::validator::MockValidator;
 
 async fn init_overlay(
@@ -38,7 +37,6 @@ async fn init_overlay(
 
     let node_id = discovery.local_enr().node_id();
     let store = MemoryContentStore::new(node_id, DistanceFunction::Xor);
-    let store = Arc::new(RwLock::new(store));
 
     let (_utp_talk_req_tx, utp_talk_req_rx) = unbounded_channel();
     let discv5_utp = Discv5UdpSocket::new(Arc::clone(&discovery), utp_talk_req_rx);
@@ -237,8 +235,8 @@ async fn overlay() {
     let content = vec![0xef];
     overlay_three
         .store
-        .write()
-        .put(content_key.clone(), &content)
+        .put(content_key.clone(), content.clone())
+        .await
         .expect("Unable to store content");
     match overlay_one.lookup_content(content_key, false).await {
         (Some(found_content), utp_transfer, _) => {
@@ -0,0 +1,162 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use tokio::sync::{mpsc, Notify};
+use tracing::info;
+
+use ethportal_api::{
+    types::{
+        admin::{AdminNodeInfo, DbStats, PeerInfo},
+        cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+        history::ImportLocalContentInfo,
+        jsonrpc::{
+            endpoints::{BeaconEndpoint, HistoryEndpoint, StateEndpoint},
+            request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+        },
+    },
+    AdminApiServer,
+};
+use portalnet::discovery::Discovery;
+use trin_utils::version::get_trin_client_version;
+
+use crate::{
+    errors::RpcServeError,
+    fetch::{
+        proxy_query_to_beacon_subnet, proxy_query_to_history_subnet, proxy_query_to_state_subnet,
+    },
+    jsonrpsee::core::{async_trait, RpcResult},
+    serde::from_value,
+};
+
+pub struct AdminApi {
+    discv5: Arc<Discovery>,
+    history_network: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+    state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    beacon_network: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+    shutdown: Arc<Notify>,
+}
+
+impl AdminApi {
+    pub fn new(
+        discv5: Arc<Discovery>,
+        history_network: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+        state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+        beacon_network: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+        shutdown: Arc<Notify>,
+    ) -> Self {
+        Self {
+            discv5,
+            history_network,
+            state_network,
+            beacon_network,
+            shutdown,
+        }
+    }
+
+    /// Names of the subnetworks that this node takes part in.
+    fn networks(&self) -> Vec<String> {
+        let mut networks = vec![];
+        if self.history_network.is_some() {
+            networks.push(HISTORY_NETWORK.to_string());
+        }
+        if self.state_network.is_some() {
+            networks.push(STATE_NETWORK.to_string());
+        }
+        if self.beacon_network.is_some() {
+            networks.push(BEACON_NETWORK.to_string());
+        }
+        networks
+    }
+
+    /// Returns the history network, which serves the endpoints that operate on the whole content
+    /// database.
+    fn history_network(
+        &self,
+    ) -> Result<&mpsc::UnboundedSender<HistoryJsonRpcRequest>, RpcServeError> {
+        self.history_network.as_ref().ok_or_else(|| {
+            RpcServeError::Message("The history network must be enabled".to_string())
+        })
+    }
+}
+
+#[async_trait]
+impl AdminApiServer for AdminApi {
+    async fn node_info(&self) -> RpcResult<AdminNodeInfo> {
+        let node_info = self
+            .discv5
+            .node_info()
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        Ok(AdminNodeInfo {
+            enr: node_info.enr,
+            node_id: node_info.node_id,
+            ip: node_info.ip,
+            listen_address: self.discv5.listen_socket.to_string(),
+            client_version: get_trin_client_version(),
+            networks: self.networks(),
+        })
+    }
+
+    async fn peers(&self) -> RpcResult<BTreeMap<String, Vec<PeerInfo>>> {
+        let mut peers = BTreeMap::new();
+        if let Some(network) = &self.history_network {
+            let result = proxy_query_to_history_subnet(network, HistoryEndpoint::Peers).await?;
+            peers.insert(HISTORY_NETWORK.to_string(), from_value(result)?);
+        }
+        if let Some(network) = &self.state_network {
+            let result = proxy_query_to_state_subnet(network, StateEndpoint::Peers).await?;
+            peers.insert(STATE_NETWORK.to_string(), from_value(result)?);
+        }
+        if let Some(network) = &self.beacon_network {
+            let result = proxy_query_to_beacon_subnet(network, BeaconEndpoint::Peers).await?;
+            peers.insert(BEACON_NETWORK.to_string(), from_value(result)?);
+        }
+        Ok(peers)
+    }
+
+    async fn db_stats(&self) -> RpcResult<BTreeMap<String, DbStats>> {
+        let mut stats = BTreeMap::new();
+        if let Some(network) = &self.history_network {
+            let result = proxy_query_to_history_subnet(network, HistoryEndpoint::DbStats).await?;
+            stats.insert(HISTORY_NETWORK.to_string(), from_value(result)?);
+        }
+        if let Some(network) = &self.beacon_network {
+            let result = proxy_query_to_beacon_subnet(network, BeaconEndpoint::DbStats).await?;
+            stats.insert(BEACON_NETWORK.to_string(), from_value(result)?);
+        }
+        Ok(stats)
+    }
+
+    async fn backup_db(&self, path: PathBuf) -> RpcResult<u64> {
+        let network = self.history_network()?;
+        let result =
+            proxy_query_to_history_subnet(network, HistoryEndpoint::BackupDb(path)).await?;
+        Ok(from_value(result)?)
+    }
+
+    async fn export_local_content(&self, path: PathBuf, offset: u64, limit: u64) -> RpcResult<u64> {
+        let network = self.history_network()?;
+        let endpoint = HistoryEndpoint::ExportLocalContent(path, offset, limit);
+        let result = proxy_query_to_history_subnet(network, endpoint).await?;
+        Ok(from_value(result)?)
+    }
+
+    async fn import_local_content(&self, path: PathBuf) -> RpcResult<ImportLocalContentInfo> {
+        let network = self.history_network()?;
+        let result =
+            proxy_query_to_history_subnet(network, HistoryEndpoint::ImportLocalContent(path))
+                .await?;
+        Ok(from_value(result)?)
+    }
+
+    async fn shutdown(&self) -> RpcResult<bool> {
+        info!("Shutdown requested over json-rpc");
+        // Stores a permit if nobody is waiting yet, so that the request is never missed.
+        self.shutdown.notify_one();
+        Ok(true)
+    }
+}
+
+impl std::fmt::Debug for AdminApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminApi").finish_non_exhaustive()
+    }
+}
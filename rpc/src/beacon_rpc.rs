@@ -1,4 +1,4 @@
-use crate::{errors::RpcServeError, serde::from_value};
+use crate::{fetch::proxy_query_to_beacon_subnet, serde::from_value};
 
 use crate::jsonrpsee::core::{async_trait, RpcResult};
 use discv5::enr::NodeId;
@@ -13,7 +13,6 @@ use ethportal_api::{
     BeaconContentKey, BeaconContentValue, BeaconNetworkApiServer, PossibleBeaconContentValue,
     RoutingTableInfo,
 };
-use serde_json::Value;
 use tokio::sync::mpsc;
 
 pub struct BeaconNetworkApi {
@@ -25,28 +24,6 @@ impl BeaconNetworkApi {
     pub fn new(network: mpsc::UnboundedSender<BeaconJsonRpcRequest>) -> Self {
         Self { network }
     }
-
-    pub async fn proxy_query_to_beacon_subnet(
-        &self,
-        endpoint: BeaconEndpoint,
-    ) -> Result<Value, RpcServeError> {
-        let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
-        let message = BeaconJsonRpcRequest {
-            endpoint,
-            resp: resp_tx,
-        };
-        let _ = self.network.send(message);
-
-        match resp_rx.recv().await {
-            Some(val) => match val {
-                Ok(result) => Ok(result),
-                Err(msg) => Err(RpcServeError::Message(msg)),
-            },
-            None => Err(RpcServeError::Message(
-                "Internal error: No response from chain beacon subnetwork".to_string(),
-            )),
-        }
-    }
 }
 
 #[async_trait]
@@ -54,7 +31,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Returns meta information about overlay routing table.
     async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo> {
         let endpoint = BeaconEndpoint::RoutingTableInfo;
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: RoutingTableInfo = from_value(result)?;
         Ok(result)
     }
@@ -62,7 +39,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Write an Ethereum Node Record to the overlay routing table.
     async fn add_enr(&self, enr: Enr) -> RpcResult<bool> {
         let endpoint = BeaconEndpoint::AddEnr(enr);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: bool = from_value(result)?;
         Ok(result)
     }
@@ -70,7 +47,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Fetch the latest ENR associated with the given node ID.
     async fn get_enr(&self, node_id: NodeId) -> RpcResult<Enr> {
         let endpoint = BeaconEndpoint::GetEnr(node_id);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: Enr = from_value(result)?;
         Ok(result)
     }
@@ -78,7 +55,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Delete Node ID from the overlay routing table.
     async fn delete_enr(&self, node_id: NodeId) -> RpcResult<bool> {
         let endpoint = BeaconEndpoint::DeleteEnr(node_id);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: bool = from_value(result)?;
         Ok(result)
     }
@@ -86,7 +63,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Fetch the ENR representation associated with the given Node ID.
     async fn lookup_enr(&self, node_id: NodeId) -> RpcResult<Enr> {
         let endpoint = BeaconEndpoint::LookupEnr(node_id);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: Enr = from_value(result)?;
         Ok(result)
     }
@@ -94,7 +71,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Send a PING message to the designated node and wait for a PONG response
     async fn ping(&self, enr: Enr) -> RpcResult<PongInfo> {
         let endpoint = BeaconEndpoint::Ping(enr);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: PongInfo = from_value(result)?;
         Ok(result)
     }
@@ -103,7 +80,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// designated peer and wait for a response
     async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo> {
         let endpoint = BeaconEndpoint::FindNodes(enr, distances);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: FindNodesInfo = from_value(result)?;
         Ok(result)
     }
@@ -111,7 +88,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Lookup a target node within in the network
     async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>> {
         let endpoint = BeaconEndpoint::RecursiveFindNodes(node_id);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: Vec<Enr> = from_value(result)?;
         Ok(result)
     }
@@ -119,7 +96,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     /// Lookup a target node within in the network
     async fn radius(&self) -> RpcResult<DataRadius> {
         let endpoint = BeaconEndpoint::DataRadius;
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: DataRadius = from_value(result)?;
         Ok(result)
     }
@@ -131,7 +108,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_key: BeaconContentKey,
     ) -> RpcResult<ContentInfo> {
         let endpoint = BeaconEndpoint::FindContent(enr, content_key);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: ContentInfo = from_value(result)?;
         Ok(result)
     }
@@ -142,7 +119,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_key: BeaconContentKey,
     ) -> RpcResult<ContentInfo> {
         let endpoint = BeaconEndpoint::RecursiveFindContent(content_key);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         if result == serde_json::Value::String(CONTENT_ABSENT.to_string()) {
             return Ok(ContentInfo::Content {
                 content: PossibleBeaconContentValue::ContentAbsent,
@@ -159,7 +136,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_key: BeaconContentKey,
     ) -> RpcResult<TraceContentInfo> {
         let endpoint = BeaconEndpoint::TraceRecursiveFindContent(content_key);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let info: TraceContentInfo = from_value(result)?;
         Ok(info)
     }
@@ -171,7 +148,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         limit: u64,
     ) -> RpcResult<PaginateLocalContentInfo> {
         let endpoint = BeaconEndpoint::PaginateLocalContentKeys(offset, limit);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: PaginateLocalContentInfo = from_value(result)?;
         Ok(result)
     }
@@ -184,7 +161,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_value: BeaconContentValue,
     ) -> RpcResult<u32> {
         let endpoint = BeaconEndpoint::Gossip(content_key, content_value);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: u32 = from_value(result)?;
         Ok(result)
     }
@@ -197,7 +174,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_value: BeaconContentValue,
     ) -> RpcResult<TraceGossipInfo> {
         let endpoint = BeaconEndpoint::TraceGossip(content_key, content_value);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: TraceGossipInfo = from_value(result)?;
         Ok(result)
     }
@@ -212,7 +189,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_value: Option<BeaconContentValue>,
     ) -> RpcResult<AcceptInfo> {
         let endpoint = BeaconEndpoint::Offer(enr, content_key, content_value);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: AcceptInfo = from_value(result)?;
         Ok(result)
     }
@@ -224,7 +201,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_value: BeaconContentValue,
     ) -> RpcResult<bool> {
         let endpoint = BeaconEndpoint::Store(content_key, content_value);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         let result: bool = from_value(result)?;
         Ok(result)
     }
@@ -235,7 +212,7 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         content_key: BeaconContentKey,
     ) -> RpcResult<PossibleBeaconContentValue> {
         let endpoint = BeaconEndpoint::LocalContent(content_key);
-        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result = proxy_query_to_beacon_subnet(&self.network, endpoint).await?;
         if result == serde_json::Value::String(CONTENT_ABSENT.to_string()) {
             return Ok(PossibleBeaconContentValue::ContentAbsent);
         };
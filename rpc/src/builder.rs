@@ -2,11 +2,19 @@ use crate::{
     errors::{RpcError, WsHttpSamePortError},
     jsonrpsee::{Methods, RpcModule},
     rpc_server::{RpcServerConfig, RpcServerHandle},
-    BeaconNetworkApi, Discv5Api, EthApi, HistoryNetworkApi, Web3Api,
+    AdminApi, BeaconNetworkApi, Discv5Api, EthApi, HistoryNetworkApi, NetApi, StateNetworkApi,
+    Web3Api,
 };
 use ethportal_api::{
-    types::jsonrpc::request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
-    BeaconNetworkApiServer, Discv5ApiServer, EthApiServer, HistoryNetworkApiServer, Web3ApiServer,
+    types::{
+        cli::DEFAULT_MAX_LOGS_BLOCK_RANGE,
+        jsonrpc::request::{
+            BeaconJsonRpcRequest, CanonicalIndicesJsonRpcRequest, HistoryJsonRpcRequest,
+            StateJsonRpcRequest,
+        },
+    },
+    AdminApiServer, BeaconNetworkApiServer, Discv5ApiServer, EthApiServer, HistoryNetworkApiServer,
+    NetApiServer, StateNetworkApiServer, Web3ApiServer,
 };
 use portalnet::discovery::Discovery;
 use serde::Deserialize;
@@ -16,7 +24,7 @@ use std::{
     sync::Arc,
 };
 use strum::{AsRefStr, EnumString, EnumVariantNames, VariantNames};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
 
 /// Represents RPC modules that are supported by Trin
 #[derive(
@@ -25,6 +33,8 @@ use tokio::sync::mpsc;
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "kebab-case")]
 pub enum PortalRpcModule {
+    /// `admin_` module
+    Admin,
     /// `portal_beacon` module
     Beacon,
     /// `discv5_` module
@@ -33,6 +43,10 @@ pub enum PortalRpcModule {
     Eth,
     /// `portal_history` module
     History,
+    /// `net_` module
+    Net,
+    /// `portal_state` module
+    State,
     /// `web3_` module
     Web3,
 }
@@ -284,6 +298,12 @@ pub struct RpcModuleBuilder {
     beacon_tx: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
     /// State protocol
     state_tx: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    /// Canonical indices protocol
+    canonical_indices_tx: Option<mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>>,
+    /// Maximum number of blocks scanned by a single eth_getLogs request
+    max_logs_block_range: u64,
+    /// Notified when a client requests the node to shut down
+    shutdown: Arc<Notify>,
 }
 
 impl RpcModuleBuilder {
@@ -294,6 +314,9 @@ impl RpcModuleBuilder {
             history_tx: None,
             beacon_tx: None,
             state_tx: None,
+            canonical_indices_tx: None,
+            max_logs_block_range: DEFAULT_MAX_LOGS_BLOCK_RANGE,
+            shutdown: Default::default(),
         }
     }
 
@@ -313,6 +336,14 @@ impl RpcModuleBuilder {
         self
     }
 
+    pub fn maybe_with_canonical_indices(
+        mut self,
+        canonical_indices_tx: Option<mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>>,
+    ) -> Self {
+        self.canonical_indices_tx = canonical_indices_tx;
+        self
+    }
+
     pub fn maybe_with_beacon(
         mut self,
         beacon_tx: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
@@ -339,6 +370,25 @@ impl RpcModuleBuilder {
         self
     }
 
+    pub fn with_canonical_indices(
+        mut self,
+        canonical_indices_tx: mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>,
+    ) -> Self {
+        self.canonical_indices_tx = Some(canonical_indices_tx);
+        self
+    }
+
+    pub fn with_max_logs_block_range(mut self, max_logs_block_range: u64) -> Self {
+        self.max_logs_block_range = max_logs_block_range;
+        self
+    }
+
+    /// Returns the signal that the `admin_` module notifies when a client requests the node to
+    /// shut down.
+    pub fn shutdown_signal(&self) -> Arc<Notify> {
+        self.shutdown.clone()
+    }
+
     /// Returns all installed methods
     pub fn methods(&self) -> Vec<Methods> {
         self.modules.values().cloned().collect()
@@ -401,6 +451,15 @@ impl RpcModuleBuilder {
                 self.modules
                     .entry(namespace)
                     .or_insert_with(|| match namespace {
+                        PortalRpcModule::Admin => AdminApi::new(
+                            self.discv5.clone(),
+                            self.history_tx.clone(),
+                            self.state_tx.clone(),
+                            self.beacon_tx.clone(),
+                            self.shutdown.clone(),
+                        )
+                        .into_rpc()
+                        .into(),
                         PortalRpcModule::Discv5 => {
                             Discv5Api::new(self.discv5.clone()).into_rpc().into()
                         }
@@ -409,7 +468,14 @@ impl RpcModuleBuilder {
                                 .history_tx
                                 .clone()
                                 .expect("History protocol not initialized");
-                            EthApi::new(history_tx).into_rpc().into()
+                            EthApi::new(
+                                history_tx,
+                                self.state_tx.clone(),
+                                self.canonical_indices_tx.clone(),
+                                self.max_logs_block_range,
+                            )
+                            .into_rpc()
+                            .into()
                         }
                         PortalRpcModule::History => {
                             let history_tx = self
@@ -425,6 +491,20 @@ impl RpcModuleBuilder {
                                 .expect("Beacon protocol not initialized");
                             BeaconNetworkApi::new(beacon_tx).into_rpc().into()
                         }
+                        PortalRpcModule::State => {
+                            let state_tx = self
+                                .state_tx
+                                .clone()
+                                .expect("State protocol not initialized");
+                            StateNetworkApi::new(state_tx).into_rpc().into()
+                        }
+                        PortalRpcModule::Net => NetApi::new(
+                            self.history_tx.clone(),
+                            self.state_tx.clone(),
+                            self.beacon_tx.clone(),
+                        )
+                        .into_rpc()
+                        .into(),
                         PortalRpcModule::Web3 => Web3Api.into_rpc().into(),
                     })
                     .clone()
@@ -464,9 +544,12 @@ mod tests {
         }
         assert_rpc_module!
         (
+                "admin" =>  PortalRpcModule::Admin,
                 "beacon" =>  PortalRpcModule::Beacon,
                 "discv5" =>  PortalRpcModule::Discv5,
                 "history" =>  PortalRpcModule::History,
+                "net" =>  PortalRpcModule::Net,
+                "state" =>  PortalRpcModule::State,
                 "web3" =>  PortalRpcModule::Web3,
             );
     }
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ethportal_api::{types::portal::ContentPage, utils::bytes::hex_encode};
+
+use crate::errors::RpcServeError;
+
+/// Page size used when the client doesn't ask for one.
+pub const DEFAULT_PAGE_SIZE: u64 = 1024 * 1024;
+
+/// Largest page size a client may ask for. Pages are hex encoded, so responses are about twice as
+/// large.
+pub const MAX_PAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// How long the remaining pages of a content value are kept after the last page was requested.
+const PAGE_TTL: Duration = Duration::from_secs(60);
+
+/// Maximum number of content values with pages left to serve.
+const MAX_PAGED_CONTENTS: usize = 32;
+
+struct PagedContent {
+    bytes: Arc<Vec<u8>>,
+    page_size: u64,
+    expires: Instant,
+}
+
+/// Content values with pages left to serve, keyed by the id of their continuation tokens.
+///
+/// Values are only kept for a short while, so clients that abandon a paginated lookup don't leak
+/// memory.
+#[derive(Clone, Default)]
+pub struct ContentPages {
+    next_id: Arc<AtomicU64>,
+    contents: Arc<Mutex<HashMap<u64, PagedContent>>>,
+}
+
+impl ContentPages {
+    /// Returns the first page of `content`, and keeps the rest of it if it doesn't fit in a page.
+    pub fn first_page(&self, content: Vec<u8>, page_size: Option<u64>) -> ContentPage {
+        let page_size = page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let bytes = Arc::new(content);
+        if (bytes.len() as u64) <= page_size {
+            return Self::page_of(&bytes, 0, page_size, None);
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut contents = self.contents.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+        contents.retain(|_, content| content.expires > now);
+        if contents.len() >= MAX_PAGED_CONTENTS {
+            // Drop the content that was requested the longest time ago.
+            if let Some(oldest) = contents
+                .iter()
+                .min_by_key(|(id, content)| (content.expires, **id))
+                .map(|(id, _)| *id)
+            {
+                contents.remove(&oldest);
+            }
+        }
+        contents.insert(
+            id,
+            PagedContent {
+                bytes: bytes.clone(),
+                page_size,
+                expires: now + PAGE_TTL,
+            },
+        );
+        Self::page_of(&bytes, 0, page_size, Some(id))
+    }
+
+    /// Returns the page pointed at by a continuation token, previously returned with a page.
+    pub fn page(&self, continuation: &str) -> Result<ContentPage, RpcServeError> {
+        let (id, offset) = parse_continuation(continuation).ok_or_else(|| {
+            RpcServeError::Message(format!("Invalid continuation token: {continuation}"))
+        })?;
+        let mut contents = self.contents.lock().unwrap_or_else(|err| err.into_inner());
+        let content = contents
+            .get_mut(&id)
+            .filter(|content| content.expires > Instant::now())
+            .ok_or_else(|| {
+                RpcServeError::Message(format!("Unknown or expired continuation token: {id}"))
+            })?;
+        if offset >= content.bytes.len() as u64 {
+            return Err(RpcServeError::Message(format!(
+                "Continuation token points past the end of the content: {continuation}"
+            )));
+        }
+        content.expires = Instant::now() + PAGE_TTL;
+        let page = Self::page_of(&content.bytes, offset, content.page_size, Some(id));
+        if page.continuation.is_none() {
+            contents.remove(&id);
+        }
+        Ok(page)
+    }
+
+    fn page_of(bytes: &[u8], offset: u64, page_size: u64, id: Option<u64>) -> ContentPage {
+        let total_size = bytes.len() as u64;
+        let end = offset.saturating_add(page_size).min(total_size);
+        let continuation = match (id, end < total_size) {
+            (Some(id), true) => Some(format!("{id}:{end}")),
+            _ => None,
+        };
+        ContentPage {
+            content: hex_encode(&bytes[offset as usize..end as usize]),
+            offset,
+            total_size,
+            continuation,
+        }
+    }
+}
+
+fn parse_continuation(continuation: &str) -> Option<(u64, u64)> {
+    let (id, offset) = continuation.split_once(':')?;
+    Some((id.parse().ok()?, offset.parse().ok()?))
+}
+
+impl std::fmt::Debug for ContentPages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentPages").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn small_content_is_not_kept() {
+        let pages = ContentPages::default();
+        let page = pages.first_page(vec![1, 2, 3], Some(3));
+        assert_eq!(page.content, "0x010203");
+        assert_eq!(page.total_size, 3);
+        assert!(page.continuation.is_none());
+        assert!(pages.contents.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pages_cover_the_whole_content() {
+        let pages = ContentPages::default();
+        let first = pages.first_page(vec![1, 2, 3, 4, 5], Some(2));
+        assert_eq!(first.content, "0x0102");
+
+        let second = pages.page(&first.continuation.unwrap()).unwrap();
+        assert_eq!(second.content, "0x0304");
+        assert_eq!(second.offset, 2);
+
+        let third = pages.page(&second.continuation.unwrap()).unwrap();
+        assert_eq!(third.content, "0x05");
+        assert_eq!(third.total_size, 5);
+        assert!(third.continuation.is_none());
+        // The content is dropped once its last page was served.
+        assert!(pages.contents.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_tokens_are_rejected() {
+        let pages = ContentPages::default();
+        let first = pages.first_page(vec![1, 2, 3], Some(1));
+        let id = first
+            .continuation
+            .unwrap()
+            .split_once(':')
+            .unwrap()
+            .0
+            .to_owned();
+        assert!(pages.page("not a token").is_err());
+        assert!(pages.page(&format!("{id}:3")).is_err());
+        assert!(pages.page("42:1").is_err());
+    }
+
+    #[test]
+    fn oldest_content_is_dropped_when_full() {
+        let pages = ContentPages::default();
+        let first = pages.first_page(vec![0; 2], Some(1));
+        for _ in 0..MAX_PAGED_CONTENTS {
+            pages.first_page(vec![0; 2], Some(1));
+        }
+        assert_eq!(pages.contents.lock().unwrap().len(), MAX_PAGED_CONTENTS);
+        assert!(pages.page(&first.continuation.unwrap()).is_err());
+    }
+}
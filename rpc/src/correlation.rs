@@ -0,0 +1,276 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hyper::{
+    body::HttpBody,
+    header::{HeaderName, HeaderValue},
+    Body, Request, Response, StatusCode,
+};
+use serde_json::{Map, Value};
+use tower::{Layer, Service};
+use tracing::{info_span, Instrument};
+
+/// Header that carries the correlation id of a request, both ways.
+///
+/// Clients may set it to choose the correlation id of their request.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Longest correlation id accepted from a client.
+const MAX_CLIENT_ID_LENGTH: usize = 64;
+
+/// Largest response whose errors are tagged with the correlation id. Larger responses are passed
+/// on as they are, rather than buffering them, and error responses are much smaller anyway.
+const MAX_TAGGED_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// A layer that gives every http json-rpc request a correlation id.
+///
+/// The request is served within a `rpc_request` span that carries the id, which subnetwork
+/// requests inherit, so that all log lines caused by the request can be found. The id is returned
+/// in the [CORRELATION_ID_HEADER] of the response, and in the `data` of any error in responses of
+/// up to [MAX_TAGGED_RESPONSE_SIZE] bytes.
+#[derive(Debug, Clone)]
+pub struct CorrelationLayer {
+    /// Distinguishes the ids of different runs of trin.
+    prefix: Arc<str>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Default for CorrelationLayer {
+    fn default() -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            prefix: format!("{started:x}").into(),
+            next_id: Default::default(),
+        }
+    }
+}
+
+impl CorrelationLayer {
+    fn next_id(&self) -> String {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        format!("{}-{id:x}", self.prefix)
+    }
+}
+
+impl<S> Layer<S> for CorrelationLayer {
+    type Service = CorrelationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CorrelationService {
+            layer: self.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [CorrelationLayer].
+#[derive(Debug, Clone)]
+pub struct CorrelationService<S> {
+    layer: CorrelationLayer,
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for CorrelationService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let correlation_id = request
+            .headers()
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| is_valid_client_id(id))
+            .map(String::from)
+            .unwrap_or_else(|| self.layer.next_id());
+        let span = info_span!("rpc_request", correlation_id = %correlation_id);
+        // The inner service was driven to readiness, so it's the one that must handle the call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(
+            async move {
+                let response = inner.call(request).await?;
+                let (mut parts, body) = response.into_parts();
+                if let Ok(header) = HeaderValue::from_str(&correlation_id) {
+                    parts
+                        .headers
+                        .insert(HeaderName::from_static(CORRELATION_ID_HEADER), header);
+                }
+                let is_small = body
+                    .size_hint()
+                    .exact()
+                    .is_some_and(|size| size <= MAX_TAGGED_RESPONSE_SIZE);
+                if !is_small {
+                    return Ok(Response::from_parts(parts, body));
+                }
+                let body = match hyper::body::to_bytes(body).await {
+                    Ok(body) => body,
+                    Err(err) => {
+                        let mut response = Response::new(Body::from(err.to_string()));
+                        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                        return Ok(response);
+                    }
+                };
+                // Only error responses are rewritten, and most responses aren't errors.
+                if !contains(&body, br#""error""#) {
+                    return Ok(Response::from_parts(parts, Body::from(body)));
+                }
+                let body = match serde_json::from_slice::<Value>(&body) {
+                    Ok(mut json_response) => {
+                        tag_errors(&mut json_response, &correlation_id);
+                        parts.headers.remove(hyper::header::CONTENT_LENGTH);
+                        Body::from(json_response.to_string())
+                    }
+                    Err(_) => Body::from(body),
+                };
+                Ok(Response::from_parts(parts, body))
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Client ids end up in logs and headers, so only short, printable ids are accepted.
+fn is_valid_client_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_CLIENT_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Adds the correlation id to the `data` of every error in a single or batch response.
+///
+/// Errors whose `data` isn't an object are left alone, rather than losing their data.
+fn tag_errors(json_response: &mut Value, correlation_id: &str) {
+    let responses: Vec<&mut Value> = match json_response {
+        Value::Array(responses) => responses.iter_mut().collect(),
+        response => vec![response],
+    };
+    for response in responses {
+        let Some(Value::Object(error)) = response.get_mut("error") else {
+            continue;
+        };
+        let data = error
+            .entry("data")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if data.is_null() {
+            *data = Value::Object(Map::new());
+        }
+        if let Value::Object(data) = data {
+            data.insert(
+                "correlationId".to_string(),
+                Value::String(correlation_id.to_string()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use tower::{service_fn, ServiceExt};
+
+    async fn send(request: Request<Body>, response: Value) -> Response<Body> {
+        let service = CorrelationLayer::default().layer(service_fn(move |_: Request<Body>| {
+            let response = response.clone();
+            async move { Ok::<_, hyper::Error>(Response::new(Body::from(response.to_string()))) }
+        }));
+        service.oneshot(request).await.unwrap()
+    }
+
+    async fn body(response: Response<Body>) -> Value {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn errors_carry_the_correlation_id() {
+        let request = Request::builder()
+            .header(CORRELATION_ID_HEADER, "my-request.1")
+            .body(Body::empty())
+            .unwrap();
+        let response = send(
+            request,
+            json!([
+                {"jsonrpc": "2.0", "error": {"code": -32099, "message": "oops"}, "id": 1},
+                {"jsonrpc": "2.0", "error": {"code": -1, "message": "", "data": "raw"}, "id": 2},
+                {"jsonrpc": "2.0", "result": "0x1", "id": 3},
+            ]),
+        )
+        .await;
+        assert_eq!(response.headers()[CORRELATION_ID_HEADER], "my-request.1");
+        assert_eq!(
+            body(response).await,
+            json!([
+                {
+                    "jsonrpc": "2.0",
+                    "error": {
+                        "code": -32099,
+                        "message": "oops",
+                        "data": {"correlationId": "my-request.1"},
+                    },
+                    "id": 1,
+                },
+                {"jsonrpc": "2.0", "error": {"code": -1, "message": "", "data": "raw"}, "id": 2},
+                {"jsonrpc": "2.0", "result": "0x1", "id": 3},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn ids_are_generated_when_missing_or_invalid() {
+        let request = Request::builder()
+            .header(CORRELATION_ID_HEADER, "not valid\t")
+            .body(Body::empty())
+            .unwrap();
+        let result = json!({"jsonrpc": "2.0", "result": "0x1", "id": 1});
+        let response = send(request, result.clone()).await;
+        let id = response.headers()[CORRELATION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_ne!(id, "not valid\t");
+        assert!(is_valid_client_id(&id));
+        assert_eq!(body(response).await, result);
+    }
+
+    #[tokio::test]
+    async fn large_responses_are_passed_on() {
+        let request = Request::builder().body(Body::empty()).unwrap();
+        let message = "x".repeat(MAX_TAGGED_RESPONSE_SIZE as usize);
+        let error = json!({"jsonrpc": "2.0", "error": {"code": -1, "message": message}, "id": 1});
+        let response = send(request, error.clone()).await;
+        assert!(response.headers().contains_key(CORRELATION_ID_HEADER));
+        assert_eq!(body(response).await, error);
+    }
+}
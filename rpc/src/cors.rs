@@ -1,6 +1,11 @@
+use std::time::Duration;
+
 use hyper::{http::HeaderValue, Method};
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
+/// How long browsers may cache the response to a preflight request.
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(600);
+
 /// Error thrown when parsing cors domains went wrong
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum CorsDomainError {
@@ -11,14 +16,20 @@ pub(crate) enum CorsDomainError {
 }
 
 /// Creates a [CorsLayer] from the given domains
+///
+/// The layer answers preflight `OPTIONS` requests itself, so they never reach the json-rpc server.
 pub(crate) fn create_cors_layer(http_cors_domains: &str) -> Result<CorsLayer, CorsDomainError> {
     let cors = match http_cors_domains.trim() {
         "*" => CorsLayer::new()
             .allow_methods([Method::GET, Method::POST])
             .allow_origin(Any)
-            .allow_headers(Any),
+            .allow_headers(Any)
+            .max_age(PREFLIGHT_MAX_AGE),
         _ => {
-            let iter = http_cors_domains.split(',');
+            let iter = http_cors_domains
+                .split(',')
+                .map(str::trim)
+                .filter(|domain| !domain.is_empty());
             if iter.clone().any(|o| o == "*") {
                 return Err(CorsDomainError::WildCardNotAllowed {
                     input: http_cors_domains.to_string(),
@@ -40,7 +51,92 @@ pub(crate) fn create_cors_layer(http_cors_domains: &str) -> Result<CorsLayer, Co
                 .allow_methods([Method::GET, Method::POST])
                 .allow_origin(origin)
                 .allow_headers(Any)
+                .max_age(PREFLIGHT_MAX_AGE)
         }
     };
     Ok(cors)
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use hyper::{
+        header::{
+            ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+            ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+        },
+        Body, Request, Response, StatusCode,
+    };
+    use tower::{service_fn, Layer, ServiceExt};
+
+    /// Sends `request` through the cors layer to a service that always responds with 200.
+    async fn send(cors_domains: &str, request: Request<Body>) -> Response<Body> {
+        let service =
+            create_cors_layer(cors_domains)
+                .unwrap()
+                .layer(service_fn(|_: Request<Body>| async {
+                    Ok::<_, hyper::Error>(Response::new(Body::from("ok")))
+                }));
+        service.oneshot(request).await.unwrap()
+    }
+
+    fn preflight(origin: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn preflight_allowed_origin() {
+        let response = send(
+            "http://localhost:3000, https://app.example.com",
+            preflight("https://app.example.com"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert!(response.headers()[ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap()
+            .contains("POST"));
+    }
+
+    #[tokio::test]
+    async fn preflight_unknown_origin() {
+        let response = send(
+            "http://localhost:3000",
+            preflight("https://evil.example.com"),
+        )
+        .await;
+        assert!(response
+            .headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn wildcard_origin() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .header(ORIGIN, "https://any.example.com")
+            .body(Body::empty())
+            .unwrap();
+        let response = send("*", request).await;
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+
+    #[test]
+    fn wildcard_in_list_is_rejected() {
+        assert!(matches!(
+            create_cors_layer("http://localhost:3000,*"),
+            Err(CorsDomainError::WildCardNotAllowed { .. })
+        ));
+    }
+}
@@ -2,7 +2,11 @@ use crate::errors::RpcServeError;
 
 use crate::jsonrpsee::core::{async_trait, RpcResult};
 use discv5::enr::NodeId;
-use ethportal_api::{types::enr::Enr, Discv5ApiServer, NodeInfo, RoutingTableInfo};
+use ethportal_api::{
+    types::{enr::Enr, jsonrpc::error::JsonRpcError},
+    utils::bytes::{hex_decode, hex_encode},
+    Discv5ApiServer, NodeInfo, RoutingTableInfo,
+};
 use portalnet::discovery::Discovery;
 use std::sync::Arc;
 
@@ -59,6 +63,36 @@ impl Discv5ApiServer for Discv5Api {
     async fn lookup_enr(&self, _node_id: NodeId) -> RpcResult<Enr> {
         Err(RpcServeError::MethodNotFound("lookup_enr".to_owned()))?
     }
+
+    /// Send a TALKREQ with the given hex-encoded protocol and payload to the designated node and
+    /// return the hex-encoded TALKRESP payload.
+    async fn talk_req(&self, enr: Enr, protocol: String, request: String) -> RpcResult<String> {
+        let protocol = hex_decode(&protocol).map_err(|err| {
+            RpcServeError::JsonRpc(JsonRpcError::invalid_params(format!(
+                "Invalid hex-encoded protocol: {err}"
+            )))
+        })?;
+        let request = hex_decode(&request).map_err(|err| {
+            RpcServeError::JsonRpc(JsonRpcError::invalid_params(format!(
+                "Invalid hex-encoded request: {err}"
+            )))
+        })?;
+        let response = self
+            .discv5
+            .send_raw_talk_req(enr, protocol, request)
+            .await
+            .map_err(|err| RpcServeError::Message(format!("TALKREQ failed: {err:?}")))?;
+        Ok(hex_encode(response))
+    }
+
+    /// Lookup the closest nodes to a target node ID in the discv5 network.
+    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>> {
+        Ok(self
+            .discv5
+            .recursive_find_nodes(node_id)
+            .await
+            .map_err(|err| RpcServeError::Message(format!("FINDNODE lookup failed: {err:?}")))?)
+    }
 }
 
 impl std::fmt::Debug for Discv5Api {
@@ -6,6 +6,7 @@ use crate::{
     rpc_server::ServerKind,
     PortalRpcModule,
 };
+use ethportal_api::types::jsonrpc::error::JsonRpcError;
 use std::io;
 
 /// Rpc Errors.
@@ -51,11 +52,20 @@ impl RpcError {
     }
 }
 
+#[derive(Debug)]
 pub enum RpcServeError {
     /// A generic error with no data
     Message(String),
     /// Method not available
     MethodNotFound(String),
+    /// A structured error returned by a portal subnetwork
+    JsonRpc(JsonRpcError),
+}
+
+impl From<JsonRpcError> for RpcServeError {
+    fn from(err: JsonRpcError) -> Self {
+        RpcServeError::JsonRpc(err)
+    }
 }
 
 impl From<RpcServeError> for ErrorObjectOwned {
@@ -68,6 +78,7 @@ impl From<RpcServeError> for ErrorObjectOwned {
             // https://docs.infura.io/networks/ethereum/json-rpc-methods#error-codes
             RpcServeError::Message(msg) => ErrorObject::owned(-32099, msg, None::<()>),
             RpcServeError::MethodNotFound(method) => ErrorObject::owned(-32601, method, None::<()>),
+            RpcServeError::JsonRpc(err) => ErrorObject::owned(err.code, err.message, err.data),
         }
     }
 }
@@ -1,26 +1,279 @@
-use ethereum_types::{H256, U256};
-use reth_rpc_types::{Block, BlockTransactions};
-use tokio::sync::mpsc;
+use std::{iter, sync::Arc};
+
+use ethereum_types::{H160, H256, U256, U64};
+use reth_rpc_types::{Block, BlockNumberOrTag, BlockTransactions, Header as RpcHeader, Log};
+use serde_json::Value;
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, RwLock},
+};
+use tracing::warn;
 
 use ethportal_api::{
-    types::{execution::block_body::BlockBody, jsonrpc::request::HistoryJsonRpcRequest},
-    EthApiServer,
+    types::{
+        execution::{
+            block_body::BlockBody,
+            call_request::CallRequest,
+            fee_history::{
+                gas_used_ratio, next_base_fee, reward_percentiles, FeeHistory,
+                MAX_FEE_HISTORY_BLOCK_COUNT,
+            },
+            header::Header,
+            log_filter::LogFilter,
+            receipts::RpcReceipt,
+            transaction::{RpcTransaction, Transaction},
+        },
+        jsonrpc::{
+            endpoints::HistoryEndpoint,
+            error::JsonRpcError,
+            request::{CanonicalIndicesJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+        },
+        state_trie::{decode_storage_value, storage_trie_key},
+        subscription::SubscriptionKind,
+    },
+    utils::bytes::hex_encode,
+    EthApiServer, HistoryContentKey,
+};
+use trin_validation::{
+    accumulator::MasterAccumulator,
+    constants::{CHAIN_ID, EPOCH_SIZE, MERGE_BLOCK_NUMBER},
 };
-use trin_validation::constants::CHAIN_ID;
 
 use crate::{
     errors::RpcServeError,
-    fetch::{find_block_body_by_hash, find_header_by_hash},
-    jsonrpsee::core::{async_trait, RpcResult},
+    evm::{estimate_gas, execute_call, StateNetworkDb},
+    fetch::{
+        find_account, find_block_body_by_hash, find_contract_bytecode, find_header_by_hash,
+        find_receipts_by_hash, find_storage_trie_value, find_transaction_index,
+        proxy_query_to_history_subnet,
+    },
+    jsonrpsee::{
+        core::{async_trait, RpcResult, SubscriptionResult},
+        PendingSubscriptionSink, SubscriptionMessage,
+    },
 };
 
 pub struct EthApi {
     network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    canonical_indices_network: Option<mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>>,
+    master_acc: MasterAccumulator,
+    max_logs_block_range: u64,
+    /// The header with the highest number that this node has stored, standing in for the chain
+    /// head.
+    latest_header: Arc<RwLock<Option<Header>>>,
 }
 
 impl EthApi {
-    pub fn new(network: mpsc::UnboundedSender<HistoryJsonRpcRequest>) -> Self {
-        Self { network }
+    pub fn new(
+        network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+        state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+        canonical_indices_network: Option<mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>>,
+        max_logs_block_range: u64,
+    ) -> Self {
+        let latest_header = Arc::new(RwLock::new(None));
+        tokio::spawn(track_latest_header(network.clone(), latest_header.clone()));
+        Self {
+            network,
+            state_network,
+            canonical_indices_network,
+            master_acc: MasterAccumulator::default(),
+            max_logs_block_range,
+            latest_header,
+        }
+    }
+
+    async fn latest_header(&self) -> Result<Header, RpcServeError> {
+        self.latest_header.read().await.clone().ok_or_else(|| {
+            RpcServeError::Message("No block header has been stored by this node yet".into())
+        })
+    }
+
+    /// Looks up the effective priority fees paid at the given percentiles of gas used in the block.
+    async fn block_rewards(
+        &self,
+        header: &Header,
+        percentiles: &[f64],
+    ) -> Result<Vec<U256>, RpcServeError> {
+        let block_hash = header.hash();
+        let transactions = find_block_body_by_hash(&self.network, block_hash)
+            .await?
+            .transactions()
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        let receipts = find_receipts_by_hash(&self.network, block_hash).await?;
+        Ok(reward_percentiles(
+            header,
+            &transactions,
+            &receipts.receipt_list,
+            percentiles,
+        ))
+    }
+
+    fn state_network(&self) -> Result<&mpsc::UnboundedSender<StateJsonRpcRequest>, RpcServeError> {
+        self.state_network.as_ref().ok_or_else(|| {
+            RpcServeError::Message("State network must be enabled to read account state".into())
+        })
+    }
+
+    fn canonical_indices_network(
+        &self,
+    ) -> Result<&mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>, RpcServeError> {
+        self.canonical_indices_network.as_ref().ok_or_else(|| {
+            RpcServeError::Message(
+                "Canonical indices network must be enabled to look up transactions".into(),
+            )
+        })
+    }
+
+    /// Looks up the header and the transactions of the block that includes the transaction with
+    /// hash `transaction_hash`, along with the index of the transaction in the block.
+    async fn find_transaction(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<(Header, Vec<Transaction>, usize), RpcServeError> {
+        let transaction_index =
+            find_transaction_index(self.canonical_indices_network()?, transaction_hash).await?;
+        let block_hash = H256::from(transaction_index.block_hash);
+        let header = find_header_by_hash(&self.network, block_hash).await?;
+        let transactions = find_block_body_by_hash(&self.network, block_hash)
+            .await?
+            .transactions()
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        let index = usize::try_from(transaction_index.index)
+            .ok()
+            .filter(|index| {
+                transactions
+                    .get(*index)
+                    .is_some_and(|transaction| transaction.hash() == transaction_hash)
+            })
+            .ok_or_else(|| {
+                RpcServeError::Message(format!(
+                    "Block {block_hash:?} has no transaction {transaction_hash:?} at index {}",
+                    transaction_index.index
+                ))
+            })?;
+        Ok((header, transactions, index))
+    }
+
+    async fn header_at(&self, block_number: BlockNumberOrTag) -> Result<Header, RpcServeError> {
+        let block_number = resolve_block_number(block_number)?;
+        let block_hash = self
+            .master_acc
+            .lookup_premerge_hash_by_number(block_number, self.network.clone())
+            .await
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        find_header_by_hash(&self.network, block_hash).await
+    }
+
+    /// Returns the state root at the end of the given block.
+    async fn state_root_at(&self, block_number: BlockNumberOrTag) -> Result<H256, RpcServeError> {
+        Ok(self.header_at(block_number).await?.state_root)
+    }
+
+    /// Returns a database for executing calls on top of the state at the end of the block with
+    /// the given `header`.
+    fn state_network_db(&self, header: &Header) -> Result<StateNetworkDb, RpcServeError> {
+        Ok(StateNetworkDb::new(
+            Handle::current(),
+            self.network.clone(),
+            self.state_network()?.clone(),
+            self.master_acc.clone(),
+            header.state_root,
+        ))
+    }
+
+    /// Looks up the hashes of all blocks in the given inclusive pre-merge range, fetching each
+    /// epoch accumulator only once.
+    async fn lookup_premerge_hashes(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<H256>, RpcServeError> {
+        if to_block > MERGE_BLOCK_NUMBER {
+            return Err(RpcServeError::Message(
+                "Post-merge blocks are not supported.".into(),
+            ));
+        }
+        let epoch_size = EPOCH_SIZE as u64;
+        let mut block_hashes = vec![];
+        for epoch_index in from_block / epoch_size..=to_block / epoch_size {
+            let epoch_hash = self.master_acc.historical_epochs[epoch_index as usize];
+            let epoch_acc = self
+                .master_acc
+                .lookup_epoch_acc(epoch_hash, self.network.clone())
+                .await
+                .map_err(|err| RpcServeError::Message(err.to_string()))?;
+            let first = from_block.max(epoch_index * epoch_size);
+            let last = to_block.min((epoch_index + 1) * epoch_size - 1);
+            for block_number in first..=last {
+                block_hashes.push(epoch_acc[(block_number % epoch_size) as usize].block_hash);
+            }
+        }
+        Ok(block_hashes)
+    }
+}
+
+/// Keeps `latest_header` up to date with the highest header stored by the history network,
+/// starting from the highest header that was stored before the node started.
+async fn track_latest_header(
+    network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    latest_header: Arc<RwLock<Option<Header>>>,
+) {
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+    let request = HistoryJsonRpcRequest::new(HistoryEndpoint::SubscribeStoredContent, resp_tx);
+    if network.send(request).is_err() {
+        return;
+    }
+    // Subscribing first means that no header stored during the lookup is missed.
+    match find_latest_stored_header(&network).await {
+        Ok(Some(header)) => update_latest_header(&latest_header, header).await,
+        Ok(None) => {}
+        Err(err) => warn!(error = %err, "Unable to look up the latest stored header"),
+    }
+    while let Some(stored_content) = resp_rx.recv().await {
+        let Some(HistoryContentKey::BlockHeaderWithProof(key)) = stored_content
+            .ok()
+            .and_then(|key| serde_json::from_value(key).ok())
+        else {
+            continue;
+        };
+        let Ok(header) = find_header_by_hash(&network, key.block_hash.into()).await else {
+            continue;
+        };
+        update_latest_header(&latest_header, header).await;
+    }
+}
+
+async fn find_latest_stored_header(
+    network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+) -> Result<Option<Header>, RpcServeError> {
+    let block_hash =
+        proxy_query_to_history_subnet(network, HistoryEndpoint::LatestStoredHeader).await?;
+    let block_hash: Option<H256> = serde_json::from_value(block_hash)
+        .map_err(|err| RpcServeError::Message(format!("Invalid latest header hash: {err}")))?;
+    match block_hash {
+        Some(block_hash) => Ok(Some(find_header_by_hash(network, block_hash).await?)),
+        None => Ok(None),
+    }
+}
+
+async fn update_latest_header(latest_header: &RwLock<Option<Header>>, header: Header) {
+    let mut latest_header = latest_header.write().await;
+    if latest_header
+        .as_ref()
+        .map_or(true, |latest_header| header.number > latest_header.number)
+    {
+        *latest_header = Some(header);
+    }
+}
+
+fn resolve_block_number(block_number: BlockNumberOrTag) -> Result<u64, RpcServeError> {
+    match block_number {
+        BlockNumberOrTag::Number(block_number) => Ok(block_number),
+        BlockNumberOrTag::Earliest => Ok(0),
+        tag => Err(RpcServeError::Message(format!(
+            "block tag {tag:?} is not supported yet"
+        ))),
     }
 }
 
@@ -44,10 +297,10 @@ impl EthApiServer for EthApi {
 
         let header = find_header_by_hash(&self.network, block_hash).await?;
         let body = find_block_body_by_hash(&self.network, block_hash).await?;
-        let transactions = match body {
-            BlockBody::Legacy(body) => body.txs,
-            BlockBody::Merge(body) => body.txs,
-            BlockBody::Shanghai(body) => body.txs,
+        let (transactions, uncles) = match body {
+            BlockBody::Legacy(body) => (body.txs, body.uncles),
+            BlockBody::Merge(body) => (body.txs, vec![]),
+            BlockBody::Shanghai(body) => (body.txs, vec![]),
         };
         let transactions = BlockTransactions::Hashes(
             transactions
@@ -60,13 +313,336 @@ impl EthApiServer for EthApi {
         let block = Block {
             header: header.into(),
             transactions,
-            uncles: vec![],
+            uncles: uncles
+                .iter()
+                .map(|uncle| uncle.hash().as_fixed_bytes().into())
+                .collect(),
             size: None,
             total_difficulty: None,
             withdrawals: None,
         };
         Ok(block)
     }
+
+    async fn get_block_by_number(
+        &self,
+        block_number: BlockNumberOrTag,
+        hydrated_transactions: bool,
+    ) -> RpcResult<Block> {
+        let block_number = resolve_block_number(block_number)?;
+        let block_hash = self
+            .master_acc
+            .lookup_premerge_hash_by_number(block_number, self.network.clone())
+            .await
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        self.get_block_by_hash(block_hash, hydrated_transactions)
+            .await
+    }
+
+    async fn get_transaction_by_hash(&self, transaction_hash: H256) -> RpcResult<RpcTransaction> {
+        let (header, mut transactions, index) = self.find_transaction(transaction_hash).await?;
+        let transaction = transactions
+            .swap_remove(index)
+            .into_rpc_transaction(&header, index as u64)
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        Ok(transaction)
+    }
+
+    async fn get_transaction_receipt(&self, transaction_hash: H256) -> RpcResult<RpcReceipt> {
+        let (header, transactions, index) = self.find_transaction(transaction_hash).await?;
+        let receipts = find_receipts_by_hash(&self.network, header.hash()).await?;
+        let receipt = receipts
+            .rpc_receipt(&header, &transactions, index)
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        Ok(receipt)
+    }
+
+    async fn get_logs(&self, filter: LogFilter) -> RpcResult<Vec<Log>> {
+        let block_hashes = match filter.block_hash {
+            Some(block_hash) => {
+                if filter.from_block.is_some() || filter.to_block.is_some() {
+                    return Err(RpcServeError::JsonRpc(JsonRpcError::invalid_params(
+                        "blockHash cannot be combined with fromBlock or toBlock",
+                    ))
+                    .into());
+                }
+                vec![block_hash]
+            }
+            None => {
+                let (Some(from_block), Some(to_block)) = (filter.from_block, filter.to_block)
+                else {
+                    return Err(RpcServeError::JsonRpc(JsonRpcError::invalid_params(
+                        "either blockHash or both fromBlock and toBlock are required",
+                    ))
+                    .into());
+                };
+                let from_block = resolve_block_number(from_block)?;
+                let to_block = resolve_block_number(to_block)?;
+                if from_block > to_block {
+                    return Err(RpcServeError::JsonRpc(JsonRpcError::invalid_params(
+                        "fromBlock must not be greater than toBlock",
+                    ))
+                    .into());
+                }
+                if to_block - from_block >= self.max_logs_block_range {
+                    return Err(RpcServeError::JsonRpc(JsonRpcError::invalid_params(format!(
+                        "block range exceeds the maximum of {} blocks",
+                        self.max_logs_block_range
+                    )))
+                    .into());
+                }
+                self.lookup_premerge_hashes(from_block, to_block).await?
+            }
+        };
+
+        let mut logs = vec![];
+        for block_hash in block_hashes {
+            let header = find_header_by_hash(&self.network, block_hash).await?;
+            let transactions = find_block_body_by_hash(&self.network, block_hash)
+                .await?
+                .transactions()
+                .map_err(|err| RpcServeError::Message(err.to_string()))?;
+            let receipts = find_receipts_by_hash(&self.network, block_hash).await?;
+
+            // Log indices count every log in the block, not only the matching ones.
+            let block_logs = transactions
+                .iter()
+                .zip(receipts.receipt_list.iter())
+                .enumerate()
+                .flat_map(|(tx_index, (tx, receipt))| {
+                    receipt
+                        .receipt()
+                        .logs
+                        .iter()
+                        .map(move |log| (tx_index, tx.hash(), log))
+                })
+                .enumerate();
+            for (log_index, (tx_index, tx_hash, log)) in block_logs {
+                if filter.matches(log) {
+                    logs.push(log.clone().into_rpc_log(
+                        block_hash,
+                        header.number,
+                        tx_hash,
+                        tx_index as u64,
+                        log_index as u64,
+                    ));
+                }
+            }
+        }
+        Ok(logs)
+    }
+
+    async fn get_balance(&self, address: H160, block_number: BlockNumberOrTag) -> RpcResult<U256> {
+        let state_root = self.state_root_at(block_number).await?;
+        let account = find_account(self.state_network()?, state_root, address).await?;
+        Ok(account.balance)
+    }
+
+    async fn get_transaction_count(
+        &self,
+        address: H160,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<U256> {
+        let state_root = self.state_root_at(block_number).await?;
+        let account = find_account(self.state_network()?, state_root, address).await?;
+        Ok(U256::from(account.nonce))
+    }
+
+    async fn get_code(&self, address: H160, block_number: BlockNumberOrTag) -> RpcResult<String> {
+        let state_root = self.state_root_at(block_number).await?;
+        let account = find_account(self.state_network()?, state_root, address).await?;
+        let code = match account.has_empty_code() {
+            true => vec![],
+            false => {
+                find_contract_bytecode(self.state_network()?, address, account.code_hash).await?
+            }
+        };
+        Ok(hex_encode(code))
+    }
+
+    async fn get_storage_at(
+        &self,
+        address: H160,
+        slot: U256,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<H256> {
+        let state_root = self.state_root_at(block_number).await?;
+        let account = find_account(self.state_network()?, state_root, address).await?;
+        if account.has_empty_storage() {
+            return Ok(H256::zero());
+        }
+        let value = find_storage_trie_value(
+            self.state_network()?,
+            state_root,
+            address,
+            account.storage_root,
+            storage_trie_key(slot),
+        )
+        .await?;
+        let value = match value {
+            Some(value) => decode_storage_value(&value).map_err(|err| {
+                RpcServeError::Message(format!("Invalid storage value for {address:?}: {err}"))
+            })?,
+            None => U256::zero(),
+        };
+        let mut value_bytes = [0u8; 32];
+        value.to_big_endian(&mut value_bytes);
+        Ok(H256::from(value_bytes))
+    }
+
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<String> {
+        let header = self.header_at(block_number).await?;
+        let db = self.state_network_db(&header)?;
+        // The EVM reads state synchronously, so it runs on a blocking thread where each read may
+        // wait on the state network.
+        let output = tokio::task::spawn_blocking(move || execute_call(db, &header, request))
+            .await
+            .map_err(|err| RpcServeError::Message(format!("Call execution failed: {err}")))??;
+        Ok(hex_encode(output))
+    }
+
+    async fn estimate_gas(
+        &self,
+        request: CallRequest,
+        block_number: BlockNumberOrTag,
+    ) -> RpcResult<U256> {
+        let header = self.header_at(block_number).await?;
+        let db = self.state_network_db(&header)?;
+        let gas = tokio::task::spawn_blocking(move || estimate_gas(db, &header, request))
+            .await
+            .map_err(|err| RpcServeError::Message(format!("Gas estimation failed: {err}")))??;
+        Ok(U256::from(gas))
+    }
+
+    async fn fee_history(
+        &self,
+        block_count: U64,
+        newest_block: BlockNumberOrTag,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> RpcResult<FeeHistory> {
+        let block_count = block_count.as_u64();
+        if block_count == 0 || block_count > MAX_FEE_HISTORY_BLOCK_COUNT {
+            return Err(RpcServeError::JsonRpc(JsonRpcError::invalid_params(format!(
+                "blockCount must be between 1 and {MAX_FEE_HISTORY_BLOCK_COUNT}"
+            )))
+            .into());
+        }
+        let percentiles = reward_percentiles.unwrap_or_default();
+        let percentiles_valid = percentiles
+            .iter()
+            .all(|percentile| (0.0..=100.0).contains(percentile))
+            && percentiles.windows(2).all(|pair| pair[0] <= pair[1]);
+        if !percentiles_valid {
+            return Err(RpcServeError::JsonRpc(JsonRpcError::invalid_params(
+                "rewardPercentiles must be increasing values between 0 and 100",
+            ))
+            .into());
+        }
+
+        let mut header = match newest_block {
+            BlockNumberOrTag::Latest => self.latest_header().await?,
+            block_number => self.header_at(block_number).await?,
+        };
+        let next_block_base_fee = next_base_fee(&header);
+        // Walk back from the newest block through the parent hashes.
+        let mut headers = vec![];
+        loop {
+            let parent_hash = header.parent_hash;
+            let is_genesis = header.number == 0;
+            headers.push(header);
+            if is_genesis || headers.len() as u64 == block_count {
+                break;
+            }
+            header = find_header_by_hash(&self.network, parent_hash).await?;
+        }
+        headers.reverse();
+
+        let reward = match percentiles.is_empty() {
+            true => None,
+            false => {
+                let mut reward = vec![];
+                for header in headers.iter() {
+                    reward.push(self.block_rewards(header, &percentiles).await?);
+                }
+                Some(reward)
+            }
+        };
+        Ok(FeeHistory {
+            oldest_block: U256::from(headers[0].number),
+            base_fee_per_gas: headers
+                .iter()
+                .map(|header| header.base_fee_per_gas.unwrap_or_default())
+                .chain(iter::once(next_block_base_fee))
+                .collect(),
+            gas_used_ratio: headers.iter().map(gas_used_ratio).collect(),
+            reward,
+        })
+    }
+
+    async fn gas_price(&self) -> RpcResult<U256> {
+        let latest_header = self.latest_header().await?;
+        let tip = self.block_rewards(&latest_header, &[50.0]).await?[0];
+        Ok(next_base_fee(&latest_header) + tip)
+    }
+
+    async fn subscribe(
+        &self,
+        pending: PendingSubscriptionSink,
+        kind: SubscriptionKind,
+    ) -> SubscriptionResult {
+        let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+        let endpoint = match kind {
+            SubscriptionKind::NewHeads | SubscriptionKind::NewContent => {
+                HistoryEndpoint::SubscribeStoredContent
+            }
+            SubscriptionKind::RoutingTable => HistoryEndpoint::SubscribeRoutingTable,
+        };
+        self.network
+            .send(HistoryJsonRpcRequest::new(endpoint, resp_tx))?;
+        let sink = pending.accept().await?;
+
+        // Dropping `resp_rx` on return ends the subscription in the history network.
+        loop {
+            let event = tokio::select! {
+                _ = sink.closed() => break,
+                event = resp_rx.recv() => match event {
+                    Some(event) => event?,
+                    None => break,
+                },
+            };
+            let notification = match kind {
+                SubscriptionKind::NewContent | SubscriptionKind::RoutingTable => event,
+                SubscriptionKind::NewHeads => {
+                    let content_key: HistoryContentKey = serde_json::from_value(event)?;
+                    let HistoryContentKey::BlockHeaderWithProof(key) = content_key else {
+                        continue;
+                    };
+                    match find_header_by_hash(&self.network, key.block_hash.into()).await {
+                        Ok(header) => serde_json::to_value(RpcHeader::from(header))?,
+                        Err(_) => {
+                            warn!(
+                                block_hash = %hex_encode(key.block_hash),
+                                "Unable to look up newly stored header"
+                            );
+                            continue;
+                        }
+                    }
+                }
+            };
+            if sink
+                .send(SubscriptionMessage::from_json(&notification)?)
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl std::fmt::Debug for EthApi {
@@ -0,0 +1,537 @@
+/// Execution of read-only calls against state fetched from the portal networks
+use std::collections::HashMap;
+
+use ethereum_types::{H160, H256, U256};
+use revm::{
+    primitives::{
+        AccountInfo, Bytecode, EVMError, ExecutionResult, Halt, Output, SpecId, TransactTo, B160,
+        B256, KECCAK_EMPTY, U256 as EvmU256,
+    },
+    Database, EVM,
+};
+use serde_json::json;
+use tokio::{runtime::Handle, sync::mpsc};
+
+use ethportal_api::{
+    types::{
+        execution::{call_request::CallRequest, header::Header},
+        jsonrpc::{
+            error::JsonRpcError,
+            request::{HistoryJsonRpcRequest, StateJsonRpcRequest},
+        },
+        state_trie::{decode_storage_value, storage_trie_key, AccountState},
+    },
+    utils::bytes::hex_encode,
+};
+use trin_validation::{
+    accumulator::MasterAccumulator,
+    constants::{CHAIN_ID, MERGE_BLOCK_NUMBER},
+};
+
+use crate::{
+    errors::RpcServeError,
+    fetch::{find_account, find_contract_bytecode, find_storage_trie_value},
+};
+
+/// The code of execution reverted errors, as returned by other clients.
+const EXECUTION_REVERTED_CODE: i32 = 3;
+
+/// A revm database that lazily fetches accounts, code and storage from the state network, as of
+/// the state with root `state_root`.
+///
+/// Everything fetched is kept, so that executing the same call again, as gas estimation does,
+/// doesn't repeat the lookups. The network lookups are blocking, so the database must only be
+/// used from a blocking thread.
+pub struct StateNetworkDb {
+    handle: Handle,
+    history_network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    state_network: mpsc::UnboundedSender<StateJsonRpcRequest>,
+    master_acc: MasterAccumulator,
+    state_root: H256,
+    /// Accounts loaded so far, with their states, since storage is always read after the account
+    /// that owns it.
+    accounts: HashMap<H160, (AccountInfo, AccountState)>,
+    storage: HashMap<(H160, EvmU256), EvmU256>,
+    block_hashes: HashMap<u64, B256>,
+}
+
+impl StateNetworkDb {
+    pub fn new(
+        handle: Handle,
+        history_network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+        state_network: mpsc::UnboundedSender<StateJsonRpcRequest>,
+        master_acc: MasterAccumulator,
+        state_root: H256,
+    ) -> Self {
+        Self {
+            handle,
+            history_network,
+            state_network,
+            master_acc,
+            state_root,
+            accounts: HashMap::new(),
+            storage: HashMap::new(),
+            block_hashes: HashMap::new(),
+        }
+    }
+
+    fn account_state(&mut self, address: H160) -> Result<AccountState, RpcServeError> {
+        match self.accounts.get(&address) {
+            Some((_, account)) => Ok(account.clone()),
+            None => {
+                self.handle
+                    .block_on(find_account(&self.state_network, self.state_root, address))
+            }
+        }
+    }
+}
+
+impl Database for StateNetworkDb {
+    type Error = RpcServeError;
+
+    fn basic(&mut self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        let address = H160::from(address.0);
+        if let Some((info, _)) = self.accounts.get(&address) {
+            return Ok(Some(info.clone()));
+        }
+        let account =
+            self.handle
+                .block_on(find_account(&self.state_network, self.state_root, address))?;
+
+        // Bytecode is keyed by address in the state network, so it can't be looked up later by
+        // its hash alone.
+        let code = match account.has_empty_code() {
+            true => Bytecode::new(),
+            false => {
+                let code = self.handle.block_on(find_contract_bytecode(
+                    &self.state_network,
+                    address,
+                    account.code_hash,
+                ))?;
+                Bytecode::new_raw(code.into())
+            }
+        };
+        let info = AccountInfo {
+            balance: to_evm_u256(account.balance),
+            nonce: account.nonce,
+            code_hash: B256(account.code_hash.to_fixed_bytes()),
+            code: Some(code),
+        };
+        self.accounts.insert(address, (info.clone(), account));
+        Ok(Some(info))
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match code_hash == KECCAK_EMPTY {
+            true => Ok(Bytecode::new()),
+            false => Err(RpcServeError::Message(format!(
+                "Bytecode can't be looked up by hash alone: {code_hash:?}"
+            ))),
+        }
+    }
+
+    fn storage(&mut self, address: B160, index: EvmU256) -> Result<EvmU256, Self::Error> {
+        let address = H160::from(address.0);
+        if let Some(value) = self.storage.get(&(address, index)) {
+            return Ok(*value);
+        }
+        let account = self.account_state(address)?;
+        // Accounts without storage have no storage trie to look the value up in.
+        if account.has_empty_storage() {
+            self.storage.insert((address, index), EvmU256::ZERO);
+            return Ok(EvmU256::ZERO);
+        }
+        let slot = U256::from_big_endian(&index.to_be_bytes::<32>());
+        let value = self.handle.block_on(find_storage_trie_value(
+            &self.state_network,
+            self.state_root,
+            address,
+            account.storage_root,
+            storage_trie_key(slot),
+        ))?;
+        let value = match value {
+            Some(value) => {
+                let value = decode_storage_value(&value).map_err(|err| {
+                    RpcServeError::Message(format!("Invalid storage value for {address:?}: {err}"))
+                })?;
+                to_evm_u256(value)
+            }
+            None => EvmU256::ZERO,
+        };
+        self.storage.insert((address, index), value);
+        Ok(value)
+    }
+
+    fn block_hash(&mut self, number: EvmU256) -> Result<B256, Self::Error> {
+        let number: u64 = number
+            .try_into()
+            .map_err(|_| RpcServeError::Message(format!("Invalid block number: {number}")))?;
+        if let Some(block_hash) = self.block_hashes.get(&number) {
+            return Ok(*block_hash);
+        }
+        let block_hash = self
+            .handle
+            .block_on(
+                self.master_acc
+                    .lookup_premerge_hash_by_number(number, self.history_network.clone()),
+            )
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        let block_hash = B256(block_hash.to_fixed_bytes());
+        self.block_hashes.insert(number, block_hash);
+        Ok(block_hash)
+    }
+}
+
+/// Executes `request` on top of the state at the end of the block with the given `header`, and
+/// returns the output of the call.
+pub fn execute_call<DB: Database<Error = RpcServeError>>(
+    db: DB,
+    header: &Header,
+    request: CallRequest,
+) -> Result<Vec<u8>, RpcServeError> {
+    let mut evm = new_evm(db, header, request);
+    match transact(&mut evm)? {
+        ExecutionResult::Success { output, .. } => match output {
+            Output::Call(output) => Ok(output.to_vec()),
+            Output::Create(output, _) => Ok(output.to_vec()),
+        },
+        ExecutionResult::Revert { output, .. } => Err(execution_reverted(&output)),
+        ExecutionResult::Halt { reason, .. } => Err(execution_halted(reason)),
+    }
+}
+
+/// Returns the lowest gas limit with which `request` succeeds on top of the state at the end of
+/// the block with the given `header`.
+///
+/// The gas used by a call isn't always enough to run it, because of gas refunds and the gas that
+/// is withheld from sub calls, so this searches between the gas used and the gas limit of the
+/// request, or of the block if the request doesn't set one.
+pub fn estimate_gas<DB: Database<Error = RpcServeError>>(
+    db: DB,
+    header: &Header,
+    request: CallRequest,
+) -> Result<u64, RpcServeError> {
+    let mut evm = new_evm(db, header, request);
+    let gas_cap = evm.env.tx.gas_limit;
+    let gas_used = match transact(&mut evm)? {
+        ExecutionResult::Success { gas_used, .. } => gas_used,
+        ExecutionResult::Revert { output, .. } => return Err(execution_reverted(&output)),
+        ExecutionResult::Halt {
+            reason: Halt::OutOfGas(_),
+            ..
+        } => {
+            return Err(RpcServeError::Message(format!(
+                "gas required exceeds allowance ({gas_cap})"
+            )))
+        }
+        ExecutionResult::Halt { reason, .. } => return Err(execution_halted(reason)),
+    };
+
+    let (mut lowest_failing, mut lowest_passing) = (gas_used - 1, gas_cap);
+    while lowest_failing + 1 < lowest_passing {
+        let gas_limit = lowest_failing + (lowest_passing - lowest_failing) / 2;
+        evm.env.tx.gas_limit = gas_limit;
+        match transact(&mut evm)? {
+            ExecutionResult::Success { .. } => lowest_passing = gas_limit,
+            _ => lowest_failing = gas_limit,
+        }
+    }
+    Ok(lowest_passing)
+}
+
+fn new_evm<DB: Database>(db: DB, header: &Header, request: CallRequest) -> EVM<DB> {
+    let mut evm = EVM::new();
+    evm.database(db);
+
+    evm.env.cfg.chain_id = EvmU256::from(CHAIN_ID);
+    evm.env.cfg.spec_id = spec_id(header.number);
+
+    evm.env.block.number = EvmU256::from(header.number);
+    evm.env.block.coinbase = B160(header.author.to_fixed_bytes());
+    evm.env.block.timestamp = EvmU256::from(header.timestamp);
+    evm.env.block.difficulty = to_evm_u256(header.difficulty);
+    evm.env.block.gas_limit = to_evm_u256(header.gas_limit);
+    // Like other clients, the base fee is only enforced for calls that set a gas price.
+    if request.gas_price.is_some() {
+        evm.env.block.basefee = to_evm_u256(header.base_fee_per_gas.unwrap_or_default());
+    }
+
+    evm.env.tx.caller = B160(request.from.unwrap_or_default().to_fixed_bytes());
+    evm.env.tx.transact_to = match request.to {
+        Some(to) => TransactTo::Call(B160(to.to_fixed_bytes())),
+        None => TransactTo::create(),
+    };
+    // Gas limits beyond u64 can't be spent anyway, so they're capped rather than truncated.
+    evm.env.tx.gas_limit = request
+        .gas
+        .unwrap_or(header.gas_limit)
+        .min(U256::from(u64::MAX))
+        .as_u64();
+    evm.env.tx.gas_price = to_evm_u256(request.gas_price.unwrap_or_default());
+    evm.env.tx.value = to_evm_u256(request.value.unwrap_or_default());
+    evm.env.tx.data = request.data.map(Into::into).unwrap_or_default();
+    evm
+}
+
+/// Executes the transaction of `evm` without committing its changes, so that it can be executed
+/// again.
+fn transact<DB: Database<Error = RpcServeError>>(
+    evm: &mut EVM<DB>,
+) -> Result<ExecutionResult, RpcServeError> {
+    let result = evm.transact().map_err(|err| match err {
+        EVMError::Database(err) => err,
+        EVMError::Transaction(err) => RpcServeError::Message(format!("Invalid call: {err:?}")),
+        _ => RpcServeError::Message("Unable to execute call".into()),
+    })?;
+    Ok(result.result)
+}
+
+fn execution_reverted(output: &[u8]) -> RpcServeError {
+    RpcServeError::JsonRpc(
+        JsonRpcError::new(EXECUTION_REVERTED_CODE, "execution reverted")
+            .with_data(json!(hex_encode(output))),
+    )
+}
+
+fn execution_halted(reason: Halt) -> RpcServeError {
+    match reason {
+        Halt::OutOfGas(_) => RpcServeError::Message("out of gas".to_string()),
+        reason => RpcServeError::Message(format!("execution halted: {reason:?}")),
+    }
+}
+
+/// Returns the mainnet hard fork active at the given block.
+fn spec_id(block_number: u64) -> SpecId {
+    match block_number {
+        0..=1_149_999 => SpecId::FRONTIER,
+        1_150_000..=2_462_999 => SpecId::HOMESTEAD,
+        2_463_000..=2_674_999 => SpecId::TANGERINE,
+        2_675_000..=4_369_999 => SpecId::SPURIOUS_DRAGON,
+        4_370_000..=7_279_999 => SpecId::BYZANTIUM,
+        7_280_000..=9_068_999 => SpecId::PETERSBURG,
+        9_069_000..=12_243_999 => SpecId::ISTANBUL,
+        12_244_000..=12_964_999 => SpecId::BERLIN,
+        12_965_000..=MERGE_BLOCK_NUMBER => SpecId::LONDON,
+        _ => SpecId::MERGE,
+    }
+}
+
+fn to_evm_u256(value: U256) -> EvmU256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    EvmU256::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ethereum_types::{Bloom, H64};
+
+    /// Returns the value of storage slot 0.
+    const SLOAD_CODE: &[u8] = &[
+        0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+    ];
+    /// Reverts with 0xdead.
+    const REVERT_CODE: &[u8] = &[
+        0x61, 0xde, 0xad, 0x60, 0x00, 0x52, 0x60, 0x02, 0x60, 0x1e, 0xfd,
+    ];
+    /// Loops until it runs out of gas.
+    const LOOP_CODE: &[u8] = &[0x5b, 0x60, 0x00, 0x56];
+
+    const SLOAD_ADDRESS: H160 = H160([0x01; 20]);
+    const REVERT_ADDRESS: H160 = H160([0x02; 20]);
+    const LOOP_ADDRESS: H160 = H160([0x03; 20]);
+
+    /// A fixture state with a contract for each of the outcomes of a call.
+    struct FixtureDb {
+        accounts: HashMap<B160, AccountInfo>,
+        storage: HashMap<(B160, EvmU256), EvmU256>,
+    }
+
+    impl FixtureDb {
+        fn new() -> Self {
+            let accounts = [
+                (SLOAD_ADDRESS, SLOAD_CODE),
+                (REVERT_ADDRESS, REVERT_CODE),
+                (LOOP_ADDRESS, LOOP_CODE),
+            ]
+            .into_iter()
+            .map(|(address, code)| {
+                let code = Bytecode::new_raw(code.to_vec().into());
+                let info = AccountInfo {
+                    balance: EvmU256::ZERO,
+                    nonce: 1,
+                    code_hash: code.hash(),
+                    code: Some(code),
+                };
+                (B160(address.to_fixed_bytes()), info)
+            })
+            .collect();
+            let storage = HashMap::from([(
+                (B160(SLOAD_ADDRESS.to_fixed_bytes()), EvmU256::ZERO),
+                EvmU256::from(42),
+            )]);
+            Self { accounts, storage }
+        }
+    }
+
+    impl Database for FixtureDb {
+        type Error = RpcServeError;
+
+        fn basic(&mut self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+            Ok(self.accounts.get(&address).cloned())
+        }
+
+        fn code_by_hash(&mut self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
+            Ok(Bytecode::new())
+        }
+
+        fn storage(&mut self, address: B160, index: EvmU256) -> Result<EvmU256, Self::Error> {
+            Ok(self
+                .storage
+                .get(&(address, index))
+                .copied()
+                .unwrap_or_default())
+        }
+
+        fn block_hash(&mut self, _number: EvmU256) -> Result<B256, Self::Error> {
+            Ok(B256::zero())
+        }
+    }
+
+    fn header() -> Header {
+        Header {
+            parent_hash: H256::zero(),
+            uncles_hash: H256::zero(),
+            author: H160::zero(),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: H256::zero(),
+            logs_bloom: Bloom::zero(),
+            difficulty: U256::from(1),
+            number: 13_000_000,
+            gas_limit: U256::from(30_000_000),
+            gas_used: U256::zero(),
+            timestamp: 1_628_166_822,
+            extra_data: vec![],
+            mix_hash: None,
+            nonce: Some(H64::zero()),
+            base_fee_per_gas: Some(U256::from(1_000_000_000)),
+            withdrawals_root: None,
+        }
+    }
+
+    fn call_to(to: H160) -> CallRequest {
+        CallRequest {
+            to: Some(to),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_storage_is_read_without_lookup() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        // The networks are gone, so any lookup would fail.
+        let mut db = StateNetworkDb::new(
+            runtime.handle().clone(),
+            mpsc::unbounded_channel().0,
+            mpsc::unbounded_channel().0,
+            MasterAccumulator::default(),
+            H256::zero(),
+        );
+        let account = AccountState::default();
+        assert!(account.has_empty_storage());
+        db.accounts
+            .insert(SLOAD_ADDRESS, (AccountInfo::default(), account));
+
+        let address = B160(SLOAD_ADDRESS.to_fixed_bytes());
+        assert_eq!(db.storage(address, EvmU256::ZERO).unwrap(), EvmU256::ZERO);
+        assert_eq!(
+            db.storage(address, EvmU256::from(1)).unwrap(),
+            EvmU256::ZERO
+        );
+    }
+
+    #[test]
+    fn call_gas_limit_is_capped() {
+        let request = CallRequest {
+            gas: Some(U256::from(u64::MAX) + 1),
+            ..call_to(SLOAD_ADDRESS)
+        };
+        let evm = new_evm(FixtureDb::new(), &header(), request);
+        assert_eq!(evm.env.tx.gas_limit, u64::MAX);
+    }
+
+    #[test]
+    fn call_returns_output() {
+        let output = execute_call(FixtureDb::new(), &header(), call_to(SLOAD_ADDRESS)).unwrap();
+        let mut expected = [0u8; 32];
+        expected[31] = 42;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn call_reverts_with_data() {
+        let err = execute_call(FixtureDb::new(), &header(), call_to(REVERT_ADDRESS)).unwrap_err();
+        match err {
+            RpcServeError::JsonRpc(err) => {
+                assert_eq!(err.code, EXECUTION_REVERTED_CODE);
+                assert_eq!(err.message, "execution reverted");
+                assert_eq!(err.data, Some(json!("0xdead")));
+            }
+            err => panic!("Expected execution reverted, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn call_runs_out_of_gas() {
+        let request = CallRequest {
+            gas: Some(U256::from(100_000)),
+            ..call_to(LOOP_ADDRESS)
+        };
+        let err = execute_call(FixtureDb::new(), &header(), request).unwrap_err();
+        assert!(matches!(err, RpcServeError::Message(msg) if msg == "out of gas"));
+    }
+
+    #[test]
+    fn estimate_gas_of_transfer() {
+        let gas = estimate_gas(FixtureDb::new(), &header(), call_to(H160([0x04; 20]))).unwrap();
+        assert_eq!(gas, 21_000);
+    }
+
+    #[test]
+    fn estimate_gas_is_lowest_passing_gas_limit() {
+        let gas = estimate_gas(FixtureDb::new(), &header(), call_to(SLOAD_ADDRESS)).unwrap();
+        // A cold SLOAD after the intrinsic cost and the pushes and memory expansion.
+        assert!(gas > 21_000 + 2_100);
+        let request = CallRequest {
+            gas: Some(U256::from(gas)),
+            ..call_to(SLOAD_ADDRESS)
+        };
+        assert!(execute_call(FixtureDb::new(), &header(), request).is_ok());
+        let request = CallRequest {
+            gas: Some(U256::from(gas - 1)),
+            ..call_to(SLOAD_ADDRESS)
+        };
+        assert!(execute_call(FixtureDb::new(), &header(), request).is_err());
+    }
+
+    #[test]
+    fn estimate_gas_of_revert() {
+        let err = estimate_gas(FixtureDb::new(), &header(), call_to(REVERT_ADDRESS)).unwrap_err();
+        assert!(matches!(err, RpcServeError::JsonRpc(err) if err.code == EXECUTION_REVERTED_CODE));
+    }
+
+    #[test]
+    fn estimate_gas_exceeds_allowance() {
+        let request = CallRequest {
+            gas: Some(U256::from(100_000)),
+            ..call_to(LOOP_ADDRESS)
+        };
+        let err = estimate_gas(FixtureDb::new(), &header(), request).unwrap_err();
+        assert!(
+            matches!(err, RpcServeError::Message(msg) if msg == "gas required exceeds allowance (100000)")
+        );
+    }
+}
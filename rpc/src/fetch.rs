@@ -1,16 +1,29 @@
 /// Fetch data from related Portal networks
-use ethereum_types::H256;
+use ethereum_types::{H160, H256};
 use serde_json::Value;
 use tokio::sync::mpsc;
 
 use ethportal_api::{
     types::{
         constants::CONTENT_ABSENT,
-        execution::{block_body::BlockBody, header::Header},
-        jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
+        execution::{block_body::BlockBody, header::Header, receipts::Receipts},
+        jsonrpc::{
+            endpoints::{BeaconEndpoint, CanonicalIndicesEndpoint, HistoryEndpoint, StateEndpoint},
+            error::JsonRpcError,
+            request::{
+                BeaconJsonRpcRequest, CanonicalIndicesJsonRpcRequest, HistoryJsonRpcRequest,
+                StateJsonRpcRequest,
+            },
+        },
+        state_trie::{
+            account_trie_key, key_nibbles, verify_bytecode, verify_node, walk_node, AccountState,
+            NodeRef, TrieStep,
+        },
     },
     utils::bytes::hex_decode,
-    ContentValue, HistoryContentKey, HistoryContentValue,
+    AccountTrieNode, CanonicalIndicesContentKey, ContentValue, ContractBytecode,
+    ContractStorageTrieNode, HistoryContentKey, HistoryContentValue, StateContentKey,
+    TransactionIndex, TransactionIndexKey,
 };
 
 use crate::errors::RpcServeError;
@@ -19,17 +32,14 @@ pub async fn proxy_query_to_history_subnet(
     network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     endpoint: HistoryEndpoint,
 ) -> Result<Value, RpcServeError> {
-    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
-    let message = HistoryJsonRpcRequest {
-        endpoint,
-        resp: resp_tx,
-    };
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+    let message = HistoryJsonRpcRequest::new(endpoint, resp_tx);
     let _ = network.send(message);
 
     match resp_rx.recv().await {
         Some(val) => match val {
             Ok(result) => Ok(result),
-            Err(msg) => Err(RpcServeError::Message(msg)),
+            Err(err) => Err(err.into()),
         },
         None => Err(RpcServeError::Message(
             "Internal error: No response from chain history subnetwork".to_string(),
@@ -37,6 +47,63 @@ pub async fn proxy_query_to_history_subnet(
     }
 }
 
+pub async fn proxy_query_to_state_subnet(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    endpoint: StateEndpoint,
+) -> Result<Value, RpcServeError> {
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+    let message = StateJsonRpcRequest::new(endpoint, resp_tx);
+    let _ = network.send(message);
+
+    match resp_rx.recv().await {
+        Some(val) => match val {
+            Ok(result) => Ok(result),
+            Err(err) => Err(err.into()),
+        },
+        None => Err(RpcServeError::Message(
+            "Internal error: No response from state subnetwork".to_string(),
+        )),
+    }
+}
+
+pub async fn proxy_query_to_beacon_subnet(
+    network: &mpsc::UnboundedSender<BeaconJsonRpcRequest>,
+    endpoint: BeaconEndpoint,
+) -> Result<Value, RpcServeError> {
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+    let message = BeaconJsonRpcRequest::new(endpoint, resp_tx);
+    let _ = network.send(message);
+
+    match resp_rx.recv().await {
+        Some(val) => match val {
+            Ok(result) => Ok(result),
+            Err(err) => Err(err.into()),
+        },
+        None => Err(RpcServeError::Message(
+            "Internal error: No response from chain beacon subnetwork".to_string(),
+        )),
+    }
+}
+
+pub async fn proxy_query_to_canonical_indices_subnet(
+    network: &mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>,
+    endpoint: CanonicalIndicesEndpoint,
+) -> Result<Value, RpcServeError> {
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+    let message = CanonicalIndicesJsonRpcRequest::new(endpoint, resp_tx);
+    let _ = network.send(message);
+
+    match resp_rx.recv().await {
+        Some(val) => match val {
+            Ok(result) => Ok(result),
+            Err(err) => Err(err.into()),
+        },
+        None => Err(RpcServeError::Message(
+            "Internal error: No response from canonical indices subnetwork".to_string(),
+        )),
+    }
+}
+
 pub async fn find_header_by_hash(
     network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     block_hash: H256,
@@ -69,6 +136,22 @@ pub async fn find_block_body_by_hash(
     }
 }
 
+pub async fn find_receipts_by_hash(
+    network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    block_hash: H256,
+) -> Result<Receipts, RpcServeError> {
+    // Request the block receipts from the history subnet.
+    let content_key: HistoryContentKey = HistoryContentKey::BlockReceipts(block_hash.into());
+    let receipts = find_content_by_hash(network, content_key).await?;
+
+    match receipts {
+        HistoryContentValue::Receipts(receipts) => Ok(receipts),
+        wrong_val => Err(RpcServeError::Message(format!(
+            "Internal trin error: got back non-receipts from a key that must only point to receipts; got {wrong_val:?}"
+        ))),
+    }
+}
+
 async fn find_content_by_hash(
     network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     content_key: HistoryContentKey,
@@ -94,3 +177,173 @@ async fn find_content_by_hash(
         RpcServeError::Message(message)
     })
 }
+
+/// Looks up the block that includes the transaction with hash `transaction_hash`, and the index of
+/// the transaction in that block.
+pub async fn find_transaction_index(
+    network: &mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>,
+    transaction_hash: H256,
+) -> Result<TransactionIndex, RpcServeError> {
+    let content_key = CanonicalIndicesContentKey::TransactionIndex(TransactionIndexKey {
+        transaction_hash: transaction_hash.to_fixed_bytes(),
+    });
+    let endpoint = CanonicalIndicesEndpoint::RecursiveFindContent(content_key);
+    let mut result = proxy_query_to_canonical_indices_subnet(network, endpoint).await?;
+    let content = match result["content"].take() {
+        Value::String(content) => content,
+        wrong_type => {
+            return Err(RpcServeError::Message(format!(
+                "Invalid internal representation of the index of transaction {transaction_hash:?}; json: {wrong_type:?}"
+            )))
+        }
+    };
+    if content == CONTENT_ABSENT {
+        return Err(RpcServeError::Message(format!(
+            "Transaction {transaction_hash:?} not found"
+        )));
+    }
+    let content = hex_decode(&content).map_err(|err| RpcServeError::Message(err.to_string()))?;
+    TransactionIndex::decode(&content).map_err(|err| {
+        RpcServeError::Message(format!(
+            "Invalid index of transaction {transaction_hash:?}: {err}"
+        ))
+    })
+}
+
+/// Looks up the state of an account in the state with root `state_root`. A missing account is
+/// returned as the empty account.
+pub async fn find_account(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    state_root: H256,
+    address: H160,
+) -> Result<AccountState, RpcServeError> {
+    let key = account_trie_key(address);
+    match find_account_trie_value(network, state_root, key).await? {
+        Some(account) => AccountState::from_rlp(&account).map_err(|err| {
+            RpcServeError::Message(format!("Invalid account state for {address:?}: {err}"))
+        }),
+        None => Ok(AccountState::default()),
+    }
+}
+
+/// Looks up the value stored under `key` in the account trie with root `state_root`, fetching and
+/// verifying every trie node along the path from the state network.
+async fn find_account_trie_value(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    state_root: H256,
+    key: H256,
+) -> Result<Option<Vec<u8>>, RpcServeError> {
+    find_trie_value(network, state_root, key, |path, node_hash| {
+        StateContentKey::AccountTrieNode(AccountTrieNode {
+            path: path.into(),
+            node_hash: node_hash.to_fixed_bytes(),
+            state_root: state_root.to_fixed_bytes(),
+        })
+    })
+    .await
+}
+
+/// Looks up the value stored under `key` in the storage trie of the contract at `address`, as of
+/// the state with root `state_root`.
+pub async fn find_storage_trie_value(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    state_root: H256,
+    address: H160,
+    storage_root: H256,
+    key: H256,
+) -> Result<Option<Vec<u8>>, RpcServeError> {
+    find_trie_value(network, storage_root, key, |path, node_hash| {
+        StateContentKey::ContractStorageTrieNode(ContractStorageTrieNode {
+            address: address.as_bytes().to_vec().into(),
+            path: path.into(),
+            node_hash: node_hash.to_fixed_bytes(),
+            state_root: state_root.to_fixed_bytes(),
+        })
+    })
+    .await
+}
+
+/// Looks up the bytecode with hash `code_hash` of the contract at `address`.
+pub async fn find_contract_bytecode(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    address: H160,
+    code_hash: H256,
+) -> Result<Vec<u8>, RpcServeError> {
+    let content_key = StateContentKey::ContractBytecode(ContractBytecode {
+        address: address.as_bytes().to_vec().into(),
+        code_hash: code_hash.to_fixed_bytes(),
+    });
+    let code = find_state_content(network, content_key).await?;
+    if !verify_bytecode(&code, code_hash) {
+        return Err(RpcServeError::Message(format!(
+            "Bytecode received from the state network does not match hash {code_hash:?}"
+        )));
+    }
+    Ok(code)
+}
+
+/// Walks the trie with root `root` along `key`, fetching every trie node that is not embedded in
+/// its parent from the state network, under the content key built by `node_key`.
+async fn find_trie_value(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    root: H256,
+    key: H256,
+    node_key: impl Fn(Vec<u8>, H256) -> StateContentKey,
+) -> Result<Option<Vec<u8>>, RpcServeError> {
+    let nibbles = key_nibbles(key.as_bytes());
+    let mut node = NodeRef::Hash(root);
+    let mut depth = 0;
+    loop {
+        let encoded_node = match node {
+            NodeRef::Hash(node_hash) => {
+                let content_key = node_key(nibbles[..depth].to_vec(), node_hash);
+                let encoded_node = find_state_content(network, content_key).await?;
+                if !verify_node(&encoded_node, node_hash) {
+                    return Err(RpcServeError::Message(format!(
+                        "Trie node received from the state network does not match hash {node_hash:?}"
+                    )));
+                }
+                encoded_node
+            }
+            NodeRef::Inline(encoded_node) => encoded_node,
+        };
+        let step = walk_node(&encoded_node, &nibbles, depth).map_err(|err| {
+            RpcServeError::Message(format!("Invalid trie node in the state network: {err}"))
+        })?;
+        match step {
+            TrieStep::Value(value) => return Ok(Some(value)),
+            TrieStep::Absent => return Ok(None),
+            TrieStep::Child {
+                node: child,
+                depth: child_depth,
+            } => {
+                node = child;
+                depth = child_depth;
+            }
+        }
+    }
+}
+
+async fn find_state_content(
+    network: &mpsc::UnboundedSender<StateJsonRpcRequest>,
+    content_key: StateContentKey,
+) -> Result<Vec<u8>, RpcServeError> {
+    let endpoint = StateEndpoint::RecursiveFindContent(content_key.clone());
+    let mut result = proxy_query_to_state_subnet(network, endpoint).await?;
+    let content = match result["content"].take() {
+        serde_json::Value::String(s) => s,
+        wrong_type => {
+            let message =
+                format!("Invalid internal representation of {content_key:?}; json: {wrong_type:?}");
+            return Err(RpcServeError::Message(message));
+        }
+    };
+    if content == CONTENT_ABSENT {
+        return Err(RpcServeError::Message("Content not found".into()));
+    };
+    hex_decode(&content).map_err(|err| {
+        let message =
+            format!("Invalid internal representation of {content_key:?}; could not decode: {err}");
+        RpcServeError::Message(message)
+    })
+}
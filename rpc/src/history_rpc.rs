@@ -1,4 +1,7 @@
-use crate::{fetch::proxy_query_to_history_subnet, serde::from_value};
+use crate::{
+    content_pages::ContentPages, errors::RpcServeError, fetch::proxy_query_to_history_subnet,
+    serde::from_value,
+};
 
 use crate::jsonrpsee::core::{async_trait, RpcResult};
 use discv5::enr::NodeId;
@@ -8,20 +11,27 @@ use ethportal_api::{
         enr::Enr,
         history::{ContentInfo, PaginateLocalContentInfo, TraceContentInfo},
         jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
-        portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
+        portal::{
+            AcceptInfo, ContentPage, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo,
+            TraceOfferInfo,
+        },
     },
-    HistoryContentKey, HistoryContentValue, HistoryNetworkApiServer, PossibleHistoryContentValue,
-    RoutingTableInfo,
+    ContentValue, HistoryContentKey, HistoryContentValue, HistoryNetworkApiServer,
+    PossibleHistoryContentValue, RoutingTableInfo,
 };
 use tokio::sync::mpsc;
 
 pub struct HistoryNetworkApi {
     network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    content_pages: ContentPages,
 }
 
 impl HistoryNetworkApi {
     pub fn new(network: mpsc::UnboundedSender<HistoryJsonRpcRequest>) -> Self {
-        Self { network }
+        Self {
+            network,
+            content_pages: ContentPages::default(),
+        }
     }
 }
 
@@ -140,6 +150,36 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(info)
     }
 
+    /// Lookup a target content key in the network, and return the first page of its value.
+    async fn recursive_find_content_paged(
+        &self,
+        content_key: HistoryContentKey,
+        page_size: Option<u64>,
+    ) -> RpcResult<ContentPage> {
+        let content = match self.recursive_find_content(content_key).await? {
+            ContentInfo::Content {
+                content: PossibleHistoryContentValue::ContentPresent(content),
+                ..
+            } => content.encode(),
+            ContentInfo::Content {
+                content: PossibleHistoryContentValue::ContentAbsent,
+                ..
+            } => vec![],
+            _ => {
+                return Err(RpcServeError::Message(
+                    "Unexpected response to recursive content lookup".to_string(),
+                )
+                .into())
+            }
+        };
+        Ok(self.content_pages.first_page(content, page_size))
+    }
+
+    /// Return the next page of a content value found by a paged recursive lookup.
+    async fn content_page(&self, continuation: String) -> RpcResult<ContentPage> {
+        Ok(self.content_pages.page(&continuation)?)
+    }
+
     /// Pagination of local content keys
     async fn paginate_local_content_keys(
         &self,
@@ -178,6 +218,18 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Send the provided content to interested peers, offering each peer all of the content it is
+    /// interested in at once. Return tracing info detailing the gossip propagation of each item.
+    async fn trace_gossip_batch(
+        &self,
+        content_items: Vec<(HistoryContentKey, HistoryContentValue)>,
+    ) -> RpcResult<Vec<TraceGossipInfo>> {
+        let endpoint = HistoryEndpoint::TraceGossipBatch(content_items);
+        let result = proxy_query_to_history_subnet(&self.network, endpoint).await?;
+        let result: Vec<TraceGossipInfo> = from_value(result)?;
+        Ok(result)
+    }
+
     /// Send an OFFER request with given ContentKey, to the designated peer and wait for a response.
     /// Returns the content keys bitlist upon successful content transmission or empty bitlist
     /// receive.
@@ -193,6 +245,20 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Send an OFFER request with the given ContentKeys, read from the local database, to the
+    /// designated peer and wait for the uTP transfer to finish. Returns the content keys bitlist
+    /// along with whether the accepted content was successfully transferred.
+    async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_keys: Vec<HistoryContentKey>,
+    ) -> RpcResult<TraceOfferInfo> {
+        let endpoint = HistoryEndpoint::TraceOffer(enr, content_keys);
+        let result = proxy_query_to_history_subnet(&self.network, endpoint).await?;
+        let result: TraceOfferInfo = from_value(result)?;
+        Ok(result)
+    }
+
     /// Store content key with a content data to the local database.
     async fn store(
         &self,
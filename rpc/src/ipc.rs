@@ -0,0 +1,219 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::RpcError;
+
+/// Prepares `path` for the ipc server to bind a socket to.
+///
+/// Missing parent directories are created, and a socket left behind by a server that didn't shut
+/// down cleanly is removed. Fails if another server is still listening on the socket.
+pub fn prepare_socket_path(path: &Path) -> Result<(), RpcError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            RpcError::Custom(format!(
+                "Unable to create ipc socket directory {}: {err}",
+                parent.display()
+            ))
+        })?;
+    }
+    remove_stale_socket(path)
+}
+
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> Result<(), RpcError> {
+    use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(RpcError::Custom(err.to_string())),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(RpcError::Custom(format!(
+            "Ipc path {} exists and is not a socket",
+            path.display()
+        )));
+    }
+    match UnixStream::connect(path) {
+        Ok(_) => Err(RpcError::Custom(format!(
+            "Ipc socket {} is already in use by another server",
+            path.display()
+        ))),
+        Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+            tracing::info!(path = %path.display(), "Removing stale ipc socket");
+            fs::remove_file(path).map_err(|err| RpcError::Custom(err.to_string()))
+        }
+        Err(err) => Err(RpcError::Custom(err.to_string())),
+    }
+}
+
+/// Named pipes are removed by the OS once their server exits.
+#[cfg(not(unix))]
+fn remove_stale_socket(_path: &Path) -> Result<(), RpcError> {
+    Ok(())
+}
+
+/// A private directory next to the ipc socket, for the server to bind the socket in.
+///
+/// Once bound, the socket is given its permissions and only then moved to its path, so it's never
+/// accessible to other users, even briefly. The directory is removed when this is dropped.
+#[cfg(unix)]
+pub struct PrivateSocketDir {
+    dir: PathBuf,
+    socket_path: PathBuf,
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl PrivateSocketDir {
+    pub fn new(path: &Path) -> Result<Self, RpcError> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(RpcError::Custom(format!(
+                "Invalid ipc socket path {}",
+                path.display()
+            )));
+        };
+        let mut dir_name = OsString::from(".");
+        dir_name.push(name);
+        dir_name.push(format!(".{}", std::process::id()));
+        let dir = parent.join(dir_name);
+        // Left behind by an earlier process with the same pid that didn't shut down cleanly.
+        let _ = fs::remove_dir_all(&dir);
+        fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|err| {
+                RpcError::Custom(format!(
+                    "Unable to create ipc socket directory {}: {err}",
+                    dir.display()
+                ))
+            })?;
+        Ok(Self {
+            socket_path: dir.join(name),
+            dir,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// The path for the server to bind the socket to.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Gives the bound socket the permissions `mode`, then moves it to its path.
+    pub fn publish(&self, mode: u32) -> Result<(), RpcError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&self.socket_path, fs::Permissions::from_mode(mode))
+            .and_then(|()| fs::rename(&self.socket_path, &self.path))
+            .map_err(|err| {
+                RpcError::Custom(format!(
+                    "Unable to publish ipc socket {}: {err}",
+                    self.path.display()
+                ))
+            })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for PrivateSocketDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Named pipes have no file permissions, so they're bound at their path.
+#[cfg(not(unix))]
+pub struct PrivateSocketDir {
+    path: PathBuf,
+}
+
+#[cfg(not(unix))]
+impl PrivateSocketDir {
+    pub fn new(path: &Path) -> Result<Self, RpcError> {
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn publish(&self, _mode: u32) -> Result<(), RpcError> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::os::unix::{fs::PermissionsExt, net::UnixListener};
+
+    use portalnet::utils::db::setup_temp_dir;
+
+    #[test]
+    fn creates_parent_directories() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir.path().join("nested/dir/trin.ipc");
+        prepare_socket_path(&path).unwrap();
+        assert!(path.parent().unwrap().is_dir());
+    }
+
+    #[test]
+    fn removes_stale_socket() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir.path().join("trin.ipc");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        prepare_socket_path(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keeps_socket_in_use() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir.path().join("trin.ipc");
+        let _listener = UnixListener::bind(&path).unwrap();
+        assert!(prepare_socket_path(&path).is_err());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn rejects_regular_file() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir.path().join("trin.ipc");
+        fs::write(&path, b"not a socket").unwrap();
+        assert!(prepare_socket_path(&path).is_err());
+    }
+
+    #[test]
+    fn publishes_socket_with_mode() {
+        use std::os::unix::net::UnixStream;
+
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir.path().join("trin.ipc");
+        let dir = PrivateSocketDir::new(&path).unwrap();
+        let _listener = UnixListener::bind(dir.socket_path()).unwrap();
+        let dir_mode = fs::metadata(dir.socket_path().parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(dir_mode & 0o777, 0o700);
+
+        dir.publish(0o600).unwrap();
+        let private_dir = dir.socket_path().parent().unwrap().to_path_buf();
+        drop(dir);
+        assert!(!private_dir.exists());
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        UnixStream::connect(&path).unwrap();
+    }
+}
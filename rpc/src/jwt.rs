@@ -0,0 +1,240 @@
+use std::{
+    collections::HashSet,
+    fs,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ethportal_api::utils::bytes::hex_decode;
+use hyper::{
+    header::{HeaderMap, AUTHORIZATION},
+    Body, Request, Response, StatusCode,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use tower::{Layer, Service};
+
+/// Length of a JWT secret, in bytes.
+const JWT_SECRET_LENGTH: usize = 32;
+
+/// The largest difference allowed between the issued-at time of a token and the local clock, as
+/// in the engine API.
+const MAX_IAT_DRIFT: Duration = Duration::from_secs(60);
+
+/// Errors thrown when loading a JWT secret or validating a bearer token.
+#[derive(Debug, thiserror::Error)]
+pub enum JwtError {
+    #[error("Unable to read JWT secret file {path}: {source}")]
+    ReadSecret {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("JWT secret must be a hex encoded string of {JWT_SECRET_LENGTH} bytes")]
+    InvalidSecret,
+    #[error("Missing bearer token in the Authorization header")]
+    MissingToken,
+    #[error("Invalid JWT: {0}")]
+    InvalidToken(#[from] jsonwebtoken::errors::Error),
+    #[error("JWT was not issued within {MAX_IAT_DRIFT:?} of the local time")]
+    StaleToken,
+}
+
+/// Claims of an engine API style JWT.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// Time at which the token was issued, in seconds since the unix epoch.
+    pub iat: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+}
+
+/// A shared secret used to sign and validate HS256 tokens.
+#[derive(Clone)]
+pub struct JwtSecret([u8; JWT_SECRET_LENGTH]);
+
+impl JwtSecret {
+    /// Parses a hex encoded secret, with or without a `0x` prefix.
+    pub fn from_hex(hex: &str) -> Result<Self, JwtError> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let bytes = hex_decode(&format!("0x{hex}")).map_err(|_| JwtError::InvalidSecret)?;
+        let secret = bytes.try_into().map_err(|_| JwtError::InvalidSecret)?;
+        Ok(Self(secret))
+    }
+
+    /// Reads a hex encoded secret from the file at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, JwtError> {
+        let hex = fs::read_to_string(path).map_err(|source| JwtError::ReadSecret {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::from_hex(&hex)
+    }
+
+    /// Signs `claims` into a token.
+    pub fn encode(&self, claims: &Claims) -> Result<String, JwtError> {
+        let key = EncodingKey::from_secret(&self.0);
+        Ok(jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            claims,
+            &key,
+        )?)
+    }
+
+    /// Validates the signature of `token`, and that it was issued around the current time.
+    pub fn validate(&self, token: &str) -> Result<(), JwtError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        // Only `iat` is required by the engine API, `exp` is checked when present.
+        validation.required_spec_claims = HashSet::new();
+        let key = DecodingKey::from_secret(&self.0);
+        let claims = jsonwebtoken::decode::<Claims>(token, &key, &validation)?.claims;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        match now.abs_diff(claims.iat) > MAX_IAT_DRIFT.as_secs() {
+            true => Err(JwtError::StaleToken),
+            false => Ok(()),
+        }
+    }
+
+    /// Validates the bearer token in the `Authorization` header of a request.
+    fn validate_headers(&self, headers: &HeaderMap) -> Result<(), JwtError> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .ok_or(JwtError::MissingToken)?;
+        self.validate(token)
+    }
+}
+
+/// A layer that rejects http requests without a valid bearer token, with `401 Unauthorized`.
+#[derive(Clone)]
+pub struct AuthLayer {
+    secret: JwtSecret,
+}
+
+impl AuthLayer {
+    pub fn new(secret: JwtSecret) -> Self {
+        Self { secret }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            secret: self.secret.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [AuthLayer].
+#[derive(Clone)]
+pub struct AuthService<S> {
+    secret: JwtSecret,
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for AuthService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        match self.secret.validate_headers(request.headers()) {
+            Ok(()) => Box::pin(self.inner.call(request)),
+            Err(err) => {
+                let mut response = Response::new(Body::from(err.to_string()));
+                *response.status_mut() = StatusCode::UNAUTHORIZED;
+                Box::pin(std::future::ready(Ok(response)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    const SECRET: &str = "0x7365637265747365637265747365637265747365637265747365637265747365";
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn parse_secret() {
+        assert!(JwtSecret::from_hex(SECRET).is_ok());
+        assert!(JwtSecret::from_hex(&format!("{}\n", &SECRET[2..])).is_ok());
+        assert!(JwtSecret::from_hex("0x1234").is_err());
+        assert!(JwtSecret::from_hex("not hex").is_err());
+    }
+
+    #[test]
+    fn validate_token() {
+        let secret = JwtSecret::from_hex(SECRET).unwrap();
+        let claims = Claims {
+            iat: now(),
+            exp: None,
+        };
+        let token = secret.encode(&claims).unwrap();
+        secret.validate(&token).unwrap();
+
+        let other_secret = JwtSecret::from_hex(&format!("0x{}", "ab".repeat(32))).unwrap();
+        assert!(matches!(
+            other_secret.validate(&token),
+            Err(JwtError::InvalidToken(_))
+        ));
+    }
+
+    #[test]
+    fn reject_stale_token() {
+        let secret = JwtSecret::from_hex(SECRET).unwrap();
+        let claims = Claims {
+            iat: now() - 2 * MAX_IAT_DRIFT.as_secs(),
+            exp: None,
+        };
+        let token = secret.encode(&claims).unwrap();
+        assert!(matches!(secret.validate(&token), Err(JwtError::StaleToken)));
+    }
+
+    #[test]
+    fn reject_missing_bearer() {
+        let secret = JwtSecret::from_hex(SECRET).unwrap();
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            secret.validate_headers(&headers),
+            Err(JwtError::MissingToken)
+        ));
+
+        let token = secret
+            .encode(&Claims {
+                iat: now(),
+                exp: None,
+            })
+            .unwrap();
+        headers.insert(AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+        secret.validate_headers(&headers).unwrap();
+    }
+}
@@ -1,20 +1,31 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
+mod admin_rpc;
 mod beacon_rpc;
 mod builder;
+mod content_pages;
+mod correlation;
 mod cors;
 mod discv5_rpc;
 mod errors;
 mod eth_rpc;
+mod evm;
 mod fetch;
 mod history_rpc;
+mod ipc;
+mod jwt;
+mod net_rpc;
+mod notification;
+mod rate_limit;
 mod rpc_server;
 mod serde;
+mod state_rpc;
 mod web3_rpc;
 
 use crate::jsonrpsee::server::ServerBuilder;
 pub use crate::rpc_server::RpcServerHandle;
+use admin_rpc::AdminApi;
 use beacon_rpc::BeaconNetworkApi;
 pub use builder::{PortalRpcModule, RpcModuleBuilder, TransportRpcModuleConfig};
 use discv5_rpc::Discv5Api;
@@ -23,11 +34,21 @@ use eth_rpc::EthApi;
 use ethportal_api::{
     jsonrpsee,
     types::{
-        cli::{TrinConfig, Web3TransportType, BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
-        jsonrpc::request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+        cli::{
+            TrinConfig, Web3TransportType, BEACON_NETWORK, CANONICAL_INDICES_NETWORK,
+            HISTORY_NETWORK, STATE_NETWORK, TRANSACTION_GOSSIP_NETWORK,
+        },
+        jsonrpc::request::{
+            BeaconJsonRpcRequest, CanonicalIndicesJsonRpcRequest, HistoryJsonRpcRequest,
+            StateJsonRpcRequest,
+        },
     },
 };
 use history_rpc::HistoryNetworkApi;
+use jwt::JwtSecret;
+use net_rpc::NetApi;
+use rate_limit::RateLimits;
+use state_rpc::StateNetworkApi;
 use web3_rpc::Web3Api;
 
 use crate::rpc_server::RpcServerConfig;
@@ -39,15 +60,34 @@ use std::{
 };
 use tokio::sync::mpsc;
 
+/// Maximum size in bytes of a json-rpc request body accepted over http or ws.
+const MAX_REQUEST_BODY_SIZE: u32 = 10 * 1024 * 1024;
+
+/// Maximum size in bytes of a json-rpc response body served over http or ws. Content values are
+/// returned hex encoded, at twice their size, so this is larger than the request limit.
+const MAX_RESPONSE_BODY_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Returns a [ServerBuilder] configured with trin's http and ws body size limits.
+fn ws_http_server_builder() -> ServerBuilder {
+    ServerBuilder::default()
+        .max_request_body_size(MAX_REQUEST_BODY_SIZE)
+        .max_response_body_size(MAX_RESPONSE_BODY_SIZE)
+}
+
 pub async fn launch_jsonrpc_server(
     trin_config: TrinConfig,
     discv5: Arc<Discovery>,
     history_handler: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
     state_handler: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
     beacon_handler: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+    canonical_indices_handler: Option<mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>>,
 ) -> Result<RpcServerHandle, RpcError> {
-    // Discv5 and Web3 modules are enabled with every network
-    let mut modules = vec![PortalRpcModule::Discv5, PortalRpcModule::Web3];
+    // Discv5, Net and Web3 modules are enabled with every network
+    let mut modules = vec![
+        PortalRpcModule::Discv5,
+        PortalRpcModule::Net,
+        PortalRpcModule::Web3,
+    ];
 
     for network in trin_config.networks.iter() {
         match network.as_str() {
@@ -55,22 +95,38 @@ pub async fn launch_jsonrpc_server(
                 modules.push(PortalRpcModule::History);
                 modules.push(PortalRpcModule::Eth);
             }
-            STATE_NETWORK => {
-                // not implemented
-            }
+            STATE_NETWORK => modules.push(PortalRpcModule::State),
             BEACON_NETWORK => modules.push(PortalRpcModule::Beacon),
+            // The transaction gossip network doesn't serve json-rpc endpoints.
+            TRANSACTION_GOSSIP_NETWORK => {}
+            // The canonical indices network is only served through the `eth_` endpoints.
+            CANONICAL_INDICES_NETWORK => {}
             _ => panic!("Unexpected network type: {network}"),
         }
     }
 
+    // The admin module can shut the node down and read or write files on its host, so it's only
+    // served to clients that are local or authenticated.
+    let with_admin = |modules: &Vec<PortalRpcModule>, allowed: bool| {
+        let mut modules = modules.clone();
+        if trin_config.web3_admin && allowed {
+            modules.push(PortalRpcModule::Admin);
+        }
+        modules
+    };
+
     let handle: RpcServerHandle = match trin_config.web3_transport {
         Web3TransportType::IPC => {
-            let transport = TransportRpcModuleConfig::default().with_ipc(modules);
-            let transport_modules = RpcModuleBuilder::new(discv5)
+            let transport =
+                TransportRpcModuleConfig::default().with_ipc(with_admin(&modules, true));
+            let builder = RpcModuleBuilder::new(discv5)
                 .maybe_with_history(history_handler)
                 .maybe_with_beacon(beacon_handler)
                 .maybe_with_state(state_handler)
-                .build(transport);
+                .maybe_with_canonical_indices(canonical_indices_handler)
+                .with_max_logs_block_range(trin_config.max_logs_block_range);
+            let shutdown = builder.shutdown_signal();
+            let transport_modules = builder.build(transport);
 
             RpcServerConfig::default()
                 .with_ipc_endpoint(
@@ -79,41 +135,65 @@ pub async fn launch_jsonrpc_server(
                         .to_str()
                         .expect("Path should be string"),
                 )
+                .with_ipc_mode(trin_config.web3_ipc_mode)
                 .with_ipc(IpcServerBuilder::default())
                 .start(transport_modules)
                 .await?
+                .with_shutdown_signal(shutdown)
         }
         Web3TransportType::HTTP => {
+            let http_address = trin_config
+                .web3_http_address
+                .socket_addrs(|| None)
+                .expect("Invalid socket address")[0];
+            let jwt_secret = trin_config
+                .web3_jwt_secret
+                .as_deref()
+                .map(JwtSecret::from_file)
+                .transpose()
+                .map_err(|err| RpcError::Custom(err.to_string()))?;
+            // The ws server listens on every interface.
             let transport = match trin_config.ws {
-                true => TransportRpcModuleConfig::default().with_ws(modules.clone()),
+                true => TransportRpcModuleConfig::default()
+                    .with_ws(with_admin(&modules, jwt_secret.is_some())),
                 false => TransportRpcModuleConfig::default(),
             };
-            let transport = transport.with_http(modules);
+            let transport = transport.with_http(with_admin(
+                &modules,
+                jwt_secret.is_some() || http_address.ip().is_loopback(),
+            ));
 
-            let transport_modules = RpcModuleBuilder::new(discv5)
+            let builder = RpcModuleBuilder::new(discv5)
                 .maybe_with_history(history_handler)
                 .maybe_with_beacon(beacon_handler)
                 .maybe_with_state(state_handler)
-                .build(transport);
+                .maybe_with_canonical_indices(canonical_indices_handler)
+                .with_max_logs_block_range(trin_config.max_logs_block_range);
+            let shutdown = builder.shutdown_signal();
+            let transport_modules = builder.build(transport);
 
             let rpc_server_config = RpcServerConfig::default()
-                .with_http_address(
-                    trin_config
-                        .web3_http_address
-                        .socket_addrs(|| None)
-                        .expect("Invalid socket address")[0],
-                )
-                .with_http(ServerBuilder::default());
+                .with_http_address(http_address)
+                .with_http(ws_http_server_builder())
+                .with_cors(trin_config.web3_http_cors_domains.clone())
+                .with_jwt_secret(jwt_secret)
+                .with_rate_limits(RateLimits {
+                    local: trin_config.web3_rate_limit_local,
+                    network: trin_config.web3_rate_limit_network,
+                });
             let rpc_server_config = match trin_config.ws {
                 true => rpc_server_config
                     .with_ws_address(SocketAddr::V4(SocketAddrV4::new(
                         Ipv4Addr::UNSPECIFIED,
                         trin_config.ws_port,
                     )))
-                    .with_ws(ServerBuilder::default()),
+                    .with_ws(ws_http_server_builder()),
                 false => rpc_server_config,
             };
-            rpc_server_config.start(transport_modules).await?
+            rpc_server_config
+                .start(transport_modules)
+                .await?
+                .with_shutdown_signal(shutdown)
         }
     };
 
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use ethereum_types::U64;
+use tokio::sync::mpsc;
+
+use ethportal_api::{
+    types::jsonrpc::{
+        endpoints::{BeaconEndpoint, HistoryEndpoint, StateEndpoint},
+        request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+    },
+    NetApiServer, RoutingTableInfo,
+};
+use trin_validation::constants::CHAIN_ID;
+
+use crate::{
+    fetch::{
+        proxy_query_to_beacon_subnet, proxy_query_to_history_subnet, proxy_query_to_state_subnet,
+    },
+    jsonrpsee::core::{async_trait, RpcResult},
+    serde::from_value,
+};
+
+pub struct NetApi {
+    history_network: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+    state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    beacon_network: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+}
+
+impl NetApi {
+    pub fn new(
+        history_network: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+        state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+        beacon_network: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+    ) -> Self {
+        Self {
+            history_network,
+            state_network,
+            beacon_network,
+        }
+    }
+}
+
+#[async_trait]
+impl NetApiServer for NetApi {
+    async fn version(&self) -> RpcResult<String> {
+        Ok(CHAIN_ID.to_string())
+    }
+
+    async fn peer_count(&self) -> RpcResult<U64> {
+        let mut routing_tables: Vec<RoutingTableInfo> = vec![];
+        if let Some(network) = &self.history_network {
+            let result =
+                proxy_query_to_history_subnet(network, HistoryEndpoint::RoutingTableInfo).await?;
+            routing_tables.push(from_value(result)?);
+        }
+        if let Some(network) = &self.state_network {
+            let result =
+                proxy_query_to_state_subnet(network, StateEndpoint::RoutingTableInfo).await?;
+            routing_tables.push(from_value(result)?);
+        }
+        if let Some(network) = &self.beacon_network {
+            let result =
+                proxy_query_to_beacon_subnet(network, BeaconEndpoint::RoutingTableInfo).await?;
+            routing_tables.push(from_value(result)?);
+        }
+
+        // A peer that takes part in several networks is only counted once.
+        let peers: HashSet<&String> = routing_tables
+            .iter()
+            .flat_map(|table| table.buckets.buckets.iter())
+            .flat_map(|bucket| bucket.node_ids.iter())
+            .collect();
+        Ok(U64::from(peers.len()))
+    }
+}
+
+impl std::fmt::Debug for NetApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetApi").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http_body::Limited;
+use hyper::{body::HttpBody, header::CONTENT_TYPE, Body, Request, Response, StatusCode};
+use serde_json::Value;
+use tower::{Layer, Service};
+
+/// Largest request that is searched for notifications. Larger requests are passed on as they are,
+/// rather than buffering them, and requests made of notifications are much smaller anyway.
+const MAX_INSPECTED_REQUEST_SIZE: u64 = 1024 * 1024;
+
+/// A layer that executes json-rpc notifications sent over http, and drops their responses.
+///
+/// Per JSON-RPC 2.0, requests without an `id` member are notifications, which the server must
+/// execute without responding to. The json-rpc server ignores them, so this layer gives them ids
+/// that the server will answer to, and then strips those answers from the response.
+///
+/// Only json requests of a known, small size are searched for notifications.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NotificationLayer;
+
+impl<S> Layer<S> for NotificationLayer {
+    type Service = NotificationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NotificationService { inner }
+    }
+}
+
+/// The service created by [NotificationLayer].
+#[derive(Debug, Clone)]
+pub struct NotificationService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for NotificationService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // The inner service was driven to readiness, so it's the one that must handle the call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let is_json = request
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .is_some_and(|content_type| content_type.starts_with("application/json"));
+            let is_small = request
+                .body()
+                .size_hint()
+                .exact()
+                .is_some_and(|size| size <= MAX_INSPECTED_REQUEST_SIZE);
+            if !is_json || !is_small {
+                return inner.call(request).await;
+            }
+
+            let (parts, body) = request.into_parts();
+            let body = Limited::new(body, MAX_INSPECTED_REQUEST_SIZE as usize);
+            let body = match hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(err) => {
+                    let mut response = Response::new(Body::from(err.to_string()));
+                    *response.status_mut() = StatusCode::BAD_REQUEST;
+                    return Ok(response);
+                }
+            };
+            let mut json_request = match serde_json::from_slice::<Value>(&body) {
+                Ok(json_request) => json_request,
+                Err(_) => {
+                    return inner
+                        .call(Request::from_parts(parts, Body::from(body)))
+                        .await
+                }
+            };
+            let notification_ids = assign_notification_ids(&mut json_request);
+            if notification_ids.is_empty() {
+                return inner
+                    .call(Request::from_parts(parts, Body::from(body)))
+                    .await;
+            }
+
+            let body = Body::from(json_request.to_string());
+            let response = inner.call(Request::from_parts(parts, body)).await?;
+            let (mut parts, body) = response.into_parts();
+            let body = match hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(err) => {
+                    let mut response = Response::new(Body::from(err.to_string()));
+                    *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                    return Ok(response);
+                }
+            };
+            let body = match serde_json::from_slice::<Value>(&body) {
+                Ok(json_response) => match strip_responses(json_response, &notification_ids) {
+                    Some(json_response) => Body::from(json_response.to_string()),
+                    None => {
+                        parts.headers.remove(hyper::header::CONTENT_TYPE);
+                        Body::empty()
+                    }
+                },
+                Err(_) => Body::from(body),
+            };
+            parts.headers.remove(hyper::header::CONTENT_LENGTH);
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// Gives an id to every notification in a single or batch request, and returns those ids.
+///
+/// The ids are distinct from the ids of the other requests, so that their responses can be told
+/// apart.
+fn assign_notification_ids(json_request: &mut Value) -> HashSet<String> {
+    let calls: Vec<&mut Value> = match json_request {
+        Value::Array(calls) => calls.iter_mut().collect(),
+        call => vec![call],
+    };
+    let taken_ids: HashSet<String> = calls
+        .iter()
+        .filter_map(|call| call.get("id")?.as_str().map(String::from))
+        .collect();
+
+    let mut notification_ids = HashSet::new();
+    let mut next_id = 0;
+    for call in calls {
+        let Value::Object(call) = call else {
+            continue;
+        };
+        if call.contains_key("id") {
+            continue;
+        }
+        let id = loop {
+            let id = format!("notification-{next_id}");
+            next_id += 1;
+            if !taken_ids.contains(&id) {
+                break id;
+            }
+        };
+        call.insert("id".to_string(), Value::String(id.clone()));
+        notification_ids.insert(id);
+    }
+    notification_ids
+}
+
+/// Removes the responses to notifications, and returns `None` if nothing is left to respond with.
+fn strip_responses(json_response: Value, notification_ids: &HashSet<String>) -> Option<Value> {
+    let is_notification = |response: &Value| {
+        response
+            .get("id")
+            .and_then(Value::as_str)
+            .map(|id| notification_ids.contains(id))
+            .unwrap_or(false)
+    };
+    match json_response {
+        Value::Array(responses) => {
+            let responses: Vec<Value> = responses
+                .into_iter()
+                .filter(|response| !is_notification(response))
+                .collect();
+            // An empty batch response must not be sent, per JSON-RPC 2.0.
+            match responses.is_empty() {
+                true => None,
+                false => Some(Value::Array(responses)),
+            }
+        }
+        response if is_notification(&response) => None,
+        response => Some(response),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use hyper::header::HeaderValue;
+    use serde_json::json;
+    use tower::{service_fn, ServiceExt};
+
+    /// Answers every call with its own method as result, like the json-rpc server would.
+    async fn echo(request: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        let body = hyper::body::to_bytes(request.into_body()).await?;
+        let respond =
+            |call: &Value| json!({"jsonrpc": "2.0", "result": call["method"], "id": call["id"]});
+        let response = match serde_json::from_slice::<Value>(&body).unwrap() {
+            Value::Array(calls) => Value::Array(calls.iter().map(respond).collect()),
+            call => respond(&call),
+        };
+        Ok(Response::new(Body::from(response.to_string())))
+    }
+
+    async fn send(json_request: Value) -> Vec<u8> {
+        let mut request = Request::new(Body::from(json_request.to_string()));
+        request
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        send_request(request).await
+    }
+
+    async fn send_request(request: Request<Body>) -> Vec<u8> {
+        let service = NotificationLayer.layer(service_fn(echo));
+        let response = service.oneshot(request).await.unwrap();
+        hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    #[tokio::test]
+    async fn single_notification_gets_no_response() {
+        let response = send(json!({"jsonrpc": "2.0", "method": "portal_historyStore"})).await;
+        assert!(response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_are_answered_in_batch_with_notifications() {
+        let response = send(json!([
+            {"jsonrpc": "2.0", "method": "portal_historyStore"},
+            {"jsonrpc": "2.0", "method": "eth_chainId", "id": "notification-0"},
+            {"jsonrpc": "2.0", "method": "web3_clientVersion", "id": null},
+        ]))
+        .await;
+        let response: Value = serde_json::from_slice(&response).unwrap();
+        assert_eq!(
+            response,
+            json!([
+                {"jsonrpc": "2.0", "result": "eth_chainId", "id": "notification-0"},
+                {"jsonrpc": "2.0", "result": "web3_clientVersion", "id": null},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_of_notifications_gets_no_response() {
+        let response = send(json!([
+            {"jsonrpc": "2.0", "method": "portal_historyStore"},
+            {"jsonrpc": "2.0", "method": "portal_historyStore"},
+        ]))
+        .await;
+        assert!(response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_are_unchanged() {
+        let request = json!({"jsonrpc": "2.0", "method": "eth_chainId", "id": 1});
+        let response: Value = serde_json::from_slice(&send(request).await).unwrap();
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": "eth_chainId", "id": 1})
+        );
+    }
+
+    #[tokio::test]
+    async fn only_json_requests_are_inspected() {
+        let request = json!({"jsonrpc": "2.0", "method": "portal_historyStore"});
+        let request = Request::new(Body::from(request.to_string()));
+        let response: Value = serde_json::from_slice(&send_request(request).await).unwrap();
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": "portal_historyStore", "id": null})
+        );
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use http_body::Limited;
+use hyper::{
+    client::HttpConnector,
+    header::{HeaderMap, HeaderValue, CONNECTION, CONTENT_TYPE, HOST, UPGRADE},
+    server::conn::Http,
+    service::service_fn,
+    upgrade::Upgraded,
+    Body, Client, Request, Response, StatusCode,
+};
+use serde_json::{json, Value};
+use soketto::{
+    connection::{Receiver, Sender},
+    handshake::{
+        self,
+        client::Header,
+        http::{self, is_upgrade_request},
+        ServerResponse,
+    },
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+use tracing::debug;
+
+use crate::{jsonrpsee::server::ServerHandle, MAX_REQUEST_BODY_SIZE};
+
+/// The code of errors returned when a client exceeds its budget, as used by other clients.
+pub const LIMIT_EXCEEDED_CODE: i32 = -32005;
+
+/// Number of buckets after which idle buckets are dropped, to bound memory use.
+const MAX_IDLE_BUCKETS: usize = 1024;
+
+/// The classes of methods that are budgeted separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MethodClass {
+    /// Methods answered from local state, such as the routing table or the local store.
+    Local,
+    /// Methods that send requests to other nodes of the portal networks.
+    Network,
+}
+
+impl MethodClass {
+    pub fn of(method: &str) -> Self {
+        const NETWORK_METHODS: [&str; 7] = [
+            "Ping",
+            "FindNodes",
+            "FindContent",
+            "LookupEnr",
+            "Gossip",
+            "Offer",
+            "talkReq",
+        ];
+        // Most `eth_` methods are answered with content looked up on the network.
+        if method.starts_with("eth_") && method != "eth_chainId" {
+            return MethodClass::Network;
+        }
+        match NETWORK_METHODS.iter().any(|name| method.contains(name)) {
+            true => MethodClass::Network,
+            false => MethodClass::Local,
+        }
+    }
+}
+
+/// The budget of every client, in requests per second, for each method class.
+///
+/// Clients may burst up to one second worth of requests. Classes without a budget are unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub local: Option<u32>,
+    pub network: Option<u32>,
+}
+
+impl RateLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.local.is_none() && self.network.is_none()
+    }
+
+    fn budget(&self, class: MethodClass) -> Option<u32> {
+        match class {
+            MethodClass::Local => self.local,
+            MethodClass::Network => self.network,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(capacity: u32) -> Self {
+        Self {
+            tokens: capacity as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, rate: u32) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+        self.last_refill = now;
+    }
+}
+
+/// Token buckets of every client, keyed by the IP address of the client so that reconnecting
+/// doesn't refill them.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Arc<Mutex<HashMap<(IpAddr, MethodClass), TokenBucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Charges every method in `methods` to the client, and returns false if any of them is over
+    /// budget. Nothing is charged for rejected requests.
+    fn try_acquire(&self, client: IpAddr, methods: &[String]) -> bool {
+        let mut costs: HashMap<MethodClass, u32> = HashMap::new();
+        for method in methods {
+            *costs.entry(MethodClass::of(method)).or_default() += 1;
+        }
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            let limits = self.limits;
+            buckets.retain(|(_, class), bucket| match limits.budget(*class) {
+                Some(rate) => {
+                    bucket.refill(rate);
+                    bucket.tokens < rate as f64
+                }
+                None => false,
+            });
+        }
+
+        let mut charges = vec![];
+        for (class, cost) in costs {
+            let Some(rate) = self.limits.budget(class) else {
+                continue;
+            };
+            let bucket = buckets
+                .entry((client, class))
+                .or_insert_with(|| TokenBucket::full(rate));
+            bucket.refill(rate);
+            if bucket.tokens < cost as f64 {
+                return false;
+            }
+            charges.push((class, cost));
+        }
+        for (class, cost) in charges {
+            if let Some(bucket) = buckets.get_mut(&(client, class)) {
+                bucket.tokens -= cost as f64;
+            }
+        }
+        true
+    }
+
+    /// Charges the calls of a json-rpc request or batch to the client. Returns the response to
+    /// send instead of executing the request if the client is over budget.
+    fn check(&self, client: IpAddr, request: &[u8]) -> Option<Value> {
+        let request: Option<Value> = serde_json::from_slice(request).ok();
+        let calls = match &request {
+            Some(Value::Array(calls)) => calls.iter().collect(),
+            Some(call) => vec![call],
+            None => vec![],
+        };
+        let methods: Vec<String> = calls
+            .iter()
+            .filter_map(|call| call.get("method")?.as_str().map(String::from))
+            .collect();
+        if self.try_acquire(client, &methods) {
+            return None;
+        }
+
+        let errors: Vec<Value> = calls
+            .iter()
+            .map(|call| limit_exceeded(call.get("id").cloned().unwrap_or(Value::Null)))
+            .collect();
+        match request {
+            Some(Value::Array(_)) => Some(Value::Array(errors)),
+            _ => Some(errors.into_iter().next().unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// A proxy in front of a json-rpc server that rejects the requests of clients over their budget,
+/// with a [LIMIT_EXCEEDED_CODE] error.
+///
+/// The json-rpc server doesn't expose the address of a client to its middleware, so the proxy
+/// accepts the connections instead, and forwards them to the server. Every request sent over
+/// http is charged, as well as every message sent over a websocket connection.
+///
+/// The json-rpc server itself listens on a random localhost port, which local processes could
+/// connect to directly, to bypass the limits. Connections over ipc aren't limited either.
+pub struct RateLimitProxy {
+    listener: TcpListener,
+    limiter: RateLimiter,
+}
+
+impl RateLimitProxy {
+    pub fn new(listener: TcpListener, limits: RateLimits) -> Self {
+        Self {
+            listener,
+            limiter: RateLimiter::new(limits),
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Forwards every accepted connection to the json-rpc server at `upstream`, until `server`
+    /// is stopped.
+    pub async fn serve(self, upstream: SocketAddr, server: ServerHandle) {
+        let proxy = Proxy {
+            upstream,
+            limiter: self.limiter,
+            http_client: Client::new(),
+        };
+        let stopped = server.stopped();
+        tokio::pin!(stopped);
+        loop {
+            let (socket, remote_addr) = tokio::select! {
+                _ = &mut stopped => break,
+                accepted = self.listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        debug!(error = %err, "Error accepting json-rpc connection");
+                        continue;
+                    }
+                },
+            };
+            let proxy = proxy.clone();
+            let service =
+                service_fn(move |request| proxy.clone().forward(request, remote_addr.ip()));
+            tokio::spawn(async move {
+                if let Err(err) = Http::new()
+                    .serve_connection(socket, service)
+                    .with_upgrades()
+                    .await
+                {
+                    debug!(error = %err, "Error serving json-rpc connection");
+                }
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Proxy {
+    upstream: SocketAddr,
+    limiter: RateLimiter,
+    http_client: Client<HttpConnector>,
+}
+
+impl Proxy {
+    async fn forward(
+        self,
+        request: Request<Body>,
+        client: IpAddr,
+    ) -> Result<Response<Body>, hyper::Error> {
+        if is_upgrade_request(&request) {
+            return Ok(self.forward_websocket(request, client).await);
+        }
+
+        let (mut parts, body) = request.into_parts();
+        // The body is buffered to be charged, so it's held to the size the server accepts.
+        let body =
+            match hyper::body::to_bytes(Limited::new(body, MAX_REQUEST_BODY_SIZE as usize)).await {
+                Ok(body) => body,
+                Err(err) => match err.downcast::<hyper::Error>() {
+                    Ok(err) => return Err(*err),
+                    Err(_) => return Ok(status_response(StatusCode::PAYLOAD_TOO_LARGE)),
+                },
+            };
+        if let Some(response) = self.limiter.check(client, &body) {
+            let mut response = Response::new(Body::from(response.to_string()));
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            return Ok(response);
+        }
+        let path = parts.uri.path_and_query().map_or("/", |path| path.as_str());
+        parts.uri = match format!("http://{}{path}", self.upstream).parse() {
+            Ok(uri) => uri,
+            Err(_) => return Ok(status_response(StatusCode::BAD_REQUEST)),
+        };
+        self.http_client
+            .request(Request::from_parts(parts, Body::from(body)))
+            .await
+    }
+
+    /// Opens a websocket connection to the json-rpc server on behalf of the client, then
+    /// completes the handshake with the client and relays the messages of the two connections.
+    async fn forward_websocket(self, request: Request<Body>, client: IpAddr) -> Response<Body> {
+        let mut client_handshake = http::Server::new();
+        let response = match client_handshake.receive_request(&request) {
+            Ok(response) => response,
+            Err(_) => return status_response(StatusCode::BAD_REQUEST),
+        };
+        let resource = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path| path.as_str())
+            .to_string();
+        let upstream = match self
+            .connect_websocket(&resource, request.headers().clone())
+            .await
+        {
+            Ok(upstream) => upstream,
+            Err(response) => return response,
+        };
+
+        let limiter = self.limiter;
+        tokio::spawn(async move {
+            let upgraded = match hyper::upgrade::on(request).await {
+                Ok(upgraded) => upgraded,
+                Err(err) => {
+                    debug!(error = %err, "Unable to upgrade json-rpc connection");
+                    return;
+                }
+            };
+            let mut downstream = client_handshake.into_builder(upgraded.compat());
+            downstream.set_max_message_size(MAX_REQUEST_BODY_SIZE as usize);
+            let downstream = downstream.finish();
+            relay_messages(client, limiter, downstream, upstream).await;
+        });
+        response.map(|()| Body::empty())
+    }
+
+    /// Opens a websocket connection to the json-rpc server, passing on the headers of the
+    /// client, so that the server authenticates the client as usual. Returns the response to
+    /// send to the client if the server refuses the connection.
+    async fn connect_websocket(
+        &self,
+        resource: &str,
+        headers: HeaderMap,
+    ) -> Result<WebSocket<TcpStream>, Response<Body>> {
+        let socket = TcpStream::connect(self.upstream)
+            .await
+            .map_err(|_| status_response(StatusCode::BAD_GATEWAY))?;
+        let host = self.upstream.to_string();
+        let headers: Vec<Header> = headers
+            .iter()
+            .filter(|(name, _)| {
+                *name != HOST
+                    && *name != CONNECTION
+                    && *name != UPGRADE
+                    && !name.as_str().starts_with("sec-websocket")
+            })
+            .map(|(name, value)| Header {
+                name: name.as_str(),
+                value: value.as_bytes(),
+            })
+            .collect();
+
+        let mut upstream = handshake::Client::new(socket.compat(), &host, resource);
+        upstream.set_headers(&headers);
+        match upstream.handshake().await {
+            Ok(ServerResponse::Accepted { .. }) => Ok(upstream.into_builder().finish()),
+            Ok(ServerResponse::Rejected { status_code }) => Err(status_response(
+                StatusCode::from_u16(status_code).unwrap_or(StatusCode::BAD_GATEWAY),
+            )),
+            Ok(ServerResponse::Redirect { .. }) | Err(_) => {
+                Err(status_response(StatusCode::BAD_GATEWAY))
+            }
+        }
+    }
+}
+
+/// The halves of a websocket connection over `T`.
+type WebSocket<T> = (Sender<Compat<T>>, Receiver<Compat<T>>);
+
+/// Relays the messages of a client to the json-rpc server, unless the client is over budget, and
+/// the messages of the server back to the client, until either side closes its connection.
+async fn relay_messages(
+    client: IpAddr,
+    limiter: RateLimiter,
+    (mut client_tx, mut client_rx): WebSocket<Upgraded>,
+    (mut upstream_tx, mut upstream_rx): WebSocket<TcpStream>,
+) {
+    // Both directions send messages to the client, so they go through a single writer.
+    let (to_client, mut client_messages) = mpsc::unbounded_channel::<String>();
+    let write_client = async {
+        while let Some(message) = client_messages.recv().await {
+            if client_tx.send_text_owned(message).await.is_err() || client_tx.flush().await.is_err()
+            {
+                break;
+            }
+        }
+    };
+    let read_client = {
+        let to_client = to_client.clone();
+        async move {
+            let mut message = vec![];
+            loop {
+                message.clear();
+                if client_rx.receive_data(&mut message).await.is_err() {
+                    break;
+                }
+                if let Some(response) = limiter.check(client, &message) {
+                    let _ = to_client.send(response.to_string());
+                    continue;
+                }
+                let Ok(message) = std::str::from_utf8(&message) else {
+                    break;
+                };
+                if upstream_tx.send_text(message).await.is_err()
+                    || upstream_tx.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+            let _ = upstream_tx.close().await;
+        }
+    };
+    let read_upstream = async move {
+        let mut message = vec![];
+        loop {
+            message.clear();
+            if upstream_rx.receive_data(&mut message).await.is_err() {
+                break;
+            }
+            let Ok(message) = String::from_utf8(std::mem::take(&mut message)) else {
+                break;
+            };
+            if to_client.send(message).is_err() {
+                break;
+            }
+        }
+    };
+    tokio::select! {
+        _ = read_client => {}
+        _ = read_upstream => {}
+        _ = write_client => {}
+    }
+    let _ = client_tx.close().await;
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn limit_exceeded(id: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": LIMIT_EXCEEDED_CODE,
+            "message": "limit exceeded",
+        },
+        "id": id,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    fn methods(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn classify_methods() {
+        assert_eq!(MethodClass::of("eth_chainId"), MethodClass::Local);
+        assert_eq!(MethodClass::of("eth_getBalance"), MethodClass::Network);
+        assert_eq!(
+            MethodClass::of("portal_historyRecursiveFindContent"),
+            MethodClass::Network
+        );
+        assert_eq!(MethodClass::of("portal_historyPing"), MethodClass::Network);
+        assert_eq!(
+            MethodClass::of("portal_historyLocalContent"),
+            MethodClass::Local
+        );
+        assert_eq!(MethodClass::of("discv5_nodeInfo"), MethodClass::Local);
+        assert_eq!(MethodClass::of("discv5_talkReq"), MethodClass::Network);
+    }
+
+    #[test]
+    fn budgets_are_per_class_and_client() {
+        let limiter = RateLimiter::new(RateLimits {
+            local: None,
+            network: Some(2),
+        });
+        let network = methods(&["portal_historyPing"]);
+
+        assert!(limiter.try_acquire(CLIENT, &network));
+        assert!(limiter.try_acquire(CLIENT, &network));
+        assert!(!limiter.try_acquire(CLIENT, &network));
+        // Unlimited classes and other clients are unaffected.
+        assert!(limiter.try_acquire(CLIENT, &methods(&["web3_clientVersion"])));
+        assert!(limiter.try_acquire([10, 0, 0, 1].into(), &network));
+    }
+
+    #[test]
+    fn rejected_batches_are_not_charged() {
+        let limiter = RateLimiter::new(RateLimits {
+            local: Some(2),
+            network: Some(2),
+        });
+        assert!(!limiter.try_acquire(
+            CLIENT,
+            &methods(&["eth_chainId", "eth_getCode", "eth_getCode", "eth_getCode"])
+        ));
+        assert!(limiter.try_acquire(CLIENT, &methods(&["eth_chainId", "eth_chainId"])));
+    }
+
+    #[tokio::test]
+    async fn oversized_requests_are_rejected() {
+        // Nothing listens upstream, so the request must be rejected before it's forwarded.
+        let proxy = Proxy {
+            upstream: SocketAddr::new(CLIENT, 0),
+            limiter: RateLimiter::new(RateLimits::default()),
+            http_client: Client::new(),
+        };
+        let body = vec![b' '; MAX_REQUEST_BODY_SIZE as usize + 1];
+        let response = proxy
+            .forward(Request::new(Body::from(body)), CLIENT)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn limit_exceeded_response() {
+        let limiter = RateLimiter::new(RateLimits {
+            local: Some(1),
+            network: None,
+        });
+        let request = br#"{"jsonrpc":"2.0","method":"web3_clientVersion","params":[],"id":"a"}"#;
+        assert_eq!(limiter.check(CLIENT, request), None);
+        assert_eq!(
+            limiter.check(CLIENT, request),
+            Some(limit_exceeded(json!("a")))
+        );
+
+        let batch = br#"[{"jsonrpc":"2.0","method":"web3_clientVersion","params":[],"id":1},{"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":2}]"#;
+        assert_eq!(
+            limiter.check(CLIENT, batch),
+            Some(json!([limit_exceeded(json!(1)), limit_exceeded(json!(2))]))
+        );
+    }
+}
@@ -1,13 +1,18 @@
 use crate::{
     builder::TransportRpcModules,
+    correlation::CorrelationLayer,
     cors,
     errors::WsHttpSamePortError,
+    ipc::{prepare_socket_path, PrivateSocketDir},
     jsonrpsee::{
         http_client::{HttpClient, HttpClientBuilder},
         server::{IdProvider, Server, ServerBuilder, ServerHandle},
         ws_client::{WsClient, WsClientBuilder},
         RpcModule,
     },
+    jwt::{AuthLayer, JwtSecret},
+    notification::NotificationLayer,
+    rate_limit::{RateLimitProxy, RateLimits},
     RpcError, TransportRpcModuleConfig,
 };
 use ethportal_api::types::cli::{
@@ -15,10 +20,16 @@ use ethportal_api::types::cli::{
 };
 use reth_ipc::server::{Builder as IpcServerBuilder, Endpoint, IpcServer};
 use std::{
-    fmt,
+    fmt, io,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::Path,
+    sync::Arc,
+};
+use tokio::{net::TcpListener, sync::Notify};
+use tower::{
+    layer::util::{Identity, Stack},
+    util::Either,
 };
-use tower::layer::util::{Identity, Stack};
 use tower_http::cors::CorsLayer;
 use tracing::instrument;
 
@@ -28,6 +39,9 @@ pub struct RpcServer {
     pub ws_http: WsHttpServer,
     /// ipc server
     pub ipc: Option<IpcServer>,
+    /// Private directory the ipc socket is bound in, and the permissions it's given before it's
+    /// moved to its path
+    ipc_socket_dir: Option<(PrivateSocketDir, u32)>,
 }
 
 impl RpcServer {
@@ -35,6 +49,7 @@ impl RpcServer {
         RpcServer {
             ws_http: Default::default(),
             ipc: None,
+            ipc_socket_dir: None,
         }
     }
 
@@ -65,6 +80,7 @@ impl RpcServer {
         let Self {
             ws_http,
             ipc: ipc_server,
+            ipc_socket_dir,
         } = self;
         let TransportRpcModules {
             config,
@@ -78,6 +94,7 @@ impl RpcServer {
             http: None,
             ws: None,
             ipc: None,
+            shutdown: Default::default(),
         };
 
         let (http, ws) = ws_http.server.start(http, ws, &config).await?;
@@ -87,7 +104,11 @@ impl RpcServer {
         if let Some((server, module)) =
             ipc_server.and_then(|server| ipc.map(|module| (server, module)))
         {
+            // The socket is bound by the time the server has started.
             handle.ipc = Some(server.start(module).await?);
+            if let Some((dir, mode)) = ipc_socket_dir {
+                dir.publish(mode)?;
+            }
         }
 
         Ok(handle)
@@ -116,6 +137,8 @@ pub struct RpcServerHandle {
     http: Option<ServerHandle>,
     ws: Option<ServerHandle>,
     ipc: Option<ServerHandle>,
+    /// Notified when a client requests the node to shut down
+    shutdown: Arc<Notify>,
 }
 
 impl RpcServerHandle {
@@ -129,6 +152,17 @@ impl RpcServerHandle {
         self.ws_local_addr
     }
 
+    /// Sets the signal that is notified by `admin_shutdown`.
+    pub(crate) fn with_shutdown_signal(mut self, shutdown: Arc<Notify>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Waits until a client requests the node to shut down, via `admin_shutdown`.
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.notified().await
+    }
+
     /// Tell the server to stop without waiting for the server to stop.
     pub fn stop(self) -> Result<(), RpcError> {
         if let Some(handle) = self.http {
@@ -146,6 +180,13 @@ impl RpcServerHandle {
         Ok(())
     }
 
+    /// Waits until the servers stopped, once they were told to stop.
+    pub async fn stopped(self) {
+        for handle in [self.http, self.ws, self.ipc].into_iter().flatten() {
+            handle.stopped().await
+        }
+    }
+
     /// Returns the url to the http server
     pub fn http_url(&self) -> Option<String> {
         self.http_local_addr.map(|addr| format!("http://{addr}"))
@@ -235,10 +276,16 @@ pub struct RpcServerConfig {
     ws_cors_domains: Option<String>,
     /// Address where to bind the ws server to
     ws_addr: Option<SocketAddr>,
+    /// Secret used to validate bearer tokens on http and ws requests
+    jwt_secret: Option<JwtSecret>,
+    /// Budgets of every client of the http and ws servers
+    rate_limits: RateLimits,
     /// Configs for JSON-RPC IPC server
     ipc_server_config: Option<IpcServerBuilder>,
     /// The Endpoint where to launch the ipc server
     ipc_endpoint: Option<Endpoint>,
+    /// Permissions of the ipc socket
+    ipc_mode: Option<u32>,
 }
 
 impl RpcServerConfig {
@@ -281,6 +328,18 @@ impl RpcServerConfig {
         self
     }
 
+    /// Requires a bearer token signed with `secret` on every http _and_ ws request
+    pub fn with_jwt_secret(mut self, secret: Option<JwtSecret>) -> Self {
+        self.jwt_secret = secret;
+        self
+    }
+
+    /// Limits the rate of requests of every client of the http _and_ ws servers
+    pub fn with_rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
     /// Configures the ws server
     pub fn with_ws(mut self, config: ServerBuilder) -> Self {
         self.ws_server_config = Some(config);
@@ -337,6 +396,14 @@ impl RpcServerConfig {
         self
     }
 
+    /// Configures the permissions of the ipc socket, e.g. `0o600` to only let the owner connect
+    ///
+    /// Default is to leave them to the process umask
+    pub fn with_ipc_mode(mut self, mode: Option<u32>) -> Self {
+        self.ipc_mode = mode;
+        self
+    }
+
     /// Returns true if any server is configured.
     ///
     /// If no server is configured, no server will be be launched on [RpcServerConfig::start].
@@ -413,6 +480,8 @@ impl RpcServerConfig {
                 builder,
                 http_socket_addr,
                 cors,
+                self.jwt_secret.clone(),
+                self.rate_limits,
                 ServerKind::WsHttp(http_socket_addr),
             )
             .await?;
@@ -441,6 +510,8 @@ impl RpcServerConfig {
                 builder,
                 ws_socket_addr,
                 self.ws_cors_domains.take(),
+                self.jwt_secret.clone(),
+                self.rate_limits,
                 ServerKind::WS(ws_socket_addr),
             )
             .await?;
@@ -454,6 +525,8 @@ impl RpcServerConfig {
                 builder,
                 http_socket_addr,
                 self.http_cors_domains.take(),
+                self.jwt_secret.clone(),
+                self.rate_limits,
                 ServerKind::Http(http_socket_addr),
             )
             .await?;
@@ -484,7 +557,17 @@ impl RpcServerConfig {
             let ipc_path = self
                 .ipc_endpoint
                 .unwrap_or_else(|| Endpoint::new(DEFAULT_WEB3_IPC_PATH.to_string()));
-            let ipc = builder.build(ipc_path.path())?;
+            let path = Path::new(ipc_path.path());
+            prepare_socket_path(path)?;
+            let ipc = match self.ipc_mode {
+                Some(mode) => {
+                    let dir = PrivateSocketDir::new(path)?;
+                    let ipc = builder.build(&dir.socket_path().to_string_lossy())?;
+                    server.ipc_socket_dir = Some((dir, mode));
+                    ipc
+                }
+                None => builder.build(ipc_path.path())?,
+            };
             server.ipc = Some(ipc);
         }
 
@@ -563,21 +646,35 @@ impl Default for WsHttpServers {
     }
 }
 
-/// Http Servers Enum
-pub enum WsHttpServerKind {
-    /// Http server
-    Plain(Server),
-    /// Http server with cors
-    WithCors(Server<Stack<CorsLayer, Identity>>),
+/// Middleware of the http and ws servers.
+///
+/// Cors and jwt authentication are only enabled when configured. Correlation ids are assigned
+/// first, so that every response carries one. Cors comes next, so that preflight requests are
+/// answered without a token.
+pub type WsHttpMiddleware = Stack<
+    NotificationLayer,
+    Stack<
+        Either<AuthLayer, Identity>,
+        Stack<Either<CorsLayer, Identity>, Stack<CorrelationLayer, Identity>>,
+    >,
+>;
+
+/// Http Server, serving http, ws or both, behind a rate limiting proxy if rate limits are
+/// configured.
+pub struct WsHttpServerKind {
+    server: Server<WsHttpMiddleware>,
+    rate_limit: Option<RateLimitProxy>,
 }
 
 impl WsHttpServerKind {
     /// Starts the server and returns the handle
     async fn start(self, module: RpcModule<()>) -> Result<ServerHandle, RpcError> {
-        match self {
-            WsHttpServerKind::Plain(server) => Ok(server.start(module)),
-            WsHttpServerKind::WithCors(server) => Ok(server.start(module)),
+        let server_addr = self.server.local_addr()?;
+        let handle = self.server.start(module);
+        if let Some(proxy) = self.rate_limit {
+            tokio::spawn(proxy.serve(server_addr, handle.clone()));
         }
+        Ok(handle)
     }
 
     /// Builds
@@ -585,28 +682,52 @@ impl WsHttpServerKind {
         builder: ServerBuilder,
         socket_addr: SocketAddr,
         cors_domains: Option<String>,
+        jwt_secret: Option<JwtSecret>,
+        rate_limits: RateLimits,
         server_kind: ServerKind,
     ) -> Result<(Self, SocketAddr), RpcError> {
-        if let Some(cors) = cors_domains.as_deref().map(cors::create_cors_layer) {
-            let cors = cors.map_err(|err| RpcError::Custom(err.to_string()))?;
-            let middleware = tower::ServiceBuilder::new().layer(cors);
-            let server = builder
-                .set_middleware(middleware)
-                .build(socket_addr)
-                .await
-                .map_err(|err| RpcError::from_jsonrpsee_error(err, server_kind))?;
-            let local_addr = server.local_addr()?;
-            let server = WsHttpServerKind::WithCors(server);
-            Ok((server, local_addr))
-        } else {
-            let server = builder
-                .build(socket_addr)
-                .await
-                .map_err(|err| RpcError::from_jsonrpsee_error(err, server_kind))?;
-            let local_addr = server.local_addr()?;
-            let server = WsHttpServerKind::Plain(server);
-            Ok((server, local_addr))
-        }
+        let cors = cors_domains
+            .as_deref()
+            .map(cors::create_cors_layer)
+            .transpose()
+            .map_err(|err| RpcError::Custom(err.to_string()))?;
+        let auth = jwt_secret.map(AuthLayer::new);
+        // The proxy takes the address of the server, which then only listens on localhost.
+        let (server_addr, rate_limit) = match rate_limits.is_unlimited() {
+            true => (socket_addr, None),
+            false => {
+                let listener = TcpListener::bind(socket_addr)
+                    .await
+                    .map_err(|err| bind_error(err, server_kind))?;
+                let localhost = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+                (localhost, Some(RateLimitProxy::new(listener, rate_limits)))
+            }
+        };
+
+        let middleware = tower::ServiceBuilder::new()
+            .layer(CorrelationLayer::default())
+            .option_layer(cors)
+            .option_layer(auth)
+            .layer(NotificationLayer);
+        let server = builder
+            .set_middleware(middleware)
+            .build(server_addr)
+            .await
+            .map_err(|err| RpcError::from_jsonrpsee_error(err, server_kind))?;
+        let local_addr = match &rate_limit {
+            Some(proxy) => proxy
+                .local_addr()
+                .map_err(|err| RpcError::Custom(err.to_string()))?,
+            None => server.local_addr()?,
+        };
+        Ok((WsHttpServerKind { server, rate_limit }, local_addr))
+    }
+}
+
+fn bind_error(err: io::Error, kind: ServerKind) -> RpcError {
+    match err.kind() {
+        io::ErrorKind::AddrInUse => RpcError::AddressAlreadyInUse { kind, error: err },
+        _ => RpcError::Custom(err.to_string()),
     }
 }
 
@@ -614,7 +735,16 @@ impl WsHttpServerKind {
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
-    use crate::{builder::RpcModuleSelection, PortalRpcModule, RpcModuleBuilder};
+    use crate::{
+        builder::RpcModuleSelection,
+        jsonrpsee::{
+            core::{client::ClientT, params::BatchRequestBuilder, Error as JsonRpseeError},
+            rpc_params,
+        },
+        jwt::Claims,
+        rate_limit::LIMIT_EXCEEDED_CODE,
+        PortalRpcModule, RpcModuleBuilder,
+    };
     use portalnet::{discovery::Discovery, utils::db::setup_temp_dir};
     use std::{io, sync::Arc};
 
@@ -715,6 +845,162 @@ mod tests {
         assert!(is_addr_in_use_kind(&err, ServerKind::WS(addr)), "{err:?}");
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_batch_request() {
+        let handle = launch_http(vec![PortalRpcModule::Web3, PortalRpcModule::Eth]).await;
+        let client = handle.http_client().unwrap();
+
+        let mut batch = BatchRequestBuilder::new();
+        batch.insert("eth_chainId", rpc_params![]).unwrap();
+        batch.insert("web3_clientVersion", rpc_params![]).unwrap();
+        batch.insert("eth_chainId", rpc_params![]).unwrap();
+        let response = client
+            .batch_request::<serde_json::Value>(batch)
+            .await
+            .unwrap();
+
+        // Responses are returned in the same order as the batched requests.
+        assert_eq!(response.num_successful_calls(), 3);
+        let results: Vec<serde_json::Value> = response.into_ok().unwrap().collect();
+        assert_eq!(results[0], results[2]);
+        assert!(results[1].as_str().unwrap().starts_with("trin/v"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_jwt_auth() {
+        let secret = JwtSecret::from_hex(&format!("0x{}", "42".repeat(32))).unwrap();
+        let builder = test_rpc_builder();
+        let server = builder.build(TransportRpcModuleConfig::set_http(vec![
+            PortalRpcModule::Web3,
+        ]));
+        let handle = server
+            .start_server(
+                RpcServerConfig::http(Default::default())
+                    .with_http_address(test_address())
+                    .with_jwt_secret(Some(secret.clone())),
+            )
+            .await
+            .unwrap();
+
+        let client = handle.http_client().unwrap();
+        let result: Result<String, _> = client.request("web3_clientVersion", rpc_params![]).await;
+        assert!(result.is_err());
+
+        let iat = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let token = secret.encode(&Claims { iat, exp: None }).unwrap();
+        let mut headers = hyper::HeaderMap::new();
+        headers.insert(
+            hyper::header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        let client = HttpClientBuilder::default()
+            .set_headers(headers)
+            .build(handle.http_url().unwrap())
+            .unwrap();
+        let version: String = client
+            .request("web3_clientVersion", rpc_params![])
+            .await
+            .unwrap();
+        assert!(version.starts_with("trin/v"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ws_request() {
+        let handle = launch_ws(vec![PortalRpcModule::Web3]).await;
+        let client = handle.ws_client().await.unwrap();
+        let version: String = client
+            .request("web3_clientVersion", rpc_params![])
+            .await
+            .unwrap();
+        assert!(version.starts_with("trin/v"));
+        // Dropping the client closes the connection, the server must keep serving new ones.
+        drop(client);
+        let client = handle.ws_client().await.unwrap();
+        let version_again: String = client
+            .request("web3_clientVersion", rpc_params![])
+            .await
+            .unwrap();
+        assert_eq!(version, version_again);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rate_limit_survives_reconnects() {
+        let builder = test_rpc_builder();
+        let modules = vec![PortalRpcModule::Web3];
+        let server =
+            builder.build(TransportRpcModuleConfig::set_ws(modules.clone()).with_http(modules));
+        let addr = test_address();
+        let handle = server
+            .start_server(
+                RpcServerConfig::ws(Default::default())
+                    .with_ws_address(addr)
+                    .with_http(Default::default())
+                    .with_http_address(addr)
+                    .with_rate_limits(RateLimits {
+                        local: Some(1),
+                        network: None,
+                    }),
+            )
+            .await
+            .unwrap();
+        fn is_limit_exceeded(result: Result<String, JsonRpseeError>) -> bool {
+            matches!(result, Err(JsonRpseeError::Call(err)) if err.code() == LIMIT_EXCEEDED_CODE)
+        }
+
+        let client = handle.ws_client().await.unwrap();
+        let version: String = client
+            .request("web3_clientVersion", rpc_params![])
+            .await
+            .unwrap();
+        assert!(version.starts_with("trin/v"));
+        assert!(is_limit_exceeded(
+            client.request("web3_clientVersion", rpc_params![]).await
+        ));
+
+        // The budget is charged to the address of the client, not to its connection.
+        drop(client);
+        let client = handle.ws_client().await.unwrap();
+        assert!(is_limit_exceeded(
+            client.request("web3_clientVersion", rpc_params![]).await
+        ));
+        let client = handle.http_client().unwrap();
+        assert!(is_limit_exceeded(
+            client.request("web3_clientVersion", rpc_params![]).await
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_admin_shutdown() {
+        let builder = test_rpc_builder();
+        let shutdown = builder.shutdown_signal();
+        let server = builder.build(TransportRpcModuleConfig::set_http(vec![
+            PortalRpcModule::Admin,
+        ]));
+        let handle = server
+            .start_server(
+                RpcServerConfig::http(Default::default()).with_http_address(test_address()),
+            )
+            .await
+            .unwrap()
+            .with_shutdown_signal(shutdown);
+
+        let client = handle.http_client().unwrap();
+        let initiated: bool = client
+            .request("admin_shutdown", rpc_params![])
+            .await
+            .unwrap();
+        assert!(initiated);
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            handle.shutdown_requested(),
+        )
+        .await
+        .unwrap();
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_launch_same_port() {
         let handle = launch_http_ws_same_port(vec![PortalRpcModule::History]).await;
@@ -0,0 +1,137 @@
+use crate::{fetch::proxy_query_to_state_subnet, serde::from_value};
+
+use crate::jsonrpsee::core::{async_trait, RpcResult};
+use ethportal_api::{
+    types::{
+        constants::CONTENT_ABSENT,
+        enr::Enr,
+        jsonrpc::{endpoints::StateEndpoint, request::StateJsonRpcRequest},
+        portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo},
+        state::ContentInfo,
+    },
+    PossibleStateContentValue, RoutingTableInfo, StateContentKey, StateContentValue,
+    StateNetworkApiServer,
+};
+use tokio::sync::mpsc;
+
+pub struct StateNetworkApi {
+    network: mpsc::UnboundedSender<StateJsonRpcRequest>,
+}
+
+impl StateNetworkApi {
+    pub fn new(network: mpsc::UnboundedSender<StateJsonRpcRequest>) -> Self {
+        Self { network }
+    }
+}
+
+#[async_trait]
+impl StateNetworkApiServer for StateNetworkApi {
+    /// Returns meta information about overlay routing table.
+    async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo> {
+        let endpoint = StateEndpoint::RoutingTableInfo;
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: RoutingTableInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Returns the node data radius
+    async fn radius(&self) -> RpcResult<DataRadius> {
+        let endpoint = StateEndpoint::DataRadius;
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: DataRadius = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Send a PING message to the designated node and wait for a PONG response
+    async fn ping(&self, enr: Enr) -> RpcResult<PongInfo> {
+        let endpoint = StateEndpoint::Ping(enr);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: PongInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Send a FINDNODES request for nodes that fall within the given set of distances, to the
+    /// designated peer and wait for a response
+    async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo> {
+        let endpoint = StateEndpoint::FindNodes(enr, distances);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: FindNodesInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Send FINDCONTENT message to get the content with a content key.
+    async fn find_content(&self, enr: Enr, content_key: StateContentKey) -> RpcResult<ContentInfo> {
+        let endpoint = StateEndpoint::FindContent(enr, content_key);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: ContentInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Lookup a target content key in the network
+    async fn recursive_find_content(&self, content_key: StateContentKey) -> RpcResult<ContentInfo> {
+        let endpoint = StateEndpoint::RecursiveFindContent(content_key);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: ContentInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Send the provided content value to interested peers. Clients may choose to send to some or
+    /// all peers. Return the number of peers that the content was gossiped to.
+    async fn gossip(
+        &self,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> RpcResult<u32> {
+        let endpoint = StateEndpoint::Gossip(content_key, content_value);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: u32 = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Send an OFFER request with given ContentKey, to the designated peer and wait for a response.
+    /// Returns the content keys bitlist upon successful content transmission or empty bitlist
+    /// receive.
+    async fn offer(
+        &self,
+        enr: Enr,
+        content_key: StateContentKey,
+        content_value: Option<StateContentValue>,
+    ) -> RpcResult<AcceptInfo> {
+        let endpoint = StateEndpoint::Offer(enr, content_key, content_value);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: AcceptInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Store content key with a content data to the local database.
+    async fn store(
+        &self,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> RpcResult<bool> {
+        let endpoint = StateEndpoint::Store(content_key, content_value);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        let result: bool = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Get a content from the local database.
+    async fn local_content(
+        &self,
+        content_key: StateContentKey,
+    ) -> RpcResult<PossibleStateContentValue> {
+        let endpoint = StateEndpoint::LocalContent(content_key);
+        let result = proxy_query_to_state_subnet(&self.network, endpoint).await?;
+        if result == serde_json::Value::String(CONTENT_ABSENT.to_string()) {
+            return Ok(PossibleStateContentValue::ContentAbsent);
+        };
+        let content: StateContentValue = from_value(result)?;
+        Ok(PossibleStateContentValue::ContentPresent(content))
+    }
+}
+
+impl std::fmt::Debug for StateNetworkApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateNetworkApi").finish_non_exhaustive()
+    }
+}
@@ -1,6 +1,6 @@
 use crate::jsonrpsee::core::{async_trait, RpcResult};
 use ethportal_api::Web3ApiServer;
-use trin_utils::version::get_trin_version;
+use trin_utils::version::get_trin_client_version;
 
 pub struct Web3Api;
 
@@ -19,8 +19,7 @@ impl Default for Web3Api {
 #[async_trait]
 impl Web3ApiServer for Web3Api {
     async fn client_version(&self) -> RpcResult<String> {
-        let trin_version = get_trin_version();
-        Ok(format!("trin v{trin_version}"))
+        Ok(get_trin_client_version())
     }
 }
 
@@ -1,32 +1,133 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
-use std::sync::Arc;
+use std::{future::Future, path::Path, sync::Arc, time::Duration};
 
+use discv5::enr::{CombinedKey, NodeId};
 use rpc::{launch_jsonrpc_server, RpcServerHandle};
-use tokio::sync::{mpsc, RwLock};
-use tracing::info;
+use tokio::{
+    sync::{mpsc, watch, RwLock},
+    task::JoinHandle,
+};
+use tracing::{error, info, warn};
 use utp_rs::socket::UtpSocket;
 
 #[cfg(windows)]
 use ethportal_api::types::cli::Web3TransportType;
-use ethportal_api::types::cli::{TrinConfig, BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK};
+use ethportal_api::types::cli::{
+    DbCheckConfig, ExportContentConfig, KeysCommands, KeysConfig, TrinConfig, BEACON_NETWORK,
+    CANONICAL_INDICES_NETWORK, HISTORY_NETWORK, STATE_NETWORK, TRANSACTION_GOSSIP_NETWORK,
+};
+use ethportal_api::utils::bytes::hex_encode;
 use portalnet::{
     config::PortalnetConfig,
-    discovery::{Discovery, Discv5UdpSocket},
+    discovery::{read_saved_enr, Discovery, Discv5UdpSocket},
     events::PortalnetEvents,
-    utils::db::{configure_node_data_dir, configure_trin_data_dir},
+    utils::db::{
+        configure_node_data_dir, configure_trin_data_dir, generate_private_key, get_node_data_dir,
+        read_application_private_key, read_private_key_file, store_application_private_key,
+    },
 };
 use trin_beacon::initialize_beacon_network;
-use trin_history::initialize_history_network;
+use trin_canonical_indices::initialize_canonical_indices_network;
+use trin_history::{era1::import_era1_dir, initialize_history_network};
 use trin_state::initialize_state_network;
-use trin_storage::PortalStorageConfig;
+use trin_storage::{
+    backup,
+    utils::{check_available_disk_space, index_distances},
+    PortalStorageConfig, StorageBackend, BYTES_IN_MB_U64,
+};
+use trin_transaction_gossip::initialize_transaction_gossip_network;
 use trin_utils::version::get_trin_version;
 use trin_validation::{accumulator::MasterAccumulator, oracle::HeaderOracle};
 
+/// Longest that a step of the shutdown is waited for, so that a stuck service doesn't keep the
+/// node from exiting.
+const SHUTDOWN_STEP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A running trin node, which can be shut down gracefully.
+pub struct TrinNode {
+    pub rpc_handle: RpcServerHandle,
+    discovery: Arc<Discovery>,
+    /// The task that routes the incoming talk requests to the subnetworks and to uTP.
+    events_task: JoinHandle<()>,
+    /// The tasks that handle the json-rpc requests of the subnetworks.
+    rpc_tasks: Vec<JoinHandle<()>>,
+    /// The tasks of the subnetworks, which stop their overlays once the node shuts down.
+    network_tasks: Vec<JoinHandle<()>>,
+    /// Tells the subnetworks to shut down.
+    shutdown_tx: watch::Sender<bool>,
+}
+
+impl TrinNode {
+    /// Shuts the node down, stopping the services in the order that lets the earlier ones
+    /// finish cleanly:
+    /// - The json-rpc servers are stopped, and waited for, so that no new work is started.
+    /// - No more talk requests are routed. The in-flight uTP transfers no longer receive packets,
+    ///   so they don't complete, and their content isn't stored.
+    /// - Every subnetwork writes the content that it accepted and queued for its store, then
+    ///   stops its storage actor, which drops the store once the earlier operations completed.
+    /// - The routing table is saved, for the node to rejoin the network through it on restart.
+    ///
+    /// Steps that don't complete within [SHUTDOWN_STEP_TIMEOUT] are logged and given up on.
+    pub async fn shutdown(self) {
+        match self.rpc_handle.clone().stop() {
+            Ok(()) => shutdown_step("json-rpc servers", self.rpc_handle.stopped()).await,
+            Err(err) => error!(err = %err, "Failed to close RPC server"),
+        }
+        let rpc_tasks = self.rpc_tasks;
+        shutdown_step("json-rpc handlers", async move {
+            for task in rpc_tasks {
+                task.abort();
+                let _ = task.await;
+            }
+        })
+        .await;
+
+        let events_task = self.events_task;
+        events_task.abort();
+        shutdown_step("talk request routing", async move {
+            let _ = events_task.await;
+        })
+        .await;
+
+        let _ = self.shutdown_tx.send(true);
+        let network_tasks = self.network_tasks;
+        shutdown_step("subnetworks", async move {
+            for task in network_tasks {
+                if let Err(err) = task.await {
+                    error!(%err, "Subnetwork failed to shut down");
+                }
+            }
+        })
+        .await;
+
+        match self.discovery.save_routing_table() {
+            Ok(peers) => info!(peers, "Saved routing table snapshot"),
+            Err(err) => warn!(%err, "Unable to save routing table snapshot"),
+        }
+        info!("Shut down trin");
+    }
+}
+
+/// Waits for a `step` of the shutdown, for up to [SHUTDOWN_STEP_TIMEOUT].
+async fn shutdown_step(step: &str, future: impl Future<Output = ()>) {
+    if tokio::time::timeout(SHUTDOWN_STEP_TIMEOUT, future)
+        .await
+        .is_err()
+    {
+        warn!(step, "Timed out shutting down, continuing without it");
+    }
+}
+
 pub async fn run_trin(
     trin_config: TrinConfig,
 ) -> Result<RpcServerHandle, Box<dyn std::error::Error>> {
+    Ok(start_trin(trin_config).await?.rpc_handle)
+}
+
+/// Starts a trin node, returning a handle to shut it down gracefully.
+pub async fn start_trin(trin_config: TrinConfig) -> Result<TrinNode, Box<dyn std::error::Error>> {
     // Panic early on a windows build that is trying to use IPC, which is unsupported for now
     // Make sure not to panic on non-windows configurations.
     #[cfg(windows)]
@@ -39,13 +140,21 @@ pub async fn run_trin(
     info!(config = %trin_config, "With:");
 
     // Setup temp trin data directory if we're in ephemeral mode
-    let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral)?;
+    let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral, trin_config.network)?;
 
-    // Configure node data dir based on the provided private key
+    // Configure node data dir based on the provided private key. In ephemeral mode, a fresh key is
+    // used unless one is provided, so that no key is saved.
+    let private_key = trin_config
+        .private_key
+        .or_else(|| trin_config.ephemeral.then(generate_private_key));
+    let keystore_password = trin_config.read_keystore_password()?;
     let (node_data_dir, private_key) =
-        configure_node_data_dir(trin_data_dir, trin_config.private_key)?;
+        configure_node_data_dir(trin_data_dir, private_key, keystore_password.as_deref())?;
 
-    let portalnet_config = PortalnetConfig::new(&trin_config, private_key);
+    let mut portalnet_config = PortalnetConfig::new(&trin_config, private_key);
+    if let Some(path) = &trin_config.bootnodes_file {
+        portalnet_config.add_bootnodes_file(path)?;
+    }
 
     // Initialize base discovery protocol
     let mut discovery = Discovery::new(portalnet_config.clone(), node_data_dir.clone())?;
@@ -63,10 +172,29 @@ pub async fn run_trin(
     let utp_socket = UtpSocket::with_socket(discv5_utp_socket);
     let utp_socket = Arc::new(utp_socket);
 
-    let storage_config = PortalStorageConfig::new(
+    let storage_backend = if trin_config.ephemeral {
+        StorageBackend::Memory
+    } else if trin_config.storage_read_only {
+        StorageBackend::SqliteReadOnly
+    } else {
+        StorageBackend::Sqlite
+    };
+    if !trin_config.ephemeral {
+        // Every subnetwork stores up to the capacity in the database, except for transaction
+        // gossip, which keeps its content in memory.
+        let db_networks = trin_config
+            .networks
+            .iter()
+            .filter(|network| *network != TRANSACTION_GOSSIP_NETWORK)
+            .count();
+        let capacity_bytes = u64::from(trin_config.mb) * BYTES_IN_MB_U64 * db_networks as u64;
+        check_available_disk_space(&node_data_dir, capacity_bytes);
+    }
+    let storage_config = PortalStorageConfig::new_with_backend(
         trin_config.mb.into(),
         node_data_dir,
         discovery.local_enr().node_id(),
+        storage_backend,
     )?;
 
     // Initialize validation oracle
@@ -78,6 +206,9 @@ pub async fn run_trin(
     let header_oracle = HeaderOracle::new(master_accumulator);
     let header_oracle = Arc::new(RwLock::new(header_oracle));
 
+    // The subnetworks run until they're told to shut down.
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
     // Initialize state sub-network service and event handlers, if selected
     let (state_handler, state_network_task, state_event_tx, state_jsonrpc_tx, state_event_stream) =
         if trin_config.networks.iter().any(|val| val == STATE_NETWORK) {
@@ -87,6 +218,7 @@ pub async fn run_trin(
                 portalnet_config.clone(),
                 storage_config.clone(),
                 header_oracle.clone(),
+                shutdown_rx.clone(),
             )
             .await?
         } else {
@@ -107,6 +239,54 @@ pub async fn run_trin(
             portalnet_config.clone(),
             storage_config.clone(),
             header_oracle.clone(),
+            shutdown_rx.clone(),
+        )
+        .await?
+    } else {
+        (None, None, None, None, None)
+    };
+
+    // Initialize transaction gossip sub-network service and event handlers, if selected
+    let (
+        transaction_gossip_network_task,
+        transaction_gossip_event_tx,
+        transaction_gossip_event_stream,
+    ) = if trin_config
+        .networks
+        .iter()
+        .any(|val| val == TRANSACTION_GOSSIP_NETWORK)
+    {
+        initialize_transaction_gossip_network(
+            &discovery,
+            Arc::clone(&utp_socket),
+            portalnet_config.clone(),
+            header_oracle.clone(),
+            shutdown_rx.clone(),
+        )
+        .await?
+    } else {
+        (None, None, None)
+    };
+
+    // Initialize canonical indices sub-network service and event handlers, if selected
+    let (
+        canonical_indices_handler,
+        canonical_indices_network_task,
+        canonical_indices_event_tx,
+        canonical_indices_jsonrpc_tx,
+        canonical_indices_event_stream,
+    ) = if trin_config
+        .networks
+        .iter()
+        .any(|val| val == CANONICAL_INDICES_NETWORK)
+    {
+        initialize_canonical_indices_network(
+            &discovery,
+            Arc::clone(&utp_socket),
+            portalnet_config.clone(),
+            storage_config.clone(),
+            header_oracle.clone(),
+            shutdown_rx.clone(),
         )
         .await?
     } else {
@@ -131,6 +311,7 @@ pub async fn run_trin(
             portalnet_config.clone(),
             storage_config.clone(),
             header_oracle.clone(),
+            shutdown_rx.clone(),
         )
         .await?
     } else {
@@ -146,41 +327,250 @@ pub async fn run_trin(
         history_jsonrpc_tx,
         state_jsonrpc_tx,
         beacon_jsonrpc_tx,
+        canonical_indices_jsonrpc_tx,
     )
     .await?;
 
+    let mut rpc_tasks = vec![];
     if let Some(handler) = state_handler {
-        tokio::spawn(handler.handle_client_queries());
+        rpc_tasks.push(tokio::spawn(handler.handle_client_queries()));
     }
     if let Some(handler) = history_handler {
-        tokio::spawn(async move { handler.handle_client_queries().await });
+        rpc_tasks.push(tokio::spawn(async move {
+            handler.handle_client_queries().await
+        }));
     }
     if let Some(handler) = beacon_handler {
-        tokio::spawn(async move { handler.handle_client_queries().await });
+        rpc_tasks.push(tokio::spawn(async move {
+            handler.handle_client_queries().await
+        }));
+    }
+    if let Some(handler) = canonical_indices_handler {
+        rpc_tasks.push(tokio::spawn(handler.handle_client_queries()));
     }
 
     // Spawn main portal events handler
-    tokio::spawn(async move {
+    let network = trin_config.network;
+    let events_task = tokio::spawn(async move {
         let events = PortalnetEvents::new(
             talk_req_rx,
             (history_event_tx, history_event_stream),
             (state_event_tx, state_event_stream),
             (beacon_event_tx, beacon_event_stream),
+            (transaction_gossip_event_tx, transaction_gossip_event_stream),
+            (canonical_indices_event_tx, canonical_indices_event_stream),
             utp_talk_reqs_tx,
+            network,
         )
         .await;
         events.start().await;
     });
 
-    if let Some(network) = history_network_task {
-        tokio::spawn(network);
+    // The subnetworks already run in their own tasks, which are joined on shutdown.
+    let network_tasks = [
+        history_network_task,
+        state_network_task,
+        beacon_network_task,
+        transaction_gossip_network_task,
+        canonical_indices_network_task,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    Ok(TrinNode {
+        rpc_handle,
+        discovery,
+        events_task,
+        rpc_tasks,
+        network_tasks,
+        shutdown_tx,
+    })
+}
+
+/// Returns the storage config of the node, for commands that work on the local storage without
+/// starting the node.
+fn local_storage_config(
+    trin_config: &TrinConfig,
+) -> Result<PortalStorageConfig, Box<dyn std::error::Error>> {
+    let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral, trin_config.network)?;
+    let keystore_password = trin_config.read_keystore_password()?;
+    let (node_data_dir, private_key) = configure_node_data_dir(
+        trin_data_dir,
+        trin_config.private_key,
+        keystore_password.as_deref(),
+    )?;
+    let enr_key = CombinedKey::secp256k1_from_bytes(private_key.0.clone().as_mut_slice())
+        .map_err(|e| format!("Unable to create enr key: {e}"))?;
+    Ok(PortalStorageConfig::new(
+        trin_config.mb.into(),
+        node_data_dir,
+        NodeId::from(enr_key.public()),
+    )?)
+}
+
+/// Validates the era1 archives in `dir` and stores their content in the history network storage of
+/// the node, so that a new node can serve history content without backfilling it over the network.
+pub fn import_era1(trin_config: &TrinConfig, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let storage_config = local_storage_config(trin_config)?;
+    let master_accumulator = MasterAccumulator::try_from_file(trin_config.master_acc_path.clone())?;
+
+    let stats = import_era1_dir(dir, storage_config, &master_accumulator)?;
+    info!(
+        archives = stats.archives,
+        blocks = stats.blocks,
+        stored = stats.stored,
+        skipped = stats.skipped,
+        "Imported era1 archives from {}",
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Writes the local history network content to a content archive, to pre-seed other nodes.
+pub fn export_content(
+    trin_config: &TrinConfig,
+    export_config: &ExportContentConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let storage_config = local_storage_config(trin_config)?;
+    // SQLite takes limits up to i64::MAX
+    let limit = export_config.limit.unwrap_or(i64::MAX as u64);
+    let entries = trin_history::archive::export_content(
+        storage_config,
+        &export_config.path,
+        export_config.offset,
+        limit,
+    )?;
+    info!(
+        entries,
+        "Exported local content to {}",
+        export_config.path.display()
+    );
+    Ok(())
+}
+
+/// Checks the integrity of the local history network content, reporting corrupt content and
+/// deleting it if requested. Fails if corrupt content was found and kept.
+pub fn check_db(
+    trin_config: &TrinConfig,
+    check_config: &DbCheckConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let storage_config = local_storage_config(trin_config)?;
+    let report = trin_history::db::check_content(storage_config, check_config.repair)?;
+    for corrupt in &report.corrupt {
+        warn!(
+            content.id = %hex_encode(&corrupt.content_id),
+            content.key = %corrupt.content_key,
+            "Corrupt content: {}",
+            corrupt.reason
+        );
     }
-    if let Some(network) = state_network_task {
-        tokio::spawn(network);
+    info!(
+        checked = report.checked,
+        corrupt = report.corrupt.len(),
+        deleted = report.deleted,
+        "Checked local content"
+    );
+    if report.deleted < report.corrupt.len() as u64 {
+        return Err(format!(
+            "Found {} corrupt content items, run with --repair to delete them",
+            report.corrupt.len()
+        )
+        .into());
     }
-    if let Some(network) = beacon_network_task {
-        tokio::spawn(network);
+    Ok(())
+}
+
+/// Replaces the content database of the node with a backup at `path`, while the node is stopped.
+pub fn restore_db(trin_config: &TrinConfig, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let storage_config = local_storage_config(trin_config)?;
+    backup::restore_db(&storage_config.sql_connection_pool, path)?;
+    // The backup may have been indexed for another node.
+    index_distances(
+        &mut storage_config.sql_connection_pool.get()?,
+        &storage_config.node_id,
+    )?;
+    info!("Restored the content database from {}", path.display());
+    Ok(())
+}
+
+/// Validates the content of a content archive, exported by another node, and stores it in the
+/// local history network storage.
+pub async fn import_content(
+    trin_config: &TrinConfig,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let storage_config = local_storage_config(trin_config)?;
+    let master_accumulator = MasterAccumulator::try_from_file(trin_config.master_acc_path.clone())?;
+    let info = trin_history::archive::import_content(
+        storage_config,
+        path.to_path_buf(),
+        master_accumulator,
+    )
+    .await?;
+    info!(
+        stored = info.stored,
+        skipped = info.skipped,
+        invalid = info.invalid,
+        "Imported content archive {}",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Generates, imports or shows the node key in the trin data dir, without starting the node.
+///
+/// The results are printed to stdout rather than logged, so that they're shown whatever the log
+/// settings.
+pub fn manage_keys(
+    trin_config: &TrinConfig,
+    keys_config: &KeysConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral, trin_config.network)?;
+    let keystore_password = trin_config.read_keystore_password()?;
+    let keystore_password = keystore_password.as_deref();
+    match &keys_config.command {
+        KeysCommands::Generate(generate_config) => {
+            store_application_private_key(
+                &trin_data_dir,
+                generate_private_key(),
+                keystore_password,
+                generate_config.force,
+            )?;
+            println!("Generated a new node key in {}", trin_data_dir.display());
+        }
+        KeysCommands::Import(import_config) => {
+            let private_key = read_private_key_file(&import_config.path, keystore_password)?;
+            store_application_private_key(
+                &trin_data_dir,
+                private_key,
+                keystore_password,
+                import_config.force,
+            )?;
+            println!(
+                "Imported the node key from {} into {}",
+                import_config.path.display(),
+                trin_data_dir.display()
+            );
+        }
+        KeysCommands::Show => {}
     }
 
-    Ok(rpc_handle)
+    let Some(private_key) = read_application_private_key(&trin_data_dir, keystore_password)? else {
+        return Err(format!(
+            "No node key in {}, generate one with `trin keys generate`",
+            trin_data_dir.display()
+        )
+        .into());
+    };
+    let enr_key = CombinedKey::secp256k1_from_bytes(private_key.0.clone().as_mut_slice())
+        .map_err(|e| format!("Unable to create enr key: {e}"))?;
+    let node_id = NodeId::from(enr_key.public());
+    println!("Node id: {}", hex_encode(node_id.raw()));
+    // The node data dir is only looked up, so that showing the key doesn't write to the data dir.
+    match read_saved_enr(&get_node_data_dir(trin_data_dir, node_id))? {
+        Some(enr) => println!("ENR: {enr}"),
+        None => println!("ENR: not known until the node has run with this key"),
+    }
+    Ok(())
 }
@@ -1,24 +1,85 @@
 #![warn(clippy::unwrap_used)]
 
-use ethportal_api::types::cli::TrinConfig;
-use tracing::error;
-use trin_utils::log::init_tracing_logger;
+use ethportal_api::types::cli::{DbCommands, TrinConfig, TrinConfigCommands};
+use tracing::{info, warn};
+use trin_utils::log::{init_logger, JsonLogConfig};
 
-use trin::run_trin;
+use trin::{
+    check_db, export_content, import_content, import_era1, manage_keys, restore_db, start_trin,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    init_tracing_logger();
     let trin_config = TrinConfig::from_cli();
-    let rpc_handle = run_trin(trin_config).await?;
-
-    tokio::signal::ctrl_c()
-        .await
-        .expect("failed to pause until ctrl-c");
+    let json_log = trin_config.json_log_dir.clone().map(|dir| JsonLogConfig {
+        dir,
+        max_file_size: u64::from(trin_config.json_log_max_file_size) * 1_000_000,
+        max_files: trin_config.json_log_max_files,
+    });
+    // Held until exit, so that the remaining JSON log lines are written.
+    let log_guard = init_logger(!trin_config.disable_console_log, json_log)?;
+    // exit program after commands that only work on the local storage, since this is all we do
+    match &trin_config.command {
+        Some(TrinConfigCommands::ImportEra1(import_config)) => {
+            return import_era1(&trin_config, &import_config.dir)
+        }
+        Some(TrinConfigCommands::ExportContent(export_config)) => {
+            return export_content(&trin_config, export_config)
+        }
+        Some(TrinConfigCommands::ImportContent(import_config)) => {
+            return import_content(&trin_config, &import_config.path).await
+        }
+        Some(TrinConfigCommands::Db(db_config)) => match &db_config.command {
+            DbCommands::Check(check_config) => return check_db(&trin_config, check_config),
+            DbCommands::Restore(restore_config) => {
+                return restore_db(&trin_config, &restore_config.path)
+            }
+        },
+        Some(TrinConfigCommands::Keys(keys_config)) => {
+            return manage_keys(&trin_config, keys_config)
+        }
+        Some(TrinConfigCommands::CreateDashboard(_)) | None => {}
+    }
+    let trin_node = start_trin(trin_config).await?;
 
-    if let Err(err) = rpc_handle.stop() {
-        error!(err = %err, "Failed to close RPC server")
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result.expect("failed to pause until ctrl-c");
+            info!("Shutting down, on SIGINT");
+        }
+        _ = terminate_signal() => info!("Shutting down, on SIGTERM"),
+        _ = trin_node.rpc_handle.shutdown_requested() => info!("Shutting down, as requested over json-rpc"),
+    }
+    // A second signal exits right away, for when the shutdown is stuck.
+    tokio::select! {
+        _ = trin_node.shutdown() => {}
+        _ = shutdown_signal() => {
+            warn!("Exiting without finishing the shutdown, on a second signal");
+            drop(log_guard);
+            std::process::exit(1);
+        }
     }
 
     Ok(())
 }
+
+/// Waits for a SIGINT or a SIGTERM.
+async fn shutdown_signal() {
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate_signal() => {}
+    }
+}
+
+/// Waits for a SIGTERM, as sent by service managers and container runtimes to stop the node.
+#[cfg(unix)]
+async fn terminate_signal() {
+    let mut signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("failed to install SIGTERM handler");
+    signal.recv().await;
+}
+
+#[cfg(not(unix))]
+async fn terminate_signal() {
+    std::future::pending().await
+}
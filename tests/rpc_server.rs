@@ -16,7 +16,8 @@ use ethportal_api::{
         execution::{block_body::BlockBody, header::HeaderWithProof},
     },
     utils::bytes::{hex_decode, hex_encode},
-    HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient,
+    AdminApiClient, BeaconNetworkApiClient, HistoryContentKey, HistoryContentValue,
+    HistoryNetworkApiClient,
 };
 use rpc::RpcServerHandle;
 
@@ -71,6 +72,54 @@ async fn test_eth_chain_id() {
     assert_eq!(chain_id, U256::from(1));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn test_inactive_network_methods_are_not_found() {
+    let (web3_server, _, native_client) = setup_web3_server().await;
+    // Only the history network is activated by default.
+    let err = BeaconNetworkApiClient::routing_table_info(&native_client)
+        .await
+        .unwrap_err();
+    web3_server.stop().unwrap();
+    assert!(err.to_string().contains("Method not found"), "{err}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn test_admin_methods_are_not_served_by_default() {
+    init_tracing();
+    let trin_config = TrinConfig::new_from(
+        [
+            "trin",
+            "--web3-transport",
+            "http",
+            "--web3-http-address",
+            "http://127.0.0.1:8998/",
+            "--ws",
+            "--ws-port",
+            "8997",
+            "--ephemeral",
+            "--discovery-port",
+            "8999",
+            "--bootnodes",
+            "none",
+        ]
+        .iter(),
+    )
+    .unwrap();
+    let web3_server = trin::run_trin(trin_config).await.unwrap();
+    let ws_client = web3_server.ws_client().await.unwrap();
+    let ws_err = AdminApiClient::shutdown(&ws_client).await.unwrap_err();
+    let http_client = web3_server.http_client().unwrap();
+    let http_err = AdminApiClient::shutdown(&http_client).await.unwrap_err();
+    web3_server.stop().unwrap();
+    assert!(ws_err.to_string().contains("Method not found"), "{ws_err}");
+    assert!(
+        http_err.to_string().contains("Method not found"),
+        "{http_err}"
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn test_eth_get_block_by_hash() {
@@ -47,6 +47,22 @@ async fn peertest_stateless() {
     handle.stop().unwrap();
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn peertest_state() {
+    let (peertest, target, handle) = setup_peertest().await;
+    peertest::scenarios::state::test_state_radius(&target).await;
+    peertest::scenarios::state::test_state_routing_table_info(&target).await;
+    peertest::scenarios::state::test_state_ping(&target, &peertest).await;
+    peertest::scenarios::state::test_state_find_nodes_zero_distance(&target, &peertest).await;
+    peertest::scenarios::state::test_state_local_content_absent(&target).await;
+    peertest::scenarios::state::test_state_offer(&target, &peertest).await;
+    peertest::scenarios::state::test_state_store(&target).await;
+    peertest::scenarios::state::test_state_gossip(&target).await;
+    peertest.exit_all_nodes();
+    handle.stop().unwrap();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn peertest_populated_offer() {
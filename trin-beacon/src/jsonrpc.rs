@@ -7,7 +7,7 @@ use ethportal_api::{
         constants::CONTENT_ABSENT,
         content_value::ContentValue,
         distance::Distance,
-        jsonrpc::{endpoints::BeaconEndpoint, request::BeaconJsonRpcRequest},
+        jsonrpc::{endpoints::BeaconEndpoint, error::JsonRpcError, request::BeaconJsonRpcRequest},
         portal::{AcceptInfo, FindNodesInfo, PongInfo},
         portal_wire::Content,
         query_trace::QueryTrace,
@@ -18,8 +18,7 @@ use ethportal_api::{
 use serde_json::{json, Value};
 use ssz::Encode;
 use tokio::sync::{mpsc, Mutex, RwLock};
-use tracing::error;
-use trin_storage::ContentStore;
+use tracing::{error, Instrument};
 
 use crate::network::BeaconNetwork;
 
@@ -35,14 +34,15 @@ impl BeaconRequestHandler {
         let rpc_rx = self.rpc_rx.clone();
         while let Some(request) = rpc_rx.lock().await.recv().await {
             let network = self.network.clone();
-            tokio::spawn(async move { complete_request(network, request).await });
+            let span = request.span.clone();
+            tokio::spawn(async move { complete_request(network, request).await }.instrument(span));
         }
     }
 }
 
 /// Generates a response for a given request and sends it to the receiver.
 async fn complete_request(network: Arc<RwLock<BeaconNetwork>>, request: BeaconJsonRpcRequest) {
-    let response: Result<Value, String> = match request.endpoint {
+    let response: Result<Value, JsonRpcError> = match request.endpoint {
         BeaconEndpoint::LocalContent(content_key) => local_content(network, content_key).await,
         BeaconEndpoint::PaginateLocalContentKeys(offset, limit) => {
             paginate_local_content_keys(network, offset, limit).await
@@ -80,8 +80,11 @@ async fn complete_request(network: Arc<RwLock<BeaconNetwork>>, request: BeaconJs
         BeaconEndpoint::Ping(enr) => ping(network, enr).await,
         BeaconEndpoint::RoutingTableInfo => {
             serde_json::to_value(network.read().await.overlay.routing_table_info())
-                .map_err(|err| err.to_string())
+                .map_err(|err| JsonRpcError::internal(err.to_string()))
         }
+        BeaconEndpoint::Peers => serde_json::to_value(network.read().await.overlay.peers())
+            .map_err(|err| JsonRpcError::internal(err.to_string())),
+        BeaconEndpoint::DbStats => db_stats(network).await,
         BeaconEndpoint::RecursiveFindNodes(node_id) => recursive_find_nodes(network, node_id).await,
     };
     let _ = request.resp.send(response);
@@ -92,10 +95,10 @@ async fn recursive_find_content(
     network: Arc<RwLock<BeaconNetwork>>,
     content_key: BeaconContentKey,
     is_trace: bool,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     // Check whether we have the data locally.
     let overlay = network.read().await.overlay.clone();
-    let local_content: Option<Vec<u8>> = match overlay.store.read().get(&content_key) {
+    let local_content: Option<Vec<u8>> = match overlay.store.get(content_key.clone()).await {
         Ok(Some(data)) => Some(data),
         Ok(None) => None,
         Err(err) => {
@@ -126,18 +129,22 @@ async fn recursive_find_content(
     // If tracing is not required, return content.
     if !is_trace {
         return Ok(json!(ContentInfo::Content {
-            content: serde_json::from_value(content_response_string).map_err(|e| e.to_string())?,
+            content: serde_json::from_value(content_response_string)
+                .map_err(|err| JsonRpcError::internal(err.to_string()))?,
             utp_transfer,
         }));
     }
     if let Some(trace) = trace {
         Ok(json!(TraceContentInfo {
-            content: serde_json::from_value(content_response_string).map_err(|e| e.to_string())?,
+            content: serde_json::from_value(content_response_string)
+                .map_err(|err| JsonRpcError::internal(err.to_string()))?,
             utp_transfer,
             trace,
         }))
     } else {
-        Err("Content query trace requested but none provided.".to_owned())
+        Err(JsonRpcError::internal(
+            "Content query trace requested but none provided.",
+        ))
     }
 }
 
@@ -145,9 +152,9 @@ async fn recursive_find_content(
 async fn local_content(
     network: Arc<RwLock<BeaconNetwork>>,
     content_key: BeaconContentKey,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let store = network.read().await.overlay.store.clone();
-    let response = match store.read().get(&content_key)
+    let response = match store.get(content_key.clone()).await
         {
             Ok(val) => match val {
                 Some(val) => {
@@ -157,9 +164,9 @@ async fn local_content(
                     Ok(Value::String(CONTENT_ABSENT.to_string()))
                 }
             },
-            Err(err) => Err(format!(
+            Err(err) => Err(JsonRpcError::storage(format!(
                 "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
-            )),
+            ))),
         };
     response
 }
@@ -169,32 +176,42 @@ async fn paginate_local_content_keys(
     network: Arc<RwLock<BeaconNetwork>>,
     offset: u64,
     limit: u64,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let store = network.read().await.overlay.store.clone();
-    let response = match store.read().paginate(&offset, &limit)
+    let response = match store.read(move |store| store.paginate(&offset, &limit)).await
         {
             Ok(val) => Ok(json!(val)),
-            Err(err) => Err(format!(
+            Err(err) => Err(JsonRpcError::storage(format!(
                 "Database error while paginating local content keys with offset: {offset:?}, limit: {limit:?}. Error message: {err}"
-            )),
+            ))),
         };
     response
 }
 
+/// Constructs a JSON call for the DbStats method.
+async fn db_stats(network: Arc<RwLock<BeaconNetwork>>) -> Result<Value, JsonRpcError> {
+    let store = network.read().await.overlay.store.clone();
+    match store.read(|store| store.db_stats()).await {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while collecting storage stats. Error message: {err}"
+        ))),
+    }
+}
+
 /// Constructs a JSON call for the Store method.
 async fn store(
     network: Arc<RwLock<BeaconNetwork>>,
     content_key: BeaconContentKey,
     content_value: BeaconContentValue,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let data = content_value.encode();
     let store = network.read().await.overlay.store.clone();
-    let response = match store
-        .write()
-        .put::<BeaconContentKey, Vec<u8>>(content_key, data)
-    {
+    let response = match store.put(content_key.clone(), data).await {
         Ok(_) => Ok(Value::Bool(true)),
-        Err(msg) => Ok(Value::String(msg.to_string())),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while storing content key: {content_key:?}, with error: {err}"
+        ))),
     };
     response
 }
@@ -203,36 +220,45 @@ async fn store(
 async fn add_enr(
     network: Arc<RwLock<BeaconNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.add_enr(enr) {
         Ok(_) => Ok(json!(true)),
-        Err(err) => Err(format!("AddEnr failed: {err:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
 /// Constructs a JSON call for the GetEnr method.
-async fn get_enr(network: Arc<RwLock<BeaconNetwork>>, node_id: NodeId) -> Result<Value, String> {
+async fn get_enr(
+    network: Arc<RwLock<BeaconNetwork>>,
+    node_id: NodeId,
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.get_enr(node_id) {
         Ok(enr) => Ok(json!(enr)),
-        Err(err) => Err(format!("GetEnr failed: {err:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
 /// Constructs a JSON call for the deleteEnr method.
-async fn delete_enr(network: Arc<RwLock<BeaconNetwork>>, node_id: NodeId) -> Result<Value, String> {
+async fn delete_enr(
+    network: Arc<RwLock<BeaconNetwork>>,
+    node_id: NodeId,
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     let is_deleted = overlay.delete_enr(node_id);
     Ok(json!(is_deleted))
 }
 
 /// Constructs a JSON call for the LookupEnr method.
-async fn lookup_enr(network: Arc<RwLock<BeaconNetwork>>, node_id: NodeId) -> Result<Value, String> {
+async fn lookup_enr(
+    network: Arc<RwLock<BeaconNetwork>>,
+    node_id: NodeId,
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.lookup_enr(node_id).await {
         Ok(enr) => Ok(json!(enr)),
-        Err(err) => Err(format!("LookupEnr failed: {err:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -241,13 +267,13 @@ async fn find_content(
     network: Arc<RwLock<BeaconNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
     content_key: BeaconContentKey,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.send_find_content(enr, content_key.into()).await {
         Ok((content, utp_transfer)) => match content{
-            Content::ConnectionId(id) => Err(format!(
+            Content::ConnectionId(id) => Err(JsonRpcError::internal(format!(
                 "FindContent request returned a connection id ({id:?}) instead of conducting utp transfer."
-            )),
+            ))),
             Content::Content(content) => Ok(json!({
                 "content": hex_encode(content),
                 "utpTransfer": utp_transfer,
@@ -256,7 +282,7 @@ async fn find_content(
                 "enrs": enrs,
             })),
         },
-        Err(msg) => Err(format!("FindContent request timeout: {msg:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -265,7 +291,7 @@ async fn find_nodes(
     network: Arc<RwLock<BeaconNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
     distances: Vec<u16>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.send_find_nodes(enr, distances).await {
         Ok(nodes) => Ok(json!(nodes
@@ -273,7 +299,7 @@ async fn find_nodes(
             .into_iter()
             .map(|enr| enr.into())
             .collect::<FindNodesInfo>())),
-        Err(msg) => Err(format!("FindNodes request timeout: {msg:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -283,7 +309,7 @@ async fn gossip(
     content_key: BeaconContentKey,
     content_value: BeaconContentValue,
     is_trace: bool,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let data = content_value.encode();
     let overlay = network.read().await.overlay.clone();
     match is_trace {
@@ -300,7 +326,7 @@ async fn offer(
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
     content_key: BeaconContentKey,
     content_value: Option<BeaconContentValue>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     if let Some(content_value) = content_value {
         let content_value = content_value.encode();
@@ -311,7 +337,7 @@ async fn offer(
             Ok(accept) => Ok(json!(AcceptInfo {
                 content_keys: accept.content_keys,
             })),
-            Err(msg) => Err(format!("Populated Offer request timeout: {msg:?}")),
+            Err(err) => Err(err.into()),
         }
     } else {
         let content_key: Vec<RawContentKey> = vec![content_key.as_ssz_bytes()];
@@ -319,7 +345,7 @@ async fn offer(
             Ok(accept) => Ok(json!(AcceptInfo {
                 content_keys: accept.content_keys,
             })),
-            Err(msg) => Err(format!("Offer request timeout: {msg:?}")),
+            Err(err) => Err(err.into()),
         }
     }
 }
@@ -328,14 +354,14 @@ async fn offer(
 async fn ping(
     network: Arc<RwLock<BeaconNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.send_ping(enr).await {
         Ok(pong) => Ok(json!(PongInfo {
             enr_seq: pong.enr_seq as u32,
             data_radius: *Distance::from(pong.custom_payload),
         })),
-        Err(msg) => Err(format!("Ping request timeout: {msg:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -343,7 +369,7 @@ async fn ping(
 async fn recursive_find_nodes(
     network: Arc<RwLock<BeaconNetwork>>,
     node_id: NodeId,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     let nodes = overlay.lookup_node(node_id).await;
     Ok(json!(nodes))
@@ -11,7 +11,7 @@ pub mod validation;
 use std::sync::Arc;
 
 use tokio::{
-    sync::{broadcast, mpsc, Mutex, RwLock},
+    sync::{broadcast, mpsc, watch, Mutex, RwLock},
     task::JoinHandle,
     time::{interval, Duration},
 };
@@ -41,6 +41,7 @@ pub async fn initialize_beacon_network(
     portalnet_config: PortalnetConfig,
     storage_config: PortalStorageConfig,
     header_oracle: Arc<RwLock<HeaderOracle>>,
+    shutdown: watch::Receiver<bool>,
 ) -> anyhow::Result<(
     BeaconHandler,
     BeaconNetworkTask,
@@ -65,8 +66,12 @@ pub async fn initialize_beacon_network(
         rpc_rx: Arc::new(Mutex::new(beacon_jsonrpc_rx)),
     };
     let beacon_network = Arc::new(beacon_network);
-    let beacon_network_task =
-        spawn_beacon_network(beacon_network.clone(), portalnet_config, beacon_message_rx);
+    let beacon_network_task = spawn_beacon_network(
+        beacon_network.clone(),
+        portalnet_config,
+        beacon_message_rx,
+        shutdown,
+    );
     spawn_beacon_heartbeat(beacon_network);
     Ok((
         Some(beacon_handler),
@@ -81,6 +86,7 @@ pub fn spawn_beacon_network(
     network: Arc<BeaconNetwork>,
     portalnet_config: PortalnetConfig,
     beacon_message_rx: mpsc::UnboundedReceiver<OverlayRequest>,
+    mut shutdown: watch::Receiver<bool>,
 ) -> JoinHandle<()> {
     let bootnode_enrs: Vec<Enr> = portalnet_config.bootnodes.into();
     info!(
@@ -100,9 +106,11 @@ pub fn spawn_beacon_network(
         // hacky test: make sure we establish a session with the boot node
         network.overlay.ping_bootnodes().await;
 
-        tokio::signal::ctrl_c()
-            .await
-            .expect("failed to pause until ctrl-c");
+        // The overlay is stopped once the node shuts down. It keeps running if the node is dropped
+        // without shutting down.
+        if shutdown.wait_for(|shutdown| *shutdown).await.is_ok() {
+            network.overlay.shutdown().await;
+        }
     })
 }
 
@@ -115,7 +123,12 @@ pub fn spawn_beacon_heartbeat(network: Arc<BeaconNetwork>) {
             // this wait at the top. Otherwise, we get two log lines immediately on startup.
             heart_interval.tick().await;
 
-            let storage_log = network.overlay.store.read().get_summary_info();
+            let storage_log = network
+                .overlay
+                .store
+                .read(|store| Ok(store.get_summary_info()))
+                .await
+                .unwrap_or_else(|err| err.to_string());
             let message_log = network.overlay.get_message_summary();
             let utp_log = network.overlay.get_utp_summary();
             info!("reports~ data: {storage_log}; msgs: {message_log}");
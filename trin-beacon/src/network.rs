@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use parking_lot::RwLock as PLRwLock;
 use tokio::sync::RwLock;
 use utp_rs::socket::UtpSocket;
 
@@ -35,9 +34,12 @@ impl BeaconNetwork {
         let bootnode_enrs: Vec<Enr> = portal_config.bootnodes.into();
         let config = OverlayConfig {
             bootnode_enrs,
+            query_parallelism: portal_config.query_parallelism,
+            query_peer_timeout: portal_config.query_peer_timeout,
+            query_timeout: portal_config.query_timeout,
             ..Default::default()
         };
-        let storage = Arc::new(PLRwLock::new(BeaconStorage::new(storage_config)?));
+        let storage = BeaconStorage::new(storage_config)?;
         let validator = Arc::new(BeaconValidator { header_oracle });
         let overlay = OverlayProtocol::new(
             config,
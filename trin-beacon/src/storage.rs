@@ -1,5 +1,6 @@
 use ethportal_api::{
     types::{
+        admin::DbStats,
         content_key::beacon::{
             LIGHT_CLIENT_BOOTSTRAP_KEY_PREFIX, LIGHT_CLIENT_FINALITY_UPDATE_KEY_PREFIX,
             LIGHT_CLIENT_OPTIMISTIC_UPDATE_KEY_PREFIX, LIGHT_CLIENT_UPDATES_BY_RANGE_KEY_PREFIX,
@@ -25,8 +26,9 @@ use trin_metrics::{portalnet::PORTALNET_METRICS, storage::StorageMetricsReporter
 use trin_storage::{
     error::ContentStoreError,
     sql::{
-        CONTENT_KEY_LOOKUP_QUERY_DB, INSERT_LC_UPDATE_QUERY, LC_UPDATE_LOOKUP_QUERY,
-        LC_UPDATE_PERIOD_LOOKUP_QUERY, TOTAL_DATA_SIZE_QUERY_DB,
+        CONTENT_KEY_LOOKUP_QUERY_NETWORK, INSERT_LC_UPDATE_QUERY, LC_UPDATE_ENTRY_COUNT_QUERY,
+        LC_UPDATE_LOOKUP_QUERY, LC_UPDATE_PERIOD_LOOKUP_QUERY, LC_UPDATE_TOTAL_SIZE_QUERY,
+        TOTAL_DATA_SIZE_QUERY_NETWORK, TOTAL_ENTRY_COUNT_QUERY_NETWORK,
     },
     utils::{get_total_size_of_directory_in_bytes, insert_value, lookup_content_value},
     ContentStore, DataSize, PortalStorageConfig, ShouldWeStoreContent, BYTES_IN_MB_U64,
@@ -113,6 +115,9 @@ pub struct BeaconStorage {
     metrics: StorageMetricsReporter,
     network: ProtocolId,
     cache: BeaconStorageCache,
+    /// Whether the database was opened read-only, in which case bootstraps and updates are never
+    /// stored. Finality and optimistic updates are only cached in memory, so they're still stored.
+    read_only: bool,
 }
 
 impl ContentStore for BeaconStorage {
@@ -219,6 +224,9 @@ impl ContentStore for BeaconStorage {
                 if is_key_available {
                     return Ok(ShouldWeStoreContent::AlreadyStored);
                 }
+                if self.read_only {
+                    return Ok(ShouldWeStoreContent::NotWithinRadius);
+                }
                 Ok(ShouldWeStoreContent::Store)
             }
             BeaconContentKey::LightClientUpdatesByRange(content_key) => {
@@ -240,6 +248,9 @@ impl ContentStore for BeaconStorage {
                         return Ok(ShouldWeStoreContent::AlreadyStored);
                     }
                 }
+                if self.read_only {
+                    return Ok(ShouldWeStoreContent::NotWithinRadius);
+                }
                 Ok(ShouldWeStoreContent::Store)
             }
             BeaconContentKey::LightClientFinalityUpdate(content_key) => {
@@ -277,6 +288,7 @@ impl BeaconStorage {
             metrics,
             network: ProtocolId::Beacon,
             cache: BeaconStorageCache::new(),
+            read_only: config.backend.is_read_only(),
         };
 
         // Report current storage capacity.
@@ -331,6 +343,16 @@ impl BeaconStorage {
         let content_id = key.content_id();
         let content_key: Vec<u8> = key.clone().into();
 
+        if self.read_only
+            && matches!(
+                content_key.first(),
+                Some(&LIGHT_CLIENT_BOOTSTRAP_KEY_PREFIX)
+                    | Some(&LIGHT_CLIENT_UPDATES_BY_RANGE_KEY_PREFIX)
+            )
+        {
+            return Err(ContentStoreError::ReadOnly);
+        }
+
         match content_key.first() {
             Some(&LIGHT_CLIENT_BOOTSTRAP_KEY_PREFIX) => {
                 // store content key w/o the 0x prefix
@@ -433,12 +455,35 @@ impl BeaconStorage {
         Ok(storage_usage)
     }
 
+    /// Returns the storage usage of the network, including the light client updates.
+    pub fn db_stats(&self) -> Result<DbStats, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        let lc_update_bytes: f64 =
+            conn.query_row(LC_UPDATE_TOTAL_SIZE_QUERY, [], |row| row.get(0))?;
+        let lc_update_count: u64 =
+            conn.query_row(LC_UPDATE_ENTRY_COUNT_QUERY, [], |row| row.get(0))?;
+        let entry_count: u64 = conn.query_row(
+            TOTAL_ENTRY_COUNT_QUERY_NETWORK,
+            [u8::from(self.network)],
+            |row| row.get(0),
+        )?;
+        Ok(DbStats {
+            content_bytes: self.get_total_storage_usage_in_bytes_from_network()?
+                + lc_update_bytes as u64,
+            capacity_bytes: self.storage_capacity_in_bytes,
+            entry_count: entry_count + lc_update_count,
+            // Beacon content is never evicted.
+            eviction_count: 0,
+            radius: *self.radius(),
+        })
+    }
+
     /// Internal method for measuring the total amount of requestable data that the node is storing.
     fn get_total_storage_usage_in_bytes_from_network(&self) -> Result<u64, ContentStoreError> {
         let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(TOTAL_DATA_SIZE_QUERY_DB)?;
+        let mut query = conn.prepare(TOTAL_DATA_SIZE_QUERY_NETWORK)?;
 
-        let result = query.query_map([], |row| {
+        let result = query.query_map([u8::from(self.network)], |row| {
             Ok(DataSize {
                 num_bytes: row.get(0)?,
             })
@@ -461,10 +506,10 @@ impl BeaconStorage {
     /// Public method for looking up a content key by its content id
     pub fn lookup_content_key(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
         let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_KEY_LOOKUP_QUERY_DB)?;
+        let mut query = conn.prepare(CONTENT_KEY_LOOKUP_QUERY_NETWORK)?;
         let id = id.to_vec();
         let result: Result<Vec<BeaconContentKey>, ContentStoreError> = query
-            .query_map([id], |row| {
+            .query_map(params![id, u8::from(self.network)], |row| {
                 let row: String = row.get(0)?;
                 Ok(row)
             })?
@@ -502,7 +547,7 @@ impl BeaconStorage {
     /// Public method for looking up a content value by its content id
     pub fn lookup_content_value(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
         let conn = self.sql_connection_pool.get()?;
-        lookup_content_value(id, conn)?
+        lookup_content_value(id, conn, u8::from(self.network))?
     }
 
     /// Public method for looking up a  light client update value by period number
@@ -1,11 +1,30 @@
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
-use ethportal_api::BeaconContentKey;
+use ssz::Decode;
 use tokio::sync::RwLock;
+use tree_hash::TreeHash;
 
+use ethportal_api::{
+    types::{
+        consensus::light_client::{bootstrap::LightClientBootstrap, update::LightClientUpdate},
+        content_value::beacon::{
+            ForkVersionedLightClientBootstrap, ForkVersionedLightClientFinalityUpdate,
+            ForkVersionedLightClientOptimisticUpdate, LightClientUpdatesByRange,
+        },
+    },
+    utils::bytes::hex_encode,
+    BeaconContentKey,
+};
 use trin_validation::{oracle::HeaderOracle, validator::Validator};
 
+/// Number of slots in a sync committee period.
+const SLOTS_PER_PERIOD: u64 = 32 * 256;
+
+/// Length of the fork digest that prefixes every beacon content value.
+const FORK_DIGEST_LENGTH: usize = 4;
+
 pub struct BeaconValidator {
     // TODO: HeaderOracle is not network agnostic name
     pub header_oracle: Arc<RwLock<HeaderOracle>>,
@@ -15,13 +34,181 @@ pub struct BeaconValidator {
 impl Validator<BeaconContentKey> for BeaconValidator {
     async fn validate_content(
         &self,
-        _content_key: &BeaconContentKey,
-        _content: &[u8],
+        content_key: &BeaconContentKey,
+        content: &[u8],
     ) -> anyhow::Result<()>
     where
         BeaconContentKey: 'async_trait,
     {
-        // todo: implement beacon network validation
-        Ok(())
+        // Fork versioned values are decoded by slicing off the fork digest, which panics when the
+        // content is shorter than the digest.
+        if content.len() < FORK_DIGEST_LENGTH {
+            return Err(anyhow!(
+                "Beacon content is too short to include a fork digest: {}",
+                hex_encode(content)
+            ));
+        }
+        // todo: verify the sync committee signatures against a trusted light client state
+        match content_key {
+            BeaconContentKey::LightClientBootstrap(key) => {
+                let bootstrap = ForkVersionedLightClientBootstrap::from_ssz_bytes(content)
+                    .map_err(|err| {
+                        anyhow!("Light client bootstrap content has invalid encoding: {err:?}")
+                    })?;
+                let header = match &bootstrap.bootstrap {
+                    LightClientBootstrap::Bellatrix(bootstrap) => &bootstrap.header.beacon,
+                    LightClientBootstrap::Capella(bootstrap) => &bootstrap.header.beacon,
+                };
+                let block_root = header.tree_hash_root();
+                if block_root.0 != key.block_hash {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid bootstrap block root. Found: {block_root:?} - Expected: {:?}",
+                        hex_encode(key.block_hash)
+                    ));
+                }
+                Ok(())
+            }
+            BeaconContentKey::LightClientUpdatesByRange(key) => {
+                let updates =
+                    LightClientUpdatesByRange::from_ssz_bytes(content).map_err(|err| {
+                        anyhow!(
+                            "Light client updates by range content has invalid encoding: {err:?}"
+                        )
+                    })?;
+                if updates.len() as u64 != key.count {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid number of updates. Found: {} - Expected: {}",
+                        updates.len(),
+                        key.count
+                    ));
+                }
+                for (period, update) in (key.start_period..).zip(updates.iter()) {
+                    let attested_slot = match &update.update {
+                        LightClientUpdate::Bellatrix(update) => update.attested_header.beacon.slot,
+                        LightClientUpdate::Capella(update) => update.attested_header.beacon.slot,
+                    };
+                    if attested_slot / SLOTS_PER_PERIOD != period {
+                        return Err(anyhow!(
+                            "Content validation failed: Update for period {period} was attested at slot {attested_slot}"
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            BeaconContentKey::LightClientFinalityUpdate(_) => {
+                ForkVersionedLightClientFinalityUpdate::from_ssz_bytes(content).map_err(|err| {
+                    anyhow!("Light client finality update content has invalid encoding: {err:?}")
+                })?;
+                Ok(())
+            }
+            BeaconContentKey::LightClientOptimisticUpdate(_) => {
+                ForkVersionedLightClientOptimisticUpdate::from_ssz_bytes(content).map_err(
+                    |err| {
+                        anyhow!(
+                            "Light client optimistic update content has invalid encoding: {err:?}"
+                        )
+                    },
+                )?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    use serde_json::Value;
+
+    use ethportal_api::{
+        types::cli::DEFAULT_MASTER_ACC_PATH, utils::bytes::hex_decode, LightClientBootstrapKey,
+        LightClientUpdatesByRangeKey,
+    };
+    use trin_validation::accumulator::MasterAccumulator;
+
+    fn validator() -> BeaconValidator {
+        let master_acc =
+            MasterAccumulator::try_from_file(PathBuf::from(DEFAULT_MASTER_ACC_PATH.to_string()))
+                .unwrap();
+        BeaconValidator {
+            header_oracle: Arc::new(RwLock::new(HeaderOracle::new(master_acc))),
+        }
+    }
+
+    /// Returns the content key and value of the test vector in `file`.
+    fn test_vector(file: &str) -> (BeaconContentKey, Vec<u8>) {
+        let file =
+            fs::read_to_string(format!("../test_assets/portalnet/content/beacon/{file}")).unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let (_, vector) = json.as_object().unwrap().iter().next().unwrap();
+        let content_key = hex_decode(vector["content_key"].as_str().unwrap()).unwrap();
+        let content_key = BeaconContentKey::from_ssz_bytes(&content_key).unwrap();
+        let content_value = hex_decode(vector["content_value"].as_str().unwrap()).unwrap();
+        (content_key, content_value)
+    }
+
+    #[tokio::test]
+    async fn validate_test_vectors() {
+        let validator = validator();
+        for file in [
+            "light_client_bootstrap.json",
+            "light_client_updates_by_range.json",
+            "light_client_finality_update.json",
+            "light_client_optimistic_update.json",
+        ] {
+            let (content_key, content_value) = test_vector(file);
+            validator
+                .validate_content(&content_key, &content_value)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn invalidate_bootstrap_with_wrong_block_root() {
+        let (_, content_value) = test_vector("light_client_bootstrap.json");
+        let content_key = BeaconContentKey::LightClientBootstrap(LightClientBootstrapKey {
+            block_hash: [0; 32],
+        });
+        assert!(validator()
+            .validate_content(&content_key, &content_value)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn invalidate_updates_with_wrong_range() {
+        let (content_key, content_value) = test_vector("light_client_updates_by_range.json");
+        let BeaconContentKey::LightClientUpdatesByRange(key) = content_key else {
+            panic!("Unexpected content key");
+        };
+        for key in [
+            LightClientUpdatesByRangeKey {
+                start_period: key.start_period,
+                count: key.count + 1,
+            },
+            LightClientUpdatesByRangeKey {
+                start_period: key.start_period + 1,
+                count: key.count,
+            },
+        ] {
+            let content_key = BeaconContentKey::LightClientUpdatesByRange(key);
+            assert!(validator()
+                .validate_content(&content_key, &content_value)
+                .await
+                .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn invalidate_truncated_content() {
+        let (content_key, _) = test_vector("light_client_finality_update.json");
+        assert!(validator()
+            .validate_content(&content_key, &[0xbb, 0xa4])
+            .await
+            .is_err());
     }
 }
@@ -0,0 +1,69 @@
+use crate::network::CanonicalIndicesNetwork;
+use ethportal_api::types::portal_wire::Message;
+use portalnet::events::OverlayRequest;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{error, warn, Instrument};
+
+pub struct CanonicalIndicesEvents {
+    pub network: Arc<CanonicalIndicesNetwork>,
+    pub message_rx: UnboundedReceiver<OverlayRequest>,
+}
+
+impl CanonicalIndicesEvents {
+    pub async fn start(mut self) {
+        loop {
+            tokio::select! {
+                Some(msg) = self.message_rx.recv() => {
+                    self.handle_canonical_indices_message(msg);
+                } else => {
+                    error!("Canonical indices event channel closed, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Handle canonical indices network OverlayRequest.
+    fn handle_canonical_indices_message(&self, msg: OverlayRequest) {
+        let network = Arc::clone(&self.network);
+        tokio::spawn(async move {
+            match msg {
+                OverlayRequest::Talk(talk_request) => {
+                    Self::handle_talk_request(talk_request, &network).await
+                }
+                OverlayRequest::Event(event) => {
+                    let _ = network.overlay.process_one_event(event).await;
+                }
+            }
+        });
+    }
+
+    /// Handle canonical indices network TALKREQ message.
+    async fn handle_talk_request(
+        request: discv5::TalkRequest,
+        network: &Arc<CanonicalIndicesNetwork>,
+    ) {
+        let talk_request_id = request.id().clone();
+        let reply = match network
+            .overlay
+            .process_one_request(&request)
+            .instrument(tracing::info_span!("canonical_indices_network", req = %talk_request_id))
+            .await
+        {
+            Ok(response) => Message::from(response).into(),
+            Err(error) => {
+                error!(
+                    error = %error,
+                    request.discv5.id = %talk_request_id,
+                    "Error processing portal canonical indices request, responding with empty TALKRESP"
+                );
+                // Return an empty TALKRESP if there was an error executing the request
+                "".into()
+            }
+        };
+        if let Err(error) = request.respond(reply) {
+            warn!(error = %error, request.discv5.id = %talk_request_id, "Error responding to TALKREQ");
+        }
+    }
+}
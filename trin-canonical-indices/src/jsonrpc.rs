@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use ethportal_api::{
+    types::{
+        constants::CONTENT_ABSENT,
+        jsonrpc::{
+            endpoints::CanonicalIndicesEndpoint, error::JsonRpcError,
+            request::CanonicalIndicesJsonRpcRequest,
+        },
+    },
+    utils::bytes::hex_encode,
+    CanonicalIndicesContentKey, ContentValue, TransactionIndex,
+};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tracing::{error, Instrument};
+
+use crate::network::CanonicalIndicesNetwork;
+
+/// Handles Canonical Indices network JSON-RPC requests
+pub struct CanonicalIndicesRequestHandler {
+    pub network: Arc<CanonicalIndicesNetwork>,
+    pub canonical_indices_rx: mpsc::UnboundedReceiver<CanonicalIndicesJsonRpcRequest>,
+}
+
+impl CanonicalIndicesRequestHandler {
+    /// Complete RPC requests for the Canonical Indices network.
+    pub async fn handle_client_queries(mut self) {
+        while let Some(request) = self.canonical_indices_rx.recv().await {
+            let network = self.network.clone();
+            let span = request.span.clone();
+            tokio::spawn(async move { complete_request(network, request).await }.instrument(span));
+        }
+    }
+}
+
+/// Generates a response for a given request and sends it to the receiver.
+async fn complete_request(
+    network: Arc<CanonicalIndicesNetwork>,
+    request: CanonicalIndicesJsonRpcRequest,
+) {
+    let response: Result<Value, JsonRpcError> = match request.endpoint {
+        CanonicalIndicesEndpoint::LocalContent(content_key) => {
+            local_content(network, content_key).await
+        }
+        CanonicalIndicesEndpoint::Store(content_key, content_value) => {
+            store(network, content_key, content_value).await
+        }
+        CanonicalIndicesEndpoint::RecursiveFindContent(content_key) => {
+            recursive_find_content(network, content_key).await
+        }
+    };
+    let _ = request.resp.send(response);
+}
+
+/// Constructs a JSON call for the RecursiveFindContent method.
+async fn recursive_find_content(
+    network: Arc<CanonicalIndicesNetwork>,
+    content_key: CanonicalIndicesContentKey,
+) -> Result<Value, JsonRpcError> {
+    // Check whether we have the data locally.
+    let local_content = match network.overlay.store.get(content_key.clone()).await {
+        Ok(Some(data)) => Some(data),
+        Ok(None) => None,
+        Err(err) => {
+            error!(
+                error = %err,
+                content.key = %content_key,
+                "Error checking data store for content",
+            );
+            None
+        }
+    };
+    let (possible_content_bytes, utp_transfer) = match local_content {
+        Some(val) => (Some(val), false),
+        None => {
+            let (content, utp_transfer, _trace) =
+                network.overlay.lookup_content(content_key, false).await;
+            (content, utp_transfer)
+        }
+    };
+
+    let content = match possible_content_bytes {
+        Some(bytes) => hex_encode(bytes),
+        None => CONTENT_ABSENT.to_string(),
+    };
+    Ok(json!({
+        "content": content,
+        "utpTransfer": utp_transfer,
+    }))
+}
+
+/// Constructs a JSON call for the LocalContent method.
+async fn local_content(
+    network: Arc<CanonicalIndicesNetwork>,
+    content_key: CanonicalIndicesContentKey,
+) -> Result<Value, JsonRpcError> {
+    match network.overlay.store.get(content_key.clone()).await {
+        Ok(Some(val)) => Ok(Value::String(hex_encode(val))),
+        Ok(None) => Ok(Value::String(CONTENT_ABSENT.to_string())),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the Store method.
+async fn store(
+    network: Arc<CanonicalIndicesNetwork>,
+    content_key: CanonicalIndicesContentKey,
+    content_value: TransactionIndex,
+) -> Result<Value, JsonRpcError> {
+    let data = content_value.encode();
+    match network.overlay.store.put(content_key.clone(), data).await {
+        Ok(_) => Ok(Value::Bool(true)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while storing content key: {content_key:?}, with error: {err}"
+        ))),
+    }
+}
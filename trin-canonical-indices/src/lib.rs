@@ -0,0 +1,107 @@
+#![warn(clippy::unwrap_used)]
+#![warn(clippy::uninlined_format_args)]
+
+use std::sync::Arc;
+
+use network::CanonicalIndicesNetwork;
+use tokio::{
+    sync::{broadcast, mpsc, watch, RwLock},
+    task::JoinHandle,
+};
+use tracing::info;
+use utp_rs::socket::UtpSocket;
+
+use crate::{events::CanonicalIndicesEvents, jsonrpc::CanonicalIndicesRequestHandler};
+use ethportal_api::types::{enr::Enr, jsonrpc::request::CanonicalIndicesJsonRpcRequest};
+use portalnet::{
+    config::PortalnetConfig,
+    discovery::{Discovery, UtpEnr},
+    events::{EventEnvelope, OverlayRequest},
+};
+use trin_storage::PortalStorageConfig;
+use trin_validation::oracle::HeaderOracle;
+
+pub mod events;
+mod jsonrpc;
+pub mod network;
+mod storage;
+pub mod validation;
+
+type CanonicalIndicesHandler = Option<CanonicalIndicesRequestHandler>;
+type CanonicalIndicesNetworkTask = Option<JoinHandle<()>>;
+type CanonicalIndicesEventTx = Option<mpsc::UnboundedSender<OverlayRequest>>;
+type CanonicalIndicesJsonRpcTx = Option<mpsc::UnboundedSender<CanonicalIndicesJsonRpcRequest>>;
+type CanonicalIndicesEventStream = Option<broadcast::Receiver<EventEnvelope>>;
+
+pub async fn initialize_canonical_indices_network(
+    discovery: &Arc<Discovery>,
+    utp_socket: Arc<UtpSocket<UtpEnr>>,
+    portalnet_config: PortalnetConfig,
+    storage_config: PortalStorageConfig,
+    header_oracle: Arc<RwLock<HeaderOracle>>,
+    shutdown: watch::Receiver<bool>,
+) -> anyhow::Result<(
+    CanonicalIndicesHandler,
+    CanonicalIndicesNetworkTask,
+    CanonicalIndicesEventTx,
+    CanonicalIndicesJsonRpcTx,
+    CanonicalIndicesEventStream,
+)> {
+    let (jsonrpc_tx, jsonrpc_rx) = mpsc::unbounded_channel::<CanonicalIndicesJsonRpcRequest>();
+    let (event_tx, event_rx) = mpsc::unbounded_channel::<OverlayRequest>();
+    let network = CanonicalIndicesNetwork::new(
+        Arc::clone(discovery),
+        utp_socket,
+        storage_config,
+        portalnet_config.clone(),
+        header_oracle,
+    )
+    .await?;
+    let network = Arc::new(network);
+    let event_stream = network.overlay.event_stream().await?;
+    let handler = CanonicalIndicesRequestHandler {
+        network: Arc::clone(&network),
+        canonical_indices_rx: jsonrpc_rx,
+    };
+    let network_task =
+        spawn_canonical_indices_network(Arc::clone(&network), portalnet_config, event_rx, shutdown);
+    Ok((
+        Some(handler),
+        Some(network_task),
+        Some(event_tx),
+        Some(jsonrpc_tx),
+        Some(event_stream),
+    ))
+}
+
+pub fn spawn_canonical_indices_network(
+    network: Arc<CanonicalIndicesNetwork>,
+    portalnet_config: PortalnetConfig,
+    message_rx: mpsc::UnboundedReceiver<OverlayRequest>,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    let bootnode_enrs: Vec<Enr> = portalnet_config.bootnodes.into();
+    info!(
+        "About to spawn Canonical Indices Network with {} boot nodes.",
+        bootnode_enrs.len()
+    );
+
+    tokio::spawn(async move {
+        let events = CanonicalIndicesEvents {
+            network: Arc::clone(&network),
+            message_rx,
+        };
+
+        // Spawn canonical indices event handler
+        tokio::spawn(events.start());
+
+        // hacky test: make sure we establish a session with the boot node
+        network.overlay.ping_bootnodes().await;
+
+        // The overlay is stopped once the node shuts down. It keeps running if the node is dropped
+        // without shutting down.
+        if shutdown.wait_for(|shutdown| *shutdown).await.is_ok() {
+            network.overlay.shutdown().await;
+        }
+    })
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use utp_rs::socket::UtpSocket;
+
+use crate::{storage::CanonicalIndicesStorage, validation::CanonicalIndicesValidator};
+use ethportal_api::{
+    types::{distance::XorMetric, enr::Enr, portal_wire::ProtocolId},
+    CanonicalIndicesContentKey,
+};
+use portalnet::{
+    config::PortalnetConfig,
+    discovery::{Discovery, UtpEnr},
+    overlay::{OverlayConfig, OverlayProtocol},
+};
+use trin_storage::PortalStorageConfig;
+use trin_validation::oracle::HeaderOracle;
+
+/// Canonical indices network layer on top of the overlay protocol. Encapsulates canonical indices
+/// network specific data and logic.
+#[derive(Clone)]
+pub struct CanonicalIndicesNetwork {
+    pub overlay: Arc<
+        OverlayProtocol<
+            CanonicalIndicesContentKey,
+            XorMetric,
+            CanonicalIndicesValidator,
+            CanonicalIndicesStorage,
+        >,
+    >,
+}
+
+impl CanonicalIndicesNetwork {
+    pub async fn new(
+        discovery: Arc<Discovery>,
+        utp_socket: Arc<UtpSocket<UtpEnr>>,
+        storage_config: PortalStorageConfig,
+        portal_config: PortalnetConfig,
+        header_oracle: Arc<RwLock<HeaderOracle>>,
+    ) -> anyhow::Result<Self> {
+        let storage = CanonicalIndicesStorage::new(storage_config, ProtocolId::CanonicalIndices);
+        let validator = Arc::new(CanonicalIndicesValidator { header_oracle });
+        let bootnode_enrs: Vec<Enr> = portal_config.bootnodes.into();
+        let config = OverlayConfig {
+            bootnode_enrs,
+            query_parallelism: portal_config.query_parallelism,
+            query_peer_timeout: portal_config.query_peer_timeout,
+            query_timeout: portal_config.query_timeout,
+            ..Default::default()
+        };
+        let overlay = OverlayProtocol::new(
+            config,
+            discovery,
+            utp_socket,
+            storage,
+            ProtocolId::CanonicalIndices,
+            validator,
+        )
+        .await;
+
+        Ok(Self {
+            overlay: Arc::new(overlay),
+        })
+    }
+}
@@ -0,0 +1,152 @@
+use discv5::enr::NodeId;
+use ethportal_api::{
+    types::{
+        distance::{Distance, Metric, XorMetric},
+        portal_wire::ProtocolId,
+    },
+    utils::bytes::hex_encode,
+    OverlayContentKey,
+};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use tracing::debug;
+use trin_storage::{
+    error::ContentStoreError,
+    utils::{insert_value, lookup_content_value},
+    ContentStore, DistanceFunction, PortalStorageConfig, ShouldWeStoreContent,
+};
+
+/// Storage layer for the canonical indices network.
+///
+/// Indices are stored in the shared content database, tagged with the canonical indices protocol.
+/// Each index is only a few bytes, so the whole keyspace is covered and nothing is pruned yet.
+#[derive(Debug)]
+pub struct CanonicalIndicesStorage {
+    node_id: NodeId,
+    radius: Distance,
+    sql_connection_pool: Pool<SqliteConnectionManager>,
+    distance_fn: DistanceFunction,
+    network: ProtocolId,
+    /// Whether the database was opened read-only, in which case indices are never stored.
+    read_only: bool,
+}
+
+impl ContentStore for CanonicalIndicesStorage {
+    fn get<K: OverlayContentKey>(&self, key: &K) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        lookup_content_value(key.content_id(), conn, u8::from(self.network))
+            .and_then(|result| result)
+            .map_err(|err| {
+                ContentStoreError::Database(format!("Error looking up content value: {err:?}"))
+            })
+    }
+
+    fn put<K: OverlayContentKey, V: AsRef<[u8]>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<(), ContentStoreError> {
+        if self.read_only {
+            return Err(ContentStoreError::ReadOnly);
+        }
+        let content_id = key.content_id();
+        let distance = self.distance_to_content_id(&content_id);
+        if distance > self.radius {
+            debug!("Not storing: {:02X?}", key.to_bytes());
+            return Err(ContentStoreError::InsufficientRadius {
+                radius: self.radius,
+                distance,
+            });
+        }
+
+        // store content key w/o the 0x prefix
+        let content_key = hex_encode(key.to_bytes())
+            .trim_start_matches("0x")
+            .to_string();
+        let conn = self.sql_connection_pool.get()?;
+        insert_value(
+            conn,
+            &content_id,
+            &content_key,
+            &value.as_ref().to_vec(),
+            u8::from(self.network),
+        )
+    }
+
+    fn is_key_within_radius_and_unavailable<K: OverlayContentKey>(
+        &self,
+        key: &K,
+    ) -> Result<ShouldWeStoreContent, ContentStoreError> {
+        if self.distance_to_content_id(&key.content_id()) > self.radius {
+            return Ok(ShouldWeStoreContent::NotWithinRadius);
+        }
+        match self.get(key)? {
+            Some(_) => Ok(ShouldWeStoreContent::AlreadyStored),
+            // Indices offered to a read-only store are declined, as if outside the radius.
+            None if self.read_only => Ok(ShouldWeStoreContent::NotWithinRadius),
+            None => Ok(ShouldWeStoreContent::Store),
+        }
+    }
+
+    fn radius(&self) -> Distance {
+        self.radius
+    }
+}
+
+impl CanonicalIndicesStorage {
+    pub fn new(config: PortalStorageConfig, protocol: ProtocolId) -> Self {
+        Self {
+            node_id: config.node_id,
+            radius: Distance::MAX,
+            sql_connection_pool: config.sql_connection_pool,
+            distance_fn: config.distance_fn,
+            network: protocol,
+            read_only: config.backend.is_read_only(),
+        }
+    }
+
+    /// Returns the distance to `content_id` from the local `NodeId` according to the distance
+    /// function.
+    fn distance_to_content_id(&self, content_id: &[u8; 32]) -> Distance {
+        match self.distance_fn {
+            DistanceFunction::Xor => XorMetric::distance(content_id, &self.node_id.raw()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ethportal_api::{CanonicalIndicesContentKey, TransactionIndexKey};
+    use portalnet::utils::db::setup_temp_dir;
+
+    #[test]
+    fn store_and_lookup_index() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let storage_config =
+            PortalStorageConfig::new(100, temp_dir.path().to_path_buf(), NodeId::random()).unwrap();
+        let mut storage =
+            CanonicalIndicesStorage::new(storage_config, ProtocolId::CanonicalIndices);
+        let content_key = CanonicalIndicesContentKey::TransactionIndex(TransactionIndexKey {
+            transaction_hash: [1; 32],
+        });
+        assert_eq!(storage.get(&content_key).unwrap(), None);
+        assert_eq!(
+            storage
+                .is_key_within_radius_and_unavailable(&content_key)
+                .unwrap(),
+            ShouldWeStoreContent::Store
+        );
+
+        storage.put(content_key.clone(), [2; 40]).unwrap();
+        assert_eq!(storage.get(&content_key).unwrap(), Some(vec![2; 40]));
+        assert_eq!(
+            storage
+                .is_key_within_radius_and_unavailable(&content_key)
+                .unwrap(),
+            ShouldWeStoreContent::AlreadyStored
+        );
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethereum_types::H256;
+use ssz::Decode;
+use tokio::sync::RwLock;
+
+use ethportal_api::{utils::bytes::hex_encode, CanonicalIndicesContentKey, TransactionIndex};
+use trin_validation::{oracle::HeaderOracle, validator::Validator};
+
+/// Checks transaction indices against the block bodies of the chain history network.
+pub struct CanonicalIndicesValidator {
+    pub header_oracle: Arc<RwLock<HeaderOracle>>,
+}
+
+#[async_trait]
+impl Validator<CanonicalIndicesContentKey> for CanonicalIndicesValidator {
+    async fn validate_content(
+        &self,
+        content_key: &CanonicalIndicesContentKey,
+        content: &[u8],
+    ) -> anyhow::Result<()>
+    where
+        CanonicalIndicesContentKey: 'async_trait,
+    {
+        match content_key {
+            CanonicalIndicesContentKey::TransactionIndex(key) => {
+                let index = TransactionIndex::from_ssz_bytes(content).map_err(|err| {
+                    anyhow!("Transaction index content has invalid encoding: {err:?}")
+                })?;
+                let block_hash = H256::from(index.block_hash);
+                let transactions = self
+                    .header_oracle
+                    .read()
+                    .await
+                    .recursive_find_block_body(block_hash)
+                    .await?
+                    .transactions()?;
+                let transaction = usize::try_from(index.index)
+                    .ok()
+                    .and_then(|index| transactions.get(index))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Content validation failed: Block {block_hash:?} has {} transactions, so there is no transaction at index {}",
+                            transactions.len(),
+                            index.index
+                        )
+                    })?;
+                let transaction_hash = transaction.hash();
+                if transaction_hash != H256::from(key.transaction_hash) {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid transaction hash. Found: {transaction_hash:?} - Expected: {:?}",
+                        hex_encode(key.transaction_hash)
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use serde_json::json;
+    use ssz::Encode;
+    use tokio::sync::mpsc;
+
+    use ethportal_api::{
+        types::{
+            execution::{
+                block_body::{BlockBody, BlockBodyLegacy},
+                transaction::Transaction,
+            },
+            history::ContentInfo,
+            jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
+        },
+        utils::bytes::hex_decode,
+        HistoryContentKey, HistoryContentValue, PossibleHistoryContentValue, TransactionIndexKey,
+    };
+    use trin_validation::accumulator::MasterAccumulator;
+
+    /// The signed example transaction of EIP-155.
+    const TRANSACTION: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    const BLOCK_HASH: [u8; 32] = [7; 32];
+
+    /// Returns a validator whose history network serves a block body holding only the example
+    /// transaction, for `BLOCK_HASH`.
+    fn validator() -> CanonicalIndicesValidator {
+        let (history_tx, mut history_rx) = mpsc::unbounded_channel::<HistoryJsonRpcRequest>();
+        tokio::spawn(async move {
+            while let Some(request) = history_rx.recv().await {
+                let content = match request.endpoint {
+                    HistoryEndpoint::RecursiveFindContent(HistoryContentKey::BlockBody(key))
+                        if key.block_hash == BLOCK_HASH =>
+                    {
+                        let body = BlockBody::Legacy(BlockBodyLegacy {
+                            txs: vec![transaction()],
+                            uncles: vec![],
+                        });
+                        PossibleHistoryContentValue::ContentPresent(HistoryContentValue::BlockBody(
+                            body,
+                        ))
+                    }
+                    _ => PossibleHistoryContentValue::ContentAbsent,
+                };
+                let content_info = ContentInfo::Content {
+                    content,
+                    utp_transfer: false,
+                };
+                let _ = request.resp.send(Ok(json!(content_info)));
+            }
+        });
+        let mut header_oracle = HeaderOracle::new(MasterAccumulator::default());
+        header_oracle.history_jsonrpc_tx = Some(history_tx);
+        CanonicalIndicesValidator {
+            header_oracle: Arc::new(RwLock::new(header_oracle)),
+        }
+    }
+
+    fn transaction() -> Transaction {
+        Transaction::decode(&hex_decode(TRANSACTION).unwrap()).unwrap()
+    }
+
+    fn content_key() -> CanonicalIndicesContentKey {
+        CanonicalIndicesContentKey::TransactionIndex(TransactionIndexKey {
+            transaction_hash: transaction().hash().0,
+        })
+    }
+
+    fn content(block_hash: [u8; 32], index: u64) -> Vec<u8> {
+        TransactionIndex { block_hash, index }.as_ssz_bytes()
+    }
+
+    #[tokio::test]
+    async fn validate_transaction_index() {
+        validator()
+            .validate_content(&content_key(), &content(BLOCK_HASH, 0))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_index_out_of_range() {
+        assert!(validator()
+            .validate_content(&content_key(), &content(BLOCK_HASH, 1))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_index_of_other_transaction() {
+        let content_key = CanonicalIndicesContentKey::TransactionIndex(TransactionIndexKey {
+            transaction_hash: [0; 32],
+        });
+        assert!(validator()
+            .validate_content(&content_key, &content(BLOCK_HASH, 0))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_index_of_unknown_block() {
+        assert!(validator()
+            .validate_content(&content_key(), &content([8; 32], 0))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ssz::Decode;
+use tokio::sync::{mpsc, RwLock};
+use tracing::warn;
+
+use ethportal_api::{
+    types::{
+        execution::header::{Header, HeaderWithProof},
+        history::ImportLocalContentInfo,
+        portal_wire::ProtocolId,
+    },
+    utils::bytes::hex_encode,
+    BlockHeaderKey, HistoryContentKey,
+};
+use portalnet::store_actor::StoreHandle;
+use trin_storage::{archive::ArchiveReader, error::ContentStoreError, PortalStorageConfig};
+use trin_validation::{accumulator::MasterAccumulator, oracle::HeaderOracle, validator::Validator};
+
+use crate::{
+    storage::HistoryStorage,
+    validation::{validate_block_body, validate_receipts, ChainHistoryValidator},
+};
+
+/// Number of content items that are read from an archive ahead of their validation.
+const IMPORT_READ_AHEAD: usize = 64;
+
+/// Writes the history network content of the local storage to a content archive at `path`, for
+/// the same range of content as local content key pagination. Returns the number of exported items.
+pub fn export_content(
+    storage_config: PortalStorageConfig,
+    path: &Path,
+    offset: u64,
+    limit: u64,
+) -> anyhow::Result<u64> {
+    let storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+    Ok(storage.archive_exporter().export(path, offset, limit)?)
+}
+
+/// Stores the validated content of a content archive at `path` in the history network storage,
+/// without access to the network. Headers are validated against the master accumulator, and block
+/// bodies and receipts against the headers in the storage.
+pub async fn import_content(
+    storage_config: PortalStorageConfig,
+    path: PathBuf,
+    master_acc: MasterAccumulator,
+) -> anyhow::Result<ImportLocalContentInfo> {
+    let store = StoreHandle::spawn(
+        HistoryStorage::new(storage_config, ProtocolId::History)?,
+        "history-store".to_string(),
+    );
+    let validator = StoredHeaderValidator {
+        validator: ChainHistoryValidator {
+            header_oracle: Arc::new(RwLock::new(HeaderOracle::new(master_acc))),
+        },
+        store: store.clone(),
+    };
+    import_archive(&store, path, &validator).await
+}
+
+/// Stores the content of a content archive at `path`, exported by another node. Content that fails
+/// validation or falls outside the radius is skipped, and the store is pruned as usual when over
+/// capacity.
+///
+/// The archive is read on the blocking thread pool, and only the validated items are sent to the
+/// storage actor.
+pub async fn import_archive<TValidator: Validator<HistoryContentKey>>(
+    store: &StoreHandle<HistoryStorage>,
+    path: PathBuf,
+    validator: &TValidator,
+) -> anyhow::Result<ImportLocalContentInfo> {
+    let (entry_tx, mut entry_rx) = mpsc::channel(IMPORT_READ_AHEAD);
+    let reader = tokio::task::spawn_blocking(move || -> Result<(), ContentStoreError> {
+        let archive = ArchiveReader::new(BufReader::new(File::open(path)?))?;
+        if archive.network() != u8::from(ProtocolId::History) {
+            return Err(ContentStoreError::InvalidData {
+                message: format!(
+                    "Archive holds content of network {}, not of the history network",
+                    archive.network()
+                ),
+            });
+        }
+        for entry in archive {
+            // The import was aborted if the entries aren't received anymore.
+            if entry_tx.blocking_send(entry?).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    let mut info = ImportLocalContentInfo {
+        stored: 0,
+        skipped: 0,
+        invalid: 0,
+    };
+    while let Some((content_key, content_value)) = entry_rx.recv().await {
+        let validation = match HistoryContentKey::try_from(content_key.clone()) {
+            Ok(key) => validator
+                .validate_content(&key, &content_value)
+                .await
+                .map(|()| key),
+            Err(err) => Err(err.into()),
+        };
+        let content_key = match validation {
+            Ok(key) => key,
+            Err(err) => {
+                warn!(
+                    content.key = %hex_encode(content_key),
+                    "Skipping invalid archive content: {err}"
+                );
+                info.invalid += 1;
+                continue;
+            }
+        };
+        match store.put(content_key, content_value).await {
+            Ok(()) => info.stored += 1,
+            Err(ContentStoreError::InsufficientRadius { .. }) => info.skipped += 1,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    reader.await??;
+    Ok(info)
+}
+
+/// Validates content without the network, looking up the headers of block bodies and receipts in
+/// the local storage. Archives are ordered by content key, so they hold headers before the bodies
+/// and receipts of their blocks, and those headers are validated and stored first.
+struct StoredHeaderValidator {
+    validator: ChainHistoryValidator,
+    store: StoreHandle<HistoryStorage>,
+}
+
+impl StoredHeaderValidator {
+    async fn stored_header(&self, block_hash: [u8; 32]) -> anyhow::Result<Header> {
+        let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey { block_hash });
+        let header_with_proof = self.store.get(content_key).await?.ok_or_else(|| {
+            anyhow!(
+                "Content validation failed: Header of block {} isn't stored",
+                hex_encode(block_hash)
+            )
+        })?;
+        let header_with_proof = HeaderWithProof::from_ssz_bytes(&header_with_proof)
+            .map_err(|err| anyhow!("Stored header with proof has invalid encoding: {err:?}"))?;
+        Ok(header_with_proof.header)
+    }
+}
+
+#[async_trait]
+impl Validator<HistoryContentKey> for StoredHeaderValidator {
+    async fn validate_content(
+        &self,
+        content_key: &HistoryContentKey,
+        content: &[u8],
+    ) -> anyhow::Result<()>
+    where
+        HistoryContentKey: 'async_trait,
+    {
+        match content_key {
+            HistoryContentKey::BlockBody(key) => {
+                validate_block_body(content, &self.stored_header(key.block_hash).await?)
+            }
+            HistoryContentKey::BlockReceipts(key) => {
+                validate_receipts(content, &self.stored_header(key.block_hash).await?)
+            }
+            _ => self.validator.validate_content(content_key, content).await,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::{fs, io::BufWriter};
+
+    use discv5::enr::NodeId;
+    use serde_json::Value;
+
+    use ethportal_api::{
+        types::cli::DEFAULT_MASTER_ACC_PATH, utils::bytes::hex_decode, BlockBodyKey,
+        OverlayContentKey,
+    };
+    use portalnet::utils::db::setup_temp_dir;
+    use trin_storage::{archive::ArchiveWriter, StorageBackend};
+
+    fn header_with_proof() -> (HistoryContentKey, Vec<u8>) {
+        let file =
+            fs::read_to_string("../trin-validation/src/assets/fluffy/header_with_proofs.json")
+                .unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let header = &json["1000001"];
+        let content_key = hex_decode(header["content_key"].as_str().unwrap()).unwrap();
+        let content_value = hex_decode(header["value"].as_str().unwrap()).unwrap();
+        (
+            HistoryContentKey::try_from(content_key).unwrap(),
+            content_value,
+        )
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn import_only_validated_content() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let (header_key, header) = header_with_proof();
+        // A header under the hash of another block, and a body without a stored header.
+        let forged_header_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: [1; 32],
+        });
+        let orphan_body_key = HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: [2; 32],
+        });
+
+        let archive_path = temp_dir.path().join("content.archive");
+        let file = BufWriter::new(File::create(&archive_path).unwrap());
+        let mut archive = ArchiveWriter::new(file, ProtocolId::History.into()).unwrap();
+        for (content_key, content_value) in [
+            (&header_key, &header),
+            (&forged_header_key, &header),
+            (&orphan_body_key, &vec![0; 8]),
+        ] {
+            archive
+                .write_entry(&content_key.to_bytes(), content_value)
+                .unwrap();
+        }
+        archive.finish().unwrap();
+
+        let storage_config = PortalStorageConfig::new_with_backend(
+            100,
+            temp_dir.path().to_path_buf(),
+            NodeId::random(),
+            StorageBackend::Memory,
+        )
+        .unwrap();
+        let store = StoreHandle::spawn(
+            HistoryStorage::new(storage_config, ProtocolId::History).unwrap(),
+            "test-store".to_string(),
+        );
+        let master_acc =
+            MasterAccumulator::try_from_file(PathBuf::from(DEFAULT_MASTER_ACC_PATH.to_string()))
+                .unwrap();
+        let validator = StoredHeaderValidator {
+            validator: ChainHistoryValidator {
+                header_oracle: Arc::new(RwLock::new(HeaderOracle::new(master_acc))),
+            },
+            store: store.clone(),
+        };
+        let info = import_archive(&store, archive_path, &validator)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            info,
+            ImportLocalContentInfo {
+                stored: 1,
+                skipped: 0,
+                invalid: 2,
+            }
+        );
+        assert_eq!(store.get(header_key).await.unwrap(), Some(header));
+        assert_eq!(store.get(forged_header_key).await.unwrap(), None);
+        assert_eq!(store.get(orphan_body_key).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,14 @@
+use ethportal_api::types::portal_wire::ProtocolId;
+use trin_storage::{check::DbCheckReport, PortalStorageConfig};
+
+use crate::storage::HistoryStorage;
+
+/// Checks the integrity of the history network content in the local storage, and deletes corrupt
+/// content if `repair` is set.
+pub fn check_content(
+    storage_config: PortalStorageConfig,
+    repair: bool,
+) -> anyhow::Result<DbCheckReport> {
+    let storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+    Ok(storage.check(repair)?)
+}
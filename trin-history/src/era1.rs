@@ -0,0 +1,574 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, ensure};
+use ethereum_types::{H256, U256};
+use tracing::info;
+use tree_hash::TreeHash;
+
+use ethportal_api::{
+    types::{
+        execution::{
+            accumulator::{EpochAccumulator, HeaderRecord},
+            block_body::{BlockBody, BlockBodyLegacy},
+            header::{AccumulatorProof, BlockHeaderProof, Header, HeaderWithProof},
+            receipts::Receipts,
+        },
+        portal_wire::ProtocolId,
+    },
+    BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, ContentValue, EpochAccumulatorKey,
+    HistoryContentKey, HistoryContentValue,
+};
+use trin_storage::{error::ContentStoreError, ContentStore, PortalStorageConfig};
+use trin_validation::{accumulator::MasterAccumulator, constants::EPOCH_SIZE};
+
+use crate::storage::HistoryStorage;
+
+/// The file extension of era1 archives.
+pub const ERA1_FILE_EXTENSION: &str = "era1";
+
+// Types of the e2store entries of era1 archives.
+const VERSION: u16 = 0x3265;
+const COMPRESSED_HEADER: u16 = 0x03;
+const COMPRESSED_BODY: u16 = 0x04;
+const COMPRESSED_RECEIPTS: u16 = 0x05;
+const TOTAL_DIFFICULTY: u16 = 0x06;
+const ACCUMULATOR: u16 = 0x07;
+const BLOCK_INDEX: u16 = 0x3266;
+
+/// The size of the header of an e2store entry: the type, the length of the value and two
+/// reserved bytes.
+const ENTRY_HEADER_SIZE: usize = 8;
+
+/// An entry of an e2store file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Entry {
+    entry_type: u16,
+    value: Vec<u8>,
+}
+
+impl Entry {
+    /// Reads the entries of an e2store file.
+    fn read_all(mut bytes: &[u8]) -> anyhow::Result<Vec<Self>> {
+        let mut entries = vec![];
+        while !bytes.is_empty() {
+            ensure!(
+                bytes.len() >= ENTRY_HEADER_SIZE,
+                "Truncated e2store entry header"
+            );
+            let entry_type = u16::from_le_bytes([bytes[0], bytes[1]]);
+            let length = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]) as usize;
+            ensure!(
+                bytes[6..ENTRY_HEADER_SIZE] == [0, 0],
+                "Reserved bytes of e2store entry header are not zero"
+            );
+            let value = bytes
+                .get(ENTRY_HEADER_SIZE..ENTRY_HEADER_SIZE + length)
+                .ok_or_else(|| anyhow!("Truncated e2store entry of type {entry_type:#06x}"))?;
+            entries.push(Self {
+                entry_type,
+                value: value.to_vec(),
+            });
+            bytes = &bytes[ENTRY_HEADER_SIZE + length..];
+        }
+        Ok(entries)
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.entry_type.to_le_bytes());
+        buf.extend_from_slice(&(self.value.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(&self.value);
+    }
+
+    /// Returns the value of the entry, after checking that the entry is of `entry_type`.
+    fn value_of_type(&self, entry_type: u16) -> anyhow::Result<&[u8]> {
+        ensure!(
+            self.entry_type == entry_type,
+            "Expected e2store entry of type {entry_type:#06x}, found {:#06x}",
+            self.entry_type
+        );
+        Ok(&self.value)
+    }
+
+    /// Returns the snappy-decompressed value of the entry, after checking that the entry is of
+    /// `entry_type`.
+    fn decompressed_value_of_type(&self, entry_type: u16) -> anyhow::Result<Vec<u8>> {
+        let mut decoder = snap::read::FrameDecoder::new(self.value_of_type(entry_type)?);
+        let mut value = vec![];
+        decoder.read_to_end(&mut value)?;
+        Ok(value)
+    }
+}
+
+/// A block of an era1 archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Era1Block {
+    pub header: Header,
+    pub body: BlockBodyLegacy,
+    pub receipts: Receipts,
+    pub total_difficulty: U256,
+}
+
+/// An era1 archive, which holds the blocks of an epoch of the pre-merge chain along with the
+/// root of their epoch accumulator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Era1 {
+    pub blocks: Vec<Era1Block>,
+    pub accumulator_root: H256,
+    /// The number of the first block.
+    pub starting_number: u64,
+}
+
+impl Era1 {
+    /// Decodes an era1 archive.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        let entries = Entry::read_all(bytes)?;
+        let (version, entries) = entries
+            .split_first()
+            .ok_or_else(|| anyhow!("Empty era1 archive"))?;
+        version.value_of_type(VERSION)?;
+        let [block_entries @ .., accumulator, block_index] = entries else {
+            bail!("Era1 archive is missing its accumulator or block index");
+        };
+        ensure!(
+            block_entries.len() % 4 == 0,
+            "Era1 archive has an incomplete block"
+        );
+
+        let blocks = block_entries
+            .chunks_exact(4)
+            .map(|block| {
+                let header = rlp::decode(&block[0].decompressed_value_of_type(COMPRESSED_HEADER)?)?;
+                let body = rlp::decode(&block[1].decompressed_value_of_type(COMPRESSED_BODY)?)?;
+                let receipts =
+                    rlp::decode(&block[2].decompressed_value_of_type(COMPRESSED_RECEIPTS)?)?;
+                let total_difficulty = block[3].value_of_type(TOTAL_DIFFICULTY)?;
+                ensure!(
+                    total_difficulty.len() == 32,
+                    "Invalid total difficulty length: {}",
+                    total_difficulty.len()
+                );
+                Ok(Era1Block {
+                    header,
+                    body,
+                    receipts,
+                    total_difficulty: U256::from_little_endian(total_difficulty),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let accumulator_root = accumulator.value_of_type(ACCUMULATOR)?;
+        ensure!(
+            accumulator_root.len() == 32,
+            "Invalid accumulator root length: {}",
+            accumulator_root.len()
+        );
+        let block_index = block_index.value_of_type(BLOCK_INDEX)?;
+        let starting_number = block_index
+            .get(..8)
+            .ok_or_else(|| anyhow!("Truncated era1 block index"))?;
+        Ok(Self {
+            blocks,
+            accumulator_root: H256::from_slice(accumulator_root),
+            starting_number: u64::from_le_bytes(starting_number.try_into()?),
+        })
+    }
+
+    /// Encodes the era1 archive. The offsets of the block index are left at zero, as they aren't
+    /// used when reading archives.
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let mut entries = vec![Entry {
+            entry_type: VERSION,
+            value: vec![],
+        }];
+        for block in &self.blocks {
+            let mut total_difficulty = [0; 32];
+            block
+                .total_difficulty
+                .to_little_endian(&mut total_difficulty);
+            entries.extend([
+                compressed_entry(COMPRESSED_HEADER, &rlp::encode(&block.header))?,
+                compressed_entry(COMPRESSED_BODY, &rlp::encode(&block.body))?,
+                compressed_entry(COMPRESSED_RECEIPTS, &rlp::encode(&block.receipts))?,
+                Entry {
+                    entry_type: TOTAL_DIFFICULTY,
+                    value: total_difficulty.to_vec(),
+                },
+            ]);
+        }
+        entries.push(Entry {
+            entry_type: ACCUMULATOR,
+            value: self.accumulator_root.as_bytes().to_vec(),
+        });
+        let mut block_index = self.starting_number.to_le_bytes().to_vec();
+        block_index.extend(vec![0; 8 * self.blocks.len()]);
+        block_index.extend_from_slice(&(self.blocks.len() as u64).to_le_bytes());
+        entries.push(Entry {
+            entry_type: BLOCK_INDEX,
+            value: block_index,
+        });
+
+        let mut buf = vec![];
+        for entry in entries {
+            entry.write(&mut buf);
+        }
+        Ok(buf)
+    }
+
+    /// Returns the epoch accumulator of the blocks.
+    pub fn epoch_accumulator(&self) -> anyhow::Result<EpochAccumulator> {
+        let header_records: Vec<HeaderRecord> = self
+            .blocks
+            .iter()
+            .map(|block| HeaderRecord {
+                block_hash: block.header.hash(),
+                total_difficulty: block.total_difficulty,
+            })
+            .collect();
+        EpochAccumulator::new(header_records)
+            .map_err(|err| anyhow!("Era1 archive has too many blocks: {err:?}"))
+    }
+
+    /// Checks that the blocks form an epoch of the canonical chain, according to
+    /// `master_accumulator`, and that the bodies and receipts match their headers.
+    ///
+    /// Returns the epoch accumulator of the blocks.
+    pub fn validate(
+        &self,
+        master_accumulator: &MasterAccumulator,
+    ) -> anyhow::Result<EpochAccumulator> {
+        ensure!(
+            self.starting_number % EPOCH_SIZE as u64 == 0,
+            "Era1 archive doesn't start at an epoch boundary: {}",
+            self.starting_number
+        );
+        for (number, block) in (self.starting_number..).zip(&self.blocks) {
+            ensure!(
+                block.header.number == number,
+                "Expected block {number}, found block {}",
+                block.header.number
+            );
+        }
+
+        let epoch_accumulator = self.epoch_accumulator()?;
+        let epoch_hash = epoch_accumulator.tree_hash_root();
+        ensure!(
+            epoch_hash == self.accumulator_root,
+            "Era1 accumulator root doesn't match its blocks: {:?} - {epoch_hash:?}",
+            self.accumulator_root
+        );
+        let epoch_index = self.starting_number / EPOCH_SIZE as u64;
+        let trusted_epoch_hash = usize::try_from(epoch_index)
+            .ok()
+            .and_then(|epoch_index| master_accumulator.historical_epochs.get(epoch_index))
+            .ok_or_else(|| anyhow!("Epoch {epoch_index} isn't in the master accumulator"))?;
+        ensure!(
+            epoch_hash == *trusted_epoch_hash,
+            "Era1 accumulator root doesn't match the master accumulator: {epoch_hash:?} - {trusted_epoch_hash:?}"
+        );
+
+        for block in &self.blocks {
+            BlockBody::Legacy(block.body.clone()).validate_against_header(&block.header)?;
+            let receipts_root = block.receipts.root()?;
+            ensure!(
+                receipts_root == block.header.receipts_root,
+                "Receipts root doesn't match header receipts root of block {}: {receipts_root:?} - {:?}",
+                block.header.number,
+                block.header.receipts_root
+            );
+        }
+        Ok(epoch_accumulator)
+    }
+
+    /// Returns the history network content of the archive: the epoch accumulator, and the header
+    /// with proof, the body and the receipts of every block.
+    pub fn content(
+        &self,
+        epoch_accumulator: &EpochAccumulator,
+    ) -> anyhow::Result<Vec<(HistoryContentKey, HistoryContentValue)>> {
+        let mut content = vec![(
+            HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+                epoch_hash: self.accumulator_root,
+            }),
+            HistoryContentValue::EpochAccumulator(epoch_accumulator.clone()),
+        )];
+        for block in &self.blocks {
+            let block_hash = block.header.hash().0;
+            let proof = MasterAccumulator::construct_proof(&block.header, epoch_accumulator)?;
+            content.extend([
+                (
+                    HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey { block_hash }),
+                    HistoryContentValue::BlockHeaderWithProof(HeaderWithProof {
+                        header: block.header.clone(),
+                        proof: BlockHeaderProof::AccumulatorProof(AccumulatorProof { proof }),
+                    }),
+                ),
+                (
+                    HistoryContentKey::BlockBody(BlockBodyKey { block_hash }),
+                    HistoryContentValue::BlockBody(BlockBody::Legacy(block.body.clone())),
+                ),
+                (
+                    HistoryContentKey::BlockReceipts(BlockReceiptsKey { block_hash }),
+                    HistoryContentValue::Receipts(block.receipts.clone()),
+                ),
+            ]);
+        }
+        Ok(content)
+    }
+}
+
+fn compressed_entry(entry_type: u16, value: &[u8]) -> anyhow::Result<Entry> {
+    let mut encoder = snap::write::FrameEncoder::new(vec![]);
+    std::io::Write::write_all(&mut encoder, value)?;
+    let value = encoder
+        .into_inner()
+        .map_err(|err| anyhow!("Unable to compress e2store entry: {err:?}"))?;
+    Ok(Entry { entry_type, value })
+}
+
+/// Finds the era1 archive of the epoch at `epoch_index` in `dir`, by the epoch index in its file
+/// name, which is of the form `<network>-<epoch index>-<short accumulator root>.era1`.
+pub fn find_era1_file(dir: &Path, epoch_index: u64) -> anyhow::Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path
+            .extension()
+            .is_some_and(|extension| extension == ERA1_FILE_EXTENSION)
+        {
+            continue;
+        }
+        let file_epoch_index = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.split('-').nth(1))
+            .and_then(|epoch_index| epoch_index.parse::<u64>().ok());
+        if file_epoch_index == Some(epoch_index) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// The outcome of importing era1 archives.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Era1ImportStats {
+    pub archives: usize,
+    pub blocks: usize,
+    /// The number of content items that were stored.
+    pub stored: usize,
+    /// The number of content items that were skipped for falling outside the radius.
+    pub skipped: usize,
+}
+
+/// Validates the era1 archives in `dir` and stores their content in the history network storage,
+/// in the order of their file names. Fails on the first invalid archive, keeping the content of
+/// the archives before it.
+pub fn import_era1_dir(
+    dir: &Path,
+    storage_config: PortalStorageConfig,
+    master_accumulator: &MasterAccumulator,
+) -> anyhow::Result<Era1ImportStats> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == ERA1_FILE_EXTENSION)
+    });
+    paths.sort();
+    ensure!(
+        !paths.is_empty(),
+        "No era1 archives found in {}",
+        dir.display()
+    );
+
+    let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+    let mut stats = Era1ImportStats::default();
+    for path in paths {
+        let era1 = Era1::decode(&fs::read(&path)?)
+            .map_err(|err| anyhow!("Invalid era1 archive {}: {err}", path.display()))?;
+        let epoch_accumulator = era1
+            .validate(master_accumulator)
+            .map_err(|err| anyhow!("Invalid era1 archive {}: {err}", path.display()))?;
+        for (content_key, content_value) in era1.content(&epoch_accumulator)? {
+            match storage.put(content_key, content_value.encode()) {
+                Ok(()) => stats.stored += 1,
+                Err(ContentStoreError::InsufficientRadius { .. }) => stats.skipped += 1,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        stats.archives += 1;
+        stats.blocks += era1.blocks.len();
+        info!(
+            path = %path.display(),
+            blocks = era1.blocks.len(),
+            "Imported era1 archive"
+        );
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use discv5::enr::NodeId;
+    use serde_json::Value;
+    use serial_test::serial;
+    use ssz::Decode;
+
+    /// Returns an archive holding only block 14764013.
+    fn era1() -> Era1 {
+        let block =
+            std::fs::read_to_string("../test_assets/mainnet/block_14764013_value.json").unwrap();
+        let block: Value = serde_json::from_str(&block).unwrap();
+        let header: Header = serde_json::from_value(block["result"].clone()).unwrap();
+        let body = std::fs::read("../test_assets/mainnet/block_body_14764013.bin").unwrap();
+        let body = BlockBodyLegacy::from_ssz_bytes(&body).unwrap();
+        let receipts = std::fs::read("../test_assets/mainnet/receipts_14764013.bin").unwrap();
+        let receipts = Receipts::from_ssz_bytes(&receipts).unwrap();
+        let mut era1 = Era1 {
+            blocks: vec![Era1Block {
+                header,
+                body,
+                receipts,
+                total_difficulty: U256::from(1234),
+            }],
+            accumulator_root: H256::zero(),
+            starting_number: 14764013,
+        };
+        era1.accumulator_root = era1.epoch_accumulator().unwrap().tree_hash_root();
+        era1
+    }
+
+    #[test]
+    fn era1_encoding_decoding_round_trip() {
+        let era1 = era1();
+        let encoded = era1.encode().unwrap();
+        assert_eq!(Era1::decode(&encoded).unwrap(), era1);
+
+        // Truncated archives are rejected.
+        assert!(Era1::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    /// Returns an archive holding block 14764013, renumbered to the first block of its epoch, so
+    /// that the archive starts at an epoch boundary.
+    fn epoch_start_era1() -> Era1 {
+        let mut era1 = era1();
+        let starting_number = 14764013 / EPOCH_SIZE as u64 * EPOCH_SIZE as u64;
+        era1.blocks[0].header.number = starting_number;
+        era1.starting_number = starting_number;
+        era1.accumulator_root = era1.epoch_accumulator().unwrap().tree_hash_root();
+        era1
+    }
+
+    /// Returns a master accumulator that holds the epoch of `era1`.
+    fn master_accumulator_of(era1: &Era1) -> MasterAccumulator {
+        let epoch_index = (era1.starting_number / EPOCH_SIZE as u64) as usize;
+        let mut historical_epochs = vec![H256::zero(); epoch_index + 1];
+        historical_epochs[epoch_index] = era1.accumulator_root;
+        MasterAccumulator {
+            historical_epochs: historical_epochs.into(),
+        }
+    }
+
+    #[test]
+    fn validate_era1() {
+        let era1 = epoch_start_era1();
+        let epoch_accumulator = era1.validate(&master_accumulator_of(&era1)).unwrap();
+        assert_eq!(epoch_accumulator.tree_hash_root(), era1.accumulator_root);
+    }
+
+    #[test]
+    fn invalidate_era1() {
+        let master_accumulator = MasterAccumulator::default();
+
+        // The archive doesn't start at an epoch boundary.
+        let err = era1().validate(&master_accumulator).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("doesn't start at an epoch boundary"));
+
+        // The accumulator root doesn't match the blocks.
+        let mut era1 = epoch_start_era1();
+        era1.accumulator_root = H256::zero();
+        let err = era1.validate(&master_accumulator).unwrap_err();
+        assert!(err.to_string().contains("doesn't match its blocks"));
+
+        // The blocks aren't those of the canonical epoch.
+        let err = epoch_start_era1()
+            .validate(&master_accumulator)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("doesn't match the master accumulator"));
+    }
+
+    #[test]
+    #[serial]
+    fn import_era1_archive() {
+        let era1 = epoch_start_era1();
+        let era1_dir = portalnet::utils::db::setup_temp_dir().unwrap();
+        fs::write(
+            era1_dir.path().join("mainnet-01802-00000000.era1"),
+            era1.encode().unwrap(),
+        )
+        .unwrap();
+        let data_dir = portalnet::utils::db::setup_temp_dir().unwrap();
+        let storage_config =
+            PortalStorageConfig::new(100, data_dir.path().to_path_buf(), NodeId::random()).unwrap();
+
+        let stats = import_era1_dir(
+            era1_dir.path(),
+            storage_config.clone(),
+            &master_accumulator_of(&era1),
+        )
+        .unwrap();
+        assert_eq!(
+            stats,
+            Era1ImportStats {
+                archives: 1,
+                blocks: 1,
+                stored: 4,
+                skipped: 0,
+            }
+        );
+
+        let storage = HistoryStorage::new(storage_config, ProtocolId::History).unwrap();
+        let block_hash = era1.blocks[0].header.hash().0;
+        for content_key in [
+            HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey { block_hash }),
+            HistoryContentKey::BlockBody(BlockBodyKey { block_hash }),
+            HistoryContentKey::BlockReceipts(BlockReceiptsKey { block_hash }),
+            HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+                epoch_hash: era1.accumulator_root,
+            }),
+        ] {
+            assert!(storage.get(&content_key).unwrap().is_some());
+        }
+        era1_dir.close().unwrap();
+        data_dir.close().unwrap();
+    }
+
+    #[test]
+    fn find_era1_file_by_epoch_index() {
+        let temp_dir = portalnet::utils::db::setup_temp_dir().unwrap();
+        for file_name in [
+            "mainnet-00001-a5364e9a.era1",
+            "mainnet-00010-5f5d4516.era1",
+            "notes",
+        ] {
+            fs::write(temp_dir.path().join(file_name), []).unwrap();
+        }
+        assert_eq!(
+            find_era1_file(temp_dir.path(), 10).unwrap(),
+            Some(temp_dir.path().join("mainnet-00010-5f5d4516.era1"))
+        );
+        assert_eq!(find_era1_file(temp_dir.path(), 2).unwrap(), None);
+        temp_dir.close().unwrap();
+    }
+}
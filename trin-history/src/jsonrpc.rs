@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use discv5::enr::NodeId;
 use ethportal_api::{
@@ -6,21 +6,25 @@ use ethportal_api::{
         constants::CONTENT_ABSENT,
         distance::Distance,
         history::{ContentInfo, TraceContentInfo},
-        jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
-        portal::{AcceptInfo, FindNodesInfo, PongInfo},
-        portal_wire::Content,
+        jsonrpc::{
+            endpoints::HistoryEndpoint, error::JsonRpcError, request::HistoryJsonRpcRequest,
+        },
+        portal::{AcceptInfo, FindNodesInfo, PongInfo, TraceOfferInfo},
+        portal_wire::{Content, MAX_CONTENT_KEYS_PER_OFFER},
         query_trace::QueryTrace,
+        subscription::RoutingTableChange,
     },
     utils::bytes::hex_encode,
     ContentValue, HistoryContentKey, OverlayContentKey, RawContentKey,
 };
+use portalnet::events::{EventEnvelope, OverlayEvent};
 use serde_json::{json, Value};
 use ssz::Encode;
-use tokio::sync::{mpsc, Mutex, RwLock};
-use tracing::error;
-use trin_storage::ContentStore;
+use tokio::sync::{broadcast::error::RecvError, mpsc, Mutex, RwLock};
+use tracing::{error, warn, Instrument};
+use trin_storage::{error::ContentStoreError, utils::path_within_data_dir};
 
-use crate::network::HistoryNetwork;
+use crate::{archive::import_archive, network::HistoryNetwork};
 
 /// Handles History network JSON-RPC requests
 pub struct HistoryRequestHandler {
@@ -34,18 +38,24 @@ impl HistoryRequestHandler {
         let history_rx = self.history_rx.clone();
         while let Some(request) = history_rx.lock().await.recv().await {
             let network = self.network.clone();
-            tokio::spawn(async move { complete_request(network, request).await });
+            let span = request.span.clone();
+            tokio::spawn(async move { complete_request(network, request).await }.instrument(span));
         }
     }
 }
 
 /// Generates a response for a given request and sends it to the receiver.
 async fn complete_request(network: Arc<RwLock<HistoryNetwork>>, request: HistoryJsonRpcRequest) {
-    let response: Result<Value, String> = match request.endpoint {
+    let response: Result<Value, JsonRpcError> = match request.endpoint {
         HistoryEndpoint::LocalContent(content_key) => local_content(network, content_key).await,
         HistoryEndpoint::PaginateLocalContentKeys(offset, limit) => {
             paginate_local_content_keys(network, offset, limit).await
         }
+        HistoryEndpoint::ExportLocalContent(path, offset, limit) => {
+            export_local_content(network, path, offset, limit).await
+        }
+        HistoryEndpoint::ImportLocalContent(path) => import_local_content(network, path).await,
+        HistoryEndpoint::BackupDb(path) => backup_db(network, path).await,
         HistoryEndpoint::Store(content_key, content_value) => {
             store(network, content_key, content_value).await
         }
@@ -72,18 +82,36 @@ async fn complete_request(network: Arc<RwLock<HistoryNetwork>>, request: History
         HistoryEndpoint::TraceGossip(content_key, content_value) => {
             gossip_trace(network, content_key, content_value).await
         }
+        HistoryEndpoint::TraceGossipBatch(content_items) => {
+            gossip_batch_trace(network, content_items).await
+        }
         HistoryEndpoint::LookupEnr(node_id) => lookup_enr(network, node_id).await,
         HistoryEndpoint::Offer(enr, content_key, content_value) => {
             offer(network, enr, content_key, content_value).await
         }
+        HistoryEndpoint::TraceOffer(enr, content_keys) => {
+            trace_offer(network, enr, content_keys).await
+        }
         HistoryEndpoint::Ping(enr) => ping(network, enr).await,
         HistoryEndpoint::RoutingTableInfo => {
             serde_json::to_value(network.read().await.overlay.routing_table_info())
-                .map_err(|err| err.to_string())
+                .map_err(|err| JsonRpcError::internal(err.to_string()))
         }
+        HistoryEndpoint::Peers => serde_json::to_value(network.read().await.overlay.peers())
+            .map_err(|err| JsonRpcError::internal(err.to_string())),
+        HistoryEndpoint::DbStats => db_stats(network).await,
+        HistoryEndpoint::LatestStoredHeader => latest_stored_header(network).await,
         HistoryEndpoint::RecursiveFindNodes(node_id) => {
             recursive_find_nodes(network, node_id).await
         }
+        HistoryEndpoint::SubscribeStoredContent => {
+            return subscribe_overlay_events(network, request.resp, stored_content_notification)
+                .await
+        }
+        HistoryEndpoint::SubscribeRoutingTable => {
+            return subscribe_overlay_events(network, request.resp, routing_table_notification)
+                .await
+        }
     };
     let _ = request.resp.send(response);
 }
@@ -93,10 +121,10 @@ async fn recursive_find_content(
     network: Arc<RwLock<HistoryNetwork>>,
     content_key: HistoryContentKey,
     is_trace: bool,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     // Check whether we have the data locally.
     let overlay = network.read().await.overlay.clone();
-    let local_content: Option<Vec<u8>> = match overlay.store.read().get(&content_key) {
+    let local_content: Option<Vec<u8>> = match overlay.store.get(content_key.clone()).await {
         Ok(Some(data)) => Some(data),
         Ok(None) => None,
         Err(err) => {
@@ -127,18 +155,22 @@ async fn recursive_find_content(
     // If tracing is not required, return content.
     if !is_trace {
         return Ok(json!(ContentInfo::Content {
-            content: serde_json::from_value(content_response_string).map_err(|e| e.to_string())?,
+            content: serde_json::from_value(content_response_string)
+                .map_err(|err| JsonRpcError::internal(err.to_string()))?,
             utp_transfer,
         }));
     }
     if let Some(trace) = trace {
         Ok(json!(TraceContentInfo {
-            content: serde_json::from_value(content_response_string).map_err(|e| e.to_string())?,
+            content: serde_json::from_value(content_response_string)
+                .map_err(|err| JsonRpcError::internal(err.to_string()))?,
             utp_transfer,
             trace,
         }))
     } else {
-        Err("Content query trace requested but none provided.".to_owned())
+        Err(JsonRpcError::internal(
+            "Content query trace requested but none provided.",
+        ))
     }
 }
 
@@ -146,9 +178,9 @@ async fn recursive_find_content(
 async fn local_content(
     network: Arc<RwLock<HistoryNetwork>>,
     content_key: HistoryContentKey,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let store = network.read().await.overlay.store.clone();
-    let response = match store.read().get(&content_key)
+    let response = match store.get(content_key.clone()).await
         {
             Ok(val) => match val {
                 Some(val) => {
@@ -158,9 +190,9 @@ async fn local_content(
                     Ok(Value::String(CONTENT_ABSENT.to_string()))
                 }
             },
-            Err(err) => Err(format!(
+            Err(err) => Err(JsonRpcError::storage(format!(
                 "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
-            )),
+            ))),
         };
     response
 }
@@ -170,32 +202,150 @@ async fn paginate_local_content_keys(
     network: Arc<RwLock<HistoryNetwork>>,
     offset: u64,
     limit: u64,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let store = network.read().await.overlay.store.clone();
-    let response = match store.read().paginate(&offset, &limit)
+    let response = match store.read(move |store| store.paginate(&offset, &limit)).await
         {
             Ok(val) => Ok(json!(val)),
-            Err(err) => Err(format!(
+            Err(err) => Err(JsonRpcError::storage(format!(
                 "Database error while paginating local content keys with offset: {offset:?}, limit: {limit:?}. Error message: {err}"
-            )),
+            ))),
         };
     response
 }
 
+/// Constructs a JSON call for the ExportLocalContent method. The archive is written on the
+/// blocking thread pool, without holding up the storage actor.
+async fn export_local_content(
+    network: Arc<RwLock<HistoryNetwork>>,
+    path: PathBuf,
+    offset: u64,
+    limit: u64,
+) -> Result<Value, JsonRpcError> {
+    let store = network.read().await.overlay.store.clone();
+    let archive_path = path.clone();
+    let exporter = store
+        .read(move |store| {
+            let archive_path = path_within_data_dir(store.node_data_dir(), &archive_path)?;
+            Ok((store.archive_exporter(), archive_path))
+        })
+        .await;
+    let result = match exporter {
+        Ok((exporter, archive_path)) => {
+            tokio::task::spawn_blocking(move || exporter.export(&archive_path, offset, limit))
+                .await
+                .unwrap_or_else(|err| {
+                    Err(ContentStoreError::Database(format!(
+                        "Export task failed: {err}"
+                    )))
+                })
+        }
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Error while exporting local content to {}. Error message: {err}",
+            path.display()
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the BackupDb method. The backup is taken on the blocking thread pool,
+/// without holding up the storage actor.
+async fn backup_db(
+    network: Arc<RwLock<HistoryNetwork>>,
+    path: PathBuf,
+) -> Result<Value, JsonRpcError> {
+    let store = network.read().await.overlay.store.clone();
+    let backup_path = path.clone();
+    let db_backup = store
+        .read(move |store| {
+            let backup_path = path_within_data_dir(store.node_data_dir(), &backup_path)?;
+            Ok((store.db_backup(), backup_path))
+        })
+        .await;
+    let result = match db_backup {
+        Ok((db_backup, backup_path)) => {
+            tokio::task::spawn_blocking(move || db_backup.write(&backup_path))
+                .await
+                .unwrap_or_else(|err| {
+                    Err(ContentStoreError::Database(format!(
+                        "Backup task failed: {err}"
+                    )))
+                })
+        }
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Error while backing up the database to {}. Error message: {err}",
+            path.display()
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the ImportLocalContent method. Every content item is validated
+/// like content accepted from peers before it's stored.
+async fn import_local_content(
+    network: Arc<RwLock<HistoryNetwork>>,
+    path: PathBuf,
+) -> Result<Value, JsonRpcError> {
+    let network = network.read().await.clone();
+    let store = network.overlay.store.clone();
+    let archive_path = path.clone();
+    let archive_path = store
+        .read(move |store| path_within_data_dir(store.node_data_dir(), &archive_path))
+        .await;
+    let result = match archive_path {
+        Ok(archive_path) => import_archive(&store, archive_path, network.validator.as_ref()).await,
+        Err(err) => Err(err.into()),
+    };
+    match result {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Error while importing local content from {}. Error message: {err}",
+            path.display()
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the DbStats method.
+async fn db_stats(network: Arc<RwLock<HistoryNetwork>>) -> Result<Value, JsonRpcError> {
+    let store = network.read().await.overlay.store.clone();
+    match store.read(|store| store.db_stats()).await {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while collecting storage stats. Error message: {err}"
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the LatestStoredHeader method, returning the hash of the header.
+async fn latest_stored_header(network: Arc<RwLock<HistoryNetwork>>) -> Result<Value, JsonRpcError> {
+    let store = network.read().await.overlay.store.clone();
+    match store.read(|store| store.latest_header()).await {
+        Ok(header) => Ok(json!(header.map(|header| header.hash()))),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while looking up the latest stored header. Error message: {err}"
+        ))),
+    }
+}
+
 /// Constructs a JSON call for the Store method.
 async fn store(
     network: Arc<RwLock<HistoryNetwork>>,
     content_key: HistoryContentKey,
     content_value: ethportal_api::HistoryContentValue,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let data = content_value.encode();
     let store = network.read().await.overlay.store.clone();
-    let response = match store
-        .write()
-        .put::<HistoryContentKey, Vec<u8>>(content_key, data)
-    {
+    let response = match store.put(content_key.clone(), data).await {
         Ok(_) => Ok(Value::Bool(true)),
-        Err(err) => Ok(Value::String(err.to_string())),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while storing content key: {content_key:?}, with error: {err}"
+        ))),
     };
     response
 }
@@ -204,20 +354,23 @@ async fn store(
 async fn add_enr(
     network: Arc<RwLock<HistoryNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.add_enr(enr) {
         Ok(_) => Ok(json!(true)),
-        Err(err) => Err(format!("AddEnr failed: {err:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
 /// Constructs a JSON call for the GetEnr method.
-async fn get_enr(network: Arc<RwLock<HistoryNetwork>>, node_id: NodeId) -> Result<Value, String> {
+async fn get_enr(
+    network: Arc<RwLock<HistoryNetwork>>,
+    node_id: NodeId,
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.get_enr(node_id) {
         Ok(enr) => Ok(json!(enr)),
-        Err(err) => Err(format!("GetEnr failed: {err:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -225,7 +378,7 @@ async fn get_enr(network: Arc<RwLock<HistoryNetwork>>, node_id: NodeId) -> Resul
 async fn delete_enr(
     network: Arc<RwLock<HistoryNetwork>>,
     node_id: NodeId,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     let is_deleted = overlay.delete_enr(node_id);
     Ok(json!(is_deleted))
@@ -235,11 +388,11 @@ async fn delete_enr(
 async fn lookup_enr(
     network: Arc<RwLock<HistoryNetwork>>,
     node_id: NodeId,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.lookup_enr(node_id).await {
         Ok(enr) => Ok(json!(enr)),
-        Err(err) => Err(format!("LookupEnr failed: {err:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -248,13 +401,13 @@ async fn find_content(
     network: Arc<RwLock<HistoryNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
     content_key: HistoryContentKey,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.send_find_content(enr, content_key.into()).await {
         Ok((content, utp_transfer)) => match content {
-            Content::ConnectionId(id) => Err(format!(
+            Content::ConnectionId(id) => Err(JsonRpcError::internal(format!(
                 "FindContent request returned a connection id ({id:?}) instead of conducting utp transfer."
-            )),
+            ))),
             Content::Content(content) => Ok(json!({
                 "content": hex_encode(content),
                 "utpTransfer": utp_transfer,
@@ -263,7 +416,7 @@ async fn find_content(
                 "enrs": enrs,
             })),
         },
-        Err(msg) => Err(format!("FindContent request timeout: {msg:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -272,7 +425,7 @@ async fn find_nodes(
     network: Arc<RwLock<HistoryNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
     distances: Vec<u16>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.send_find_nodes(enr, distances).await {
         Ok(nodes) => Ok(json!(nodes
@@ -280,7 +433,7 @@ async fn find_nodes(
             .into_iter()
             .map(|enr| enr.into())
             .collect::<FindNodesInfo>())),
-        Err(msg) => Err(format!("FindNodes request timeout: {msg:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -289,7 +442,7 @@ async fn gossip(
     network: Arc<RwLock<HistoryNetwork>>,
     content_key: HistoryContentKey,
     content_value: ethportal_api::HistoryContentValue,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let data = content_value.encode();
     let overlay = network.read().await.overlay.clone();
     Ok(overlay.propagate_gossip(vec![(content_key, data)]).into())
@@ -300,7 +453,7 @@ async fn gossip_trace(
     network: Arc<RwLock<HistoryNetwork>>,
     content_key: HistoryContentKey,
     content_value: ethportal_api::HistoryContentValue,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let data = content_value.encode();
     let overlay = network.read().await.overlay.clone();
     Ok(json!(
@@ -308,13 +461,28 @@ async fn gossip_trace(
     ))
 }
 
+/// Constructs a JSON call for the GossipBatch method, with tracing enabled.
+async fn gossip_batch_trace(
+    network: Arc<RwLock<HistoryNetwork>>,
+    content_items: Vec<(HistoryContentKey, ethportal_api::HistoryContentValue)>,
+) -> Result<Value, JsonRpcError> {
+    let content_items = content_items
+        .into_iter()
+        .map(|(content_key, content_value)| (content_key, content_value.encode()))
+        .collect();
+    let overlay = network.read().await.overlay.clone();
+    Ok(json!(
+        overlay.propagate_gossip_batch_trace(content_items).await
+    ))
+}
+
 /// Constructs a JSON call for the Offer method.
 async fn offer(
     network: Arc<RwLock<HistoryNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
     content_key: HistoryContentKey,
     content_value: Option<ethportal_api::HistoryContentValue>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     if let Some(content_value) = content_value {
         let content_value = content_value.encode();
@@ -325,7 +493,7 @@ async fn offer(
             Ok(accept) => Ok(json!(AcceptInfo {
                 content_keys: accept.content_keys,
             })),
-            Err(msg) => Err(format!("Populated Offer request timeout: {msg:?}")),
+            Err(err) => Err(err.into()),
         }
     } else {
         let content_key: Vec<RawContentKey> = vec![content_key.as_ssz_bytes()];
@@ -333,23 +501,63 @@ async fn offer(
             Ok(accept) => Ok(json!(AcceptInfo {
                 content_keys: accept.content_keys,
             })),
-            Err(msg) => Err(format!("Offer request timeout: {msg:?}")),
+            Err(err) => Err(err.into()),
         }
     }
 }
 
+/// Constructs a JSON call for the TraceOffer method.
+async fn trace_offer(
+    network: Arc<RwLock<HistoryNetwork>>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_keys: Vec<HistoryContentKey>,
+) -> Result<Value, JsonRpcError> {
+    // The ACCEPT bitlist can only represent a limited number of content keys.
+    if content_keys.is_empty() || content_keys.len() > MAX_CONTENT_KEYS_PER_OFFER {
+        return Err(JsonRpcError::invalid_params(format!(
+            "Expected between 1 and {MAX_CONTENT_KEYS_PER_OFFER} content keys, got {}",
+            content_keys.len()
+        )));
+    }
+    let overlay = network.read().await.overlay.clone();
+    let mut content_items = vec![];
+    for content_key in content_keys {
+        let content_value = match overlay.store.get(content_key.clone()).await {
+            Ok(Some(val)) => val,
+            Ok(None) => {
+                return Err(JsonRpcError::invalid_params(format!(
+                    "Content key not found in local storage: {content_key:?}"
+                )))
+            }
+            Err(err) => {
+                return Err(JsonRpcError::storage(format!(
+                    "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+                )))
+            }
+        };
+        content_items.push((content_key.into(), content_value));
+    }
+    match overlay.send_offer_with_result(enr, content_items).await {
+        Ok((accept, transferred)) => Ok(json!(TraceOfferInfo {
+            content_keys: accept.content_keys,
+            transferred,
+        })),
+        Err(err) => Err(err.into()),
+    }
+}
+
 /// Constructs a JSON call for the Ping method.
 async fn ping(
     network: Arc<RwLock<HistoryNetwork>>,
     enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     match overlay.send_ping(enr).await {
         Ok(pong) => Ok(json!(PongInfo {
             enr_seq: pong.enr_seq as u32,
             data_radius: *Distance::from(pong.custom_payload),
         })),
-        Err(msg) => Err(format!("Ping request timeout: {msg:?}")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -357,8 +565,76 @@ async fn ping(
 async fn recursive_find_nodes(
     network: Arc<RwLock<HistoryNetwork>>,
     node_id: NodeId,
-) -> Result<Value, String> {
+) -> Result<Value, JsonRpcError> {
     let overlay = network.read().await.overlay.clone();
     let nodes = overlay.lookup_node(node_id).await;
     Ok(json!(nodes))
 }
+
+/// Sends a notification through `resp` for every overlay event that `notification` maps to one,
+/// until the receiving end is dropped.
+async fn subscribe_overlay_events(
+    network: Arc<RwLock<HistoryNetwork>>,
+    resp: mpsc::UnboundedSender<Result<Value, JsonRpcError>>,
+    notification: fn(OverlayEvent) -> Option<Value>,
+) {
+    let overlay = network.read().await.overlay.clone();
+    let mut event_stream = match overlay.event_stream().await {
+        Ok(event_stream) => event_stream,
+        Err(err) => {
+            let _ = resp.send(Err(JsonRpcError::internal(format!(
+                "Unable to subscribe to overlay events: {err}"
+            ))));
+            return;
+        }
+    };
+    loop {
+        tokio::select! {
+            _ = resp.closed() => break,
+            event = event_stream.recv() => {
+                let event = match event {
+                    Ok(EventEnvelope { payload, .. }) => payload,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Subscription lagged behind the overlay event stream");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let Some(notification) = notification(event) else {
+                    continue;
+                };
+                if resp.send(Ok(notification)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Notifies the content key of newly stored content.
+fn stored_content_notification(event: OverlayEvent) -> Option<Value> {
+    let OverlayEvent::ContentStored(raw_content_key) = event else {
+        return None;
+    };
+    match HistoryContentKey::try_from(raw_content_key) {
+        Ok(content_key) => Some(json!(content_key)),
+        Err(err) => {
+            error!(error = %err, "Unable to decode stored content key");
+            None
+        }
+    }
+}
+
+/// Notifies the peers that were connected in or removed from the routing table.
+fn routing_table_notification(event: OverlayEvent) -> Option<Value> {
+    let change = match event {
+        OverlayEvent::PeerAdded(node_id) => RoutingTableChange::PeerAdded {
+            node_id: hex_encode(node_id.raw()),
+        },
+        OverlayEvent::PeerRemoved(node_id) => RoutingTableChange::PeerRemoved {
+            node_id: hex_encode(node_id.raw()),
+        },
+        _ => return None,
+    };
+    Some(json!(change))
+}
@@ -1,6 +1,9 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
+pub mod archive;
+pub mod db;
+pub mod era1;
 pub mod events;
 mod jsonrpc;
 pub mod network;
@@ -12,7 +15,7 @@ use std::sync::Arc;
 
 use network::HistoryNetwork;
 use tokio::{
-    sync::{broadcast, mpsc, Mutex, RwLock},
+    sync::{broadcast, mpsc, watch, Mutex, RwLock},
     task::JoinHandle,
     time::{interval, Duration},
 };
@@ -42,6 +45,7 @@ pub async fn initialize_history_network(
     portalnet_config: PortalnetConfig,
     storage_config: PortalStorageConfig,
     header_oracle: Arc<RwLock<HeaderOracle>>,
+    shutdown: watch::Receiver<bool>,
 ) -> anyhow::Result<(
     HistoryHandler,
     HistoryNetworkTask,
@@ -67,8 +71,12 @@ pub async fn initialize_history_network(
         history_rx: Arc::new(Mutex::new(history_jsonrpc_rx)),
     };
     let history_network = Arc::new(history_network);
-    let history_network_task =
-        spawn_history_network(history_network.clone(), portalnet_config, history_event_rx);
+    let history_network_task = spawn_history_network(
+        history_network.clone(),
+        portalnet_config,
+        history_event_rx,
+        shutdown,
+    );
     spawn_history_heartbeat(history_network);
     Ok((
         Some(history_handler),
@@ -83,6 +91,7 @@ pub fn spawn_history_network(
     network: Arc<HistoryNetwork>,
     portalnet_config: PortalnetConfig,
     history_message_rx: mpsc::UnboundedReceiver<OverlayRequest>,
+    mut shutdown: watch::Receiver<bool>,
 ) -> JoinHandle<()> {
     let bootnode_enrs: Vec<Enr> = portalnet_config.bootnodes.into();
     info!(
@@ -102,9 +111,11 @@ pub fn spawn_history_network(
         // hacky test: make sure we establish a session with the boot node
         network.overlay.ping_bootnodes().await;
 
-        tokio::signal::ctrl_c()
-            .await
-            .expect("failed to pause until ctrl-c");
+        // The overlay is stopped once the node shuts down. It keeps running if the node is dropped
+        // without shutting down.
+        if shutdown.wait_for(|shutdown| *shutdown).await.is_ok() {
+            network.overlay.shutdown().await;
+        }
     })
 }
 
@@ -117,7 +128,12 @@ pub fn spawn_history_heartbeat(network: Arc<HistoryNetwork>) {
             // this wait at the top. Otherwise, we get two log lines immediately on startup.
             heart_interval.tick().await;
 
-            let storage_log = network.overlay.store.read().get_summary_info();
+            let storage_log = network
+                .overlay
+                .store
+                .read(|store| Ok(store.get_summary_info()))
+                .await
+                .unwrap_or_else(|err| err.to_string());
             let message_log = network.overlay.get_message_summary();
             let utp_log = network.overlay.get_utp_summary();
             info!("reports~ data: {storage_log}; msgs: {message_log}");
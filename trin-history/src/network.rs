@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use parking_lot::RwLock as PLRwLock;
 use tokio::sync::RwLock;
 use utp_rs::socket::UtpSocket;
 
@@ -11,6 +10,7 @@ use ethportal_api::{
 };
 use portalnet::{
     config::PortalnetConfig,
+    content_cache::DEFAULT_CONTENT_CACHE_CAPACITY_BYTES,
     discovery::{Discovery, UtpEnr},
     overlay::{OverlayConfig, OverlayProtocol},
 };
@@ -25,6 +25,9 @@ use trin_storage::PortalStorageConfig;
 pub struct HistoryNetwork {
     pub overlay:
         Arc<OverlayProtocol<HistoryContentKey, XorMetric, ChainHistoryValidator, HistoryStorage>>,
+    /// The validator of the content accepted by the overlay, which also validates imported
+    /// content.
+    pub validator: Arc<ChainHistoryValidator>,
 }
 
 impl HistoryNetwork {
@@ -38,13 +41,15 @@ impl HistoryNetwork {
         let bootnode_enrs: Vec<Enr> = portal_config.bootnodes.into();
         let config = OverlayConfig {
             bootnode_enrs,
+            query_parallelism: portal_config.query_parallelism,
+            query_peer_timeout: portal_config.query_peer_timeout,
+            query_timeout: portal_config.query_timeout,
             disable_poke: portal_config.disable_poke,
+            // History content never changes once stored, so it can be served from the cache.
+            content_cache_capacity_bytes: DEFAULT_CONTENT_CACHE_CAPACITY_BYTES,
             ..Default::default()
         };
-        let storage = Arc::new(PLRwLock::new(HistoryStorage::new(
-            storage_config,
-            ProtocolId::History,
-        )?));
+        let storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
         let validator = Arc::new(ChainHistoryValidator { header_oracle });
         let overlay = OverlayProtocol::new(
             config,
@@ -52,12 +57,13 @@ impl HistoryNetwork {
             utp_socket,
             storage,
             ProtocolId::History,
-            validator,
+            Arc::clone(&validator),
         )
         .await;
 
         Ok(Self {
             overlay: Arc::new(overlay),
+            validator,
         })
     }
 }
@@ -1,7 +1,15 @@
 use discv5::enr::NodeId;
+use ethereum_types::H256;
 use ethportal_api::{
     types::{
+        admin::DbStats,
         distance::{Distance, Metric, XorMetric},
+        execution::{
+            accumulator::EpochAccumulator,
+            block_body::BlockBody,
+            header::{Header, HeaderWithProof},
+            receipts::Receipts,
+        },
         history::PaginateLocalContentInfo,
         portal_wire::ProtocolId,
     },
@@ -10,24 +18,45 @@ use ethportal_api::{
 };
 use r2d2::Pool;
 use r2d2_sqlite::{rusqlite, SqliteConnectionManager};
-use std::path::PathBuf;
+use rusqlite::{params, OptionalExtension};
+use ssz::Decode;
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
 use tracing::debug;
+use tree_hash::TreeHash;
 use trin_metrics::{portalnet::PORTALNET_METRICS, storage::StorageMetricsReporter};
 use trin_storage::{
+    archive::ArchiveWriter,
+    backup::DbBackup,
+    check::{CorruptContent, CorruptionReason, DbCheckReport},
     error::ContentStoreError,
     sql::{
-        CONTENT_KEY_LOOKUP_QUERY_DB, CONTENT_SIZE_LOOKUP_QUERY_DB, DELETE_QUERY_DB,
-        PAGINATE_QUERY_DB, TOTAL_DATA_SIZE_QUERY_DB, TOTAL_ENTRY_COUNT_QUERY_NETWORK,
-        XOR_FIND_FARTHEST_QUERY_NETWORK,
+        CHECK_QUERY_NETWORK, CONTENT_KEY_LOOKUP_QUERY_NETWORK, CONTENT_SIZE_TOTAL_QUERY_NETWORK,
+        DELETE_QUERY_NETWORK, EXPORT_QUERY_NETWORK, HEADER_VALUES_QUERY_NETWORK,
+        PAGINATE_QUERY_NETWORK, TOTAL_ENTRY_COUNT_QUERY_NETWORK, XOR_FIND_FARTHEST_QUERY_NETWORK,
     },
     utils::{
-        byte_vector_to_u32, get_total_size_of_directory_in_bytes, insert_value,
-        lookup_content_value,
+        byte_vector_to_u32, content_id_from_bytes, distances_indexed_for, farthest_content,
+        get_total_size_of_directory_in_bytes, insert_value, insert_values, lookup_content_value,
     },
-    ContentId, ContentStore, DataSize, DistanceFunction, EntryCount, PortalStorageConfig,
-    ShouldWeStoreContent, BYTES_IN_MB_U64,
+    ContentStore, DistanceFunction, EntryCount, PortalStorageConfig, ShouldWeStoreContent,
+    BYTES_IN_MB_U64,
 };
 
+/// Percentage of the storage capacity below which the radius is grown again, to take in more
+/// content after it was shrunk to fit the capacity.
+const RADIUS_GROWTH_WATERMARK_PERCENT: u64 = 90;
+
+/// Percentage of the storage capacity that the stored content is pruned to once it exceeds the
+/// capacity, so that the store doesn't have to prune again on every insert.
+const PRUNING_LOW_WATERMARK_PERCENT: u64 = 95;
+
+/// Number of the farthest content items looked up at a time while pruning.
+const PRUNING_BATCH_SIZE: u64 = 100;
+
 /// Storage layer for the history network. Encapsulates history network specific data and logic.
 #[derive(Debug)]
 pub struct HistoryStorage {
@@ -39,6 +68,12 @@ pub struct HistoryStorage {
     distance_fn: DistanceFunction,
     metrics: StorageMetricsReporter,
     network: ProtocolId,
+    /// Whether the database was opened read-only, in which case content is never stored or
+    /// deleted.
+    read_only: bool,
+    /// Whether the distances of the stored content are indexed for the local node id, which is
+    /// only not the case for a read-only database indexed for another node.
+    distance_indexed: bool,
 }
 
 impl ContentStore for HistoryStorage {
@@ -57,6 +92,19 @@ impl ContentStore for HistoryStorage {
         self.store(&key, &value.as_ref().to_vec())
     }
 
+    fn put_batch<K: OverlayContentKey, V: AsRef<[u8]>>(
+        &mut self,
+        items: Vec<(K, V)>,
+    ) -> Vec<Result<(), ContentStoreError>> {
+        if self.read_only {
+            return items
+                .iter()
+                .map(|_| Err(ContentStoreError::ReadOnly))
+                .collect();
+        }
+        self.store_batch(items)
+    }
+
     fn is_key_within_radius_and_unavailable<K: OverlayContentKey>(
         &self,
         key: &K,
@@ -76,6 +124,10 @@ impl ContentStore for HistoryStorage {
         if is_key_available {
             return Ok(ShouldWeStoreContent::AlreadyStored);
         }
+        // Content offered to a read-only store is declined, as if it was outside the radius.
+        if self.read_only {
+            return Ok(ShouldWeStoreContent::NotWithinRadius);
+        }
         Ok(ShouldWeStoreContent::Store)
     }
 
@@ -96,6 +148,8 @@ impl HistoryStorage {
             storage_metrics: PORTALNET_METRICS.storage(),
             protocol: protocol.to_string(),
         };
+        let distance_indexed =
+            distances_indexed_for(&config.sql_connection_pool.get()?, &config.node_id)?;
         let mut storage = Self {
             node_id: config.node_id,
             node_data_dir: config.node_data_dir,
@@ -105,6 +159,8 @@ impl HistoryStorage {
             distance_fn: config.distance_fn,
             metrics,
             network: protocol,
+            read_only: config.backend.is_read_only(),
+            distance_indexed,
         };
 
         // Set the metrics to the default radius, to start
@@ -112,6 +168,15 @@ impl HistoryStorage {
 
         // Check whether we already have data, and use it to set radius
         match storage.total_entry_count()? {
+            // A read-only store never prunes, so its radius covers all of its content.
+            entry_count if storage.read_only => {
+                storage.metrics.report_entry_count(entry_count);
+                let radius = match storage.find_farthest_content_id()? {
+                    Some(farthest) => storage.distance_to_content_id(&farthest),
+                    None => Distance::ZERO,
+                };
+                storage.set_radius(radius);
+            }
             0 => {
                 // Default radius is left in place, unless user selected 0mb capacity
                 if storage.storage_capacity_in_bytes == 0 {
@@ -122,7 +187,9 @@ impl HistoryStorage {
             entry_count => {
                 storage.metrics.report_entry_count(entry_count);
 
-                let _ = storage.prune_db()?;
+                if storage.prune_db()? == 0 {
+                    storage.grow_radius_if_below_watermark()?;
+                }
             }
         }
 
@@ -153,19 +220,20 @@ impl HistoryStorage {
         self.metrics.report_radius(radius);
     }
 
-    /// Returns a paginated list of all available content keys from local storage (from any
-    /// subnetwork) according to the provided offset and limit.
+    /// Returns a paginated list of all available content keys of the history network from local
+    /// storage according to the provided offset and limit.
     pub fn paginate(
         &self,
         offset: &u64,
         limit: &u64,
     ) -> Result<PaginateLocalContentInfo, ContentStoreError> {
         let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(PAGINATE_QUERY_DB)?;
+        let mut query = conn.prepare(PAGINATE_QUERY_NETWORK)?;
 
         let content_keys: Result<Vec<HistoryContentKey>, ContentStoreError> = query
             .query_map(
                 &[
+                    (":network", u8::from(self.network).to_string().as_str()),
                     (":offset", offset.to_string().as_str()),
                     (":limit", limit.to_string().as_str()),
                 ],
@@ -187,6 +255,97 @@ impl HistoryStorage {
         })
     }
 
+    /// Returns an exporter of the content in local storage to content archives, which can run
+    /// without access to the store.
+    pub fn archive_exporter(&self) -> ArchiveExporter {
+        ArchiveExporter {
+            sql_connection_pool: self.sql_connection_pool.clone(),
+            network: self.network,
+        }
+    }
+
+    /// The directory that the content database is stored in.
+    pub fn node_data_dir(&self) -> &Path {
+        &self.node_data_dir
+    }
+
+    /// Returns a copier of the content database, holding the content of every subnetwork, to take
+    /// backups on another thread while the node keeps running.
+    pub fn db_backup(&self) -> DbBackup {
+        DbBackup::new(self.sql_connection_pool.clone())
+    }
+
+    /// Checks the integrity of the stored content of the history network: that every content key
+    /// decodes and matches the content id it is stored under, and that every header and epoch
+    /// accumulator hashes to the hash in its content key. Corrupt content is deleted if `repair` is
+    /// set.
+    ///
+    /// Block bodies and receipts can only be validated against their header, which may not be
+    /// stored, so they are only checked to decode.
+    pub fn check(&self, repair: bool) -> Result<DbCheckReport, ContentStoreError> {
+        if repair && self.read_only {
+            return Err(ContentStoreError::ReadOnly);
+        }
+        let conn = self.sql_connection_pool.get()?;
+        let mut query = conn.prepare(CHECK_QUERY_NETWORK)?;
+        let rows = query.query_map([u8::from(self.network)], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut report = DbCheckReport::default();
+        for row in rows {
+            let (content_id, content_key, content_value) = row?;
+            report.checked += 1;
+            if let Err(reason) = check_content(&content_id, &content_key, &content_value) {
+                report.corrupt.push(CorruptContent {
+                    content_id,
+                    content_key,
+                    reason,
+                });
+            }
+        }
+
+        if repair {
+            for corrupt in &report.corrupt {
+                conn.execute(
+                    DELETE_QUERY_NETWORK,
+                    params![corrupt.content_id, u8::from(self.network)],
+                )?;
+                self.metrics.decrease_entry_count();
+                report.deleted += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns the stored block header with the highest block number, if any header is stored.
+    pub fn latest_header(&self) -> Result<Option<Header>, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        let mut query = conn.prepare(HEADER_VALUES_QUERY_NETWORK)?;
+        let rows = query.query_map([u8::from(self.network)], |row| row.get::<_, String>(0))?;
+
+        let mut latest_header: Option<Header> = None;
+        for row in rows {
+            let content_value = hex_decode(&row?)?;
+            let header = HeaderWithProof::from_ssz_bytes(&content_value)
+                .map_err(|err| ContentStoreError::InvalidData {
+                    message: format!("Stored block header is invalid: {err:?}"),
+                })?
+                .header;
+            if latest_header
+                .as_ref()
+                .map_or(true, |latest_header| header.number > latest_header.number)
+            {
+                latest_header = Some(header);
+            }
+        }
+        Ok(latest_header)
+    }
+
     fn total_entry_count(&self) -> Result<u64, ContentStoreError> {
         let conn = self.sql_connection_pool.get()?;
         let mut query = conn.prepare(TOTAL_ENTRY_COUNT_QUERY_NETWORK)?;
@@ -214,6 +373,9 @@ impl HistoryStorage {
         key: &impl OverlayContentKey,
         value: &Vec<u8>,
     ) -> Result<(), ContentStoreError> {
+        if self.read_only {
+            return Err(ContentStoreError::ReadOnly);
+        }
         let content_id = key.content_id();
         let distance_to_content_id = self.distance_to_content_id(&content_id);
 
@@ -236,7 +398,61 @@ impl HistoryStorage {
         } else {
             self.metrics.increase_entry_count();
         }
-        self.prune_db()?;
+        self.maintain_capacity()
+    }
+
+    /// Method for storing several pieces of content in a single transaction. Returns the outcome
+    /// of storing each piece of content, in order.
+    fn store_batch<K: OverlayContentKey, V: AsRef<[u8]>>(
+        &mut self,
+        items: Vec<(K, V)>,
+    ) -> Vec<Result<(), ContentStoreError>> {
+        let mut results = Vec::with_capacity(items.len());
+        let mut rows = vec![];
+        for (key, value) in items {
+            let content_id = key.content_id();
+            let content_key: Vec<u8> = key.into();
+            let distance_to_content_id = self.distance_to_content_id(&content_id);
+            if distance_to_content_id > self.radius {
+                debug!("Not storing: {content_key:02X?}");
+                results.push(Err(ContentStoreError::InsufficientRadius {
+                    radius: self.radius,
+                    distance: distance_to_content_id,
+                }));
+                continue;
+            }
+            // store content key w/o the 0x prefix
+            let content_key = hex_encode(content_key).trim_start_matches("0x").to_string();
+            rows.push((content_id, content_key, value.as_ref().to_vec()));
+            results.push(Ok(()));
+        }
+        if rows.is_empty() {
+            return results;
+        }
+
+        let conn = match self.sql_connection_pool.get() {
+            Ok(conn) => conn,
+            Err(err) => return batch_error(results, &err.into()),
+        };
+        if let Err(err) = insert_values(conn, &rows, u8::from(self.network)) {
+            debug!("Error writing batch of {} items to db: {err:?}", rows.len());
+            return batch_error(results, &err);
+        }
+        for _ in &rows {
+            self.metrics.increase_entry_count();
+        }
+        if let Err(err) = self.maintain_capacity() {
+            return batch_error(results, &err);
+        }
+        results
+    }
+
+    /// Prunes the store if it is over capacity, or else grows its radius if it is well within
+    /// capacity, after content was stored.
+    fn maintain_capacity(&mut self) -> Result<(), ContentStoreError> {
+        if self.prune_db()? == 0 {
+            self.grow_radius_if_below_watermark()?;
+        }
         let total_bytes_on_disk = self.get_total_storage_usage_in_bytes_on_disk()?;
         self.metrics
             .report_total_storage_usage_bytes(total_bytes_on_disk as f64);
@@ -244,103 +460,115 @@ impl HistoryStorage {
         Ok(())
     }
 
-    /// Internal method for pruning any data that falls outside of the radius of the store.
-    /// Resets the data radius if it prunes any data. Does nothing if the store is empty.
+    /// Internal method for pruning the content farthest from the local node id once the stored
+    /// content exceeds the capacity, until it takes up no more than
+    /// `PRUNING_LOW_WATERMARK_PERCENT` of the capacity. The radius is then shrunk to the distance
+    /// of the farthest remaining content, or to zero if none remains. Does nothing while the store
+    /// is within capacity.
     /// Returns the number of items removed during pruning
     fn prune_db(&mut self) -> Result<usize, ContentStoreError> {
-        let mut farthest_content_id: Option<[u8; 32]> = self.find_farthest_content_id()?;
+        if !self.capacity_reached()? {
+            return Ok(0);
+        }
+        let low_watermark = self.storage_capacity_in_bytes / 100 * PRUNING_LOW_WATERMARK_PERCENT;
         let mut num_removed_items = 0;
-        // Delete furthest data until our data usage is less than capacity.
-        while self.capacity_reached()? {
-            // If the database were empty, then `capacity_reached()` would be false, because the
-            // amount of content (zero) would not be greater than capacity.
-            let id_to_remove =
-                farthest_content_id.expect("Capacity reached, but no farthest id found!");
-            // Test if removing the item would put us under capacity
-            if self.does_eviction_cause_under_capacity(&id_to_remove)? {
-                // If so, we're done pruning
+        let mut total_bytes = self.get_total_storage_usage_in_bytes_from_network()?;
+        'pruning: while total_bytes > low_watermark {
+            let farthest = self.find_farthest_content(PRUNING_BATCH_SIZE)?;
+            if farthest.is_empty() {
+                break;
+            }
+            for (id_to_remove, content_size) in farthest {
+                if total_bytes <= low_watermark {
+                    break 'pruning;
+                }
                 debug!(
-                    "Removing item would drop us below capacity. We target slight overfilling. {}",
+                    "Capacity reached, deleting farthest: {}",
                     hex_encode(id_to_remove)
                 );
-                self.set_radius(self.distance_to_content_id(&id_to_remove));
-                break;
-            }
-            debug!(
-                "Capacity reached, deleting farthest: {}",
-                hex_encode(id_to_remove)
-            );
-            if let Err(err) = self.evict(id_to_remove) {
-                debug!("Error writing content ID {id_to_remove:?} to db: {err:?}",);
-            } else {
+                if let Err(err) = self.evict(id_to_remove) {
+                    debug!("Error removing content ID {id_to_remove:?} from db: {err:?}");
+                    break 'pruning;
+                }
                 num_removed_items += 1;
+                total_bytes = total_bytes.saturating_sub(content_size);
             }
-            // Calculate new farthest_content_id and reset radius
-            match self.find_farthest_content_id()? {
-                None => {
-                    // We get here if the entire db has been pruned,
-                    // eg. user selected 0mb capacity for storage
-                    self.set_radius(Distance::ZERO);
-                }
-                Some(farthest) => {
-                    debug!("Found new farthest: {}", hex_encode(farthest));
-                    self.set_radius(self.distance_to_content_id(&farthest));
-                    farthest_content_id = Some(farthest);
-                }
+        }
+        self.metrics
+            .report_content_data_storage_bytes(total_bytes as f64);
+
+        match self.find_farthest_content_id()? {
+            // We get here if the entire db has been pruned,
+            // eg. user selected 0mb capacity for storage
+            None => self.set_radius(Distance::ZERO),
+            Some(farthest) => {
+                debug!("Found new farthest: {}", hex_encode(farthest));
+                self.set_radius(self.distance_to_content_id(&farthest));
             }
         }
         Ok(num_removed_items)
     }
 
-    /// Internal method for testing if an eviction would cause the store to fall under capacity.
-    /// Returns true if the store would fall under capacity, false otherwise.
-    /// Raises an error if there is a problem accessing the database.
-    fn does_eviction_cause_under_capacity(&self, id: &[u8; 32]) -> Result<bool, ContentStoreError> {
-        let total_bytes_on_disk = self.get_total_storage_usage_in_bytes_from_network()?;
-        // Get the size of the content we're about to remove
-        let bytes_to_remove = self.get_content_size(id)?;
-        Ok(total_bytes_on_disk - bytes_to_remove < self.storage_capacity_in_bytes)
-    }
-
-    /// Internal method for getting the size of a content item in bytes.
-    /// Returns the size of the content item in bytes.
-    /// Raises an error if there is a problem accessing the database.
-    fn get_content_size(&self, id: &[u8; 32]) -> Result<u64, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_SIZE_LOOKUP_QUERY_DB)?;
-        let id_vec = id.to_vec();
-        let result = query.query_map([id_vec], |row| {
-            Ok(DataSize {
-                num_bytes: row.get(0)?,
-            })
-        });
-        let byte_size = match result?.next() {
-            Some(data_size) => data_size,
-            None => {
-                // Build error message with hex encoded content id
-                let err = format!("Unable to determine size of item {}", hex_encode(id));
-                return Err(ContentStoreError::Database(err));
-            }
-        }?
-        .num_bytes;
-
-        Ok(byte_size as u64)
+    /// Internal method for growing the radius when the stored content takes up less than
+    /// `RADIUS_GROWTH_WATERMARK_PERCENT` of the capacity, eg. after the capacity was increased.
+    /// Content ids are spread evenly, so the stored content grows in proportion to the radius: the
+    /// radius is grown by the share of the capacity that is still free up to the watermark, rather
+    /// than taking in more content than fits at once. The radius is never grown past the maximum,
+    /// nor from zero.
+    fn grow_radius_if_below_watermark(&mut self) -> Result<(), ContentStoreError> {
+        if self.radius == Distance::MAX || self.radius == Distance::ZERO {
+            return Ok(());
+        }
+        let watermark = self.storage_capacity_in_bytes / 100 * RADIUS_GROWTH_WATERMARK_PERCENT;
+        let total_bytes = self.get_total_storage_usage_in_bytes_from_network()?;
+        if total_bytes >= watermark {
+            return Ok(());
+        }
+        let radius = match total_bytes {
+            0 => Distance::MAX,
+            total_bytes => (*self.radius / total_bytes)
+                .checked_mul(watermark.into())
+                .map_or(Distance::MAX, Distance::from),
+        };
+        if radius > self.radius {
+            debug!(
+                "Storage is below the growth watermark, increasing radius to: {}",
+                radius
+            );
+            self.set_radius(radius);
+        }
+        Ok(())
     }
 
     /// Public method for evicting a certain content id.
     pub fn evict(&self, id: [u8; 32]) -> anyhow::Result<()> {
+        if self.read_only {
+            return Err(ContentStoreError::ReadOnly.into());
+        }
         self.db_remove(&id)?;
         self.metrics.decrease_entry_count();
+        self.metrics.increase_eviction_count();
         Ok(())
     }
 
+    /// Returns the storage usage of the network.
+    pub fn db_stats(&self) -> Result<DbStats, ContentStoreError> {
+        Ok(DbStats {
+            content_bytes: self.get_total_storage_usage_in_bytes_from_network()?,
+            capacity_bytes: self.storage_capacity_in_bytes,
+            entry_count: self.total_entry_count()?,
+            eviction_count: self.metrics.eviction_count(),
+            radius: *self.radius,
+        })
+    }
+
     /// Public method for looking up a content key by its content id
     pub fn lookup_content_key(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
         let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_KEY_LOOKUP_QUERY_DB)?;
+        let mut query = conn.prepare(CONTENT_KEY_LOOKUP_QUERY_NETWORK)?;
         let id = id.to_vec();
         let result: Result<Vec<HistoryContentKey>, ContentStoreError> = query
-            .query_map([id], |row| {
+            .query_map(params![id, u8::from(self.network)], |row| {
                 let row: String = row.get(0)?;
                 Ok(row)
             })?
@@ -361,7 +589,7 @@ impl HistoryStorage {
     pub fn lookup_content_value(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
         let conn = self.sql_connection_pool.get()?;
 
-        lookup_content_value(id, conn)?
+        lookup_content_value(id, conn, u8::from(self.network))?
     }
 
     /// Public method for retrieving the node's current radius.
@@ -390,9 +618,10 @@ impl HistoryStorage {
 
     /// Internal method for removing a given content-id from the db.
     fn db_remove(&self, content_id: &[u8; 32]) -> Result<(), ContentStoreError> {
-        self.sql_connection_pool
-            .get()?
-            .execute(DELETE_QUERY_DB, [content_id.to_vec()])?;
+        self.sql_connection_pool.get()?.execute(
+            DELETE_QUERY_NETWORK,
+            params![content_id.to_vec(), u8::from(self.network)],
+        )?;
         Ok(())
     }
 
@@ -403,71 +632,57 @@ impl HistoryStorage {
     }
 
     /// Internal method for measuring the total amount of requestable data that the node is storing.
+    /// The total is kept in `store_info` as content is inserted and deleted.
     fn get_total_storage_usage_in_bytes_from_network(&self) -> Result<u64, ContentStoreError> {
         let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(TOTAL_DATA_SIZE_QUERY_DB)?;
-
-        let result = query.query_map([], |row| {
-            Ok(DataSize {
-                num_bytes: row.get(0)?,
-            })
-        });
-
-        let sum = match result?.next() {
-            Some(total) => total,
-            None => {
-                let err = "Unable to compute sum over content item sizes".to_string();
-                return Err(ContentStoreError::Database(err));
-            }
-        }?
-        .num_bytes;
-
-        self.metrics.report_content_data_storage_bytes(sum);
-
-        Ok(sum as u64)
+        let total: Option<u64> = conn
+            .query_row(
+                CONTENT_SIZE_TOTAL_QUERY_NETWORK,
+                [u8::from(self.network)],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let total = total.unwrap_or_default();
+
+        self.metrics.report_content_data_storage_bytes(total as f64);
+
+        Ok(total)
     }
 
     /// Internal method for finding the piece of stored data that has the farthest content id from
     /// our node id, according to xor distance. Used to determine which data to drop when at a
     /// capacity.
     fn find_farthest_content_id(&self) -> Result<Option<[u8; 32]>, ContentStoreError> {
-        let result = match self.distance_fn {
+        Ok(self
+            .find_farthest_content(1)?
+            .first()
+            .map(|(content_id, _)| *content_id))
+    }
+
+    /// Internal method for finding the ids and sizes of up to `limit` pieces of stored data
+    /// farthest from our node id, from the farthest to the closest.
+    fn find_farthest_content(&self, limit: u64) -> Result<Vec<([u8; 32], u64)>, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        match self.distance_fn {
+            DistanceFunction::Xor if self.distance_indexed => {
+                farthest_content(&conn, u8::from(self.network), limit)
+            }
+            // The distances of a read-only database may have been indexed for another node.
             DistanceFunction::Xor => {
                 let node_id_u32 = byte_vector_to_u32(self.node_id.raw().to_vec());
-
-                let conn = self.sql_connection_pool.get()?;
                 let mut query = conn.prepare(XOR_FIND_FARTHEST_QUERY_NETWORK)?;
-
-                let mut result =
-                    query.query_map([node_id_u32, u8::from(self.network).into()], |row| {
-                        Ok(ContentId {
-                            id_long: row.get(0)?,
-                        })
+                let rows = query
+                    .query_map(params![node_id_u32, u8::from(self.network)], |row| {
+                        Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, u64>(1)?))
                     })?;
-
-                let result = match result.next() {
-                    Some(row) => row,
-                    None => {
-                        return Ok(None);
-                    }
-                };
-                let result = result?.id_long;
-                let result_vec: [u8; 32] = match result.len() {
-                    // If exact data size, safe to expect conversion.
-                    32 => result.try_into().expect(
-                        "Unexpectedly failed to convert 32 element vec to 32 element array.",
-                    ),
-                    // Received data of size other than 32 bytes.
-                    length => {
-                        let err = format!("content ID of length {length} != 32");
-                        return Err(ContentStoreError::InvalidData { message: err });
-                    }
-                };
-                result_vec
+                rows.take(limit as usize)
+                    .map(|row| {
+                        let (content_id, content_size) = row?;
+                        Ok((content_id_from_bytes(content_id)?, content_size))
+                    })
+                    .collect()
             }
-        };
-
-        Ok(Some(result))
+        }
     }
 
     /// Method that returns the distance between our node ID and a given content ID.
@@ -483,6 +698,109 @@ impl HistoryStorage {
     }
 }
 
+/// Writes the content of the history network from local storage to content archives.
+pub struct ArchiveExporter {
+    sql_connection_pool: Pool<SqliteConnectionManager>,
+    network: ProtocolId,
+}
+
+impl ArchiveExporter {
+    /// Writes the content to a content archive at `path`, for the same range of content as
+    /// `HistoryStorage::paginate`. Returns the number of exported items.
+    pub fn export(&self, path: &Path, offset: u64, limit: u64) -> Result<u64, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        let mut query = conn.prepare(EXPORT_QUERY_NETWORK)?;
+        let rows = query.query_map(
+            &[
+                (":network", u8::from(self.network).to_string().as_str()),
+                (":offset", offset.to_string().as_str()),
+                (":limit", limit.to_string().as_str()),
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?;
+
+        let mut archive =
+            ArchiveWriter::new(BufWriter::new(File::create(path)?), self.network.into())?;
+        for row in rows {
+            let (content_key, content_value) = row?;
+            // keys are stored without 0x prefix, so we must add it
+            let content_key = hex_decode(&format!("0x{content_key}"))?;
+            let content_value = hex_decode(&content_value)?;
+            archive.write_entry(&content_key, &content_value)?;
+        }
+        Ok(archive.finish()?)
+    }
+}
+
+/// Checks a stored history content item, given the columns of its row.
+fn check_content(
+    content_id: &[u8],
+    content_key: &str,
+    content_value: &str,
+) -> Result<(), CorruptionReason> {
+    // keys are stored without 0x prefix, so we must add it
+    let content_key = hex_decode(&format!("0x{content_key}"))
+        .map_err(|err| CorruptionReason::InvalidContentKey(err.to_string()))?;
+    let content_key = HistoryContentKey::try_from(content_key)
+        .map_err(|err| CorruptionReason::InvalidContentKey(err.to_string()))?;
+    let expected = content_key.content_id();
+    if content_id != expected.as_slice() {
+        return Err(CorruptionReason::ContentIdMismatch { expected });
+    }
+
+    let content_value = hex_decode(content_value)
+        .map_err(|err| CorruptionReason::InvalidContentValue(err.to_string()))?;
+    let invalid_value =
+        |err: ssz::DecodeError| CorruptionReason::InvalidContentValue(format!("{err:?}"));
+    match content_key {
+        HistoryContentKey::BlockHeaderWithProof(key) => {
+            let header_with_proof =
+                HeaderWithProof::from_ssz_bytes(&content_value).map_err(invalid_value)?;
+            let header_hash = header_with_proof.header.hash();
+            if header_hash != H256::from(key.block_hash) {
+                return Err(CorruptionReason::HashMismatch(format!(
+                    "header hashes to {header_hash:?}"
+                )));
+            }
+        }
+        HistoryContentKey::BlockBody(_) => {
+            BlockBody::from_ssz_bytes(&content_value).map_err(invalid_value)?;
+        }
+        HistoryContentKey::BlockReceipts(_) => {
+            Receipts::from_ssz_bytes(&content_value).map_err(invalid_value)?;
+        }
+        HistoryContentKey::EpochAccumulator(key) => {
+            let epoch_acc =
+                EpochAccumulator::from_ssz_bytes(&content_value).map_err(invalid_value)?;
+            let tree_hash_root = epoch_acc.tree_hash_root();
+            if tree_hash_root != key.epoch_hash {
+                return Err(CorruptionReason::HashMismatch(format!(
+                    "epoch accumulator hashes to {tree_hash_root:?}"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fails the content of a batch that was going to be stored with `err`, keeping the errors of the
+/// content that was rejected beforehand.
+fn batch_error(
+    results: Vec<Result<(), ContentStoreError>>,
+    err: &ContentStoreError,
+) -> Vec<Result<(), ContentStoreError>> {
+    results
+        .into_iter()
+        .map(|result| {
+            result.and_then(|()| {
+                Err(ContentStoreError::Database(format!(
+                    "Error storing batch of content: {err}"
+                )))
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 pub mod test {
@@ -490,12 +808,15 @@ pub mod test {
     use discv5::enr::{CombinedKey, Enr as Discv5Enr};
     use ethportal_api::{
         types::{distance::Distance, portal_wire::ProtocolId},
-        BlockHeaderKey, HistoryContentKey, IdentityContentKey,
+        BlockHeaderKey, EpochAccumulatorKey, HistoryContentKey, IdentityContentKey,
     };
     use portalnet::utils::db::{configure_node_data_dir, setup_temp_dir};
     use quickcheck::{quickcheck, QuickCheck, TestResult};
     use rand::RngCore;
     use serial_test::serial;
+    use ssz::Encode;
+    use std::io::BufReader;
+    use trin_storage::{archive::ArchiveReader, StorageBackend};
 
     const CAPACITY_MB: u64 = 2;
 
@@ -506,7 +827,7 @@ pub mod test {
     }
 
     fn get_active_node_id(temp_dir: PathBuf) -> NodeId {
-        let (_, mut pk) = configure_node_data_dir(temp_dir, None).unwrap();
+        let (_, mut pk) = configure_node_data_dir(temp_dir, None, None).unwrap();
         let pk = CombinedKey::secp256k1_from_bytes(pk.0.as_mut_slice()).unwrap();
         Discv5Enr::empty(&pk).unwrap().node_id()
     }
@@ -580,6 +901,31 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_get_data_from_memory_backend() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config = PortalStorageConfig::new_with_backend(
+            CAPACITY_MB,
+            temp_dir.path().to_path_buf(),
+            node_id,
+            StorageBackend::Memory,
+        )
+        .unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey::default());
+        let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+        storage.store(&content_key, &value)?;
+
+        assert_eq!(storage.get(&content_key).unwrap().unwrap(), value);
+        assert!(!temp_dir.path().join(trin_storage::DATABASE_NAME).exists());
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_get_total_storage() -> Result<(), ContentStoreError> {
@@ -602,6 +948,297 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_db_stats() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        let evictions = storage.db_stats()?.eviction_count;
+
+        let content_keys = [generate_random_content_key(), generate_random_content_key()];
+        for content_key in &content_keys {
+            storage.store(content_key, &vec![0; 32])?;
+        }
+        storage.evict(content_keys[0].content_id()).unwrap();
+
+        let stats = storage.db_stats()?;
+        assert_eq!(stats.content_bytes, 32);
+        assert_eq!(stats.capacity_bytes, CAPACITY_MB * BYTES_IN_MB_U64);
+        assert_eq!(stats.entry_count, 1);
+        assert_eq!(stats.eviction_count, evictions + 1);
+        assert_eq!(stats.radius, *Distance::MAX);
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_put_batch() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+
+        let content_keys = [generate_random_content_key(), generate_random_content_key()];
+        let items = content_keys
+            .iter()
+            .map(|content_key| (content_key.clone(), vec![1; 32]))
+            .collect();
+        let results = storage.put_batch(items);
+        assert!(results.iter().all(Result::is_ok));
+        for content_key in &content_keys {
+            assert_eq!(storage.get(content_key)?, Some(vec![1; 32]));
+        }
+        assert_eq!(storage.db_stats()?.entry_count, 2);
+
+        // Content outside the radius is rejected, without failing the rest of the batch.
+        let (near_key, far_key) = (generate_random_content_key(), generate_random_content_key());
+        let (near_distance, far_distance) = (
+            storage.distance_to_key(&near_key),
+            storage.distance_to_key(&far_key),
+        );
+        let (near_key, far_key) = match near_distance <= far_distance {
+            true => (near_key, far_key),
+            false => (far_key, near_key),
+        };
+        storage.set_radius(near_distance.min(far_distance));
+        let results = storage.put_batch(vec![(far_key, vec![2]), (near_key.clone(), vec![3])]);
+        assert!(matches!(
+            results[0],
+            Err(ContentStoreError::InsufficientRadius { .. })
+        ));
+        assert!(results[1].is_ok());
+        assert_eq!(storage.get(&near_key)?, Some(vec![3]));
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_export_archive() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        let mut content = vec![];
+        for _ in 0..3 {
+            let mut block_hash = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut block_hash);
+            let content_key =
+                HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey { block_hash });
+            storage.put(content_key.clone(), block_hash)?;
+            content.push((content_key.to_bytes(), block_hash.to_vec()));
+        }
+        // Archives are ordered by content key.
+        content.sort();
+
+        let archive_path = temp_dir.path().join("content.archive");
+        let exporter = storage.archive_exporter();
+        assert_eq!(exporter.export(&archive_path, 0, 2)?, 2);
+        assert_eq!(exporter.export(&archive_path, 0, 10)?, 3);
+
+        let archive = ArchiveReader::new(BufReader::new(File::open(&archive_path)?))?;
+        assert_eq!(archive.network(), u8::from(ProtocolId::History));
+        let entries = archive.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries, content);
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_check_and_repair() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        storage.set_radius(Distance::MAX);
+
+        let epoch_acc = EpochAccumulator::default();
+        let valid_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+            epoch_hash: epoch_acc.tree_hash_root(),
+        });
+        storage.put(valid_key, epoch_acc.as_ssz_bytes())?;
+        let wrong_hash_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+            epoch_hash: H256::repeat_byte(1),
+        });
+        storage.put(wrong_hash_key.clone(), epoch_acc.as_ssz_bytes())?;
+        let invalid_value_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: [2; 32],
+        });
+        storage.put(invalid_value_key.clone(), [3; 10])?;
+        // A content key stored under the content id of another key.
+        let misplaced_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: [4; 32],
+        });
+        let misplaced_key_hex = hex_encode(misplaced_key.to_bytes())
+            .trim_start_matches("0x")
+            .to_string();
+        storage.db_insert(&[5; 32], &misplaced_key_hex, &vec![])?;
+
+        let report = storage.check(false)?;
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.deleted, 0);
+        let mut corrupt_ids: Vec<Vec<u8>> = report
+            .corrupt
+            .iter()
+            .map(|corrupt| corrupt.content_id.clone())
+            .collect();
+        corrupt_ids.sort();
+        let mut expected_ids = vec![
+            wrong_hash_key.content_id().to_vec(),
+            invalid_value_key.content_id().to_vec(),
+            vec![5; 32],
+        ];
+        expected_ids.sort();
+        assert_eq!(corrupt_ids, expected_ids);
+        assert!(report.corrupt.iter().any(|corrupt| matches!(
+            corrupt.reason,
+            CorruptionReason::ContentIdMismatch { expected } if expected == misplaced_key.content_id()
+        )));
+
+        let report = storage.check(true)?;
+        assert_eq!(report.deleted, 3);
+        let report = storage.check(false)?;
+        assert_eq!(report.checked, 1);
+        assert!(report.corrupt.is_empty());
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_latest_header() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        storage.set_radius(Distance::MAX);
+        assert!(storage.latest_header()?.is_none());
+
+        let file =
+            std::fs::read_to_string("../trin-validation/src/assets/fluffy/header_with_proofs.json")
+                .unwrap();
+        let headers: serde_json::Value = serde_json::from_str(&file).unwrap();
+        for block_number in ["1000002", "1000005", "1000003"] {
+            let header = &headers[block_number];
+            let content_key: HistoryContentKey =
+                serde_json::from_value(header["content_key"].clone()).unwrap();
+            let content_value = hex_decode(header["value"].as_str().unwrap()).unwrap();
+            storage.put(content_key, content_value)?;
+        }
+        // Content other than headers is ignored.
+        let epoch_acc = EpochAccumulator::default();
+        let epoch_acc_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+            epoch_hash: epoch_acc.tree_hash_root(),
+        });
+        storage.put(epoch_acc_key, epoch_acc.as_ssz_bytes())?;
+
+        assert_eq!(storage.latest_header()?.unwrap().number, 1000005);
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_read_only_storage() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        let stored_key = generate_random_content_key();
+        storage.put(stored_key.clone(), [1; 10])?;
+        drop(storage);
+
+        let read_only_config = PortalStorageConfig::new_with_backend(
+            CAPACITY_MB,
+            temp_dir.path().to_path_buf(),
+            node_id,
+            StorageBackend::SqliteReadOnly,
+        )
+        .unwrap();
+        let mut storage = HistoryStorage::new(read_only_config, ProtocolId::History)?;
+        assert_eq!(storage.get(&stored_key)?, Some(vec![1; 10]));
+        assert_eq!(
+            storage.radius(),
+            storage.distance_to_content_id(&stored_key.content_id())
+        );
+
+        let new_key = generate_random_content_key();
+        assert!(matches!(
+            storage.put(new_key.clone(), [2; 10]),
+            Err(ContentStoreError::ReadOnly)
+        ));
+        assert!(matches!(
+            storage.put_batch(vec![(new_key.clone(), [2; 10])])[..],
+            [Err(ContentStoreError::ReadOnly)]
+        ));
+        assert_ne!(
+            storage.is_key_within_radius_and_unavailable(&new_key)?,
+            ShouldWeStoreContent::Store
+        );
+        assert!(storage.evict(stored_key.content_id()).is_err());
+        assert_eq!(storage.get(&stored_key)?, Some(vec![1; 10]));
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_networks_are_separated() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut history_storage = HistoryStorage::new(storage_config.clone(), ProtocolId::History)?;
+        let mut other_storage = HistoryStorage::new(storage_config, ProtocolId::State)?;
+
+        // The same content key is stored separately for each network.
+        let content_key = generate_random_content_key();
+        history_storage.store(&content_key, &vec![1; 32])?;
+        assert_eq!(other_storage.get(&content_key)?, None);
+        other_storage.store(&content_key, &vec![2; 64])?;
+        assert_eq!(history_storage.get(&content_key)?, Some(vec![1; 32]));
+        assert_eq!(other_storage.get(&content_key)?, Some(vec![2; 64]));
+
+        // Each network only accounts for its own content.
+        assert_eq!(
+            history_storage.get_total_storage_usage_in_bytes_from_network()?,
+            32
+        );
+        assert_eq!(
+            other_storage.get_total_storage_usage_in_bytes_from_network()?,
+            64
+        );
+        history_storage.evict(content_key.content_id()).unwrap();
+        assert_eq!(other_storage.get(&content_key)?, Some(vec![2; 64]));
+
+        drop(history_storage);
+        drop(other_storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_restarting_storage_with_decreased_capacity() -> Result<(), ContentStoreError> {
@@ -627,10 +1264,10 @@ pub mod test {
             PortalStorageConfig::new(1, temp_dir.path().to_path_buf(), node_id).unwrap();
         let new_storage = HistoryStorage::new(new_storage_config, ProtocolId::History)?;
 
-        // test that previously set value has been pruned
+        // test that previously set value has been pruned, down to the low watermark
         let bytes = new_storage.get_total_storage_usage_in_bytes_from_network()?;
-        assert_eq!(1024000, bytes);
-        assert_eq!(32, new_storage.total_entry_count().unwrap());
+        assert_eq!(928000, bytes); // 32kb * 29
+        assert_eq!(29, new_storage.total_entry_count().unwrap());
         assert_eq!(new_storage.storage_capacity_in_bytes, BYTES_IN_MB_U64);
         // test that radius has decreased now that we're at capacity
         assert!(new_storage.radius < Distance::MAX);
@@ -663,17 +1300,22 @@ pub mod test {
             PortalStorageConfig::new(min_capacity, temp_dir.path().to_path_buf(), node_id).unwrap();
         let mut storage = HistoryStorage::new(storage_config.clone(), ProtocolId::History)?;
 
-        // Fill up the storage.
+        // Fill up the storage, until it gets pruned.
         for _ in 0..32 {
             let content_key = generate_random_content_key();
             let value: Vec<u8> = vec![0; 32000];
             storage.store(&content_key, &value)?;
             // Speed up the test by ending the loop as soon as possible
-            if storage.capacity_reached()? {
+            if storage.radius < Distance::MAX {
                 break;
             }
         }
-        assert!(storage.capacity_reached()?);
+        // The store was pruned to below the capacity.
+        assert!(!storage.capacity_reached()?);
+        assert!(
+            storage.get_total_storage_usage_in_bytes_from_network()?
+                <= min_capacity * BYTES_IN_MB_U64 / 100 * PRUNING_LOW_WATERMARK_PERCENT
+        );
 
         // Save the number of items, to compare with the restarted storage
         let total_entry_count = storage.total_entry_count().unwrap();
@@ -686,8 +1328,8 @@ pub mod test {
 
         // The restarted store should have the same number of items
         assert_eq!(total_entry_count, new_storage.total_entry_count().unwrap());
-        // The restarted store should be full
-        assert!(new_storage.capacity_reached()?);
+        // The restarted store should not need pruning
+        assert!(!new_storage.capacity_reached()?);
         // The restarted store should have the same radius as the original
         assert_eq!(radius, new_storage.radius);
 
@@ -697,12 +1339,46 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_restarting_full_storage_with_increased_capacity() -> Result<(), ContentStoreError>
+    {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = get_active_node_id(temp_dir.path().to_path_buf());
+        let storage_config =
+            PortalStorageConfig::new(1, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+
+        // Fill up the storage, so that the radius shrinks.
+        for _ in 0..32 {
+            let content_key = generate_random_content_key();
+            let value: Vec<u8> = vec![0; 32000];
+            storage.store(&content_key, &value)?;
+            if storage.capacity_reached()? {
+                break;
+            }
+        }
+        let radius = storage.radius;
+        assert!(radius < Distance::MAX);
+        std::mem::drop(storage);
+
+        // Restart with the capacity doubled, which leaves the store below the watermark.
+        let new_storage_config =
+            PortalStorageConfig::new(2, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let new_storage = HistoryStorage::new(new_storage_config, ProtocolId::History)?;
+        assert!(new_storage.radius > radius);
+
+        std::mem::drop(new_storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_restarting_storage_with_increased_capacity() -> Result<(), ContentStoreError> {
         let temp_dir = setup_temp_dir().unwrap();
         let (node_data_dir, mut private_key) =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), None).unwrap();
+            configure_node_data_dir(temp_dir.path().to_path_buf(), None, None).unwrap();
         let private_key = CombinedKey::secp256k1_from_bytes(private_key.0.as_mut_slice()).unwrap();
         let node_id = Discv5Enr::empty(&private_key).unwrap().node_id();
         let storage_config =
@@ -766,6 +1442,47 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_distance_index() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), NodeId::random())
+                .unwrap();
+        let mut storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        let keys: Vec<IdentityContentKey> =
+            (0..10).map(|_| generate_random_content_key()).collect();
+        for key in keys.iter() {
+            storage.put(key.clone(), [1; 10])?;
+        }
+        drop(storage);
+
+        // The distances are indexed again when the database is opened by another node.
+        let node_id = NodeId::random();
+        let storage_config =
+            PortalStorageConfig::new(CAPACITY_MB, temp_dir.path().to_path_buf(), node_id).unwrap();
+        let storage = HistoryStorage::new(storage_config, ProtocolId::History)?;
+        assert!(storage.distance_indexed);
+
+        let mut content_ids: Vec<[u8; 32]> = keys.iter().map(|key| key.content_id()).collect();
+        content_ids.sort_by_key(|content_id| {
+            std::cmp::Reverse(storage.distance_to_content_id(content_id))
+        });
+        let farthest: Vec<[u8; 32]> = storage
+            .find_farthest_content(3)?
+            .into_iter()
+            .map(|(content_id, content_size)| {
+                assert_eq!(content_size, 10);
+                content_id
+            })
+            .collect();
+        assert_eq!(farthest, content_ids[..3]);
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_find_farthest_empty_db() -> Result<(), ContentStoreError> {
@@ -50,7 +50,10 @@ pub fn bucket_entries_to_json(bucket_entries: BTreeMap<usize, Vec<NodeTuple>>) -
                             // decoding.
                             let port = match enr.udp4_socket() {
                                 Some(port) => format!("{port}"),
-                                None => "None".to_string(),
+                                None => match enr.udp6_socket() {
+                                    Some(port) => format!("{port}"),
+                                    None => "None".to_string(),
+                                },
                             };
                             map.insert("address".to_owned(), port);
                         }
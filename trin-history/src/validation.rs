@@ -50,11 +50,15 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
                     .read()
                     .await
                     .master_acc
-                    .validate_header_with_proof(&header_with_proof)
+                    .validate_header_with_proof(&header_with_proof)?;
+                // State proofs against this header's state root can be accepted from now on.
+                self.header_oracle
+                    .write()
+                    .await
+                    .add_state_root(header_with_proof.header.state_root);
+                Ok(())
             }
             HistoryContentKey::BlockBody(key) => {
-                let block_body = BlockBody::from_ssz_bytes(content)
-                    .map_err(|msg| anyhow!("Block Body content has invalid encoding: {:?}", msg))?;
                 let trusted_header: Header = self
                     .header_oracle
                     .read()
@@ -62,28 +66,9 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
                     .recursive_find_header_with_proof(H256::from(key.block_hash))
                     .await?
                     .header;
-                let actual_uncles_root = block_body.uncles_root()?;
-                if actual_uncles_root != trusted_header.uncles_hash {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid uncles root. Found: {:?} - Expected: {:?}",
-                        actual_uncles_root,
-                        trusted_header.uncles_hash
-                    ));
-                }
-                let actual_txs_root = block_body.transactions_root()?;
-                if actual_txs_root != trusted_header.transactions_root {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid transactions root. Found: {:?} - Expected: {:?}",
-                        actual_txs_root,
-                        trusted_header.transactions_root
-                    ));
-                }
-                Ok(())
+                validate_block_body(content, &trusted_header)
             }
             HistoryContentKey::BlockReceipts(key) => {
-                let receipts = Receipts::from_ssz_bytes(content).map_err(|msg| {
-                    anyhow!("Block Receipts content has invalid encoding: {:?}", msg)
-                })?;
                 let trusted_header: Header = self
                     .header_oracle
                     .read()
@@ -91,15 +76,7 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
                     .recursive_find_header_with_proof(H256::from(key.block_hash))
                     .await?
                     .header;
-                let actual_receipts_root = receipts.root()?;
-                if actual_receipts_root != trusted_header.receipts_root {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid receipts root. Found: {:?} - Expected: {:?}",
-                        actual_receipts_root,
-                        trusted_header.receipts_root
-                    ));
-                }
-                Ok(())
+                validate_receipts(content, &trusted_header)
             }
             HistoryContentKey::EpochAccumulator(key) => {
                 let epoch_acc = EpochAccumulator::from_ssz_bytes(content).map_err(|msg| {
@@ -127,6 +104,33 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
     }
 }
 
+/// Validates a block body against the trusted header of its block.
+pub fn validate_block_body(content: &[u8], trusted_header: &Header) -> anyhow::Result<()> {
+    // The header's timestamp decides which fork's body encoding is expected, so that
+    // post-Shanghai bodies are always checked against the withdrawals root.
+    let block_body =
+        BlockBody::decode_with_timestamp(content.to_vec(), trusted_header.timestamp)
+            .map_err(|msg| anyhow!("Block Body content has invalid encoding: {:?}", msg))?;
+    block_body
+        .validate_against_header(trusted_header)
+        .map_err(|err| anyhow!("Content validation failed: {err}"))
+}
+
+/// Validates the receipts of a block against the trusted header of the block.
+pub fn validate_receipts(content: &[u8], trusted_header: &Header) -> anyhow::Result<()> {
+    let receipts = Receipts::from_ssz_bytes(content)
+        .map_err(|msg| anyhow!("Block Receipts content has invalid encoding: {:?}", msg))?;
+    let actual_receipts_root = receipts.root()?;
+    if actual_receipts_root != trusted_header.receipts_root {
+        return Err(anyhow!(
+            "Content validation failed: Invalid receipts root. Found: {:?} - Expected: {:?}",
+            actual_receipts_root,
+            trusted_header.receipts_root
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -170,6 +174,35 @@ mod tests {
             .unwrap();
     }
 
+    #[test_log::test(tokio::test)]
+    async fn validated_headers_anchor_state_proofs() {
+        let hwp_ssz = get_hwp_ssz();
+        let hwp = HeaderWithProof::from_ssz_bytes(&hwp_ssz).expect("error decoding header");
+        let header_oracle = default_header_oracle();
+        let state_root = hwp.header.state_root;
+        assert!(header_oracle
+            .read()
+            .await
+            .validate_state_root(state_root)
+            .is_err());
+
+        let chain_history_validator = ChainHistoryValidator {
+            header_oracle: header_oracle.clone(),
+        };
+        let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: hwp.header.hash().0,
+        });
+        chain_history_validator
+            .validate_content(&content_key, &hwp_ssz)
+            .await
+            .unwrap();
+        header_oracle
+            .read()
+            .await
+            .validate_state_root(state_root)
+            .unwrap();
+    }
+
     #[test_log::test(tokio::test)]
     #[should_panic(expected = "Merkle proof validation failed for pre-merge header")]
     async fn invalidate_header_with_invalid_number() {
@@ -19,6 +19,7 @@ pub struct OverlayMetrics {
     pub utp_outcome_total: IntCounterVec,
     pub utp_active_gauge: IntGaugeVec,
     pub validation_total: IntCounterVec,
+    pub offered_content_keys_total: IntCounterVec,
 }
 
 impl OverlayMetrics {
@@ -55,11 +56,20 @@ impl OverlayMetrics {
             &["protocol", "success"],
             registry
         )?;
+        let offered_content_keys_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_offered_content_keys_total",
+                "count all content keys offered to peers accepted and declined"
+            ),
+            &["protocol", "accepted"],
+            registry
+        )?;
         Ok(Self {
             message_total,
             utp_outcome_total,
             utp_active_gauge,
             validation_total,
+            offered_content_keys_total,
         })
     }
 }
@@ -179,6 +189,32 @@ impl OverlayMetricsReporter {
             .inc();
     }
 
+    //
+    // Offered content keys
+    //
+    /// Returns the value of the given metric with the specified labels.
+    fn offered_content_keys_total_by_outcome(&self, accepted: bool) -> u64 {
+        let accepted = accepted.to_string();
+        let labels: [&str; 2] = [&self.protocol, accepted.as_str()];
+        self.overlay_metrics
+            .offered_content_keys_total
+            .with_label_values(&labels)
+            .get()
+    }
+
+    /// Reports the number of content keys of an outbound OFFER that the peer accepted and
+    /// declined.
+    pub fn report_offered_content_keys(&self, accepted: u64, declined: u64) {
+        for (outcome, count) in [(true, accepted), (false, declined)] {
+            let outcome = outcome.to_string();
+            let labels: [&str; 2] = [&self.protocol, outcome.as_str()];
+            self.overlay_metrics
+                .offered_content_keys_total
+                .with_label_values(&labels)
+                .inc_by(count);
+        }
+    }
+
     pub fn get_utp_summary(&self) -> String {
         let inbound_success =
             self.utp_outcome_total(UtpDirectionLabel::Inbound, UtpOutcomeLabel::Success);
@@ -236,14 +272,18 @@ impl OverlayMetricsReporter {
         // for every offer you received, how many accepts did you make
         let successful_validations = self.validation_total_by_outcome(true);
         let failed_validations = self.validation_total_by_outcome(false);
+        let accepted_keys = self.offered_content_keys_total_by_outcome(true);
+        let declined_keys = self.offered_content_keys_total_by_outcome(false);
         format!(
-            "offers={}/{}, accepts={}/{}, validations={}/{}",
+            "offers={}/{}, accepts={}/{}, validations={}/{}, offered_keys={}/{}",
             self.message_total_by_labels(MessageDirectionLabel::Received, MessageLabel::Accept),
             self.message_total_by_labels(MessageDirectionLabel::Sent, MessageLabel::Offer),
             self.message_total_by_labels(MessageDirectionLabel::Sent, MessageLabel::Accept),
             self.message_total_by_labels(MessageDirectionLabel::Received, MessageLabel::Offer),
             successful_validations,
             successful_validations + failed_validations,
+            accepted_keys,
+            accepted_keys + declined_keys,
         )
     }
 }
@@ -2,8 +2,8 @@ use ethportal_api::types::distance::Distance;
 use prometheus_exporter::{
     self,
     prometheus::{
-        opts, register_gauge_vec_with_registry, register_int_gauge_vec_with_registry, GaugeVec,
-        IntGaugeVec, Registry,
+        opts, register_gauge_vec_with_registry, register_int_counter_vec_with_registry,
+        register_int_gauge_vec_with_registry, GaugeVec, IntCounterVec, IntGaugeVec, Registry,
     },
 };
 
@@ -15,6 +15,7 @@ pub struct StorageMetrics {
     pub storage_capacity_bytes: GaugeVec,
     pub radius_ratio: GaugeVec,
     pub entry_count: IntGaugeVec,
+    pub eviction_count: IntCounterVec,
 }
 
 const BYTES_IN_MB_F64: f64 = 1000.0 * 1000.0;
@@ -58,12 +59,21 @@ impl StorageMetrics {
             &["protocol"],
             registry
         )?;
+        let eviction_count = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_eviction_count",
+                "total number of storage entries evicted to stay within capacity"
+            ),
+            &["protocol"],
+            registry
+        )?;
         Ok(Self {
             content_storage_usage_bytes,
             total_storage_usage_bytes,
             storage_capacity_bytes,
             radius_ratio,
             entry_count,
+            eviction_count,
         })
     }
 }
@@ -135,6 +145,21 @@ impl StorageMetricsReporter {
             .dec();
     }
 
+    pub fn increase_eviction_count(&self) {
+        self.storage_metrics
+            .eviction_count
+            .with_label_values(&[&self.protocol])
+            .inc();
+    }
+
+    /// Returns the number of entries evicted since the node started.
+    pub fn eviction_count(&self) -> u64 {
+        self.storage_metrics
+            .eviction_count
+            .with_label_values(&[&self.protocol])
+            .get()
+    }
+
     pub fn get_summary(&self) -> String {
         let radius_percent = self
             .storage_metrics
@@ -1,10 +1,22 @@
 use std::sync::Arc;
 
+use ethportal_api::{
+    types::{
+        constants::CONTENT_ABSENT,
+        distance::Distance,
+        jsonrpc::{endpoints::StateEndpoint, error::JsonRpcError, request::StateJsonRpcRequest},
+        portal::{AcceptInfo, FindNodesInfo, PongInfo},
+        portal_wire::Content,
+        state::ContentInfo,
+    },
+    utils::bytes::hex_encode,
+    ContentValue, RawContentKey, StateContentKey, StateContentValue,
+};
+use serde_json::{json, Value};
 use tokio::sync::mpsc;
-use tracing::error;
+use tracing::{error, Instrument};
 
 use crate::network::StateNetwork;
-use ethportal_api::types::jsonrpc::request::StateJsonRpcRequest;
 
 /// Handles State network JSON-RPC requests
 pub struct StateRequestHandler {
@@ -13,9 +25,213 @@ pub struct StateRequestHandler {
 }
 
 impl StateRequestHandler {
+    /// Complete RPC requests for the State network.
     pub async fn handle_client_queries(mut self) {
-        while let Some(_request) = self.state_rx.recv().await {
-            error!("State JSON-RPC handler is not implemented!")
+        while let Some(request) = self.state_rx.recv().await {
+            let network = self.network.clone();
+            let span = request.span.clone();
+            tokio::spawn(async move { complete_request(network, request).await }.instrument(span));
         }
     }
 }
+
+/// Generates a response for a given request and sends it to the receiver.
+async fn complete_request(network: Arc<StateNetwork>, request: StateJsonRpcRequest) {
+    let response: Result<Value, JsonRpcError> = match request.endpoint {
+        StateEndpoint::DataRadius => {
+            let radius = network.overlay.data_radius();
+            Ok(json!(*radius))
+        }
+        StateEndpoint::FindContent(enr, content_key) => {
+            find_content(network, enr, content_key).await
+        }
+        StateEndpoint::FindNodes(enr, distances) => find_nodes(network, enr, distances).await,
+        StateEndpoint::Gossip(content_key, content_value) => {
+            gossip(network, content_key, content_value).await
+        }
+        StateEndpoint::LocalContent(content_key) => local_content(network, content_key).await,
+        StateEndpoint::Offer(enr, content_key, content_value) => {
+            offer(network, enr, content_key, content_value).await
+        }
+        StateEndpoint::Store(content_key, content_value) => {
+            store(network, content_key, content_value).await
+        }
+        StateEndpoint::Ping(enr) => ping(network, enr).await,
+        StateEndpoint::RecursiveFindContent(content_key) => {
+            recursive_find_content(network, content_key).await
+        }
+        StateEndpoint::RoutingTableInfo => {
+            serde_json::to_value(network.overlay.routing_table_info())
+                .map_err(|err| JsonRpcError::internal(err.to_string()))
+        }
+        StateEndpoint::Peers => serde_json::to_value(network.overlay.peers())
+            .map_err(|err| JsonRpcError::internal(err.to_string())),
+    };
+    let _ = request.resp.send(response);
+}
+
+/// Constructs a JSON call for the RecursiveFindContent method.
+async fn recursive_find_content(
+    network: Arc<StateNetwork>,
+    content_key: StateContentKey,
+) -> Result<Value, JsonRpcError> {
+    // Check whether we have the data locally.
+    let local_content = match network.overlay.store.get(content_key.clone()).await {
+        Ok(Some(data)) => Some(data),
+        Ok(None) => None,
+        Err(err) => {
+            error!(
+                error = %err,
+                content.key = %content_key,
+                "Error checking data store for content",
+            );
+            None
+        }
+    };
+    let (possible_content_bytes, utp_transfer) = match local_content {
+        Some(val) => (Some(val), false),
+        None => {
+            let (content, utp_transfer, _trace) =
+                network.overlay.lookup_content(content_key, false).await;
+            (content, utp_transfer)
+        }
+    };
+
+    // Format as string.
+    let content_response_string = match possible_content_bytes {
+        Some(bytes) => Value::String(hex_encode(bytes)),
+        None => Value::String(CONTENT_ABSENT.to_string()), // "0x"
+    };
+
+    Ok(json!(ContentInfo::Content {
+        content: serde_json::from_value(content_response_string)
+            .map_err(|err| JsonRpcError::internal(err.to_string()))?,
+        utp_transfer,
+    }))
+}
+
+/// Constructs a JSON call for the LocalContent method.
+async fn local_content(
+    network: Arc<StateNetwork>,
+    content_key: StateContentKey,
+) -> Result<Value, JsonRpcError> {
+    match network.overlay.store.get(content_key.clone()).await {
+        Ok(Some(val)) => Ok(Value::String(hex_encode(val))),
+        Ok(None) => Ok(Value::String(CONTENT_ABSENT.to_string())),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the Store method.
+async fn store(
+    network: Arc<StateNetwork>,
+    content_key: StateContentKey,
+    content_value: StateContentValue,
+) -> Result<Value, JsonRpcError> {
+    let data = content_value.encode();
+    match network.overlay.store.put(content_key.clone(), data).await {
+        Ok(_) => Ok(Value::Bool(true)),
+        Err(err) => Err(JsonRpcError::storage(format!(
+            "Database error while storing content key: {content_key:?}, with error: {err}"
+        ))),
+    }
+}
+
+/// Constructs a JSON call for the FindContent method.
+async fn find_content(
+    network: Arc<StateNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_key: StateContentKey,
+) -> Result<Value, JsonRpcError> {
+    match network
+        .overlay
+        .send_find_content(enr, content_key.into())
+        .await
+    {
+        Ok((content, utp_transfer)) => match content {
+            Content::ConnectionId(id) => Err(JsonRpcError::internal(format!(
+                "FindContent request returned a connection id ({id:?}) instead of conducting utp transfer."
+            ))),
+            Content::Content(content) => Ok(json!({
+                "content": hex_encode(content),
+                "utpTransfer": utp_transfer,
+            })),
+            Content::Enrs(enrs) => Ok(json!({
+                "enrs": enrs,
+            })),
+        },
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Constructs a JSON call for the FindNodes method.
+async fn find_nodes(
+    network: Arc<StateNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    distances: Vec<u16>,
+) -> Result<Value, JsonRpcError> {
+    match network.overlay.send_find_nodes(enr, distances).await {
+        Ok(nodes) => Ok(json!(nodes
+            .enrs
+            .into_iter()
+            .map(|enr| enr.into())
+            .collect::<FindNodesInfo>())),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Constructs a JSON call for the Gossip method.
+async fn gossip(
+    network: Arc<StateNetwork>,
+    content_key: StateContentKey,
+    content_value: StateContentValue,
+) -> Result<Value, JsonRpcError> {
+    let data = content_value.encode();
+    Ok(network
+        .overlay
+        .propagate_gossip(vec![(content_key, data)])
+        .into())
+}
+
+/// Constructs a JSON call for the Offer method.
+async fn offer(
+    network: Arc<StateNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_key: StateContentKey,
+    content_value: Option<StateContentValue>,
+) -> Result<Value, JsonRpcError> {
+    let accept = match content_value {
+        Some(content_value) => {
+            network
+                .overlay
+                .send_populated_offer(enr, content_key.into(), content_value.encode())
+                .await
+        }
+        None => {
+            let content_key: Vec<RawContentKey> = vec![content_key.into()];
+            network.overlay.send_offer(content_key, enr).await
+        }
+    };
+    match accept {
+        Ok(accept) => Ok(json!(AcceptInfo {
+            content_keys: accept.content_keys,
+        })),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Constructs a JSON call for the Ping method.
+async fn ping(
+    network: Arc<StateNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+) -> Result<Value, JsonRpcError> {
+    match network.overlay.send_ping(enr).await {
+        Ok(pong) => Ok(json!(PongInfo {
+            enr_seq: pong.enr_seq as u32,
+            data_radius: *Distance::from(pong.custom_payload),
+        })),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 use network::StateNetwork;
 use tokio::{
-    sync::{broadcast, mpsc, RwLock},
+    sync::{broadcast, mpsc, watch, RwLock},
     task::JoinHandle,
 };
 use tracing::info;
@@ -40,6 +40,7 @@ pub async fn initialize_state_network(
     portalnet_config: PortalnetConfig,
     storage_config: PortalStorageConfig,
     header_oracle: Arc<RwLock<HeaderOracle>>,
+    shutdown: watch::Receiver<bool>,
 ) -> anyhow::Result<(
     StateHandler,
     StateNetworkTask,
@@ -63,8 +64,12 @@ pub async fn initialize_state_network(
         network: Arc::clone(&state_network),
         state_rx: state_jsonrpc_rx,
     };
-    let state_network_task =
-        spawn_state_network(Arc::clone(&state_network), portalnet_config, state_event_rx);
+    let state_network_task = spawn_state_network(
+        Arc::clone(&state_network),
+        portalnet_config,
+        state_event_rx,
+        shutdown,
+    );
     Ok((
         Some(state_handler),
         Some(state_network_task),
@@ -78,6 +83,7 @@ pub fn spawn_state_network(
     network: Arc<StateNetwork>,
     portalnet_config: PortalnetConfig,
     state_message_rx: mpsc::UnboundedReceiver<OverlayRequest>,
+    mut shutdown: watch::Receiver<bool>,
 ) -> JoinHandle<()> {
     let bootnode_enrs: Vec<Enr> = portalnet_config.bootnodes.into();
     info!(
@@ -97,8 +103,10 @@ pub fn spawn_state_network(
         // hacky test: make sure we establish a session with the boot node
         network.overlay.ping_bootnodes().await;
 
-        tokio::signal::ctrl_c()
-            .await
-            .expect("failed to pause until ctrl-c");
+        // The overlay is stopped once the node shuts down. It keeps running if the node is dropped
+        // without shutting down.
+        if shutdown.wait_for(|shutdown| *shutdown).await.is_ok() {
+            network.overlay.shutdown().await;
+        }
     })
 }
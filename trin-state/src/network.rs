@@ -1,4 +1,3 @@
-use parking_lot::RwLock as PLRwLock;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use utp_rs::socket::UtpSocket;
@@ -33,14 +32,14 @@ impl StateNetwork {
         portal_config: PortalnetConfig,
         header_oracle: Arc<RwLock<HeaderOracle>>,
     ) -> anyhow::Result<Self> {
-        let storage = Arc::new(PLRwLock::new(StateStorage::new(
-            storage_config,
-            ProtocolId::State,
-        )?));
+        let storage = StateStorage::new(storage_config, ProtocolId::State)?;
         let validator = Arc::new(StateValidator { header_oracle });
         let bootnode_enrs: Vec<Enr> = portal_config.bootnodes.into();
         let config = OverlayConfig {
             bootnode_enrs,
+            query_parallelism: portal_config.query_parallelism,
+            query_peer_timeout: portal_config.query_peer_timeout,
+            query_timeout: portal_config.query_timeout,
             ..Default::default()
         };
         let overlay = OverlayProtocol::new(
@@ -1,9 +1,22 @@
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
+use ethereum_types::{Address, H256};
+use ssz::Decode;
 use tokio::sync::RwLock;
 
-use ethportal_api::StateContentKey;
+use ethportal_api::{
+    types::{
+        content_value::state::{ContractStorageProof, TrieProof},
+        state_trie::{
+            account_trie_key, storage_trie_key, verify_bytecode, verify_node, verify_proof,
+            AccountState,
+        },
+    },
+    utils::bytes::hex_encode,
+    StateContentKey,
+};
 use trin_validation::{oracle::HeaderOracle, validator::Validator};
 
 pub struct StateValidator {
@@ -14,13 +27,300 @@ pub struct StateValidator {
 impl Validator<StateContentKey> for StateValidator {
     async fn validate_content(
         &self,
-        _content_key: &StateContentKey,
-        _content: &[u8],
+        content_key: &StateContentKey,
+        content: &[u8],
     ) -> anyhow::Result<()>
     where
         StateContentKey: 'async_trait,
     {
-        // todo: implement state network validation
-        Ok(())
+        match content_key {
+            StateContentKey::AccountTrieNode(key) => validate_trie_node(content, key.node_hash),
+            StateContentKey::ContractStorageTrieNode(key) => {
+                validate_trie_node(content, key.node_hash)
+            }
+            StateContentKey::AccountTrieProof(key) => {
+                let proof = TrieProof::from_ssz_bytes(content).map_err(|err| {
+                    anyhow!("Account trie proof content has invalid encoding: {err:?}")
+                })?;
+                self.validate_state_root(key.state_root).await?;
+                find_account(&proof, key.state_root, Address::from_slice(&key.address))?;
+                Ok(())
+            }
+            StateContentKey::ContractStorageTrieProof(key) => {
+                let proof = ContractStorageProof::from_ssz_bytes(content).map_err(|err| {
+                    anyhow!("Contract storage trie proof content has invalid encoding: {err:?}")
+                })?;
+                self.validate_state_root(key.state_root).await?;
+                let address = Address::from_slice(&key.address);
+                let account = find_account(&proof.account_proof, key.state_root, address)?
+                    .unwrap_or_default();
+                let slot_key = storage_trie_key(key.slot);
+                verify_proof(account.storage_root, &nodes(&proof.storage_proof), slot_key.as_bytes())
+                    .map_err(|err| {
+                        anyhow!(
+                            "Content validation failed: Invalid storage proof for slot {} of {address:?}: {err}",
+                            key.slot
+                        )
+                    })?;
+                Ok(())
+            }
+            StateContentKey::ContractBytecode(key) => {
+                if !verify_bytecode(content, H256::from(key.code_hash)) {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid bytecode hash. Expected: {}",
+                        hex_encode(key.code_hash)
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl StateValidator {
+    /// Proofs are only accepted against the state root of a header validated by the chain history
+    /// network, as anyone can build a valid proof against a made up state root.
+    async fn validate_state_root(&self, state_root: [u8; 32]) -> anyhow::Result<()> {
+        self.header_oracle
+            .read()
+            .await
+            .validate_state_root(H256::from(state_root))
+    }
+}
+
+fn validate_trie_node(content: &[u8], node_hash: [u8; 32]) -> anyhow::Result<()> {
+    if !verify_node(content, H256::from(node_hash)) {
+        return Err(anyhow!(
+            "Content validation failed: Invalid trie node hash. Expected: {}",
+            hex_encode(node_hash)
+        ));
+    }
+    Ok(())
+}
+
+fn nodes(proof: &TrieProof) -> Vec<&[u8]> {
+    proof.iter().map(|node| &node[..]).collect()
+}
+
+/// Returns the state of the account at `address` proven by `proof`, or `None` if the proof shows
+/// the account doesn't exist.
+fn find_account(
+    proof: &TrieProof,
+    state_root: [u8; 32],
+    address: Address,
+) -> anyhow::Result<Option<AccountState>> {
+    let key = account_trie_key(address);
+    let value =
+        verify_proof(H256::from(state_root), &nodes(proof), key.as_bytes()).map_err(|err| {
+            anyhow!("Content validation failed: Invalid account proof for {address:?}: {err}")
+        })?;
+    value
+        .map(|value| {
+            AccountState::from_rlp(&value)
+                .map_err(|err| anyhow!("Account proof of {address:?} has invalid account: {err}"))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use eth_trie::{EthTrie, MemoryDB, Trie};
+    use ethereum_types::U256;
+    use ssz::Encode;
+
+    use ethportal_api::{
+        types::{content_key::state, content_value::state::EncodedTrieNode},
+        utils::bytes::hex_decode,
+    };
+    use trin_validation::accumulator::MasterAccumulator;
+
+    const CONTRACT: u64 = 7;
+
+    /// Returns a validator that accepts proofs against the given state roots.
+    fn validator(state_roots: &[H256]) -> StateValidator {
+        let mut header_oracle = HeaderOracle::new(MasterAccumulator::default());
+        for state_root in state_roots {
+            header_oracle.add_state_root(*state_root);
+        }
+        StateValidator {
+            header_oracle: Arc::new(RwLock::new(header_oracle)),
+        }
+    }
+
+    fn to_trie_proof(proof: Vec<Vec<u8>>) -> TrieProof {
+        proof
+            .into_iter()
+            .map(EncodedTrieNode::from)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Builds an account trie whose account `CONTRACT` has storage, and returns it with the
+    /// storage trie of that account.
+    fn build_tries() -> (EthTrie<MemoryDB>, EthTrie<MemoryDB>) {
+        let mut storage_trie = EthTrie::new(Arc::new(MemoryDB::new(true)));
+        for slot in 0..16u64 {
+            let value = rlp::encode(&U256::from(slot + 1));
+            storage_trie
+                .insert(storage_trie_key(U256::from(slot)).as_bytes(), &value)
+                .unwrap();
+        }
+        let storage_root = storage_trie.root_hash().unwrap();
+
+        let mut account_trie = EthTrie::new(Arc::new(MemoryDB::new(true)));
+        for i in 0..64u64 {
+            let mut account = AccountState {
+                nonce: i,
+                ..Default::default()
+            };
+            if i == CONTRACT {
+                account.storage_root = storage_root;
+            }
+            let key = account_trie_key(Address::from_low_u64_be(i));
+            account_trie
+                .insert(key.as_bytes(), &rlp::encode(&account))
+                .unwrap();
+        }
+        (account_trie, storage_trie)
+    }
+
+    fn account_proof(account_trie: &mut EthTrie<MemoryDB>, address: Address) -> TrieProof {
+        let key = account_trie_key(address);
+        to_trie_proof(account_trie.get_proof(key.as_bytes()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn validate_account_trie_proof() {
+        let (mut account_trie, _) = build_tries();
+        let state_root = account_trie.root_hash().unwrap().0;
+        let address = Address::from_low_u64_be(3);
+        let content_key = StateContentKey::AccountTrieProof(state::AccountTrieProof {
+            address: address.as_bytes().to_vec().into(),
+            state_root,
+        });
+        let proof = account_proof(&mut account_trie, address);
+        let validator = validator(&[H256::from(state_root), H256::zero()]);
+        validator
+            .validate_content(&content_key, &proof.as_ssz_bytes())
+            .await
+            .unwrap();
+
+        // The proof of another account doesn't prove this one.
+        let other_proof = account_proof(&mut account_trie, Address::from_low_u64_be(4));
+        assert!(validator
+            .validate_content(&content_key, &other_proof.as_ssz_bytes())
+            .await
+            .is_err());
+
+        let content_key = StateContentKey::AccountTrieProof(state::AccountTrieProof {
+            address: address.as_bytes().to_vec().into(),
+            state_root: [0; 32],
+        });
+        assert!(validator
+            .validate_content(&content_key, &proof.as_ssz_bytes())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn reject_proofs_against_unknown_state_roots() {
+        let (mut account_trie, _) = build_tries();
+        let state_root = account_trie.root_hash().unwrap();
+        let address = Address::from_low_u64_be(3);
+        let content_key = StateContentKey::AccountTrieProof(state::AccountTrieProof {
+            address: address.as_bytes().to_vec().into(),
+            state_root: state_root.0,
+        });
+        let proof = account_proof(&mut account_trie, address);
+        let err = validator(&[])
+            .validate_content(&content_key, &proof.as_ssz_bytes())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("doesn't belong to a known header"));
+    }
+
+    #[tokio::test]
+    async fn validate_contract_storage_trie_proof() {
+        let (mut account_trie, mut storage_trie) = build_tries();
+        let state_root = account_trie.root_hash().unwrap().0;
+        let address = Address::from_low_u64_be(CONTRACT);
+        let slot = U256::from(5);
+        let content_key =
+            StateContentKey::ContractStorageTrieProof(state::ContractStorageTrieProof {
+                address: address.as_bytes().to_vec().into(),
+                slot,
+                state_root,
+            });
+        let storage_proof = storage_trie
+            .get_proof(storage_trie_key(slot).as_bytes())
+            .unwrap();
+        let proof = ContractStorageProof {
+            account_proof: account_proof(&mut account_trie, address),
+            storage_proof: to_trie_proof(storage_proof),
+        };
+        let validator = validator(&[H256::from(state_root)]);
+        validator
+            .validate_content(&content_key, &proof.as_ssz_bytes())
+            .await
+            .unwrap();
+
+        // The storage proof must be against the storage root of the proven account.
+        let other_address = Address::from_low_u64_be(1);
+        let content_key =
+            StateContentKey::ContractStorageTrieProof(state::ContractStorageTrieProof {
+                address: other_address.as_bytes().to_vec().into(),
+                slot,
+                state_root,
+            });
+        let proof = ContractStorageProof {
+            account_proof: account_proof(&mut account_trie, other_address),
+            ..proof
+        };
+        assert!(validator
+            .validate_content(&content_key, &proof.as_ssz_bytes())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn validate_trie_nodes_and_bytecode() {
+        let (mut account_trie, _) = build_tries();
+        let state_root = account_trie.root_hash().unwrap();
+        // Proofs start at the root node.
+        let root_node = account_proof(&mut account_trie, Address::zero())[0].to_vec();
+        let content_key = StateContentKey::AccountTrieNode(state::AccountTrieNode {
+            path: vec![].into(),
+            node_hash: state_root.0,
+            state_root: state_root.0,
+        });
+        let validator = validator(&[]);
+        validator
+            .validate_content(&content_key, &root_node)
+            .await
+            .unwrap();
+        assert!(validator
+            .validate_content(&content_key, &root_node[1..])
+            .await
+            .is_err());
+
+        let code = hex_decode("0x6001600101").unwrap();
+        let content_key = StateContentKey::ContractBytecode(state::ContractBytecode {
+            address: Address::from_low_u64_be(CONTRACT)
+                .as_bytes()
+                .to_vec()
+                .into(),
+            code_hash: keccak_hash::keccak(&code).0,
+        });
+        validator
+            .validate_content(&content_key, &code)
+            .await
+            .unwrap();
+        assert!(validator
+            .validate_content(&content_key, &code[1..])
+            .await
+            .is_err());
     }
 }
@@ -0,0 +1,143 @@
+//! A portable file format for the content of a subnetwork, used to seed the storage of a node
+//! with content exported by another node.
+//!
+//! An archive starts with `ARCHIVE_MAGIC` and the id of the subnetwork of its content, followed by
+//! the content items. Each item is its content key and its content value, both prefixed by their
+//! length as a little-endian `u32`.
+
+use std::io::{self, Read, Write};
+
+use crate::error::ContentStoreError;
+
+/// The bytes that every content archive starts with, ending in the version of the format.
+pub const ARCHIVE_MAGIC: [u8; 8] = *b"TRINCA01";
+
+/// Writes content items to an archive.
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    entries: u64,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Starts an archive of the content of the subnetwork with id `network`.
+    pub fn new(mut writer: W, network: u8) -> io::Result<Self> {
+        writer.write_all(&ARCHIVE_MAGIC)?;
+        writer.write_all(&[network])?;
+        Ok(Self { writer, entries: 0 })
+    }
+
+    pub fn write_entry(&mut self, content_key: &[u8], content_value: &[u8]) -> io::Result<()> {
+        for bytes in [content_key, content_value] {
+            let length = u32::try_from(bytes.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Content too large"))?;
+            self.writer.write_all(&length.to_le_bytes())?;
+            self.writer.write_all(bytes)?;
+        }
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Flushes the archive and returns the number of content items written to it.
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.writer.flush()?;
+        Ok(self.entries)
+    }
+}
+
+/// Reads the content items of an archive.
+pub struct ArchiveReader<R: Read> {
+    reader: R,
+    network: u8,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, ContentStoreError> {
+        let mut header = [0; ARCHIVE_MAGIC.len() + 1];
+        reader.read_exact(&mut header)?;
+        if header[..ARCHIVE_MAGIC.len()] != ARCHIVE_MAGIC {
+            return Err(ContentStoreError::InvalidData {
+                message: "Not a content archive, or of an unsupported version".to_string(),
+            });
+        }
+        Ok(Self {
+            reader,
+            network: header[ARCHIVE_MAGIC.len()],
+        })
+    }
+
+    /// Returns the id of the subnetwork of the content in the archive.
+    pub fn network(&self) -> u8 {
+        self.network
+    }
+
+    /// Reads the next content item, or returns `None` at the end of the archive.
+    pub fn read_entry(&mut self) -> Result<Option<(Vec<u8>, Vec<u8>)>, ContentStoreError> {
+        let mut length = [0; 4];
+        // The archive may only end between items.
+        match self.reader.read(&mut length[..1])? {
+            0 => return Ok(None),
+            _ => self.reader.read_exact(&mut length[1..])?,
+        }
+        let content_key = self.read_bytes(u32::from_le_bytes(length))?;
+        self.reader.read_exact(&mut length)?;
+        let content_value = self.read_bytes(u32::from_le_bytes(length))?;
+        Ok(Some((content_key, content_value)))
+    }
+
+    fn read_bytes(&mut self, length: u32) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        (&mut self.reader)
+            .take(length.into())
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != length as usize {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes)
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = Result<(Vec<u8>, Vec<u8>), ContentStoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn archive_round_trip() {
+        let entries = vec![
+            (vec![0, 1, 2], vec![3; 100]),
+            (vec![4], vec![]),
+            (vec![5; 33], vec![6; 1000]),
+        ];
+        let mut writer = ArchiveWriter::new(vec![], 7).unwrap();
+        for (content_key, content_value) in &entries {
+            writer.write_entry(content_key, content_value).unwrap();
+        }
+        assert_eq!(writer.entries, 3);
+        let archive = writer.writer;
+
+        let reader = ArchiveReader::new(archive.as_slice()).unwrap();
+        assert_eq!(reader.network(), 7);
+        let read_entries: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(read_entries, entries);
+
+        // Truncated archives fail on the last item.
+        let reader = ArchiveReader::new(&archive[..archive.len() - 1]).unwrap();
+        let read_entries: Vec<_> = reader.collect();
+        assert_eq!(read_entries.len(), 3);
+        assert!(read_entries[2].is_err());
+    }
+
+    #[test]
+    fn reject_invalid_archive() {
+        assert!(ArchiveReader::new(&b"TRINCA02\x00"[..]).is_err());
+        assert!(ArchiveReader::new(&b"TRIN"[..]).is_err());
+    }
+}
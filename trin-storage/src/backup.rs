@@ -0,0 +1,158 @@
+//! Online backups of the content database, taken while the node keeps running and restored while
+//! it's stopped.
+
+use std::{fs, path::Path, time::Duration};
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{backup::Backup, Connection, OpenFlags};
+
+use crate::{
+    error::ContentStoreError,
+    migrations::{latest_schema_version, run_migrations, schema_version},
+};
+
+/// The number of database pages copied at once. The database is only locked while copying pages,
+/// so that content can still be stored while a large database is being copied.
+const PAGES_PER_STEP: i32 = 1024;
+
+/// The time between copying batches of pages, during which other connections may write.
+const PAUSE_BETWEEN_STEPS: Duration = Duration::from_millis(10);
+
+/// Takes backups of the content database, without access to the store that it was created by.
+#[derive(Clone)]
+pub struct DbBackup {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl DbBackup {
+    pub fn new(pool: Pool<SqliteConnectionManager>) -> Self {
+        Self { pool }
+    }
+
+    /// Copies the content database to a new database file at `path`, like [`backup_db`].
+    pub fn write(&self, path: &Path) -> Result<u64, ContentStoreError> {
+        backup_db(&self.pool, path)
+    }
+}
+
+/// Copies the content database to a new database file at `path`. Returns the size of the backup
+/// in bytes.
+///
+/// Content stored during the backup is included in it, and the backup is consistent as of the time
+/// that it completed.
+pub fn backup_db(
+    pool: &Pool<SqliteConnectionManager>,
+    path: &Path,
+) -> Result<u64, ContentStoreError> {
+    if path.exists() {
+        return Err(ContentStoreError::InvalidData {
+            message: format!("Backup target {} already exists", path.display()),
+        });
+    }
+    let conn = pool.get()?;
+    let mut backup_conn = Connection::open(path)?;
+    Backup::new(&conn, &mut backup_conn)?.run_to_completion(
+        PAGES_PER_STEP,
+        PAUSE_BETWEEN_STEPS,
+        None,
+    )?;
+    drop(backup_conn);
+    Ok(fs::metadata(path)?.len())
+}
+
+/// Replaces the content of the content database with that of a backup at `path`, then upgrades it
+/// to the latest schema, if the backup was taken by an older version of Trin.
+///
+/// The stores keep the radius and metrics that they derived from the replaced content, so the
+/// content database must only be restored while no stores are using it.
+pub fn restore_db(
+    pool: &Pool<SqliteConnectionManager>,
+    path: &Path,
+) -> Result<(), ContentStoreError> {
+    let backup_conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let version = schema_version(&backup_conn)?;
+    if version > latest_schema_version() {
+        return Err(ContentStoreError::UnsupportedSchemaVersion {
+            found: version,
+            supported: latest_schema_version(),
+        });
+    }
+    let content_tables: u64 = backup_conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'content_data'",
+        [],
+        |row| row.get(0),
+    )?;
+    if content_tables == 0 {
+        return Err(ContentStoreError::InvalidData {
+            message: format!("{} is not a content database backup", path.display()),
+        });
+    }
+
+    let mut conn = pool.get()?;
+    Backup::new(&backup_conn, &mut conn)?.run_to_completion(
+        PAGES_PER_STEP,
+        PAUSE_BETWEEN_STEPS,
+        None,
+    )?;
+    run_migrations(&mut conn)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::{env, process};
+
+    use crate::utils::{insert_value, lookup_content_value, setup_memory_sql};
+
+    #[test]
+    fn backup_and_restore() {
+        let path = env::temp_dir().join(format!("trin-backup-test-{}.sqlite", process::id()));
+        let pool = setup_memory_sql().unwrap();
+        let content_id = [1; 32];
+        insert_value(
+            pool.get().unwrap(),
+            &content_id,
+            &"00".to_string(),
+            &vec![2; 10],
+            0,
+        )
+        .unwrap();
+
+        assert!(backup_db(&pool, &path).unwrap() > 0);
+        // Existing files are never overwritten.
+        assert!(backup_db(&pool, &path).is_err());
+
+        let restored_pool = setup_memory_sql().unwrap();
+        assert_eq!(
+            lookup_content_value(content_id, restored_pool.get().unwrap(), 0)
+                .unwrap()
+                .unwrap(),
+            None
+        );
+        restore_db(&restored_pool, &path).unwrap();
+        assert_eq!(
+            lookup_content_value(content_id, restored_pool.get().unwrap(), 0)
+                .unwrap()
+                .unwrap(),
+            Some(vec![2; 10])
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reject_restoring_other_database() {
+        let path = env::temp_dir().join(format!("trin-restore-test-{}.sqlite", process::id()));
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE other (id INTEGER)")
+            .unwrap();
+
+        let pool = setup_memory_sql().unwrap();
+        assert!(restore_db(&pool, &path).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+//! Integrity checks of the content stored in the content database.
+
+use std::fmt;
+
+use ethportal_api::utils::bytes::hex_encode;
+
+/// A stored content item that failed an integrity check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorruptContent {
+    /// The content id that the item is stored under.
+    pub content_id: Vec<u8>,
+    /// The stored content key, hex encoded without the 0x prefix.
+    pub content_key: String,
+    pub reason: CorruptionReason,
+}
+
+/// Why a stored content item failed an integrity check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CorruptionReason {
+    /// The stored content key can't be decoded.
+    InvalidContentKey(String),
+    /// The content item isn't stored under the content id derived from its content key.
+    ContentIdMismatch { expected: [u8; 32] },
+    /// The stored content value can't be decoded.
+    InvalidContentValue(String),
+    /// The hash of the content value doesn't match the hash committed to by its content key.
+    HashMismatch(String),
+}
+
+impl fmt::Display for CorruptionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorruptionReason::InvalidContentKey(err) => write!(f, "invalid content key: {err}"),
+            CorruptionReason::ContentIdMismatch { expected } => write!(
+                f,
+                "stored under the wrong content id, expected {}",
+                hex_encode(expected)
+            ),
+            CorruptionReason::InvalidContentValue(err) => {
+                write!(f, "invalid content value: {err}")
+            }
+            CorruptionReason::HashMismatch(err) => write!(f, "content hash mismatch: {err}"),
+        }
+    }
+}
+
+/// The outcome of checking the integrity of the stored content of a subnetwork.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DbCheckReport {
+    /// The number of checked content items.
+    pub checked: u64,
+    /// The content items that failed the check.
+    pub corrupt: Vec<CorruptContent>,
+    /// The number of corrupt content items that were deleted.
+    pub deleted: u64,
+}
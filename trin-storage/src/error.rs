@@ -10,6 +10,10 @@ pub enum ContentStoreError {
     #[error("An error from the underlying database: {0:?}")]
     Database(String),
 
+    /// Unable to store or delete content because the storage was opened read-only.
+    #[error("storage is read-only")]
+    ReadOnly,
+
     #[error("IO error: {0:?}")]
     Io(#[from] std::io::Error),
 
@@ -35,4 +39,15 @@ pub enum ContentStoreError {
 
     #[error("unable to use content key {0}")]
     ContentKey(#[from] ContentKeyError),
+
+    /// The database has a newer schema than this version of Trin supports.
+    #[error("database schema version {found} is newer than the supported version {supported}")]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+
+    /// Unable to upgrade the database schema. The database is left at the previous version.
+    #[error("migration to database schema version {version} failed: {source}")]
+    Migration {
+        version: u32,
+        source: rusqlite::Error,
+    },
 }
@@ -1,8 +1,12 @@
+pub mod archive;
+pub mod backup;
+pub mod check;
 pub mod error;
+pub mod migrations;
 pub mod sql;
 pub mod utils;
 
-use crate::utils::setup_sql;
+use crate::utils::{index_distances, setup_memory_sql, setup_read_only_sql, setup_sql};
 use discv5::enr::NodeId;
 use error::ContentStoreError;
 use ethportal_api::types::{
@@ -44,6 +48,19 @@ pub trait ContentStore {
         value: V,
     ) -> Result<(), ContentStoreError>;
 
+    /// Puts several pieces of content into the store, returning the outcome of each put in order.
+    ///
+    /// Stores may write the whole batch at once, which is cheaper than a put per piece of content.
+    fn put_batch<K: OverlayContentKey, V: AsRef<[u8]>>(
+        &mut self,
+        items: Vec<(K, V)>,
+    ) -> Vec<Result<(), ContentStoreError>> {
+        items
+            .into_iter()
+            .map(|(key, value)| self.put(key, value))
+            .collect()
+    }
+
     /// Returns whether the content denoted by `key` is within the radius of the data store and not
     /// already stored within the data store.
     fn is_key_within_radius_and_unavailable<K: OverlayContentKey>(
@@ -135,6 +152,25 @@ impl ContentStore for MemoryContentStore {
     }
 }
 
+/// The backend that holds the content database of the subnetwork storages.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// A SQLite database file in the node data directory.
+    #[default]
+    Sqlite,
+    /// A SQLite database held in memory, which is lost when the node stops.
+    Memory,
+    /// An existing SQLite database file in the node data directory, opened read-only. The storages
+    /// serve the content of the database, but reject storing or deleting content.
+    SqliteReadOnly,
+}
+
+impl StorageBackend {
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, StorageBackend::SqliteReadOnly)
+    }
+}
+
 /// Struct for configuring a `PortalStorage` instance.
 #[derive(Clone)]
 pub struct PortalStorageConfig {
@@ -142,6 +178,7 @@ pub struct PortalStorageConfig {
     pub node_id: NodeId,
     pub node_data_dir: PathBuf,
     pub distance_fn: DistanceFunction,
+    pub backend: StorageBackend,
     pub sql_connection_pool: Pool<SqliteConnectionManager>,
 }
 
@@ -151,12 +188,35 @@ impl PortalStorageConfig {
         node_data_dir: PathBuf,
         node_id: NodeId,
     ) -> anyhow::Result<Self> {
-        let sql_connection_pool = setup_sql(&node_data_dir)?;
+        Self::new_with_backend(
+            storage_capacity_mb,
+            node_data_dir,
+            node_id,
+            StorageBackend::default(),
+        )
+    }
+
+    /// Constructs a config whose storages keep their content in `backend`.
+    pub fn new_with_backend(
+        storage_capacity_mb: u64,
+        node_data_dir: PathBuf,
+        node_id: NodeId,
+        backend: StorageBackend,
+    ) -> anyhow::Result<Self> {
+        let sql_connection_pool = match backend {
+            StorageBackend::Sqlite => setup_sql(&node_data_dir)?,
+            StorageBackend::Memory => setup_memory_sql()?,
+            StorageBackend::SqliteReadOnly => setup_read_only_sql(&node_data_dir)?,
+        };
+        if !backend.is_read_only() {
+            index_distances(&mut sql_connection_pool.get()?, &node_id)?;
+        }
         Ok(Self {
             storage_capacity_mb,
             node_id,
             node_data_dir,
             distance_fn: DistanceFunction::Xor,
+            backend,
             sql_connection_pool,
         })
     }
@@ -178,6 +238,7 @@ pub struct EntryCount(pub u64);
 pub mod test {
     use super::*;
     use ethportal_api::IdentityContentKey;
+    use std::path::Path;
 
     #[test]
     fn memory_store_contains_key() {
@@ -224,6 +285,58 @@ pub mod test {
         assert_eq!(store.get(&arb_key).unwrap(), Some(val));
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn available_disk_space_is_known() {
+        let available_bytes = utils::available_disk_space(Path::new(".")).unwrap();
+        assert!(available_bytes > 0);
+        assert!(utils::available_disk_space(Path::new("/does/not/exist")).is_none());
+    }
+
+    #[test]
+    fn memory_backend_is_shared_by_connections() {
+        let config = PortalStorageConfig::new_with_backend(
+            1,
+            PathBuf::new(),
+            NodeId::random(),
+            StorageBackend::Memory,
+        )
+        .unwrap();
+        let other_config = PortalStorageConfig::new_with_backend(
+            1,
+            PathBuf::new(),
+            NodeId::random(),
+            StorageBackend::Memory,
+        )
+        .unwrap();
+        // Content written through one connection of the pool is seen through the others.
+        let reader = config.sql_connection_pool.get().unwrap();
+        let content_id = [1; 32];
+        utils::insert_value(
+            config.sql_connection_pool.get().unwrap(),
+            &content_id,
+            &"01".to_string(),
+            &vec![0xef],
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            utils::lookup_content_value(content_id, reader, 0)
+                .unwrap()
+                .unwrap(),
+            Some(vec![0xef])
+        );
+
+        // Every pool has its own database.
+        let conn = other_config.sql_connection_pool.get().unwrap();
+        assert_eq!(
+            utils::lookup_content_value(content_id, conn, 0)
+                .unwrap()
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn memory_store_is_within_radius_and_unavailable() {
         let node_id = NodeId::random();
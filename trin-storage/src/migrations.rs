@@ -0,0 +1,198 @@
+use rusqlite::Connection;
+use tracing::info;
+
+use crate::{
+    error::ContentStoreError,
+    sql::{
+        CONTENT_DATA_DISTANCE_INDEX, CONTENT_DATA_NETWORK_PRIMARY_KEY, CONTENT_SIZE_TOTALS,
+        CREATE_QUERY_DB, LC_UPDATE_CREATE_TABLE,
+    },
+};
+
+/// A change of the schema of the content database.
+struct Migration {
+    /// The schema version that the migration upgrades the database to.
+    version: u32,
+    description: &'static str,
+    statements: &'static [&'static str],
+}
+
+/// All migrations of the content database, ordered by version.
+///
+/// The schema version of a database is kept in its `user_version`. Migrations are never changed
+/// once released: a schema change is made by appending a migration with the next version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create the content and light client update tables",
+        statements: &[CREATE_QUERY_DB, LC_UPDATE_CREATE_TABLE],
+    },
+    Migration {
+        version: 2,
+        description: "key content by content id and network",
+        statements: &[CONTENT_DATA_NETWORK_PRIMARY_KEY],
+    },
+    Migration {
+        version: 3,
+        description: "index content by distance to the node id",
+        statements: &[CONTENT_DATA_DISTANCE_INDEX],
+    },
+    Migration {
+        version: 4,
+        description: "keep the total content size of each network",
+        statements: &[CONTENT_SIZE_TOTALS],
+    },
+];
+
+/// Returns the schema version that this version of Trin uses.
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Returns the schema version of the database.
+pub fn schema_version(conn: &Connection) -> Result<u32, ContentStoreError> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Upgrades the database to the latest schema version.
+///
+/// Each migration runs in its own transaction, so a failed migration leaves the database at the
+/// previous version. Databases with a newer schema than the latest known one are rejected, rather
+/// than used with a schema that isn't understood.
+pub fn run_migrations(conn: &mut Connection) -> Result<(), ContentStoreError> {
+    let version = schema_version(conn)?;
+    let latest_version = latest_schema_version();
+    if version > latest_version {
+        return Err(ContentStoreError::UnsupportedSchemaVersion {
+            found: version,
+            supported: latest_version,
+        });
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > version)
+    {
+        info!(
+            version = migration.version,
+            description = migration.description,
+            "Migrating content database"
+        );
+        let apply = |conn: &mut Connection| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            for statement in migration.statements {
+                tx.execute_batch(statement)?;
+            }
+            tx.pragma_update(None, "user_version", &migration.version)?;
+            tx.commit()
+        };
+        apply(conn).map_err(|source| ContentStoreError::Migration {
+            version: migration.version,
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use rusqlite::params;
+
+    use crate::sql::CONTENT_SIZE_TOTAL_QUERY_NETWORK;
+
+    #[test]
+    fn migrations_are_ordered() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+        }
+    }
+
+    #[test]
+    fn migrate_new_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+        for table in ["content_data", "lc_update"] {
+            let count: u64 = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), params![], |row| {
+                    row.get(0)
+                })
+                .unwrap();
+            assert_eq!(count, 0);
+        }
+
+        // Migrating again is a no-op.
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+    }
+
+    #[test]
+    fn migrate_unversioned_database() {
+        // Databases created before migrations were introduced have the tables, but not
+        // necessarily all indices, and no schema version.
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(CREATE_QUERY_DB).unwrap();
+        conn.execute_batch(LC_UPDATE_CREATE_TABLE).unwrap();
+        conn.execute_batch("DROP INDEX network_idx").unwrap();
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+        let network_idx: u64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = 'network_idx'",
+                params![],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(network_idx, 1);
+    }
+
+    #[test]
+    fn keep_content_size_totals() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        let insert = |id: &str, network: u8, size: u64| {
+            conn.execute(
+                "INSERT OR IGNORE INTO content_data (content_id_long, content_id_short, content_key, content_value, network, content_size)
+                    VALUES (?1, 0, '', '', ?2, ?3)",
+                params![id, network, size],
+            )
+            .unwrap();
+        };
+        insert("01", 0, 10);
+        insert("02", 0, 5);
+        insert("01", 1, 7);
+        // Content that is already stored isn't counted twice.
+        insert("01", 0, 10);
+        conn.execute(
+            "DELETE FROM content_data WHERE content_id_long = '02' AND network = 0",
+            params![],
+        )
+        .unwrap();
+
+        let total = |network: u8| -> u64 {
+            conn.query_row(CONTENT_SIZE_TOTAL_QUERY_NETWORK, params![network], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(total(0), 10);
+        assert_eq!(total(1), 7);
+    }
+
+    #[test]
+    fn reject_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", &(latest_schema_version() + 1))
+            .unwrap();
+
+        assert!(matches!(
+            run_migrations(&mut conn),
+            Err(ContentStoreError::UnsupportedSchemaVersion { .. })
+        ));
+    }
+}
@@ -8,44 +8,129 @@ pub const CREATE_QUERY_DB: &str = "CREATE TABLE IF NOT EXISTS content_data (
                                 network INTEGER NOT NULL DEFAULT 0,
                                 content_size INTEGER
                             );
-                            CREATE INDEX content_size_idx ON content_data(content_size);
-                            CREATE INDEX content_id_short_idx ON content_data(content_id_short);
-                            CREATE INDEX content_id_long_idx ON content_data(content_id_long);
-                            CREATE INDEX network_idx ON content_data(network);";
+                            CREATE INDEX IF NOT EXISTS content_size_idx ON content_data(content_size);
+                            CREATE INDEX IF NOT EXISTS content_id_short_idx ON content_data(content_id_short);
+                            CREATE INDEX IF NOT EXISTS content_id_long_idx ON content_data(content_id_long);
+                            CREATE INDEX IF NOT EXISTS network_idx ON content_data(network);";
 
+/// Rebuilds the content table so that content is keyed by both its content id and its network,
+/// which keeps the content of one subnetwork from shadowing that of another.
+pub const CONTENT_DATA_NETWORK_PRIMARY_KEY: &str = "CREATE TABLE content_data_new (
+                                content_id_long TEXT NOT NULL,
+                                content_id_short INTEGER NOT NULL,
+                                content_key TEXT NOT NULL,
+                                content_value TEXT NOT NULL,
+                                network INTEGER NOT NULL DEFAULT 0,
+                                content_size INTEGER,
+                                PRIMARY KEY (content_id_long, network)
+                            );
+                            INSERT INTO content_data_new
+                                SELECT content_id_long, content_id_short, content_key,
+                                    content_value, network, content_size
+                                FROM content_data;
+                            DROP TABLE content_data;
+                            ALTER TABLE content_data_new RENAME TO content_data;
+                            CREATE INDEX IF NOT EXISTS content_size_idx ON content_data(content_size);
+                            CREATE INDEX IF NOT EXISTS content_id_short_idx ON content_data(content_id_short);
+                            CREATE INDEX IF NOT EXISTS content_id_long_idx ON content_data(content_id_long);
+                            CREATE INDEX IF NOT EXISTS network_idx ON content_data(network);";
+
+/// Indexes content by the distance of its content id to the node id, so that the farthest content
+/// and the content within a radius are found without computing the distance of every item.
+///
+/// `distance_short` is the XOR distance of `content_id_short` to the most significant 4 bytes of the
+/// node id that was indexed for, which is kept in `store_info`.
+pub const CONTENT_DATA_DISTANCE_INDEX: &str = "ALTER TABLE content_data ADD COLUMN distance_short INTEGER;
+                            CREATE INDEX IF NOT EXISTS distance_short_idx ON content_data(network, distance_short);
+                            CREATE TABLE IF NOT EXISTS store_info (
+                                key TEXT PRIMARY KEY,
+                                value INTEGER NOT NULL
+                            );";
+
+/// Keeps the total size of the content of each network in `store_info`, so that the storage usage
+/// is looked up instead of summed over all content. Triggers keep the sizes up to date on every
+/// insert and delete.
+pub const CONTENT_SIZE_TOTALS: &str = "INSERT OR REPLACE INTO store_info (key, value)
+                                SELECT 'content_size_' || network, TOTAL(content_size)
+                                FROM content_data GROUP BY network;
+                            CREATE TRIGGER IF NOT EXISTS content_size_insert AFTER INSERT ON content_data
+                            BEGIN
+                                INSERT OR IGNORE INTO store_info (key, value)
+                                    VALUES ('content_size_' || NEW.network, 0);
+                                UPDATE store_info SET value = value + IFNULL(NEW.content_size, 0)
+                                    WHERE key = 'content_size_' || NEW.network;
+                            END;
+                            CREATE TRIGGER IF NOT EXISTS content_size_delete AFTER DELETE ON content_data
+                            BEGIN
+                                UPDATE store_info SET value = value - IFNULL(OLD.content_size, 0)
+                                    WHERE key = 'content_size_' || OLD.network;
+                            END;";
+
+/// The distance of new content is derived from the indexed node id in the same statement.
 pub const INSERT_QUERY_NETWORK: &str =
-    "INSERT OR IGNORE INTO content_data (content_id_long, content_id_short, content_key, content_value, network, content_size)
-                            VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+    "INSERT OR IGNORE INTO content_data (content_id_long, content_id_short, content_key, content_value, network, content_size, distance_short)
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6,
+                                (SELECT (?2 | value) - (?2 & value) FROM store_info WHERE key = 'distance_node_id'))";
+
+pub const DISTANCE_NODE_ID_LOOKUP_QUERY: &str =
+    "SELECT value FROM store_info WHERE key = 'distance_node_id'";
+
+pub const DISTANCE_NODE_ID_UPDATE_QUERY: &str =
+    "INSERT OR REPLACE INTO store_info (key, value) VALUES ('distance_node_id', ?1)";
+
+pub const INDEX_DISTANCES_QUERY: &str =
+    "UPDATE content_data SET distance_short = (content_id_short | ?1) - (content_id_short & ?1)";
+
+pub const INDEX_MISSING_DISTANCES_QUERY: &str =
+    "UPDATE content_data SET distance_short = (content_id_short | ?1) - (content_id_short & ?1)
+                            WHERE distance_short IS NULL";
+
+pub const FARTHEST_QUERY_NETWORK: &str =
+    "SELECT content_id_long, content_size FROM content_data WHERE network = (?1) ORDER BY distance_short DESC LIMIT (?2)";
 
 pub const INSERT_LC_UPDATE_QUERY: &str =
     "INSERT OR IGNORE INTO lc_update (period, value, score, update_size)
                             VALUES (?1, ?2, ?3, ?4)";
 
-pub const DELETE_QUERY_DB: &str = "DELETE FROM content_data
-                            WHERE content_id_long = (?1)";
+pub const DELETE_QUERY_NETWORK: &str = "DELETE FROM content_data
+                            WHERE content_id_long = (?1) AND network = (?2)";
 
+/// Finds the farthest content without the distance index, for databases indexed for another node.
 pub const XOR_FIND_FARTHEST_QUERY_NETWORK: &str = "SELECT
-                                    content_id_long
+                                    content_id_long, content_size
                                     FROM content_data
                                     WHERE network = (?2)
                                     ORDER BY ((?1 | content_id_short) - (?1 & content_id_short)) DESC";
 
-pub const CONTENT_KEY_LOOKUP_QUERY_DB: &str =
-    "SELECT content_key FROM content_data WHERE content_id_long = (?1) LIMIT 1";
+pub const CONTENT_KEY_LOOKUP_QUERY_NETWORK: &str =
+    "SELECT content_key FROM content_data WHERE content_id_long = (?1) AND network = (?2) LIMIT 1";
+
+pub const CONTENT_VALUE_LOOKUP_QUERY_NETWORK: &str =
+    "SELECT content_value FROM content_data WHERE content_id_long = (?1) AND network = (?2) LIMIT 1";
 
-pub const CONTENT_VALUE_LOOKUP_QUERY_DB: &str =
-    "SELECT content_value FROM content_data WHERE content_id_long = (?1) LIMIT 1";
+pub const TOTAL_DATA_SIZE_QUERY_NETWORK: &str =
+    "SELECT TOTAL(content_size) FROM content_data WHERE network = (?1)";
 
-pub const TOTAL_DATA_SIZE_QUERY_DB: &str = "SELECT TOTAL(content_size) FROM content_data";
+/// Looks up the total content size of a network, as kept up to date by `CONTENT_SIZE_TOTALS`.
+pub const CONTENT_SIZE_TOTAL_QUERY_NETWORK: &str =
+    "SELECT value FROM store_info WHERE key = 'content_size_' || (?1)";
 
 pub const TOTAL_ENTRY_COUNT_QUERY_NETWORK: &str =
     "SELECT COUNT(content_id_long) FROM content_data WHERE network = (?1)";
 
-pub const PAGINATE_QUERY_DB: &str =
-    "SELECT content_key FROM content_data ORDER BY content_key LIMIT :limit OFFSET :offset";
+pub const PAGINATE_QUERY_NETWORK: &str =
+    "SELECT content_key FROM content_data WHERE network = :network ORDER BY content_key LIMIT :limit OFFSET :offset";
 
-pub const CONTENT_SIZE_LOOKUP_QUERY_DB: &str =
-    "SELECT content_size FROM content_data WHERE content_id_long = (?1)";
+pub const EXPORT_QUERY_NETWORK: &str =
+    "SELECT content_key, content_value FROM content_data WHERE network = :network ORDER BY content_key LIMIT :limit OFFSET :offset";
+
+pub const CHECK_QUERY_NETWORK: &str =
+    "SELECT content_id_long, content_key, content_value FROM content_data WHERE network = (?1)";
+
+/// Block header content keys start with the `00` selector, and are stored as hex without the 0x
+/// prefix.
+pub const HEADER_VALUES_QUERY_NETWORK: &str =
+    "SELECT content_value FROM content_data WHERE network = (?1) AND content_key LIKE '00%'";
 
 pub const LC_UPDATE_CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS lc_update (
                                           period INTEGER PRIMARY KEY,
@@ -53,10 +138,14 @@ pub const LC_UPDATE_CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS lc_update (
                                           score INTEGER NOT NULL,
                                           update_size INTEGER
                                       );
-                                     CREATE INDEX update_size_idx ON lc_update(update_size);
-                                     CREATE INDEX period_idx ON lc_update(period);";
+                                     CREATE INDEX IF NOT EXISTS update_size_idx ON lc_update(update_size);
+                                     CREATE INDEX IF NOT EXISTS period_idx ON lc_update(period);";
 
 pub const LC_UPDATE_LOOKUP_QUERY: &str = "SELECT value FROM lc_update WHERE period = (?1) LIMIT 1";
 
 pub const LC_UPDATE_PERIOD_LOOKUP_QUERY: &str =
     "SELECT period FROM lc_update WHERE period = (?1) LIMIT 1";
+
+pub const LC_UPDATE_TOTAL_SIZE_QUERY: &str = "SELECT TOTAL(update_size) FROM lc_update";
+
+pub const LC_UPDATE_ENTRY_COUNT_QUERY: &str = "SELECT COUNT(period) FROM lc_update";
@@ -1,18 +1,26 @@
 use crate::{
     error::ContentStoreError,
+    migrations::{latest_schema_version, run_migrations, schema_version},
     sql::{
-        CONTENT_VALUE_LOOKUP_QUERY_DB, CREATE_QUERY_DB, INSERT_QUERY_NETWORK,
-        LC_UPDATE_CREATE_TABLE,
+        CONTENT_VALUE_LOOKUP_QUERY_NETWORK, DISTANCE_NODE_ID_LOOKUP_QUERY,
+        DISTANCE_NODE_ID_UPDATE_QUERY, FARTHEST_QUERY_NETWORK, INDEX_DISTANCES_QUERY,
+        INDEX_MISSING_DISTANCES_QUERY, INSERT_QUERY_NETWORK,
     },
-    DATABASE_NAME,
+    BYTES_IN_MB_U64, DATABASE_NAME,
 };
 use anyhow::Error;
+use discv5::enr::NodeId;
 use ethportal_api::utils::bytes::{hex_decode, hex_encode};
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
-use std::{fs, path::Path};
-use tracing::{debug, info};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracing::{debug, info, warn};
 
 /// Helper function for opening a SQLite connection.
 pub fn setup_sql(node_data_dir: &Path) -> Result<Pool<SqliteConnectionManager>, ContentStoreError> {
@@ -21,11 +29,67 @@ pub fn setup_sql(node_data_dir: &Path) -> Result<Pool<SqliteConnectionManager>,
 
     let manager = SqliteConnectionManager::file(sql_path);
     let pool = Pool::new(manager)?;
-    pool.get()?.execute(CREATE_QUERY_DB, params![])?;
-    pool.get()?.execute(LC_UPDATE_CREATE_TABLE, params![])?;
+    create_tables(&pool)?;
+    Ok(pool)
+}
+
+/// Helper function for opening read-only connections to an existing SQLite database.
+///
+/// The database can't be migrated, so it must already have the latest schema.
+pub fn setup_read_only_sql(
+    node_data_dir: &Path,
+) -> Result<Pool<SqliteConnectionManager>, ContentStoreError> {
+    let sql_path = node_data_dir.join(DATABASE_NAME);
+    info!(path = %sql_path.display(), "Opening SqliteDB read-only");
+
+    let manager = SqliteConnectionManager::file(sql_path)
+        .with_flags(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX);
+    let pool = Pool::new(manager)?;
+    let version = schema_version(&pool.get()?)?;
+    if version != latest_schema_version() {
+        return Err(ContentStoreError::InvalidData {
+            message: format!(
+                "Read-only database has schema version {version}, but version {} is required",
+                latest_schema_version()
+            ),
+        });
+    }
+    Ok(pool)
+}
+
+/// Helper function for opening connections to a new SQLite database held in memory.
+///
+/// All connections of the pool share the same database, which lives as long as the pool.
+pub fn setup_memory_sql() -> Result<Pool<SqliteConnectionManager>, ContentStoreError> {
+    // Every pool gets its own database, named uniquely within the process.
+    static NEXT_DATABASE_ID: AtomicUsize = AtomicUsize::new(0);
+    let database_id = NEXT_DATABASE_ID.fetch_add(1, Ordering::Relaxed);
+    let uri = format!(
+        "file:trin-{}-{database_id}?mode=memory&cache=shared",
+        process::id()
+    );
+    info!(uri, "Setting up in-memory SqliteDB");
+
+    let manager = SqliteConnectionManager::file(uri).with_flags(
+        OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_CREATE,
+    );
+    // The database is dropped along with its last connection, so connections are never closed
+    // for being idle or old.
+    let pool = Pool::builder()
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .build(manager)?;
+    create_tables(&pool)?;
     Ok(pool)
 }
 
+/// Creates the tables of the content database or upgrades them to the latest schema.
+fn create_tables(pool: &Pool<SqliteConnectionManager>) -> Result<(), ContentStoreError> {
+    run_migrations(&mut pool.get()?)
+}
+
 /// Internal method used to measure on-disk storage usage.
 pub fn get_total_size_of_directory_in_bytes(
     path: impl AsRef<Path>,
@@ -59,15 +123,83 @@ pub fn get_total_size_of_directory_in_bytes(
     Ok(size)
 }
 
-/// Internal method for looking up a content value by its content id
+/// Returns the number of bytes available to unprivileged users on the volume that holds `path`,
+/// or `None` if it can't be determined.
+#[cfg(unix)]
+pub fn available_disk_space(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is a valid nul terminated string and `stats` is only read once `statvfs`
+    // reported that it initialized it.
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stats.assume_init()
+    };
+    // The field types differ between platforms, so the conversions are useless only on some.
+    #[allow(clippy::useless_conversion)]
+    let available_bytes = u64::from(stats.f_bavail).saturating_mul(u64::from(stats.f_frsize));
+    Some(available_bytes)
+}
+
+#[cfg(not(unix))]
+pub fn available_disk_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Warns if the storage capacity exceeds the free disk space of the node data directory, so that
+/// the node doesn't unexpectedly run out of disk space once the storage fills up. The content
+/// that is already stored counts towards the capacity, so it's added to the free space.
+pub fn check_available_disk_space(node_data_dir: &Path, capacity_bytes: u64) {
+    let Some(available_bytes) = available_disk_space(node_data_dir) else {
+        debug!("Unable to determine the free disk space of {node_data_dir:?}");
+        return;
+    };
+    let used_bytes = get_total_size_of_directory_in_bytes(node_data_dir).unwrap_or(0);
+    let usable_bytes = available_bytes.saturating_add(used_bytes);
+    if capacity_bytes > usable_bytes {
+        warn!(
+            capacity_mb = capacity_bytes / BYTES_IN_MB_U64,
+            usable_mb = usable_bytes / BYTES_IN_MB_U64,
+            "The storage capacity exceeds the free disk space of {node_data_dir:?}, the disk will fill up before the storage does"
+        );
+    }
+}
+
+/// Resolves a path given over json-rpc against the node data directory. Only relative paths that
+/// stay within the directory are accepted, so that clients can't read or write arbitrary files.
+pub fn path_within_data_dir(
+    node_data_dir: &Path,
+    path: &Path,
+) -> Result<PathBuf, ContentStoreError> {
+    let is_within = path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !is_within {
+        return Err(ContentStoreError::InvalidData {
+            message: format!(
+                "Path {} must be relative to the node data directory, without leaving it",
+                path.display()
+            ),
+        });
+    }
+    Ok(node_data_dir.join(path))
+}
+
+/// Internal method for looking up a content value of a network by its content id
 pub fn lookup_content_value(
     id: [u8; 32],
     conn: PooledConnection<SqliteConnectionManager>,
+    network_id: u8,
 ) -> Result<Result<Option<Vec<u8>>, Error>, Error> {
-    let mut query = conn.prepare(CONTENT_VALUE_LOOKUP_QUERY_DB)?;
+    let mut query = conn.prepare(CONTENT_VALUE_LOOKUP_QUERY_NETWORK)?;
     let id = id.to_vec();
     let result: Result<Vec<Vec<u8>>, ContentStoreError> = query
-        .query_map([id], |row| {
+        .query_map(params![id, network_id], |row| {
             let row: String = row.get(0)?;
             Ok(row)
         })?
@@ -80,6 +212,68 @@ pub fn lookup_content_value(
     })
 }
 
+/// Indexes the distance of the stored content to `node_id`. The distances of all content are
+/// computed again if the database was last indexed for another node, e.g. after restoring a backup
+/// of another node, otherwise only the distances of content stored by older versions of Trin.
+pub fn index_distances(conn: &mut Connection, node_id: &NodeId) -> Result<(), ContentStoreError> {
+    let node_id_short = byte_vector_to_u32(node_id.raw().to_vec());
+    let tx = conn.transaction()?;
+    if distance_node_id(&tx)? == Some(node_id_short) {
+        tx.execute(INDEX_MISSING_DISTANCES_QUERY, params![node_id_short])?;
+    } else {
+        info!("Indexing content distances for the local node id");
+        tx.execute(DISTANCE_NODE_ID_UPDATE_QUERY, params![node_id_short])?;
+        tx.execute(INDEX_DISTANCES_QUERY, params![node_id_short])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns whether the distances of the stored content are indexed for `node_id`.
+pub fn distances_indexed_for(
+    conn: &Connection,
+    node_id: &NodeId,
+) -> Result<bool, ContentStoreError> {
+    Ok(distance_node_id(conn)? == Some(byte_vector_to_u32(node_id.raw().to_vec())))
+}
+
+fn distance_node_id(conn: &Connection) -> Result<Option<u32>, ContentStoreError> {
+    Ok(conn
+        .query_row(DISTANCE_NODE_ID_LOOKUP_QUERY, [], |row| row.get(0))
+        .optional()?)
+}
+
+/// Looks up the content ids and sizes of the `limit` content items of a network that are farthest
+/// from the indexed node id, from the farthest to the closest.
+///
+/// Distances are indexed by their most significant 4 bytes, so content at nearly the same
+/// distance may be out of order.
+pub fn farthest_content(
+    conn: &Connection,
+    network_id: u8,
+    limit: u64,
+) -> Result<Vec<([u8; 32], u64)>, ContentStoreError> {
+    let mut query = conn.prepare(FARTHEST_QUERY_NETWORK)?;
+    let rows = query.query_map(params![network_id, limit], |row| {
+        Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, u64>(1)?))
+    })?;
+    rows.map(|row| {
+        let (content_id, content_size) = row?;
+        Ok((content_id_from_bytes(content_id)?, content_size))
+    })
+    .collect()
+}
+
+/// Converts a content id read from the database to an array.
+pub fn content_id_from_bytes(content_id: Vec<u8>) -> Result<[u8; 32], ContentStoreError> {
+    let length = content_id.len();
+    content_id
+        .try_into()
+        .map_err(|_| ContentStoreError::InvalidData {
+            message: format!("content ID of length {length} != 32"),
+        })
+}
+
 /// Converts most significant 4 bytes of a vector to a u32.
 pub fn byte_vector_to_u32(vec: Vec<u8>) -> u32 {
     if vec.len() < 4 {
@@ -125,3 +319,59 @@ pub fn insert_value(
         Err(err) => Err(err.into()),
     }
 }
+
+/// Inserts several content items into the database in a single transaction, which is much cheaper
+/// than committing every item on its own. Either all items are inserted, or none are.
+pub fn insert_values(
+    mut conn: PooledConnection<SqliteConnectionManager>,
+    values: &[([u8; 32], String, Vec<u8>)],
+    network_id: u8,
+) -> Result<(), ContentStoreError> {
+    if values
+        .iter()
+        .any(|(_, content_key, _)| content_key.starts_with("0x"))
+    {
+        return Err(ContentStoreError::InvalidData {
+            message: "Content key should not start with 0x".to_string(),
+        });
+    }
+    let tx = conn.transaction()?;
+    {
+        let mut statement = tx.prepare(INSERT_QUERY_NETWORK)?;
+        for (content_id, content_key, value) in values {
+            statement.execute(params![
+                content_id.to_vec(),
+                byte_vector_to_u32(content_id.to_vec()),
+                content_key,
+                hex_encode(value),
+                network_id,
+                value.len()
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paths_are_confined_to_the_data_dir() {
+        let data_dir = Path::new("/data/trin");
+        assert_eq!(
+            path_within_data_dir(data_dir, Path::new("archives/seed.archive")).unwrap(),
+            data_dir.join("archives/seed.archive")
+        );
+        for path in [
+            "",
+            "/etc/passwd",
+            "../seed.archive",
+            "archives/../../seed.archive",
+        ] {
+            assert!(path_within_data_dir(data_dir, Path::new(path)).is_err());
+        }
+    }
+}
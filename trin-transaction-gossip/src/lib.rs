@@ -0,0 +1,92 @@
+#![warn(clippy::unwrap_used)]
+#![warn(clippy::uninlined_format_args)]
+
+use std::sync::Arc;
+
+use network::TransactionGossipNetwork;
+use tokio::{
+    sync::{broadcast, mpsc, watch, RwLock},
+    task::JoinHandle,
+};
+use tracing::info;
+use utp_rs::socket::UtpSocket;
+
+use crate::events::TransactionGossipEvents;
+use ethportal_api::types::enr::Enr;
+use portalnet::{
+    config::PortalnetConfig,
+    discovery::{Discovery, UtpEnr},
+    events::{EventEnvelope, OverlayRequest},
+};
+use trin_validation::oracle::HeaderOracle;
+
+pub mod events;
+pub mod network;
+pub mod storage;
+pub mod validation;
+
+type TransactionGossipNetworkTask = Option<JoinHandle<()>>;
+type TransactionGossipEventTx = Option<mpsc::UnboundedSender<OverlayRequest>>;
+type TransactionGossipEventStream = Option<broadcast::Receiver<EventEnvelope>>;
+
+pub async fn initialize_transaction_gossip_network(
+    discovery: &Arc<Discovery>,
+    utp_socket: Arc<UtpSocket<UtpEnr>>,
+    portalnet_config: PortalnetConfig,
+    header_oracle: Arc<RwLock<HeaderOracle>>,
+    shutdown: watch::Receiver<bool>,
+) -> anyhow::Result<(
+    TransactionGossipNetworkTask,
+    TransactionGossipEventTx,
+    TransactionGossipEventStream,
+)> {
+    let (event_tx, event_rx) = mpsc::unbounded_channel::<OverlayRequest>();
+    let network = TransactionGossipNetwork::new(
+        Arc::clone(discovery),
+        utp_socket,
+        portalnet_config.clone(),
+        header_oracle,
+    )
+    .await?;
+    let network = Arc::new(network);
+    let event_stream = network.overlay.event_stream().await?;
+    let network_task = spawn_transaction_gossip_network(
+        Arc::clone(&network),
+        portalnet_config,
+        event_rx,
+        shutdown,
+    );
+    Ok((Some(network_task), Some(event_tx), Some(event_stream)))
+}
+
+pub fn spawn_transaction_gossip_network(
+    network: Arc<TransactionGossipNetwork>,
+    portalnet_config: PortalnetConfig,
+    message_rx: mpsc::UnboundedReceiver<OverlayRequest>,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    let bootnode_enrs: Vec<Enr> = portalnet_config.bootnodes.into();
+    info!(
+        "About to spawn Transaction Gossip Network with {} boot nodes.",
+        bootnode_enrs.len()
+    );
+
+    tokio::spawn(async move {
+        let events = TransactionGossipEvents {
+            network: Arc::clone(&network),
+            message_rx,
+        };
+
+        // Spawn transaction gossip event handler
+        tokio::spawn(events.start());
+
+        // hacky test: make sure we establish a session with the boot node
+        network.overlay.ping_bootnodes().await;
+
+        // The overlay is stopped once the node shuts down. It keeps running if the node is dropped
+        // without shutting down.
+        if shutdown.wait_for(|shutdown| *shutdown).await.is_ok() {
+            network.overlay.shutdown().await;
+        }
+    })
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use utp_rs::socket::UtpSocket;
+
+use crate::{storage::TransactionGossipStorage, validation::TransactionGossipValidator};
+use ethportal_api::{
+    types::{distance::XorMetric, enr::Enr, portal_wire::ProtocolId},
+    TransactionGossipContentKey,
+};
+use portalnet::{
+    config::PortalnetConfig,
+    discovery::{Discovery, UtpEnr},
+    overlay::{OverlayConfig, OverlayProtocol},
+};
+use trin_validation::oracle::HeaderOracle;
+
+/// Transaction gossip network layer on top of the overlay protocol. Encapsulates transaction
+/// gossip network specific data and logic.
+#[derive(Clone)]
+pub struct TransactionGossipNetwork {
+    pub overlay: Arc<
+        OverlayProtocol<
+            TransactionGossipContentKey,
+            XorMetric,
+            TransactionGossipValidator,
+            TransactionGossipStorage,
+        >,
+    >,
+}
+
+impl TransactionGossipNetwork {
+    pub async fn new(
+        discovery: Arc<Discovery>,
+        utp_socket: Arc<UtpSocket<UtpEnr>>,
+        portal_config: PortalnetConfig,
+        header_oracle: Arc<RwLock<HeaderOracle>>,
+    ) -> anyhow::Result<Self> {
+        let storage = TransactionGossipStorage::default();
+        let validator = Arc::new(TransactionGossipValidator { header_oracle });
+        let bootnode_enrs: Vec<Enr> = portal_config.bootnodes.into();
+        let config = OverlayConfig {
+            bootnode_enrs,
+            query_parallelism: portal_config.query_parallelism,
+            query_peer_timeout: portal_config.query_peer_timeout,
+            query_timeout: portal_config.query_timeout,
+            ..Default::default()
+        };
+        let overlay = OverlayProtocol::new(
+            config,
+            discovery,
+            utp_socket,
+            storage,
+            ProtocolId::TransactionGossip,
+            validator,
+        )
+        .await;
+
+        Ok(Self {
+            overlay: Arc::new(overlay),
+        })
+    }
+}
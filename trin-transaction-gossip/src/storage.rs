@@ -0,0 +1,136 @@
+use std::collections::{HashMap, VecDeque};
+
+use ethportal_api::{types::distance::Distance, OverlayContentKey};
+use trin_storage::{error::ContentStoreError, ContentStore, ShouldWeStoreContent};
+
+/// Maximum number of pending transactions kept by the pool.
+pub const MAX_PENDING_TRANSACTIONS: usize = 4096;
+
+/// Storage layer for the transaction gossip network.
+///
+/// Pending transactions are short-lived, so they are kept in memory rather than in the database,
+/// and the oldest transactions are dropped once the pool is full. Every node keeps every
+/// transaction offered to it, so the radius is always the maximum.
+#[derive(Debug)]
+pub struct TransactionGossipStorage {
+    transactions: HashMap<[u8; 32], Vec<u8>>,
+    /// Content ids of the pending transactions, from the oldest to the newest.
+    arrival_order: VecDeque<[u8; 32]>,
+    capacity: usize,
+}
+
+impl Default for TransactionGossipStorage {
+    fn default() -> Self {
+        Self::new(MAX_PENDING_TRANSACTIONS)
+    }
+}
+
+impl TransactionGossipStorage {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            transactions: HashMap::new(),
+            arrival_order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+}
+
+impl ContentStore for TransactionGossipStorage {
+    fn get<K: OverlayContentKey>(&self, key: &K) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        Ok(self.transactions.get(&key.content_id()).cloned())
+    }
+
+    fn put<K: OverlayContentKey, V: AsRef<[u8]>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<(), ContentStoreError> {
+        let content_id = key.content_id();
+        if self
+            .transactions
+            .insert(content_id, value.as_ref().to_vec())
+            .is_some()
+        {
+            return Ok(());
+        }
+        self.arrival_order.push_back(content_id);
+        while self.transactions.len() > self.capacity {
+            match self.arrival_order.pop_front() {
+                Some(oldest) => {
+                    self.transactions.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn is_key_within_radius_and_unavailable<K: OverlayContentKey>(
+        &self,
+        key: &K,
+    ) -> Result<ShouldWeStoreContent, ContentStoreError> {
+        match self.transactions.contains_key(&key.content_id()) {
+            true => Ok(ShouldWeStoreContent::AlreadyStored),
+            false => Ok(ShouldWeStoreContent::Store),
+        }
+    }
+
+    fn radius(&self) -> Distance {
+        Distance::MAX
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ethportal_api::{TransactionGossipContentKey, TransactionKey};
+
+    fn key(i: u8) -> TransactionGossipContentKey {
+        TransactionGossipContentKey::Transaction(TransactionKey {
+            transaction_hash: [i; 32],
+        })
+    }
+
+    #[test]
+    fn oldest_transactions_are_dropped_when_full() {
+        let mut storage = TransactionGossipStorage::new(2);
+        for i in 0..3 {
+            storage.put(key(i), [i]).unwrap();
+        }
+
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage.get(&key(0)).unwrap(), None);
+        assert_eq!(storage.get(&key(1)).unwrap(), Some(vec![1]));
+        assert_eq!(storage.get(&key(2)).unwrap(), Some(vec![2]));
+    }
+
+    #[test]
+    fn stored_transactions_are_not_stored_again() {
+        let mut storage = TransactionGossipStorage::new(2);
+        assert_eq!(
+            storage
+                .is_key_within_radius_and_unavailable(&key(0))
+                .unwrap(),
+            ShouldWeStoreContent::Store
+        );
+
+        storage.put(key(0), [0]).unwrap();
+        storage.put(key(0), [0]).unwrap();
+        assert_eq!(storage.len(), 1);
+        assert_eq!(
+            storage
+                .is_key_within_radius_and_unavailable(&key(0))
+                .unwrap(),
+            ShouldWeStoreContent::AlreadyStored
+        );
+    }
+}
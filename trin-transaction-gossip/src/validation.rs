@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethereum_types::{H256, U256};
+use ssz::Decode;
+use tokio::sync::RwLock;
+
+use ethportal_api::{
+    types::{
+        execution::transaction::Transaction,
+        state_trie::{account_trie_key, verify_proof, AccountState},
+    },
+    utils::bytes::hex_encode,
+    TransactionGossipContentKey, TransactionGossipContentValue,
+};
+use trin_validation::{oracle::HeaderOracle, validator::Validator};
+
+/// Checks pending transactions before they are stored and gossiped further.
+///
+/// A transaction must be signed, and its sender must be able to pay for it from the account proven
+/// by the gossiped account proof, which must be against the state root of a validated header.
+pub struct TransactionGossipValidator {
+    pub header_oracle: Arc<RwLock<HeaderOracle>>,
+}
+
+#[async_trait]
+impl Validator<TransactionGossipContentKey> for TransactionGossipValidator {
+    async fn validate_content(
+        &self,
+        content_key: &TransactionGossipContentKey,
+        content: &[u8],
+    ) -> anyhow::Result<()>
+    where
+        TransactionGossipContentKey: 'async_trait,
+    {
+        match content_key {
+            TransactionGossipContentKey::Transaction(key) => {
+                let value =
+                    TransactionGossipContentValue::from_ssz_bytes(content).map_err(|err| {
+                        anyhow!("Transaction gossip content has invalid encoding: {err:?}")
+                    })?;
+                let transaction_hash = keccak_hash::keccak(&value.transaction[..]);
+                if transaction_hash != H256::from(key.transaction_hash) {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid transaction hash. Found: {transaction_hash:?} - Expected: {:?}",
+                        hex_encode(key.transaction_hash)
+                    ));
+                }
+                let transaction = Transaction::decode(&value.transaction)
+                    .map_err(|err| anyhow!("Transaction has invalid encoding: {err:?}"))?;
+                let sender = transaction.sender()?;
+
+                let state_root = H256::from(value.state_root);
+                self.header_oracle
+                    .read()
+                    .await
+                    .validate_state_root(state_root)?;
+                let account_proof: Vec<&[u8]> =
+                    value.account_proof.iter().map(|node| &node[..]).collect();
+                let account = verify_proof(
+                    state_root,
+                    &account_proof,
+                    account_trie_key(sender).as_bytes(),
+                )
+                .map_err(|err| {
+                    anyhow!(
+                        "Content validation failed: Invalid account proof for {sender:?}: {err}"
+                    )
+                })?
+                .map(|account| AccountState::from_rlp(&account))
+                .transpose()
+                .map_err(|err| anyhow!("Account proof of {sender:?} has invalid account: {err}"))?
+                .unwrap_or_default();
+
+                if transaction.nonce() < U256::from(account.nonce) {
+                    return Err(anyhow!(
+                        "Content validation failed: Nonce {} of {sender:?} was already used, the account nonce is {}",
+                        transaction.nonce(),
+                        account.nonce
+                    ));
+                }
+                if account.balance < transaction.max_cost() {
+                    return Err(anyhow!(
+                        "Content validation failed: Balance {} of {sender:?} can't pay for the transaction, which costs up to {}",
+                        account.balance,
+                        transaction.max_cost()
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    use eth_trie::{EthTrie, MemoryDB, Trie};
+    use ethereum_types::Address;
+    use ssz::Encode;
+
+    use ethportal_api::{
+        types::content_value::state::EncodedTrieNode, utils::bytes::hex_decode, TransactionKey,
+    };
+    use trin_validation::accumulator::MasterAccumulator;
+
+    /// The signed example transaction of EIP-155, with nonce 9, that costs up to 1.00042 ether.
+    const TRANSACTION: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    const SENDER: &str = "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+
+    /// Returns a validator that accepts account proofs against the given state root.
+    fn validator(state_root: [u8; 32]) -> TransactionGossipValidator {
+        let mut header_oracle = HeaderOracle::new(MasterAccumulator::default());
+        header_oracle.add_state_root(H256::from(state_root));
+        TransactionGossipValidator {
+            header_oracle: Arc::new(RwLock::new(header_oracle)),
+        }
+    }
+
+    fn state_root(content: &[u8]) -> [u8; 32] {
+        TransactionGossipContentValue::from_ssz_bytes(content)
+            .unwrap()
+            .state_root
+    }
+
+    /// Returns the gossiped transaction along with the proof of its sender's `account`.
+    fn content(account: &AccountState) -> (TransactionGossipContentKey, Vec<u8>) {
+        let mut trie = EthTrie::new(Arc::new(MemoryDB::new(true)));
+        for i in 0..16u64 {
+            let key = account_trie_key(Address::from_low_u64_be(i));
+            trie.insert(key.as_bytes(), &rlp::encode(&AccountState::default()))
+                .unwrap();
+        }
+        let sender_key = account_trie_key(Address::from_str(SENDER).unwrap());
+        trie.insert(sender_key.as_bytes(), &rlp::encode(account))
+            .unwrap();
+        let state_root = trie.root_hash().unwrap();
+        let account_proof: Vec<EncodedTrieNode> = trie
+            .get_proof(sender_key.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(EncodedTrieNode::from)
+            .collect();
+
+        let transaction = hex_decode(TRANSACTION).unwrap();
+        let content_key = TransactionGossipContentKey::Transaction(TransactionKey {
+            transaction_hash: keccak_hash::keccak(&transaction).0,
+        });
+        let value = TransactionGossipContentValue {
+            transaction: transaction.into(),
+            state_root: state_root.0,
+            account_proof: account_proof.into(),
+        };
+        (content_key, value.as_ssz_bytes())
+    }
+
+    fn account(nonce: u64, balance: U256) -> AccountState {
+        AccountState {
+            nonce,
+            balance,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_transaction() {
+        let ether = U256::exp10(18);
+        let (content_key, content) = content(&account(9, ether * 2));
+        validator(state_root(&content))
+            .validate_content(&content_key, &content)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_with_used_nonce() {
+        let ether = U256::exp10(18);
+        let (content_key, content) = content(&account(10, ether * 2));
+        assert!(validator(state_root(&content))
+            .validate_content(&content_key, &content)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_with_insufficient_balance() {
+        let ether = U256::exp10(18);
+        let (content_key, content) = content(&account(9, ether));
+        assert!(validator(state_root(&content))
+            .validate_content(&content_key, &content)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_with_wrong_hash() {
+        let ether = U256::exp10(18);
+        let (_, content) = content(&account(9, ether * 2));
+        let content_key = TransactionGossipContentKey::Transaction(TransactionKey {
+            transaction_hash: [0; 32],
+        });
+        assert!(validator(state_root(&content))
+            .validate_content(&content_key, &content)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn invalidate_transaction_with_unknown_state_root() {
+        let ether = U256::exp10(18);
+        let (content_key, content) = content(&account(9, ether * 2));
+        let err = validator([0; 32])
+            .validate_content(&content_key, &content)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("doesn't belong to a known header"));
+    }
+}
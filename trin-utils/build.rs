@@ -1,4 +1,8 @@
-use std::{env, process::Command};
+use std::{
+    env,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 fn main() {
     let output = Command::new("git")
@@ -26,4 +30,44 @@ fn main() {
     // Printing to stdout is how build scripts communicate with cargo
     // https://doc.rust-lang.org/cargo/reference/build-scripts.html#outputs-of-the-build-script
     println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+    println!(
+        "cargo:rustc-env=TRIN_BUILD_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+    println!("cargo:rustc-env=TRIN_BUILD_DATE={}", build_date());
+}
+
+/// Returns the UTC date of the build as YYYY-MM-DD. SOURCE_DATE_EPOCH is respected, so that
+/// reproducible builds embed a fixed date.
+fn build_date() -> String {
+    let timestamp = match env::var("SOURCE_DATE_EPOCH") {
+        Ok(val) => val
+            .parse()
+            .expect("SOURCE_DATE_EPOCH must be a unix timestamp"),
+        Err(_) => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time must be after the unix epoch")
+            .as_secs(),
+    };
+    let (year, month, day) = civil_from_days((timestamp / 86400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Converts a number of days since the unix epoch into a (year, month, day) date, following
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = match month_index < 10 {
+        true => month_index + 3,
+        false => month_index - 9,
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
@@ -1,17 +1,70 @@
-use std::env;
-use tracing_subscriber::EnvFilter;
+use std::{
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_appender::non_blocking::{NonBlocking, NonBlockingBuilder, WorkerGuard};
+use tracing_subscriber::{
+    fmt::MakeWriter,
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+/// Name of the JSON log file in the log directory. Rotated files get a numbered suffix, with
+/// `trin.log.1` being the most recent one.
+const JSON_LOG_FILE_NAME: &str = "trin.log";
+
+/// Configuration of the JSON log files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonLogConfig {
+    /// The directory that the log files are written to.
+    pub dir: PathBuf,
+    /// The size in bytes at which the log file is rotated.
+    pub max_file_size: u64,
+    /// The number of rotated log files to keep besides the current one.
+    pub max_files: usize,
+}
 
 pub fn init_tracing_logger() {
+    // Without a JSON log, there is no logging thread to hold a guard for.
+    init_logger(true, None).expect("Initializing the console logger to work");
+}
+
+/// Initializes the logger, writing to the console unless it's disabled, and as JSON lines to
+/// rotating files if a JSON log config is given.
+///
+/// The JSON lines are written by a logging thread, which stops once the returned guard is dropped,
+/// after writing the remaining lines. The guard must be held until the program exits.
+pub fn init_logger(
+    console: bool,
+    json_log: Option<JsonLogConfig>,
+) -> io::Result<Option<WorkerGuard>> {
     let rust_log = env::var(EnvFilter::DEFAULT_ENV).unwrap_or_default();
     let env_filter = match rust_log.is_empty() {
         true => EnvFilter::builder().parse_lossy("info,discv5=error,utp_rs=error"),
         false => EnvFilter::builder().parse_lossy(rust_log),
     };
 
-    tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
-        .with_ansi(detect_ansi_support())
+    let console_layer =
+        console.then(|| tracing_subscriber::fmt::layer().with_ansi(detect_ansi_support()));
+    let (json_layer, guard) = json_log.map(JsonLayer::new).transpose()?.unzip();
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(console_layer)
+        .with(json_layer)
         .init();
+    Ok(guard)
 }
 
 pub fn detect_ansi_support() -> bool {
@@ -36,3 +89,249 @@ pub fn detect_ansi_support() -> bool {
             .unwrap_or(false)
     }
 }
+
+/// A layer that writes every event as a JSON line with its timestamp, level, target and fields,
+/// as well as the fields of the spans that it happened in.
+///
+/// Lines are sent to a logging thread that writes them to the log file, so that events don't wait
+/// on the disk.
+pub struct JsonLayer {
+    writer: NonBlocking,
+}
+
+impl JsonLayer {
+    /// Opens the log file and spawns the logging thread, which stops once the returned guard is
+    /// dropped.
+    pub fn new(config: JsonLogConfig) -> io::Result<(Self, WorkerGuard)> {
+        // Events wait for the logging thread when it falls behind, rather than dropping lines.
+        let (writer, guard) = NonBlockingBuilder::default()
+            .lossy(false)
+            .thread_name("json-log")
+            .finish(RotatingFile::open(config)?);
+        Ok((Self { writer }, guard))
+    }
+}
+
+impl<S> Layer<S> for JsonLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = JsonFields::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<JsonFields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let spans: Vec<Value> = ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let mut span_fields = Map::new();
+                span_fields.insert("name".to_string(), span.name().into());
+                if let Some(fields) = span.extensions().get::<JsonFields>() {
+                    span_fields.extend(fields.0.clone());
+                }
+                Value::Object(span_fields)
+            })
+            .collect();
+
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Micros, true)
+                .into(),
+        );
+        line.insert("level".to_string(), metadata.level().to_string().into());
+        line.insert("target".to_string(), metadata.target().into());
+        line.insert("fields".to_string(), Value::Object(fields.0));
+        line.insert("spans".to_string(), Value::Array(spans));
+
+        let mut line = Value::Object(line).to_string();
+        line.push('\n');
+        // Errors can't be logged, as that would recurse into this layer.
+        if let Err(err) = self.writer.make_writer().write_all(line.as_bytes()) {
+            eprintln!("Error writing JSON log, the logging thread stopped: {err}");
+        }
+    }
+}
+
+/// The fields of an event or span, as JSON values.
+#[derive(Clone, Debug, Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+/// A log file that is rotated once it reaches its maximum size, keeping a limited number of
+/// rotated files. Every write is expected to be a whole line, so that lines aren't split across
+/// files.
+struct RotatingFile {
+    config: JsonLogConfig,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(config: JsonLogConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        let file = open_append(&config.dir.join(JSON_LOG_FILE_NAME))?;
+        let size = file.metadata()?.len();
+        Ok(Self { config, file, size })
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let len = line.len() as u64;
+        if self.size > 0 && self.size + len > self.config.max_file_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// Shifts the rotated files by one, dropping the oldest, and starts a new log file.
+    fn rotate(&mut self) -> io::Result<()> {
+        let path = self.config.dir.join(JSON_LOG_FILE_NAME);
+        let rotated_path = |index: usize| path.with_extension(format!("log.{index}"));
+        if self.config.max_files == 0 {
+            fs::remove_file(&path)?;
+        } else {
+            remove_if_exists(&rotated_path(self.config.max_files))?;
+            for index in (1..self.config.max_files).rev() {
+                let from = rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&path, rotated_path(1))?;
+        }
+        self.file = open_append(&path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The logging thread doesn't report write errors.
+        if let Err(err) = self.write_line(buf) {
+            eprintln!("Error writing JSON log: {err}");
+            return Err(err);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    fn json_log_config(dir: &Path, max_file_size: u64) -> JsonLogConfig {
+        JsonLogConfig {
+            dir: dir.to_path_buf(),
+            max_file_size,
+            max_files: 2,
+        }
+    }
+
+    #[test]
+    fn test_json_log_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (layer, guard) = JsonLayer::new(json_log_config(temp_dir.path(), 1_000_000)).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("query", peers = 3);
+            let _guard = span.enter();
+            tracing::warn!(content_id = "0x01", "Content not found");
+        });
+        // Waits for the logging thread to write the line.
+        drop(guard);
+
+        let logs = fs::read_to_string(temp_dir.path().join(JSON_LOG_FILE_NAME)).unwrap();
+        let line: Value = serde_json::from_str(logs.trim()).unwrap();
+        assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], "trin_utils::log::test");
+        assert_eq!(line["fields"]["message"], "Content not found");
+        assert_eq!(line["fields"]["content_id"], "0x01");
+        assert_eq!(line["spans"][0]["name"], "query");
+        assert_eq!(line["spans"][0]["peers"], 3);
+    }
+
+    #[test]
+    fn test_log_file_rotation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(JSON_LOG_FILE_NAME);
+        let mut file = RotatingFile::open(json_log_config(temp_dir.path(), 10)).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            file.write_all(format!("{line}\n").as_bytes()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(path.with_extension("log.1")).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(path.with_extension("log.2")).unwrap(),
+            "second\n"
+        );
+        assert!(!path.with_extension("log.3").exists());
+    }
+}
@@ -10,3 +10,10 @@ pub fn get_trin_version() -> String {
     };
     format!("{TRIN_VERSION}-{git_revision_short}")
 }
+
+/// Returns the full client version, with the target triple and date of the build.
+pub fn get_trin_client_version() -> String {
+    let target = env!("TRIN_BUILD_TARGET");
+    let build_date = env!("TRIN_BUILD_DATE");
+    format!("trin/v{}/{target}/{build_date}", get_trin_version())
+}
@@ -22,7 +22,9 @@ use ethportal_api::{
             accumulator::EpochAccumulator,
             header::{BlockHeaderProof, Header, HeaderWithProof},
         },
-        jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
+        jsonrpc::{
+            endpoints::HistoryEndpoint, error::JsonRpcError, request::HistoryJsonRpcRequest,
+        },
     },
     utils::bytes::hex_decode,
     EpochAccumulatorKey, HistoryContentKey,
@@ -128,20 +130,19 @@ impl MasterAccumulator {
     ) -> anyhow::Result<EpochAccumulator> {
         let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey { epoch_hash });
         let endpoint = HistoryEndpoint::RecursiveFindContent(content_key);
-        let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
-        let request = HistoryJsonRpcRequest {
-            endpoint,
-            resp: resp_tx,
-        };
+        let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+        let request = HistoryJsonRpcRequest::new(endpoint, resp_tx);
         history_jsonrpc_tx.send(request)?;
 
-        let epoch_acc_ssz = match resp_rx.recv().await {
+        let content_info = match resp_rx.recv().await {
             Some(val) => {
                 val.map_err(|msg| anyhow!("Chain history subnetwork request error: {:?}", msg))?
             }
             None => return Err(anyhow!("No response from chain history subnetwork")),
         };
-        let epoch_acc_ssz = epoch_acc_ssz
+        // The epoch accumulator is decoded directly, rather than through ContentInfo, since its
+        // bytes are not guaranteed to be rejected by the other history content decoders.
+        let epoch_acc_ssz = content_info["content"]
             .as_str()
             .ok_or_else(|| anyhow!("Invalid epoch acc received from chain history network"))?;
         let epoch_acc_ssz = hex_decode(epoch_acc_ssz)?;
@@ -420,7 +421,10 @@ mod test {
                     let epoch_acc_path = format!("./src/assets/epoch_accs/{epoch_acc_hash}.bin");
                     let epoch_acc = fs::read(epoch_acc_path).unwrap();
                     let epoch_acc = hex_encode(epoch_acc);
-                    let content: Value = json!(epoch_acc);
+                    let content: Value = json!({
+                        "content": epoch_acc,
+                        "utpTransfer": false,
+                    });
                     let _ = request.resp.send(Ok(content));
                 }
                 _ => panic!("Unexpected request endpoint"),
@@ -1,3 +1,5 @@
+use std::collections::{HashSet, VecDeque};
+
 use anyhow::anyhow;
 use ethereum_types::H256;
 use serde_json::Value;
@@ -6,16 +8,22 @@ use tokio::sync::mpsc;
 use crate::accumulator::MasterAccumulator;
 use ethportal_api::{
     types::{
-        execution::header::HeaderWithProof,
+        execution::{block_body::BlockBody, header::HeaderWithProof},
         history::ContentInfo,
         jsonrpc::{
             endpoints::HistoryEndpoint,
+            error::JsonRpcError,
             request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest},
         },
     },
-    BlockHeaderKey, HistoryContentKey, HistoryContentValue, PossibleHistoryContentValue,
+    BlockBodyKey, BlockHeaderKey, HistoryContentKey, HistoryContentValue,
+    PossibleHistoryContentValue,
 };
 
+/// The number of most recently validated headers whose state roots are accepted as the roots of
+/// state proofs.
+const MAX_KNOWN_STATE_ROOTS: usize = 8192;
+
 /// Responsible for dispatching cross-overlay-network requests
 /// for data to perform validation.
 #[derive(Clone, Debug)]
@@ -26,6 +34,7 @@ pub struct HeaderOracle {
     pub history_jsonrpc_tx: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
     pub beacon_jsonrpc_tx: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
     pub master_acc: MasterAccumulator,
+    known_state_roots: KnownStateRoots,
 }
 
 impl HeaderOracle {
@@ -34,9 +43,25 @@ impl HeaderOracle {
             history_jsonrpc_tx: None,
             beacon_jsonrpc_tx: None,
             master_acc,
+            known_state_roots: KnownStateRoots::default(),
         }
     }
 
+    /// Records the state root of a validated header, so that state proofs against it are accepted.
+    pub fn add_state_root(&mut self, state_root: H256) {
+        self.known_state_roots.insert(state_root);
+    }
+
+    /// Fails unless `state_root` is the state root of a recently validated header.
+    pub fn validate_state_root(&self, state_root: H256) -> anyhow::Result<()> {
+        if !self.known_state_roots.contains(&state_root) {
+            return Err(anyhow!(
+                "Content validation failed: State root {state_root:?} doesn't belong to a known header"
+            ));
+        }
+        Ok(())
+    }
+
     // Only serves pre-block hashes aka. portal-network verified data only
     pub async fn get_hash_at_height(&self, block_number: u64) -> anyhow::Result<H256> {
         self.master_acc
@@ -53,9 +78,37 @@ impl HeaderOracle {
         let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
             block_hash: block_hash.0,
         });
+        match self.recursive_find_content(content_key, "HeaderWithProof").await? {
+            HistoryContentValue::BlockHeaderWithProof(content) => Ok(content),
+            content => Err(anyhow!(
+                "Invalid HistoryContentValue received from HeaderWithProof lookup, expected BlockHeaderWithProof: {content:?}"
+            )),
+        }
+    }
+
+    /// Returns the BlockBody for the given block hash by performing a recursive find content
+    /// request.
+    pub async fn recursive_find_block_body(&self, block_hash: H256) -> anyhow::Result<BlockBody> {
+        let content_key = HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: block_hash.0,
+        });
+        match self.recursive_find_content(content_key, "BlockBody").await? {
+            HistoryContentValue::BlockBody(content) => Ok(content),
+            content => Err(anyhow!(
+                "Invalid HistoryContentValue received from BlockBody lookup, expected BlockBody: {content:?}"
+            )),
+        }
+    }
+
+    /// Looks up content from the chain history network, naming the type of content in errors.
+    async fn recursive_find_content(
+        &self,
+        content_key: HistoryContentKey,
+        content_type: &str,
+    ) -> anyhow::Result<HistoryContentValue> {
         let endpoint = HistoryEndpoint::RecursiveFindContent(content_key);
-        let (resp, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
-        let request = HistoryJsonRpcRequest { endpoint, resp };
+        let (resp, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, JsonRpcError>>();
+        let request = HistoryJsonRpcRequest::new(endpoint, resp);
         let tx = self.history_jsonrpc_tx()?;
         tx.send(request)?;
 
@@ -69,28 +122,20 @@ impl HeaderOracle {
             ContentInfo::Content { content, .. } => content,
             ContentInfo::ConnectionId { .. } => {
                 return Err(anyhow!(
-                    "Invalid ContentInfo (cid) received from HeaderWithProof lookup"
+                    "Invalid ContentInfo (cid) received from {content_type} lookup"
                 ))
             }
             ContentInfo::Enrs { .. } => {
                 return Err(anyhow!(
-                    "Invalid ContentInfo (enrs) received from HeaderWithProof lookup"
+                    "Invalid ContentInfo (enrs) received from {content_type} lookup"
                 ))
             }
         };
-        let content = match content {
-            PossibleHistoryContentValue::ContentPresent(header) => header,
+        match content {
+            PossibleHistoryContentValue::ContentPresent(content) => Ok(content),
             PossibleHistoryContentValue::ContentAbsent => {
-                return Err(anyhow!(
-                    "ContentAbsent received from HeaderWithProof lookup"
-                ))
+                Err(anyhow!("ContentAbsent received from {content_type} lookup"))
             }
-        };
-        match content {
-            HistoryContentValue::BlockHeaderWithProof(content) => Ok(content),
-            _ => Err(anyhow!(
-                "Invalid HistoryContentValue received from HeaderWithProof lookup, expected BlockHeaderWithProof: {content:?}"
-            )),
         }
     }
 
@@ -104,6 +149,32 @@ impl HeaderOracle {
     }
 }
 
+/// The state roots of the most recently validated headers, dropping the oldest beyond
+/// `MAX_KNOWN_STATE_ROOTS`.
+#[derive(Clone, Debug, Default)]
+struct KnownStateRoots {
+    order: VecDeque<H256>,
+    roots: HashSet<H256>,
+}
+
+impl KnownStateRoots {
+    fn insert(&mut self, state_root: H256) {
+        if !self.roots.insert(state_root) {
+            return;
+        }
+        self.order.push_back(state_root);
+        if self.order.len() > MAX_KNOWN_STATE_ROOTS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.roots.remove(&oldest);
+            }
+        }
+    }
+
+    fn contains(&self, state_root: &H256) -> bool {
+        self.roots.contains(state_root)
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod test {
@@ -125,4 +196,15 @@ mod test {
             H256::from_str(DEFAULT_MASTER_ACC_HASH).unwrap(),
         );
     }
+
+    #[test]
+    fn known_state_roots_keep_the_most_recent() {
+        let mut known_state_roots = KnownStateRoots::default();
+        for index in 0..=MAX_KNOWN_STATE_ROOTS as u64 {
+            known_state_roots.insert(H256::from_low_u64_be(index));
+        }
+        assert!(!known_state_roots.contains(&H256::from_low_u64_be(0)));
+        assert!(known_state_roots.contains(&H256::from_low_u64_be(1)));
+        assert!(known_state_roots.contains(&H256::from_low_u64_be(MAX_KNOWN_STATE_ROOTS as u64)));
+    }
 }